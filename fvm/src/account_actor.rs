@@ -7,17 +7,19 @@
 //! eliminated. Refer to https://github.com/filecoin-project/fvm/issues/229 for
 //! details.
 
-use cid::Cid;
+use anyhow::Context;
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_ipld_encoding::Cbor;
 use fvm_shared::address::Address;
+pub use fvm_shared::builtin::singletons::SYSTEM_ACTOR_ID;
 use fvm_shared::econ::TokenAmount;
 use num_traits::Zero;
 
+use crate::kernel::{ClassifyResult, Result};
+use crate::machine::Manifest;
 use crate::state_tree::ActorState;
-use crate::EMPTY_ARR_CID;
-
-pub const SYSTEM_ACTOR_ID: u64 = 0;
+use crate::{put_empty_arr, EMPTY_ARR_CID};
 
 /// State specifies the key address for the actor.
 #[derive(Serialize_tuple, Deserialize_tuple)]
@@ -25,14 +27,52 @@ pub struct State {
     pub address: Address,
 }
 
-/// Returns an ActorState representing a brand new account with no balance.
-pub fn zero_state(code_cid: Cid) -> ActorState {
-    ActorState {
-        code: code_cid,
-        state: *EMPTY_ARR_CID,
-        sequence: 0,
-        balance: TokenAmount::zero(),
-    }
+/// Returns an ActorState representing a brand new account with no balance, before its constructor
+/// sets its [`State`]: its code is the account code of `manifest`, and its state is the empty
+/// array (see [`EMPTY_ARR_CID`]), which is written to `store`.
+pub fn zero_state(manifest: &Manifest, store: &impl Blockstore) -> Result<ActorState> {
+    put_empty_arr(store)
+        .context("failed to write the account zero state")
+        .or_fatal()?;
+    Ok(ActorState::new(
+        *manifest.get_account_code(),
+        *EMPTY_ARR_CID,
+        TokenAmount::zero(),
+        0,
+    ))
 }
 
 impl Cbor for State {}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cid::Cid;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+
+    use super::*;
+
+    /// The head of actors created by Lotus until their constructor runs (`EmptyObjectCid`).
+    const LOTUS_EMPTY_OBJECT_CID: &str =
+        "bafy2bzacea3wsdh6y3a36tb3skempjoxqpuyompjbmfeyf34fi3uy6uue42v4";
+
+    #[test]
+    fn account_zero_state() {
+        let manifest = Manifest::dummy();
+        let store = MemoryBlockstore::default();
+        let state = zero_state(&manifest, &store).unwrap();
+
+        assert_eq!(
+            state,
+            ActorState {
+                code: *manifest.get_account_code(),
+                state: Cid::from_str(LOTUS_EMPTY_OBJECT_CID).unwrap(),
+                sequence: 0,
+                balance: TokenAmount::zero(),
+                delegated_address: None,
+            }
+        );
+        assert_eq!(store.get(&state.state).unwrap(), Some(vec![0x80]));
+    }
+}
@@ -2,13 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{Cursor, Read, Seek};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
 use cid::Cid;
-use fvm_ipld_blockstore::{Blockstore, Buffered};
+use fvm_ipld_blockstore::{Blockstore, BlockstoreContext, BlockstoreOp, Buffered};
 use fvm_ipld_encoding::DAG_CBOR;
 use fvm_shared::commcid::{FIL_COMMITMENT_SEALED, FIL_COMMITMENT_UNSEALED};
 
@@ -34,6 +34,38 @@ where
     pub fn into_inner(self) -> BS {
         self.base
     }
+
+    /// Flushes the write buffer like [`Buffered::flush`], additionally returning the number of
+    /// blocks written to the base blockstore and dropped from the buffer.
+    pub fn flush_with_stats(&self, root: &Cid) -> Result<BufferFlushStats> {
+        let mut buffer = Vec::new();
+        let mut s = self.write.borrow_mut();
+        copy_rec(&s, *root, &mut HashSet::new(), &mut buffer)?;
+
+        let stats = BufferFlushStats {
+            blocks_written: buffer.len() as u64,
+            blocks_skipped: (s.len() - buffer.len()) as u64,
+        };
+        self.base
+            .put_many_keyed(buffer)
+            .with_context(|| format!("failed to write the blocks under {}", root))?;
+        *s = Default::default();
+
+        Ok(stats)
+    }
+
+    /// Creates a new buffered blockstore sharing this store's base blockstore, along with a copy of
+    /// this store's (unflushed) write buffer. Writes to either store won't be visible to the other
+    /// until flushed.
+    pub fn fork(&self) -> Self
+    where
+        BS: Clone,
+    {
+        Self {
+            base: self.base.clone(),
+            write: RefCell::new(self.write.borrow().clone()),
+        }
+    }
 }
 
 impl<BS> Buffered for BufferedBlockstore<BS>
@@ -44,17 +76,19 @@ where
     /// This will recursively traverse the cache and write all data connected by links to this
     /// root Cid.
     fn flush(&self, root: &Cid) -> Result<()> {
-        let mut buffer = Vec::new();
-        let mut s = self.write.borrow_mut();
-        copy_rec(&s, *root, &mut buffer)?;
-
-        self.base.put_many_keyed(buffer)?;
-        *s = Default::default();
-
-        Ok(())
+        self.flush_with_stats(root).map(|_| ())
     }
 }
 
+/// What a [`BufferedBlockstore::flush_with_stats`] did with the write buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BufferFlushStats {
+    /// The blocks reachable from the root, written to the base blockstore.
+    pub blocks_written: u64,
+    /// The blocks unreachable from the root, dropped.
+    pub blocks_skipped: u64,
+}
+
 /// Given a CBOR encoded Buffer, returns a tuple of:
 /// the type of the CBOR object along with extra
 /// elements we expect to read. More info on this can be found in
@@ -163,10 +197,12 @@ where
     Ok(())
 }
 
-/// Copies the IPLD DAG under `root` from the cache to the base store.
+/// Copies the IPLD DAG under `root` from the cache to the base store, skipping the blocks in
+/// `seen` (and adding those it copies).
 fn copy_rec<'a>(
     cache: &'a HashMap<Cid, Vec<u8>>,
     root: Cid,
+    seen: &mut HashSet<Cid>,
     buffer: &mut Vec<(Cid, &'a [u8])>,
 ) -> Result<()> {
     const DAG_RAW: u64 = 0x55;
@@ -190,7 +226,7 @@ fn copy_rec<'a>(
         // We shouldn't be creating these at the moment, but lotus' vm.Copy supports them.
         (DAG_CBOR, IDENTITY, _) => {
             return scan_for_links(&mut Cursor::new(root.hash().digest()), |link| {
-                copy_rec(cache, link, buffer)
+                copy_rec(cache, link, seen, buffer)
             })
         }
         // Ignore commitments (not even going to check the hash function.
@@ -213,12 +249,17 @@ fn copy_rec<'a>(
         None => return Ok(()),
     };
 
+    // Shared sub-DAGs only need to be copied once.
+    if !seen.insert(root) {
+        return Ok(());
+    }
+
     // At the moment, we only expect dag-cbor and raw.
     // In M2, we'll need to copy explicitly.
     if root.codec() == DAG_CBOR {
         // TODO(M2): Make this non-recursive.
         scan_for_links(&mut Cursor::new(block), |link| {
-            copy_rec(cache, link, buffer)
+            copy_rec(cache, link, seen, buffer)
         })?;
     }
 
@@ -236,7 +277,7 @@ where
         Ok(if let Some(data) = self.write.borrow().get(cid) {
             Some(data.clone())
         } else {
-            self.base.get(cid)?
+            self.base.get(cid).block_context(BlockstoreOp::Get, cid)?
         })
     }
 
@@ -249,8 +290,34 @@ where
         if self.write.borrow().contains_key(k) {
             Ok(true)
         } else {
-            Ok(self.base.has(k)?)
+            self.base.has(k).block_context(BlockstoreOp::Has, k)
+        }
+    }
+
+    fn has_many(&self, ks: &[Cid]) -> Result<Vec<bool>> {
+        let write = self.write.borrow();
+        let mut result: Vec<_> = ks.iter().map(|k| write.contains_key(k)).collect();
+
+        // Ask the base store about the remaining blocks in one batch.
+        let missing: Vec<_> = ks
+            .iter()
+            .zip(&result)
+            .filter(|(_, found)| !**found)
+            .map(|(k, _)| *k)
+            .collect();
+        if !missing.is_empty() {
+            let mut in_base = self
+                .base
+                .has_many(&missing)
+                .with_context(|| format!("failed to check for {} blocks", missing.len()))?
+                .into_iter();
+            for found in result.iter_mut().filter(|found| !**found) {
+                *found = in_base
+                    .next()
+                    .ok_or_else(|| anyhow!("base blockstore returned too few results"))?;
+            }
         }
+        Ok(result)
     }
 
     fn put_many_keyed<D, I>(&self, blocks: I) -> Result<()>
@@ -269,7 +336,7 @@ where
 #[cfg(test)]
 mod tests {
     use cid::multihash::{Code, Multihash};
-    use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+    use fvm_ipld_blockstore::{Block, Blockstore, MemoryBlockstore};
     use fvm_ipld_encoding::CborStore;
     use fvm_shared::{commcid, IDENTITY_HASH};
     use serde::{Deserialize, Serialize};
@@ -293,6 +360,43 @@ mod tests {
         assert!(buf_store.write.borrow().get(&cid).is_none());
     }
 
+    #[test]
+    fn buffered_flush_stats() {
+        let mem = MemoryBlockstore::default();
+        let buf_store = BufferedBlockstore::new(&mem);
+        let leaf = buf_store.put_cbor(&8u8, Code::Blake2b256).unwrap();
+        let root = buf_store.put_cbor(&(leaf, leaf), Code::Blake2b256).unwrap();
+        buf_store.put_cbor(&27u8, Code::Blake2b256).unwrap();
+
+        // The leaf is linked twice, but only written once.
+        assert_eq!(
+            buf_store.flush_with_stats(&root).unwrap(),
+            BufferFlushStats {
+                blocks_written: 2,
+                blocks_skipped: 1,
+            }
+        );
+        assert_eq!(mem.get_cbor::<u8>(&leaf).unwrap(), Some(8));
+        assert!(buf_store.write.borrow().is_empty());
+    }
+
+    #[test]
+    fn buffered_has_many() {
+        let mem = MemoryBlockstore::default();
+        let in_base = mem.put_cbor(&1u8, Code::Blake2b256).unwrap();
+        let buf_store = BufferedBlockstore::new(&mem);
+        let in_buffer = buf_store.put_cbor(&2u8, Code::Blake2b256).unwrap();
+        let missing = Block::new(DAG_CBOR, [3u8]).cid(Code::Blake2b256);
+
+        assert_eq!(
+            buf_store
+                .has_many(&[missing, in_buffer, in_base, missing])
+                .unwrap(),
+            vec![false, true, true, false]
+        );
+        assert_eq!(buf_store.has_many(&[]).unwrap(), Vec::<bool>::new());
+    }
+
     #[test]
     fn buffered_store_with_links() {
         let mem = MemoryBlockstore::default();
@@ -1,4 +1,4 @@
 //! Private blockstores for use in the FVM.
 
 mod buffered;
-pub use buffered::BufferedBlockstore;
+pub use buffered::{BufferFlushStats, BufferedBlockstore};
@@ -1,27 +1,55 @@
+use std::any::Any;
+use std::collections::HashSet;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::rc::Rc;
+use std::time::Instant;
+
 use anyhow::{anyhow, Context};
+use cid::Cid;
 use derive_more::{Deref, DerefMut};
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::{to_vec, RawBytes, DAG_CBOR};
-use fvm_shared::address::{Address, Protocol};
+use fvm_shared::address::{Address, Payload};
+use fvm_shared::builtin::singletons::SYSTEM_ACTOR_ID;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::state::StateTreeVersion;
 use fvm_shared::sys::BlockId;
+use fvm_shared::version::NetworkVersion;
 use fvm_shared::{ActorID, MethodNum, METHOD_SEND};
 use num_traits::Zero;
+use wasmtime::{AsContextMut, Trap, TypedFunc};
 
 use super::{Backtrace, CallManager, InvocationResult, NO_DATA_BLOCK_ID};
 use crate::call_manager::backtrace::Frame;
 use crate::call_manager::FinishRet;
-use crate::gas::{Gas, GasTracker};
-use crate::kernel::{Block, BlockRegistry, ExecutionError, Kernel, Result, SyscallError};
-use crate::machine::Machine;
+use crate::gas::{Gas, GasCharge, GasLimit, GasMultiplier, GasTracker};
+use crate::kernel::{
+    Block, BlockBudget, BlockRegistry, ClassifyResult, ExecutionError, Kernel, Result, SyscallError,
+};
+use crate::machine::{AbiVersion, Engine, Machine, ValidationError};
 use crate::syscalls::error::Abort;
 use crate::syscalls::{charge_for_exec, update_gas_available};
 use crate::trace::{ExecutionEvent, ExecutionTrace};
-use crate::{account_actor, syscall_error};
+use crate::{account_actor, placeholder_actor, syscall_error};
 
 /// The default [`CallManager`] implementation.
+///
+/// The call manager is moved into the kernel of each invoked actor, and back out once the actor
+/// returns. If the FVM panics in between, the call manager (and its machine) is lost, leaving it
+/// poisoned: sends and transactions then fail with fatal errors recording the panic, and so does
+/// [`CallManager::finish`].
 #[repr(transparent)]
-pub struct DefaultCallManager<M>(Option<Box<InnerDefaultCallManager<M>>>);
+pub struct DefaultCallManager<M>(std::result::Result<Box<InnerDefaultCallManager<M>>, Poison>);
+
+/// Why a call manager is poisoned.
+struct Poison(String);
+
+impl Poison {
+    fn error(&self) -> ExecutionError {
+        ExecutionError::Fatal(anyhow!("call manager is poisoned: {}", self.0))
+    }
+}
 
 #[doc(hidden)]
 #[derive(Deref, DerefMut)]
@@ -37,8 +65,15 @@ pub struct InnerDefaultCallManager<M> {
     origin: (ActorID, Address),
     /// The nonce of the chain message that initiated this call stack.
     nonce: u64,
-    /// Number of actors created in this call stack.
+    /// Number of actor addresses handed out by [`CallManager::next_actor_idx`] in this call stack.
+    /// This feeds into the addresses of new actors, so it must never change meaning; it's bumped
+    /// even if no actor ends up being created, and doesn't cover accounts or placeholders.
     num_actors_created: u64,
+    /// Number of actors actually created in this call stack, of any kind, as counted by
+    /// [`CallManager::count_actor_creation`]. This is what
+    /// [`NetworkConfig::max_actors_created`](crate::machine::NetworkConfig::max_actors_created)
+    /// limits.
+    actors_created_for_limit: u64,
     /// Current call-stack depth.
     call_stack_depth: u32,
     /// The current chain of errors, if any.
@@ -47,21 +82,59 @@ pub struct InnerDefaultCallManager<M> {
     exec_trace: ExecutionTrace,
     /// Number of actors that have been invoked in this message execution.
     invocation_count: u64,
+    /// Limits the total size of the blocks held by the block registries in this call stack.
+    block_budget: Rc<BlockBudget>,
+    /// The actors that self-destructed during their current invocation, and are deleted when it
+    /// returns. Sends to them fail as if they had already been deleted.
+    pending_deletions: HashSet<ActorID>,
+    /// Set while an account actor's constructor is running, to forbid creating further account
+    /// actors from within it.
+    creating_account: bool,
 }
 
-#[doc(hidden)]
-impl<M> std::ops::Deref for DefaultCallManager<M> {
-    type Target = InnerDefaultCallManager<M>;
+impl<M> InnerDefaultCallManager<M> {
+    fn trace(&mut self, trace: ExecutionEvent) {
+        self.exec_trace.extend(
+            self.gas_tracker
+                .drain_trace()
+                .map(ExecutionEvent::GasCharge),
+        );
 
-    fn deref(&self) -> &Self::Target {
-        self.0.as_ref().expect("call manager is poisoned")
+        self.exec_trace.push(trace);
     }
 }
 
-#[doc(hidden)]
-impl<M> std::ops::DerefMut for DefaultCallManager<M> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.0.as_mut().expect("call manager is poisoned")
+impl<M> DefaultCallManager<M> {
+    /// Returns the call manager's state, or a fatal error if a panic poisoned it.
+    fn inner(&self) -> Result<&InnerDefaultCallManager<M>> {
+        self.0.as_deref().map_err(Poison::error)
+    }
+
+    /// Returns the call manager's state, or a fatal error if a panic poisoned it.
+    fn inner_mut(&mut self) -> Result<&mut InnerDefaultCallManager<M>> {
+        self.0.as_deref_mut().map_err(|poison| poison.error())
+    }
+
+    /// Returns the call manager's state for the [`CallManager`] accessors, which can't fail.
+    ///
+    /// # Panics
+    ///
+    /// If the call manager is poisoned. Sends and transactions fail with a fatal error as soon as
+    /// that happens, so this is only reachable by the kernels the panic unwound through, and their
+    /// invocations catch the panic in turn.
+    fn live(&self) -> &InnerDefaultCallManager<M> {
+        match &self.0 {
+            Ok(inner) => inner,
+            Err(poison) => panic!("call manager is poisoned: {}", poison.0),
+        }
+    }
+
+    /// Like [`DefaultCallManager::live`], but mutable.
+    fn live_mut(&mut self) -> &mut InnerDefaultCallManager<M> {
+        match &mut self.0 {
+            Ok(inner) => inner,
+            Err(poison) => panic!("call manager is poisoned: {}", poison.0),
+        }
     }
 }
 
@@ -73,25 +146,33 @@ where
 
     fn new(
         machine: M,
-        gas_limit: i64,
+        gas_limit: GasLimit,
         origin: (ActorID, Address),
         nonce: u64,
         gas_premium: TokenAmount,
     ) -> Self {
-        let mut gas_tracker = GasTracker::new(Gas::new(gas_limit), Gas::zero(), gas_premium);
+        let mut gas_tracker = GasTracker::new(gas_limit.as_gas(), Gas::zero(), gas_premium);
         if machine.context().tracing {
             gas_tracker.enable_tracing()
         }
-        DefaultCallManager(Some(Box::new(InnerDefaultCallManager {
+        if machine.context().gas_calibration {
+            gas_tracker.enable_stats()
+        }
+        let block_budget = Rc::new(BlockBudget::new(machine.context().max_block_registry_bytes));
+        DefaultCallManager(Ok(Box::new(InnerDefaultCallManager {
             machine,
             gas_tracker,
             origin,
             nonce,
             num_actors_created: 0,
+            actors_created_for_limit: 0,
             call_stack_depth: 0,
             backtrace: Backtrace::default(),
             exec_trace: vec![],
             invocation_count: 0,
+            block_budget,
+            pending_deletions: HashSet::new(),
+            creating_account: false,
         })))
     }
 
@@ -106,8 +187,9 @@ where
     where
         K: Kernel<CallManager = Self>,
     {
-        if self.machine.context().tracing {
-            self.trace(ExecutionEvent::Call {
+        let inner = self.inner_mut()?;
+        if inner.machine.context().tracing {
+            inner.trace(ExecutionEvent::Call {
                 from,
                 to,
                 method,
@@ -132,35 +214,21 @@ where
         //
         // NOTE: Unlike the FVM, Lotus adds _then_ checks. It does this because the
         // `call_stack_depth` in lotus is 0 for the top-level call, unlike in the FVM where it's 1.
-        if self.call_stack_depth > self.machine.context().max_call_depth {
+        if inner.call_stack_depth > inner.machine.context().max_call_depth {
             let sys_err = syscall_error!(LimitExceeded, "message execution exceeds call depth");
-            if self.machine.context().tracing {
-                self.trace(ExecutionEvent::CallError(sys_err.clone()));
+            if inner.machine.context().tracing {
+                inner.trace(ExecutionEvent::CallError(sys_err.clone()));
             }
             return Err(sys_err.into());
         }
-        self.call_stack_depth += 1;
+        inner.call_stack_depth += 1;
         let result = self.send_unchecked::<K>(from, to, method, params, value);
-        self.call_stack_depth -= 1;
-
-        if self.machine.context().tracing {
-            self.trace(match &result {
-                Ok(InvocationResult::Return(v)) => ExecutionEvent::CallReturn(
-                    v.as_ref()
-                        .map(|blk| RawBytes::from(blk.data().to_vec()))
-                        .unwrap_or_default(),
-                ),
-                Ok(InvocationResult::Failure(code)) => ExecutionEvent::CallAbort(*code),
-
-                Err(ExecutionError::OutOfGas) => ExecutionEvent::CallError(SyscallError::new(
-                    ErrorNumber::Forbidden,
-                    "out of gas",
-                )),
-                Err(ExecutionError::Fatal(_)) => {
-                    ExecutionEvent::CallError(SyscallError::new(ErrorNumber::Forbidden, "fatal"))
-                }
-                Err(ExecutionError::Syscall(s)) => ExecutionEvent::CallError(s.clone()),
-            });
+
+        // The send may have poisoned the call manager.
+        let inner = self.inner_mut()?;
+        inner.call_stack_depth -= 1;
+        if inner.machine.context().tracing {
+            inner.trace(return_event(&result));
         }
 
         result
@@ -170,80 +238,115 @@ where
         &mut self,
         f: impl FnOnce(&mut Self) -> Result<InvocationResult>,
     ) -> Result<InvocationResult> {
-        self.state_tree_mut().begin_transaction();
+        self.inner_mut()?
+            .machine
+            .state_tree_mut()
+            .begin_transaction();
         let (revert, res) = match f(self) {
-            Ok(v) => (!v.exit_code().is_success(), Ok(v)),
+            Ok(v) => (!v.exit_code.is_success(), Ok(v)),
             Err(e) => (true, Err(e)),
         };
-        self.state_tree_mut().end_transaction(revert)?;
+        // If the call manager was poisoned, the state tree is gone along with the transaction.
+        self.inner_mut()?
+            .machine
+            .state_tree_mut()
+            .end_transaction(revert)?;
         res
     }
 
-    fn finish(mut self) -> (FinishRet, Self::Machine) {
+    fn finish(self) -> Result<(FinishRet, Self::Machine)> {
         let InnerDefaultCallManager {
             machine,
             backtrace,
             mut gas_tracker,
             mut exec_trace,
+            actors_created_for_limit: actors_created,
             ..
-        } = *self.0.take().expect("call manager is poisoned");
+        } = *self.0.map_err(|poison| poison.error())?;
 
-        // TODO: Having to check against zero here is fishy, but this is what lotus does.
-        let gas_used = gas_tracker.gas_used().max(Gas::zero()).round_up();
+        // The gas tracker never lets gas used go negative (or exceed the limit). The executor
+        // checks this in release builds.
+        debug_assert!(!gas_tracker.gas_used().is_negative());
+        let gas_used = gas_tracker.gas_used().round_up();
+
+        let gas_stats = gas_tracker.take_stats();
 
         // Finalize any trace events, if we're tracing.
         if machine.context().tracing {
             exec_trace.extend(gas_tracker.drain_trace().map(ExecutionEvent::GasCharge));
         }
 
-        (
+        Ok((
             FinishRet {
                 gas_used,
                 backtrace,
                 exec_trace,
+                gas_stats,
+                actors_created,
             },
             machine,
-        )
+        ))
     }
 
     // Accessor methods so the trait can implement some common methods by default.
 
     fn machine(&self) -> &Self::Machine {
-        &self.machine
+        &self.live().machine
     }
 
     fn machine_mut(&mut self) -> &mut Self::Machine {
-        &mut self.machine
+        &mut self.live_mut().machine
     }
 
     fn gas_tracker(&self) -> &GasTracker {
-        &self.gas_tracker
+        &self.live().gas_tracker
     }
 
     fn gas_tracker_mut(&mut self) -> &mut GasTracker {
-        &mut self.gas_tracker
+        &mut self.live_mut().gas_tracker
     }
 
     // Other accessor methods
 
     fn origin(&self) -> (ActorID, &Address) {
-        (self.origin.0, &self.origin.1)
+        let origin = &self.live().origin;
+        (origin.0, &origin.1)
     }
 
     fn nonce(&self) -> u64 {
-        self.nonce
+        self.live().nonce
     }
 
     // Helper for creating actors. This really doesn't belong on this trait.
 
     fn next_actor_idx(&mut self) -> u64 {
-        let ret = self.num_actors_created;
-        self.num_actors_created += 1;
+        let inner = self.live_mut();
+        let ret = inner.num_actors_created;
+        inner.num_actors_created += 1;
         ret
     }
 
+    fn count_actor_creation(&mut self, id: ActorID) -> Result<()> {
+        self.check_actor_creation_limit()?;
+        let inner = self.inner_mut()?;
+        inner.actors_created_for_limit += 1;
+        if inner.machine.context().tracing {
+            let count = inner.actors_created_for_limit;
+            inner.trace(ExecutionEvent::ActorCreated { id, count });
+        }
+        Ok(())
+    }
+
+    fn actors_created(&self) -> u64 {
+        self.live().actors_created_for_limit
+    }
+
+    fn mark_pending_deletion(&mut self, id: ActorID) {
+        self.live_mut().pending_deletions.insert(id);
+    }
+
     fn invocation_count(&self) -> u64 {
-        self.invocation_count
+        self.live().invocation_count
     }
 }
 
@@ -251,22 +354,41 @@ impl<M> DefaultCallManager<M>
 where
     M: Machine,
 {
-    fn trace(&mut self, trace: ExecutionEvent) {
-        // The price of deref magic is that you sometimes need to tell the compiler: no, this is
-        // fine.
-        let s = &mut **self;
-
-        s.exec_trace
-            .extend(s.gas_tracker.drain_trace().map(ExecutionEvent::GasCharge));
-
-        s.exec_trace.push(trace);
+    /// Fails with `LimitExceeded` if the message has already created
+    /// [`NetworkConfig::max_actors_created`](crate::machine::NetworkConfig::max_actors_created)
+    /// actors.
+    fn check_actor_creation_limit(&self) -> Result<()> {
+        let inner = self.inner()?;
+        if let Some(max) = inner.machine.context().max_actors_created {
+            if inner.actors_created_for_limit >= max {
+                return Err(syscall_error!(
+                    LimitExceeded;
+                    "message exceeds the limit of {} created actors", max
+                )
+                .into());
+            }
+        }
+        Ok(())
     }
 
-    fn create_account_actor<K>(&mut self, addr: &Address) -> Result<ActorID>
+    /// Creates an account actor for a key address and invokes its constructor. Returns the exit
+    /// code if the constructor fails; the caller is expected to revert the creation.
+    fn create_account_actor<K>(
+        &mut self,
+        addr: &Address,
+    ) -> Result<std::result::Result<ActorID, ExitCode>>
     where
         K: Kernel<CallManager = Self>,
     {
-        self.charge_gas(self.price_list().on_create_actor())?;
+        if self.inner()?.creating_account {
+            return Err(syscall_error!(
+                Forbidden;
+                "cannot create account actor {} from within an account constructor", addr
+            )
+            .into());
+        }
+
+        self.charge_gas(self.price_list().on_create_account_actor())?;
 
         if addr.is_bls_zero_address() {
             return Err(
@@ -274,33 +396,105 @@ where
             );
         }
 
+        // Check the limit before assigning an ID to the address.
+        self.check_actor_creation_limit()?;
+
         // Create the actor in the state tree.
         let id = {
-            let code_cid = self.builtin_actors().get_account_code();
-            let state = account_actor::zero_state(*code_cid);
-            self.create_actor(addr, state)?
+            let state =
+                account_actor::zero_state(self.machine().builtin_actors(), self.blockstore())?;
+            let id = self.machine_mut().create_actor(addr, state)?;
+            self.count_actor_creation(id)?;
+            id
         };
 
         // Now invoke the constructor; first create the parameters, then
         // instantiate a new kernel to invoke the constructor.
-        let params = to_vec(&addr).map_err(|e| {
-            // This shouldn't happen, but we treat it as an illegal argument error and move on.
-            // It _likely_ means that the inputs were invalid in some unexpected way.
-            log::error!(
-                "failed to serialize address when creating actor, ignoring: {}",
-                e
-            );
-            syscall_error!(IllegalArgument; "failed to serialize params: {}", e)
-        })?;
+        //
+        // Serializing an address can't fail, so a failure here is a bug in the FVM, not the
+        // sender's fault.
+        let params = to_vec(&addr)
+            .context("failed to serialize account constructor params")
+            .or_fatal()?;
+
+        // The params are passed to the constructor like those of any other send.
+        self.charge_gas(self.price_list().on_send_params(params.len()))?;
+
+        let inner = self.inner_mut()?;
+        if inner.machine.context().tracing {
+            inner.trace(ExecutionEvent::ImplicitCall {
+                from: SYSTEM_ACTOR_ID,
+                to: Address::new_id(id),
+                method: fvm_shared::METHOD_CONSTRUCTOR,
+                params: params.clone().into(),
+                value: TokenAmount::zero(),
+            });
+        }
 
-        self.send_resolved::<K>(
-            account_actor::SYSTEM_ACTOR_ID,
+        inner.creating_account = true;
+        let res = self.send_resolved::<K>(
+            SYSTEM_ACTOR_ID,
             id,
             fvm_shared::METHOD_CONSTRUCTOR,
             Some(Block::new(DAG_CBOR, params)),
             &TokenAmount::zero(),
-        )?;
+        );
+
+        // The constructor may have poisoned the call manager.
+        let inner = self.inner_mut()?;
+        inner.creating_account = false;
+        if inner.machine.context().tracing {
+            inner.trace(return_event(&res));
+        }
+
+        let res = res?;
+        if res.exit_code.is_success() {
+            Ok(Ok(id))
+        } else {
+            Ok(Err(res.exit_code))
+        }
+    }
+
+    /// Creates a placeholder actor for an f4 address. The address's namespace actor must exist, the
+    /// network must have a placeholder actor, and the state tree must be able to record delegated
+    /// addresses (V5 or later).
+    fn create_placeholder_actor(&mut self, addr: &Address) -> Result<ActorID> {
+        let namespace = match addr.payload() {
+            Payload::Delegated(delegated) => delegated.namespace(),
+            _ => return Err(syscall_error!(NotFound; "actor does not exist: {}", addr).into()),
+        };
+
+        if self.state_tree().version() < StateTreeVersion::V5 {
+            return Err(syscall_error!(
+                NotFound;
+                "actor does not exist: {} ({:?} state trees can't hold delegated addresses)",
+                addr, self.state_tree().version()
+            )
+            .into());
+        }
+
+        let state = match placeholder_actor::zero_state(
+            self.machine().builtin_actors(),
+            self.blockstore(),
+            addr,
+        )? {
+            Some(state) => state,
+            None => return Err(syscall_error!(NotFound; "actor does not exist: {}", addr).into()),
+        };
+
+        if self.state_tree().get_actor_id(namespace)?.is_none() {
+            return Err(syscall_error!(
+                NotFound;
+                "actor does not exist: {} (namespace actor {} does not exist)", addr, namespace
+            )
+            .into());
+        }
+
+        self.charge_gas(self.price_list().on_create_actor())?;
+        self.check_actor_creation_limit()?;
 
+        let id = self.machine_mut().create_actor(addr, state)?;
+        self.count_actor_creation(id)?;
         Ok(id)
     }
 
@@ -316,14 +510,33 @@ where
     where
         K: Kernel<CallManager = Self>,
     {
-        // Get the receiver; this will resolve the address.
-        let to = match self.state_tree().lookup_id(&to)? {
+        // Get the receiver; this will resolve the address. Actors pay for resolving the addresses
+        // they send to, but resolving the receiver of the top-level message is free.
+        let lookup = if self.inner()?.call_stack_depth > 1 {
+            self.with_metered_state_tree(|st| st.lookup_id(&to))?
+        } else {
+            self.state_tree().lookup_id(&to)?
+        };
+        let to = match lookup {
             Some(addr) => addr,
-            None => match to.protocol() {
-                Protocol::BLS | Protocol::Secp256k1 => {
+            None => match to.payload() {
+                Payload::BLS(_) | Payload::Secp256k1(_) => {
                     // Try to create an account actor if the receiver is a key address.
-                    self.create_account_actor::<K>(&to)?
+                    match self.create_account_actor::<K>(&to)? {
+                        Ok(id) => id,
+                        Err(code) => {
+                            // The constructor's failure is already on the backtrace. Fail the send
+                            // without transferring any funds; the caller's transaction reverts the
+                            // actor creation.
+                            log::trace!("failed to construct account actor {}: {}", to, code);
+                            return Ok(InvocationResult::failure(ExitCode::SYS_INVALID_RECEIVER));
+                        }
+                    }
                 }
+                // Sending to an f4 address creates a placeholder to hold the funds until the actor
+                // behind the address is deployed.
+                Payload::Delegated(_) => self.create_placeholder_actor(&to)?,
+                // Unresolved actor (f2) addresses, and IDs above the maximum.
                 _ => return Err(syscall_error!(NotFound; "actor does not exist: {}", to).into()),
             },
         };
@@ -345,28 +558,126 @@ where
     where
         K: Kernel<CallManager = Self>,
     {
+        // An actor that self-destructed can't be invoked again, or receive funds, before it's
+        // deleted.
+        if self.inner()?.pending_deletions.contains(&to) {
+            return Err(syscall_error!(NotFound; "actor {} is being deleted", to).into());
+        }
+
         // Lookup the actor.
         let state = self
             .state_tree()
             .get_actor_id(to)?
             .ok_or_else(|| syscall_error!(NotFound; "actor does not exist: {}", to))?;
 
+        // Builtin actors deployed under another network version run as the equivalent actor of the
+        // current version.
+        let code = self.machine().builtin_actors().resolve_code(&state.code);
+
+        // Scale everything charged while this actor's frame is active, restoring the caller's
+        // multiplier once it returns.
+        let multiplier = self
+            .price_list()
+            .actor_multiplier(&code)
+            .unwrap_or(GasMultiplier::ONE);
+        let caller_multiplier = self.inner_mut()?.gas_tracker.set_multiplier(multiplier);
+        let res = self.invoke_resolved::<K>(from, to, code, method, params, value);
+        // The invocation may have poisoned the call manager.
+        self.inner_mut()?
+            .gas_tracker
+            .set_multiplier(caller_multiplier);
+        res
+    }
+
+    /// Invoke the actor with the given (resolved) code, in its own frame.
+    fn invoke_resolved<K>(
+        &mut self,
+        from: ActorID,
+        to: ActorID,
+        code: Cid,
+        method: MethodNum,
+        params: Option<Block>,
+        value: &TokenAmount,
+    ) -> Result<InvocationResult>
+    where
+        K: Kernel<CallManager = Self>,
+    {
         // Charge the method gas. Not sure why this comes second, but it does.
         self.charge_gas(self.price_list().on_method_invocation(value, method))?;
 
+        // Placeholders can receive funds, but have no code to invoke.
+        if method != METHOD_SEND && self.machine().builtin_actors().is_placeholder_actor(&code) {
+            return Ok(InvocationResult::failure(ExitCode::SYS_INVALID_RECEIVER));
+        }
+
+        // Builtin methods introduced in a later network version don't exist yet.
+        if let Some(since) = self
+            .machine()
+            .builtin_actors()
+            .name_by_code(&code)
+            .and_then(|name| {
+                self.context()
+                    .feature_availability
+                    .method_since(name, method)
+            })
+        {
+            if self.context().network_version < since {
+                log::trace!("{}::{} unavailable before nv{}", to, method, since);
+                return Ok(InvocationResult::failure(ExitCode::USR_UNHANDLED_MESSAGE));
+            }
+        }
+
+        // Reject params the builtin method can't decode without instantiating the actor.
+        if self.context().validate_builtin_params {
+            if let (Some(params), Some(schema)) = (
+                &params,
+                self.machine().builtin_actors().param_schema(&code, method),
+            ) {
+                if !schema.accepts(params) {
+                    log::trace!("invalid params for {}::{}", to, method);
+                    return Ok(InvocationResult::failure(ExitCode::USR_SERIALIZATION));
+                }
+            }
+        }
+
         // Transfer, if necessary.
         if !value.is_zero() {
-            self.machine.transfer(from, to, value)?;
+            self.inner_mut()?.machine.transfer(from, to, value)?;
         }
 
         // Abort early if we have a send.
         if method == METHOD_SEND {
             log::trace!("sent {} -> {}: {}", from, to, &value);
-            return Ok(InvocationResult::Return(Default::default()));
+            return Ok(InvocationResult::default());
+        }
+
+        // Let the embedder complete the call instead of the actor, if it wants to.
+        if let Some(interceptor) = self.context().call_interceptor.clone() {
+            let intercepted = interceptor
+                .lock()
+                .map_err(|_| anyhow!("call interceptor poisoned"))
+                .or_fatal()?
+                .intercept(from, to, method, params.as_ref(), value);
+            if let Some(res) = intercepted {
+                log::trace!("intercepted {} -> {}::{}", from, to, method);
+                let inner = self.inner_mut()?;
+                if inner.machine.context().tracing {
+                    inner.trace(ExecutionEvent::Intercepted(res.exit_code));
+                }
+                self.charge_gas(GasCharge::new("OnInterceptedCall", res.gas, Gas::zero()))?;
+                return Ok(if res.exit_code.is_success() {
+                    InvocationResult::ok(
+                        (!res.return_data.is_empty())
+                            .then(|| Block::new(DAG_CBOR, res.return_data.bytes())),
+                    )
+                } else {
+                    InvocationResult::failure(res.exit_code)
+                });
+            }
         }
 
         // Store the parametrs, and initialize the block registry for the target actor.
-        let mut block_registry = BlockRegistry::new();
+        let mut block_registry = BlockRegistry::with_budget(self.inner()?.block_budget.clone());
         let params_id = if let Some(blk) = params {
             block_registry.put(blk)?
         } else {
@@ -374,37 +685,82 @@ where
         };
 
         // Increment invocation count
-        self.invocation_count += 1;
+        self.inner_mut()?.invocation_count += 1;
 
         // This is a cheap operation as it doesn't actually clone the struct,
         // it returns a referenced copy.
-        let engine = self.engine().clone();
+        let engine = self.machine().engine().clone();
+
+        let metrics = self.context().metrics.clone();
+        if engine.is_module_cached(&code) {
+            metrics.record_module_cache_hit();
+        } else {
+            metrics.record_module_cache_miss();
+        }
+
+        // Charge for loading the actor's code. This must happen before compiling it, so that running
+        // out of gas can't be used to get compilation work for free. The charge only depends on the
+        // size of the code, never on whether this node has already compiled it. Builtin actors are
+        // loaded when the machine is constructed, so they're not charged.
+        if !self.machine().builtin_actors().is_builtin_actor(&code) {
+            let size = engine
+                .code_size(self.blockstore(), &code)
+                .with_context(|| format!("failed to lookup the code {} of actor {}", code, to))
+                .or_fatal()?
+                .ok_or_else(|| {
+                    ExecutionError::Fatal(anyhow!("code {} of actor {} not found", code, to))
+                })?;
+            self.charge_gas(self.price_list().on_load_module(size))?;
+        }
 
         // Ensure that actor's code is loaded and cached in the engine.
         // NOTE: this does not cover the EVM smart contract actor, which is a built-in actor, is
         // listed the manifest, and therefore preloaded during system initialization.
         #[cfg(feature = "m2-native")]
-        self.engine()
-            .prepare_actor_code(&state.code, self.blockstore())
-            .map_err(
-                |_| syscall_error!(NotFound; "actor code cid does not exist {}", &state.code),
-            )?;
+        self.machine()
+            .engine()
+            .prepare_actor_code(&code, self.blockstore())
+            .map_err(|_| syscall_error!(NotFound; "actor code cid does not exist {}", &code))?;
+
+        // Load the actor's code to find the ABI version it follows, so that the invocation is
+        // traced before any of the actor's own charges. Invalid code fails the call below, once the
+        // actor's frame has been set up.
+        let abi_version = self.abi_version(&engine, &code, to);
+        if let Ok(version) = abi_version {
+            let inner = self.inner_mut()?;
+            if inner.machine.context().tracing {
+                inner.trace(ExecutionEvent::Invoke(version));
+            }
+        }
 
         log::trace!("calling {} -> {}::{}", from, to, method);
+        let gas_multiplier = self.inner()?.gas_tracker.multiplier();
         self.map_mut(|cm| {
             // Make the kernel.
             let kernel = K::new(cm, block_registry, from, to, method, value.clone());
 
             // Make a store.
             let mut store = engine.new_store(kernel);
+            store.data_mut().gas_multiplier = gas_multiplier;
 
             // From this point on, there are no more syscall errors, only aborts.
-            let result: std::result::Result<BlockId, Abort> = (|| {
+            let invoke_actor = || -> std::result::Result<BlockId, Abort> {
+                let version = abi_version?;
+
                 // Instantiate the module.
-                let instance = engine
-                    .get_instance(&mut store, &state.code)
-                    .and_then(|i| i.context("actor code not found"))
+                let instantiation_start = Instant::now();
+                let (instance, _) = engine
+                    .get_instance(&mut store, &code)
+                    .map_err(invalid_code_abort)?
+                    .with_context(|| format!("code {} of actor {} not found", code, to))
                     .map_err(Abort::Fatal)?;
+                metrics.record_wasm_instantiation(
+                    instantiation_start
+                        .elapsed()
+                        .as_nanos()
+                        .try_into()
+                        .unwrap_or(u64::MAX),
+                );
 
                 // Resolve and store a reference to the exported memory.
                 let memory = instance
@@ -413,20 +769,23 @@ where
                     .map_err(Abort::Fatal)?;
                 store.data_mut().memory = memory;
 
-                // Lookup the invoke method.
-                let invoke: wasmtime::TypedFunc<(u32,), u32> = instance
-                    .get_typed_func(&mut store, "invoke")
-                    // All actors will have an invoke method.
-                    .map_err(Abort::Fatal)?;
+                // Lookup the invoke method, following the actor's ABI.
+                let invoke = match version {
+                    AbiVersion::V1 => instance
+                        .get_typed_func(&mut store, "invoke")
+                        .map(Invoke::V1),
+                    AbiVersion::V2 => instance
+                        .get_typed_func(&mut store, "invoke")
+                        .map(Invoke::V2),
+                }
+                // All actors will have an invoke method.
+                .map_err(Abort::Fatal)?;
 
                 // Set the available gas.
                 update_gas_available(&mut store)?;
 
                 // Invoke it.
-                let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    invoke.call(&mut store, (params_id,))
-                }))
-                .map_err(|panic| Abort::Fatal(anyhow!("panic within actor: {:?}", panic)))?;
+                let res = invoke.call(&mut store, params_id);
 
                 // Charge for any remaining uncharged execution gas, returning an error if we run
                 // out.
@@ -435,13 +794,55 @@ where
                 // If the invocation failed due to running out of exec_units, we have already
                 // detected it and returned OutOfGas above. Any other invocation failure is returned
                 // here as an Abort
-                Ok(res?)
-            })();
+                let (code, ret_id) = split_return(res?);
+
+                // Actors following the V2 ABI (only accepted from nv17) may fail by returning a
+                // non-zero exit code, in which case we discard their state and return value, as if
+                // they had aborted.
+                if code != 0 {
+                    let code = ExitCode::new(code);
+                    return Err(if code.is_system_error() {
+                        Abort::Exit(
+                            ExitCode::SYS_ILLEGAL_EXIT_CODE,
+                            format!("actor returned exit code {}", code),
+                        )
+                    } else {
+                        Abort::Exit(code, "actor returned a non-zero exit code".to_owned())
+                    });
+                }
+
+                // The invocation succeeded, so commit the actor's final state-root.
+                store
+                    .data_mut()
+                    .kernel
+                    .commit_root()
+                    .map_err(Abort::from_error_as_fatal)?;
+
+                Ok(ret_id)
+            };
+
+            // Panics (e.g., in syscalls) are caught while the store still holds the call manager,
+            // and become fatal errors.
+            let result = catch_unwind(AssertUnwindSafe(invoke_actor)).unwrap_or_else(|panic| {
+                Err(Abort::Fatal(anyhow!(
+                    "panic within actor: {}",
+                    panic_message(panic)
+                )))
+            });
 
             let invocation_data = store.into_data();
             let last_error = invocation_data.last_error;
             let (mut cm, block_registry) = invocation_data.kernel.into_inner();
 
+            // If a nested call poisoned the call manager, the actor's result is moot.
+            if let Some(err) = cm.inner().err() {
+                return (Err(err), cm);
+            }
+
+            // Once the invocation returns, the actor has either been deleted or, if it aborted,
+            // restored.
+            cm.live_mut().pending_deletions.remove(&to);
+
             // Resolve the return block's ID into an actual block, converting to an abort if it
             // doesn't exist.
             let result = result.and_then(|ret_id| {
@@ -458,16 +859,24 @@ where
             });
 
             // Process the result, updating the backtrace if necessary.
+            let max_return_size = cm.context().max_return_size;
             let ret = match result {
-                Ok(ret) => Ok(InvocationResult::Return(ret.cloned())),
+                Ok(Some(blk)) if blk.size() as usize > max_return_size => Err(syscall_error!(
+                    LimitExceeded;
+                    "return value of {} bytes exceeds the maximum of {} bytes",
+                    blk.size(),
+                    max_return_size
+                )
+                .into()),
+                Ok(ret) => Ok(InvocationResult::ok(ret.cloned())),
                 Err(abort) => {
                     if let Some(err) = last_error {
-                        cm.backtrace.begin(err);
+                        cm.live_mut().backtrace.begin(err);
                     }
 
                     let (code, message, res) = match abort {
                         Abort::Exit(code, message) => {
-                            (code, message, Ok(InvocationResult::Failure(code)))
+                            (code, message, Ok(InvocationResult::failure(code)))
                         }
                         Abort::OutOfGas => (
                             ExitCode::SYS_OUT_OF_GAS,
@@ -481,7 +890,7 @@ where
                         ),
                     };
 
-                    cm.backtrace.push_frame(Frame {
+                    cm.live_mut().backtrace.push_frame(Frame {
                         source: to,
                         method,
                         message,
@@ -500,7 +909,7 @@ where
                         to,
                         method,
                         from,
-                        val.exit_code()
+                        val.exit_code
                     ),
                     Err(e) => log::trace!("failing {}::{} -> {} (err:{})", to, method, from, e),
                 }
@@ -510,10 +919,135 @@ where
         })
     }
 
-    fn map_mut<F, T>(&mut self, f: F) -> T
+    /// Loads the code of the actor `to` (compiling it if necessary), and returns the ABI version it
+    /// follows. ABI versions other than V1 are only supported from nv17.
+    fn abi_version(
+        &self,
+        engine: &Engine,
+        code: &Cid,
+        to: ActorID,
+    ) -> std::result::Result<AbiVersion, Abort> {
+        let module = engine
+            .get_module(self.blockstore(), code)
+            .map_err(invalid_code_abort)?
+            .with_context(|| format!("code {} of actor {} not found", code, to))
+            .map_err(Abort::Fatal)?;
+        let version = AbiVersion::of_module(&module)
+            .with_context(|| format!("code {} of actor {} has no valid invoke export", code, to))
+            .map_err(Abort::Fatal)?;
+        if version != AbiVersion::V1 && self.context().network_version < NetworkVersion::V17 {
+            return Err(Abort::Exit(
+                ExitCode::SYS_ILLEGAL_INSTRUCTION,
+                format!(
+                    "invalid actor code: ABI version {} is not supported before nv17",
+                    version.value()
+                ),
+            ));
+        }
+        Ok(version)
+    }
+
+    /// Moves the call manager into `f` and back. If `f` panics, the call manager is left poisoned,
+    /// and the panic is returned as a fatal error.
+    fn map_mut<F, T>(&mut self, f: F) -> Result<T>
     where
-        F: FnOnce(Self) -> (T, Self),
+        F: FnOnce(Self) -> (Result<T>, Self),
     {
-        replace_with::replace_with_and_return(self, || DefaultCallManager(None), f)
+        let placeholder = DefaultCallManager(Err(Poison("moved out".to_owned())));
+        let cm = std::mem::replace(self, placeholder);
+        match catch_unwind(AssertUnwindSafe(|| f(cm))) {
+            Ok((ret, cm)) => {
+                *self = cm;
+                ret
+            }
+            Err(panic) => {
+                let poison = Poison(panic_message(panic));
+                let err = poison.error();
+                *self = DefaultCallManager(Err(poison));
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Renders the payload of a caught panic, then drops it. Dropping the payload may panic in turn,
+/// with nothing left to catch it, so that aborts the process.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    struct AbortOnUnwind;
+
+    impl Drop for AbortOnUnwind {
+        fn drop(&mut self) {
+            std::process::abort()
+        }
+    }
+
+    let message = if let Some(s) = payload.downcast_ref::<&str>() {
+        (*s).to_owned()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "non-string panic payload".to_owned()
+    };
+    let guard = AbortOnUnwind;
+    drop(payload);
+    std::mem::forget(guard);
+    message
+}
+
+/// Converts an error loading or instantiating an actor's code into an abort. Invalid actor code is
+/// the actor's fault, not the system's.
+fn invalid_code_abort(e: anyhow::Error) -> Abort {
+    match e.downcast_ref::<ValidationError>() {
+        Some(err) => Abort::Exit(
+            ExitCode::SYS_ILLEGAL_INSTRUCTION,
+            format!("invalid actor code: {}", err),
+        ),
+        None => Abort::Fatal(e),
+    }
+}
+
+/// An actor's `invoke` export, typed according to the actor's [`AbiVersion`].
+enum Invoke {
+    V1(TypedFunc<(u32,), u32>),
+    V2(TypedFunc<(u32,), u64>),
+}
+
+impl Invoke {
+    /// Invoke the actor with the given parameters block, returning the exit code in the high 32
+    /// bits and the ID of the return block in the low 32 bits, whatever the ABI version.
+    fn call(&self, store: impl AsContextMut, params_id: BlockId) -> std::result::Result<u64, Trap> {
+        match self {
+            Invoke::V1(f) => f.call(store, (params_id,)).map(u64::from),
+            Invoke::V2(f) => f.call(store, (params_id,)),
+        }
+    }
+}
+
+/// Split the value returned by [`Invoke::call`] into an exit code and a return block ID.
+fn split_return(ret: u64) -> (u32, BlockId) {
+    ((ret >> 32) as u32, ret as u32)
+}
+
+/// The trace event recording the result of a call.
+fn return_event(result: &Result<InvocationResult>) -> ExecutionEvent {
+    match result {
+        Ok(InvocationResult {
+            exit_code,
+            return_data,
+        }) if exit_code.is_success() => ExecutionEvent::CallReturn(
+            return_data
+                .as_ref()
+                .map(|blk| RawBytes::from(blk.data().to_vec()))
+                .unwrap_or_default(),
+        ),
+        Ok(InvocationResult { exit_code, .. }) => ExecutionEvent::CallAbort(*exit_code),
+
+        Err(ExecutionError::OutOfGas) => {
+            ExecutionEvent::CallError(SyscallError::new(ErrorNumber::Forbidden, "out of gas"))
+        }
+        Err(ExecutionError::Fatal(_)) => {
+            ExecutionEvent::CallError(SyscallError::new(ErrorNumber::Forbidden, "fatal"))
+        }
+        Err(ExecutionError::Syscall(s)) => ExecutionEvent::CallError(s.clone()),
     }
 }
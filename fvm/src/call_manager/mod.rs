@@ -3,7 +3,7 @@ use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ExitCode;
 use fvm_shared::{ActorID, MethodNum};
 
-use crate::gas::{GasCharge, GasTracker, PriceList};
+use crate::gas::{GasCharge, GasLimit, GasStats, GasTimer, GasTracker, PriceList};
 use crate::kernel::{self, Result};
 use crate::machine::{Machine, MachineContext};
 use crate::state_tree::StateTree;
@@ -44,7 +44,7 @@ pub trait CallManager: 'static {
     /// Construct a new call manager.
     fn new(
         machine: Self::Machine,
-        gas_limit: i64,
+        gas_limit: GasLimit,
         origin: (ActorID, Address),
         nonce: u64,
         gas_premium: TokenAmount,
@@ -68,7 +68,9 @@ pub trait CallManager: 'static {
     ) -> Result<InvocationResult>;
 
     /// Finishes execution, returning the gas used, machine, and exec trace if requested.
-    fn finish(self) -> (FinishRet, Self::Machine);
+    ///
+    /// Fails with a fatal error if the machine was lost to a panic during execution.
+    fn finish(self) -> Result<(FinishRet, Self::Machine)>;
 
     /// Returns a reference to the machine.
     fn machine(&self) -> &Self::Machine;
@@ -89,12 +91,28 @@ pub trait CallManager: 'static {
     /// Gets and increment the call-stack actor creation index.
     fn next_actor_idx(&mut self) -> u64;
 
+    /// Counts the creation of the actor `id` by the current message, recording it in the execution
+    /// trace if tracing. Fails with `LimitExceeded` if the message has already created
+    /// [`NetworkConfig::max_actors_created`](crate::machine::NetworkConfig::max_actors_created)
+    /// actors, in which case the actor must not be created (or its creation must be reverted).
+    fn count_actor_creation(&mut self, id: ActorID) -> Result<()>;
+
+    /// Gets the number of actors created by the current message so far, including those whose
+    /// creation was later reverted.
+    fn actors_created(&self) -> u64;
+
+    /// Records that the actor `id` self-destructed during its current invocation. It's only
+    /// deleted once that invocation returns successfully (see
+    /// [`Kernel::commit_root`](crate::kernel::Kernel::commit_root)); until then, sends to it,
+    /// including re-entrant ones, fail with `NotFound` as if it had already been deleted.
+    fn mark_pending_deletion(&mut self, id: ActorID);
+
     /// Gets the total invocations done on this call stack.
     fn invocation_count(&self) -> u64;
 
     /// Returns the current price list.
     fn price_list(&self) -> &PriceList {
-        self.machine().context().price_list
+        &self.machine().context().price_list
     }
 
     /// Returns the machine context.
@@ -122,35 +140,58 @@ pub trait CallManager: 'static {
         self.machine_mut().state_tree_mut()
     }
 
-    /// Charge gas.
-    fn charge_gas(&mut self, charge: GasCharge) -> Result<()> {
-        self.gas_tracker_mut().apply_charge(charge)?;
-        Ok(())
+    /// Charge gas, returning a timer for the charged operation (see [`GasTracker::apply_charge`]).
+    fn charge_gas(&mut self, charge: GasCharge) -> Result<GasTimer> {
+        self.gas_tracker_mut().apply_charge(charge)
+    }
+
+    /// Runs `f` against the state tree, then charges for the actor lookups, updates, and address
+    /// resolutions it made (see [`StateTree::begin_metering`]). This is for state-tree accesses
+    /// triggered by actors; the machine's own accesses are free.
+    fn with_metered_state_tree<T>(
+        &mut self,
+        f: impl FnOnce(&mut StateTree<<Self::Machine as Machine>::Blockstore>) -> Result<T>,
+    ) -> Result<T> {
+        self.state_tree().begin_metering();
+        let res = f(self.state_tree_mut());
+        for access in self.state_tree().end_metering() {
+            self.charge_gas(self.price_list().on_state_access(access))?;
+        }
+        res
     }
 }
 
-/// The result of a method invocation.
+/// The result of a method invocation that ran to completion, whether the actor returned or aborted.
+/// Errors that abort the caller as well (running out of gas, fatal errors, etc.) are reported as
+/// errors instead.
 #[derive(Clone, Debug)]
-pub enum InvocationResult {
-    /// Indicates that the actor successfully returned. The value may be empty.
-    Return(Option<kernel::Block>),
-    /// Indicates that the actor aborted with the given exit code.
-    Failure(ExitCode),
+pub struct InvocationResult {
+    /// The exit code: [`ExitCode::OK`] if the actor returned, or the code it aborted with.
+    pub exit_code: ExitCode,
+    /// The returned block, if any. Always `None` if the actor aborted.
+    pub return_data: Option<kernel::Block>,
 }
 
 impl Default for InvocationResult {
     fn default() -> Self {
-        Self::Return(Default::default())
+        Self::ok(None)
     }
 }
 
 impl InvocationResult {
-    /// Get the exit code for the invocation result. [`ExitCode::Ok`] on success, or the exit code
-    /// from the [`Failure`](InvocationResult::Failure) variant otherwise.
-    pub fn exit_code(&self) -> ExitCode {
-        match self {
-            Self::Return(_) => ExitCode::OK,
-            Self::Failure(e) => *e,
+    /// The result of an actor successfully returning the given (possibly empty) value.
+    pub fn ok(return_data: Option<kernel::Block>) -> Self {
+        Self {
+            exit_code: ExitCode::OK,
+            return_data,
+        }
+    }
+
+    /// The result of an actor aborting with the given exit code.
+    pub fn failure(exit_code: ExitCode) -> Self {
+        Self {
+            exit_code,
+            return_data: None,
         }
     }
 }
@@ -160,4 +201,8 @@ pub struct FinishRet {
     pub gas_used: i64,
     pub backtrace: Backtrace,
     pub exec_trace: ExecutionTrace,
+    /// Per-charge gas statistics, if gas calibration is enabled.
+    pub gas_stats: Option<GasStats>,
+    /// The number of actors created by the message. See [`CallManager::actors_created`].
+    pub actors_created: u64,
 }
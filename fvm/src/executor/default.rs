@@ -1,22 +1,30 @@
-use std::ops::{Deref, DerefMut};
 use std::result::Result as StdResult;
+use std::time::Instant;
 
 use anyhow::{anyhow, Result};
 use cid::Cid;
-use fvm_ipld_encoding::{RawBytes, DAG_CBOR};
+use fvm_ipld_encoding::{to_vec, RawBytes, DAG_CBOR};
 use fvm_shared::address::Address;
+use fvm_shared::builtin::singletons::{BURNT_FUNDS_ACTOR_ADDR, REWARD_ACTOR_ADDR, SYSTEM_ACTOR_ID};
+use fvm_shared::crypto::signature::SignatureType;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::{ErrorNumber, ExitCode};
-use fvm_shared::message::Message;
+use fvm_shared::message::{Message, SignedMessage, MAX_MESSAGE_PARAMS_LEN};
 use fvm_shared::receipt::Receipt;
-use fvm_shared::ActorID;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, MethodNum, BLOCK_GAS_LIMIT, TOTAL_FILECOIN};
 use num_traits::Zero;
 
-use super::{ApplyFailure, ApplyKind, ApplyRet, Executor};
+use super::invariants::InvariantChecker;
+use super::{
+    ApplyFailure, ApplyKind, ApplyRet, Executor, ExecutorBlockstore, SenderPolicy, StateReader,
+};
 use crate::call_manager::{backtrace, CallManager, InvocationResult};
-use crate::gas::{Gas, GasCharge, GasOutputs};
-use crate::kernel::{Block, ClassifyResult, Context as _, ExecutionError, Kernel};
-use crate::machine::{Machine, BURNT_FUNDS_ACTOR_ADDR, REWARD_ACTOR_ADDR};
+use crate::externs::ExternError;
+use crate::gas::{Gas, GasCharge, GasLimit, GasOutputs};
+use crate::kernel::{Block, Context as _, ExecutionError, Kernel};
+use crate::machine::{FlushStats, Machine};
+use crate::state_tree::ActorState;
 
 /// The default [`Executor`].
 ///
@@ -25,50 +33,169 @@ use crate::machine::{Machine, BURNT_FUNDS_ACTOR_ADDR, REWARD_ACTOR_ADDR};
 /// Message execution might run out of stack and crash (the entire process) if it doesn't have at
 /// least 64MiB of stacks space. If you can't guarantee 64MiB of stack space, wrap this executor in
 /// a [`ThreadedExecutor`][super::ThreadedExecutor].
-// If the inner value is `None` it means the machine got poisoned and is unusable.
-#[repr(transparent)]
-pub struct DefaultExecutor<K: Kernel>(Option<<K::CallManager as CallManager>::Machine>);
+pub struct DefaultExecutor<K: Kernel> {
+    // If the inner value is `None` it means the machine got poisoned and is unusable.
+    machine: Option<<K::CallManager as CallManager>::Machine>,
+    // Set before the first message when the machine context enables invariant checks.
+    invariants: Option<InvariantChecker>,
+}
 
-impl<K: Kernel> Deref for DefaultExecutor<K> {
-    type Target = <K::CallManager as CallManager>::Machine;
+impl<K> Executor for DefaultExecutor<K>
+where
+    K: Kernel,
+{
+    type Kernel = K;
 
-    fn deref(&self) -> &Self::Target {
-        &*self.0.as_ref().expect("machine poisoned")
+    /// This is the entrypoint to execute a message.
+    fn execute_message(
+        &mut self,
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+    ) -> anyhow::Result<ApplyRet> {
+        self.execute(msg, apply_kind, raw_length, false)
+    }
+
+    /// Flush the state-tree to the underlying blockstore.
+    fn flush(&mut self) -> anyhow::Result<Cid> {
+        self.flush_with_stats().map(|stats| stats.root)
     }
-}
 
-impl<K: Kernel> DerefMut for DefaultExecutor<K> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut *self.0.as_mut().expect("machine poisoned")
+    fn state_reader(&self) -> anyhow::Result<StateReader<'_, ExecutorBlockstore<Self>>> {
+        Ok(StateReader::new(self.machine()?.state_tree()))
     }
 }
 
-impl<K> Executor for DefaultExecutor<K>
+/// How the executor establishes that the sender of an explicit message sent it.
+enum SenderResolution {
+    /// The sender is an account, whose signature on the message was already verified.
+    Account,
+    /// The sender must accept a call to the given method with the message.
+    Authenticate(MethodNum),
+    /// The sender can't send messages, for the given reason.
+    Invalid(&'static str),
+}
+
+impl<K> DefaultExecutor<K>
 where
     K: Kernel,
 {
-    type Kernel = K;
+    /// Create a new [`DefaultExecutor`] for executing messages on the [`Machine`].
+    pub fn new(m: <K::CallManager as CallManager>::Machine) -> Self {
+        Self {
+            machine: Some(m),
+            invariants: None,
+        }
+    }
 
-    /// This is the entrypoint to execute a message.
-    fn execute_message(
+    /// Returns the machine, or an error if it was lost to a panic.
+    pub fn machine(&self) -> anyhow::Result<&<K::CallManager as CallManager>::Machine> {
+        self.machine.as_ref().ok_or_else(poisoned)
+    }
+
+    /// Returns the machine mutably, or an error if it was lost to a panic.
+    pub fn machine_mut(&mut self) -> anyhow::Result<&mut <K::CallManager as CallManager>::Machine> {
+        self.machine.as_mut().ok_or_else(poisoned)
+    }
+
+    /// Executes an explicit signed message, like [`Executor::execute_message`], charging for its
+    /// size on chain (see [`SignedMessage::chain_length`]).
+    ///
+    /// BLS and secp256k1 signatures are verified here (see [`SignedMessage::verify`]), and an
+    /// invalid signature is an error. Delegated signatures aren't verified: the sender actor
+    /// authenticates the message instead (see [`SenderPolicy::Authenticate`]), so messages with
+    /// delegated signatures are invalid when sent by account actors, or when the machine doesn't
+    /// authenticate senders.
+    ///
+    /// [`SenderPolicy::Authenticate`]: super::SenderPolicy::Authenticate
+    pub fn execute_signed_message(&mut self, smsg: SignedMessage) -> anyhow::Result<ApplyRet> {
+        let raw_length = smsg.chain_length()?;
+        let delegated = smsg.signature.signature_type() == SignatureType::Delegated;
+        if !delegated {
+            smsg.verify()
+                .map_err(|e| anyhow!("invalid message signature: {}", e))?;
+        }
+        self.execute(smsg.message, ApplyKind::Explicit, raw_length, delegated)
+    }
+
+    /// Executes the message. If `delegated` is true, the message carries a delegated signature the
+    /// sender actor must authenticate.
+    fn execute(
+        &mut self,
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+        delegated: bool,
+    ) -> anyhow::Result<ApplyRet> {
+        // Record the total balance before the first message.
+        if self.invariants.is_none() && self.machine()?.context().check_invariants {
+            self.invariants = Some(InvariantChecker::new(self.machine()?.state_tree())?);
+        }
+        // Record the actors the message touches, from preflight through the gas refund.
+        let machine = self.machine_mut()?;
+        if machine.context().tracing {
+            machine.state_tree_mut().begin_access_tracking();
+        }
+        let mut ret = self.apply_message(msg, apply_kind, raw_length, delegated)?;
+        if let Some(accesses) = self.machine_mut()?.state_tree_mut().end_access_tracking() {
+            ret.actors_read = accesses.read;
+            ret.actors_modified = accesses.modified;
+        }
+        if let Some(checker) = &mut self.invariants {
+            let machine = self.machine.as_ref().ok_or_else(poisoned)?;
+            checker.check_message(machine.state_tree())?;
+        }
+        Ok(ret)
+    }
+
+    /// Flushes the state-tree to the underlying blockstore like [`Executor::flush`], returning
+    /// statistics about the flush along with the new root.
+    pub fn flush_with_stats(&mut self) -> anyhow::Result<FlushStats> {
+        if let Some(checker) = &self.invariants {
+            checker.check_full(self.machine()?.state_tree())?;
+        }
+        let stats = self.machine_mut()?.flush()?;
+        Ok(stats)
+    }
+
+    /// Consume consumes the executor and returns the Machine. If the Machine had
+    /// been poisoned during execution, the Option will be None.
+    pub fn into_machine(self) -> Option<<K::CallManager as CallManager>::Machine> {
+        self.machine
+    }
+
+    fn apply_message(
         &mut self,
         msg: Message,
         apply_kind: ApplyKind,
         raw_length: usize,
+        delegated: bool,
     ) -> anyhow::Result<ApplyRet> {
+        let start = Instant::now();
+
         // Validate if the message was correct, charge for it, and extract some preliminary data.
-        let (sender_id, gas_cost, inclusion_cost) =
-            match self.preflight_message(&msg, apply_kind, raw_length)? {
+        let (sender_id, gas_limit, gas_cost, inclusion_cost, auth_cost) =
+            match self.preflight_message(&msg, apply_kind, raw_length, delegated)? {
                 Ok(res) => res,
-                Err(apply_ret) => return Ok(apply_ret),
+                Err(mut apply_ret) => {
+                    // Explicit messages count toward the block gas limit even if they fail
+                    // preflight: the block still includes them. Their gas limit may be out of
+                    // range, so only count what a block can hold.
+                    if apply_kind == ApplyKind::Explicit {
+                        apply_ret.gas_burned_for_block_accounting =
+                            msg.gas_limit.clamp(0, BLOCK_GAS_LIMIT);
+                    }
+                    self.record_message_applied(&apply_ret.msg_receipt, start)?;
+                    return Ok(apply_ret);
+                }
             };
 
         // Apply the message.
-        let (res, gas_used, mut backtrace, exec_trace) = self.map_machine(|machine| {
+        let applied = self.map_machine(|machine| {
             // We're processing a chain message, so the sender is the origin of the call stack.
             let mut cm = K::CallManager::new(
                 machine,
-                msg.gas_limit,
+                gas_limit,
                 (sender_id, msg.from),
                 msg.sequence,
                 msg.gas_premium.clone(),
@@ -76,7 +203,13 @@ where
             // This error is fatal because it should have already been accounted for inside
             // preflight_message.
             if let Err(e) = cm.charge_gas(inclusion_cost) {
-                return (Err(e), cm.finish().1);
+                return (Err(e), cm.finish().ok().map(|(_, machine)| machine));
+            }
+            // Likewise, the sender's authentication of the message fit in its gas limit.
+            if let Some(auth_cost) = auth_cost {
+                if let Err(e) = cm.charge_gas(auth_cost) {
+                    return (Err(e), cm.finish().ok().map(|(_, machine)| machine));
+                }
             }
 
             let params = if msg.params.is_empty() {
@@ -90,27 +223,55 @@ where
                 let ret = cm.send::<K>(sender_id, msg.to, msg.method_num, params, &msg.value)?;
 
                 // Charge for including the result (before we end the transaction).
-                if let InvocationResult::Return(value) = &ret {
-                    cm.charge_gas(cm.context().price_list.on_chain_return_value(
-                        value.as_ref().map(|v| v.size() as usize).unwrap_or(0),
-                    ))?;
+                if ret.exit_code.is_success() {
+                    let size = ret.return_data.as_ref().map(|v| v.size() as usize);
+                    cm.charge_gas(
+                        cm.context()
+                            .price_list
+                            .on_chain_return_value(size.unwrap_or(0)),
+                    )?;
                 }
 
                 Ok(ret)
             });
-            let (res, machine) = cm.finish();
+            let (res, machine) = match cm.finish() {
+                Ok(finished) => finished,
+                // A panic poisoned the call manager, and the machine was lost with it. There's no
+                // machine left to charge the message on, so fail it.
+                Err(e) => return (Err(e), None),
+            };
+            if res.gas_used < 0 || res.gas_used > msg.gas_limit {
+                return (
+                    Err(ExecutionError::Fatal(anyhow!(
+                        "gas used {} out of range (limit {})",
+                        res.gas_used,
+                        msg.gas_limit
+                    ))),
+                    Some(machine),
+                );
+            }
             (
-                Ok((result, res.gas_used, res.backtrace, res.exec_trace)),
-                machine,
+                Ok((
+                    result,
+                    res.gas_used,
+                    res.backtrace,
+                    res.exec_trace,
+                    res.actors_created,
+                )),
+                Some(machine),
             )
         })?;
+        let (res, gas_used, mut backtrace, exec_trace, created) = applied?;
 
         // Extract the exit code and build the result of the message application.
         let receipt = match res {
-            Ok(InvocationResult::Return(return_value)) => {
+            Ok(InvocationResult {
+                exit_code: ExitCode::OK,
+                return_data,
+            }) => {
                 // Convert back into a top-level return "value". We throw away the codec here,
                 // unfortunately.
-                let return_data = return_value
+                let return_data = return_data
                     .map(|blk| RawBytes::from(blk.data().to_vec()))
                     .unwrap_or_default();
 
@@ -121,16 +282,11 @@ where
                     gas_used,
                 }
             }
-            Ok(InvocationResult::Failure(exit_code)) => {
-                if exit_code.is_success() {
-                    return Err(anyhow!("actor failed with status OK"));
-                }
-                Receipt {
-                    exit_code,
-                    return_data: Default::default(),
-                    gas_used,
-                }
-            }
+            Ok(InvocationResult { exit_code, .. }) => Receipt {
+                exit_code,
+                return_data: Default::default(),
+                gas_used,
+            },
             Err(ExecutionError::OutOfGas) => Receipt {
                 exit_code: ExitCode::SYS_OUT_OF_GAS,
                 return_data: Default::default(),
@@ -153,6 +309,18 @@ where
                     gas_used,
                 }
             }
+            Err(ExecutionError::Fatal(err)) if err.is::<ExternError>() => {
+                // The node failed to answer an extern. Other nodes may not hit the same failure, so
+                // we can't produce a receipt: the caller must abort validating this block.
+                return Err(err.context(format!(
+                    "extern failed while applying message [from={}, to={}, seq={}, m={}, h={}]",
+                    msg.from,
+                    msg.to,
+                    msg.sequence,
+                    msg.method_num,
+                    self.machine()?.context().network_context.epoch,
+                )));
+            }
             Err(ExecutionError::Fatal(err)) => {
                 // We produce a receipt with SYS_ASSERTION_FAILED exit code, and
                 // we consume the full gas amount so that, in case of a network-
@@ -168,7 +336,7 @@ where
                     msg.to,
                     msg.sequence,
                     msg.method_num,
-                    self.context().network_context.epoch,
+                    self.machine()?.context().network_context.epoch,
                 ));
                 backtrace.set_cause(backtrace::Cause::from_fatal(err));
                 Receipt {
@@ -179,6 +347,8 @@ where
             }
         };
 
+        self.record_message_applied(&receipt, start)?;
+
         let failure_info = if backtrace.is_empty() || receipt.exit_code.is_success() {
             None
         } else {
@@ -190,6 +360,7 @@ where
                 .finish_message(msg, receipt, failure_info, gas_cost)
                 .map(|mut apply_ret| {
                     apply_ret.exec_trace = exec_trace;
+                    apply_ret.actors_created = created;
                     apply_ret
                 }),
             ApplyKind::Implicit => Ok(ApplyRet {
@@ -201,32 +372,23 @@ where
                 refund: TokenAmount::zero(),
                 gas_refund: 0,
                 gas_burned: 0,
+                gas_burned_for_block_accounting: 0,
                 failure_info,
                 exec_trace,
+                actors_read: vec![],
+                actors_modified: vec![],
+                actors_created: created,
             }),
         }
     }
 
-    /// Flush the state-tree to the underlying blockstore.
-    fn flush(&mut self) -> anyhow::Result<Cid> {
-        let k = (&mut **self).flush()?;
-        Ok(k)
-    }
-}
-
-impl<K> DefaultExecutor<K>
-where
-    K: Kernel,
-{
-    /// Create a new [`DefaultExecutor`] for executing messages on the [`Machine`].
-    pub fn new(m: <K::CallManager as CallManager>::Machine) -> Self {
-        Self(Some(m))
-    }
-
-    /// Consume consumes the executor and returns the Machine. If the Machine had
-    /// been poisoned during execution, the Option will be None.
-    pub fn into_machine(self) -> Option<<K::CallManager as CallManager>::Machine> {
-        self.0
+    fn record_message_applied(&self, receipt: &Receipt, start: Instant) -> anyhow::Result<()> {
+        self.machine()?.context().metrics.record_message_applied(
+            receipt.gas_used,
+            start.elapsed(),
+            receipt.exit_code,
+        );
+        Ok(())
     }
 
     // TODO: The return type here is very strange because we have three cases:
@@ -234,20 +396,26 @@ where
     //  2. Short-circuit (return ApplyRet).
     //  3. Fail (return an error).
     //  We could use custom types, but that would be even more annoying.
+    #[allow(clippy::type_complexity)]
     fn preflight_message(
         &mut self,
         msg: &Message,
         apply_kind: ApplyKind,
         raw_length: usize,
-    ) -> Result<StdResult<(ActorID, TokenAmount, GasCharge), ApplyRet>> {
-        msg.check().or_fatal()?;
-
+        delegated: bool,
+    ) -> Result<StdResult<(ActorID, GasLimit, TokenAmount, GasCharge, Option<GasCharge>), ApplyRet>>
+    {
         // TODO We don't like having price lists _inside_ the FVM, but passing
         //  these across the boundary is also a no-go.
-        let pl = &self.context().price_list;
+        let context = self.machine()?.context();
+        let pl = &context.price_list;
 
-        let (inclusion_cost, miner_penalty_amount) = match apply_kind {
+        let (gas_limit, inclusion_cost, miner_penalty_amount) = match apply_kind {
             ApplyKind::Implicit => (
+                // Implicit messages may exceed the block gas limit.
+                GasLimit::implicit(msg.gas_limit).ok_or_else(|| {
+                    anyhow!("implicit message gas limit {} is negative", msg.gas_limit)
+                })?,
                 GasCharge::new("none", Gas::zero(), Gas::zero()),
                 Default::default(),
             ),
@@ -255,22 +423,81 @@ where
                 let inclusion_cost = pl.on_chain_message(raw_length);
                 let inclusion_total = inclusion_cost.total().round_up();
 
+                // Messages with gas limits outside [0, BLOCK_GAS_LIMIT] can't be included in a
+                // block. Like Lotus, we fail them as out of gas, penalizing the miner for the
+                // inclusion cost.
+                let gas_limit = match GasLimit::explicit(msg.gas_limit) {
+                    Some(gas_limit) => gas_limit,
+                    None => {
+                        return Ok(Err(ApplyRet::prevalidation_fail(
+                            ExitCode::SYS_OUT_OF_GAS,
+                            format!(
+                                "gas limit {} out of range [0, {}]",
+                                msg.gas_limit, BLOCK_GAS_LIMIT
+                            ),
+                            &context.network_context.base_fee * inclusion_total,
+                        )));
+                    }
+                };
+
                 // Verify the cost of the message is not over the message gas limit.
                 if inclusion_total > msg.gas_limit {
                     return Ok(Err(ApplyRet::prevalidation_fail(
                         ExitCode::SYS_OUT_OF_GAS,
                         format!("Out of gas ({} > {})", inclusion_total, msg.gas_limit),
-                        &self.context().network_context.base_fee * inclusion_total,
+                        &context.network_context.base_fee * inclusion_total,
                     )));
                 }
 
-                let miner_penalty_amount = &self.context().network_context.base_fee * msg.gas_limit;
-                (inclusion_cost, miner_penalty_amount)
+                let miner_penalty_amount = &context.network_context.base_fee * msg.gas_limit;
+                (gas_limit, inclusion_cost, miner_penalty_amount)
             }
         };
 
+        // From nv17, messages may neither transfer negative values, nor more than the total supply.
+        // Such messages can't be included in a block, so we fail them before touching the sender.
+        if context.network_version >= NetworkVersion::V17
+            && (msg.value.is_negative() || msg.value > *TOTAL_FILECOIN)
+        {
+            if apply_kind == ApplyKind::Implicit {
+                return Err(anyhow!("implicit message value {} out of range", msg.value));
+            }
+            return Ok(Err(ApplyRet::prevalidation_fail(
+                ExitCode::SYS_ASSERTION_FAILED,
+                format!(
+                    "message value {} out of range [0, {}]",
+                    msg.value, *TOTAL_FILECOIN
+                ),
+                miner_penalty_amount,
+            )));
+        }
+
+        // From nv17, explicit messages must also fit the protocol's bounds on message fields.
+        // These are distinct from (and tighter than) the machine's limits on sends made by actors.
+        if apply_kind == ApplyKind::Explicit && context.network_version >= NetworkVersion::V17 {
+            if msg.version != 0 {
+                return Ok(Err(ApplyRet::prevalidation_fail(
+                    ExitCode::SYS_ASSERTION_FAILED,
+                    format!("unsupported message version {}", msg.version),
+                    miner_penalty_amount,
+                )));
+            }
+            if msg.params.len() > MAX_MESSAGE_PARAMS_LEN {
+                return Ok(Err(ApplyRet::prevalidation_fail(
+                    ExitCode::SYS_ASSERTION_FAILED,
+                    format!(
+                        "message params of {} bytes exceed the maximum of {} bytes",
+                        msg.params.len(),
+                        MAX_MESSAGE_PARAMS_LEN
+                    ),
+                    miner_penalty_amount,
+                )));
+            }
+        }
+
         // Load sender actor state.
         let sender_id = match self
+            .machine()?
             .state_tree()
             .lookup_id(&msg.from)
             .with_context(|| format!("failed to lookup actor {}", &msg.from))?
@@ -286,10 +513,17 @@ where
         };
 
         if apply_kind == ApplyKind::Implicit {
-            return Ok(Ok((sender_id, TokenAmount::zero(), inclusion_cost)));
+            return Ok(Ok((
+                sender_id,
+                gas_limit,
+                TokenAmount::zero(),
+                inclusion_cost,
+                None,
+            )));
         }
 
-        let sender = match self
+        let mut sender = match self
+            .machine()?
             .state_tree()
             .get_actor(&Address::new_id(sender_id))
             .with_context(|| format!("failed to lookup actor {}", &msg.from))?
@@ -304,15 +538,46 @@ where
             }
         };
 
-        // If sender is not an account actor, the message is invalid.
-        let sender_is_account = self.builtin_actors().is_account_actor(&sender.code);
+        let gas_cost: TokenAmount = msg.gas_fee_cap.clone() * msg.gas_limit;
 
-        if !sender_is_account {
-            return Ok(Err(ApplyRet::prevalidation_fail(
-                ExitCode::SYS_SENDER_INVALID,
-                "Send not from account actor",
-                miner_penalty_amount,
-            )));
+        // Make sure the sender sent the message.
+        let auth_cost = match self.resolve_sender(&sender, delegated)? {
+            SenderResolution::Account => None,
+            SenderResolution::Authenticate(method) => {
+                match self.authenticate_message(
+                    msg,
+                    sender_id,
+                    method,
+                    gas_limit,
+                    &gas_cost,
+                    &inclusion_cost,
+                )? {
+                    Ok(auth_cost) => {
+                        // The sender may have changed its own state while authenticating.
+                        sender = self
+                            .machine()?
+                            .state_tree()
+                            .get_actor_id(sender_id)
+                            .with_context(|| format!("failed to lookup actor {}", &msg.from))?
+                            .ok_or_else(|| anyhow!("sender {} disappeared", &msg.from))?;
+                        Some(auth_cost)
+                    }
+                    Err((code, reason)) => {
+                        return Ok(Err(ApplyRet::prevalidation_fail(
+                            code,
+                            reason,
+                            miner_penalty_amount,
+                        )));
+                    }
+                }
+            }
+            SenderResolution::Invalid(reason) => {
+                return Ok(Err(ApplyRet::prevalidation_fail(
+                    ExitCode::SYS_SENDER_INVALID,
+                    reason,
+                    miner_penalty_amount,
+                )));
+            }
         };
 
         // Check sequence is correct
@@ -328,7 +593,6 @@ where
         };
 
         // Ensure from actor has enough balance to cover the gas cost of the message.
-        let gas_cost: TokenAmount = msg.gas_fee_cap.clone() * msg.gas_limit;
         if sender.balance < gas_cost {
             return Ok(Err(ApplyRet::prevalidation_fail(
                 ExitCode::SYS_SENDER_STATE_INVALID,
@@ -341,13 +605,124 @@ where
         }
 
         // Deduct message inclusion gas cost and increment sequence.
-        self.state_tree_mut().mutate_actor_id(sender_id, |act| {
-            act.deduct_funds(&gas_cost)?;
-            act.sequence += 1;
-            Ok(())
-        })?;
+        self.machine_mut()?
+            .state_tree_mut()
+            .mutate_actor_id(sender_id, |act| {
+                act.deduct_funds(&gas_cost)?;
+                act.sequence += 1;
+                Ok(())
+            })?;
+
+        Ok(Ok((
+            sender_id,
+            gas_limit,
+            gas_cost,
+            inclusion_cost,
+            auth_cost,
+        )))
+    }
+
+    /// Determines how to establish that the sender of an explicit message sent it, according to
+    /// the network's [`SenderPolicy`].
+    fn resolve_sender(
+        &self,
+        sender: &ActorState,
+        delegated: bool,
+    ) -> anyhow::Result<SenderResolution> {
+        let machine = self.machine()?;
+        let actors = machine.builtin_actors();
+        if actors.is_account_actor(&sender.code) {
+            if delegated {
+                return Ok(SenderResolution::Invalid(
+                    "Delegated signature from account actor",
+                ));
+            }
+            return Ok(SenderResolution::Account);
+        }
+        if actors.is_placeholder_actor(&sender.code) {
+            return Ok(SenderResolution::Invalid("Send from placeholder actor"));
+        }
+        Ok(match machine.context().sender_policy {
+            SenderPolicy::AccountsOnly => SenderResolution::Invalid("Send not from account actor"),
+            SenderPolicy::Authenticate { method } => SenderResolution::Authenticate(method),
+        })
+    }
 
-        Ok(Ok((sender_id, gas_cost, inclusion_cost)))
+    /// Calls the sender's authentication `method` with the message, in a call stack of its own
+    /// bounded by the message's gas limit, returning the gas charge for the call. The message is
+    /// invalid, and the call's state changes are reverted, unless the sender accepts the message
+    /// and is then still able to send it (with the right sequence and enough funds for gas). If it
+    /// isn't, returns the exit code and reason to fail the message with.
+    fn authenticate_message(
+        &mut self,
+        msg: &Message,
+        sender_id: ActorID,
+        method: MethodNum,
+        gas_limit: GasLimit,
+        gas_cost: &TokenAmount,
+        inclusion_cost: &GasCharge,
+    ) -> Result<StdResult<GasCharge, (ExitCode, &'static str)>> {
+        let params = Block::new(DAG_CBOR, to_vec(msg)?);
+        let (res, auth_gas) = self.map_machine(|machine| {
+            let mut cm = K::CallManager::new(
+                machine,
+                gas_limit,
+                (sender_id, msg.from),
+                msg.sequence,
+                msg.gas_premium.clone(),
+            );
+            let res = match cm.charge_gas(inclusion_cost.clone()) {
+                Ok(_) => cm.with_transaction(|cm| {
+                    let ret = cm.send::<K>(
+                        SYSTEM_ACTOR_ID,
+                        Address::new_id(sender_id),
+                        method,
+                        Some(params),
+                        &TokenAmount::zero(),
+                    )?;
+                    if !ret.exit_code.is_success() {
+                        return Ok(ret);
+                    }
+                    // Check the sender's state within the transaction, so that its changes are
+                    // reverted if the message turns out to be invalid.
+                    Ok(match cm.state_tree().get_actor_id(sender_id)? {
+                        None => InvocationResult::failure(ExitCode::SYS_SENDER_INVALID),
+                        Some(sender)
+                            if sender.sequence != msg.sequence || &sender.balance < gas_cost =>
+                        {
+                            InvocationResult::failure(ExitCode::SYS_SENDER_STATE_INVALID)
+                        }
+                        Some(_) => ret,
+                    })
+                }),
+                Err(e) => Err(e),
+            };
+            // A fatal error may come from a panic that poisoned the call manager.
+            let auth_gas = match &res {
+                Err(ExecutionError::Fatal(_)) => Gas::zero(),
+                _ => cm.gas_tracker().gas_used() - inclusion_cost.total(),
+            };
+            (
+                (res, auth_gas),
+                cm.finish().ok().map(|(_, machine)| machine),
+            )
+        })?;
+        match res {
+            Ok(ret) if ret.exit_code.is_success() => Ok(Ok(GasCharge::new(
+                "OnAuthenticateMessage",
+                auth_gas,
+                Gas::zero(),
+            ))),
+            Ok(ret) if ret.exit_code == ExitCode::SYS_SENDER_STATE_INVALID => Ok(Err((
+                ret.exit_code,
+                "Sender can't cover the message after authenticating it",
+            ))),
+            Ok(_) | Err(ExecutionError::OutOfGas) | Err(ExecutionError::Syscall(_)) => Ok(Err((
+                ExitCode::SYS_SENDER_INVALID,
+                "Sender failed to authenticate the message",
+            ))),
+            Err(ExecutionError::Fatal(e)) => Err(e),
+        }
     }
 
     fn finish_message(
@@ -369,7 +744,7 @@ where
         } = GasOutputs::compute(
             receipt.gas_used,
             msg.gas_limit,
-            &self.context().network_context.base_fee,
+            &self.machine()?.context().network_context.base_fee,
             &msg.gas_fee_cap,
             &msg.gas_premium,
         );
@@ -382,7 +757,8 @@ where
                 return Ok(());
             }
 
-            self.state_tree_mut()
+            self.machine_mut()?
+                .state_tree_mut()
                 .mutate_actor(addr, |act| {
                     act.deposit_funds(amt);
                     Ok(())
@@ -405,6 +781,7 @@ where
             return Err(anyhow!("Gas handling math is wrong"));
         }
         Ok(ApplyRet {
+            gas_burned_for_block_accounting: msg.gas_limit.clamp(0, BLOCK_GAS_LIMIT),
             msg_receipt: receipt,
             penalty: miner_penalty,
             miner_tip,
@@ -415,22 +792,28 @@ where
             gas_burned,
             failure_info,
             exec_trace: vec![],
+            actors_read: vec![],
+            actors_modified: vec![],
+            actors_created: 0,
         })
     }
 
-    fn map_machine<F, T>(&mut self, f: F) -> T
+    /// Moves the machine into `f` and back. If `f` doesn't return the machine (e.g., because it
+    /// was lost to a panic), the executor is left poisoned. Fails if it already is.
+    fn map_machine<F, T>(&mut self, f: F) -> anyhow::Result<T>
     where
         F: FnOnce(
             <K::CallManager as CallManager>::Machine,
-        ) -> (T, <K::CallManager as CallManager>::Machine),
+        ) -> (T, Option<<K::CallManager as CallManager>::Machine>),
     {
-        replace_with::replace_with_and_return(
-            &mut self.0,
-            || None,
-            |m| {
-                let (ret, machine) = f(m.unwrap());
-                (ret, Some(machine))
-            },
-        )
+        let machine = self.machine.take().ok_or_else(poisoned)?;
+        let (ret, machine) = f(machine);
+        self.machine = machine;
+        Ok(ret)
     }
 }
+
+/// The error returned once the executor's machine has been lost to a panic.
+fn poisoned() -> anyhow::Error {
+    anyhow!("machine poisoned")
+}
@@ -0,0 +1,252 @@
+//! Debug-only invariant checks run by the [`DefaultExecutor`][super::DefaultExecutor] when
+//! [`MachineContext::check_invariants`][crate::machine::MachineContext::check_invariants] is set.
+//!
+//! Burnt funds are held by the burnt funds actor, so the sum of all actor balances must be the same
+//! before and after every message.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::ActorID;
+use num_traits::Zero;
+
+use crate::state_tree::{ActorState, StateTree};
+
+/// Balance and sequence of an actor as of the last check (`None` if it didn't exist).
+type Observed = Option<(TokenAmount, u64)>;
+
+fn observe(actor: Option<&ActorState>) -> Observed {
+    actor.map(|a| (a.balance.clone(), a.sequence))
+}
+
+/// Tracks the total FIL balance across a sequence of messages.
+pub(crate) struct InvariantChecker {
+    /// The sum of all actor balances when the checker was created.
+    total: TokenAmount,
+    /// Every cached actor as of the last check. Actors missing from this map haven't changed since
+    /// the state tree was loaded.
+    seen: HashMap<ActorID, Observed>,
+}
+
+impl InvariantChecker {
+    /// Creates a checker, recording the current total balance with a full walk of the state tree.
+    pub fn new<B: Blockstore>(tree: &StateTree<B>) -> Result<Self> {
+        let total = total_balance(tree)?;
+        let mut seen = HashMap::new();
+        tree.for_each_cached(|id, actor| {
+            seen.insert(id, observe(actor));
+            Ok(())
+        })?;
+        Ok(Self { total, seen })
+    }
+
+    /// Checks the invariants after a message, only looking at the actors cached by the state tree.
+    pub fn check_message<B: Blockstore>(&mut self, tree: &StateTree<B>) -> Result<()> {
+        let mut delta = TokenAmount::zero();
+        let mut violations = Vec::new();
+        let mut updates = Vec::new();
+        tree.for_each_cached(|id, actor| {
+            let now = observe(actor);
+            let before = match self.seen.get(&id) {
+                Some(before) => before.clone(),
+                None => observe(tree.get_flushed_actor_id(id)?.as_ref()),
+            };
+            if now == before {
+                return Ok(());
+            }
+            if let Some((balance, _)) = &now {
+                if balance.is_negative() {
+                    violations.push(format!("actor {} has a negative balance: {}", id, balance));
+                }
+            }
+            if let (Some((_, seq_before)), Some((_, seq_now))) = (&before, &now) {
+                if seq_now < seq_before {
+                    violations.push(format!(
+                        "actor {} sequence decreased from {} to {}",
+                        id, seq_before, seq_now
+                    ));
+                }
+            }
+            if let Some((balance, _)) = &now {
+                delta += balance;
+            }
+            if let Some((balance, _)) = &before {
+                delta -= balance;
+            }
+            updates.push((id, now));
+            Ok(())
+        })?;
+        self.seen.extend(updates);
+
+        if !delta.is_zero() {
+            violations.push(format!("total balance changed by {}", delta));
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("invariants violated: {}", violations.join("; ")))
+        }
+    }
+
+    /// Checks the total balance with a full walk of the state tree. This is expensive, so it's
+    /// only done at the end of a block.
+    pub fn check_full<B: Blockstore>(&self, tree: &StateTree<B>) -> Result<()> {
+        let total = total_balance(tree)?;
+        if total != self.total {
+            return Err(anyhow!(
+                "invariants violated: total balance changed from {} to {}",
+                self.total,
+                total
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Sums the balances of all actors, including changes that haven't been flushed yet.
+fn total_balance<B: Blockstore>(tree: &StateTree<B>) -> Result<TokenAmount> {
+    let mut cached = HashMap::new();
+    tree.for_each_cached(|id, actor| {
+        cached.insert(id, actor.map(|a| a.balance.clone()));
+        Ok(())
+    })?;
+
+    let mut total = TokenAmount::zero();
+    tree.for_each(|addr, actor| {
+        let id = addr.id()?;
+        if !cached.contains_key(&id) {
+            total += &actor.balance;
+        }
+        Ok(())
+    })?;
+    for balance in cached.values().flatten() {
+        total += balance;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::Multihash;
+    use cid::Cid;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::DAG_CBOR;
+    use fvm_shared::state::StateTreeVersion;
+    use fvm_shared::IDENTITY_HASH;
+
+    use super::*;
+
+    fn empty_cid() -> Cid {
+        Cid::new_v1(DAG_CBOR, Multihash::wrap(IDENTITY_HASH, &[]).unwrap())
+    }
+
+    fn setup(store: &MemoryBlockstore) -> StateTree<&MemoryBlockstore> {
+        let mut tree = StateTree::new(store, StateTreeVersion::V4).unwrap();
+        for id in 100..103 {
+            tree.set_actor_id(
+                id,
+                ActorState::new(empty_cid(), empty_cid(), TokenAmount::from_atto(1000), 0),
+            )
+            .unwrap();
+        }
+        tree.flush().unwrap();
+        tree
+    }
+
+    /// A transfer that can be made to mint funds out of thin air, standing in for a buggy
+    /// transfer path.
+    fn transfer(tree: &mut StateTree<&MemoryBlockstore>, from: ActorID, to: ActorID, mint: bool) {
+        let amt = TokenAmount::from_atto(10);
+        if !mint {
+            tree.mutate_actor_id(from, |a| {
+                a.balance -= &amt;
+                a.sequence += 1;
+                Ok(())
+            })
+            .unwrap();
+        }
+        tree.mutate_actor_id(to, |a| {
+            a.balance += &amt;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn conserved() {
+        let store = MemoryBlockstore::default();
+        let mut tree = setup(&store);
+        let mut checker = InvariantChecker::new(&tree).unwrap();
+
+        transfer(&mut tree, 100, 101, false);
+        checker.check_message(&tree).unwrap();
+        transfer(&mut tree, 101, 102, false);
+        checker.check_message(&tree).unwrap();
+        checker.check_full(&tree).unwrap();
+
+        // Still holds across a flush.
+        tree.flush().unwrap();
+        transfer(&mut tree, 102, 100, false);
+        checker.check_message(&tree).unwrap();
+        checker.check_full(&tree).unwrap();
+    }
+
+    #[test]
+    fn minted_funds() {
+        let store = MemoryBlockstore::default();
+        let mut tree = setup(&store);
+        let mut checker = InvariantChecker::new(&tree).unwrap();
+
+        transfer(&mut tree, 100, 101, true);
+        let err = checker.check_message(&tree).unwrap_err();
+        assert!(err.to_string().contains("total balance changed"), "{}", err);
+        assert!(checker.check_full(&tree).is_err());
+    }
+
+    #[test]
+    fn deleted_funds() {
+        let store = MemoryBlockstore::default();
+        let mut tree = setup(&store);
+        let mut checker = InvariantChecker::new(&tree).unwrap();
+
+        tree.delete_actor_id(100).unwrap();
+        assert!(checker.check_message(&tree).is_err());
+        assert!(checker.check_full(&tree).is_err());
+    }
+
+    #[test]
+    fn negative_balance_and_sequence() {
+        let store = MemoryBlockstore::default();
+        let mut tree = setup(&store);
+        let mut checker = InvariantChecker::new(&tree).unwrap();
+
+        tree.mutate_actor_id(100, |a| {
+            a.balance -= TokenAmount::from_atto(2000);
+            Ok(())
+        })
+        .unwrap();
+        tree.mutate_actor_id(101, |a| {
+            a.balance += TokenAmount::from_atto(2000);
+            Ok(())
+        })
+        .unwrap();
+        let err = checker.check_message(&tree).unwrap_err();
+        assert!(err.to_string().contains("negative balance"), "{}", err);
+
+        tree.mutate_actor_id(102, |a| {
+            a.sequence = 5;
+            Ok(())
+        })
+        .unwrap();
+        checker.check_message(&tree).unwrap();
+        tree.mutate_actor_id(102, |a| {
+            a.sequence = 4;
+            Ok(())
+        })
+        .unwrap();
+        let err = checker.check_message(&tree).unwrap_err();
+        assert!(err.to_string().contains("sequence decreased"), "{}", err);
+    }
+}
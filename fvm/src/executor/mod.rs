@@ -1,4 +1,6 @@
 mod default;
+mod invariants;
+mod reader;
 mod threaded;
 
 use std::fmt::Display;
@@ -10,13 +12,20 @@ use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ExitCode;
 use fvm_shared::message::Message;
 use fvm_shared::receipt::Receipt;
+use fvm_shared::{ActorID, MethodNum};
 use num_traits::Zero;
+pub use reader::StateReader;
 pub use threaded::ThreadedExecutor;
 
-use crate::call_manager::Backtrace;
+use crate::call_manager::{Backtrace, CallManager};
+use crate::machine::Machine;
 use crate::trace::ExecutionTrace;
 use crate::Kernel;
 
+/// The blockstore backing an executor's state.
+type ExecutorBlockstore<E> =
+    <<<<E as Executor>::Kernel as Kernel>::CallManager as CallManager>::Machine as Machine>::Blockstore;
+
 /// An executor executes messages on the underlying machine/kernel. It's responsible for:
 ///
 /// 1. Validating messages (nonce, sender, etc).
@@ -41,6 +50,11 @@ pub trait Executor {
 
     /// Flushes the state-tree, returning the new root CID.
     fn flush(&mut self) -> anyhow::Result<Cid>;
+
+    /// Returns a read-only view of the current state, including changes that haven't been flushed
+    /// yet. Messages can't be executed while the reader is alive. Fails if the executor can no
+    /// longer access its state (e.g., because a panic poisoned it).
+    fn state_reader(&self) -> anyhow::Result<StateReader<'_, ExecutorBlockstore<Self>>>;
 }
 
 /// A description of some failure encountered when applying a message.
@@ -83,11 +97,26 @@ pub struct ApplyRet {
     pub refund: TokenAmount,
     pub gas_refund: i64,
     pub gas_burned: i64,
+    /// The gas the message counts toward its block's gas limit: the gas limit of explicit messages
+    /// (whatever gas they actually used, and even if they failed preflight), and zero for implicit
+    /// messages (e.g., cron), which aren't bound by the block gas limit.
+    pub gas_burned_for_block_accounting: i64,
 
     /// Additional failure information for debugging, if any.
     pub failure_info: Option<ApplyFailure>,
     /// Execution trace information, for debugging.
     pub exec_trace: ExecutionTrace,
+    /// The IDs of the actors whose state was read while applying the message, in ascending order.
+    /// Only populated when tracing is enabled.
+    pub actors_read: Vec<ActorID>,
+    /// The IDs of the actors whose state was modified by the message, in ascending order. Writes
+    /// made by calls that were later reverted aren't included. Only populated when tracing is
+    /// enabled.
+    pub actors_modified: Vec<ActorID>,
+    /// The number of actors created while applying the message (see
+    /// [`NetworkConfig::max_actors_created`](crate::machine::NetworkConfig::max_actors_created)),
+    /// including those whose creation was reverted.
+    pub actors_created: u64,
 }
 
 impl ApplyRet {
@@ -110,16 +139,21 @@ impl ApplyRet {
             refund: TokenAmount::zero(),
             gas_refund: 0,
             gas_burned: 0,
+            // Set by the executor for explicit messages, which count toward the block gas limit.
+            gas_burned_for_block_accounting: 0,
             failure_info: Some(ApplyFailure::PreValidation(message.into())),
             exec_trace: vec![],
+            actors_read: vec![],
+            actors_modified: vec![],
+            actors_created: 0,
         }
     }
 }
 
 /// The kind of message being applied:
 ///
-/// 1. Explicit messages may only come from account actors and charge the sending account for gas
-/// consumed.
+/// 1. Explicit messages may only come from account actors (or other actors authenticating them, see
+/// [`SenderPolicy`]) and charge the sending account for gas consumed.
 /// 2. Implicit messages may come from any actor, ignore the nonce, and charge no gas (but still
 /// account for it).
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
@@ -127,3 +161,26 @@ pub enum ApplyKind {
     Explicit,
     Implicit,
 }
+
+/// Which actors may send explicit messages. See [`NetworkConfig::sender_policy`].
+///
+/// [`NetworkConfig::sender_policy`]: crate::machine::NetworkConfig::sender_policy
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum SenderPolicy {
+    /// Only account actors, whose messages are authenticated by their signatures, may send
+    /// messages.
+    AccountsOnly,
+    /// Other actors may send messages too, if they authenticate them. Before the message is
+    /// invoked, the system actor calls the sender's `method` with the DAG-CBOR encoded message as
+    /// params, charging the message's gas. The message is invalid (as if its signature were) unless
+    /// the call succeeds, and the sender's sequence is only checked and incremented once it does.
+    ///
+    /// Placeholder actors may never send messages.
+    Authenticate { method: MethodNum },
+}
+
+impl Default for SenderPolicy {
+    fn default() -> Self {
+        SenderPolicy::AccountsOnly
+    }
+}
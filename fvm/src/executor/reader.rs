@@ -0,0 +1,76 @@
+use anyhow::Context;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use fvm_shared::address::Address;
+use fvm_shared::ActorID;
+use serde::de::DeserializeOwned;
+
+use crate::state_tree::{ActorState, StateTree};
+
+/// A read-only view of an executor's current state, including the changes made since it was last
+/// flushed. Obtained through [`Executor::state_reader`](super::Executor::state_reader), it borrows
+/// the executor, so no message can be executed while it's alive.
+pub struct StateReader<'a, B: Blockstore> {
+    state_tree: &'a StateTree<B>,
+}
+
+impl<'a, B: Blockstore> StateReader<'a, B> {
+    /// Create a reader over the given state tree.
+    pub fn new(state_tree: &'a StateTree<B>) -> Self {
+        StateReader { state_tree }
+    }
+
+    /// Resolve an address to an actor ID, if the actor exists.
+    pub fn resolve_address(&self, addr: &Address) -> anyhow::Result<Option<ActorID>> {
+        self.state_tree
+            .lookup_id(addr)
+            .map_err(anyhow::Error::from)
+            .with_context(|| format!("failed to resolve address {}", addr))
+    }
+
+    /// Get the actor at the given address, if it exists.
+    pub fn get_actor(&self, addr: &Address) -> anyhow::Result<Option<ActorState>> {
+        self.state_tree
+            .get_actor(addr)
+            .map_err(anyhow::Error::from)
+            .with_context(|| format!("failed to load actor {}", addr))
+    }
+
+    /// Get the actor with the given ID, if it exists.
+    pub fn get_actor_id(&self, id: ActorID) -> anyhow::Result<Option<ActorState>> {
+        self.state_tree
+            .get_actor_id(id)
+            .map_err(anyhow::Error::from)
+            .with_context(|| format!("failed to load actor {}", id))
+    }
+
+    /// Load and decode the state of the actor at the given address. Returns `None` if the actor
+    /// doesn't exist, and fails if its state is missing or can't be decoded as a `T`.
+    pub fn actor_state<T: DeserializeOwned>(&self, addr: &Address) -> anyhow::Result<Option<T>> {
+        let actor = match self.get_actor(addr)? {
+            Some(actor) => actor,
+            None => return Ok(None),
+        };
+        let state = self
+            .get_cbor(&actor.state)?
+            .with_context(|| format!("state {} of actor {} not found", actor.state, addr))?;
+        Ok(Some(state))
+    }
+
+    /// Load and decode a CBOR object, e.g., to follow links from an actor's state.
+    pub fn get_cbor<T: DeserializeOwned>(&self, cid: &Cid) -> anyhow::Result<Option<T>> {
+        self.state_tree
+            .store()
+            .get_cbor(cid)
+            .with_context(|| format!("failed to load {}", cid))
+    }
+
+    /// Get the raw bytes of a block.
+    pub fn get_block(&self, cid: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        self.state_tree
+            .store()
+            .get(cid)
+            .with_context(|| format!("failed to load {}", cid))
+    }
+}
@@ -3,7 +3,7 @@ use cid::Cid;
 use fvm_shared::message::Message;
 use lazy_static::lazy_static;
 
-use super::{ApplyKind, ApplyRet, Executor};
+use super::{ApplyKind, ApplyRet, Executor, ExecutorBlockstore, StateReader};
 
 lazy_static! {
     static ref EXEC_POOL: yastl::Pool = yastl::Pool::with_config(
@@ -48,4 +48,8 @@ where
     fn flush(&mut self) -> anyhow::Result<Cid> {
         self.0.flush()
     }
+
+    fn state_reader(&self) -> anyhow::Result<StateReader<'_, ExecutorBlockstore<Self>>> {
+        self.0.state_reader()
+    }
 }
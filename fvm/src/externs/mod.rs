@@ -1,37 +1,54 @@
 //! This module contains the logic to invoke the node by traversing Boundary A.
+//!
+//! Externs may do arbitrary work in the node (e.g., traversing the chain), so along with their
+//! result they report the gas that work consumed, typically for the IPLD reads it made. The kernel
+//! charges this on top of the extern's flat price, under the extern's name, before using the
+//! result. Reported gas must never be negative.
 
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::randomness::Randomness;
 pub trait Externs: Rand + Consensus {}
 
+/// An error returned by an extern.
+///
+/// Externs must be careful to distinguish between the two cases: data that is missing on one node
+/// and present on another must never surface as [`ExternError::NotAvailable`], otherwise the nodes
+/// will disagree on the outcome of the message.
+#[derive(thiserror::Error, Debug)]
+pub enum ExternError {
+    /// The extern failed for a reason specific to this node (e.g., a database error). This aborts
+    /// the message with a fatal error instead of producing a receipt.
+    #[error("fatal extern error: {0:#}")]
+    Fatal(#[from] anyhow::Error),
+    /// The requested data doesn't exist on any honest node (e.g., randomness for a future epoch,
+    /// or a consensus fault that can't be verified). This is reported to the calling actor.
+    #[error("not available: {0}")]
+    NotAvailable(String),
+}
+
 /// Consensus related methods.
 pub trait Consensus {
-    /// Verify a consensus fault.
+    /// Verify a consensus fault, returning the fault (if any) and the gas consumed. Return
+    /// [`ExternError::NotAvailable`] if the fault can't be verified (e.g., the block headers can't
+    /// be decoded); the kernel treats that as "no fault".
     fn verify_consensus_fault(
         &self,
         h1: &[u8],
         h2: &[u8],
         extra: &[u8],
-    ) -> anyhow::Result<(Option<ConsensusFault>, i64)>;
+    ) -> Result<(Option<ConsensusFault>, i64), ExternError>;
 }
 
-/// Randomness provider trait
+/// Randomness provider trait. Implementations supply the raw entropy for an epoch; the kernel
+/// mixes in the domain separation tag, epoch, and entropy with
+/// [`fvm_shared::randomness::draw_randomness`].
 pub trait Rand {
-    /// Gets 32 bytes of randomness for ChainRand paramaterized by the DomainSeparationTag,
-    /// ChainEpoch, Entropy from the ticket chain.
-    fn get_chain_randomness(
-        &self,
-        pers: i64,
-        round: ChainEpoch,
-        entropy: &[u8],
-    ) -> anyhow::Result<[u8; 32]>;
+    /// Gets the 32-byte digest of the ticket at the given epoch of the ticket chain, and the gas
+    /// consumed.
+    fn get_chain_randomness(&self, round: ChainEpoch) -> Result<(Randomness, i64), ExternError>;
 
-    /// Gets 32 bytes of randomness for ChainRand paramaterized by the DomainSeparationTag,
-    /// ChainEpoch, Entropy from the latest beacon entry.
-    fn get_beacon_randomness(
-        &self,
-        pers: i64,
-        round: ChainEpoch,
-        entropy: &[u8],
-    ) -> anyhow::Result<[u8; 32]>;
+    /// Gets the 32-byte digest of the latest beacon entry as of the given epoch, and the gas
+    /// consumed.
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> Result<(Randomness, i64), ExternError>;
 }
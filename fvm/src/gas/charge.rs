@@ -3,32 +3,40 @@
 
 use std::borrow::Cow;
 
-use super::Gas;
+use super::timer::GasDuration;
+use super::{Gas, GasValues};
 
 /// Single gas charge in the VM. Contains information about what gas was for, as well
-/// as the amount of gas needed for computation and storage respectively.
+/// as the amount of gas needed for computation and everything else (mostly storage) respectively.
 #[derive(Clone, Debug)]
 pub struct GasCharge {
     pub name: Cow<'static, str>,
     /// Compute costs
     pub compute_gas: Gas,
-    /// Storage costs
-    pub storage_gas: Gas,
+    /// Other costs (mostly storage)
+    pub other_gas: Gas,
+    /// How long the operation covered by this charge took. Only recorded when tracing.
+    pub elapsed: GasDuration,
+    /// The compute and other gas before they were scaled by the executing actor's gas
+    /// multiplier, if any (see [`PriceList::with_actor_multipliers`][super::PriceList::with_actor_multipliers]).
+    pub raw: Option<GasValues>,
 }
 
 impl GasCharge {
-    pub fn new(name: impl Into<Cow<'static, str>>, compute_gas: Gas, storage_gas: Gas) -> Self {
+    pub fn new(name: impl Into<Cow<'static, str>>, compute_gas: Gas, other_gas: Gas) -> Self {
         let name = name.into();
         Self {
             name,
             compute_gas,
-            storage_gas,
+            other_gas,
+            elapsed: GasDuration::default(),
+            raw: None,
         }
     }
 
     /// Calculates total gas charge (in milligas) by summing compute and
-    /// storage gas associated with this charge.
+    /// other gas associated with this charge.
     pub fn total(&self) -> Gas {
-        self.compute_gas + self.storage_gas
+        self.compute_gas + self.other_gas
     }
 }
@@ -5,16 +5,23 @@ use std::fmt::{Debug, Display};
 use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 
 use fvm_shared::econ::TokenAmount;
+use fvm_shared::BLOCK_GAS_LIMIT;
 use num_traits::Zero;
 
 pub use self::charge::GasCharge;
 pub(crate) use self::outputs::GasOutputs;
-pub use self::price_list::{price_list_by_network_version, PriceList, WasmGasPrices};
+pub use self::price_list::{
+    price_list_by_network_version, GasValues, Price, PriceList, WasmGasPrices,
+};
+pub use self::stats::{ChargeStats, GasStats};
+pub use self::timer::{GasDuration, GasTimer};
 use crate::kernel::{ExecutionError, Result};
 
 mod charge;
 mod outputs;
 mod price_list;
+mod stats;
+mod timer;
 
 pub const MILLIGAS_PRECISION: i64 = 1000;
 
@@ -67,6 +74,11 @@ impl Gas {
         Gas(gas.saturating_mul(MILLIGAS_PRECISION))
     }
 
+    #[inline]
+    pub const fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+
     #[inline]
     pub const fn is_saturated(&self) -> bool {
         self.0 == i64::MAX
@@ -151,22 +163,143 @@ impl Mul<i32> for Gas {
     }
 }
 
+/// A factor by which gas charges are scaled, with a precision of 1/1000. See
+/// [`PriceList::with_actor_multipliers`].
+///
+/// Multipliers are never zero: that would make everything an actor does free.
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Debug)]
+pub struct GasMultiplier(u32 /* thousandths */);
+
+impl GasMultiplier {
+    /// Leaves gas charges unchanged.
+    pub const ONE: GasMultiplier = GasMultiplier(1000);
+
+    /// Construct a multiplier scaling gas charges by a whole factor. Returns `None` if the factor
+    /// is zero.
+    #[inline]
+    pub const fn new(factor: u32) -> Option<GasMultiplier> {
+        GasMultiplier::from_thousandths(factor.saturating_mul(1000))
+    }
+
+    /// Construct a multiplier scaling gas charges by `thousandths / 1000`. Returns `None` if
+    /// `thousandths` is zero.
+    #[inline]
+    pub const fn from_thousandths(thousandths: u32) -> Option<GasMultiplier> {
+        if thousandths == 0 {
+            None
+        } else {
+            Some(GasMultiplier(thousandths))
+        }
+    }
+
+    /// Returns the multiplier, in thousandths.
+    #[inline]
+    pub const fn as_thousandths(&self) -> u32 {
+        self.0
+    }
+
+    /// Scales the given amount of gas, rounding down. Saturates at `i64::MAX` milligas.
+    #[inline]
+    pub fn apply(&self, gas: Gas) -> Gas {
+        let milligas = gas.as_milligas() as i128 * self.0 as i128 / 1000;
+        Gas::from_milligas(milligas.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+
+    /// Returns the largest amount of gas that, once scaled, doesn't exceed the given amount.
+    /// Saturates at `i64::MAX` milligas.
+    #[inline]
+    pub fn unapply(&self, gas: Gas) -> Gas {
+        let milligas = gas.as_milligas() as i128 * 1000 / self.0 as i128;
+        Gas::from_milligas(milligas.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl Default for GasMultiplier {
+    fn default() -> Self {
+        GasMultiplier::ONE
+    }
+}
+
+/// The gas limit of a message, in whole gas units. It's validated when constructed, so an
+/// out-of-range limit can never reach the [`GasTracker`].
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Debug)]
+pub struct GasLimit(i64);
+
+impl GasLimit {
+    /// The largest gas limit, for implicit messages that aren't meant to be bounded. It's
+    /// equivalent to `i64::MAX`, which can't be represented in milligas: it's converted to the
+    /// largest representable amount of [`Gas`] instead.
+    pub const UNLIMITED: GasLimit = GasLimit(i64::MAX);
+
+    /// Validates the gas limit of an explicit message, which must be in `[0, BLOCK_GAS_LIMIT]`.
+    pub fn explicit(gas_limit: i64) -> Option<GasLimit> {
+        (0..=BLOCK_GAS_LIMIT)
+            .contains(&gas_limit)
+            .then(|| GasLimit(gas_limit))
+    }
+
+    /// Validates the gas limit of an implicit message, which must not be negative. Unlike explicit
+    /// messages, implicit messages may exceed the block gas limit.
+    pub fn implicit(gas_limit: i64) -> Option<GasLimit> {
+        (gas_limit >= 0).then(|| GasLimit(gas_limit))
+    }
+
+    /// Returns the gas limit, in whole gas units.
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+
+    /// Returns the gas limit as [`Gas`]. Limits too large to be represented in milligas, e.g.
+    /// [`GasLimit::UNLIMITED`], saturate at `i64::MAX` milligas.
+    pub fn as_gas(&self) -> Gas {
+        if *self == GasLimit::UNLIMITED {
+            Gas::from_milligas(i64::MAX)
+        } else {
+            Gas::new(self.0)
+        }
+    }
+}
+
+/// Tracks the gas used by a message.
+///
+/// The tracker maintains the following invariants:
+///
+/// - The gas limit is non-negative.
+/// - Gas used never decreases, and never exceeds the gas limit: a charge that would exceed the
+///   limit fails with [`ExecutionError::OutOfGas`], using up all remaining gas.
+/// - Gas used plus gas available always equals the gas limit.
 pub struct GasTracker {
     gas_limit: Gas,
     gas_used: Gas,
     gas_premium: TokenAmount,
     trace: Option<Vec<GasCharge>>,
+    stats: Option<GasStats>,
+    multiplier: GasMultiplier,
 }
 
 impl GasTracker {
     /// Gas limit and gas used are provided in protocol units (i.e. full units).
     /// They are converted to milligas for internal canonical accounting.
+    ///
+    /// The gas limit must be non-negative, and the gas used must be between zero and the gas limit.
+    /// Out-of-range values are clamped (and panic in debug builds).
     pub fn new(gas_limit: Gas, gas_used: Gas, gas_premium: TokenAmount) -> Self {
+        debug_assert!(!gas_limit.is_negative(), "negative gas limit {}", gas_limit);
+        debug_assert!(
+            !gas_used.is_negative() && gas_used <= gas_limit,
+            "gas used {} out of range (limit {})",
+            gas_used,
+            gas_limit
+        );
+        let gas_limit = gas_limit.max(Gas::zero());
+        let gas_used = gas_used.max(Gas::zero()).min(gas_limit);
         Self {
             gas_limit,
             gas_used,
             gas_premium,
             trace: None,
+            stats: None,
+            multiplier: GasMultiplier::ONE,
         }
     }
 
@@ -174,9 +307,49 @@ impl GasTracker {
         self.trace = Some(vec![]);
     }
 
+    /// Enables recording of per-charge [`GasStats`] (gas calibration).
+    pub fn enable_stats(&mut self) {
+        self.stats = Some(GasStats::default());
+    }
+
+    /// Scales all subsequent charges by the given multiplier, returning the previous one. The call
+    /// manager sets it while an actor with a [multiplier][PriceList::actor_multiplier] executes.
+    pub fn set_multiplier(&mut self, multiplier: GasMultiplier) -> GasMultiplier {
+        std::mem::replace(&mut self.multiplier, multiplier)
+    }
+
+    /// Getter for the multiplier applied to charges.
+    pub fn multiplier(&self) -> GasMultiplier {
+        self.multiplier
+    }
+
+    /// Scales the charge by the current multiplier, recording its raw values if they change.
+    fn scale(&self, mut charge: GasCharge) -> GasCharge {
+        if self.multiplier != GasMultiplier::ONE {
+            charge.raw = Some(GasValues {
+                compute_gas: charge.compute_gas,
+                other_gas: charge.other_gas,
+            });
+            charge.compute_gas = self.multiplier.apply(charge.compute_gas);
+            charge.other_gas = self.multiplier.apply(charge.other_gas);
+        }
+        charge
+    }
+
     fn charge_gas_inner(&mut self, name: &str, to_use: Gas) -> Result<()> {
         log::trace!("charging gas: {} {}", name, to_use);
-        // The gas type uses saturating math.
+        // Gas is never refunded. A negative charge is a bug in the price list (or the caller).
+        if to_use.is_negative() {
+            return Err(ExecutionError::Fatal(anyhow::anyhow!(
+                "negative gas charge {}: {}",
+                name,
+                to_use
+            )));
+        }
+        if let Some(stats) = &mut self.stats {
+            stats.record(name, to_use);
+        }
+        // The gas type uses saturating math, so this saturates at i64::MAX milligas.
         self.gas_used += to_use;
         if self.gas_used > self.gas_limit {
             log::trace!("gas limit reached");
@@ -188,22 +361,33 @@ impl GasTracker {
     }
 
     /// Safely consumes gas and returns an out of gas error if there is not sufficient
-    /// enough gas remaining for charge.
+    /// enough gas remaining for charge. The gas is scaled by the current multiplier.
     pub fn charge_gas(&mut self, name: &str, to_use: Gas) -> Result<()> {
-        let res = self.charge_gas_inner(name, to_use);
+        let charge = self.scale(GasCharge::new(name.to_owned(), to_use, Gas::zero()));
+        let res = self.charge_gas_inner(name, charge.total());
         if let Some(trace) = &mut self.trace {
-            trace.push(GasCharge::new(name.to_owned(), to_use, Gas::zero()))
+            trace.push(charge)
         }
         res
     }
 
-    /// Applies the specified gas charge, where quantities are supplied in milligas.
-    pub fn apply_charge(&mut self, charge: GasCharge) -> Result<()> {
+    /// Applies the specified gas charge, where quantities are supplied in milligas, scaled by the
+    /// current multiplier.
+    ///
+    /// Returns a [`GasTimer`] to be stopped once the operation covered by the charge completes. The
+    /// timer only records anything when tracing is enabled.
+    pub fn apply_charge(&mut self, charge: GasCharge) -> Result<GasTimer> {
+        let mut charge = self.scale(charge);
         let res = self.charge_gas_inner(&charge.name, charge.total());
-        if let Some(trace) = &mut self.trace {
-            trace.push(charge);
-        }
-        res
+        let timer = match &mut self.trace {
+            Some(trace) => {
+                let timer = GasTimer::start(&mut charge.elapsed);
+                trace.push(charge);
+                timer
+            }
+            None => GasTimer::empty(),
+        };
+        res.map(|_| timer)
     }
 
     /// Getter for the maximum gas usable by this message.
@@ -226,6 +410,11 @@ impl GasTracker {
         self.gas_premium.clone()
     }
 
+    /// Takes the recorded gas stats, if gas calibration is enabled.
+    pub fn take_stats(&mut self) -> Option<GasStats> {
+        self.stats.take()
+    }
+
     pub fn drain_trace(&mut self) -> impl Iterator<Item = GasCharge> + '_ {
         self.trace
             .as_mut()
@@ -267,6 +456,187 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn gas_tracker_invariants() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        for _ in 0..1000 {
+            let limit = Gas::from_milligas(rng.gen_range(0..1_000_000));
+            let mut t = GasTracker::new(limit, Gas::zero(), Zero::zero());
+            let mut out_of_gas = false;
+            for _ in 0..rng.gen_range(0..50) {
+                let before = t.gas_used();
+                let charge = Gas::from_milligas(rng.gen_range(0..100_000));
+                let res = t.charge_gas("", charge);
+
+                assert!(t.gas_used() >= before);
+                assert!(t.gas_used() <= limit);
+                assert_eq!(t.gas_used() + t.gas_available(), limit);
+                match res {
+                    Ok(()) => {
+                        assert!(
+                            !out_of_gas || charge == Gas::zero(),
+                            "charge succeeded after running out of gas"
+                        );
+                        assert_eq!(t.gas_used(), before + charge);
+                    }
+                    Err(ExecutionError::OutOfGas) => {
+                        out_of_gas = true;
+                        assert_eq!(t.gas_used(), limit);
+                    }
+                    Err(e) => panic!("unexpected error: {}", e),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zero_gas_limit() {
+        let mut t = GasTracker::new(Gas::zero(), Gas::zero(), Zero::zero());
+        assert_eq!(t.gas_available(), Gas::zero());
+        t.charge_gas("", Gas::zero()).unwrap();
+        assert!(matches!(
+            t.charge_gas("", Gas::from_milligas(1)),
+            Err(ExecutionError::OutOfGas)
+        ));
+        assert_eq!(t.gas_used(), Gas::zero());
+    }
+
+    #[test]
+    fn gas_stats() -> Result<()> {
+        let mut t = GasTracker::new(Gas::new(100), Gas::zero(), Zero::zero());
+        t.enable_stats();
+        t.apply_charge(GasCharge::new("foo", Gas::new(5), Gas::new(1)))?;
+        t.apply_charge(GasCharge::new("bar", Gas::new(10), Gas::zero()))?;
+        t.charge_gas("foo", Gas::from_milligas(1500))?;
+
+        let stats = t.take_stats().expect("stats should be enabled");
+        assert_eq!(
+            stats.charges["foo"],
+            ChargeStats {
+                count: 2,
+                total: Gas::from_milligas(7500),
+            }
+        );
+        assert_eq!(stats.charges["bar"].count, 1);
+        assert_eq!(stats.total(), t.gas_used());
+        Ok(())
+    }
+
+    #[test]
+    fn saturating_gas_tracker() -> Result<()> {
+        let max = Gas::from_milligas(i64::MAX);
+        let mut t = GasTracker::new(max, Gas::zero(), Zero::zero());
+        t.apply_charge(GasCharge::new("", max, Gas::new(1)))?;
+        assert!(t.gas_used().is_saturated());
+        t.charge_gas("", max)?;
+        assert!(t.gas_used().is_saturated());
+        assert!(t.gas_available().is_zero());
+        Ok(())
+    }
+
+    #[test]
+    fn gas_limit_range() {
+        assert_eq!(GasLimit::explicit(0).map(|l| l.value()), Some(0));
+        assert_eq!(
+            GasLimit::explicit(BLOCK_GAS_LIMIT).map(|l| l.value()),
+            Some(BLOCK_GAS_LIMIT)
+        );
+        assert_eq!(GasLimit::explicit(BLOCK_GAS_LIMIT + 1), None);
+        assert_eq!(GasLimit::explicit(-1), None);
+
+        assert_eq!(GasLimit::implicit(i64::MAX), Some(GasLimit::UNLIMITED));
+        assert_eq!(GasLimit::implicit(-1), None);
+        assert_eq!(GasLimit::UNLIMITED.as_gas(), Gas::from_milligas(i64::MAX));
+        assert_eq!(
+            GasLimit::explicit(BLOCK_GAS_LIMIT).unwrap().as_gas(),
+            Gas::new(BLOCK_GAS_LIMIT)
+        );
+    }
+
+    #[test]
+    fn negative_gas_charge() {
+        let mut t = GasTracker::new(Gas::new(20), Gas::new(10), Zero::zero());
+        assert!(matches!(
+            t.apply_charge(GasCharge::new("", Gas::new(5), Gas::new(-6))),
+            Err(ExecutionError::Fatal(_))
+        ));
+        assert!(matches!(
+            t.charge_gas("", Gas::from_milligas(-1)),
+            Err(ExecutionError::Fatal(_))
+        ));
+        assert_eq!(t.gas_used(), Gas::new(10));
+    }
+
+    #[test]
+    fn gas_timer_only_when_tracing() -> Result<()> {
+        let mut t = GasTracker::new(Gas::new(20), Gas::zero(), Zero::zero());
+        let timer = t.apply_charge(GasCharge::new("foo", Gas::new(5), Gas::zero()))?;
+        assert!(!timer.is_recording());
+        timer.stop();
+
+        t.enable_tracing();
+        let timer = t.apply_charge(GasCharge::new("bar", Gas::new(5), Gas::zero()))?;
+        assert!(timer.is_recording());
+        timer.stop();
+        let trace: Vec<_> = t.drain_trace().collect();
+        assert_eq!(trace.len(), 1);
+        assert_eq!(trace[0].name, "bar");
+        assert!(trace[0].elapsed.get().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn gas_multiplier() -> Result<()> {
+        let mut t = GasTracker::new(Gas::new(100), Gas::zero(), Zero::zero());
+        t.enable_tracing();
+        assert_eq!(
+            t.set_multiplier(GasMultiplier::new(3).unwrap()),
+            GasMultiplier::ONE
+        );
+        t.apply_charge(GasCharge::new("foo", Gas::new(5), Gas::new(1)))?;
+        t.charge_gas("bar", Gas::new(2))?;
+        assert_eq!(t.gas_used(), Gas::new(24));
+
+        // Restoring the multiplier stops scaling.
+        t.set_multiplier(GasMultiplier::ONE);
+        t.charge_gas("baz", Gas::new(2))?;
+        assert_eq!(t.gas_used(), Gas::new(26));
+
+        let trace: Vec<_> = t.drain_trace().collect();
+        assert_eq!(trace[0].total(), Gas::new(18));
+        assert_eq!(
+            trace[0].raw,
+            Some(GasValues {
+                compute_gas: Gas::new(5),
+                other_gas: Gas::new(1),
+            })
+        );
+        assert_eq!(trace[1].total(), Gas::new(6));
+        assert!(trace[2].raw.is_none());
+
+        let half = GasMultiplier::from_thousandths(500).unwrap();
+        assert_eq!(half.apply(Gas::from_milligas(3)), Gas::from_milligas(1));
+        assert_eq!(half.unapply(Gas::from_milligas(3)), Gas::from_milligas(6));
+        assert_eq!(
+            GasMultiplier::new(3)
+                .unwrap()
+                .apply(Gas::from_milligas(i64::MAX)),
+            Gas::from_milligas(i64::MAX)
+        );
+        let tiny = GasMultiplier::from_thousandths(1).unwrap();
+        assert_eq!(
+            tiny.unapply(Gas::from_milligas(i64::MAX)),
+            Gas::from_milligas(i64::MAX)
+        );
+
+        // Zero multipliers are rejected.
+        assert_eq!(GasMultiplier::new(0), None);
+        assert_eq!(GasMultiplier::from_thousandths(0), None);
+        Ok(())
+    }
+
     #[test]
     fn milligas_to_gas_round() {
         assert_eq!(milligas_to_gas(100, false), 0);
@@ -1,8 +1,10 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
+use cid::Cid;
 use fvm_shared::crypto::signature::SignatureType;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::piece::PieceInfo;
@@ -17,8 +19,10 @@ use fvm_wasm_instrument::parity_wasm::elements::Instruction;
 use lazy_static::lazy_static;
 use num_traits::Zero;
 
-use super::GasCharge;
+use super::{GasCharge, GasMultiplier};
 use crate::gas::Gas;
+use crate::kernel::SupportedHashes;
+use crate::state_tree::StateAccess;
 
 lazy_static! {
     static ref OH_SNAP_PRICES: PriceList = PriceList {
@@ -34,6 +38,8 @@ lazy_static! {
         send_transfer_funds: Gas::new(27500),
         send_transfer_only_premium: Gas::new(159672),
         send_invoke_method: Gas::new(-5377),
+        send_params_per_byte: Zero::zero(),
+        send_return_per_byte: Zero::zero(),
 
         create_actor_compute: Gas::new(1108454),
         create_actor_storage: Gas::new(36 + 40),
@@ -42,8 +48,49 @@ lazy_static! {
         bls_sig_cost: Gas::new(16598605),
         secp256k1_sig_cost: Gas::new(1637292),
         secp256k1_recover_cost: Gas::new(1637292), // TODO measure & revisit this value
+        // verify_bls_aggregate is only available from nv17.
+        bls_aggregate_sig_cost: ScalingCost {
+            flat: Zero::zero(),
+            scale: Zero::zero(),
+        },
 
-        hashing_base: Gas::new(31355),
+        hashing_cost: [
+            (
+                SupportedHashes::Sha2_256,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Zero::zero(),
+                },
+            ),
+            (
+                SupportedHashes::Blake2b256,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Zero::zero(),
+                },
+            ),
+            (
+                SupportedHashes::Blake2b512,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Zero::zero(),
+                },
+            ),
+            (
+                SupportedHashes::Keccak256,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Zero::zero(),
+                },
+            ),
+            (
+                SupportedHashes::Ripemd160,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Zero::zero(),
+                },
+            ),
+        ].iter().copied().collect(),
         compute_unsealed_sector_cid_base: Gas::new(98647),
         verify_seal_base: Gas::new(2000), // TODO revisit potential removal of this
 
@@ -139,12 +186,22 @@ lazy_static! {
         block_read_base: Zero::zero(),
         block_stat_base: Zero::zero(),
 
+        load_module_base: Zero::zero(),
+        load_module_per_byte: Zero::zero(),
+
+        actor_lookup: Zero::zero(),
+        actor_update: Zero::zero(),
+        address_lookup: Zero::zero(),
+
         syscall_cost: Zero::zero(),
         extern_cost: Zero::zero(),
 
         wasm_rules: WasmGasPrices{
             exec_instruction_cost: Zero::zero(),
         },
+
+        overrides: HashMap::new(),
+        actor_multipliers: HashMap::new(),
     };
 
     static ref SKYR_PRICES: PriceList = PriceList {
@@ -160,6 +217,9 @@ lazy_static! {
         send_transfer_funds: Gas::new(27500),
         send_transfer_only_premium: Gas::new(159672),
         send_invoke_method: Gas::new(-5377),
+        // Passing params and return values is only charged from nv17.
+        send_params_per_byte: Zero::zero(),
+        send_return_per_byte: Zero::zero(),
 
         create_actor_compute: Gas::new(1108454),
         create_actor_storage: Gas::new(36 + 40),
@@ -168,8 +228,49 @@ lazy_static! {
         bls_sig_cost: Gas::new(16598605),
         secp256k1_sig_cost: Gas::new(1637292),
         secp256k1_recover_cost: Gas::new(1637292), // TODO measure & revisit this value
+        // verify_bls_aggregate is only available from nv17.
+        bls_aggregate_sig_cost: ScalingCost {
+            flat: Zero::zero(),
+            scale: Zero::zero(),
+        },
 
-        hashing_base: Gas::new(31355),
+        hashing_cost: [
+            (
+                SupportedHashes::Sha2_256,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Zero::zero(),
+                },
+            ),
+            (
+                SupportedHashes::Blake2b256,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Zero::zero(),
+                },
+            ),
+            (
+                SupportedHashes::Blake2b512,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Zero::zero(),
+                },
+            ),
+            (
+                SupportedHashes::Keccak256,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Zero::zero(),
+                },
+            ),
+            (
+                SupportedHashes::Ripemd160,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Zero::zero(),
+                },
+            ),
+        ].iter().copied().collect(),
         compute_unsealed_sector_cid_base: Gas::new(98647),
         verify_seal_base: Gas::new(2000), // TODO revisit potential removal of this
 
@@ -265,12 +366,87 @@ lazy_static! {
         block_read_base: Zero::zero(),
         block_stat_base: Zero::zero(),
 
+        // Loading actor code is only charged from nv17.
+        load_module_base: Zero::zero(),
+        load_module_per_byte: Zero::zero(),
+
+        actor_lookup: Zero::zero(),
+        actor_update: Zero::zero(),
+        address_lookup: Zero::zero(),
+
         syscall_cost: Gas::new(14000),
         extern_cost: Gas::new(21000),
 
         wasm_rules: WasmGasPrices{
             exec_instruction_cost: Gas::new(4),
         },
+
+        overrides: HashMap::new(),
+        actor_multipliers: HashMap::new(),
+    };
+
+    static ref CANDY_PRICES: PriceList = PriceList {
+        bls_aggregate_sig_cost: ScalingCost {
+            flat: Gas::new(16598605),
+            scale: Gas::new(8299302),
+        },
+
+        // Priced as a block open (plus extern overhead) per HAMT traversal.
+        actor_lookup: Gas::new(135617),
+        actor_update: Gas::new(353640),
+        // The init actor's address map and the state tree are both traversed.
+        address_lookup: Gas::new(271234),
+
+        // Hashing is charged per byte from nv17.
+        // TODO: calibrate the per-byte costs.
+        hashing_cost: [
+            (
+                SupportedHashes::Sha2_256,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Gas::new(7),
+                },
+            ),
+            (
+                SupportedHashes::Blake2b256,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Gas::new(10),
+                },
+            ),
+            (
+                SupportedHashes::Blake2b512,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Gas::new(10),
+                },
+            ),
+            (
+                SupportedHashes::Keccak256,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Gas::new(33),
+                },
+            ),
+            (
+                SupportedHashes::Ripemd160,
+                ScalingCost {
+                    flat: Gas::new(31355),
+                    scale: Gas::new(35),
+                },
+            ),
+        ].iter().copied().collect(),
+
+        // Priced like a block copy in and out of actor memory.
+        send_params_per_byte: Gas::new(10),
+        send_return_per_byte: Gas::new(10),
+
+        // Priced like opening the module's bytecode as a block (block_open_base, plus the memret
+        // and memcpy per-byte costs).
+        load_module_base: Gas::new(114617),
+        load_module_per_byte: Gas::from_milligas(10500),
+
+        ..SKYR_PRICES.clone()
     };
 }
 
@@ -349,6 +525,12 @@ pub struct PriceList {
     /// Accounts for the cost of loading receiver code and method dispatch.
     pub(crate) send_invoke_method: Gas,
 
+    /// Gas cost for every byte of parameters passed to an actor-initiated send.
+    pub(crate) send_params_per_byte: Gas,
+
+    /// Gas cost for every byte of return data passed back from an actor-initiated send.
+    pub(crate) send_return_per_byte: Gas,
+
     /// Gas cost for creating a new actor (via InitActor's Exec method).
     /// Note: this costs assume that the extra will be partially or totally refunded while
     /// the base is covering for the put.
@@ -365,12 +547,14 @@ pub struct PriceList {
     pub(crate) secp256k1_sig_cost: Gas,
     /// Gas cost for recovering secp256k1 signer public key
     pub(crate) secp256k1_recover_cost: Gas,
+    /// Gas cost for verifying an aggregate bls signature, per signer
+    pub(crate) bls_aggregate_sig_cost: ScalingCost,
 
-    pub(crate) hashing_base: Gas,
+    /// Gas cost for hashing data, per hash function and byte.
+    pub(crate) hashing_cost: HashMap<SupportedHashes, ScalingCost>,
 
     pub(crate) compute_unsealed_sector_cid_base: Gas,
     pub(crate) verify_seal_base: Gas,
-    #[allow(unused)]
     pub(crate) verify_aggregate_seal_base: Gas,
     pub(crate) verify_aggregate_seal_per: HashMap<RegisteredSealProof, Gas>,
     pub(crate) verify_aggregate_seal_steps: HashMap<RegisteredSealProof, StepCost>,
@@ -407,6 +591,18 @@ pub struct PriceList {
     /// Gas cost for statting a block.
     pub(crate) block_stat_base: Gas,
 
+    /// Gas cost for loading an actor's Wasm module.
+    pub(crate) load_module_base: Gas,
+    /// Gas cost for every byte of Wasm bytecode loaded.
+    pub(crate) load_module_per_byte: Gas,
+
+    /// Gas cost for an actor-triggered lookup of an actor in the state tree.
+    pub(crate) actor_lookup: Gas,
+    /// Gas cost for an actor-triggered update of an actor in the state tree.
+    pub(crate) actor_update: Gas,
+    /// Gas cost for an actor-triggered resolution of a non-ID address.
+    pub(crate) address_lookup: Gas,
+
     /// General gas cost for performing a syscall, accounting for the overhead thereof.
     pub(crate) syscall_cost: Gas,
     /// General gas cost for calling an extern, accounting for the overhead thereof.
@@ -414,6 +610,21 @@ pub struct PriceList {
 
     /// Rules for execution gas.
     pub(crate) wasm_rules: WasmGasPrices,
+
+    /// Fixed gas values for named charges, replacing the computed values. Empty on all network
+    /// price lists.
+    pub(crate) overrides: HashMap<String, GasValues>,
+
+    /// Multipliers for the gas charged while actors with the given code CIDs are executing. Empty
+    /// on all network price lists.
+    pub(crate) actor_multipliers: HashMap<Cid, GasMultiplier>,
+}
+
+/// The compute and other (storage) gas of a single overridden charge.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GasValues {
+    pub compute_gas: Gas,
+    pub other_gas: Gas,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -421,11 +632,275 @@ pub struct WasmGasPrices {
     pub(crate) exec_instruction_cost: Gas,
 }
 
+/// A single price in a [`PriceList`], as listed by [`PriceList::prices`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Price {
+    /// A fixed amount of gas.
+    Fixed(Gas),
+    /// A factor other prices are multiplied by.
+    Multiplier(i64),
+    /// A flat amount of gas, plus an amount per unit (e.g., per byte or per signer).
+    Scaling { flat: Gas, scale: Gas },
+    /// An amount of gas by steps, as `(start, cost)` pairs: each cost applies to the counts from
+    /// its start up to the next step's start. Counts below the first step cost nothing.
+    Steps(Vec<(i64, Gas)>),
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Price::Fixed(gas) => write!(f, "{}", gas),
+            Price::Multiplier(factor) => write!(f, "x{}", factor),
+            Price::Scaling { flat, scale } => write!(f, "{} + {} per unit", flat, scale),
+            Price::Steps(steps) => {
+                f.write_str("[")?;
+                for (i, (start, cost)) in steps.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", start, cost)?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+impl From<ScalingCost> for Price {
+    fn from(cost: ScalingCost) -> Self {
+        Price::Scaling {
+            flat: cost.flat,
+            scale: cost.scale,
+        }
+    }
+}
+
+impl From<&StepCost> for Price {
+    fn from(cost: &StepCost) -> Self {
+        Price::Steps(cost.0.iter().map(|s| (s.start, s.cost)).collect())
+    }
+}
+
 impl PriceList {
+    /// Returns a copy of this price list where the charges with the given names (e.g.,
+    /// `"OnBlockOpenBase"`) cost exactly the specified gas values.
+    ///
+    /// This is consensus-critical and should only be used for devnets and gas calibration.
+    pub fn with_overrides(&self, overrides: HashMap<String, GasValues>) -> PriceList {
+        let mut price_list = self.clone();
+        price_list.overrides.extend(overrides);
+        price_list
+    }
+
+    /// Returns a copy of this price list where the gas charged while an actor with one of the
+    /// given code CIDs is executing (its method invocation, syscalls, and execution, but not its
+    /// nested calls) is scaled by the corresponding multiplier.
+    ///
+    /// This is consensus-critical and should only be used for devnets: machines refuse to run
+    /// mainnet with such a price list.
+    pub fn with_actor_multipliers(&self, multipliers: HashMap<Cid, GasMultiplier>) -> PriceList {
+        let mut price_list = self.clone();
+        price_list.actor_multipliers.extend(multipliers);
+        price_list
+    }
+
+    /// Returns the gas multiplier for actors with the given code CID, if any.
+    pub fn actor_multiplier(&self, code: &Cid) -> Option<GasMultiplier> {
+        self.actor_multipliers.get(code).copied()
+    }
+
+    /// Returns `true` if this price list scales the gas charged to some actors.
+    pub fn has_actor_multipliers(&self) -> bool {
+        !self.actor_multipliers.is_empty()
+    }
+
+    /// Lists every price by name: the name of the price list's field (e.g., `"send_base"`),
+    /// followed by the hash function or proof type for prices that depend on one (e.g.,
+    /// `"hashing_cost.Sha2_256"`). Wasm execution prices are prefixed with `"wasm_"`; execution
+    /// gas is charged in milligas, one unit of wasm fuel per milligas.
+    ///
+    /// Overrides and actor multipliers aren't listed.
+    pub fn prices(&self) -> BTreeMap<String, Price> {
+        // Bind every field so that adding one to the price list fails to compile until it's listed
+        // here (and in the snapshots).
+        let PriceList {
+            storage_gas_multiplier,
+            on_chain_message_compute_base,
+            on_chain_message_storage_base,
+            on_chain_message_storage_per_byte,
+            on_chain_return_value_per_byte,
+            send_base,
+            send_transfer_funds,
+            send_transfer_only_premium,
+            send_invoke_method,
+            send_params_per_byte,
+            send_return_per_byte,
+            create_actor_compute,
+            create_actor_storage,
+            delete_actor,
+            bls_sig_cost,
+            secp256k1_sig_cost,
+            secp256k1_recover_cost,
+            bls_aggregate_sig_cost,
+            hashing_cost,
+            compute_unsealed_sector_cid_base,
+            verify_seal_base,
+            verify_aggregate_seal_base,
+            verify_aggregate_seal_per,
+            verify_aggregate_seal_steps,
+            verify_post_lookup,
+            verify_consensus_fault,
+            verify_replica_update,
+            get_randomness_base,
+            get_randomness_per_byte,
+            block_memcpy_per_byte_cost,
+            block_open_base,
+            block_open_memret_per_byte_cost,
+            block_link_base,
+            block_link_storage_per_byte_cost,
+            block_create_base,
+            block_create_memret_per_byte_cost,
+            block_read_base,
+            block_stat_base,
+            load_module_base,
+            load_module_per_byte,
+            actor_lookup,
+            actor_update,
+            address_lookup,
+            syscall_cost,
+            extern_cost,
+            wasm_rules,
+            // These adjust the charges computed from the prices above rather than pricing any
+            // operation themselves.
+            overrides: _,
+            actor_multipliers: _,
+        } = self;
+
+        let fixed = |name: &str, gas: &Gas| (name.to_owned(), Price::Fixed(*gas));
+        let memory_grow_cost = match wasm_rules.memory_grow_cost() {
+            MemoryGrowCost::Free => Gas::zero(),
+            MemoryGrowCost::Linear(cost) => Gas::from_milligas(cost.get() as i64),
+        };
+        let mut prices: BTreeMap<_, _> = [
+            (
+                "storage_gas_multiplier".to_owned(),
+                Price::Multiplier(*storage_gas_multiplier),
+            ),
+            fixed(
+                "on_chain_message_compute_base",
+                on_chain_message_compute_base,
+            ),
+            fixed(
+                "on_chain_message_storage_base",
+                on_chain_message_storage_base,
+            ),
+            fixed(
+                "on_chain_message_storage_per_byte",
+                on_chain_message_storage_per_byte,
+            ),
+            fixed(
+                "on_chain_return_value_per_byte",
+                on_chain_return_value_per_byte,
+            ),
+            fixed("send_base", send_base),
+            fixed("send_transfer_funds", send_transfer_funds),
+            fixed("send_transfer_only_premium", send_transfer_only_premium),
+            fixed("send_invoke_method", send_invoke_method),
+            fixed("send_params_per_byte", send_params_per_byte),
+            fixed("send_return_per_byte", send_return_per_byte),
+            fixed("create_actor_compute", create_actor_compute),
+            fixed("create_actor_storage", create_actor_storage),
+            fixed("delete_actor", delete_actor),
+            fixed("bls_sig_cost", bls_sig_cost),
+            fixed("secp256k1_sig_cost", secp256k1_sig_cost),
+            fixed("secp256k1_recover_cost", secp256k1_recover_cost),
+            (
+                "bls_aggregate_sig_cost".to_owned(),
+                Price::from(*bls_aggregate_sig_cost),
+            ),
+            fixed(
+                "compute_unsealed_sector_cid_base",
+                compute_unsealed_sector_cid_base,
+            ),
+            fixed("verify_seal_base", verify_seal_base),
+            fixed("verify_aggregate_seal_base", verify_aggregate_seal_base),
+            fixed("verify_consensus_fault", verify_consensus_fault),
+            fixed("verify_replica_update", verify_replica_update),
+            fixed("get_randomness_base", get_randomness_base),
+            fixed("get_randomness_per_byte", get_randomness_per_byte),
+            fixed("block_memcpy_per_byte_cost", block_memcpy_per_byte_cost),
+            fixed("block_open_base", block_open_base),
+            fixed(
+                "block_open_memret_per_byte_cost",
+                block_open_memret_per_byte_cost,
+            ),
+            fixed("block_link_base", block_link_base),
+            fixed(
+                "block_link_storage_per_byte_cost",
+                block_link_storage_per_byte_cost,
+            ),
+            fixed("block_create_base", block_create_base),
+            fixed(
+                "block_create_memret_per_byte_cost",
+                block_create_memret_per_byte_cost,
+            ),
+            fixed("block_read_base", block_read_base),
+            fixed("block_stat_base", block_stat_base),
+            fixed("load_module_base", load_module_base),
+            fixed("load_module_per_byte", load_module_per_byte),
+            fixed("actor_lookup", actor_lookup),
+            fixed("actor_update", actor_update),
+            fixed("address_lookup", address_lookup),
+            fixed("syscall_cost", syscall_cost),
+            fixed("extern_cost", extern_cost),
+            fixed(
+                "wasm_exec_instruction_cost",
+                &wasm_rules.exec_instruction_cost,
+            ),
+            fixed("wasm_memory_grow_cost_per_page", &memory_grow_cost),
+        ]
+        .into_iter()
+        .collect();
+
+        prices.extend(
+            hashing_cost
+                .iter()
+                .map(|(hasher, cost)| (format!("hashing_cost.{:?}", hasher), Price::from(*cost))),
+        );
+        prices.extend(verify_aggregate_seal_per.iter().map(|(proof, gas)| {
+            (
+                format!("verify_aggregate_seal_per.{:?}", proof),
+                Price::Fixed(*gas),
+            )
+        }));
+        prices.extend(verify_aggregate_seal_steps.iter().map(|(proof, steps)| {
+            (
+                format!("verify_aggregate_seal_steps.{:?}", proof),
+                Price::from(steps),
+            )
+        }));
+        prices.extend(verify_post_lookup.iter().map(|(proof, cost)| {
+            (
+                format!("verify_post_lookup.{:?}", proof),
+                Price::from(*cost),
+            )
+        }));
+        prices
+    }
+
+    /// Constructs a gas charge, applying any override for the given charge name.
+    #[inline]
+    fn charge(&self, name: &'static str, compute_gas: Gas, other_gas: Gas) -> GasCharge {
+        match self.overrides.get(name) {
+            Some(v) => GasCharge::new(name, v.compute_gas, v.other_gas),
+            None => GasCharge::new(name, compute_gas, other_gas),
+        }
+    }
+
     /// Returns the gas required for storing a message of a given size in the chain.
     #[inline]
     pub fn on_chain_message(&self, msg_size: usize) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnChainMessage",
             self.on_chain_message_compute_base,
             (self.on_chain_message_storage_base
@@ -437,7 +912,7 @@ impl PriceList {
     /// Returns the gas required for storing the response of a message in the chain.
     #[inline]
     pub fn on_chain_return_value(&self, data_size: usize) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnChainReturnValue",
             Zero::zero(),
             self.on_chain_return_value_per_byte * data_size as i64 * self.storage_gas_multiplier,
@@ -457,28 +932,61 @@ impl PriceList {
         if method_num != METHOD_SEND {
             ret += self.send_invoke_method;
         }
-        GasCharge::new("OnMethodInvocation", ret, Zero::zero())
+        self.charge("OnMethodInvocation", ret, Zero::zero())
+    }
+
+    /// Returns the gas required to pass parameters of the given size to an actor-initiated send.
+    #[inline]
+    pub fn on_send_params(&self, size: usize) -> GasCharge {
+        self.charge(
+            "OnSendParams",
+            self.send_params_per_byte * size as i64,
+            Zero::zero(),
+        )
+    }
+
+    /// Returns the gas required to pass return data of the given size back from an
+    /// actor-initiated send.
+    #[inline]
+    pub fn on_send_return(&self, size: usize) -> GasCharge {
+        self.charge(
+            "OnSendReturn",
+            self.send_return_per_byte * size as i64,
+            Zero::zero(),
+        )
     }
 
     /// Returns the gas cost to be applied on a syscall.
     pub fn on_syscall(&self) -> GasCharge {
-        GasCharge::new("OnSyscall", self.syscall_cost, Zero::zero())
+        self.charge("OnSyscall", self.syscall_cost, Zero::zero())
     }
 
     /// Returns the gas required for creating an actor.
     #[inline]
     pub fn on_create_actor(&self) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnCreateActor",
             self.create_actor_compute,
             self.create_actor_storage * self.storage_gas_multiplier,
         )
     }
 
+    /// Returns the gas required for creating an account actor when sending to a new key address.
+    /// This costs the same as [`PriceList::on_create_actor`], but is charged under its own name.
+    /// The constructor invocation is charged separately, like any other call.
+    #[inline]
+    pub fn on_create_account_actor(&self) -> GasCharge {
+        self.charge(
+            "OnCreateAccountActor",
+            self.create_actor_compute,
+            self.create_actor_storage * self.storage_gas_multiplier,
+        )
+    }
+
     /// Returns the gas required for deleting an actor.
     #[inline]
     pub fn on_delete_actor(&self) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnDeleteActor",
             Zero::zero(),
             self.delete_actor * self.storage_gas_multiplier,
@@ -490,25 +998,49 @@ impl PriceList {
     pub fn on_verify_signature(&self, sig_type: SignatureType) -> GasCharge {
         let val = match sig_type {
             SignatureType::BLS => self.bls_sig_cost,
-            SignatureType::Secp256k1 => self.secp256k1_sig_cost,
+            // Delegated signatures are rejected by the kernel before gas is charged.
+            SignatureType::Secp256k1 | SignatureType::Delegated => self.secp256k1_sig_cost,
         };
-        GasCharge::new("OnVerifySignature", val, Zero::zero())
+        self.charge("OnVerifySignature", val, Zero::zero())
+    }
+
+    /// Returns gas required for verifying an aggregate bls signature over `num_signers` messages.
+    #[inline]
+    pub fn on_verify_bls_aggregate(&self, num_signers: usize) -> GasCharge {
+        let cost = &self.bls_aggregate_sig_cost;
+        self.charge(
+            "OnVerifyBlsAggregate",
+            cost.flat + cost.scale * num_signers as i64,
+            Zero::zero(),
+        )
     }
 
     /// Returns gas required for recovering signer pubkey from signature
     #[inline]
     pub fn on_recover_secp_public_key(&self) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnRecoverSecpPublicKey",
             self.secp256k1_recover_cost,
             Zero::zero(),
         )
     }
 
-    /// Returns gas required for hashing data.
+    /// Returns gas required for hashing data with the given hash function.
     #[inline]
-    pub fn on_hashing(&self, _: usize) -> GasCharge {
-        GasCharge::new("OnHashing", self.hashing_base, Zero::zero())
+    pub fn on_hashing(&self, hasher: SupportedHashes, data_size: usize) -> GasCharge {
+        let size = data_size as i64;
+        let gas = match self.hashing_cost.get(&hasher) {
+            Some(cost) => cost.flat + cost.scale * size,
+            // Every supported hash function should have a price. If one doesn't, charge the
+            // highest price in the table instead.
+            None => self
+                .hashing_cost
+                .values()
+                .map(|cost| cost.flat + cost.scale * size)
+                .max()
+                .unwrap_or_default(),
+        };
+        self.charge("OnHashing", gas, Zero::zero())
     }
 
     /// Returns gas required for computing unsealed sector Cid.
@@ -518,7 +1050,7 @@ impl PriceList {
         _proof: RegisteredSealProof,
         _pieces: &[PieceInfo],
     ) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnComputeUnsealedSectorCid",
             self.compute_unsealed_sector_cid_base,
             Zero::zero(),
@@ -528,7 +1060,7 @@ impl PriceList {
     /// Returns gas required for seal verification.
     #[inline]
     pub fn on_verify_seal(&self, _info: &SealVerifyInfo) -> GasCharge {
-        GasCharge::new("OnVerifySeal", self.verify_seal_base, Zero::zero())
+        self.charge("OnVerifySeal", self.verify_seal_base, Zero::zero())
     }
     #[inline]
     pub fn on_verify_aggregate_seals(
@@ -559,7 +1091,7 @@ impl PriceList {
             });
         // Should be safe because there is a limit to how much seals get aggregated
         let num = aggregate.infos.len() as i64;
-        GasCharge::new(
+        self.charge(
             "OnVerifyAggregateSeals",
             per_proof * num + step.lookup(num),
             Zero::zero(),
@@ -569,7 +1101,7 @@ impl PriceList {
     /// Returns gas required for replica verification.
     #[inline]
     pub fn on_verify_replica_update(&self, _replica: &ReplicaUpdateInfo) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnVerifyReplicaUpdate",
             self.verify_replica_update,
             Zero::zero(),
@@ -592,13 +1124,13 @@ impl PriceList {
 
         let gas_used = cost.flat + cost.scale * info.challenged_sectors.len() as i64;
 
-        GasCharge::new("OnVerifyPost", gas_used, Zero::zero())
+        self.charge("OnVerifyPost", gas_used, Zero::zero())
     }
 
     /// Returns gas required for verifying consensus fault.
     #[inline]
     pub fn on_verify_consensus_fault(&self) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnVerifyConsensusFault",
             self.extern_cost + self.verify_consensus_fault,
             Zero::zero(),
@@ -609,7 +1141,7 @@ impl PriceList {
     /// numebr of bytes of entropy.
     #[inline]
     pub fn on_get_randomness(&self, entropy_size: usize) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnGetRandomness",
             self.extern_cost
                 + self.get_randomness_base
@@ -621,7 +1153,7 @@ impl PriceList {
     /// Returns the base gas required for loading an object, independent of the object's size.
     #[inline]
     pub fn on_block_open_base(&self) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnBlockOpenBase",
             self.extern_cost + self.block_open_base,
             Zero::zero(),
@@ -632,7 +1164,7 @@ impl PriceList {
     #[inline]
     pub fn on_block_open_per_byte(&self, data_size: usize) -> GasCharge {
         let size = data_size as i64;
-        GasCharge::new(
+        self.charge(
             "OnBlockOpenPerByte",
             (self.block_open_memret_per_byte_cost * size)
                 + (self.block_memcpy_per_byte_cost * size),
@@ -643,7 +1175,7 @@ impl PriceList {
     /// Returns the gas required for reading a loaded object.
     #[inline]
     pub fn on_block_read(&self, data_size: usize) -> GasCharge {
-        GasCharge::new(
+        self.charge(
             "OnBlockRead",
             self.block_read_base + (self.block_memcpy_per_byte_cost * data_size as i64),
             Zero::zero(),
@@ -656,7 +1188,7 @@ impl PriceList {
         let size = data_size as i64;
         let mem_costs = (self.block_create_memret_per_byte_cost * size)
             + (self.block_memcpy_per_byte_cost * size);
-        GasCharge::new(
+        self.charge(
             "OnBlockCreate",
             self.block_create_base + mem_costs,
             Zero::zero(),
@@ -668,7 +1200,7 @@ impl PriceList {
     pub fn on_block_link(&self, data_size: usize) -> GasCharge {
         let size = data_size as i64;
         let memcpy = self.block_memcpy_per_byte_cost * size;
-        GasCharge::new(
+        self.charge(
             "OnBlockLink",
             // twice the memcpy cost:
             // - one from the block registry to the FVM BufferedBlockstore
@@ -682,7 +1214,45 @@ impl PriceList {
     /// Returns the gas required for storing an object.
     #[inline]
     pub fn on_block_stat(&self) -> GasCharge {
-        GasCharge::new("OnBlockStat", self.block_stat_base, Zero::zero())
+        self.charge("OnBlockStat", self.block_stat_base, Zero::zero())
+    }
+
+    /// Returns the gas required for loading an actor's Wasm module of `size` bytes.
+    #[inline]
+    pub fn on_load_module(&self, size: usize) -> GasCharge {
+        self.charge(
+            "OnLoadModule",
+            self.load_module_base + self.load_module_per_byte * size as i64,
+            Zero::zero(),
+        )
+    }
+
+    /// Returns the gas required for looking up an actor in the state tree.
+    #[inline]
+    pub fn on_actor_lookup(&self) -> GasCharge {
+        self.charge("OnActorLookup", self.actor_lookup, Zero::zero())
+    }
+
+    /// Returns the gas required for updating an actor in the state tree.
+    #[inline]
+    pub fn on_actor_update(&self) -> GasCharge {
+        self.charge("OnActorUpdate", self.actor_update, Zero::zero())
+    }
+
+    /// Returns the gas required for resolving a non-ID address to an actor ID.
+    #[inline]
+    pub fn on_address_lookup(&self) -> GasCharge {
+        self.charge("OnAddressLookup", self.address_lookup, Zero::zero())
+    }
+
+    /// Returns the gas required for an actor-triggered access to the state tree.
+    #[inline]
+    pub fn on_state_access(&self, access: StateAccess) -> GasCharge {
+        match access {
+            StateAccess::ActorLookup => self.on_actor_lookup(),
+            StateAccess::ActorUpdate => self.on_actor_update(),
+            StateAccess::AddressLookup => self.on_address_lookup(),
+        }
     }
 }
 
@@ -690,7 +1260,8 @@ impl PriceList {
 pub fn price_list_by_network_version(network_version: NetworkVersion) -> &'static PriceList {
     match network_version {
         NetworkVersion::V15 => &OH_SNAP_PRICES,
-        _ => &SKYR_PRICES,
+        NetworkVersion::V16 => &SKYR_PRICES,
+        _ => &CANDY_PRICES,
     }
 }
 
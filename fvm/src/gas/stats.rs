@@ -0,0 +1,38 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use num_traits::Zero;
+
+use super::Gas;
+
+/// The number of times a named gas charge was applied, and the total gas charged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChargeStats {
+    pub count: u64,
+    pub total: Gas,
+}
+
+/// Per-charge gas statistics collected over a message execution when gas calibration is enabled.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasStats {
+    pub charges: BTreeMap<Cow<'static, str>, ChargeStats>,
+}
+
+impl GasStats {
+    /// Records a single charge of `gas` under the given charge name.
+    pub fn record(&mut self, name: &str, gas: Gas) {
+        let stats = match self.charges.get_mut(name) {
+            Some(stats) => stats,
+            None => self.charges.entry(Cow::Owned(name.to_owned())).or_default(),
+        };
+        stats.count += 1;
+        stats.total += gas;
+    }
+
+    /// Returns the total gas recorded across all charges.
+    pub fn total(&self) -> Gas {
+        self.charges
+            .values()
+            .fold(Gas::zero(), |acc, stats| acc + stats.total)
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The wall-clock duration of the operation covered by a gas charge, for gas calibration.
+///
+/// This is only allocated when the charge is traced. Otherwise it's always empty.
+#[derive(Clone, Debug, Default)]
+pub struct GasDuration(Option<Arc<Mutex<Option<Duration>>>>);
+
+impl GasDuration {
+    /// Returns the recorded duration, if the charge was traced and its timer was stopped.
+    pub fn get(&self) -> Option<Duration> {
+        self.0
+            .as_ref()
+            .and_then(|d| *d.lock().expect("gas duration lock poisoned"))
+    }
+}
+
+/// Times the operation covered by a gas charge. Call [`GasTimer::stop`] when the operation is
+/// complete. A timer dropped without being stopped records nothing.
+#[derive(Debug)]
+pub struct GasTimer(Option<(Instant, Arc<Mutex<Option<Duration>>>)>);
+
+impl GasTimer {
+    /// Returns a timer that records nothing, for charges that aren't traced.
+    pub fn empty() -> Self {
+        GasTimer(None)
+    }
+
+    /// Starts a timer that records into the given duration.
+    pub(super) fn start(duration: &mut GasDuration) -> Self {
+        let slot = Arc::new(Mutex::new(None));
+        duration.0 = Some(slot.clone());
+        GasTimer(Some((Instant::now(), slot)))
+    }
+
+    /// Returns true if this timer records anything.
+    pub fn is_recording(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Stops the timer, recording the time elapsed since the charge was applied.
+    pub fn stop(self) {
+        if let Some((start, slot)) = self.0 {
+            *slot.lock().expect("gas duration lock poisoned") = Some(start.elapsed());
+        }
+    }
+
+    /// Stops the timer and passes through the given value (usually the result of the timed
+    /// operation).
+    pub fn stop_with<T>(self, value: T) -> T {
+        self.stop();
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_on_stop() {
+        let mut duration = GasDuration::default();
+        assert_eq!(duration.get(), None);
+
+        let timer = GasTimer::start(&mut duration);
+        assert!(timer.is_recording());
+        assert_eq!(duration.get(), None);
+        assert_eq!(timer.stop_with(5), 5);
+        assert!(duration.get().is_some());
+
+        // Dropping a timer without stopping it records nothing.
+        let mut duration = GasDuration::default();
+        drop(GasTimer::start(&mut duration));
+        assert_eq!(duration.get(), None);
+    }
+
+    #[test]
+    fn empty() {
+        let timer = GasTimer::empty();
+        assert!(!timer.is_recording());
+        timer.stop();
+    }
+}
@@ -19,14 +19,12 @@ use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_ipld_encoding::{Cbor, CborStore};
 use fvm_ipld_hamt::Hamt;
-use fvm_shared::address::{Address, Payload};
+use fvm_shared::address::{Address, Payload, FIRST_NON_SINGLETON_ADDR};
+pub use fvm_shared::builtin::singletons::{INIT_ACTOR_ADDR, INIT_ACTOR_ID};
 use fvm_shared::{ActorID, HAMT_BIT_WIDTH};
 
-use crate::state_tree::{ActorState, StateTree};
-
-pub const INIT_ACTOR_ADDR: Address = Address::new_id(1);
-
 use crate::kernel::{ClassifyResult, Result};
+use crate::state_tree::{ActorState, StateTree};
 
 #[derive(Serialize_tuple, Deserialize_tuple, Debug)]
 pub struct State {
@@ -58,7 +56,7 @@ impl State {
 
         State {
             address_map: e_cid,
-            next_id: 100,
+            next_id: FIRST_NON_SINGLETON_ADDR,
             network_name: "test".to_owned(),
             #[cfg(feature = "m2-native")]
             installed_actors: el_cid,
@@ -78,8 +76,9 @@ impl State {
         let state = state_tree
             .store()
             .get_cbor(&init_act.state)
+            .with_context(|| format!("failed to load init actor state {}", init_act.state))
             .or_fatal()?
-            .context("init actor state not found")
+            .with_context(|| format!("init actor state {} not found", init_act.state))
             .or_fatal()?;
 
         Ok((state, init_act))
@@ -95,9 +94,25 @@ impl State {
         self.next_id += 1;
 
         let mut map = Hamt::<B, _>::load_with_bit_width(&self.address_map, store, HAMT_BIT_WIDTH)
+            .with_context(|| {
+                format!(
+                    "failed to load the init actor's address map {}",
+                    self.address_map
+                )
+            })
+            .or_fatal()?;
+        map.set(addr.to_bytes().into(), id)
+            .with_context(|| {
+                format!(
+                    "failed to map {} to actor {} in the init actor's address map",
+                    addr, id
+                )
+            })
+            .or_fatal()?;
+        self.address_map = map
+            .flush()
+            .context("failed to flush the init actor's address map")
             .or_fatal()?;
-        map.set(addr.to_bytes().into(), id).or_fatal()?;
-        self.address_map = map.flush().or_fatal()?;
 
         Ok(id)
     }
@@ -123,8 +138,38 @@ impl State {
         }
 
         let map = Hamt::<B, _>::load_with_bit_width(&self.address_map, store, HAMT_BIT_WIDTH)
+            .with_context(|| {
+                format!(
+                    "failed to load the init actor's address map {}",
+                    self.address_map
+                )
+            })
             .or_fatal()?;
 
-        Ok(map.get(&addr.to_bytes()).or_fatal()?.copied())
+        Ok(map
+            .get(&addr.to_bytes())
+            .with_context(|| format!("failed to lookup {} in the init actor's address map", addr))
+            .or_fatal()?
+            .copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::builtin::singletons::is_singleton;
+
+    use super::*;
+
+    #[test]
+    fn first_id_is_not_a_singleton() {
+        let store = MemoryBlockstore::default();
+        let mut state = State::new_test(&store);
+
+        let addr = Address::new_actor(b"first");
+        let id = state.map_address_to_new_id(&store, &addr).unwrap();
+        assert_eq!(id, FIRST_NON_SINGLETON_ADDR);
+        assert!(!is_singleton(id));
+        assert_eq!(state.resolve_address(&store, &addr).unwrap(), Some(id));
     }
 }
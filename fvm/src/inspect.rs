@@ -0,0 +1,314 @@
+//! Renders actor states as JSON for debugging tools (explorers, conformance diffs, etc.).
+//!
+//! The states of the builtin actors the FVM knows about (system, init, account, and cron) are
+//! decoded field by field. Any other actor's state is rendered as CBOR in [diagnostic
+//! notation](https://www.rfc-editor.org/rfc/rfc8949.html#name-diagnostic-notation).
+//!
+//! CIDs are rendered as strings, addresses in f-notation, and token amounts in FIL.
+
+use std::fmt::Write as _;
+use std::io::Write;
+
+use anyhow::{anyhow, Context};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::CborStore;
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared::address::Address;
+use fvm_shared::{ActorID, MethodNum, HAMT_BIT_WIDTH};
+use libipld_core::ipld::Ipld;
+use serde_json::{json, Map, Value};
+
+use crate::machine::Manifest;
+use crate::state_tree::{ActorState, StateTree};
+use crate::{account_actor, init_actor, system_actor};
+
+/// Options controlling how much of each actor's state is rendered.
+#[derive(Clone, Debug)]
+pub struct InspectOptions {
+    /// The maximum number of entries rendered from each collection (e.g., the init actor's
+    /// address map). Collections are always rendered with their total size.
+    pub max_entries: usize,
+}
+
+impl Default for InspectOptions {
+    fn default() -> Self {
+        Self { max_entries: 1000 }
+    }
+}
+
+/// The cron actor's state. The FVM doesn't otherwise need it.
+#[derive(Deserialize_tuple)]
+struct CronState {
+    entries: Vec<CronEntry>,
+}
+
+#[derive(Deserialize_tuple)]
+struct CronEntry {
+    receiver: Address,
+    method_num: MethodNum,
+}
+
+/// Renders the actor at `address` in the state tree rooted at `state_root`, or `None` if there's
+/// no such actor.
+pub fn dump_actor<B: Blockstore>(
+    store: &B,
+    state_root: &Cid,
+    address: &Address,
+    options: &InspectOptions,
+) -> anyhow::Result<Option<Value>> {
+    let tree = load_tree(store, state_root)?;
+    let manifest = load_manifest(&tree)?;
+    let actor = match tree.get_actor(address).map_err(anyhow::Error::from)? {
+        Some(actor) => actor,
+        None => return Ok(None),
+    };
+    let id = tree
+        .lookup_id(address)
+        .map_err(anyhow::Error::from)?
+        .ok_or_else(|| anyhow!("actor {} has no ID address", address))?;
+    dump(store, &manifest, id, &actor, options).map(Some)
+}
+
+/// Renders every actor in the state tree rooted at `state_root`, writing one JSON object per line.
+pub fn dump_all<B: Blockstore, W: Write>(
+    store: &B,
+    state_root: &Cid,
+    mut writer: W,
+    options: &InspectOptions,
+) -> anyhow::Result<()> {
+    let tree = load_tree(store, state_root)?;
+    let manifest = load_manifest(&tree)?;
+    tree.for_each(|addr, actor| {
+        let value = dump(store, &manifest, addr.id()?, actor, options)?;
+        serde_json::to_writer(&mut writer, &value)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    })?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn load_tree<B: Blockstore>(store: &B, state_root: &Cid) -> anyhow::Result<StateTree<&B>> {
+    StateTree::new_from_root(store, state_root)
+        .map_err(anyhow::Error::from)
+        .context("failed to load state tree")
+}
+
+fn load_manifest<B: Blockstore>(tree: &StateTree<B>) -> anyhow::Result<Manifest> {
+    let (state, _) = system_actor::State::load(tree).map_err(anyhow::Error::from)?;
+    let data = manifest_data(tree.store(), &state.builtin_actors)?;
+    Manifest::load(tree.store(), &data, 1)
+}
+
+/// Returns the CID of the builtin actors manifest data referenced by the system actor. This is
+/// usually the data itself, but test networks may reference a `(version, data)` manifest root.
+fn manifest_data<B: Blockstore>(store: &B, cid: &Cid) -> anyhow::Result<Cid> {
+    match store.get_cbor::<(u32, Cid)>(cid) {
+        Ok(Some((1, data))) => Ok(data),
+        Ok(Some((version, _))) => Err(anyhow!("unsupported manifest version {}", version)),
+        _ => Ok(*cid),
+    }
+}
+
+fn dump<B: Blockstore>(
+    store: &B,
+    manifest: &Manifest,
+    id: ActorID,
+    actor: &ActorState,
+    options: &InspectOptions,
+) -> anyhow::Result<Value> {
+    let name = manifest.name_by_code(&actor.code);
+    let state = dump_state(store, name, &actor.state, options)
+        .with_context(|| format!("failed to render the state of actor {}", id))?;
+    Ok(json!({
+        "address": Address::new_id(id).to_string(),
+        "code": actor.code.to_string(),
+        "name": name,
+        "head": actor.state.to_string(),
+        "sequence": actor.sequence,
+        "balance": actor.balance.to_string(),
+        "delegated_address": actor.delegated_address.map(|addr| addr.to_string()),
+        "state": state,
+    }))
+}
+
+fn dump_state<B: Blockstore>(
+    store: &B,
+    name: Option<&str>,
+    head: &Cid,
+    options: &InspectOptions,
+) -> anyhow::Result<Value> {
+    fn get<T: serde::de::DeserializeOwned, B: Blockstore>(
+        store: &B,
+        head: &Cid,
+    ) -> anyhow::Result<T> {
+        store
+            .get_cbor(head)?
+            .ok_or_else(|| anyhow!("state {} not found", head))
+    }
+
+    Ok(match name {
+        Some("system") => {
+            let state: system_actor::State = get(store, head)?;
+            let actors: Vec<(String, Cid)> =
+                get(store, &manifest_data(store, &state.builtin_actors)?)?;
+            json!({
+                "builtin_actors": actors
+                    .into_iter()
+                    .map(|(name, code)| (name, Value::String(code.to_string())))
+                    .collect::<Map<_, _>>(),
+            })
+        }
+        Some("init") => {
+            let state: init_actor::State = get(store, head)?;
+            let map = Hamt::<_, ActorID, BytesKey>::load_with_bit_width(
+                &state.address_map,
+                store,
+                HAMT_BIT_WIDTH,
+            )?;
+            let mut entries = Map::new();
+            let mut count = 0;
+            map.for_each(|k, id| {
+                if count < options.max_entries {
+                    let addr = Address::from_bytes(&k.0)?;
+                    entries.insert(
+                        addr.to_string(),
+                        Value::String(Address::new_id(*id).to_string()),
+                    );
+                }
+                count += 1;
+                Ok(())
+            })?;
+            json!({
+                "address_map": { "count": count, "entries": entries },
+                "next_id": state.next_id,
+                "network_name": state.network_name,
+            })
+        }
+        Some("account") => {
+            let state: account_actor::State = get(store, head)?;
+            json!({ "address": state.address.to_string() })
+        }
+        Some("cron") => {
+            let state: CronState = get(store, head)?;
+            json!({
+                "entries": {
+                    "count": state.entries.len(),
+                    "entries": state
+                        .entries
+                        .iter()
+                        .take(options.max_entries)
+                        .map(|e| json!({
+                            "receiver": e.receiver.to_string(),
+                            "method_num": e.method_num,
+                        }))
+                        .collect::<Vec<_>>(),
+                },
+            })
+        }
+        _ => {
+            let state: Ipld = get(store, head)?;
+            let mut diag = String::new();
+            write_diag(&mut diag, &state);
+            json!({ "cbor": diag })
+        }
+    })
+}
+
+/// Writes an IPLD value in CBOR diagnostic notation. Links are written as tag 42.
+fn write_diag(out: &mut String, ipld: &Ipld) {
+    fn write_bytes(out: &mut String, bytes: &[u8]) {
+        out.push_str("h'");
+        for b in bytes {
+            let _ = write!(out, "{:02x}", b);
+        }
+        out.push('\'');
+    }
+
+    match ipld {
+        Ipld::Null => out.push_str("null"),
+        Ipld::Bool(b) => {
+            let _ = write!(out, "{}", b);
+        }
+        Ipld::Integer(i) => {
+            let _ = write!(out, "{}", i);
+        }
+        Ipld::Float(f) => {
+            let _ = write!(out, "{:?}", f);
+        }
+        Ipld::String(s) => {
+            let _ = write!(out, "{:?}", s);
+        }
+        Ipld::Bytes(b) => write_bytes(out, b),
+        Ipld::List(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_diag(out, item);
+            }
+            out.push(']');
+        }
+        Ipld::Map(entries) => {
+            out.push('{');
+            for (i, (k, v)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                let _ = write!(out, "{:?}: ", k);
+                write_diag(out, v);
+            }
+            out.push('}');
+        }
+        Ipld::Link(cid) => {
+            // DAG-CBOR links are byte strings prefixed with the multibase identity prefix (0x00).
+            out.push_str("42(");
+            let mut bytes = vec![0];
+            bytes.extend(cid.to_bytes());
+            write_bytes(out, &bytes);
+            out.push(')');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code, Multihash};
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::IDENTITY_HASH;
+
+    use super::*;
+
+    #[test]
+    fn diag_notation() {
+        let link = Cid::new_v1(0x55, Multihash::wrap(IDENTITY_HASH, b"a").unwrap());
+        let value = Ipld::List(vec![
+            Ipld::Integer(-1),
+            Ipld::String("hi \"there\"".into()),
+            Ipld::Bytes(vec![0xde, 0xad]),
+            Ipld::Map([("k".to_owned(), Ipld::Bool(true))].into_iter().collect()),
+            Ipld::Null,
+            Ipld::Link(link),
+        ]);
+        let mut diag = String::new();
+        write_diag(&mut diag, &value);
+        assert_eq!(
+            diag,
+            r#"[-1, "hi \"there\"", h'dead', {"k": true}, null, 42(h'000155000161')]"#
+        );
+    }
+
+    #[test]
+    fn unknown_state() {
+        let store = MemoryBlockstore::default();
+        let head = store
+            .put_cbor(&(1u64, "x".to_owned()), Code::Blake2b256)
+            .unwrap();
+        let value =
+            dump_state(&store, Some("multisig"), &head, &InspectOptions::default()).unwrap();
+        assert_eq!(value, json!({ "cbor": r#"[1, "x"]"# }));
+    }
+}
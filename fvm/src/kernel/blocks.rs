@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::convert::TryInto;
 use std::rc::Rc;
 
@@ -11,6 +12,45 @@ use crate::syscall_error;
 #[derive(Default)]
 pub struct BlockRegistry {
     blocks: Vec<Block>,
+    /// The total size of the blocks in this registry.
+    size: usize,
+    /// The byte budget shared with the other block registries in the call stack.
+    budget: Rc<BlockBudget>,
+}
+
+/// Limits the total size of the blocks held by all block registries in a call stack. Every block
+/// registry in the call stack shares the same budget, and returns its bytes when dropped.
+#[derive(Debug)]
+pub struct BlockBudget {
+    used: Cell<usize>,
+    max: usize,
+}
+
+impl BlockBudget {
+    /// Creates a new budget allowing at most `max` bytes.
+    pub fn new(max: usize) -> Self {
+        Self {
+            used: Cell::new(0),
+            max,
+        }
+    }
+
+    /// Returns the number of bytes currently held.
+    pub fn used(&self) -> usize {
+        self.used.get()
+    }
+
+    /// Returns the maximum number of bytes that may be held.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl Default for BlockBudget {
+    /// An unlimited budget.
+    fn default() -> Self {
+        Self::new(usize::MAX)
+    }
 }
 
 /// Blocks in the block registry are addressed by an ordinal, starting from 1 (`FIRST_ID`).
@@ -77,6 +117,8 @@ impl Block {
 pub enum BlockPutError {
     #[error("too many blocks have been written")]
     TooManyBlocks,
+    #[error("block registry size limit exceeded: {0} bytes already held")]
+    TooManyBytes(usize),
     #[error("invalid or forbidden ipld codec")]
     InvalidCodec(u64),
 }
@@ -84,7 +126,9 @@ pub enum BlockPutError {
 impl From<BlockPutError> for super::SyscallError {
     fn from(e: BlockPutError) -> Self {
         match e {
-            BlockPutError::TooManyBlocks => syscall_error!(LimitExceeded; "{}", e),
+            BlockPutError::TooManyBlocks | BlockPutError::TooManyBytes(_) => {
+                syscall_error!(LimitExceeded; "{}", e)
+            }
             BlockPutError::InvalidCodec(_) => syscall_error!(IllegalCodec; "{}", e),
         }
     }
@@ -114,7 +158,22 @@ impl From<InvalidHandleError> for ExecutionError {
 
 impl BlockRegistry {
     pub(crate) fn new() -> Self {
-        Self { blocks: Vec::new() }
+        Self::default()
+    }
+
+    /// Creates a new block registry, drawing from the specified shared budget.
+    pub fn with_budget(budget: Rc<BlockBudget>) -> Self {
+        Self {
+            blocks: Vec::new(),
+            size: 0,
+            budget,
+        }
+    }
+}
+
+impl Drop for BlockRegistry {
+    fn drop(&mut self) {
+        self.budget.used.set(self.budget.used.get() - self.size);
     }
 }
 
@@ -129,6 +188,14 @@ impl BlockRegistry {
             return Err(BlockPutError::InvalidCodec(block.codec));
         }
 
+        let used = self.budget.used.get();
+        let size = block.size() as usize;
+        if size > self.budget.max.saturating_sub(used) {
+            return Err(BlockPutError::TooManyBytes(used));
+        }
+        self.budget.used.set(used + size);
+        self.size += size;
+
         let id = FIRST_ID + self.blocks.len() as u32;
         self.blocks.push(block);
         Ok(id)
@@ -8,14 +8,17 @@ use byteorder::{BigEndian, WriteBytesExt};
 use cid::Cid;
 use filecoin_proofs_api::{self as proofs, ProverId, PublicReplicaInfo, SectorId};
 use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::{bytes_32, from_slice, to_vec};
+use fvm_ipld_encoding::{from_slice, to_vec};
 use fvm_shared::address::Protocol;
 use fvm_shared::bigint::Zero;
+use fvm_shared::builtin::singletons::{INIT_ACTOR_ID, SYSTEM_ACTOR_ID};
+use fvm_shared::chainid::ChainID;
 use fvm_shared::consensus::ConsensusFault;
 use fvm_shared::crypto::signature;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ErrorNumber;
 use fvm_shared::piece::{zero_piece_commitment, PaddedPieceSize};
+use fvm_shared::randomness::draw_randomness;
 use fvm_shared::sector::SectorInfo;
 use fvm_shared::version::NetworkVersion;
 use fvm_shared::{commcid, ActorID};
@@ -27,8 +30,8 @@ use super::blocks::{Block, BlockRegistry};
 use super::error::Result;
 use super::hash::SupportedHashes;
 use super::*;
-use crate::call_manager::{CallManager, InvocationResult, NO_DATA_BLOCK_ID};
-use crate::externs::{Consensus, Rand};
+use crate::call_manager::{CallManager, NO_DATA_BLOCK_ID};
+use crate::externs::{Consensus, ExternError, Rand};
 use crate::gas::GasCharge;
 use crate::state_tree::ActorState;
 use crate::{syscall_error, EMPTY_ARR_CID};
@@ -59,6 +62,14 @@ pub struct DefaultKernel<C> {
     ///
     /// This does not yet reason about reachability.
     blocks: BlockRegistry,
+    /// The state-root set by this actor that hasn't yet been committed to the state tree. It's
+    /// committed before the actor sends a message (so callees observe it), and when the invocation
+    /// returns successfully.
+    pending_root: Option<Cid>,
+    /// Whether the actor self-destructed during this invocation. The actor is only removed from
+    /// the state tree when the invocation returns successfully, so an abort anywhere in the call
+    /// stack resurrects it.
+    deleted: bool,
 }
 
 // Even though all children traits are implemented, Rust needs to know that the
@@ -76,6 +87,19 @@ where
         (self.call_manager, self.blocks)
     }
 
+    fn commit_root(&mut self) -> Result<()> {
+        self.commit_pending_root()?;
+        if !self.deleted {
+            return Ok(());
+        }
+
+        // The call manager rejects sends to the actor while its deletion is pending, so it can't
+        // have received any funds since self-destructing.
+        self.call_manager
+            .state_tree_mut()
+            .delete_actor_id(self.actor_id)
+    }
+
     fn new(
         mgr: C,
         blocks: BlockRegistry,
@@ -91,6 +115,8 @@ where
             actor_id,
             method,
             value_received,
+            pending_root: None,
+            deleted: false,
         }
     }
 
@@ -138,9 +164,9 @@ where
             .state_tree()
             .store()
             .get(&act.state)
-            .context("failed to look up state")
+            .with_context(|| format!("failed to look up the state of account {}", addr))
             .or_fatal()?
-            .context("account actor state not found")
+            .with_context(|| format!("state {} of account {} not found", act.state, addr))
             .or_fatal()?;
 
         if charge_gas {
@@ -158,6 +184,17 @@ where
         Ok(state.address)
     }
 
+    /// Commits the state-root set by the actor (if any) to the state tree, but not its deletion.
+    fn commit_pending_root(&mut self) -> Result<()> {
+        match self.pending_root.take() {
+            Some(root) => self.mutate_self(|actor_state| {
+                actor_state.state = root;
+                Ok(())
+            }),
+            None => Ok(()),
+        }
+    }
+
     /// Returns `Some(actor_state)` or `None` if this actor has been deleted.
     fn get_self(&self) -> Result<Option<ActorState>> {
         self.call_manager
@@ -167,6 +204,25 @@ where
             .context("error when finding current actor")
     }
 
+    /// Charges the gas an extern reported consuming in the node, on top of its flat price, from
+    /// nv17. If this exceeds the gas remaining, the message runs out of gas before the extern's
+    /// result is used.
+    fn charge_extern_gas(&mut self, name: &'static str, gas: i64) -> Result<()> {
+        if gas < 0 {
+            return Err(ExecutionError::Fatal(anyhow!(
+                "extern {} reported negative gas {}",
+                name,
+                gas
+            )));
+        }
+        if self.network_version() < NetworkVersion::V17 {
+            return Ok(());
+        }
+        self.call_manager
+            .charge_gas(GasCharge::new(name, Gas::new(gas), Gas::zero()))?;
+        Ok(())
+    }
+
     /// Mutates this actor's state, returning a syscall error if this actor has been deleted.
     fn mutate_self<F>(&mut self, mutate: F) -> Result<()>
     where
@@ -184,6 +240,20 @@ where
                 }
             })
     }
+
+    /// Returns a `Forbidden` error unless this actor may install or upgrade actor code: the System
+    /// actor, or the network's upgrade admin (if any).
+    fn check_upgrade_privilege(&self) -> Result<()> {
+        let upgrade_admin = self.call_manager.context().upgrade_admin;
+        if self.actor_id == SYSTEM_ACTOR_ID || upgrade_admin == Some(self.actor_id) {
+            Ok(())
+        } else {
+            Err(
+                syscall_error!(Forbidden; "actor {} may not change actor code", self.actor_id)
+                    .into(),
+            )
+        }
+    }
 }
 
 impl<C> SelfOps for DefaultKernel<C>
@@ -191,19 +261,32 @@ where
     C: CallManager,
 {
     fn root(&self) -> Result<Cid> {
+        if self.deleted {
+            return Err(
+                syscall_error!(IllegalOperation; "state root requested after self-destruct").into(),
+            );
+        }
         // This can fail during normal operations if the actor has been deleted.
-        Ok(self
+        let state = self
             .get_self()?
             .context("state root requested after actor deletion")
             .or_error(ErrorNumber::IllegalOperation)?
-            .state)
+            .state;
+        Ok(self.pending_root.unwrap_or(state))
     }
 
     fn set_root(&mut self, new: Cid) -> Result<()> {
-        self.mutate_self(|actor_state| {
-            actor_state.state = new;
-            Ok(())
-        })
+        // The state tree is only updated when the root is committed, but the actor pays for it here.
+        self.call_manager
+            .charge_gas(self.call_manager.price_list().on_actor_update())?;
+
+        if self.deleted || self.get_self()?.is_none() {
+            return Err(syscall_error!(IllegalOperation; "actor deleted").into());
+        }
+
+        // The new root is committed to the state tree later, see `Kernel::commit_root`.
+        self.pending_root = Some(new);
+        Ok(())
     }
 
     fn current_balance(&self) -> Result<TokenAmount> {
@@ -212,8 +295,8 @@ where
     }
 
     fn self_destruct(&mut self, beneficiary: &Address) -> Result<()> {
-        // Idempotentcy: If the actor doesn't exist, this won't actually do anything. The current
-        // balance will be zero, and `delete_actor_id` will be a no-op.
+        // Idempotentcy: If the actor has already self-destructed, this won't actually do anything.
+        // The current balance will be zero, as it can't receive funds while its deletion is pending.
         self.call_manager
             .charge_gas(self.call_manager.price_list().on_delete_actor())?;
 
@@ -238,10 +321,14 @@ where
                 .transfer(self.actor_id, beneficiary_id, &balance)?;
         }
 
-        // Delete the executing actor
-        self.call_manager
-            .state_tree_mut()
-            .delete_actor_id(self.actor_id)
+        // Discard any uncommitted state-root, and mark the actor for deletion. It stays in the state
+        // tree (with no balance) until the invocation returns, so it can still send messages and
+        // read the blocks it has open, but the call manager treats it as deleted when it's sent
+        // to. See `Kernel::commit_root`.
+        self.pending_root = None;
+        self.deleted = true;
+        self.call_manager.mark_pending_deletion(self.actor_id);
+        Ok(())
     }
 }
 
@@ -261,13 +348,25 @@ where
             .get(cid)
             // TODO: This is really "super fatal". It means we failed to store state, and should
             // probably abort the entire block.
+            .with_context(|| format!("actor {} failed to open block {}", self.actor_id, cid))
             .or_fatal()?
-            .ok_or_else(|| anyhow!("missing state: {}", cid))
-            // Missing state is a fatal error because it means we have a bug. Once we do
-            // reachability checking (for user actors) we won't get here unless the block is known
-            // to be in the state-tree.
+            // Missing state is a fatal error because it means we have a bug (or the embedder's
+            // blockstore is incomplete), not that the actor misbehaved. Once we do reachability
+            // checking (for user actors) we won't get here unless the block is known to be in the
+            // state-tree.
+            .with_context(|| {
+                format!(
+                    "missing state: actor {} opened block {}",
+                    self.actor_id, cid
+                )
+            })
             .or_fatal()?;
 
+        self.call_manager
+            .context()
+            .metrics
+            .record_blockstore_io(data.len() as u64, 0);
+
         let block = Block::new(cid.codec(), data);
 
         self.call_manager.charge_gas(
@@ -314,7 +413,12 @@ where
             .put_keyed(&k, block.data())
             // TODO: This is really "super fatal". It means we failed to store state, and should
             // probably abort the entire block.
+            .with_context(|| format!("actor {} failed to write block {}", self.actor_id, k))
             .or_fatal()?;
+        self.call_manager
+            .context()
+            .metrics
+            .record_blockstore_io(0, block.size() as u64);
         Ok(k)
     }
 
@@ -412,31 +516,52 @@ where
             Some(self.blocks.get(params_id)?.clone())
         };
 
+        // Check and charge for the size of the parameters.
+        let params_size = params.as_ref().map(|blk| blk.size() as usize).unwrap_or(0);
+        let max_params_size = self.call_manager.context().max_params_size;
+        if params_size > max_params_size {
+            return Err(syscall_error!(
+                LimitExceeded;
+                "parameters of {} bytes exceed the maximum of {} bytes",
+                params_size,
+                max_params_size
+            )
+            .into());
+        }
+        self.call_manager
+            .charge_gas(self.call_manager.price_list().on_send_params(params_size))?;
+
         // Make sure we can actually store the return block.
         if self.blocks.is_full() {
             return Err(syscall_error!(LimitExceeded; "cannot store return block").into());
         }
 
+        // Commit our state-root so the callee (which may call back into us) observes it. If we've
+        // self-destructed, we're only deleted once we return.
+        self.commit_pending_root()?;
+
         // Send.
         let result = self
             .call_manager
             .with_transaction(|cm| cm.send::<Self>(from, *recipient, method, params, value))?;
 
         // Store result and return.
-        Ok(match result {
-            InvocationResult::Return(None) => {
-                SendResult::Return(NO_DATA_BLOCK_ID, BlockStat { codec: 0, size: 0 })
-            }
-            InvocationResult::Return(Some(blk)) => {
+        if !result.exit_code.is_success() {
+            return Ok(SendResult::Abort(result.exit_code));
+        }
+        Ok(match result.return_data {
+            None => SendResult::Return(NO_DATA_BLOCK_ID, BlockStat { codec: 0, size: 0 }),
+            Some(blk) => {
                 let stat = blk.stat();
-                let ret_id = self
-                    .blocks
-                    .put(blk)
-                    .or_fatal()
-                    .context("failed to store a valid return value")?;
+                self.call_manager.charge_gas(
+                    self.call_manager
+                        .price_list()
+                        .on_send_return(stat.size as usize),
+                )?;
+                // This can only fail if the block registry's byte budget has been exhausted.
+                let ret_id = self.blocks.put(blk)?;
                 SendResult::Return(ret_id, stat)
             }
-            InvocationResult::Failure(code) => SendResult::Abort(code),
         })
     }
 }
@@ -464,7 +589,14 @@ where
         signer: &Address,
         plaintext: &[u8],
     ) -> Result<bool> {
-        self.call_manager
+        if sig_type == SignatureType::Delegated {
+            return Err(syscall_error!(IllegalArgument;
+                "delegated signatures can't be verified by the kernel; the receiving actor must authenticate them")
+            .into());
+        }
+
+        let t = self
+            .call_manager
             .charge_gas(self.call_manager.price_list().on_verify_signature(sig_type))?;
 
         // Resolve to key address before verifying signature.
@@ -472,9 +604,41 @@ where
 
         // Verify signature, catching errors. Signature verification can include some complicated
         // math.
-        catch_and_log_panic("verifying signature", || {
+        t.stop_with(catch_and_log_panic("verifying signature", || {
             Ok(signature::verify(sig_type, signature, plaintext, &signing_addr).is_ok())
-        })
+        }))
+    }
+
+    fn verify_bls_aggregate(
+        &mut self,
+        aggregate_sig: &[u8; BLS_SIG_LEN],
+        pub_keys: &[[u8; BLS_PUB_LEN]],
+        plaintexts: &[&[u8]],
+    ) -> Result<bool> {
+        if pub_keys.len() != plaintexts.len() {
+            return Err(syscall_error!(IllegalArgument;
+                "unequal numbers of bls public keys ({}) and plaintexts ({})",
+                pub_keys.len(), plaintexts.len())
+            .into());
+        }
+
+        let t = self.call_manager.charge_gas(
+            self.call_manager
+                .price_list()
+                .on_verify_bls_aggregate(pub_keys.len()),
+        )?;
+
+        let pub_keys: Vec<_> = pub_keys.iter().collect();
+        t.stop_with(catch_and_log_panic(
+            "verifying bls aggregate signature",
+            || {
+                Ok(signature::verify_bls_aggregate(
+                    plaintexts,
+                    &pub_keys,
+                    aggregate_sig,
+                ))
+            },
+        ))
     }
 
     fn recover_secp_public_key(
@@ -482,20 +646,20 @@ where
         hash: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
         signature: &[u8; SECP_SIG_LEN],
     ) -> Result<[u8; SECP_PUB_LEN]> {
-        self.call_manager
+        let t = self
+            .call_manager
             .charge_gas(self.call_manager.price_list().on_recover_secp_public_key())?;
 
-        signature::ops::recover_secp_public_key(hash, signature)
-            .map(|pubkey| pubkey.serialize())
-            .map_err(|e| {
-                syscall_error!(IllegalArgument; "public key recovery failed: {}", e).into()
-            })
+        t.stop_with(
+            signature::ops::recover_secp_public_key(hash, signature)
+                .map(|pubkey| pubkey.serialize())
+                .map_err(|e| {
+                    syscall_error!(IllegalArgument; "public key recovery failed: {}", e).into()
+                }),
+        )
     }
 
     fn hash(&mut self, code: u64, data: &[u8]) -> Result<MultihashGeneric<64>> {
-        self.call_manager
-            .charge_gas(self.call_manager.price_list().on_hashing(data.len()))?;
-
         let hasher = SupportedHashes::try_from(code).map_err(|e| {
             if let multihash::Error::UnsupportedCode(code) = e {
                 syscall_error!(IllegalArgument; "unsupported hash code {}", code)
@@ -503,7 +667,14 @@ where
                 syscall_error!(AssertionFailed; "hash expected unsupported code, got {}", e)
             }
         })?;
-        Ok(hasher.digest(data))
+
+        let t = self.call_manager.charge_gas(
+            self.call_manager
+                .price_list()
+                .on_hashing(hasher, data.len()),
+        )?;
+
+        t.stop_with(Ok(hasher.digest(data)))
     }
 
     fn compute_unsealed_sector_cid(
@@ -511,15 +682,15 @@ where
         proof_type: RegisteredSealProof,
         pieces: &[PieceInfo],
     ) -> Result<Cid> {
-        self.call_manager.charge_gas(
+        let t = self.call_manager.charge_gas(
             self.call_manager
                 .price_list()
                 .on_compute_unsealed_sector_cid(proof_type, pieces),
         )?;
 
-        catch_and_log_panic("computing unsealed sector CID", || {
+        t.stop_with(catch_and_log_panic("computing unsealed sector CID", || {
             compute_unsealed_sector_cid(proof_type, pieces)
-        })
+        }))
     }
 
     /// Verify seal proof for sectors. This proof verifies that a sector was sealed by the miner.
@@ -551,12 +722,19 @@ where
 
         // This syscall cannot be resolved inside the FVM, so we need to traverse
         // the node boundary through an extern.
-        let (fault, gas) = self
+        let (fault, gas) = match self
             .call_manager
             .externs()
             .verify_consensus_fault(h1, h2, extra)
-            .or_illegal_argument()?;
+        {
+            Ok(res) => res,
+            // A fault we can't verify isn't a fault.
+            Err(ExternError::NotAvailable(_)) => (None, 0),
+            Err(e) => return Err(extern_error("failed to verify consensus fault", e)),
+        };
 
+        self.charge_extern_gas("verify_consensus_fault", gas)?;
+        // Before that, nv15 charged for the state the extern accessed, and nv16 didn't.
         if self.network_version() <= NetworkVersion::V15 {
             self.call_manager.charge_gas(GasCharge::new(
                 "verify_consensus_fault_accesses",
@@ -694,6 +872,19 @@ where
 
         Ok(None)
     }
+
+    fn chain_id(&self) -> ChainID {
+        self.call_manager.context().chain_id
+    }
+}
+
+/// Clamps the height randomness is looked up at for negative epochs, including
+/// [`EPOCH_UNDEFINED`], to genesis (epoch 0) so that they're never passed straight to the externs.
+/// Like Lotus, the caller's original epoch is still mixed into the drawn randomness.
+///
+/// [`EPOCH_UNDEFINED`]: fvm_shared::clock::EPOCH_UNDEFINED
+fn clamp_rand_epoch(rand_epoch: ChainEpoch) -> ChainEpoch {
+    rand_epoch.max(ChainEpoch::new(0))
 }
 
 impl<C> RandomnessOps for DefaultKernel<C>
@@ -705,19 +896,21 @@ where
         personalization: i64,
         rand_epoch: ChainEpoch,
         entropy: &[u8],
-    ) -> Result<[u8; RANDOMNESS_LENGTH]> {
+    ) -> Result<Randomness> {
         self.call_manager.charge_gas(
             self.call_manager
                 .price_list()
                 .on_get_randomness(entropy.len()),
         )?;
 
-        // TODO(M2): Check error code
-        // Specifically, lookback length?
-        self.call_manager
+        let (base, gas) = self
+            .call_manager
             .externs()
-            .get_chain_randomness(personalization, rand_epoch, entropy)
-            .or_illegal_argument()
+            .get_chain_randomness(clamp_rand_epoch(rand_epoch))
+            .map_err(|e| extern_error("failed to get chain randomness", e))?;
+        self.charge_extern_gas("get_chain_randomness", gas)?;
+
+        Ok(draw_randomness(&base, personalization, rand_epoch, entropy))
     }
 
     fn get_randomness_from_beacon(
@@ -725,19 +918,21 @@ where
         personalization: i64,
         rand_epoch: ChainEpoch,
         entropy: &[u8],
-    ) -> Result<[u8; RANDOMNESS_LENGTH]> {
+    ) -> Result<Randomness> {
         self.call_manager.charge_gas(
             self.call_manager
                 .price_list()
                 .on_get_randomness(entropy.len()),
         )?;
 
-        // TODO(M2): Check error code
-        // Specifically, lookback length?
-        self.call_manager
+        let (base, gas) = self
+            .call_manager
             .externs()
-            .get_beacon_randomness(personalization, rand_epoch, entropy)
-            .or_illegal_argument()
+            .get_beacon_randomness(clamp_rand_epoch(rand_epoch))
+            .map_err(|e| extern_error("failed to get beacon randomness", e))?;
+        self.charge_extern_gas("get_beacon_randomness", gas)?;
+
+        Ok(draw_randomness(&base, personalization, rand_epoch, entropy))
     }
 }
 
@@ -745,17 +940,19 @@ impl<C> ActorOps for DefaultKernel<C>
 where
     C: CallManager,
 {
-    fn resolve_address(&self, address: &Address) -> Result<Option<ActorID>> {
-        self.call_manager.state_tree().lookup_id(address)
+    fn resolve_address(&mut self, address: &Address) -> Result<Option<ActorID>> {
+        self.call_manager
+            .with_metered_state_tree(|st| st.lookup_id(address))
     }
 
-    fn get_actor_code_cid(&self, id: ActorID) -> Result<Option<Cid>> {
+    fn get_actor_code_cid(&mut self, id: ActorID) -> Result<Option<Cid>> {
         Ok(self
             .call_manager
-            .state_tree()
-            .get_actor_id(id)
-            .context("failed to lookup actor to get code CID")
-            .or_fatal()?
+            .with_metered_state_tree(|st| {
+                st.get_actor_id(id)
+                    .context("failed to lookup actor to get code CID")
+                    .or_fatal()
+            })?
             .map(|act| act.code))
     }
 
@@ -802,6 +999,7 @@ where
 
         self.call_manager
             .charge_gas(self.call_manager.price_list().on_create_actor())?;
+        self.call_manager.count_actor_creation(actor_id)?;
 
         let state_tree = self.call_manager.state_tree_mut();
         state_tree.set_actor_id(
@@ -829,6 +1027,9 @@ where
 
     #[cfg(feature = "m2-native")]
     fn install_actor(&mut self, code_id: Cid) -> Result<()> {
+        if self.actor_id != INIT_ACTOR_ID {
+            self.check_upgrade_privilege()?;
+        }
         // TODO figure out gas
         self.call_manager
             .machine()
@@ -837,11 +1038,41 @@ where
             .map_err(|_| syscall_error!(IllegalArgument; "failed to load actor code").into())
     }
 
-    fn balance_of(&self, actor_id: ActorID) -> Result<TokenAmount> {
-        let balance = self
+    fn upgrade_actor(&mut self, actor_id: ActorID, new_code_cid: Cid) -> Result<()> {
+        self.check_upgrade_privilege()?;
+
+        self.call_manager
+            .charge_gas(self.call_manager.price_list().on_actor_lookup())?;
+        if self
             .call_manager
             .state_tree()
-            .get_actor_id(actor_id)
+            .get_actor_id(actor_id)?
+            .is_none()
+        {
+            return Err(syscall_error!(NotFound; "actor {} not found", actor_id).into());
+        }
+
+        // Load the new code up-front so a missing or invalid module fails the upgrade instead of
+        // the actor's next invocation. Compiled modules are cached by code CID, so the actor's old
+        // module doesn't need to be evicted (other actors may still be running that code).
+        self.call_manager
+            .machine()
+            .engine()
+            .prepare_actor_code(&new_code_cid, self.call_manager.blockstore())
+            .map_err(|e| syscall_error!(IllegalArgument; "failed to load actor code: {}", e))?;
+
+        self.call_manager
+            .charge_gas(self.call_manager.price_list().on_actor_update())?;
+        self.call_manager
+            .state_tree_mut()
+            .set_actor_code(actor_id, new_code_cid)?;
+        Ok(())
+    }
+
+    fn balance_of(&mut self, actor_id: ActorID) -> Result<TokenAmount> {
+        let balance = self
+            .call_manager
+            .with_metered_state_tree(|st| st.get_actor_id(actor_id))
             .context("cannot find actor")?
             .map(|a| a.balance)
             .unwrap_or_default();
@@ -911,6 +1142,21 @@ where
     }
 }
 
+/// Classifies an extern error: data that isn't available is reported to the calling actor, while
+/// anything else aborts the message. Extern failures must never become actor-visible, as nodes
+/// that didn't hit the same failure would execute the message differently.
+fn extern_error(context: &str, e: ExternError) -> ExecutionError {
+    match e {
+        ExternError::NotAvailable(msg) => {
+            syscall_error!(IllegalArgument; "{}: {}", context, msg).into()
+        }
+        // Keep the `ExternError` so the executor can tell this apart from other fatal errors.
+        e @ ExternError::Fatal(_) => {
+            ExecutionError::Fatal(anyhow::Error::new(e).context(context.to_owned()))
+        }
+    }
+}
+
 fn catch_and_log_panic<F: FnOnce() -> Result<R> + UnwindSafe, R>(context: &str, f: F) -> Result<R> {
     match panic::catch_unwind(f) {
         Ok(v) => v,
@@ -928,6 +1174,7 @@ enum ProofType {
     Window,
 }
 
+#[cfg_attr(feature = "mock-proofs", allow(dead_code))]
 fn prover_id_from_u64(id: u64) -> ProverId {
     let mut prover_id = ProverId::default();
     let prover_bytes = Address::new_id(id).payload().to_raw_bytes();
@@ -979,6 +1226,7 @@ fn to_fil_public_replica_infos(
     Ok(replicas)
 }
 
+#[cfg(not(feature = "mock-proofs"))]
 fn verify_seal(vi: &SealVerifyInfo) -> Result<bool> {
     let commr = commcid::cid_to_replica_commitment_v1(&vi.sealed_cid).or_illegal_argument()?;
     let commd = commcid::cid_to_data_commitment_v1(&vi.unsealed_cid).or_illegal_argument()?;
@@ -993,8 +1241,8 @@ fn verify_seal(vi: &SealVerifyInfo) -> Result<bool> {
         commd,
         prover_id,
         SectorId::from(vi.sector_id.number),
-        bytes_32(&vi.randomness.0),
-        bytes_32(&vi.interactive_randomness.0),
+        vi.randomness.0,
+        vi.interactive_randomness.0,
         &vi.proof,
     )
     .or_illegal_argument()
@@ -1005,6 +1253,7 @@ fn verify_seal(vi: &SealVerifyInfo) -> Result<bool> {
     .context("failed to verify seal proof")
 }
 
+#[cfg(not(feature = "mock-proofs"))]
 fn verify_post(verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
     let WindowPoStVerifyInfo {
         ref proofs,
@@ -1013,7 +1262,7 @@ fn verify_post(verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
         ..
     } = verify_info;
 
-    let Randomness(mut randomness) = verify_info.randomness.clone();
+    let Randomness(mut randomness) = verify_info.randomness;
 
     // Necessary to be valid bls12 381 element.
     randomness[31] &= 0x3f;
@@ -1032,10 +1281,11 @@ fn verify_post(verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
     let prover_id = prover_id_from_u64(*prover);
 
     // Verify Proof
-    proofs::post::verify_window_post(&bytes_32(&randomness), &proofs, &replicas, prover_id)
+    proofs::post::verify_window_post(&randomness, &proofs, &replicas, prover_id)
         .or_illegal_argument()
 }
 
+#[cfg(not(feature = "mock-proofs"))]
 fn verify_aggregate_seals(aggregate: &AggregateSealVerifyProofAndInfos) -> Result<bool> {
     if aggregate.infos.is_empty() {
         return Err(syscall_error!(IllegalArgument; "no seal verify infos").into());
@@ -1060,8 +1310,8 @@ fn verify_aggregate_seals(aggregate: &AggregateSealVerifyProofAndInfos) -> Resul
             Ok(AggregationInputs {
                 commr,
                 commd,
-                ticket: bytes_32(&info.randomness.0),
-                seed: bytes_32(&info.interactive_randomness.0),
+                ticket: info.randomness.0,
+                seed: info.interactive_randomness.0,
                 sector_id: SectorId::from(info.sector_number),
             })
         })
@@ -1101,6 +1351,7 @@ fn verify_aggregate_seals(aggregate: &AggregateSealVerifyProofAndInfos) -> Resul
     .or_illegal_argument()
 }
 
+#[cfg(not(feature = "mock-proofs"))]
 fn verify_replica_update(replica: &ReplicaUpdateInfo) -> Result<bool> {
     let up: proofs::RegisteredUpdateProof =
         replica.update_proof_type.try_into().or_illegal_argument()?;
@@ -1122,6 +1373,63 @@ fn verify_replica_update(replica: &ReplicaUpdateInfo) -> Result<bool> {
     .or_illegal_argument()
 }
 
+/// Checks the structure of a seal proof (the proof type and commitments), without verifying the
+/// proof itself. A structurally valid seal verifies iff its proof is non-empty.
+#[cfg(feature = "mock-proofs")]
+fn verify_seal(vi: &SealVerifyInfo) -> Result<bool> {
+    let _: proofs::RegisteredSealProof = vi.registered_proof.try_into().or_illegal_argument()?;
+    commcid::cid_to_replica_commitment_v1(&vi.sealed_cid).or_illegal_argument()?;
+    commcid::cid_to_data_commitment_v1(&vi.unsealed_cid).or_illegal_argument()?;
+    Ok(!vi.proof.is_empty())
+}
+
+/// Checks the structure of a window PoSt (the proof types and challenged sectors), without
+/// verifying the proofs themselves. A structurally valid PoSt verifies iff none of its proofs are
+/// empty.
+#[cfg(feature = "mock-proofs")]
+fn verify_post(verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
+    if verify_info.proofs.is_empty() {
+        return Err(syscall_error!(IllegalArgument; "no PoSt proofs").into());
+    }
+    for proof in &verify_info.proofs {
+        let _: proofs::RegisteredPoStProof = proof.post_proof.try_into().or_illegal_argument()?;
+    }
+    to_fil_public_replica_infos(&verify_info.challenged_sectors, ProofType::Window)?;
+    Ok(verify_info
+        .proofs
+        .iter()
+        .all(|proof| !proof.proof_bytes.is_empty()))
+}
+
+/// Checks the structure of an aggregate seal proof (the proof types and commitments), without
+/// verifying the proof itself. A structurally valid aggregate verifies iff its proof is non-empty.
+#[cfg(feature = "mock-proofs")]
+fn verify_aggregate_seals(aggregate: &AggregateSealVerifyProofAndInfos) -> Result<bool> {
+    if aggregate.infos.is_empty() {
+        return Err(syscall_error!(IllegalArgument; "no seal verify infos").into());
+    }
+    let _: proofs::RegisteredSealProof = aggregate.seal_proof.try_into().or_illegal_argument()?;
+    let _: proofs::RegisteredAggregationProof =
+        aggregate.aggregate_proof.try_into().or_illegal_argument()?;
+    for info in &aggregate.infos {
+        commcid::cid_to_replica_commitment_v1(&info.sealed_cid).or_illegal_argument()?;
+        commcid::cid_to_data_commitment_v1(&info.unsealed_cid).or_illegal_argument()?;
+    }
+    Ok(!aggregate.proof.is_empty())
+}
+
+/// Checks the structure of a replica update (the proof type and commitments), without verifying
+/// the proof itself. A structurally valid update verifies iff its proof is non-empty.
+#[cfg(feature = "mock-proofs")]
+fn verify_replica_update(replica: &ReplicaUpdateInfo) -> Result<bool> {
+    let _: proofs::RegisteredUpdateProof =
+        replica.update_proof_type.try_into().or_illegal_argument()?;
+    commcid::cid_to_replica_commitment_v1(&replica.old_sealed_cid).or_illegal_argument()?;
+    commcid::cid_to_replica_commitment_v1(&replica.new_sealed_cid).or_illegal_argument()?;
+    commcid::cid_to_data_commitment_v1(&replica.new_unsealed_cid).or_illegal_argument()?;
+    Ok(!replica.proof.is_empty())
+}
+
 fn compute_unsealed_sector_cid(
     proof_type: RegisteredSealProof,
     pieces: &[PieceInfo],
@@ -1148,7 +1456,7 @@ fn compute_unsealed_sector_cid(
                     commitment: zero_piece_commitment(p),
                 });
 
-                sum.0 += p.0;
+                *sum += p;
             }
         };
         for p in pieces {
@@ -1156,7 +1464,7 @@ fn compute_unsealed_sector_cid(
             pad_to(ps, &mut all_pieces, &mut sum);
 
             all_pieces.push(proofs::PieceInfo::try_from(p).or_illegal_argument()?);
-            sum.0 += p.size.0;
+            sum += p.size;
         }
 
         let (ps, _) = get_required_padding(sum, pssize);
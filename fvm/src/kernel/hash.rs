@@ -1,7 +1,7 @@
 use multihash::derive::Multihash;
 use multihash::{Blake2b256, Blake2b512, Keccak256, Ripemd160, Sha2_256};
 
-#[derive(Clone, Copy, Debug, Eq, Multihash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Multihash, PartialEq)]
 #[mh(alloc_size = 64)]
 /// Codes and hashers supported by FVM.
 /// You _can_ use this hash directly inside of your actor,
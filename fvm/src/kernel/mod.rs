@@ -1,15 +1,16 @@
-pub use blocks::{Block, BlockId, BlockRegistry, BlockStat};
+pub use blocks::{Block, BlockBudget, BlockId, BlockRegistry, BlockStat};
 use cid::Cid;
 use fvm_shared::address::Address;
+use fvm_shared::chainid::ChainID;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::consensus::ConsensusFault;
 use fvm_shared::crypto::signature::{
-    SignatureType, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
+    SignatureType, BLS_PUB_LEN, BLS_SIG_LEN, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
 };
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ExitCode;
 use fvm_shared::piece::PieceInfo;
-use fvm_shared::randomness::{Randomness, RANDOMNESS_LENGTH};
+use fvm_shared::randomness::Randomness;
 use fvm_shared::sector::{
     AggregateSealVerifyProofAndInfos, RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo,
     WindowPoStVerifyInfo,
@@ -25,11 +26,14 @@ pub mod default;
 mod error;
 
 pub use error::{ClassifyResult, Context, ExecutionError, Result, SyscallError};
+pub use hash::SupportedHashes;
 use multihash::MultihashGeneric;
+use wasmtime::Linker;
 
 use crate::call_manager::CallManager;
 use crate::gas::{Gas, PriceList};
 use crate::machine::Machine;
+use crate::syscalls::InvocationData;
 
 pub enum SendResult {
     Return(BlockId, BlockStat),
@@ -65,6 +69,13 @@ pub trait Kernel:
     where
         Self: Sized;
 
+    /// Commit the state-root set by the actor (if any) to the state tree, and delete the actor if
+    /// it self-destructed. This is called by the [`CallManager`] once the invocation returns
+    /// successfully (the state-root alone is also committed before the actor sends a message). If
+    /// the invocation aborts, the state tree reverts any committed roots and the actor isn't
+    /// deleted.
+    fn commit_root(&mut self) -> Result<()>;
+
     /// Construct a new [`Kernel`] from the given [`CallManager`].
     ///
     /// - `caller` is the ID of the _immediate_ caller.
@@ -86,6 +97,18 @@ pub trait Kernel:
 
     /// The kernel's underlying "machine".
     fn machine(&self) -> &<Self::CallManager as CallManager>::Machine;
+
+    /// Bind the syscalls this kernel exposes in addition to the standard ones. Actors may only
+    /// import them if they're listed in
+    /// [`WasmLimits::extra_syscall_imports`](crate::machine::WasmLimits::extra_syscall_imports).
+    ///
+    /// By default, no extra syscalls are bound.
+    fn bind_extra_syscalls(_linker: &mut Linker<InvocationData<Self>>) -> anyhow::Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
 }
 
 /// Network-related operations.
@@ -104,6 +127,9 @@ pub trait NetworkOps {
 
     /// epoch tipset cid
     fn tipset_cid(&self, epoch: i64) -> Result<Option<Cid>>;
+
+    /// The network's chain ID (constant).
+    fn chain_id(&self) -> ChainID;
 }
 
 /// Accessors to query attributes of the incoming message.
@@ -166,19 +192,26 @@ pub trait IpldBlockOps {
 /// Depends on BlockOps to read and write blocks in the state tree.
 pub trait SelfOps: IpldBlockOps {
     /// Get the state root.
+    ///
+    /// This method will fail with `IllegalOperation` if the actor has self-destructed.
     fn root(&self) -> Result<Cid>;
 
     /// Update the state-root.
     ///
-    /// This method will fail if the new state-root isn't reachable.
+    /// This method will fail if the new state-root isn't reachable, or with `IllegalOperation` if
+    /// the actor has self-destructed.
     fn set_root(&mut self, root: Cid) -> Result<()>;
 
-    /// The balance of the receiver.
+    /// The balance of the receiver, or zero if it has been deleted. This doesn't charge gas.
     fn current_balance(&self) -> Result<TokenAmount>;
 
     /// Deletes the executing actor from the state tree, transferring any balance to beneficiary.
     /// Aborts if the beneficiary does not exist.
     /// May only be called by the actor itself.
+    ///
+    /// The balance is transferred immediately, but the actor is only deleted once the invocation
+    /// returns successfully (see [`Kernel::commit_root`]). Until then, it can still send messages
+    /// (from its now-zero balance) and read the blocks it has open, but not access its state.
     fn self_destruct(&mut self, beneficiary: &Address) -> Result<()>;
 }
 
@@ -188,10 +221,10 @@ pub trait ActorOps {
     /// Resolves an address of any protocol to an ID address (via the Init actor's table).
     /// This allows resolution of externally-provided SECP, BLS, or actor addresses to the canonical form.
     /// If the argument is an ID address it is returned directly.
-    fn resolve_address(&self, address: &Address) -> Result<Option<ActorID>>;
+    fn resolve_address(&mut self, address: &Address) -> Result<Option<ActorID>>;
 
     /// Look up the code CID of an actor.
-    fn get_actor_code_cid(&self, id: ActorID) -> Result<Option<Cid>>;
+    fn get_actor_code_cid(&mut self, id: ActorID) -> Result<Option<Cid>>;
 
     /// Computes an address for a new actor. The returned address is intended to uniquely refer to
     /// the actor even in the event of a chain re-org (whereas an ID-address might refer to a
@@ -204,17 +237,24 @@ pub trait ActorOps {
     fn create_actor(&mut self, code_cid: Cid, actor_id: ActorID) -> Result<()>;
 
     /// Installs actor code pointed by cid
+    /// May only be called by the Init actor, the System actor, or the network's upgrade admin.
     #[cfg(feature = "m2-native")]
     fn install_actor(&mut self, code_cid: Cid) -> Result<()>;
 
+    /// Replaces the code of actor `actor_id` with `new_code_cid`, preserving its balance, sequence,
+    /// and state root. The new code is loaded (and validated) before the actor is changed.
+    /// May only be called by the System actor or the network's upgrade admin.
+    fn upgrade_actor(&mut self, actor_id: ActorID, new_code_cid: Cid) -> Result<()>;
+
     /// Returns the actor's "type" (if builitin) or 0 (if not).
     fn get_builtin_actor_type(&self, code_cid: &Cid) -> u32;
 
     /// Returns the CodeCID for the supplied built-in actor type.
     fn get_code_cid_for_type(&self, typ: u32) -> Result<Cid>;
 
-    /// Returns the balance associated with an actor id
-    fn balance_of(&self, actor_id: ActorID) -> Result<TokenAmount>;
+    /// Returns the balance of the specified actor, or zero if the actor doesn't exist (matching
+    /// [`SelfOps::current_balance`] for deleted actors). Charges for an actor lookup.
+    fn balance_of(&mut self, actor_id: ActorID) -> Result<TokenAmount>;
 }
 
 /// Operations to send messages to other actors.
@@ -260,6 +300,9 @@ pub trait GasOps {
 /// Cryptographic primitives provided by the kernel.
 pub trait CryptoOps {
     /// Verifies that a signature is valid for an address and plaintext.
+    ///
+    /// Delegated signatures are rejected with `IllegalArgument`: they must be authenticated by the
+    /// actor that receives them.
     fn verify_signature(
         &mut self,
         sig_type: SignatureType,
@@ -268,6 +311,15 @@ pub trait CryptoOps {
         plaintext: &[u8],
     ) -> Result<bool>;
 
+    /// Verifies an aggregate BLS signature over `plaintexts`, where the i-th plaintext was signed
+    /// by the i-th public key.
+    fn verify_bls_aggregate(
+        &mut self,
+        aggregate_sig: &[u8; BLS_SIG_LEN],
+        pub_keys: &[[u8; BLS_PUB_LEN]],
+        plaintexts: &[&[u8]],
+    ) -> Result<bool>;
+
     /// Given a message hash and its signature, recovers the public key of the signer.
     fn recover_secp_public_key(
         &mut self,
@@ -334,22 +386,32 @@ pub trait RandomnessOps {
     /// Randomness returns a (pseudo)random byte array drawing from the latest
     /// ticket chain from a given epoch and incorporating requisite entropy.
     /// This randomness is fork dependant but also biasable because of this.
+    ///
+    /// For negative epochs (e.g., [`EPOCH_UNDEFINED`]), the randomness is looked up at epoch 0,
+    /// but drawn with the original epoch.
+    ///
+    /// [`EPOCH_UNDEFINED`]: fvm_shared::clock::EPOCH_UNDEFINED
     fn get_randomness_from_tickets(
         &mut self,
         personalization: i64,
         rand_epoch: ChainEpoch,
         entropy: &[u8],
-    ) -> Result<[u8; RANDOMNESS_LENGTH]>;
+    ) -> Result<Randomness>;
 
     /// Randomness returns a (pseudo)random byte array drawing from the latest
     /// beacon from a given epoch and incorporating requisite entropy.
     /// This randomness is not tied to any fork of the chain, and is unbiasable.
+    ///
+    /// For negative epochs (e.g., [`EPOCH_UNDEFINED`]), the randomness is looked up at epoch 0,
+    /// but drawn with the original epoch.
+    ///
+    /// [`EPOCH_UNDEFINED`]: fvm_shared::clock::EPOCH_UNDEFINED
     fn get_randomness_from_beacon(
         &mut self,
         personalization: i64,
         rand_epoch: ChainEpoch,
         entropy: &[u8],
-    ) -> Result<[u8; RANDOMNESS_LENGTH]>;
+    ) -> Result<Randomness>;
 }
 
 /// Debugging APIs.
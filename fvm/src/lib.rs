@@ -26,6 +26,8 @@ mod account_actor;
 #[cfg(not(feature = "testing"))]
 mod init_actor;
 #[cfg(not(feature = "testing"))]
+mod placeholder_actor;
+#[cfg(not(feature = "testing"))]
 mod system_actor;
 
 #[cfg(feature = "testing")]
@@ -33,12 +35,18 @@ pub mod account_actor;
 #[cfg(feature = "testing")]
 pub mod init_actor;
 #[cfg(feature = "testing")]
+pub mod placeholder_actor;
+#[cfg(feature = "testing")]
 pub mod system_actor;
 
 pub mod trace;
 
+#[cfg(feature = "json")]
+pub mod inspect;
+
 use cid::multihash::{Code, MultihashDigest};
 use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::{to_vec, DAG_CBOR};
 
 lazy_static::lazy_static! {
@@ -49,15 +57,24 @@ lazy_static::lazy_static! {
     };
 }
 
+/// Writes the empty array to the store, under [`EMPTY_ARR_CID`]. It's the state of the actors the
+/// FVM creates until their constructor runs.
+fn put_empty_arr(store: &impl Blockstore) -> anyhow::Result<()> {
+    let empty = to_vec::<[(); 0]>(&[])?;
+    store.put_keyed(&EMPTY_ARR_CID, &empty)
+}
+
 #[cfg(test)]
 mod test {
     use fvm_ipld_blockstore::MemoryBlockstore;
     use fvm_ipld_encoding::CborStore;
+    use fvm_shared::clock::ChainEpoch;
+    use fvm_shared::randomness::Randomness;
     use fvm_shared::state::StateTreeVersion;
     use multihash::Code;
 
     use crate::call_manager::DefaultCallManager;
-    use crate::externs::{Consensus, Externs, Rand};
+    use crate::externs::{Consensus, ExternError, Externs, Rand};
     use crate::machine::{DefaultMachine, Engine, Manifest, NetworkConfig};
     use crate::state_tree::StateTree;
     use crate::{executor, DefaultKernel};
@@ -69,22 +86,18 @@ mod test {
     impl Rand for DummyExterns {
         fn get_chain_randomness(
             &self,
-            _pers: i64,
-            _round: fvm_shared::clock::ChainEpoch,
-            _entropy: &[u8],
-        ) -> anyhow::Result<[u8; 32]> {
+            _round: ChainEpoch,
+        ) -> Result<(Randomness, i64), ExternError> {
             let msg = "mel was here".as_bytes();
-            let mut out = [0u8; 32];
-            out[..msg.len()].copy_from_slice(msg);
-            Ok(out)
+            let mut out = Randomness::default();
+            out.0[..msg.len()].copy_from_slice(msg);
+            Ok((out, 0))
         }
 
         fn get_beacon_randomness(
             &self,
-            _pers: i64,
-            _round: fvm_shared::clock::ChainEpoch,
-            _entropy: &[u8],
-        ) -> anyhow::Result<[u8; 32]> {
+            _round: ChainEpoch,
+        ) -> Result<(Randomness, i64), ExternError> {
             todo!()
         }
     }
@@ -95,7 +108,7 @@ mod test {
             _h1: &[u8],
             _h2: &[u8],
             _extra: &[u8],
-        ) -> anyhow::Result<(Option<fvm_shared::consensus::ConsensusFault>, i64)> {
+        ) -> Result<(Option<fvm_shared::consensus::ConsensusFault>, i64), ExternError> {
             // consensus is always valid for tests :)
             Ok((None, 0))
         }
@@ -116,9 +129,11 @@ mod test {
 
         let actors_cid = bs.put_cbor(&(1, manifest_cid), Code::Blake2b256).unwrap();
 
-        let mc = NetworkConfig::new(fvm_shared::version::NetworkVersion::V15)
+        let mut mc = NetworkConfig::new(fvm_shared::version::NetworkVersion::V15)
             .override_actors(actors_cid)
-            .for_epoch(0, root);
+            .for_epoch(ChainEpoch::new(0), root);
+        // The state tree is empty.
+        mc.require_singletons([]);
 
         let machine = DefaultMachine::new(
             &Engine::new_default((&mc.network).into()).unwrap(),
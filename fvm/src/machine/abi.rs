@@ -0,0 +1,52 @@
+use wasmtime::{ExternType, Module, ValType};
+
+/// The name of the global actors may export to declare the [`AbiVersion`] their code was compiled
+/// against. It must be an immutable `i32` constant. Actors that don't export it follow
+/// [`AbiVersion::V1`].
+pub const ABI_VERSION_EXPORT: &str = "abi_version";
+
+/// The calling convention of an actor's `invoke` export. All versions import the same syscalls,
+/// and take the ID of the parameters block (or [`NO_DATA_BLOCK_ID`]) as their only argument.
+///
+/// [`NO_DATA_BLOCK_ID`]: crate::call_manager::NO_DATA_BLOCK_ID
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AbiVersion {
+    /// `invoke(params: u32) -> u32`: returns the ID of the return block. Actors can only fail by
+    /// calling `vm::abort`.
+    V1 = 1,
+    /// `invoke(params: u32) -> u64`: returns the exit code in the high 32 bits, and the ID of the
+    /// return block in the low 32 bits. A non-zero exit code fails the call as if the actor had
+    /// aborted with it, and the return block is ignored.
+    V2 = 2,
+}
+
+impl AbiVersion {
+    /// Returns the ABI version with the given number, if it's supported.
+    pub fn new(version: i32) -> Option<AbiVersion> {
+        match version {
+            1 => Some(AbiVersion::V1),
+            2 => Some(AbiVersion::V2),
+            _ => None,
+        }
+    }
+
+    /// Returns the version number, as exported by actors.
+    pub fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Returns the ABI version of a loaded module. Validation ensures that the result type of the
+    /// `invoke` export matches the version declared by the module, so it's enough to look at the
+    /// former (compiled modules don't retain the value of their globals).
+    pub(crate) fn of_module(module: &Module) -> Option<AbiVersion> {
+        let invoke = match module.get_export("invoke")? {
+            ExternType::Func(ty) => ty,
+            _ => return None,
+        };
+        match invoke.results().collect::<Vec<_>>()[..] {
+            [ValType::I32] => Some(AbiVersion::V1),
+            [ValType::I64] => Some(AbiVersion::V2),
+            _ => None,
+        }
+    }
+}
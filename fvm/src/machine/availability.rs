@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::MethodNum;
+
+/// The network version from which each gated syscall and builtin-actor method is available.
+/// Anything not listed is available at every network version.
+///
+/// Calling a syscall before it's available fails with
+/// [`ErrorNumber::IllegalOperation`](fvm_shared::error::ErrorNumber::IllegalOperation) without
+/// reaching the kernel (the syscall gas is still charged). Invoking a builtin-actor method before
+/// it's available fails with
+/// [`ExitCode::USR_UNHANDLED_MESSAGE`](fvm_shared::error::ExitCode::USR_UNHANDLED_MESSAGE),
+/// without instantiating the actor.
+///
+/// This is consensus-critical: it must only differ from the [default][FeatureAvailability::default]
+/// on local test networks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureAvailability {
+    /// Module -> name -> since.
+    syscalls: BTreeMap<&'static str, BTreeMap<&'static str, NetworkVersion>>,
+    /// Actor name -> method -> since.
+    methods: BTreeMap<String, BTreeMap<MethodNum, NetworkVersion>>,
+}
+
+impl Default for FeatureAvailability {
+    /// The syscalls introduced after nv15.
+    fn default() -> Self {
+        let mut availability = FeatureAvailability::empty();
+        availability
+            .gate_syscall("network", "chain_id", NetworkVersion::V16)
+            .gate_syscall("crypto", "verify_bls_aggregate", NetworkVersion::V17)
+            .gate_syscall("actor", "upgrade_actor", NetworkVersion::V16);
+        availability
+    }
+}
+
+impl FeatureAvailability {
+    /// Makes every syscall and method available at every network version.
+    pub fn empty() -> Self {
+        FeatureAvailability {
+            syscalls: BTreeMap::new(),
+            methods: BTreeMap::new(),
+        }
+    }
+
+    /// Makes the syscall `module::name` available from network version `since`.
+    pub fn gate_syscall(
+        &mut self,
+        module: &'static str,
+        name: &'static str,
+        since: NetworkVersion,
+    ) -> &mut Self {
+        self.syscalls.entry(module).or_default().insert(name, since);
+        self
+    }
+
+    /// Makes the method `method` of the builtin actor named `actor` (e.g., `"multisig"`, see
+    /// [`Manifest::name_by_code`][super::Manifest::name_by_code]) available from network version
+    /// `since`.
+    pub fn gate_method(
+        &mut self,
+        actor: impl Into<String>,
+        method: MethodNum,
+        since: NetworkVersion,
+    ) -> &mut Self {
+        self.methods
+            .entry(actor.into())
+            .or_default()
+            .insert(method, since);
+        self
+    }
+
+    /// Returns the network version from which the syscall `module::name` is available, or `None`
+    /// if it isn't gated.
+    pub fn syscall_since(&self, module: &str, name: &str) -> Option<NetworkVersion> {
+        self.syscalls.get(module)?.get(name).copied()
+    }
+
+    /// Returns true if the syscall `module::name` is available at network version `nv`.
+    pub fn syscall_available(&self, module: &str, name: &str, nv: NetworkVersion) -> bool {
+        self.syscall_since(module, name)
+            .map_or(true, |since| nv >= since)
+    }
+
+    /// Returns the network version from which the method `method` of the builtin actor named
+    /// `actor` is available, or `None` if it isn't gated.
+    pub fn method_since(&self, actor: &str, method: MethodNum) -> Option<NetworkVersion> {
+        self.methods.get(actor)?.get(&method).copied()
+    }
+
+    /// Returns true if the method `method` of the builtin actor named `actor` is available at
+    /// network version `nv`.
+    pub fn method_available(&self, actor: &str, method: MethodNum, nv: NetworkVersion) -> bool {
+        self.method_since(actor, method)
+            .map_or(true, |since| nv >= since)
+    }
+
+    /// Iterates over the gated syscalls as `(module, name, since)`, in order.
+    pub fn syscalls(
+        &self,
+    ) -> impl Iterator<Item = (&'static str, &'static str, NetworkVersion)> + '_ {
+        self.syscalls.iter().flat_map(|(&module, names)| {
+            names
+                .iter()
+                .map(move |(&name, &since)| (module, name, since))
+        })
+    }
+
+    /// Iterates over the gated builtin-actor methods as `(actor, method, since)`, in order.
+    pub fn methods(&self) -> impl Iterator<Item = (&str, MethodNum, NetworkVersion)> + '_ {
+        self.methods.iter().flat_map(|(actor, methods)| {
+            methods
+                .iter()
+                .map(move |(&method, &since)| (actor.as_str(), method, since))
+        })
+    }
+
+    /// Iterates over the gated syscalls unavailable at network version `nv`, as `(module, name)`.
+    pub fn unavailable_syscalls(
+        &self,
+        nv: NetworkVersion,
+    ) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+        self.syscalls()
+            .filter(move |&(_, _, since)| nv < since)
+            .map(|(module, name, _)| (module, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_gates() {
+        let availability = FeatureAvailability::default();
+        assert_eq!(
+            availability.syscall_since("network", "chain_id"),
+            Some(NetworkVersion::V16)
+        );
+        assert!(!availability.syscall_available("network", "chain_id", NetworkVersion::V15));
+        assert!(availability.syscall_available("network", "chain_id", NetworkVersion::V16));
+
+        // Ungated syscalls are always available.
+        assert_eq!(availability.syscall_since("vm", "abort"), None);
+        assert!(availability.syscall_available("vm", "abort", NetworkVersion::V0));
+
+        assert_eq!(
+            availability
+                .unavailable_syscalls(NetworkVersion::V15)
+                .collect::<Vec<_>>(),
+            vec![
+                ("actor", "upgrade_actor"),
+                ("crypto", "verify_bls_aggregate"),
+                ("network", "chain_id"),
+            ]
+        );
+        assert_eq!(
+            availability
+                .unavailable_syscalls(NetworkVersion::V16)
+                .collect::<Vec<_>>(),
+            vec![("crypto", "verify_bls_aggregate")]
+        );
+        assert_eq!(
+            availability
+                .unavailable_syscalls(NetworkVersion::V17)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn method_gates() {
+        let mut availability = FeatureAvailability::empty();
+        availability
+            .gate_method("multisig", 3, NetworkVersion::V16)
+            .gate_method("multisig", 5, NetworkVersion::V17)
+            .gate_method("miner", 3, NetworkVersion::V17);
+
+        assert_eq!(
+            availability.method_since("multisig", 3),
+            Some(NetworkVersion::V16)
+        );
+        assert_eq!(
+            availability.method_since("multisig", 5),
+            Some(NetworkVersion::V17)
+        );
+        assert_eq!(availability.method_since("multisig", 4), None);
+        assert_eq!(availability.method_since("market", 3), None);
+
+        assert!(!availability.method_available("multisig", 5, NetworkVersion::V16));
+        assert!(availability.method_available("multisig", 5, NetworkVersion::V17));
+        assert!(availability.method_available("multisig", 4, NetworkVersion::V0));
+
+        assert_eq!(
+            availability.methods().collect::<Vec<_>>(),
+            vec![
+                ("miner", 3, NetworkVersion::V17),
+                ("multisig", 3, NetworkVersion::V16),
+                ("multisig", 5, NetworkVersion::V17),
+            ]
+        );
+    }
+}
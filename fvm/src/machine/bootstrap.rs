@@ -0,0 +1,406 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::Context as _;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::builtin::singletons::SYSTEM_ACTOR_ID;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, IDENTITY_HASH};
+
+use super::default::load_manifest;
+use super::{MachineContext, Manifest};
+use crate::state_tree::StateTree;
+use crate::system_actor::State as SystemActorState;
+
+/// An error constructing a machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MachineError {
+    /// The blockstore doesn't have (usable) state the machine needs to boot. Lists every missing
+    /// piece, so an embedder that forgot to load, e.g., the builtin-actors bundle, finds out all at
+    /// once.
+    Bootstrap(Vec<MissingPiece>),
+}
+
+impl fmt::Display for MachineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MachineError::Bootstrap(missing) => {
+                write!(
+                    f,
+                    "the blockstore is missing state the machine needs to boot"
+                )?;
+                for piece in missing {
+                    write!(f, "\n- {}", piece)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for MachineError {}
+
+/// A piece of state a machine needs to boot, missing from its blockstore or unusable. See
+/// [`MachineError::Bootstrap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MissingPiece {
+    /// The initial state root.
+    StateRoot(Cid),
+    /// The initial state root exists, but isn't a state tree of a supported version, or its actors
+    /// HAMT can't be loaded.
+    StateTree { root: Cid, reason: String },
+    /// A builtin-actors manifest: either one configured in the [`NetworkConfig`][super::NetworkConfig],
+    /// or the one referenced by the system actor.
+    Manifest { cid: Cid, reason: String },
+    /// The code of a builtin actor listed in a manifest.
+    ActorCode { name: String, code: Cid },
+    /// A singleton actor required by [`MachineContext::required_singletons`] (or the system actor,
+    /// when the manifest must be read from its state), absent from the state tree or unreadable.
+    Singleton { id: ActorID, reason: String },
+}
+
+impl fmt::Display for MissingPiece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MissingPiece::StateRoot(root) => write!(f, "state root {}", root),
+            MissingPiece::StateTree { root, reason } => {
+                write!(f, "state tree {}: {}", root, reason)
+            }
+            MissingPiece::Manifest { cid, reason } => {
+                write!(f, "builtin-actors manifest {}: {}", cid, reason)
+            }
+            MissingPiece::ActorCode { name, code } => {
+                write!(f, "code {} of builtin actor {}", code, name)
+            }
+            MissingPiece::Singleton { id, reason } => write!(f, "actor {}: {}", id, reason),
+        }
+    }
+}
+
+/// Loads the initial state tree and the builtin-actors manifests of a machine constructed with
+/// `context`, checking that `blockstore` has everything the machine needs to boot:
+///
+/// - The initial state root, which must be a state tree of a supported version.
+/// - The [required singletons][MachineContext::required_singletons] in that tree.
+/// - The builtin-actors manifests for the network version, and every other configured version.
+/// - The code of every builtin actor in those manifests. Identity-hashed code CIDs (used by test
+///   manifests) have no code to check.
+///
+/// The machine boots from the returned tree and manifests, so nothing is loaded twice. Fails with a
+/// [`MachineError::Bootstrap`] listing every missing piece, or with another error if the
+/// blockstore itself fails.
+pub(super) fn bootstrap<B: Blockstore>(
+    context: &MachineContext,
+    blockstore: B,
+) -> anyhow::Result<(StateTree<B>, BTreeMap<NetworkVersion, Manifest>)> {
+    // Reported in this order: the state tree and singletons, the manifests, then the code.
+    let mut missing = Vec::new();
+    let mut missing_manifests = Vec::new();
+    let mut missing_code = Vec::new();
+
+    // Load the configured manifests (and check for their code) before handing the blockstore over
+    // to the state tree. The manifest for the network version, if not configured, is the override
+    // or the system actor's.
+    let mut configured: Vec<(NetworkVersion, Cid)> = context
+        .builtin_actors_by_version
+        .iter()
+        .map(|(&nv, &cid)| (nv, cid))
+        .collect();
+    let mut from_system_actor = false;
+    if !context
+        .builtin_actors_by_version
+        .contains_key(&context.network_version)
+    {
+        match context.builtin_actors_override {
+            Some(cid) => configured.push((context.network_version, cid)),
+            None => from_system_actor = true,
+        }
+    }
+    let mut manifests = BTreeMap::new();
+    for (nv, cid) in configured {
+        match load_manifest(&blockstore, &cid) {
+            Ok(manifest) => {
+                manifests.insert(nv, manifest);
+            }
+            Err(e) => missing_manifests.push(MissingPiece::Manifest {
+                cid,
+                reason: format!("{:#}", e),
+            }),
+        }
+    }
+    check_actor_code(&blockstore, manifests.values(), &mut missing_code)?;
+
+    let root = context.initial_state_root;
+    let state_tree = if !blockstore
+        .has(&root)
+        .context("failed to load initial state-root")?
+    {
+        missing.push(MissingPiece::StateRoot(root));
+        None
+    } else {
+        match StateTree::new_from_root(blockstore, &root) {
+            Ok(tree) => Some(tree),
+            Err(e) => {
+                missing.push(MissingPiece::StateTree {
+                    root,
+                    reason: format!("{:#}", anyhow::Error::from(e)),
+                });
+                None
+            }
+        }
+    };
+
+    // Without a state tree (already reported), there are no singletons to check, and no system
+    // actor to find the manifest in.
+    if let Some(tree) = &state_tree {
+        for &id in &context.required_singletons {
+            let reason = match tree.get_actor_id(id) {
+                Ok(Some(_)) => continue,
+                Ok(None) => "not in the state tree".to_owned(),
+                Err(e) => format!("{:#}", anyhow::Error::from(e)),
+            };
+            missing.push(MissingPiece::Singleton { id, reason });
+        }
+
+        if from_system_actor {
+            match SystemActorState::load(tree) {
+                Ok((state, _)) => match Manifest::load(tree.store(), &state.builtin_actors, 1) {
+                    Ok(manifest) => {
+                        check_actor_code(tree.store(), [&manifest], &mut missing_code)?;
+                        manifests.insert(context.network_version, manifest);
+                    }
+                    Err(e) => missing_manifests.push(MissingPiece::Manifest {
+                        cid: state.builtin_actors,
+                        reason: format!("{:#}", e),
+                    }),
+                },
+                // Don't report the system actor twice.
+                Err(_) if context.required_singletons.contains(&SYSTEM_ACTOR_ID) => {}
+                Err(e) => missing_manifests.push(MissingPiece::Singleton {
+                    id: SYSTEM_ACTOR_ID,
+                    reason: format!("{:#}", anyhow::Error::from(e)),
+                }),
+            }
+        }
+    }
+
+    missing.extend(missing_manifests);
+    missing.extend(missing_code);
+    match state_tree {
+        Some(tree) if missing.is_empty() => Ok((tree, manifests)),
+        _ => Err(MachineError::Bootstrap(missing).into()),
+    }
+}
+
+/// Checks that `blockstore` has the code of every builtin actor in `manifests`, adding any that's
+/// missing to `missing` (unless it's already there).
+fn check_actor_code<'a>(
+    blockstore: &impl Blockstore,
+    manifests: impl IntoIterator<Item = &'a Manifest>,
+    missing: &mut Vec<MissingPiece>,
+) -> anyhow::Result<()> {
+    let mut codes: Vec<(&str, Cid)> = manifests
+        .into_iter()
+        .flat_map(|manifest| {
+            manifest
+                .builtin_actor_codes()
+                .map(move |code| (manifest.name_by_code(code).unwrap_or("unknown"), *code))
+        })
+        .filter(|(_, code)| code.hash().code() != IDENTITY_HASH)
+        .collect();
+    codes.sort();
+    codes.dedup();
+    let cids: Vec<Cid> = codes.iter().map(|(_, code)| *code).collect();
+    let present = blockstore
+        .has_many(&cids)
+        .context("failed to check for the builtin actors' code")?;
+    for ((name, code), present) in codes.into_iter().zip(present) {
+        let piece = MissingPiece::ActorCode {
+            name: name.to_owned(),
+            code,
+        };
+        if !present && !missing.contains(&piece) {
+            missing.push(piece);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code, MultihashDigest};
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use fvm_shared::builtin::singletons::{INIT_ACTOR_ID, REWARD_ACTOR_ID};
+    use fvm_shared::clock::ChainEpoch;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::state::StateTreeVersion;
+    use fvm_shared::IPLD_RAW;
+    use num_traits::Zero;
+
+    use super::*;
+    use crate::machine::NetworkConfig;
+    use crate::state_tree::ActorState;
+    use crate::EMPTY_ARR_CID;
+
+    /// Builds a state tree with the given singletons, returning its root.
+    fn state_tree(bs: &MemoryBlockstore, singletons: &[ActorID]) -> Cid {
+        let mut tree = StateTree::new(bs, StateTreeVersion::V4).unwrap();
+        for &id in singletons {
+            let code = Manifest::DUMMY_CODES[0].1;
+            tree.set_actor_id(
+                id,
+                ActorState::new(code, *EMPTY_ARR_CID, TokenAmount::zero(), 0),
+            )
+            .unwrap();
+        }
+        tree.flush().unwrap()
+    }
+
+    /// Stores a manifest with the dummy codes and a "reward" actor with real code, returning the
+    /// CID of its `(version, manifest)` tuple and the reward actor's code.
+    fn manifest(bs: &MemoryBlockstore) -> (Cid, Cid) {
+        let reward_code = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"reward code"));
+        let mut codes = Manifest::DUMMY_CODES.to_vec();
+        codes.push(("reward", reward_code));
+        let manifest_cid = bs.put_cbor(&codes, Code::Blake2b256).unwrap();
+        let cid = bs.put_cbor(&(1, manifest_cid), Code::Blake2b256).unwrap();
+        (cid, reward_code)
+    }
+
+    fn check(bs: &MemoryBlockstore, manifest: Cid, root: Cid) -> Result<(), MachineError> {
+        let mc = NetworkConfig::new(NetworkVersion::V16)
+            .override_actors(manifest)
+            .for_epoch(ChainEpoch::new(0), root);
+        bootstrap(&mc, bs)
+            .map(|_| ())
+            .map_err(|e| e.downcast().unwrap())
+    }
+
+    const SINGLETONS: &[ActorID] = &[SYSTEM_ACTOR_ID, INIT_ACTOR_ID, REWARD_ACTOR_ID];
+
+    #[test]
+    fn complete() {
+        let bs = MemoryBlockstore::default();
+        let root = state_tree(&bs, SINGLETONS);
+        let (manifest, reward_code) = manifest(&bs);
+        bs.put_keyed(&reward_code, b"reward code").unwrap();
+        assert_eq!(check(&bs, manifest, root), Ok(()));
+
+        // The machine boots from the tree and manifests loaded along the way.
+        let mc = NetworkConfig::new(NetworkVersion::V16)
+            .override_actors(manifest)
+            .for_epoch(ChainEpoch::new(0), root);
+        let (mut tree, manifests) = bootstrap(&mc, &bs).unwrap();
+        assert_eq!(tree.flush().unwrap(), root);
+        assert_eq!(
+            manifests.keys().copied().collect::<Vec<_>>(),
+            vec![NetworkVersion::V16]
+        );
+    }
+
+    #[test]
+    fn missing_state_root() {
+        let bs = MemoryBlockstore::default();
+        let root = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"missing"));
+        let (manifest, reward_code) = manifest(&bs);
+        bs.put_keyed(&reward_code, b"reward code").unwrap();
+        assert_eq!(
+            check(&bs, manifest, root),
+            Err(MachineError::Bootstrap(vec![MissingPiece::StateRoot(root)]))
+        );
+    }
+
+    #[test]
+    fn invalid_state_tree() {
+        let bs = MemoryBlockstore::default();
+        let root = bs.put_cbor(&"not a state tree", Code::Blake2b256).unwrap();
+        let (manifest, reward_code) = manifest(&bs);
+        bs.put_keyed(&reward_code, b"reward code").unwrap();
+        match check(&bs, manifest, root) {
+            Err(MachineError::Bootstrap(missing)) => assert!(
+                matches!(&missing[..], [MissingPiece::StateTree { root: r, .. }] if *r == root),
+                "{:?}",
+                missing
+            ),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn missing_manifest() {
+        let bs = MemoryBlockstore::default();
+        let root = state_tree(&bs, SINGLETONS);
+        let manifest = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"missing"));
+        match check(&bs, manifest, root) {
+            Err(MachineError::Bootstrap(missing)) => assert!(
+                matches!(&missing[..], [MissingPiece::Manifest { cid, .. }] if *cid == manifest),
+                "{:?}",
+                missing
+            ),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn missing_actor_code() {
+        let bs = MemoryBlockstore::default();
+        let root = state_tree(&bs, SINGLETONS);
+        let (manifest, reward_code) = manifest(&bs);
+        // The dummy (identity) codes have nothing to check.
+        assert_eq!(
+            check(&bs, manifest, root),
+            Err(MachineError::Bootstrap(vec![MissingPiece::ActorCode {
+                name: "reward".to_owned(),
+                code: reward_code,
+            }]))
+        );
+    }
+
+    #[test]
+    fn missing_singletons() {
+        let bs = MemoryBlockstore::default();
+        let root = state_tree(&bs, &[INIT_ACTOR_ID]);
+        let (manifest, reward_code) = manifest(&bs);
+        bs.put_keyed(&reward_code, b"reward code").unwrap();
+        let singleton = |id| MissingPiece::Singleton {
+            id,
+            reason: "not in the state tree".to_owned(),
+        };
+        assert_eq!(
+            check(&bs, manifest, root),
+            Err(MachineError::Bootstrap(vec![
+                singleton(SYSTEM_ACTOR_ID),
+                singleton(REWARD_ACTOR_ID),
+            ]))
+        );
+
+        // Test trees may go without.
+        let mut mc = NetworkConfig::new(NetworkVersion::V16)
+            .override_actors(manifest)
+            .for_epoch(ChainEpoch::new(0), root);
+        mc.require_singletons([INIT_ACTOR_ID]);
+        bootstrap(&mc, &bs).unwrap();
+    }
+
+    #[test]
+    fn lists_every_missing_piece() {
+        let bs = MemoryBlockstore::default();
+        let root = state_tree(&bs, &[]);
+        let (manifest, reward_code) = manifest(&bs);
+        let err = check(&bs, manifest, root).unwrap_err();
+        let MachineError::Bootstrap(missing) = &err;
+        assert_eq!(missing.len(), 4, "{:?}", missing);
+        assert_eq!(
+            missing[3],
+            MissingPiece::ActorCode {
+                name: "reward".to_owned(),
+                code: reward_code,
+            }
+        );
+        assert!(err
+            .to_string()
+            .contains(&format!("\n- code {} of builtin actor reward", reward_code)));
+    }
+}
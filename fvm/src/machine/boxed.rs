@@ -1,9 +1,8 @@
-use cid::Cid;
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::ActorID;
 
-use super::{Engine, Machine, MachineContext, Manifest};
+use super::{Engine, FlushStats, Machine, MachineContext, Manifest};
 use crate::kernel::Result;
 use crate::state_tree::{ActorState, StateTree};
 
@@ -64,7 +63,7 @@ impl<M: Machine> Machine for Box<M> {
     }
 
     #[inline(always)]
-    fn flush(&mut self) -> Result<Cid> {
+    fn flush(&mut self) -> Result<FlushStats> {
         (**self).flush()
     }
 
@@ -1,18 +1,23 @@
+use std::collections::BTreeMap;
 use std::ops::RangeInclusive;
+use std::time::Instant;
 
 use anyhow::{anyhow, Context as _};
 use cid::Cid;
-use fvm_ipld_blockstore::{Blockstore, Buffered};
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::CborStore;
 use fvm_shared::address::Address;
+use fvm_shared::chainid::ChainID;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ErrorNumber;
 use fvm_shared::version::NetworkVersion;
 use fvm_shared::ActorID;
 use log::debug;
 
-use super::{Engine, Machine, MachineContext};
+use super::bootstrap::bootstrap;
+use super::{check_transfer_value, Engine, FlushStats, Machine, MachineContext};
 use crate::blockstore::BufferedBlockstore;
+use crate::executor::SenderPolicy;
 use crate::externs::Externs;
 #[cfg(feature = "m2-native")]
 use crate::init_actor::State as InitActorState;
@@ -20,7 +25,9 @@ use crate::kernel::{ClassifyResult, Context as _, Result};
 use crate::machine::Manifest;
 use crate::state_tree::{ActorState, StateTree};
 use crate::syscall_error;
-use crate::system_actor::State as SystemActorState;
+
+const SUPPORTED_VERSIONS: RangeInclusive<NetworkVersion> =
+    NetworkVersion::V15..=NetworkVersion::V17;
 
 pub struct DefaultMachine<B, E> {
     /// The initial execution context for this epoch.
@@ -36,8 +43,10 @@ pub struct DefaultMachine<B, E> {
     ///
     /// Owned.
     state_tree: StateTree<BufferedBlockstore<B>>,
-    /// Mapping of CIDs to builtin actor types.
+    /// Mapping of CIDs to builtin actor types, for the current network version.
     builtin_actors: Manifest,
+    /// The builtin actor manifests of every configured network version.
+    manifests: BTreeMap<NetworkVersion, Manifest>,
     /// Somewhat unique ID of the machine consisting of (epoch, randomness)
     /// randomness is generated with `initial_state_root`
     id: String,
@@ -63,9 +72,6 @@ where
         blockstore: B,
         externs: E,
     ) -> anyhow::Result<Self> {
-        const SUPPORTED_VERSIONS: RangeInclusive<NetworkVersion> =
-            NetworkVersion::V15..=NetworkVersion::V16;
-
         debug!(
             "initializing a new machine, epoch={}, base_fee={}, nv={:?}, root={}",
             context.network_context.epoch,
@@ -81,39 +87,27 @@ where
             ));
         }
 
-        // Sanity check that the blockstore contains the supplied state root.
-        if !blockstore
-            .has(&context.initial_state_root)
-            .context("failed to load initial state-root")?
-        {
-            return Err(anyhow!(
-                "blockstore doesn't have the initial state-root {}",
-                &context.initial_state_root
-            ));
+        check_sender_policy(context.sender_policy, context.network_version)?;
+
+        // Scaling the gas of some actors is only meant for devnets.
+        if context.price_list.has_actor_multipliers() && context.chain_id == ChainID::MAINNET {
+            return Err(anyhow!("actor gas multipliers can't be used on mainnet"));
         }
 
-        // Create a new state tree from the supplied root.
-        let state_tree = {
-            let bstore = BufferedBlockstore::new(blockstore);
-            StateTree::new_from_root(bstore, &context.initial_state_root)?
-        };
+        // Create a new state tree from the supplied root, and load the built-in actors manifests,
+        // checking that the blockstore has everything else the machine needs to boot. Everything
+        // that's missing is reported at once, rather than failing confusingly later.
+        let (state_tree, manifests) = bootstrap(context, BufferedBlockstore::new(blockstore))?;
+
+        // In dynamic supply mode, the circulating supply is computed from the initial state.
+        let mut context = context.clone();
+        if let Some(calculator) = &context.supply_calculator {
+            context.circ_supply = calculator
+                .circulating_supply(&state_tree, context.network_context.epoch)
+                .context("failed to compute the circulating supply")?;
+        }
 
-        // Load the built-in actors manifest.
-        let (builtin_actors_cid, manifest_version) = match context.builtin_actors_override {
-            Some(manifest_cid) => {
-                let (version, cid): (u32, Cid) = state_tree
-                    .store()
-                    .get_cbor(&manifest_cid)?
-                    .context("failed to load actor manifest")?;
-                (cid, version)
-            }
-            None => {
-                let (state, _) = SystemActorState::load(&state_tree)?;
-                (state.builtin_actors, 1)
-            }
-        };
-        let builtin_actors =
-            Manifest::load(state_tree.store(), &builtin_actors_cid, manifest_version)?;
+        let builtin_actors = merge_manifests(&manifests, context.network_version)?;
 
         // Preload any uncached modules.
         // This interface works for now because we know all actor CIDs
@@ -122,7 +116,9 @@ where
         // Skip preloading all builtin actors when testing. This results in JIT
         // bytecode to machine code compilation, and leads to faster tests.
         #[cfg(not(any(test, feature = "testing")))]
-        engine.preload(state_tree.store(), builtin_actors.builtin_actor_codes())?;
+        for manifest in manifests.values() {
+            engine.preload(state_tree.store(), manifest.builtin_actor_codes())?;
+        }
 
         #[cfg(feature = "m2-native")]
         {
@@ -139,22 +135,114 @@ where
             engine.preload(state_tree.store(), &installed_actors)?;
         }
 
-        // 16 bytes is random _enough_
-        let randomness: [u8; 16] = rand::random();
-
         Ok(DefaultMachine {
-            context: context.clone(),
+            id: new_machine_id(&context),
+            context,
             engine: engine.clone(),
             externs,
             state_tree,
             builtin_actors,
-            id: format!(
-                "{}-{}",
-                context.network_context.epoch,
-                cid::multibase::encode(cid::multibase::Base::Base32Lower, &randomness)
-            ),
+            manifests,
         })
     }
+
+    /// Switches the machine to the builtin actors of another network version (e.g., when
+    /// crossing an upgrade epoch), which must be configured in
+    /// [`NetworkConfig::builtin_actors_by_version`][super::NetworkConfig::builtin_actors_by_version].
+    /// From then on, builtin actors deployed under any configured version are dispatched to the
+    /// actor of the same name in the new version.
+    ///
+    /// The engine, price list, and other network parameters are left unchanged.
+    pub fn set_network_version(&mut self, nv: NetworkVersion) -> anyhow::Result<()> {
+        if !SUPPORTED_VERSIONS.contains(&nv) {
+            return Err(anyhow!("unsupported network version: {}", nv));
+        }
+        check_sender_policy(self.context.sender_policy, nv)?;
+        self.builtin_actors = merge_manifests(&self.manifests, nv)?;
+        self.context.network_version = nv;
+        Ok(())
+    }
+}
+
+impl<B, E> DefaultMachine<B, E>
+where
+    B: Blockstore + Clone + 'static,
+    E: Externs + Clone + 'static,
+{
+    /// Forks the machine at its current state, e.g., to evaluate different sets of messages in
+    /// parallel.
+    ///
+    /// The fork shares the underlying blockstore (use an `Arc` to share a single store) and the
+    /// engine with this machine, but has its own write buffer and state-tree cache: neither machine
+    /// will observe the other's writes, and the fork's writes only reach the underlying blockstore
+    /// if it's flushed.
+    ///
+    /// This flushes the state-tree into the write buffer, so it fails if called in the middle of a
+    /// message execution.
+    pub fn fork(&mut self) -> anyhow::Result<Self> {
+        let root = self.state_tree.flush()?;
+        let state_tree = StateTree::new_from_root(self.state_tree.store().fork(), &root)?;
+
+        let mut context = self.context.clone();
+        context.initial_state_root = root;
+
+        Ok(DefaultMachine {
+            id: new_machine_id(&context),
+            context,
+            engine: self.engine.clone(),
+            externs: self.externs.clone(),
+            state_tree,
+            builtin_actors: self.builtin_actors.clone(),
+            manifests: self.manifests.clone(),
+        })
+    }
+}
+
+/// Loads a builtin-actors manifest, given the CID of its `(version, manifest)` tuple.
+pub(super) fn load_manifest(bs: &impl Blockstore, manifest_cid: &Cid) -> anyhow::Result<Manifest> {
+    let (version, cid): (u32, Cid) = bs
+        .get_cbor(manifest_cid)?
+        .context("failed to load actor manifest")?;
+    Manifest::load(bs, &cid, version)
+}
+
+/// Returns the manifest for network version `nv`, recognizing the actors of every other manifest
+/// as legacy actors.
+fn merge_manifests(
+    manifests: &BTreeMap<NetworkVersion, Manifest>,
+    nv: NetworkVersion,
+) -> anyhow::Result<Manifest> {
+    let mut manifest = manifests
+        .get(&nv)
+        .with_context(|| format!("no builtin actors for network version {}", nv))?
+        .clone();
+    for (_, other) in manifests.iter().filter(|(&v, _)| v != nv) {
+        manifest.add_legacy(other);
+    }
+    Ok(manifest)
+}
+
+/// Checks that network version `nv` may use the given sender policy: before nv16, only account
+/// actors may send messages.
+fn check_sender_policy(policy: SenderPolicy, nv: NetworkVersion) -> anyhow::Result<()> {
+    if policy != SenderPolicy::AccountsOnly && nv < NetworkVersion::V16 {
+        return Err(anyhow!(
+            "network version {} only allows account actors to send messages",
+            nv
+        ));
+    }
+    Ok(())
+}
+
+/// Generates a somewhat unique machine ID of the form `epoch-randomness`.
+fn new_machine_id(context: &MachineContext) -> String {
+    // 16 bytes is random _enough_
+    let randomness: [u8; 16] = rand::random();
+    format!(
+        "{}-{}",
+        context.network_context.epoch,
+        cid::multibase::encode(cid::multibase::Base::Base32Lower, &randomness)
+    )
 }
 
 impl<B, E> Machine for DefaultMachine<B, E>
@@ -193,15 +281,27 @@ where
         &mut self.state_tree
     }
 
-    /// Flushes the state-tree and returns the new root CID.
+    /// Flushes the state-tree and returns the new root CID, along with statistics about the
+    /// flush (also recorded in the machine's [`MachineMetrics`][super::MachineMetrics]).
     ///
     /// This method also flushes all new blocks (reachable from this new root CID) from the write
     /// buffer into the underlying blockstore (the blockstore with which the machine was
     /// constructed).
-    fn flush(&mut self) -> Result<Cid> {
-        let root = self.state_tree_mut().flush()?;
-        self.blockstore().flush(&root).or_fatal()?;
-        Ok(root)
+    fn flush(&mut self) -> Result<FlushStats> {
+        let start = Instant::now();
+        let (root, tree) = self.state_tree_mut().flush_with_stats()?;
+        let buffer = self.blockstore().flush_with_stats(&root).or_fatal()?;
+        let stats = FlushStats {
+            root,
+            cache: self.state_tree.cache_stats(),
+            dirty_actors: tree.dirty_actors,
+            hamt_nodes_written: tree.hamt_nodes_written,
+            blocks_written: buffer.blocks_written,
+            blocks_skipped: buffer.blocks_skipped,
+            wall_time: start.elapsed(),
+        };
+        self.context.metrics.record_flush(&stats);
+        Ok(stats)
     }
 
     /// Creates an uninitialized actor.
@@ -221,11 +321,7 @@ where
     }
 
     fn transfer(&mut self, from: ActorID, to: ActorID, value: &TokenAmount) -> Result<()> {
-        if value.is_negative() {
-            return Err(syscall_error!(IllegalArgument;
-                "attempted to transfer negative transfer value {}", value)
-            .into());
-        }
+        check_transfer_value(self.context.network_version, value)?;
 
         // If the from actor doesn't exist, we return "insufficient funds" to distinguish between
         // that and the case where the _receiving_ actor doesn't exist.
@@ -0,0 +1,357 @@
+//! Diffs two state trees, e.g. to debug why a conformance vector's post state-root doesn't match.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use anyhow::Context;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use libipld_core::ipld::Ipld;
+
+use crate::state_tree::{ActorState, StateTree};
+
+/// A difference between the same actor in two state trees.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActorDiff {
+    /// The actor only exists in the second tree.
+    Added(Address, ActorState),
+    /// The actor only exists in the first tree.
+    Removed(Address, ActorState),
+    /// The actor exists in both trees, but differs. Fields that didn't change are `None`.
+    Changed {
+        address: Address,
+        code: Option<(Cid, Cid)>,
+        head: Option<(Cid, Cid)>,
+        sequence: Option<(u64, u64)>,
+        balance: Option<(TokenAmount, TokenAmount)>,
+        /// The differences between the actor's states, if its head changed.
+        state: Vec<FieldDiff>,
+    },
+}
+
+/// A difference between two actor states.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    /// The path of the value within the state, e.g. `/1/0`. Links are followed, so values inside
+    /// HAMTs, AMTs, and other nested structures are reported at the path of the link.
+    pub path: String,
+    /// The value in the first tree, if any.
+    pub before: Option<Ipld>,
+    /// The value in the second tree, if any.
+    pub after: Option<Ipld>,
+}
+
+impl fmt::Display for ActorDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActorDiff::Added(addr, actor) => write!(f, "+ {}: {:?}", addr, actor),
+            ActorDiff::Removed(addr, actor) => write!(f, "- {}: {:?}", addr, actor),
+            ActorDiff::Changed {
+                address,
+                code,
+                head,
+                sequence,
+                balance,
+                state,
+            } => {
+                write!(f, "~ {}:", address)?;
+                if let Some((a, b)) = code {
+                    write!(f, "\n    code: {} -> {}", a, b)?;
+                }
+                if let Some((a, b)) = head {
+                    write!(f, "\n    head: {} -> {}", a, b)?;
+                }
+                if let Some((a, b)) = sequence {
+                    write!(f, "\n    sequence: {} -> {}", a, b)?;
+                }
+                if let Some((a, b)) = balance {
+                    write!(f, "\n    balance: {} -> {}", a, b)?;
+                }
+                for field in state {
+                    write!(
+                        f,
+                        "\n    state{}: {:?} -> {:?}",
+                        field.path, field.before, field.after
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Diffs the state trees rooted at `root_a` and `root_b`, returning the actors that were added,
+/// removed, or changed between them.
+///
+/// The trees are walked one actor at a time rather than loaded into memory. Actor states are
+/// decoded as generic IPLD and compared structurally (builtin actor states are tuple-encoded, so
+/// their fields are reported by index), following changed links into nested structures. Values
+/// that can't be decoded as DAG-CBOR are compared as raw links.
+pub fn diff_state<BS: Blockstore>(
+    store: &BS,
+    root_a: &Cid,
+    root_b: &Cid,
+) -> anyhow::Result<Vec<ActorDiff>> {
+    let mut diffs = Vec::new();
+    if root_a == root_b {
+        return Ok(diffs);
+    }
+
+    let tree_a = StateTree::new_from_root(store, root_a)
+        .map_err(anyhow::Error::from)
+        .context("failed to load first state tree")?;
+    let tree_b = StateTree::new_from_root(store, root_b)
+        .map_err(anyhow::Error::from)
+        .context("failed to load second state tree")?;
+
+    tree_a.for_each(|addr, a| {
+        match tree_b.get_actor(&addr).map_err(anyhow::Error::from)? {
+            None => diffs.push(ActorDiff::Removed(addr, a.clone())),
+            Some(b) if &b != a => diffs.push(diff_actor(store, addr, a, &b)?),
+            Some(_) => {}
+        }
+        Ok(())
+    })?;
+
+    tree_b.for_each(|addr, b| {
+        if tree_a
+            .get_actor(&addr)
+            .map_err(anyhow::Error::from)?
+            .is_none()
+        {
+            diffs.push(ActorDiff::Added(addr, b.clone()));
+        }
+        Ok(())
+    })?;
+
+    Ok(diffs)
+}
+
+fn diff_actor<BS: Blockstore>(
+    store: &BS,
+    address: Address,
+    a: &ActorState,
+    b: &ActorState,
+) -> anyhow::Result<ActorDiff> {
+    fn changed<T: PartialEq + Clone>(a: &T, b: &T) -> Option<(T, T)> {
+        (a != b).then(|| (a.clone(), b.clone()))
+    }
+
+    let mut state = Vec::new();
+    diff_ipld(
+        store,
+        String::new(),
+        Ipld::Link(a.state),
+        Ipld::Link(b.state),
+        &mut state,
+    )
+    .with_context(|| format!("failed to diff the state of actor {}", address))?;
+
+    Ok(ActorDiff::Changed {
+        address,
+        code: changed(&a.code, &b.code),
+        head: changed(&a.state, &b.state),
+        sequence: changed(&a.sequence, &b.sequence),
+        balance: changed(&a.balance, &b.balance),
+        state,
+    })
+}
+
+fn diff_ipld<BS: Blockstore>(
+    store: &BS,
+    path: String,
+    a: Ipld,
+    b: Ipld,
+    out: &mut Vec<FieldDiff>,
+) -> anyhow::Result<()> {
+    if a == b {
+        return Ok(());
+    }
+    match (a, b) {
+        (Ipld::List(a), Ipld::List(b)) => {
+            let len = a.len().max(b.len());
+            let (mut a, mut b) = (a.into_iter(), b.into_iter());
+            for i in 0..len {
+                diff_optional(store, format!("{}/{}", path, i), a.next(), b.next(), out)?;
+            }
+        }
+        (Ipld::Map(mut a), Ipld::Map(mut b)) => {
+            let keys: BTreeSet<String> = a.keys().chain(b.keys()).cloned().collect();
+            for k in keys {
+                let (va, vb) = (a.remove(&k), b.remove(&k));
+                diff_optional(store, format!("{}/{}", path, k), va, vb, out)?;
+            }
+        }
+        (Ipld::Link(a), Ipld::Link(b)) if a.codec() == DAG_CBOR && b.codec() == DAG_CBOR => {
+            match (store.get_cbor::<Ipld>(&a)?, store.get_cbor::<Ipld>(&b)?) {
+                (Some(va), Some(vb)) => diff_ipld(store, path, va, vb, out)?,
+                // We can't follow the link, so report it as-is.
+                _ => out.push(FieldDiff {
+                    path,
+                    before: Some(Ipld::Link(a)),
+                    after: Some(Ipld::Link(b)),
+                }),
+            }
+        }
+        (a, b) => out.push(FieldDiff {
+            path,
+            before: Some(a),
+            after: Some(b),
+        }),
+    }
+    Ok(())
+}
+
+fn diff_optional<BS: Blockstore>(
+    store: &BS,
+    path: String,
+    a: Option<Ipld>,
+    b: Option<Ipld>,
+    out: &mut Vec<FieldDiff>,
+) -> anyhow::Result<()> {
+    match (a, b) {
+        (Some(a), Some(b)) => diff_ipld(store, path, a, b, out),
+        (None, None) => Ok(()),
+        (before, after) => {
+            out.push(FieldDiff {
+                path,
+                before,
+                after,
+            });
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code, Multihash};
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_hamt::{BytesKey, Hamt};
+    use fvm_shared::state::StateTreeVersion;
+    use fvm_shared::{IDENTITY_HASH, IPLD_RAW};
+    use num_traits::Zero;
+
+    use super::*;
+
+    fn code(name: &str) -> Cid {
+        Cid::new_v1(
+            IPLD_RAW,
+            Multihash::wrap(IDENTITY_HASH, name.as_bytes()).unwrap(),
+        )
+    }
+
+    /// Stores a (count, map) state, where the map is a HAMT large enough to have internal nodes.
+    fn put_state(store: &MemoryBlockstore, overrides: &[(u64, u64)]) -> Cid {
+        let mut map: Hamt<_, u64> = Hamt::new_with_bit_width(store, 5);
+        for i in 0..500u64 {
+            map.set(BytesKey(i.to_be_bytes().to_vec()), i).unwrap();
+        }
+        for (k, v) in overrides {
+            map.set(BytesKey(k.to_be_bytes().to_vec()), *v).unwrap();
+        }
+        let map = map.flush().unwrap();
+        store.put_cbor(&(7u64, map), Code::Blake2b256).unwrap()
+    }
+
+    fn tree(store: &MemoryBlockstore, balance: u64, overrides: &[(u64, u64)]) -> Cid {
+        let mut tree = StateTree::new(store, StateTreeVersion::V4).unwrap();
+        let empty = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        tree.set_actor_id(
+            100,
+            ActorState::new(code("account"), empty, TokenAmount::from_atto(balance), 0),
+        )
+        .unwrap();
+        tree.set_actor_id(
+            101,
+            ActorState::new(
+                code("registry"),
+                put_state(store, overrides),
+                TokenAmount::zero(),
+                0,
+            ),
+        )
+        .unwrap();
+        tree.flush().unwrap()
+    }
+
+    #[test]
+    fn identical() {
+        let store = MemoryBlockstore::default();
+        let root = tree(&store, 10, &[]);
+        assert!(diff_state(&store, &root, &root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn balance_and_hamt_entry() {
+        let store = MemoryBlockstore::default();
+        let a = tree(&store, 10, &[]);
+        let b = tree(&store, 20, &[(42, 4242)]);
+
+        let diffs = diff_state(&store, &a, &b).unwrap();
+        assert_eq!(diffs.len(), 2);
+        let find = |id| {
+            diffs
+                .iter()
+                .find(|d| matches!(d, ActorDiff::Changed { address, .. } if *address == Address::new_id(id)))
+                .unwrap_or_else(|| panic!("no diff for actor {}", id))
+        };
+
+        assert_eq!(
+            find(100),
+            &ActorDiff::Changed {
+                address: Address::new_id(100),
+                code: None,
+                head: None,
+                sequence: None,
+                balance: Some((TokenAmount::from_atto(10), TokenAmount::from_atto(20))),
+                state: Vec::new(),
+            }
+        );
+
+        match find(101) {
+            ActorDiff::Changed {
+                code,
+                head,
+                balance,
+                state,
+                ..
+            } => {
+                assert!(code.is_none());
+                assert!(balance.is_none());
+                assert!(head.is_some());
+                // Only the changed entry is reported, from deep inside the HAMT.
+                assert_eq!(state.len(), 1);
+                assert!(state[0].path.starts_with("/1/"));
+                assert_eq!(state[0].before, Some(Ipld::Integer(42)));
+                assert_eq!(state[0].after, Some(Ipld::Integer(4242)));
+            }
+            other => panic!("unexpected diff: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn added_and_removed() {
+        let store = MemoryBlockstore::default();
+        let a = tree(&store, 10, &[]);
+
+        let mut tree_b = StateTree::new_from_root(&store, &a).unwrap();
+        let removed = tree_b.get_actor_id(100).unwrap().unwrap();
+        tree_b.delete_actor_id(100).unwrap();
+        let added = ActorState::new(code("account"), removed.state, TokenAmount::zero(), 3);
+        tree_b.set_actor_id(200, added.clone()).unwrap();
+        let b = tree_b.flush().unwrap();
+
+        let diffs = diff_state(&store, &a, &b).unwrap();
+        assert_eq!(
+            diffs,
+            vec![
+                ActorDiff::Removed(Address::new_id(100), removed),
+                ActorDiff::Added(Address::new_id(200), added),
+            ]
+        );
+    }
+}
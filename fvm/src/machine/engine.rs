@@ -1,6 +1,7 @@
 use std::any::{Any, TypeId};
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 
@@ -10,10 +11,17 @@ use fvm_ipld_blockstore::Blockstore;
 use fvm_wasm_instrument::gas_metering::GAS_COUNTER_NAME;
 use fvm_wasm_instrument::parity_wasm::elements;
 use wasmtime::OptLevel::Speed;
-use wasmtime::{Global, GlobalType, Linker, Memory, MemoryType, Module, Mutability, Val, ValType};
-
-use super::Machine;
-use crate::gas::WasmGasPrices;
+use wasmtime::{
+    Global, GlobalType, InstanceAllocationStrategy, InstanceLimits, InstancePre, Linker, Memory,
+    MemoryType, Module, Mutability, PoolingAllocationStrategy, ResourceLimiter, Val, ValType,
+};
+
+use super::abi::AbiVersion;
+use super::validation::{
+    validate_module, ValidationError, WasmFeatureSet, WasmLimits, WASM_PAGE_SIZE,
+};
+use super::{Machine, Manifest};
+use crate::gas::{GasMultiplier, WasmGasPrices};
 use crate::machine::NetworkConfig;
 use crate::syscalls::{bind_syscalls, InvocationData};
 use crate::Kernel;
@@ -24,42 +32,113 @@ pub struct Engine(Arc<EngineInner>);
 
 /// Container managing engines with different consensus-affecting configurations.
 #[derive(Clone)]
-pub struct MultiEngine(Arc<Mutex<HashMap<EngineConfig, Engine>>>);
+pub struct MultiEngine {
+    concurrency: u32,
+    module_cache: Option<Arc<dyn ModuleCache>>,
+    engines: Arc<Mutex<HashMap<EngineConfig, Engine>>>,
+}
+
+/// Identifies a compiled actor module in a [`ModuleCache`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ModuleCacheKey {
+    /// Hash of everything that affects the compiled machine code (wasmtime version & settings,
+    /// instrumentation parameters, etc.). Engines with different hashes never share modules.
+    pub engine: [u8; 32],
+    /// The code CID of the actor.
+    pub code: Cid,
+}
+
+/// A cache of compiled actor modules, provided by the embedder. Compiled modules are native code
+/// that the engine loads without recompiling, so this cache must be local to the node and must
+/// never be filled from the chain (or any other untrusted source).
+pub trait ModuleCache: Send + Sync {
+    /// Returns the artifact stored under the key, if any.
+    fn get(&self, key: &ModuleCacheKey) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Stores an artifact under the key.
+    fn put(&self, key: &ModuleCacheKey, artifact: Vec<u8>) -> anyhow::Result<()>;
+}
 
 /// The proper way of getting this struct is to convert from `NetworkConfig`
 #[derive(Clone, Eq, PartialEq, Hash)]
 pub struct EngineConfig {
+    pub max_call_depth: u32,
     pub max_wasm_stack: u32,
     pub wasm_prices: &'static WasmGasPrices,
     pub actor_redirect: Vec<(Cid, Cid)>,
+    pub wasm_limits: WasmLimits,
+    pub wasm_features: WasmFeatureSet,
+
+    /// Allocate actor instances from a pre-allocated pool instead of on-demand. This only affects
+    /// performance, not execution.
+    ///
+    /// The pool holds an instance for every call on the call stack of every concurrent machine,
+    /// and reserves address space for each instance's largest possible memory and tables up front
+    /// (see [`WasmLimits`]). Only enable this with bounded limits, on hosts with the address space
+    /// to spare.
+    ///
+    /// DEFAULT: `false`
+    pub pooling: bool,
+
+    /// The maximum number of machines using the engine at the same time. The instance pool is
+    /// sized to support this many concurrent machines at the maximum call depth.
+    ///
+    /// DEFAULT: 1
+    pub concurrency: u32,
 }
 
 impl From<&NetworkConfig> for EngineConfig {
     fn from(nc: &NetworkConfig) -> Self {
         EngineConfig {
+            max_call_depth: nc.max_call_depth,
             max_wasm_stack: nc.max_wasm_stack,
             wasm_prices: &nc.price_list.wasm_rules,
             actor_redirect: nc.actor_redirect.clone(),
+            wasm_limits: nc.wasm_limits.clone(),
+            wasm_features: nc.wasm_features.clone(),
+            pooling: false,
+            concurrency: 1,
         }
     }
 }
 
 impl MultiEngine {
     pub fn new() -> MultiEngine {
-        MultiEngine(Arc::new(Mutex::new(HashMap::new())))
+        Self::with_concurrency(1)
+    }
+
+    /// Creates a new `MultiEngine` whose engines may be used by up to `concurrency` machines at
+    /// the same time (see [`EngineConfig::concurrency`]).
+    pub fn with_concurrency(concurrency: u32) -> MultiEngine {
+        MultiEngine {
+            concurrency,
+            module_cache: None,
+            engines: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Loads and stores compiled actor modules through the given cache (see
+    /// [`Engine::new_with_cache`]).
+    pub fn with_module_cache(mut self, cache: Arc<dyn ModuleCache>) -> MultiEngine {
+        self.module_cache = Some(cache);
+        self
     }
 
     pub fn get(&self, nc: &NetworkConfig) -> anyhow::Result<Engine> {
         let mut engines = self
-            .0
+            .engines
             .lock()
             .map_err(|_| anyhow::Error::msg("multiengine lock is poisoned"))?;
 
-        let ec: EngineConfig = nc.into();
+        let mut ec: EngineConfig = nc.into();
+        ec.concurrency = self.concurrency;
 
         let engine = match engines.entry(ec.clone()) {
             Occupied(entry) => entry.into_mut(),
-            Vacant(entry) => entry.insert(Engine::new_default(ec)?),
+            Vacant(entry) => entry.insert(match &self.module_cache {
+                Some(cache) => Engine::new_with_cache(ec, cache.clone())?,
+                None => Engine::new_default(ec)?,
+            }),
         };
 
         Ok(engine.clone())
@@ -103,10 +182,9 @@ pub fn default_wasmtime_config() -> wasmtime::Config {
     // wasmtime default: depends on the arch
     // > This is true by default on x86-64, and false by default on other architectures.
     //
+    // Always set explicitly so that the same modules are accepted on every architecture.
     // Not supported in wasm-instrument/parity-wasm; adding support will be complicated.
     // Note: stack limits may need adjusting after this is enabled
-    // NOTE: only needed when backtraces are enabled.
-    #[cfg(feature = "wasmtime/wasm-backtrace")]
     c.wasm_reference_types(false);
 
     // wasmtime default: false
@@ -120,7 +198,8 @@ pub fn default_wasmtime_config() -> wasmtime::Config {
     c.cranelift_nan_canonicalization(true);
 
     // wasmtime default: 512KiB
-    // Set to something much higher than the instrumented limiter.
+    // Set to something much higher than the instrumented limiter. `Engine::new_default` derives
+    // this from the configured instrumented limit (see `native_wasm_stack`).
     // Note: This is in bytes, while the instrumented limit is in stack elements
     c.max_wasm_stack(4 << 20);
 
@@ -148,6 +227,68 @@ pub fn default_wasmtime_config() -> wasmtime::Config {
     c
 }
 
+/// Enables the Wasm proposals in the feature set, and disables all others. This is the only place
+/// proposals are configured; validation happens against the resulting engine, so a module is
+/// either rejected when it's loaded or not at all.
+fn configure_features(c: &mut wasmtime::Config, features: &WasmFeatureSet) -> anyhow::Result<()> {
+    if features.reference_types && !features.bulk_memory {
+        return Err(anyhow!("the reference types proposal requires bulk memory"));
+    }
+    c.wasm_threads(false);
+    c.wasm_multi_memory(false);
+    c.wasm_memory64(false);
+    c.wasm_simd(features.simd);
+    c.wasm_bulk_memory(features.bulk_memory);
+    c.wasm_reference_types(features.reference_types);
+    c.wasm_multi_value(features.multi_value);
+    Ok(())
+}
+
+/// Configures wasmtime to allocate instances from a pool sized according to the engine config.
+fn configure_pooling(c: &mut wasmtime::Config, ec: &EngineConfig) {
+    // Every call on the call stack holds an instance, plus one for the top-level call. A smaller
+    // pool could run out of instances part way down a call stack, failing the call on some nodes
+    // but not others.
+    let count = ec
+        .max_call_depth
+        .saturating_add(1)
+        .saturating_mul(ec.concurrency);
+
+    c.allocation_strategy(InstanceAllocationStrategy::Pooling {
+        strategy: PoolingAllocationStrategy::ReuseAffinity,
+        instance_limits: InstanceLimits {
+            count,
+            tables: ec.wasm_limits.max_tables as u32,
+            table_elements: ec.wasm_limits.max_table_elements,
+            memories: 1,
+            memory_pages: (ec.wasm_limits.max_memory_bytes / WASM_PAGE_SIZE) as u64,
+            ..Default::default()
+        },
+    });
+
+    // Only reserve as much address space per memory as the memory limit allows. Memory accesses
+    // are bounds-checked either way.
+    c.static_memory_maximum_size(ec.wasm_limits.max_memory_bytes as u64);
+    c.static_memory_guard_size(WASM_PAGE_SIZE as u64);
+}
+
+/// Enforces the memory and table limits at runtime. The pooling allocator enforces these limits
+/// on its own; this limiter makes sure on-demand allocation behaves identically.
+pub(crate) struct InstanceLimiter {
+    max_memory_bytes: usize,
+    max_table_elements: u32,
+}
+
+impl ResourceLimiter for InstanceLimiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> bool {
+        desired <= self.max_memory_bytes
+    }
+
+    fn table_growing(&mut self, _current: u32, desired: u32, _maximum: Option<u32>) -> bool {
+        desired <= self.max_table_elements
+    }
+}
+
 struct EngineInner {
     engine: wasmtime::Engine,
 
@@ -160,9 +301,17 @@ struct EngineInner {
     dummy_memory: Memory,
 
     module_cache: Mutex<HashMap<Cid, Module>>,
+    /// The sizes of the Wasm bytecode of loaded modules, which actors are charged for loading.
+    code_sizes: Mutex<HashMap<Cid, usize>>,
+    /// The embedder's cache of compiled modules, if any.
+    compiled_cache: Option<Arc<dyn ModuleCache>>,
     instance_cache: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
     config: EngineConfig,
 
+    /// Hash of everything that affects the compiled machine code (wasmtime version & settings,
+    /// instrumentation parameters, etc.). Compiled modules are cached under this hash.
+    config_hash: [u8; 32],
+
     actor_redirect: HashMap<Cid, Cid>,
 }
 
@@ -174,13 +323,47 @@ impl Deref for Engine {
     }
 }
 
+/// Native stack bytes allowed per element of the instrumented wasm stack limit. Cranelift frames
+/// are far smaller than this per element, so the instrumented limiter (which fails
+/// deterministically) always trips before wasmtime's native stack guard (which doesn't).
+const NATIVE_STACK_BYTES_PER_ELEMENT: usize = 2 << 10;
+
+/// The native wasm stack limit, in bytes, for an instrumented limit of `max_wasm_stack` elements.
+fn native_wasm_stack(max_wasm_stack: u32) -> usize {
+    (max_wasm_stack as usize * NATIVE_STACK_BYTES_PER_ELEMENT).max(4 << 20)
+}
+
 impl Engine {
     pub fn new_default(ec: EngineConfig) -> anyhow::Result<Self> {
-        Engine::new(&default_wasmtime_config(), ec)
+        Engine::new(&Engine::wasmtime_config(&ec)?, ec)
+    }
+
+    /// Like [`Engine::new_default`], but loads compiled modules from the given cache instead of
+    /// recompiling actor code. [`Engine::persist_compiled`] fills the cache.
+    pub fn new_with_cache(ec: EngineConfig, cache: Arc<dyn ModuleCache>) -> anyhow::Result<Self> {
+        Engine::new_inner(&Engine::wasmtime_config(&ec)?, ec, Some(cache))
     }
 
     /// Create a new Engine from a wasmtime config.
     pub fn new(c: &wasmtime::Config, ec: EngineConfig) -> anyhow::Result<Self> {
+        Engine::new_inner(c, ec, None)
+    }
+
+    fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
+        let mut c = default_wasmtime_config();
+        c.max_wasm_stack(native_wasm_stack(ec.max_wasm_stack));
+        configure_features(&mut c, &ec.wasm_features)?;
+        if ec.pooling {
+            configure_pooling(&mut c, ec);
+        }
+        Ok(c)
+    }
+
+    fn new_inner(
+        c: &wasmtime::Config,
+        ec: EngineConfig,
+        compiled_cache: Option<Arc<dyn ModuleCache>>,
+    ) -> anyhow::Result<Self> {
         let engine = wasmtime::Engine::new(c)?;
 
         let mut dummy_store = wasmtime::Store::new(&engine, ());
@@ -193,20 +376,57 @@ impl Engine {
 
         let actor_redirect = ec.actor_redirect.iter().cloned().collect();
 
+        let config_hash = {
+            let mut hasher = Blake2bHasher(blake2b_simd::Params::new().hash_length(32).to_state());
+            engine.precompile_compatibility_hash().hash(&mut hasher);
+            // Instrumentation may change between FVM versions.
+            env!("CARGO_PKG_VERSION").hash(&mut hasher);
+            ec.max_wasm_stack.hash(&mut hasher);
+            ec.wasm_prices.hash(&mut hasher);
+            ec.wasm_limits.hash(&mut hasher);
+            ec.wasm_features.hash(&mut hasher);
+            let mut out = [0u8; 32];
+            out.copy_from_slice(hasher.0.finalize().as_bytes());
+            out
+        };
+
         Ok(Engine(Arc::new(EngineInner {
             engine,
             dummy_memory,
             dummy_gas_global: dummy_gg,
             module_cache: Default::default(),
+            code_sizes: Default::default(),
+            compiled_cache,
             instance_cache: Mutex::new(HashMap::new()),
             config: ec,
+            config_hash,
             actor_redirect,
         })))
     }
 }
 
+/// Prefix of all compiled module artifacts, followed by the engine's config hash, the code CID,
+/// and the blake2b-256 digest of the compiled module.
+const PRECOMPILED_MAGIC: &[u8] = b"fvm/precompiled-module/v2";
+
+/// Feeds [`Hash`] implementations into a blake2b state.
+struct Blake2bHasher(blake2b_simd::State);
+
+impl Hasher for Blake2bHasher {
+    fn finish(&self) -> u64 {
+        let hash = self.0.finalize();
+        u64::from_le_bytes(hash.as_bytes()[..8].try_into().unwrap())
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+}
+
 struct Cache<K> {
     linker: wasmtime::Linker<InvocationData<K>>,
+    /// Modules pre-linked against the syscalls, along with their ABI version, by code CID.
+    instances: HashMap<Cid, (InstancePre<InvocationData<K>>, AbiVersion)>,
 }
 
 impl Engine {
@@ -219,23 +439,17 @@ impl Engine {
         blockstore: BS,
     ) -> anyhow::Result<()> {
         let code_cid = self.with_redirect(code_cid);
-        if self
-            .0
-            .module_cache
-            .lock()
-            .expect("module_cache poisoned")
-            .contains_key(code_cid)
-        {
-            return Ok(());
+        let mut cache = self.0.module_cache.lock().expect("module_cache poisoned");
+        if let Vacant(v) = cache.entry(*code_cid) {
+            // compile (or deserialize) and cache instantiated WASM module
+            let module = self.load(&blockstore, code_cid)?.ok_or_else(|| {
+                anyhow!(
+                    "no wasm bytecode in blockstore for CID {}",
+                    &code_cid.to_string()
+                )
+            })?;
+            v.insert(module);
         }
-        let wasm = blockstore.get(code_cid)?.ok_or_else(|| {
-            anyhow!(
-                "no wasm bytecode in blockstore for CID {}",
-                &code_cid.to_string()
-            )
-        })?;
-        // compile and cache instantiated WASM module
-        self.prepare_wasm_bytecode(code_cid, &wasm)?;
         Ok(())
     }
 
@@ -258,6 +472,166 @@ impl Engine {
         Ok(())
     }
 
+    /// Compiles the Wasm modules for all builtin actors in the manifest, and stores the compiled
+    /// modules in the engine's module cache (see [`Engine::persist_compiled`]).
+    pub fn prepare_all<BS: Blockstore>(
+        &self,
+        blockstore: BS,
+        manifest: &Manifest,
+    ) -> anyhow::Result<()> {
+        self.persist_compiled(blockstore, manifest.builtin_actor_codes())
+    }
+
+    /// Compiles the Wasm modules for the bytecodes addressed by the supplied CIDs, and stores them
+    /// in the engine's module cache under the keys returned by [`Engine::module_cache_key`].
+    /// Modules that are already cached are skipped. Fails if the engine has no module cache (see
+    /// [`Engine::new_with_cache`]).
+    pub fn persist_compiled<'a, BS, I>(&self, blockstore: BS, cids: I) -> anyhow::Result<()>
+    where
+        BS: Blockstore,
+        I: IntoIterator<Item = &'a Cid>,
+    {
+        let cache = self
+            .0
+            .compiled_cache
+            .as_ref()
+            .ok_or_else(|| anyhow!("engine has no module cache"))?;
+        for cid in cids {
+            let key = self.module_cache_key(cid);
+            if cache.get(&key)?.is_some() {
+                continue;
+            }
+            log::trace!("persisting compiled code CID {cid}");
+            let module = self
+                .get_module(&blockstore, cid)?
+                .ok_or_else(|| anyhow!("no wasm bytecode in blockstore for CID {}", cid))?;
+            let compiled = module
+                .serialize()
+                .with_context(|| anyhow!("failed to serialize module for CID {}", cid))?;
+
+            let mut artifact = self.artifact_header(&key.code);
+            artifact.extend(
+                blake2b_simd::Params::new()
+                    .hash_length(32)
+                    .hash(&compiled)
+                    .as_bytes(),
+            );
+            artifact.extend(compiled);
+            cache.put(&key, artifact)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the key under which the compiled module for the given code CID is cached. The key
+    /// includes the engine's configuration, so engines with different configurations (or wasmtime
+    /// versions) never share compiled modules.
+    pub fn module_cache_key(&self, code_cid: &Cid) -> ModuleCacheKey {
+        ModuleCacheKey {
+            engine: self.0.config_hash,
+            code: *self.with_redirect(code_cid),
+        }
+    }
+
+    fn artifact_header(&self, code_cid: &Cid) -> Vec<u8> {
+        [PRECOMPILED_MAGIC, &self.0.config_hash, &code_cid.to_bytes()].concat()
+    }
+
+    /// Loads a module, preferring a compiled module from the module cache over compiling the raw
+    /// Wasm bytecode from the blockstore. Returns `None` if the bytecode isn't in the blockstore.
+    fn load(&self, blockstore: &impl Blockstore, k: &Cid) -> anyhow::Result<Option<Module>> {
+        if let Some(module) = self.load_precompiled(k)? {
+            return Ok(Some(module));
+        }
+        blockstore
+            .get(k)
+            .context("failed to lookup wasm module in blockstore")?
+            .map(|raw_wasm| {
+                self.record_code_size(k, raw_wasm.len());
+                self.load_raw(&raw_wasm)
+            })
+            .transpose()
+    }
+
+    /// Returns the size of the Wasm bytecode for the given code CID, or `None` if it isn't in the
+    /// blockstore. The size is cached along with the module, so the bytecode is read at most once,
+    /// even if the module is never compiled from it (e.g., because it was precompiled).
+    pub fn code_size(
+        &self,
+        blockstore: &impl Blockstore,
+        k: &Cid,
+    ) -> anyhow::Result<Option<usize>> {
+        if let Some(size) = self
+            .0
+            .code_sizes
+            .lock()
+            .expect("code_sizes poisoned")
+            .get(k)
+        {
+            return Ok(Some(*size));
+        }
+        let size = blockstore
+            .get(k)
+            .context("failed to lookup wasm module in blockstore")?
+            .map(|raw_wasm| raw_wasm.len());
+        if let Some(size) = size {
+            self.record_code_size(k, size);
+        }
+        Ok(size)
+    }
+
+    fn record_code_size(&self, k: &Cid, size: usize) {
+        self.0
+            .code_sizes
+            .lock()
+            .expect("code_sizes poisoned")
+            .insert(*k, size);
+    }
+
+    fn load_precompiled(&self, k: &Cid) -> anyhow::Result<Option<Module>> {
+        let cache = match &self.0.compiled_cache {
+            Some(cache) => cache,
+            None => return Ok(None),
+        };
+        let artifact = match cache
+            .get(&self.module_cache_key(k))
+            .context("failed to lookup compiled module in module cache")?
+        {
+            Some(artifact) => artifact,
+            None => return Ok(None),
+        };
+
+        // Check that the artifact was produced for this code CID by an engine with the same
+        // configuration, and that it's intact, before handing it to wasmtime.
+        let header = self.artifact_header(k);
+        let (digest, compiled) = match artifact.strip_prefix(header.as_slice()) {
+            Some(rest) if rest.len() >= 32 => rest.split_at(32),
+            _ => {
+                log::warn!("ignoring compiled module for CID {k}: header mismatch");
+                return Ok(None);
+            }
+        };
+        if blake2b_simd::Params::new()
+            .hash_length(32)
+            .hash(compiled)
+            .as_bytes()
+            != digest
+        {
+            log::warn!("ignoring compiled module for CID {k}: checksum mismatch");
+            return Ok(None);
+        }
+
+        // SAFETY: the artifact was produced by `Module::serialize` (see `Engine::persist_compiled`)
+        // and stored in the embedder's local module cache, and we've checked that it's intact.
+        // wasmtime additionally checks its version & settings on deserialization.
+        match unsafe { Module::deserialize(&self.0.engine, compiled) } {
+            Ok(module) => Ok(Some(module)),
+            Err(e) => {
+                log::warn!("ignoring compiled module for CID {k}: {e}");
+                Ok(None)
+            }
+        }
+    }
+
     fn with_redirect<'a>(&'a self, k: &'a Cid) -> &'a Cid {
         match &self.0.actor_redirect.get(k) {
             Some(cid) => cid,
@@ -273,6 +647,7 @@ impl Engine {
             Some(module) => module.clone(),
             None => {
                 let module = self.load_raw(wasm)?;
+                self.record_code_size(k, wasm.len());
                 cache.insert(*k, module.clone());
                 module
             }
@@ -281,9 +656,16 @@ impl Engine {
     }
 
     fn load_raw(&self, raw_wasm: &[u8]) -> anyhow::Result<Module> {
+        let limits = &self.0.config.wasm_limits;
+        if raw_wasm.len() > limits.max_module_size {
+            return Err(
+                ValidationError::ModuleTooLarge(raw_wasm.len(), limits.max_module_size).into(),
+            );
+        }
+
         // First make sure that non-instrumented wasm is valid
         Module::validate(&self.0.engine, raw_wasm)
-            .map_err(anyhow::Error::msg)
+            .map_err(|e| ValidationError::InvalidWasm(e.to_string()))
             .with_context(|| "failed to validate actor wasm")?;
 
         // Note: when adding debug mode support (with recorded syscall replay) don't instrument to
@@ -293,13 +675,19 @@ impl Engine {
         use fvm_wasm_instrument::inject_stack_limiter;
         use fvm_wasm_instrument::parity_wasm::deserialize_buffer;
 
-        let m = deserialize_buffer(raw_wasm)?;
+        let m = deserialize_buffer(raw_wasm)
+            .map_err(|e| ValidationError::InvalidWasm(e.to_string()))?;
+
+        // Then check the imports, exports, and limits before instrumenting anything.
+        validate_module(&m, limits).with_context(|| "failed to validate actor wasm")?;
 
         // stack limiter adds post/pre-ambles to call instructions; We want to do that
         // before injecting gas accounting calls to avoid this overhead in every single
         // block of code.
-        let m =
-            inject_stack_limiter(m, self.0.config.max_wasm_stack).map_err(anyhow::Error::msg)?;
+        //
+        // Modules we can't instrument are invalid actor code, like any other module we can't load.
+        let m = inject_stack_limiter(m, self.0.config.max_wasm_stack)
+            .map_err(|e| ValidationError::InvalidWasm(format!("failed to limit stack: {}", e)))?;
 
         // inject gas metering based on a price list. This function will
         // * add a new mutable i64 global import, gas.gas_counter
@@ -310,11 +698,13 @@ impl Engine {
         // * divide code into metered blocks, and add a call to the gas counter
         //   function before entering each metered block
         let mut m = inject(m, self.0.config.wasm_prices, "gas")
-            .map_err(|_| anyhow::Error::msg("injecting gas counter failed"))?;
+            .map_err(|_| ValidationError::InvalidWasm("injecting gas counter failed".into()))?;
 
         // Work around #602. Remove this once paritytech/parity-wasm#331 is merged and bubbled.
         fix_wasm_sections(&mut m);
 
+        export_gas_counter(&mut m)?;
+
         let wasm = m.to_bytes()?;
         let module = Module::from_binary(&self.0.engine, wasm.as_slice())?;
 
@@ -340,6 +730,17 @@ impl Engine {
         Ok(module)
     }
 
+    /// Returns true if the module for the given code CID has already been loaded (e.g., preloaded,
+    /// or invoked before) and won't need to be compiled.
+    pub fn is_module_cached(&self, k: &Cid) -> bool {
+        let k = self.with_redirect(k);
+        self.0
+            .module_cache
+            .lock()
+            .expect("module_cache poisoned")
+            .contains_key(k)
+    }
+
     /// Lookup a loaded wasmtime module.
     pub fn get_module(
         &self,
@@ -355,21 +756,20 @@ impl Engine {
             .entry(*k)
         {
             Occupied(v) => Ok(Some(v.get().clone())),
-            Vacant(v) => blockstore
-                .get(k)
-                .context("failed to lookup wasm module in blockstore")?
-                .map(|raw_wasm| Ok(v.insert(self.load_raw(&raw_wasm)?).clone()))
-                .transpose(),
+            Vacant(v) => Ok(self
+                .load(blockstore, k)?
+                .map(|module| v.insert(module).clone())),
         }
     }
 
-    /// Lookup and instantiate a loaded wasmtime module with the given store. This will cache the
-    /// linker, syscalls, etc.
+    /// Lookup and instantiate a loaded wasmtime module with the given store, returning the instance
+    /// along with the [`AbiVersion`] its `invoke` export follows. This will cache the linker,
+    /// syscalls, etc.
     pub fn get_instance<K: Kernel>(
         &self,
         store: &mut wasmtime::Store<InvocationData<K>>,
         k: &Cid,
-    ) -> anyhow::Result<Option<wasmtime::Instance>> {
+    ) -> anyhow::Result<Option<(wasmtime::Instance, AbiVersion)>> {
         let k = self.with_redirect(k);
         let mut instance_cache = self.0.instance_cache.lock().expect("cache poisoned");
 
@@ -385,51 +785,74 @@ impl Engine {
                     linker.allow_shadowing(true);
 
                     bind_syscalls(&mut linker)?;
-                    Box::new(Cache { linker })
+                    K::bind_extra_syscalls(&mut linker)?;
+                    Box::new(Cache {
+                        linker,
+                        instances: HashMap::new(),
+                    })
                 })
                 .downcast_mut()
                 .expect("invalid instance cache entry"),
         };
-        cache
-            .linker
-            .define("gas", GAS_COUNTER_NAME, store.data_mut().avail_gas_global)?;
-
-        let mut module_cache = self.0.module_cache.lock().expect("module_cache poisoned");
-        match module_cache.entry(*k) {
-            Occupied(v) => Ok(Some(cache.linker.instantiate(&mut *store, v.get())?)),
-            Vacant(v) => match store
-                .data()
-                .kernel
-                .machine()
-                .blockstore()
-                .get(k)
-                .context("failed to lookup wasm module in blockstore")?
-            {
-                Some(raw_wasm) => Ok(Some(
-                    cache
-                        .linker
-                        .instantiate(&mut *store, v.insert(self.load_raw(&raw_wasm)?))?,
-                )),
-                None => Ok(None),
-            },
-        }
+
+        let (instance_pre, abi_version) = match cache.instances.entry(*k) {
+            Occupied(e) => e.into_mut(),
+            Vacant(e) => {
+                let mut module_cache = self.0.module_cache.lock().expect("module_cache poisoned");
+                let module = match module_cache.entry(*k) {
+                    Occupied(v) => v.into_mut(),
+                    Vacant(v) => match self.load(store.data().kernel.machine().blockstore(), k)? {
+                        Some(module) => v.insert(module),
+                        None => return Ok(None),
+                    },
+                };
+                let abi_version = AbiVersion::of_module(module)
+                    .ok_or_else(|| anyhow!("module for CID {} has no valid invoke export", k))?;
+                e.insert((
+                    cache.linker.instantiate_pre(&mut *store, module)?,
+                    abi_version,
+                ))
+            }
+        };
+        let abi_version = *abi_version;
+
+        let instance = instance_pre.instantiate(&mut *store)?;
+
+        // Resolve and store a reference to the gas counter. The module defines its own (see
+        // `export_gas_counter`) so that it doesn't import anything specific to this store.
+        store.data_mut().avail_gas_global = instance
+            .get_global(&mut *store, GAS_COUNTER_EXPORT)
+            .context("actor has no gas counter")?;
+
+        Ok(Some((instance, abi_version)))
     }
 
     /// Construct a new wasmtime "store" from the given kernel.
     pub fn new_store<K: Kernel>(&self, kernel: K) -> wasmtime::Store<InvocationData<K>> {
+        let nv = kernel.network_version();
+        let unavailable_syscalls = kernel
+            .machine()
+            .context()
+            .feature_availability
+            .syscalls()
+            .filter(|&(_, _, since)| nv < since)
+            .collect();
         let id = InvocationData {
             kernel,
             last_error: None,
             avail_gas_global: self.0.dummy_gas_global,
             last_milligas_available: 0,
+            gas_multiplier: GasMultiplier::ONE,
             memory: self.0.dummy_memory,
+            limiter: InstanceLimiter {
+                max_memory_bytes: self.0.config.wasm_limits.max_memory_bytes,
+                max_table_elements: self.0.config.wasm_limits.max_table_elements,
+            },
+            unavailable_syscalls,
         };
 
         let mut store = wasmtime::Store::new(&self.0.engine, id);
-        let ggtype = GlobalType::new(ValType::I64, Mutability::Var);
-        let gg = Global::new(&mut store, ggtype, Val::I64(0))
-            .expect("failed to create available_gas global");
-        store.data_mut().avail_gas_global = gg;
+        store.limiter(|data| &mut data.limiter);
 
         store
     }
@@ -449,3 +872,64 @@ fn fix_wasm_sections(module: &mut elements::Module) {
             .expect("section wasn't deleted");
     }
 }
+
+/// The name under which instrumented modules export their gas counter.
+pub(super) const GAS_COUNTER_EXPORT: &str = "fvm_gas_counter";
+
+/// Replaces the gas counter imported by the gas instrumentation with a global defined and exported
+/// by the module itself. The module then doesn't import anything tied to a specific store, so it
+/// can be linked once and instantiated in any store.
+///
+/// Actor modules may only import functions, so the gas counter is the only imported global. It
+/// can therefore be replaced by the first defined global without renumbering any references.
+fn export_gas_counter(module: &mut elements::Module) -> anyhow::Result<()> {
+    use elements::{
+        ExportEntry, External, GlobalEntry, GlobalSection, InitExpr, Instruction, Internal,
+        Section, ValueType,
+    };
+
+    let imports = module
+        .import_section_mut()
+        .context("module has no imports")?
+        .entries_mut();
+    let global_imports: Vec<usize> = imports
+        .iter()
+        .enumerate()
+        .filter(|(_, i)| matches!(i.external(), External::Global(_)))
+        .map(|(idx, _)| idx)
+        .collect();
+    match global_imports[..] {
+        [idx] if imports[idx].module() == "gas" && imports[idx].field() == GAS_COUNTER_NAME => {
+            imports.remove(idx);
+        }
+        _ => {
+            return Err(anyhow!(
+                "expected the gas counter to be the only imported global"
+            ))
+        }
+    }
+
+    let gas_counter = GlobalEntry::new(
+        elements::GlobalType::new(ValueType::I64, true),
+        InitExpr::new(vec![Instruction::I64Const(0), Instruction::End]),
+    );
+    match module.global_section_mut() {
+        Some(globals) => globals.entries_mut().insert(0, gas_counter),
+        None => module
+            .insert_section(Section::Global(GlobalSection::with_entries(vec![
+                gas_counter,
+            ])))
+            .map_err(anyhow::Error::msg)?,
+    }
+
+    module
+        .export_section_mut()
+        .context("module has no exports")?
+        .entries_mut()
+        .push(ExportEntry::new(
+            GAS_COUNTER_EXPORT.into(),
+            Internal::Global(0),
+        ));
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+#[cfg(feature = "json")]
+use std::io::Write;
+
+use anyhow::{anyhow, Context as _};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+#[cfg(feature = "json")]
+use fvm_shared::address::Address;
+#[cfg(feature = "json")]
+use serde_json::Value;
+
+use super::{diff_state, ActorDiff};
+use crate::executor::StateReader;
+#[cfg(feature = "json")]
+use crate::inspect::{dump_actor, dump_all, InspectOptions};
+use crate::state_tree::StateTree;
+
+/// A machine opened on an arbitrary state root only to read state, e.g., in tools inspecting
+/// historical states.
+///
+/// Unlike a [`DefaultMachine`][super::DefaultMachine], it loads neither the builtin actors nor an
+/// engine, and needs no externs. It can't execute messages: it doesn't implement
+/// [`Machine`][super::Machine], so it can't be handed to an executor.
+///
+/// ```compile_fail
+/// use fvm::call_manager::DefaultCallManager;
+/// use fvm::executor::DefaultExecutor;
+/// use fvm::machine::InspectionMachine;
+/// use fvm::DefaultKernel;
+/// use fvm_ipld_blockstore::MemoryBlockstore;
+///
+/// fn execute(machine: InspectionMachine<MemoryBlockstore>) {
+///     DefaultExecutor::<DefaultKernel<DefaultCallManager<_>>>::new(machine);
+/// }
+/// ```
+pub struct InspectionMachine<B> {
+    state_root: Cid,
+    state_tree: StateTree<B>,
+}
+
+impl<B: Blockstore> InspectionMachine<B> {
+    /// Opens the state tree rooted at `state_root`, which must be in the blockstore.
+    pub fn new(blockstore: B, state_root: &Cid) -> anyhow::Result<Self> {
+        if !blockstore
+            .has(state_root)
+            .context("failed to load the state root")?
+        {
+            return Err(anyhow!(
+                "blockstore doesn't have the state root {}",
+                state_root
+            ));
+        }
+        let state_tree = StateTree::new_from_root(blockstore, state_root)
+            .map_err(anyhow::Error::from)
+            .context("failed to load state tree")?;
+        Ok(InspectionMachine {
+            state_root: *state_root,
+            state_tree,
+        })
+    }
+
+    /// The state root the machine was opened on.
+    pub fn state_root(&self) -> &Cid {
+        &self.state_root
+    }
+
+    /// The state tree.
+    pub fn state_tree(&self) -> &StateTree<B> {
+        &self.state_tree
+    }
+
+    /// The blockstore the state is read from.
+    pub fn blockstore(&self) -> &B {
+        self.state_tree.store()
+    }
+
+    /// Returns a reader over the state.
+    pub fn state_reader(&self) -> StateReader<'_, B> {
+        StateReader::new(&self.state_tree)
+    }
+
+    /// Renders the actor at `address`, or `None` if there's no such actor. See
+    /// [`inspect::dump_actor`][crate::inspect::dump_actor].
+    #[cfg(feature = "json")]
+    pub fn dump_actor(
+        &self,
+        address: &Address,
+        options: &InspectOptions,
+    ) -> anyhow::Result<Option<Value>> {
+        dump_actor(self.blockstore(), &self.state_root, address, options)
+    }
+
+    /// Renders every actor, writing one JSON object per line. See
+    /// [`inspect::dump_all`][crate::inspect::dump_all].
+    #[cfg(feature = "json")]
+    pub fn dump_all<W: Write>(&self, writer: W, options: &InspectOptions) -> anyhow::Result<()> {
+        dump_all(self.blockstore(), &self.state_root, writer, options)
+    }
+
+    /// Diffs this state against the state rooted at `other`, which must be in the same
+    /// blockstore. See [`diff_state`].
+    pub fn diff(&self, other: &Cid) -> anyhow::Result<Vec<ActorDiff>> {
+        diff_state(self.blockstore(), &self.state_root, other)
+    }
+
+    /// Consumes the machine, returning the blockstore.
+    pub fn into_store(self) -> B {
+        self.state_tree.into_store()
+    }
+}
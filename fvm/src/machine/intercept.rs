@@ -0,0 +1,40 @@
+use std::fmt::Debug;
+
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::{ActorID, MethodNum};
+
+use crate::gas::Gas;
+use crate::kernel::Block;
+
+/// Intercepts sends before the receiving actor is invoked, e.g., to fake the responses of some
+/// actor in a debugger or simulator. This changes execution, so it must never be used when
+/// validating the chain.
+pub trait CallInterceptor: Debug + Send {
+    /// Called for every send to an actor method (other than plain value transfers), after the
+    /// value has been transferred to the receiver. Returning `Some` completes the call with the
+    /// given result instead of invoking the receiver.
+    fn intercept(
+        &mut self,
+        from: ActorID,
+        to: ActorID,
+        method: MethodNum,
+        params: Option<&Block>,
+        value: &TokenAmount,
+    ) -> Option<InterceptResult>;
+}
+
+/// The result of an intercepted call.
+#[derive(Clone, Debug)]
+pub struct InterceptResult {
+    /// The exit code of the call. If it isn't [`ExitCode::OK`], the call fails, and the value
+    /// transfer and any other state changes made by the call are reverted as usual.
+    pub exit_code: ExitCode,
+    /// The DAG-CBOR encoded value returned to the caller if the call succeeds. Empty for no
+    /// return value.
+    pub return_data: RawBytes,
+    /// The gas charged for the call, in place of the gas the receiver would have used. It's charged
+    /// like any other gas, so the call may run out of gas.
+    pub gas: Gas,
+}
@@ -4,6 +4,9 @@ use anyhow::{anyhow, Context};
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::CborStore;
+use fvm_shared::MethodNum;
+
+use super::params::{builtin_param_schema, ParamSchema};
 
 const SINGLETON_ACTOR_NAMES: &[&str] = &[
     "system",
@@ -18,8 +21,13 @@ const SINGLETON_ACTOR_NAMES: &[&str] = &[
 const ACCOUNT_ACTOR_NAME: &str = "account";
 const INIT_ACTOR_NAME: &str = "init";
 const SYSTEM_ACTOR_NAME: &str = "system";
+const PLACEHOLDER_ACTOR_NAME: &str = "placeholder";
 
 /// A mapping of builtin actor CIDs to their respective types.
+///
+/// A manifest may also recognize the actors of other builtin-actors versions (see
+/// [`Manifest::add_legacy`]), treating them as the actors of the same name in this manifest.
+#[derive(Clone)]
 pub struct Manifest {
     account_code: Cid,
     system_code: Cid,
@@ -28,6 +36,10 @@ pub struct Manifest {
 
     by_id: HashMap<u32, Cid>,
     by_code: HashMap<Cid, u32>,
+    by_name: HashMap<String, Cid>,
+
+    /// Names of builtin actors by code CID, including those of legacy manifests.
+    names: HashMap<Cid, String>,
 }
 
 /// Create an "id CID" (for testing).
@@ -89,6 +101,7 @@ impl Manifest {
         let mut by_name = HashMap::new();
         let mut by_id = HashMap::new();
         let mut by_code = HashMap::new();
+        let mut names = HashMap::new();
 
         // Actors are indexed sequentially, starting at 1, in the order in which they appear in the
         // manifest. 0 is reserved for "everything else" (i.e., not a builtin actor).
@@ -96,6 +109,7 @@ impl Manifest {
             let name = name.into();
             by_id.insert(id, code_cid);
             by_code.insert(code_cid, id);
+            names.insert(code_cid, name.clone());
             by_name.insert(name, code_cid);
         }
 
@@ -124,27 +138,78 @@ impl Manifest {
             singletons,
             by_id,
             by_code,
+            by_name,
+            names,
         })
     }
 
+    /// Recognizes the builtin actors of `other` (e.g., the manifest of a previous network version)
+    /// as the actors of the same name in this manifest.
+    pub fn add_legacy(&mut self, other: &Manifest) {
+        for (name, code) in other.by_name.iter() {
+            self.names.entry(*code).or_insert_with(|| name.clone());
+        }
+    }
+
     /// Returns the code CID for a builtin actor, given the actor's ID.
     pub fn code_by_id(&self, id: u32) -> Option<&Cid> {
         self.by_id.get(&id)
     }
 
-    /// Returns the the actor code's "id" if it's a builtin actor. Otherwise, returns 0.
+    /// Returns the code CID for a builtin actor, given the actor's name (e.g., "account").
+    pub fn code_by_name(&self, name: &str) -> Option<&Cid> {
+        self.by_name.get(name)
+    }
+
+    /// Returns the name of the builtin actor with the given code CID, which may belong to this
+    /// manifest or to a legacy one.
+    pub fn name_by_code(&self, code: &Cid) -> Option<&str> {
+        self.names.get(code).map(String::as_str)
+    }
+
+    /// Returns the code CID in this manifest of the builtin actor with the given code CID, which may
+    /// belong to a legacy manifest. Any other code CID is returned as is.
+    pub fn resolve_code(&self, code: &Cid) -> Cid {
+        self.names
+            .get(code)
+            .and_then(|name| self.by_name.get(name))
+            .copied()
+            .unwrap_or(*code)
+    }
+
+    /// Returns the the actor code's "id" if it's a builtin actor (of this or a legacy manifest).
+    /// Otherwise, returns 0.
     pub fn id_by_code(&self, code: &Cid) -> u32 {
-        self.by_code.get(code).copied().unwrap_or(0)
+        self.by_code
+            .get(&self.resolve_code(code))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns the expected shape of the params of the given method of the builtin actor with the
+    /// given code, if known.
+    pub fn param_schema(&self, code: &Cid, method: MethodNum) -> Option<ParamSchema> {
+        builtin_param_schema(self.name_by_code(code)?, method)
     }
 
     /// Returns true id the passed code CID is the account actor.
     pub fn is_account_actor(&self, cid: &Cid) -> bool {
-        &self.account_code == cid
+        self.account_code == self.resolve_code(cid)
+    }
+
+    /// Returns true if the passed code CID is the placeholder actor.
+    pub fn is_placeholder_actor(&self, cid: &Cid) -> bool {
+        self.get_placeholder_code() == Some(&self.resolve_code(cid))
+    }
+
+    /// Returns true if the passed code CID is a builtin actor (of this or a legacy manifest).
+    pub fn is_builtin_actor(&self, cid: &Cid) -> bool {
+        self.by_code.contains_key(&self.resolve_code(cid))
     }
 
     /// Returns true id the passed code is a singleton actor.
     pub fn is_singleton_actor(&self, cid: &Cid) -> bool {
-        self.singletons.contains(cid)
+        self.singletons.contains(&self.resolve_code(cid))
     }
 
     pub fn builtin_actor_codes(&self) -> impl Iterator<Item = &Cid> {
@@ -165,4 +230,55 @@ impl Manifest {
     pub fn get_system_code(&self) -> &Cid {
         &self.system_code
     }
+
+    /// Returns the code CID for the placeholder actor, if the manifest has one. Placeholders hold
+    /// the funds sent to f4 addresses before the actor behind the address is deployed.
+    pub fn get_placeholder_code(&self) -> Option<&Cid> {
+        self.by_name.get(PLACEHOLDER_ACTOR_NAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_actors() {
+        let v1 = Manifest::dummy();
+        let v2_codes = [
+            ("system", id_cid(b"fil/test/v2/system")),
+            ("init", id_cid(b"fil/test/v2/init")),
+            ("account", id_cid(b"fil/test/v2/account")),
+            ("cron", id_cid(b"fil/test/v2/cron")),
+            ("multisig", id_cid(b"fil/test/v2/multisig")),
+        ];
+        let mut v2 = Manifest::new(v2_codes).unwrap();
+
+        let v1_account = *v1.code_by_name("account").unwrap();
+        let v1_cron = *v1.code_by_name("cron").unwrap();
+        let v2_account = *v2.code_by_name("account").unwrap();
+        let v2_cron = *v2.code_by_name("cron").unwrap();
+        assert!(!v2.is_account_actor(&v1_account));
+        assert!(!v2.is_builtin_actor(&v1_cron));
+        assert_eq!(v2.id_by_code(&v1_cron), 0);
+
+        v2.add_legacy(&v1);
+
+        assert_eq!(v2.resolve_code(&v1_account), v2_account);
+        assert_eq!(v2.resolve_code(&v2_account), v2_account);
+        assert!(v2.is_account_actor(&v1_account));
+        assert!(v2.is_singleton_actor(&v1_cron));
+        assert!(v2.is_builtin_actor(&v1_cron));
+        assert_eq!(v2.name_by_code(&v1_cron), Some("cron"));
+        assert_eq!(v2.id_by_code(&v1_cron), v2.id_by_code(&v2_cron));
+        assert_eq!(v2.id_by_code(&v2_cron), 4);
+
+        // Non-builtin code is left alone.
+        let other = id_cid(b"fil/test/other");
+        assert_eq!(v2.resolve_code(&other), other);
+        assert_eq!(v2.id_by_code(&other), 0);
+        assert!(!v2.is_builtin_actor(&other));
+        assert_eq!(v2.name_by_code(&other), None);
+        assert_eq!(v2.code_by_name("multisig"), Some(&v2_codes[4].1));
+    }
 }
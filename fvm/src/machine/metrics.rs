@@ -0,0 +1,145 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use fvm_shared::error::ExitCode;
+
+use super::FlushStats;
+
+/// Receives metrics from the machine, e.g., to export them to Prometheus. Every method defaults to
+/// doing nothing.
+///
+/// Implementations are called synchronously during execution, so they should be cheap.
+pub trait MachineMetrics: Debug + Send + Sync {
+    /// A message was applied, using `gas_used` gas and taking `wall_time` to execute.
+    fn record_message_applied(&self, gas_used: i64, wall_time: Duration, exit_code: ExitCode) {
+        let _ = (gas_used, wall_time, exit_code);
+    }
+
+    /// An actor was invoked, and its Wasm module was already compiled.
+    fn record_module_cache_hit(&self) {}
+
+    /// An actor was invoked, and its Wasm module had to be compiled.
+    fn record_module_cache_miss(&self) {}
+
+    /// An actor read or wrote blocks.
+    fn record_blockstore_io(&self, bytes_read: u64, bytes_written: u64) {
+        let _ = (bytes_read, bytes_written);
+    }
+
+    /// An actor's Wasm module was instantiated, taking `nanos` nanoseconds.
+    fn record_wasm_instantiation(&self, nanos: u64) {
+        let _ = nanos;
+    }
+
+    /// The machine was flushed.
+    fn record_flush(&self, stats: &FlushStats) {
+        let _ = stats;
+    }
+}
+
+/// The default [`MachineMetrics`], which discards everything.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopMetrics;
+
+impl MachineMetrics for NoopMetrics {}
+
+/// A [`MachineMetrics`] implementation that records everything in memory. Mostly useful for
+/// testing.
+#[derive(Debug, Default)]
+pub struct MemoryMetrics {
+    messages: Mutex<Vec<AppliedMessage>>,
+    module_cache_hits: AtomicU64,
+    module_cache_misses: AtomicU64,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    instantiations: Mutex<Vec<u64>>,
+    flushes: Mutex<Vec<FlushStats>>,
+}
+
+/// A message recorded by [`MemoryMetrics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppliedMessage {
+    pub gas_used: i64,
+    pub wall_time: Duration,
+    pub exit_code: ExitCode,
+}
+
+impl MemoryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the messages applied so far, in order.
+    pub fn messages(&self) -> Vec<AppliedMessage> {
+        self.messages.lock().expect("metrics poisoned").clone()
+    }
+
+    pub fn module_cache_hits(&self) -> u64 {
+        self.module_cache_hits.load(Ordering::Relaxed)
+    }
+
+    pub fn module_cache_misses(&self) -> u64 {
+        self.module_cache_misses.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written.load(Ordering::Relaxed)
+    }
+
+    /// Returns the duration of every Wasm instantiation so far, in nanoseconds.
+    pub fn instantiations(&self) -> Vec<u64> {
+        self.instantiations
+            .lock()
+            .expect("metrics poisoned")
+            .clone()
+    }
+
+    /// Returns the statistics of every flush so far, in order.
+    pub fn flushes(&self) -> Vec<FlushStats> {
+        self.flushes.lock().expect("metrics poisoned").clone()
+    }
+}
+
+impl MachineMetrics for MemoryMetrics {
+    fn record_message_applied(&self, gas_used: i64, wall_time: Duration, exit_code: ExitCode) {
+        self.messages
+            .lock()
+            .expect("metrics poisoned")
+            .push(AppliedMessage {
+                gas_used,
+                wall_time,
+                exit_code,
+            });
+    }
+
+    fn record_module_cache_hit(&self) {
+        self.module_cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_module_cache_miss(&self) {
+        self.module_cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_blockstore_io(&self, bytes_read: u64, bytes_written: u64) {
+        self.bytes_read.fetch_add(bytes_read, Ordering::Relaxed);
+        self.bytes_written
+            .fetch_add(bytes_written, Ordering::Relaxed);
+    }
+
+    fn record_wasm_instantiation(&self, nanos: u64) {
+        self.instantiations
+            .lock()
+            .expect("metrics poisoned")
+            .push(nanos);
+    }
+
+    fn record_flush(&self, stats: &FlushStats) {
+        self.flushes.lock().expect("metrics poisoned").push(*stats);
+    }
+}
@@ -0,0 +1,420 @@
+//! Migrates the state of every actor in a state tree, e.g. when a network upgrade changes the
+//! builtin actors' state schemas.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Context};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::ActorID;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::state_tree::{ActorState, StateTree};
+
+/// Migrates the state of actors with a given code CID.
+pub trait Migrator<BS>: Send + Sync {
+    /// Migrates an actor's state, returning its new code CID and state root.
+    fn migrate_state(&self, store: &BS, head: &Cid) -> anyhow::Result<(Cid, Cid)>;
+}
+
+/// Migrates the state of an actor (usually a singleton) that depends on the state of other actors.
+/// Deferred migrations run after all other actors have been migrated, in the order the actors
+/// appear in the state tree.
+pub trait DeferredMigrator<BS>: Send + Sync {
+    /// Migrates the state of actor `id`, returning its new code CID and state root. `tree` is the
+    /// migrated state tree.
+    fn migrate_state(
+        &self,
+        tree: &StateTree<&BS>,
+        id: ActorID,
+        head: &Cid,
+    ) -> anyhow::Result<(Cid, Cid)>;
+}
+
+/// A [`Migrator`] that changes an actor's code, leaving its state untouched.
+#[derive(Debug, Clone, Copy)]
+pub struct CodeMigrator(pub Cid);
+
+impl<BS> Migrator<BS> for CodeMigrator {
+    fn migrate_state(&self, _store: &BS, head: &Cid) -> anyhow::Result<(Cid, Cid)> {
+        Ok((self.0, *head))
+    }
+}
+
+/// The migrations to apply to a state tree, keyed by the code CID of the actors they apply to.
+/// Every actor in the tree must have a migration.
+pub struct Migrations<BS> {
+    migrators: HashMap<Cid, Box<dyn Migrator<BS>>>,
+    deferred: HashMap<Cid, Box<dyn DeferredMigrator<BS>>>,
+}
+
+impl<BS> Default for Migrations<BS> {
+    fn default() -> Self {
+        Migrations {
+            migrators: HashMap::new(),
+            deferred: HashMap::new(),
+        }
+    }
+}
+
+impl<BS> Migrations<BS> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Migrates actors with code `old_code` with the specified migrator.
+    pub fn add(&mut self, old_code: Cid, migrator: impl Migrator<BS> + 'static) -> &mut Self {
+        self.migrators.insert(old_code, Box::new(migrator));
+        self
+    }
+
+    /// Migrates actors with code `old_code` with the specified deferred migrator, once all other
+    /// actors have been migrated.
+    pub fn add_deferred(
+        &mut self,
+        old_code: Cid,
+        migrator: impl DeferredMigrator<BS> + 'static,
+    ) -> &mut Self {
+        self.deferred.insert(old_code, Box::new(migrator));
+        self
+    }
+}
+
+/// Caches the results of (non-deferred) actor migrations by the actor's old code CID and state
+/// root. Reusing a cache across runs of the same migrations (e.g., a "premigration" a few epochs
+/// before the upgrade, then the migration itself) means only actors whose state changed in between
+/// are migrated again.
+///
+/// A cache must only be reused with the same set of migrations.
+#[derive(Debug, Default)]
+pub struct MigrationCache(RwLock<HashMap<(Cid, Cid), (Cid, Cid)>>);
+
+impl MigrationCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of cached migration results.
+    pub fn len(&self) -> usize {
+        self.0.read().expect("migration cache poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get(&self, code: &Cid, head: &Cid) -> Option<(Cid, Cid)> {
+        self.0
+            .read()
+            .expect("migration cache poisoned")
+            .get(&(*code, *head))
+            .copied()
+    }
+
+    fn insert(&self, code: Cid, head: Cid, result: (Cid, Cid)) {
+        self.0
+            .write()
+            .expect("migration cache poisoned")
+            .insert((code, head), result);
+    }
+}
+
+/// Migrates every actor in the state tree rooted at `old_root`, returning the root of the migrated
+/// state tree.
+///
+/// Actors are migrated in parallel on `workers` threads (or one per CPU, if `workers` is 0). The
+/// result doesn't depend on the number of workers.
+pub fn migrate_state_tree<BS>(
+    store: &BS,
+    old_root: &Cid,
+    migrations: &Migrations<BS>,
+    cache: &MigrationCache,
+    workers: usize,
+) -> anyhow::Result<Cid>
+where
+    BS: Blockstore + Sync,
+{
+    let old_tree = StateTree::new_from_root(store, old_root)
+        .map_err(anyhow::Error::from)
+        .context("failed to load state tree")?;
+
+    let mut actors: Vec<(ActorID, ActorState)> = Vec::new();
+    old_tree.for_each(|addr, actor| {
+        actors.push((addr.id()?, actor.clone()));
+        Ok(())
+    })?;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(workers)
+        .build()
+        .context("failed to start migration workers")?;
+    let migrated = pool.install(|| {
+        actors
+            .par_iter()
+            .filter(|(_, actor)| !migrations.deferred.contains_key(&actor.code))
+            .map(|(id, actor)| {
+                let (code, state) = migrate_actor(store, migrations, cache, actor)
+                    .with_context(|| format!("failed to migrate actor {}", id))?;
+                Ok((
+                    *id,
+                    ActorState {
+                        code,
+                        state,
+                        ..actor.clone()
+                    },
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+    })?;
+
+    let mut new_tree = StateTree::new(store, old_tree.version()).map_err(anyhow::Error::from)?;
+    for (id, actor) in migrated {
+        new_tree
+            .set_actor_id(id, actor)
+            .map_err(anyhow::Error::from)?;
+    }
+
+    // Flush so deferred migrations can iterate over the migrated actors.
+    new_tree.flush().map_err(anyhow::Error::from)?;
+    for (id, actor) in &actors {
+        if let Some(migrator) = migrations.deferred.get(&actor.code) {
+            let (code, state) = migrator
+                .migrate_state(&new_tree, *id, &actor.state)
+                .with_context(|| format!("failed to migrate actor {}", id))?;
+            new_tree
+                .set_actor_id(
+                    *id,
+                    ActorState {
+                        code,
+                        state,
+                        ..actor.clone()
+                    },
+                )
+                .map_err(anyhow::Error::from)?;
+        }
+    }
+
+    new_tree
+        .flush()
+        .map_err(anyhow::Error::from)
+        .context("failed to flush migrated state tree")
+}
+
+fn migrate_actor<BS>(
+    store: &BS,
+    migrations: &Migrations<BS>,
+    cache: &MigrationCache,
+    actor: &ActorState,
+) -> anyhow::Result<(Cid, Cid)> {
+    if let Some(result) = cache.get(&actor.code, &actor.state) {
+        return Ok(result);
+    }
+    let migrator = migrations
+        .migrators
+        .get(&actor.code)
+        .ok_or_else(|| anyhow!("no migration for actor code {}", actor.code))?;
+    let result = migrator.migrate_state(store, &actor.state)?;
+    cache.insert(actor.code, actor.state, result);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use cid::multihash::{Code, Multihash};
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::CborStore;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::state::StateTreeVersion;
+    use fvm_shared::{IDENTITY_HASH, IPLD_RAW};
+    use num_traits::Zero;
+
+    use super::*;
+
+    fn code(name: &str) -> Cid {
+        Cid::new_v1(
+            IPLD_RAW,
+            Multihash::wrap(IDENTITY_HASH, name.as_bytes()).unwrap(),
+        )
+    }
+
+    /// Migrates counters (a `u64` state) to (value, double the value) pairs.
+    struct CounterMigrator(Arc<AtomicUsize>);
+
+    impl Migrator<MemoryBlockstore> for CounterMigrator {
+        fn migrate_state(
+            &self,
+            store: &MemoryBlockstore,
+            head: &Cid,
+        ) -> anyhow::Result<(Cid, Cid)> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let count: u64 = store.get_cbor(head)?.context("missing state")?;
+            let head = store.put_cbor(&(count, count * 2), Code::Blake2b256)?;
+            Ok((code("counter/v2"), head))
+        }
+    }
+
+    /// Migrates a registry to the sum of all migrated counters.
+    struct RegistryMigrator;
+
+    impl DeferredMigrator<MemoryBlockstore> for RegistryMigrator {
+        fn migrate_state(
+            &self,
+            tree: &StateTree<&MemoryBlockstore>,
+            _id: ActorID,
+            _head: &Cid,
+        ) -> anyhow::Result<(Cid, Cid)> {
+            let mut total = 0;
+            tree.for_each(|_, actor| {
+                if actor.code == code("counter/v2") {
+                    let (count, _): (u64, u64) = tree
+                        .store()
+                        .get_cbor(&actor.state)?
+                        .context("missing state")?;
+                    total += count;
+                }
+                Ok(())
+            })?;
+            let head = tree.store().put_cbor(&total, Code::Blake2b256)?;
+            Ok((code("registry/v2"), head))
+        }
+    }
+
+    /// Builds a tree with a registry, 200 counters, and 50 accounts.
+    fn fixture(store: &MemoryBlockstore) -> StateTree<&MemoryBlockstore> {
+        let mut tree = StateTree::new(store, StateTreeVersion::V4).unwrap();
+        let empty = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        tree.set_actor_id(
+            10,
+            ActorState::new(code("registry/v1"), empty, TokenAmount::zero(), 0),
+        )
+        .unwrap();
+        for id in 100..300u64 {
+            let head = store.put_cbor(&id, Code::Blake2b256).unwrap();
+            let actor = ActorState::new(code("counter/v1"), head, TokenAmount::from_atto(id), id);
+            tree.set_actor_id(id, actor).unwrap();
+        }
+        for id in 1000..1050u64 {
+            let actor = ActorState::new(code("account/v1"), empty, TokenAmount::zero(), 1);
+            tree.set_actor_id(id, actor).unwrap();
+        }
+        tree
+    }
+
+    fn migrations(calls: &Arc<AtomicUsize>) -> Migrations<MemoryBlockstore> {
+        let mut migrations = Migrations::new();
+        migrations
+            .add(code("counter/v1"), CounterMigrator(calls.clone()))
+            .add(code("account/v1"), CodeMigrator(code("account/v2")))
+            .add_deferred(code("registry/v1"), RegistryMigrator);
+        migrations
+    }
+
+    #[test]
+    fn migrate() {
+        let store = MemoryBlockstore::default();
+        let root = fixture(&store).flush().unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let new_root = migrate_state_tree(
+            &store,
+            &root,
+            &migrations(&calls),
+            &MigrationCache::new(),
+            4,
+        )
+        .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 200);
+
+        let tree = StateTree::new_from_root(&store, &new_root).unwrap();
+        let counter = tree.get_actor_id(150).unwrap().unwrap();
+        assert_eq!(counter.code, code("counter/v2"));
+        assert_eq!(counter.sequence, 150);
+        assert_eq!(counter.balance, TokenAmount::from_atto(150));
+        let state: (u64, u64) = store.get_cbor(&counter.state).unwrap().unwrap();
+        assert_eq!(state, (150, 300));
+
+        let account = tree.get_actor_id(1000).unwrap().unwrap();
+        assert_eq!(account.code, code("account/v2"));
+
+        let registry = tree.get_actor_id(10).unwrap().unwrap();
+        assert_eq!(registry.code, code("registry/v2"));
+        let total: u64 = store.get_cbor(&registry.state).unwrap().unwrap();
+        assert_eq!(total, (100..300u64).sum::<u64>());
+    }
+
+    #[test]
+    fn deterministic_across_workers() {
+        let store = MemoryBlockstore::default();
+        let root = fixture(&store).flush().unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let migrations = migrations(&calls);
+
+        let roots: Vec<Cid> = [1, 8]
+            .into_iter()
+            .map(|workers| {
+                migrate_state_tree(&store, &root, &migrations, &MigrationCache::new(), workers)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(roots[0], roots[1]);
+    }
+
+    #[test]
+    fn premigration_cache() {
+        let store = MemoryBlockstore::default();
+        let mut tree = fixture(&store);
+        let premigration_root = tree.flush().unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let migrations = migrations(&calls);
+        let cache = MigrationCache::new();
+
+        migrate_state_tree(&store, &premigration_root, &migrations, &cache, 4).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 200);
+        // The accounts all share the same code and state.
+        assert_eq!(cache.len(), 201);
+
+        // Change a single counter before the upgrade; only that counter is migrated again.
+        let head = store.put_cbor(&1234u64, Code::Blake2b256).unwrap();
+        tree.mutate_actor_id(150, |actor| {
+            actor.state = head;
+            Ok(())
+        })
+        .unwrap();
+        let root = tree.flush().unwrap();
+
+        let cached = migrate_state_tree(&store, &root, &migrations, &cache, 4).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 201);
+
+        let uncached =
+            migrate_state_tree(&store, &root, &migrations, &MigrationCache::new(), 4).unwrap();
+        assert_eq!(cached, uncached);
+    }
+
+    #[test]
+    fn missing_migration() {
+        let store = MemoryBlockstore::default();
+        let mut tree = fixture(&store);
+        let empty = store.put_cbor(&(), Code::Blake2b256).unwrap();
+        tree.set_actor_id(
+            2000,
+            ActorState::new(code("unknown"), empty, TokenAmount::zero(), 0),
+        )
+        .unwrap();
+        let root = tree.flush().unwrap();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let err = migrate_state_tree(
+            &store,
+            &root,
+            &migrations(&calls),
+            &MigrationCache::new(),
+            4,
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("no migration for actor code"));
+    }
+}
@@ -1,17 +1,26 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 use cid::Cid;
 use derive_more::{Deref, DerefMut};
 use fvm_ipld_blockstore::Blockstore;
 use fvm_shared::address::Address;
+use fvm_shared::builtin::singletons::{INIT_ACTOR_ID, REWARD_ACTOR_ID, SYSTEM_ACTOR_ID};
+use fvm_shared::chainid::ChainID;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::version::NetworkVersion;
-use fvm_shared::ActorID;
+use fvm_shared::{ActorID, TOTAL_FILECOIN};
 use num_traits::Zero;
 
+use crate::executor::SenderPolicy;
 use crate::externs::Externs;
 use crate::gas::{price_list_by_network_version, PriceList};
 use crate::kernel::Result;
 use crate::state_tree::{ActorState, StateTree};
+use crate::syscall_error;
 
 mod default;
 
@@ -23,14 +32,56 @@ pub use manifest::Manifest;
 
 mod engine;
 
-pub use engine::{Engine, EngineConfig, MultiEngine};
+pub(crate) use engine::InstanceLimiter;
+pub use engine::{Engine, EngineConfig, ModuleCache, ModuleCacheKey, MultiEngine};
 
 mod boxed;
 
-pub const REWARD_ACTOR_ADDR: Address = Address::new_id(2);
+pub mod migration;
+
+mod diff;
+
+pub use diff::{diff_state, ActorDiff, FieldDiff};
+
+mod inspection;
+
+pub use inspection::InspectionMachine;
+
+mod validation;
+
+pub use validation::{ValidationError, WasmFeatureSet, WasmLimits};
+
+mod availability;
+
+pub use availability::FeatureAvailability;
+
+mod bootstrap;
+
+pub use bootstrap::{MachineError, MissingPiece};
+
+mod abi;
+
+pub use abi::{AbiVersion, ABI_VERSION_EXPORT};
+
+mod intercept;
+
+pub use intercept::{CallInterceptor, InterceptResult};
 
-/// Distinguished Account actor that is the destination of all burnt funds.
-pub const BURNT_FUNDS_ACTOR_ADDR: Address = Address::new_id(99);
+mod metrics;
+
+mod supply;
+
+pub use supply::{CirculatingSupplyCalculator, LockedFunds, VestingTranche};
+
+pub mod params;
+
+pub use fvm_shared::builtin::singletons::{BURNT_FUNDS_ACTOR_ADDR, REWARD_ACTOR_ADDR};
+pub use metrics::{AppliedMessage, MachineMetrics, MemoryMetrics, NoopMetrics};
+
+pub use crate::state_tree::CacheStats;
+
+/// The default [`MachineContext::required_singletons`].
+const DEFAULT_REQUIRED_SINGLETONS: &[ActorID] = &[SYSTEM_ACTOR_ID, INIT_ACTOR_ID, REWARD_ACTOR_ID];
 
 /// The Machine is the top-level object of the FVM.
 ///
@@ -77,11 +128,33 @@ pub trait Machine: 'static {
     ///
     /// If either the receiver or the sender do not exist, this method fails with a FATAL error.
     /// Otherwise, if the amounts are invalid, etc., it fails with a syscall error.
+    ///
+    /// A transfer from an actor to itself leaves its balance untouched, but still fails if the
+    /// actor's balance is less than the value.
+    ///
+    /// Values must pass [`check_transfer_value`]: transfers of negative values (or, from nv17, of
+    /// more than the total supply) fail with `IllegalArgument`. This is the only place transfer
+    /// values are checked.
     fn transfer(&mut self, from: ActorID, to: ActorID, value: &TokenAmount) -> Result<()>;
 
-    /// Flushes the state-tree and returns the new root CID.
-    fn flush(&mut self) -> Result<Cid> {
-        self.state_tree_mut().flush()
+    /// Flushes the state-tree and returns the new root CID, along with statistics about the
+    /// flush.
+    ///
+    /// The default implementation only flushes the state tree, so it reports no blocks written or
+    /// skipped.
+    fn flush(&mut self) -> Result<FlushStats> {
+        let start = Instant::now();
+        let state_tree = self.state_tree_mut();
+        let (root, tree) = state_tree.flush_with_stats()?;
+        Ok(FlushStats {
+            root,
+            cache: state_tree.cache_stats(),
+            dirty_actors: tree.dirty_actors,
+            hamt_nodes_written: tree.hamt_nodes_written,
+            blocks_written: 0,
+            blocks_skipped: 0,
+            wall_time: start.elapsed(),
+        })
     }
 
     /// Consumes the machine and returns the owned blockstore.
@@ -91,6 +164,42 @@ pub trait Machine: 'static {
     fn machine_id(&self) -> &str;
 }
 
+/// Statistics about a [`Machine::flush`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlushStats {
+    /// The new state root.
+    pub root: Cid,
+    /// The state tree's cache hits and misses since the machine was constructed.
+    pub cache: CacheStats,
+    /// The actors created, modified, or deleted since the last flush.
+    pub dirty_actors: u64,
+    /// The state tree's HAMT nodes rewritten, including its root.
+    pub hamt_nodes_written: u64,
+    /// The blocks copied from the write buffer to the underlying blockstore.
+    pub blocks_written: u64,
+    /// The blocks dropped from the write buffer because they're unreachable from the new root.
+    pub blocks_skipped: u64,
+    /// How long the flush took.
+    pub wall_time: Duration,
+}
+
+/// Checks that a value can be transferred between actors under the given network version: it may
+/// not be negative and, from nv17, it may not be more than the total supply. (Before nv17, such
+/// transfers fail with `InsufficientFunds` instead, as no actor can have that much.)
+pub fn check_transfer_value(nv: NetworkVersion, value: &TokenAmount) -> Result<()> {
+    if value.is_negative() {
+        return Err(syscall_error!(IllegalArgument;
+            "attempted to transfer negative value {}", value)
+        .into());
+    }
+    if nv >= NetworkVersion::V17 && value > &*TOTAL_FILECOIN {
+        return Err(syscall_error!(IllegalArgument;
+            "attempted to transfer {}, more than the total supply", value)
+        .into());
+    }
+    Ok(())
+}
+
 /// Network-level settings. Except when testing locally, changing any of these likely requires a
 /// network upgrade.
 #[derive(Debug, Clone)]
@@ -103,16 +212,43 @@ pub struct NetworkConfig {
     /// DEFAULT: 4096
     pub max_call_depth: u32,
 
-    /// The maximum number of elements on wasm stack
-    /// DEFAULT: 64Ki (512KiB of u64 elements)
+    /// The maximum number of elements on the wasm stack of a single actor invocation, enforced by
+    /// instrumenting the actor's code so that it fails identically on every platform. Exceeding it
+    /// fails the invocation with `SYS_ILLEGAL_INSTRUCTION`.
+    ///
+    /// DEFAULT: 2048
     pub max_wasm_stack: u32,
 
+    /// The maximum size of the parameters an actor may pass when sending a message.
+    ///
+    /// DEFAULT: 1MiB from nv17, unlimited before
+    pub max_params_size: usize,
+
+    /// The maximum size of the value an actor may return.
+    ///
+    /// DEFAULT: 1MiB from nv17, unlimited before
+    pub max_return_size: usize,
+
+    /// The maximum total size of the blocks held by the block registries of all actors in a
+    /// call stack.
+    ///
+    /// DEFAULT: 64MiB from nv17, unlimited before
+    pub max_block_registry_bytes: usize,
+
     /// An override for builtin-actors. If specified, this should be the CID of a builtin-actors
     /// "manifest".
     ///
     /// DEFAULT: `None`
     pub builtin_actors_override: Option<Cid>,
 
+    /// Builtin-actors "manifests" (like [`NetworkConfig::builtin_actors_override`]) for specific
+    /// network versions. The machine recognizes the actors of every one of these versions, and can
+    /// switch between them with [`DefaultMachine::set_network_version`]. The manifest for the
+    /// network version itself, if present, takes precedence over the override.
+    ///
+    /// DEFAULT: empty
+    pub builtin_actors_by_version: BTreeMap<NetworkVersion, Cid>,
+
     /// Enable actor debugging.
     ///
     /// DEFAULT: `false`
@@ -121,23 +257,104 @@ pub struct NetworkConfig {
     /// The price list.
     ///
     /// DEFAULT: The price-list for the current network version.
-    pub price_list: &'static PriceList,
+    pub price_list: Cow<'static, PriceList>,
 
     /// Actor redirects for debug execution
     pub actor_redirect: Vec<(Cid, Cid)>,
+
+    /// The network's chain ID, exposed to actors to prevent cross-chain replay.
+    ///
+    /// DEFAULT: 0
+    pub chain_id: ChainID,
+
+    /// Static limits enforced on actor Wasm modules when they're loaded.
+    ///
+    /// DEFAULT: [`WasmLimits::for_network_version`]
+    pub wasm_limits: WasmLimits,
+
+    /// The Wasm proposals actor code may use.
+    ///
+    /// DEFAULT: [`WasmFeatureSet::default()`]
+    pub wasm_features: WasmFeatureSet,
+
+    /// An actor that may, in addition to the System actor, install and upgrade actor code. This
+    /// should only be set on devnets.
+    ///
+    /// DEFAULT: `None`
+    pub upgrade_admin: Option<ActorID>,
+
+    /// Receives metrics about message execution. This doesn't affect consensus.
+    ///
+    /// DEFAULT: [`NoopMetrics`]
+    pub metrics: Arc<dyn MachineMetrics>,
+
+    /// Reject params that can't be decoded by the target builtin actor method (see
+    /// [`params`]) with [`ExitCode::USR_SERIALIZATION`](fvm_shared::error::ExitCode::USR_SERIALIZATION),
+    /// without instantiating the actor. This affects gas usage, so it's consensus-critical.
+    ///
+    /// DEFAULT: `false`
+    pub validate_builtin_params: bool,
+
+    /// Intercepts sends, e.g., to fake the responses of some actors when debugging. This changes
+    /// execution, so it must never be set when validating the chain.
+    ///
+    /// DEFAULT: `None`
+    pub call_interceptor: Option<Arc<Mutex<dyn CallInterceptor>>>,
+
+    /// Which actors may send explicit messages. Only network versions after nv15 may let actors
+    /// other than accounts send messages.
+    ///
+    /// DEFAULT: [`SenderPolicy::AccountsOnly`]
+    pub sender_policy: SenderPolicy,
+
+    /// The network versions from which syscalls and builtin-actor methods are available. See
+    /// [`FeatureAvailability`].
+    ///
+    /// DEFAULT: [`FeatureAvailability::default()`]
+    pub feature_availability: FeatureAvailability,
+
+    /// The maximum number of actors a single message may create: accounts created by sends to new
+    /// key addresses, placeholders created by sends to new f4 addresses, and actors deployed by the
+    /// init actor. Creating more fails with `LimitExceeded`. This affects execution, so it's
+    /// consensus-critical.
+    ///
+    /// DEFAULT: `None` (no limit)
+    pub max_actors_created: Option<u64>,
 }
 
 impl NetworkConfig {
     /// Create a new network config for the given network version.
     pub fn new(network_version: NetworkVersion) -> Self {
+        // The params, return and block registry limits are only enforced from nv17.
+        let limit = |max: usize| {
+            if network_version >= NetworkVersion::V17 {
+                max
+            } else {
+                usize::MAX
+            }
+        };
         NetworkConfig {
             network_version,
             max_call_depth: 1024,
             max_wasm_stack: 2048,
+            max_params_size: limit(1 << 20),
+            max_return_size: limit(1 << 20),
+            max_block_registry_bytes: limit(64 << 20),
             actor_debugging: false,
             builtin_actors_override: None,
-            price_list: price_list_by_network_version(network_version),
+            builtin_actors_by_version: BTreeMap::new(),
+            price_list: Cow::Borrowed(price_list_by_network_version(network_version)),
             actor_redirect: vec![],
+            chain_id: ChainID::default(),
+            wasm_limits: WasmLimits::for_network_version(network_version),
+            wasm_features: WasmFeatureSet::default(),
+            upgrade_admin: None,
+            metrics: Arc::new(NoopMetrics),
+            validate_builtin_params: false,
+            call_interceptor: None,
+            sender_policy: SenderPolicy::AccountsOnly,
+            feature_availability: FeatureAvailability::default(),
+            max_actors_created: None,
         }
     }
 
@@ -155,6 +372,82 @@ impl NetworkConfig {
         self
     }
 
+    /// Use the specified builtin-actors manifest for the given network version. See
+    /// [`NetworkConfig::builtin_actors_by_version`].
+    pub fn override_actors_for(&mut self, nv: NetworkVersion, manifest: Cid) -> &mut Self {
+        self.builtin_actors_by_version.insert(nv, manifest);
+        self
+    }
+
+    /// Override the price list, e.g., with one returned by [`PriceList::with_overrides`] or
+    /// [`PriceList::with_actor_multipliers`]. This is a consensus-critical option, so it should only
+    /// be used for local testing, devnets, or gas calibration. Machines can't be constructed for
+    /// mainnet with actor gas multipliers.
+    pub fn override_price_list(&mut self, price_list: PriceList) -> &mut Self {
+        self.price_list = Cow::Owned(price_list);
+        self
+    }
+
+    /// Set the network's chain ID.
+    pub fn chain_id(&mut self, id: ChainID) -> &mut Self {
+        self.chain_id = id;
+        self
+    }
+
+    /// Allow the specified actor to install and upgrade actor code. This is a consensus-critical
+    /// option, so it should only be used for local testing or devnets.
+    pub fn set_upgrade_admin(&mut self, actor_id: ActorID) -> &mut Self {
+        self.upgrade_admin = Some(actor_id);
+        self
+    }
+
+    /// Validate params sent to builtin actors before instantiating them. See
+    /// [`NetworkConfig::validate_builtin_params`]. This is a consensus-critical option, so it
+    /// should only be enabled for local testing or as a network-wide parameter.
+    pub fn enable_builtin_param_validation(&mut self) -> &mut Self {
+        self.validate_builtin_params = true;
+        self
+    }
+
+    /// Record metrics about message execution with the given [`MachineMetrics`].
+    pub fn set_metrics(&mut self, metrics: Arc<dyn MachineMetrics>) -> &mut Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Intercept sends with the given [`CallInterceptor`]. See
+    /// [`NetworkConfig::call_interceptor`].
+    pub fn set_call_interceptor(
+        &mut self,
+        interceptor: Arc<Mutex<dyn CallInterceptor>>,
+    ) -> &mut Self {
+        self.call_interceptor = Some(interceptor);
+        self
+    }
+
+    /// Let actors send explicit messages according to the given [`SenderPolicy`]. This is a
+    /// consensus-critical option, so it should only be used for local testing or as a network-wide
+    /// parameter.
+    pub fn set_sender_policy(&mut self, policy: SenderPolicy) -> &mut Self {
+        self.sender_policy = policy;
+        self
+    }
+
+    /// Use the given [`FeatureAvailability`] instead of the default one. This is a
+    /// consensus-critical option, so it should only be used for local testing or devnets.
+    pub fn set_feature_availability(&mut self, availability: FeatureAvailability) -> &mut Self {
+        self.feature_availability = availability;
+        self
+    }
+
+    /// Limit the number of actors a single message may create. See
+    /// [`NetworkConfig::max_actors_created`]. This is a consensus-critical option, so it should
+    /// only be used for local testing or as a network-wide parameter.
+    pub fn limit_actors_created(&mut self, max: u64) -> &mut Self {
+        self.max_actors_created = Some(max);
+        self
+    }
+
     /// Set actor redirects for debug execution
     pub fn redirect_actors(&mut self, actor_redirect: Vec<(Cid, Cid)>) -> &mut Self {
         self.actor_redirect = actor_redirect;
@@ -173,7 +466,11 @@ impl NetworkConfig {
             },
             initial_state_root: initial_state,
             circ_supply: fvm_shared::TOTAL_FILECOIN.clone(),
+            supply_calculator: None,
             tracing: false,
+            gas_calibration: false,
+            check_invariants: false,
+            required_singletons: DEFAULT_REQUIRED_SINGLETONS.to_vec(),
         }
     }
 
@@ -188,7 +485,11 @@ impl NetworkConfig {
             network_context: net_ctx,
             initial_state_root: initial_state,
             circ_supply: fvm_shared::TOTAL_FILECOIN.clone(),
+            supply_calculator: None,
             tracing: false,
+            gas_calibration: false,
+            check_invariants: false,
+            required_singletons: DEFAULT_REQUIRED_SINGLETONS.to_vec(),
         }
     }
 }
@@ -198,7 +499,10 @@ pub struct NetworkContext {
     /// The network epoch at which the Machine runs.
     pub epoch: ChainEpoch,
 
-    /// The UNIX timestamp (in seconds) of the current tipset
+    /// The UNIX timestamp (in seconds) of the current tipset. This is the same for every message
+    /// in the tipset, and never decreases from one tipset to the next.
+    ///
+    /// Default: 0.
     pub timestamp: u64,
 
     /// The tipset CIDs for the last finality
@@ -231,9 +535,31 @@ pub struct MachineContext {
     /// DEFAULT: Total FIL supply (likely not what you want).
     pub circ_supply: TokenAmount,
 
+    /// Dynamic supply mode: when set, [`MachineContext::circ_supply`] is ignored, and the
+    /// circulating supply is computed from the initial state when the machine is constructed.
+    ///
+    /// DEFAULT: `None`
+    pub supply_calculator: Option<Arc<CirculatingSupplyCalculator>>,
+
     /// Whether or not to produce execution traces in the returned result.
     /// Not consensus-critical, but has a performance impact.
     pub tracing: bool,
+
+    /// Whether or not to collect per-charge gas statistics (see [`crate::gas::GasStats`]).
+    /// Not consensus-critical, but has a performance impact.
+    pub gas_calibration: bool,
+
+    /// Whether or not to check that every message conserves the total FIL balance, leaves no
+    /// negative balances, and never decreases an actor's sequence. Violations are returned as
+    /// errors from the executor.
+    /// Not consensus-critical, but has a performance impact.
+    pub check_invariants: bool,
+
+    /// The singleton actors that must exist in the initial state tree for the machine to be
+    /// constructed (see [`MachineError::Bootstrap`]). Test trees may require fewer.
+    ///
+    /// DEFAULT: the system, init, and reward actors.
+    pub required_singletons: Vec<ActorID>,
 }
 
 impl MachineContext {
@@ -243,15 +569,49 @@ impl MachineContext {
         self
     }
 
+    /// Sets [`NetworkContext::timestamp`] to the timestamp of the tipset being executed.
+    pub fn set_timestamp(&mut self, timestamp: u64) -> &mut Self {
+        self.network_context.timestamp = timestamp;
+        self
+    }
+
     /// Set [`MachineContext::circ_supply`].
     pub fn set_circulating_supply(&mut self, amt: TokenAmount) -> &mut Self {
         self.circ_supply = amt;
         self
     }
 
+    /// Compute the circulating supply from the initial state with the given calculator.
+    /// [`MachineContext::supply_calculator`].
+    pub fn enable_dynamic_supply(
+        &mut self,
+        calculator: Arc<CirculatingSupplyCalculator>,
+    ) -> &mut Self {
+        self.supply_calculator = Some(calculator);
+        self
+    }
+
     /// Enable execution traces. [`MachineContext::tracing`].
     pub fn enable_tracing(&mut self) -> &mut Self {
         self.tracing = true;
         self
     }
+
+    /// Enable gas calibration. [`MachineContext::gas_calibration`].
+    pub fn enable_gas_calibration(&mut self) -> &mut Self {
+        self.gas_calibration = true;
+        self
+    }
+
+    /// Enable invariant checks. [`MachineContext::check_invariants`].
+    pub fn enable_invariant_checks(&mut self) -> &mut Self {
+        self.check_invariants = true;
+        self
+    }
+
+    /// Set [`MachineContext::required_singletons`].
+    pub fn require_singletons(&mut self, ids: impl IntoIterator<Item = ActorID>) -> &mut Self {
+        self.required_singletons = ids.into_iter().collect();
+        self
+    }
 }
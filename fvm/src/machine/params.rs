@@ -0,0 +1,131 @@
+//! The expected shape of the parameters of builtin actor methods.
+//!
+//! When [`NetworkConfig::validate_builtin_params`](super::NetworkConfig::validate_builtin_params)
+//! is enabled, params that clearly can't be decoded by the target builtin method are rejected
+//! before the actor is instantiated. User actors define their own params, so they can't be
+//! pre-validated.
+
+use fvm_ipld_encoding::DAG_CBOR;
+use fvm_shared::MethodNum;
+
+use crate::kernel::Block;
+
+/// The major type of a CBOR data item (the top 3 bits of its first byte).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MajorType {
+    UnsignedInt = 0,
+    NegativeInt = 1,
+    ByteString = 2,
+    TextString = 3,
+    Array = 4,
+    Map = 5,
+    Tag = 6,
+    Simple = 7,
+}
+
+impl MajorType {
+    /// Returns the major type of the CBOR data item starting with the given byte.
+    pub fn of(initial_byte: u8) -> Self {
+        match initial_byte >> 5 {
+            0 => Self::UnsignedInt,
+            1 => Self::NegativeInt,
+            2 => Self::ByteString,
+            3 => Self::TextString,
+            4 => Self::Array,
+            5 => Self::Map,
+            6 => Self::Tag,
+            _ => Self::Simple,
+        }
+    }
+}
+
+/// The expected shape of a builtin method's params.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParamSchema {
+    /// The major type of the top-level CBOR data item.
+    pub major_type: MajorType,
+    /// The maximum size of the encoded params, if the params have a bounded size.
+    pub max_size: Option<usize>,
+}
+
+impl ParamSchema {
+    const fn new(major_type: MajorType, max_size: Option<usize>) -> Self {
+        Self {
+            major_type,
+            max_size,
+        }
+    }
+
+    /// Returns `true` if the params may be valid. Params must be DAG-CBOR, fit in `max_size`, and
+    /// start with a data item of the expected major type.
+    pub fn accepts(&self, params: &Block) -> bool {
+        let data = params.data();
+        params.codec() == DAG_CBOR
+            && self.max_size.map_or(true, |max| data.len() <= max)
+            && data.first().map(|&b| MajorType::of(b)) == Some(self.major_type)
+    }
+}
+
+/// The maximum size of an encoded address.
+const ADDRESS: Option<usize> = Some(128);
+/// The maximum size of small, fixed-shape tuples (e.g., a transaction ID and a hash).
+const SMALL_TUPLE: Option<usize> = Some(1 << 10);
+
+/// Returns the schema of the params of the given method of the builtin actor with the given
+/// manifest name, if known. Only methods whose params have had the same shape in every
+/// builtin-actors version are listed.
+pub fn builtin_param_schema(actor: &str, method: MethodNum) -> Option<ParamSchema> {
+    use MajorType::*;
+
+    let schema = match (actor, method) {
+        // Constructor
+        ("account", 1) => ParamSchema::new(ByteString, ADDRESS),
+        // Constructor, Exec
+        ("init", 1 | 2) => ParamSchema::new(Array, None),
+        // AwardBlockReward
+        ("reward", 2) => ParamSchema::new(Array, SMALL_TUPLE),
+        // CreateMiner
+        ("storagepower", 2) => ParamSchema::new(Array, None),
+        // AddBalance
+        ("storagemarket", 2) => ParamSchema::new(ByteString, ADDRESS),
+        // WithdrawBalance
+        ("storagemarket", 3) => ParamSchema::new(Array, SMALL_TUPLE),
+        // Constructor, Propose
+        ("multisig", 1 | 2) => ParamSchema::new(Array, None),
+        // Approve, Cancel, AddSigner, RemoveSigner, SwapSigner, ChangeNumApprovalsThreshold,
+        // LockBalance
+        ("multisig", 3..=9) => ParamSchema::new(Array, SMALL_TUPLE),
+        // Constructor
+        ("paymentchannel", 1) => ParamSchema::new(Array, SMALL_TUPLE),
+        // UpdateChannelState
+        ("paymentchannel", 2) => ParamSchema::new(Array, None),
+        _ => return None,
+    };
+    Some(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::IPLD_RAW;
+
+    use super::*;
+
+    #[test]
+    fn accepts() {
+        let schema = builtin_param_schema("multisig", 3).unwrap();
+        let block = |codec, data: &[u8]| Block::new(codec, data.to_vec());
+
+        assert!(schema.accepts(&block(DAG_CBOR, &[0x82, 0x00, 0x40])));
+        // Wrong major type, empty, or not CBOR.
+        assert!(!schema.accepts(&block(DAG_CBOR, &[0x01])));
+        assert!(!schema.accepts(&block(DAG_CBOR, &[])));
+        assert!(!schema.accepts(&block(IPLD_RAW, &[0x82, 0x00, 0x40])));
+        // Too large.
+        let mut large = vec![0x82, 0x00, 0x59, 0x08, 0x00];
+        large.resize(large.len() + 0x800, 0);
+        assert!(!schema.accepts(&block(DAG_CBOR, &large)));
+
+        assert_eq!(builtin_param_schema("multisig", 100), None);
+        assert_eq!(builtin_param_schema("evm", 2), None);
+    }
+}
@@ -0,0 +1,297 @@
+use std::fmt::Debug;
+
+use anyhow::{anyhow, Context as _};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::builtin::singletons::{
+    BURNT_FUNDS_ACTOR_ID, RESERVE_ACTOR_ID, REWARD_ACTOR_ID, STORAGE_MARKET_ACTOR_ID,
+    STORAGE_POWER_ACTOR_ID,
+};
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::supply;
+use fvm_shared::ActorID;
+use num_traits::Zero;
+
+use crate::state_tree::{ActorState, StateTree};
+
+/// Reads the FIL locked by the storage market (deal collateral and storage fees) and the storage
+/// power actor (pledge collateral) from their states.
+///
+/// The layout of these states is defined by the builtin actors, so embedders provide it.
+pub trait LockedFunds: Debug + Send + Sync {
+    /// Returns the total FIL locked by the storage market and power actors, given their actor
+    /// states.
+    fn locked_funds(
+        &self,
+        blockstore: &dyn Blockstore,
+        market: &ActorState,
+        power: &ActorState,
+    ) -> anyhow::Result<TokenAmount>;
+}
+
+/// An amount vesting linearly over `duration` epochs from `start_epoch`, like the genesis
+/// multisigs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VestingTranche {
+    pub amount: TokenAmount,
+    pub start_epoch: ChainEpoch,
+    pub duration: i64,
+}
+
+impl VestingTranche {
+    /// Returns the amount vested at `epoch`. Like a multisig, the locked remainder is rounded
+    /// down.
+    pub fn vested_at(&self, epoch: ChainEpoch) -> TokenAmount {
+        let elapsed = match epoch.checked_since(self.start_epoch) {
+            Some(elapsed) => elapsed,
+            None if epoch > self.start_epoch => i64::MAX,
+            None => i64::MIN,
+        };
+        if elapsed >= self.duration {
+            return self.amount.clone();
+        }
+        if elapsed <= 0 {
+            return TokenAmount::zero();
+        }
+        let locked = (self.amount.clone() * (self.duration - elapsed)).div_floor(self.duration);
+        self.amount.clone() - locked
+    }
+}
+
+/// Computes the circulating supply from a state tree, as
+///
+/// ```text
+/// vested + mined + reserve disbursed - burnt - locked
+/// ```
+///
+/// where:
+///
+/// - `vested` is the FIL vested by the genesis [tranches][VestingTranche].
+/// - `mined` is the FIL paid out by the reward actor since genesis (i.e., the
+///   [storage mining allocation][supply::STORAGE_MINING_ALLOCATION] minus its balance).
+/// - `reserve disbursed` is the FIL paid out by the reserve actor since genesis.
+/// - `burnt` is the balance of the burnt funds actor.
+/// - `locked` is the FIL locked by the market and power actors, read by [`LockedFunds`].
+///
+/// Negative results are clamped to zero.
+///
+/// See [`MachineContext::enable_dynamic_supply`][super::MachineContext::enable_dynamic_supply].
+#[derive(Debug)]
+pub struct CirculatingSupplyCalculator {
+    vesting: Vec<VestingTranche>,
+    locked: Box<dyn LockedFunds>,
+}
+
+impl CirculatingSupplyCalculator {
+    /// Creates a calculator for a network with the given genesis vesting schedule.
+    pub fn new(vesting: Vec<VestingTranche>, locked: impl LockedFunds + 'static) -> Self {
+        CirculatingSupplyCalculator {
+            vesting,
+            locked: Box::new(locked),
+        }
+    }
+
+    /// The genesis vesting schedule.
+    pub fn vesting(&self) -> &[VestingTranche] {
+        &self.vesting
+    }
+
+    /// Returns the FIL vested by the genesis tranches at `epoch`.
+    pub fn vested(&self, epoch: ChainEpoch) -> TokenAmount {
+        self.vesting.iter().map(|t| t.vested_at(epoch)).sum()
+    }
+
+    /// Computes the circulating supply at `epoch`, given the state tree at that epoch.
+    pub fn circulating_supply<B: Blockstore>(
+        &self,
+        state_tree: &StateTree<B>,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<TokenAmount> {
+        let balance = |id| get_singleton(state_tree, id).map(|act| act.balance);
+
+        let mined = supply::storage_mining_allocation() - balance(REWARD_ACTOR_ID)?;
+        let disbursed = supply::mining_reserve_allocation() - balance(RESERVE_ACTOR_ID)?;
+        let burnt = balance(BURNT_FUNDS_ACTOR_ID)?;
+        let locked = self
+            .locked
+            .locked_funds(
+                state_tree.store(),
+                &get_singleton(state_tree, STORAGE_MARKET_ACTOR_ID)?,
+                &get_singleton(state_tree, STORAGE_POWER_ACTOR_ID)?,
+            )
+            .context("failed to read locked funds")?;
+
+        let circ = self.vested(epoch) + mined + disbursed - burnt - locked;
+        Ok(circ.max(TokenAmount::zero()))
+    }
+}
+
+fn get_singleton<B: Blockstore>(
+    state_tree: &StateTree<B>,
+    id: ActorID,
+) -> anyhow::Result<ActorState> {
+    state_tree
+        .get_actor_id(id)
+        .map_err(anyhow::Error::from)
+        .with_context(|| format!("failed to load actor {}", id))?
+        .ok_or_else(|| anyhow!("actor {} doesn't exist", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::Multihash;
+    use cid::Cid;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::DAG_CBOR;
+    use fvm_shared::state::StateTreeVersion;
+    use fvm_shared::IDENTITY_HASH;
+
+    use super::*;
+
+    /// Reports a fixed amount of locked funds.
+    #[derive(Debug)]
+    struct FixedLocked(TokenAmount);
+
+    impl LockedFunds for FixedLocked {
+        fn locked_funds(
+            &self,
+            _: &dyn Blockstore,
+            _: &ActorState,
+            _: &ActorState,
+        ) -> anyhow::Result<TokenAmount> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn empty_cid() -> Cid {
+        Cid::new_v1(DAG_CBOR, Multihash::wrap(IDENTITY_HASH, &[]).unwrap())
+    }
+
+    fn set_balance(tree: &mut StateTree<&MemoryBlockstore>, id: ActorID, balance: TokenAmount) {
+        tree.set_actor_id(id, ActorState::new(empty_cid(), empty_cid(), balance, 0))
+            .unwrap();
+    }
+
+    /// Builds the singletons of a genesis state.
+    fn genesis(store: &MemoryBlockstore) -> StateTree<&MemoryBlockstore> {
+        let mut tree = StateTree::new(store, StateTreeVersion::V4).unwrap();
+        set_balance(
+            &mut tree,
+            REWARD_ACTOR_ID,
+            supply::storage_mining_allocation(),
+        );
+        set_balance(
+            &mut tree,
+            RESERVE_ACTOR_ID,
+            supply::mining_reserve_allocation(),
+        );
+        for id in [
+            BURNT_FUNDS_ACTOR_ID,
+            STORAGE_MARKET_ACTOR_ID,
+            STORAGE_POWER_ACTOR_ID,
+        ] {
+            set_balance(&mut tree, id, TokenAmount::zero());
+        }
+        tree
+    }
+
+    fn calculator(locked: TokenAmount) -> CirculatingSupplyCalculator {
+        CirculatingSupplyCalculator::new(
+            vec![
+                VestingTranche {
+                    amount: TokenAmount::from_whole(10_000),
+                    start_epoch: ChainEpoch::new(0),
+                    duration: 0,
+                },
+                VestingTranche {
+                    amount: TokenAmount::from_whole(3_000),
+                    start_epoch: ChainEpoch::new(0),
+                    duration: 300,
+                },
+            ],
+            FixedLocked(locked),
+        )
+    }
+
+    #[test]
+    fn genesis_supply() {
+        let store = MemoryBlockstore::default();
+        let tree = genesis(&store);
+        let calc = calculator(TokenAmount::zero());
+
+        // Only the tranche vesting immediately is circulating.
+        assert_eq!(
+            calc.circulating_supply(&tree, ChainEpoch::new(0)).unwrap(),
+            TokenAmount::from_whole(10_000)
+        );
+        assert_eq!(
+            calc.circulating_supply(&tree, ChainEpoch::new(100))
+                .unwrap(),
+            TokenAmount::from_whole(11_000)
+        );
+        assert_eq!(
+            calc.circulating_supply(&tree, ChainEpoch::new(1_000))
+                .unwrap(),
+            TokenAmount::from_whole(13_000)
+        );
+    }
+
+    #[test]
+    fn mined_disbursed_burnt_and_locked() {
+        let store = MemoryBlockstore::default();
+        let mut tree = genesis(&store);
+        set_balance(
+            &mut tree,
+            REWARD_ACTOR_ID,
+            supply::storage_mining_allocation() - TokenAmount::from_whole(500),
+        );
+        set_balance(
+            &mut tree,
+            RESERVE_ACTOR_ID,
+            supply::mining_reserve_allocation() - TokenAmount::from_whole(50),
+        );
+        set_balance(&mut tree, BURNT_FUNDS_ACTOR_ID, TokenAmount::from_whole(20));
+
+        let calc = calculator(TokenAmount::from_whole(30));
+        assert_eq!(
+            calc.circulating_supply(&tree, ChainEpoch::new(0)).unwrap(),
+            TokenAmount::from_whole(10_000 + 500 + 50 - 20 - 30)
+        );
+
+        // The supply never goes negative.
+        let calc = calculator(TokenAmount::from_whole(1_000_000));
+        assert_eq!(
+            calc.circulating_supply(&tree, ChainEpoch::new(0)).unwrap(),
+            TokenAmount::zero()
+        );
+    }
+
+    #[test]
+    fn missing_singleton() {
+        let store = MemoryBlockstore::default();
+        let tree = StateTree::new(&store, StateTreeVersion::V4).unwrap();
+        assert!(calculator(TokenAmount::zero())
+            .circulating_supply(&tree, ChainEpoch::new(0))
+            .is_err());
+    }
+
+    #[test]
+    fn vesting_rounds_locked_down() {
+        let tranche = VestingTranche {
+            amount: TokenAmount::from_atto(10),
+            start_epoch: ChainEpoch::new(5),
+            duration: 3,
+        };
+        assert_eq!(tranche.vested_at(ChainEpoch::new(0)), TokenAmount::zero());
+        assert_eq!(tranche.vested_at(ChainEpoch::new(5)), TokenAmount::zero());
+        // 10 * 2 / 3 = 6 locked.
+        assert_eq!(
+            tranche.vested_at(ChainEpoch::new(6)),
+            TokenAmount::from_atto(4)
+        );
+        assert_eq!(
+            tranche.vested_at(ChainEpoch::new(8)),
+            TokenAmount::from_atto(10)
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use fvm_wasm_instrument::parity_wasm::elements::{
+    External, FunctionType, Instruction, Internal, Module, Type, ValueType,
+};
+
+use fvm_shared::version::NetworkVersion;
+
+use super::abi::{AbiVersion, ABI_VERSION_EXPORT};
+use super::engine::GAS_COUNTER_EXPORT;
+use crate::syscalls::is_syscall_import;
+
+/// The size of a Wasm page, in bytes.
+pub(super) const WASM_PAGE_SIZE: usize = 64 << 10;
+
+/// The size of the largest memory a 32-bit Wasm module can address, in bytes.
+const MAX_WASM32_MEMORY_BYTES: usize = 4 << 30;
+
+/// Static limits enforced on actor Wasm modules when they're first loaded. Changing any of these
+/// likely requires a network upgrade.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct WasmLimits {
+    /// The maximum size of a Wasm module, in bytes.
+    ///
+    /// DEFAULT: 16MiB
+    pub max_module_size: usize,
+
+    /// The maximum number of functions (imported and defined).
+    ///
+    /// DEFAULT: 100_000
+    pub max_functions: usize,
+
+    /// The maximum number of globals.
+    ///
+    /// DEFAULT: 1_000
+    pub max_globals: usize,
+
+    /// The maximum number of tables.
+    ///
+    /// DEFAULT: 1
+    pub max_tables: usize,
+
+    /// The maximum number of elements in a table, either initially or after growing it.
+    ///
+    /// DEFAULT: 65_536 (unlimited before network version 17)
+    pub max_table_elements: u32,
+
+    /// The maximum size of an actor's memory, in bytes, either initially or after growing it.
+    ///
+    /// DEFAULT: 512MiB (4GiB, the most a 32-bit module can address, before network version 17)
+    pub max_memory_bytes: usize,
+
+    /// Allow floating point types and instructions. Floating point is a source of
+    /// non-determinism, so this should only be enabled for local testing.
+    ///
+    /// DEFAULT: `false`
+    pub allow_floats: bool,
+
+    /// Syscalls, as `(module, name)` pairs, actors may import in addition to the standard ones.
+    /// These must be bound by the kernel, see [`Kernel::bind_extra_syscalls`].
+    ///
+    /// DEFAULT: none
+    ///
+    /// [`Kernel::bind_extra_syscalls`]: crate::kernel::Kernel::bind_extra_syscalls
+    pub extra_syscall_imports: Vec<(String, String)>,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        WasmLimits {
+            max_module_size: 16 << 20,
+            max_functions: 100_000,
+            max_globals: 1_000,
+            max_tables: 1,
+            max_table_elements: 65_536,
+            max_memory_bytes: 512 << 20,
+            allow_floats: false,
+            extra_syscall_imports: vec![],
+        }
+    }
+}
+
+impl WasmLimits {
+    /// Returns the default limits for the given network version. Actor memories and tables are
+    /// only limited from network version 17.
+    pub fn for_network_version(nv: NetworkVersion) -> Self {
+        let limits = WasmLimits::default();
+        if nv >= NetworkVersion::V17 {
+            return limits;
+        }
+        WasmLimits {
+            max_table_elements: u32::MAX,
+            max_memory_bytes: MAX_WASM32_MEMORY_BYTES,
+            ..limits
+        }
+    }
+}
+
+/// The Wasm proposals the engine accepts, on top of the MVP. Modules using a disabled proposal are
+/// rejected when they're loaded, never when they're instantiated. Threads are never supported, as
+/// they can't be made deterministic.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct WasmFeatureSet {
+    /// The SIMD proposal. Not supported by the instrumentation.
+    ///
+    /// DEFAULT: `false`
+    pub simd: bool,
+
+    /// The bulk memory proposal.
+    ///
+    /// DEFAULT: `true`
+    pub bulk_memory: bool,
+
+    /// The reference types proposal. Requires `bulk_memory`.
+    ///
+    /// DEFAULT: `false`
+    pub reference_types: bool,
+
+    /// The multi-value proposal.
+    ///
+    /// DEFAULT: `false`
+    pub multi_value: bool,
+}
+
+impl Default for WasmFeatureSet {
+    fn default() -> Self {
+        WasmFeatureSet {
+            simd: false,
+            bulk_memory: true,
+            reference_types: false,
+            multi_value: false,
+        }
+    }
+}
+
+/// The reason an actor's Wasm module was rejected.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("invalid wasm: {0}")]
+    InvalidWasm(String),
+    #[error("module is {0} bytes, exceeding the limit of {1} bytes")]
+    ModuleTooLarge(usize, usize),
+    #[error("module has {1} {0}, exceeding the limit of {2}")]
+    TooMany(&'static str, usize, usize),
+    #[error("module imports unknown function {0}::{1}")]
+    UnknownImport(String, String),
+    #[error("module imports a non-function {0}::{1}")]
+    NonFunctionImport(String, String),
+    #[error("module uses floating point")]
+    FloatingPoint,
+    #[error("module has no {0} export")]
+    MissingExport(&'static str),
+    #[error("module's invoke export has the wrong type")]
+    InvalidInvokeExport,
+    #[error("module exports reserved name {0}")]
+    ReservedExport(String),
+    #[error("module's abi_version export must be an immutable i32 constant")]
+    InvalidAbiVersionExport,
+    #[error("module uses unsupported ABI version {0}")]
+    UnsupportedAbiVersion(i32),
+}
+
+/// Statically validates an (uninstrumented) actor Wasm module. SIMD and other unsupported
+/// proposals are rejected earlier, when the module is parsed.
+pub(crate) fn validate_module(module: &Module, limits: &WasmLimits) -> Result<(), ValidationError> {
+    for (what, count, max) in [
+        ("functions", module.functions_space(), limits.max_functions),
+        ("globals", module.globals_space(), limits.max_globals),
+        ("tables", module.table_space(), limits.max_tables),
+    ] {
+        if count > max {
+            return Err(ValidationError::TooMany(what, count, max));
+        }
+    }
+
+    let tables = module
+        .table_section()
+        .map(|s| s.entries())
+        .unwrap_or_default();
+    for table in tables {
+        let elements = table.limits().initial() as usize;
+        let max = limits.max_table_elements as usize;
+        if elements > max {
+            return Err(ValidationError::TooMany("table elements", elements, max));
+        }
+    }
+
+    let memories = module
+        .memory_section()
+        .map(|s| s.entries())
+        .unwrap_or_default();
+    for memory in memories {
+        let bytes = memory.limits().initial() as usize * WASM_PAGE_SIZE;
+        if bytes > limits.max_memory_bytes {
+            return Err(ValidationError::TooMany(
+                "bytes of memory",
+                bytes,
+                limits.max_memory_bytes,
+            ));
+        }
+    }
+
+    let imports = module
+        .import_section()
+        .map(|s| s.entries())
+        .unwrap_or_default();
+    for import in imports {
+        let (m, f) = (import.module(), import.field());
+        if !matches!(import.external(), External::Function(_)) {
+            return Err(ValidationError::NonFunctionImport(m.into(), f.into()));
+        }
+        let is_extra = limits
+            .extra_syscall_imports
+            .iter()
+            .any(|(em, ef)| em == m && ef == f);
+        if !is_syscall_import(m, f) && !is_extra {
+            return Err(ValidationError::UnknownImport(m.into(), f.into()));
+        }
+    }
+
+    if !limits.allow_floats && uses_floats(module) {
+        return Err(ValidationError::FloatingPoint);
+    }
+
+    let exports = module
+        .export_section()
+        .map(|s| s.entries())
+        .unwrap_or_default();
+
+    if !exports
+        .iter()
+        .any(|e| e.field() == "memory" && matches!(e.internal(), Internal::Memory(_)))
+    {
+        return Err(ValidationError::MissingExport("memory"));
+    }
+
+    if let Some(e) = exports.iter().find(|e| e.field() == GAS_COUNTER_EXPORT) {
+        return Err(ValidationError::ReservedExport(e.field().into()));
+    }
+
+    let invoke_idx = exports
+        .iter()
+        .find_map(|e| match e.internal() {
+            Internal::Function(idx) if e.field() == "invoke" => Some(*idx),
+            _ => None,
+        })
+        .ok_or(ValidationError::MissingExport("invoke"))?;
+
+    let abi_version = match exports.iter().find(|e| e.field() == ABI_VERSION_EXPORT) {
+        Some(e) => match e.internal() {
+            Internal::Global(idx) => abi_version(module, *idx)?,
+            _ => return Err(ValidationError::InvalidAbiVersionExport),
+        },
+        None => AbiVersion::V1,
+    };
+
+    // The invoke method takes the params block ID, and returns whatever the ABI version dictates.
+    let result = match abi_version {
+        AbiVersion::V1 => ValueType::I32,
+        AbiVersion::V2 => ValueType::I64,
+    };
+    match function_type(module, invoke_idx) {
+        Some(ty) if ty.params() == [ValueType::I32] && ty.results() == [result] => Ok(()),
+        _ => Err(ValidationError::InvalidInvokeExport),
+    }
+}
+
+/// Reads the ABI version declared by the global at the given index. Globals can't be imported, so
+/// the index is into the module's own globals.
+fn abi_version(module: &Module, idx: u32) -> Result<AbiVersion, ValidationError> {
+    let global = module
+        .global_section()
+        .and_then(|s| s.entries().get(idx as usize))
+        .ok_or(ValidationError::InvalidAbiVersionExport)?;
+    let ty = global.global_type();
+    if ty.is_mutable() || ty.content_type() != ValueType::I32 {
+        return Err(ValidationError::InvalidAbiVersionExport);
+    }
+    match global.init_expr().code() {
+        [Instruction::I32Const(version), Instruction::End] => {
+            AbiVersion::new(*version).ok_or(ValidationError::UnsupportedAbiVersion(*version))
+        }
+        _ => Err(ValidationError::InvalidAbiVersionExport),
+    }
+}
+
+/// Looks up the type of the function at the given index in the function index space (imports
+/// first, then defined functions).
+fn function_type(module: &Module, idx: u32) -> Option<&FunctionType> {
+    let imported: Vec<u32> = module
+        .import_section()
+        .map(|s| s.entries())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|i| match i.external() {
+            External::Function(ty) => Some(*ty),
+            _ => None,
+        })
+        .collect();
+
+    let type_idx = match imported.get(idx as usize) {
+        Some(ty) => *ty,
+        None => module
+            .function_section()?
+            .entries()
+            .get(idx as usize - imported.len())?
+            .type_ref(),
+    };
+
+    match module.type_section()?.types().get(type_idx as usize)? {
+        Type::Function(ty) => Some(ty),
+    }
+}
+
+fn is_float_type(ty: &ValueType) -> bool {
+    matches!(ty, ValueType::F32 | ValueType::F64)
+}
+
+fn uses_floats(module: &Module) -> bool {
+    let types = module.type_section().map(|s| s.types()).unwrap_or_default();
+    if types.iter().any(|Type::Function(ty)| {
+        ty.params().iter().any(is_float_type) || ty.results().iter().any(is_float_type)
+    }) {
+        return true;
+    }
+
+    let globals = module
+        .global_section()
+        .map(|s| s.entries())
+        .unwrap_or_default();
+    if globals
+        .iter()
+        .any(|g| is_float_type(&g.global_type().content_type()))
+    {
+        return true;
+    }
+
+    let bodies = module
+        .code_section()
+        .map(|s| s.bodies())
+        .unwrap_or_default();
+    bodies.iter().any(|body| {
+        body.locals().iter().any(|l| is_float_type(&l.value_type()))
+            || body.code().elements().iter().any(is_float_instruction)
+    })
+}
+
+fn is_float_instruction(instr: &Instruction) -> bool {
+    use Instruction::*;
+    matches!(
+        instr,
+        F32Load(..)
+            | F64Load(..)
+            | F32Store(..)
+            | F64Store(..)
+            | F32Const(_)
+            | F64Const(_)
+            | F32Eq
+            | F32Ne
+            | F32Lt
+            | F32Gt
+            | F32Le
+            | F32Ge
+            | F64Eq
+            | F64Ne
+            | F64Lt
+            | F64Gt
+            | F64Le
+            | F64Ge
+            | F32Abs
+            | F32Neg
+            | F32Ceil
+            | F32Floor
+            | F32Trunc
+            | F32Nearest
+            | F32Sqrt
+            | F32Add
+            | F32Sub
+            | F32Mul
+            | F32Div
+            | F32Min
+            | F32Max
+            | F32Copysign
+            | F64Abs
+            | F64Neg
+            | F64Ceil
+            | F64Floor
+            | F64Trunc
+            | F64Nearest
+            | F64Sqrt
+            | F64Add
+            | F64Sub
+            | F64Mul
+            | F64Div
+            | F64Min
+            | F64Max
+            | F64Copysign
+            | I32TruncSF32
+            | I32TruncUF32
+            | I32TruncSF64
+            | I32TruncUF64
+            | I64TruncSF32
+            | I64TruncUF32
+            | I64TruncSF64
+            | I64TruncUF64
+            | F32ConvertSI32
+            | F32ConvertUI32
+            | F32ConvertSI64
+            | F32ConvertUI64
+            | F32DemoteF64
+            | F64ConvertSI32
+            | F64ConvertUI32
+            | F64ConvertSI64
+            | F64ConvertUI64
+            | F64PromoteF32
+            | I32ReinterpretF32
+            | I64ReinterpretF64
+            | F32ReinterpretI32
+            | F64ReinterpretI64
+    )
+}
@@ -0,0 +1,80 @@
+//! This module contains the minimal logic for the FVM to handle placeholder actor
+//! auto-creation (on first transfer to an f4 address whose actor doesn't exist yet).
+//!
+//! The placeholder actor has no state of its own: when the actor the f4 address belongs to is
+//! deployed, it replaces the placeholder, keeping its balance.
+
+use anyhow::Context;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use num_traits::Zero;
+
+use crate::kernel::{ClassifyResult, Result};
+use crate::machine::Manifest;
+use crate::state_tree::ActorState;
+use crate::{put_empty_arr, EMPTY_ARR_CID};
+
+/// Returns an ActorState representing a brand new placeholder for the f4 address `addr`, with no
+/// balance, or `None` if `manifest` has no placeholder actor: its code is the placeholder code of
+/// `manifest`, and its state is the empty array (see [`EMPTY_ARR_CID`]), which is written to
+/// `store`.
+pub fn zero_state(
+    manifest: &Manifest,
+    store: &impl Blockstore,
+    addr: &Address,
+) -> Result<Option<ActorState>> {
+    let code = match manifest.get_placeholder_code() {
+        Some(code) => *code,
+        None => return Ok(None),
+    };
+    put_empty_arr(store)
+        .context("failed to write the placeholder zero state")
+        .or_fatal()?;
+    Ok(Some(ActorState {
+        delegated_address: Some(*addr),
+        ..ActorState::new(code, *EMPTY_ARR_CID, TokenAmount::zero(), 0)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::{Code, MultihashDigest};
+    use cid::Cid;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::IPLD_RAW;
+
+    use super::*;
+
+    #[test]
+    fn placeholder_zero_state() {
+        let addr = Address::new_delegated(10, b"foobar").unwrap();
+        let store = MemoryBlockstore::default();
+
+        // Networks without a placeholder actor can't create placeholders.
+        assert_eq!(zero_state(&Manifest::dummy(), &store, &addr).unwrap(), None);
+        assert!(!store.has(&EMPTY_ARR_CID).unwrap());
+
+        let placeholder_code =
+            Cid::new_v1(IPLD_RAW, Code::Identity.digest(b"fil/test/placeholder"));
+        let manifest = Manifest::new(
+            Manifest::DUMMY_CODES
+                .iter()
+                .copied()
+                .chain([("placeholder", placeholder_code)]),
+        )
+        .unwrap();
+        let state = zero_state(&manifest, &store, &addr).unwrap().unwrap();
+        assert_eq!(
+            state,
+            ActorState {
+                code: placeholder_code,
+                state: *EMPTY_ARR_CID,
+                sequence: 0,
+                balance: TokenAmount::zero(),
+                delegated_address: Some(addr),
+            }
+        );
+        assert_eq!(store.get(&EMPTY_ARR_CID).unwrap(), Some(vec![0x80]));
+    }
+}
@@ -1,19 +1,20 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
 
 use anyhow::{anyhow, Context as _};
 use cid::{multihash, Cid};
 use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::tuple::*;
-use fvm_ipld_encoding::CborStore;
+use fvm_ipld_encoding::{de, ser, CborStore};
 use fvm_ipld_hamt::Hamt;
 use fvm_shared::address::{Address, Payload};
 use fvm_shared::econ::TokenAmount;
-use fvm_shared::state::{StateInfo0, StateRoot, StateTreeVersion};
+use fvm_shared::state::{StateRoot, StateTreeVersion};
 use fvm_shared::{ActorID, HAMT_BIT_WIDTH};
+use libipld_core::ipld::Ipld;
+use serde::{Deserialize, Serialize};
 
 use crate::init_actor::State as InitActorState;
 use crate::kernel::{ClassifyResult, Context as _, ExecutionError, Result};
@@ -22,13 +23,142 @@ use crate::syscall_error;
 /// State tree implementation using hamt. This structure is not threadsafe and should only be used
 /// in sync contexts.
 pub struct StateTree<S> {
-    hamt: Hamt<S, ActorState>,
+    hamt: Hamt<S, StoredActorState>,
 
     version: StateTreeVersion,
-    info: Option<Cid>,
+    /// The CID of the state tree's info block.
+    info: Cid,
 
     /// State cache
     snaps: StateSnapshots,
+
+    /// The actors accessed since access tracking began, if enabled.
+    accesses: Option<AccessTracker>,
+
+    /// Cache hits and misses since the state tree was loaded.
+    cache_stats: Cell<CacheStats>,
+
+    /// The accesses made since metering began, if enabled.
+    metered: RefCell<Option<Vec<StateAccess>>>,
+}
+
+/// An access to the state tree, recorded while metering is enabled (see
+/// [`StateTree::begin_metering`]) so that the actor that triggered it can be charged for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateAccess {
+    /// An actor was looked up by ID.
+    ActorLookup,
+    /// An actor was set or deleted.
+    ActorUpdate,
+    /// A non-ID address was resolved to an actor ID.
+    AddressLookup,
+}
+
+/// Counts the lookups served by the state tree's cache since the state tree was loaded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Actor lookups served from the cache (including lookups of deleted actors).
+    pub actor_hits: u64,
+    /// Actor lookups that had to load the actor from the HAMT.
+    pub actor_misses: u64,
+    /// Address resolutions served from the cache.
+    pub address_hits: u64,
+    /// Address resolutions that had to go through the init actor.
+    pub address_misses: u64,
+}
+
+/// What a [`StateTree::flush_with_stats`] wrote.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TreeFlushStats {
+    /// The actors created, modified, or deleted since the last flush.
+    pub dirty_actors: u64,
+    /// The HAMT nodes rewritten, including the root.
+    pub hamt_nodes_written: u64,
+}
+
+/// The state tree's info block, linked from the [`StateRoot`]. It has no fields yet, but is
+/// reserved for future use (e.g., a registry of actor versions). It's encoded as a CBOR array, and
+/// fields this version doesn't know about are preserved when it's re-encoded.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StateTreeInfo {
+    /// Fields unknown to this version, in order.
+    unknown: Vec<Ipld>,
+}
+
+impl StateTreeInfo {
+    /// Returns the fields this version doesn't know about, in order.
+    pub fn unknown_fields(&self) -> &[Ipld] {
+        &self.unknown
+    }
+}
+
+/// The IDs of the actors read and modified while access tracking was enabled, in ascending order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActorAccesses {
+    pub read: Vec<ActorID>,
+    pub modified: Vec<ActorID>,
+}
+
+/// Records actor accesses, following the transaction stack so that writes made in reverted
+/// transactions are forgotten. Reads are never forgotten.
+#[derive(Debug)]
+struct AccessTracker {
+    read: RefCell<BTreeSet<ActorID>>,
+    /// Actors modified in each open transaction, innermost last. The first entry holds the writes
+    /// made outside of any transaction.
+    modified: Vec<BTreeSet<ActorID>>,
+}
+
+impl AccessTracker {
+    fn new() -> Self {
+        Self {
+            read: Default::default(),
+            modified: vec![BTreeSet::new()],
+        }
+    }
+
+    fn record_read(&self, id: ActorID) {
+        self.read.borrow_mut().insert(id);
+    }
+
+    fn record_write(&mut self, id: ActorID) {
+        self.modified
+            .last_mut()
+            .expect("access tracker has no base layer")
+            .insert(id);
+    }
+
+    fn begin_transaction(&mut self) {
+        self.modified.push(BTreeSet::new());
+    }
+
+    fn end_transaction(&mut self, revert: bool) {
+        if self.modified.len() < 2 {
+            // Tracking began inside this transaction; keep what we have.
+            if revert {
+                self.modified[0].clear();
+            }
+            return;
+        }
+        let last = self.modified.pop().unwrap();
+        if !revert {
+            self.modified.last_mut().unwrap().extend(last);
+        }
+    }
+
+    fn finish(self) -> ActorAccesses {
+        ActorAccesses {
+            read: self.read.into_inner().into_iter().collect(),
+            modified: self
+                .modified
+                .into_iter()
+                .flatten()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+        }
+    }
 }
 
 /// Collection of state snapshots
@@ -206,22 +336,21 @@ where
                     version
                 )))
             }
-            StateTreeVersion::V3 | StateTreeVersion::V4 => {
-                let cid = store
-                    .put_cbor(&StateInfo0::default(), multihash::Code::Blake2b256)
-                    .context("failed to put state info")
-                    .or_fatal()?;
-                Some(cid)
+            StateTreeVersion::V3 | StateTreeVersion::V4 | StateTreeVersion::V5 => {
+                put_info(&store, &StateTreeInfo::default())?
             }
         };
 
-        // Both V3 and V4 use bitwidt=5.
+        // V3 and later use bitwidth=5.
         let hamt = Hamt::new_with_bit_width(store, HAMT_BIT_WIDTH);
         Ok(Self {
             hamt,
             version,
             info,
             snaps: StateSnapshots::new(),
+            accesses: None,
+            cache_stats: Default::default(),
+            metered: Default::default(),
         })
     }
 
@@ -233,7 +362,7 @@ where
                 version,
                 info,
                 actors,
-            })) => (version, Some(info), actors),
+            })) => (version, info, actors),
             Ok(None) => {
                 return Err(ExecutionError::Fatal(anyhow!(
                     "failed to find state tree {}",
@@ -256,9 +385,14 @@ where
                     version
                 )))
             }
-            StateTreeVersion::V3 | StateTreeVersion::V4 => {
+            StateTreeVersion::V3 | StateTreeVersion::V4 | StateTreeVersion::V5 => {
                 let hamt = Hamt::load_with_bit_width(&actors, store, HAMT_BIT_WIDTH)
-                    .context("failed to load state tree")
+                    .with_context(|| {
+                        format!(
+                            "failed to load the actors HAMT {} of state tree {}",
+                            actors, c
+                        )
+                    })
                     .or_fatal()?;
 
                 Ok(Self {
@@ -266,11 +400,36 @@ where
                     version,
                     info,
                     snaps: StateSnapshots::new(),
+                    accesses: None,
+                    cache_stats: Default::default(),
+                    metered: Default::default(),
                 })
             }
         }
     }
 
+    /// Returns the version of this state tree.
+    pub fn version(&self) -> StateTreeVersion {
+        self.version
+    }
+
+    /// Loads the state tree's info block.
+    pub fn info(&self) -> Result<StateTreeInfo> {
+        self.store()
+            .get_cbor(&self.info)
+            .with_context(|| format!("failed to load state info {}", self.info))
+            .or_fatal()?
+            .with_context(|| format!("state info {} not found", self.info))
+            .or_fatal()
+    }
+
+    /// Replaces the state tree's info block. The new block is written immediately, and linked from
+    /// the state root on the next flush.
+    pub fn set_info(&mut self, info: &StateTreeInfo) -> Result<()> {
+        self.info = put_info(self.store(), info)?;
+        Ok(())
+    }
+
     /// Retrieve store reference to modify db.
     pub fn store(&self) -> &S {
         self.hamt.store()
@@ -287,8 +446,18 @@ where
 
     /// Get actor state from an actor ID.
     pub fn get_actor_id(&self, id: ActorID) -> Result<Option<ActorState>> {
+        self.meter(StateAccess::ActorLookup);
+        if let Some(accesses) = &self.accesses {
+            accesses.record_read(id);
+        }
         // Check cache for actor state
-        Ok(match self.snaps.get_actor(id) {
+        let cached = self.snaps.get_actor(id);
+        if let StateCacheResult::Uncached = cached {
+            self.count(|stats| stats.actor_misses += 1);
+        } else {
+            self.count(|stats| stats.actor_hits += 1);
+        }
+        Ok(match cached {
             StateCacheResult::Exists(state) => Some(state),
             StateCacheResult::Deleted => None,
             StateCacheResult::Uncached => {
@@ -297,9 +466,9 @@ where
                 let act = self
                     .hamt
                     .get(&key)
-                    .with_context(|| format!("failed to lookup actor {}", id))
+                    .with_context(|| format!("failed to lookup actor {} in the actors HAMT", id))
                     .or_fatal()?
-                    .cloned();
+                    .map(|stored| stored.state.clone());
 
                 // Update cache if state was found
                 if let Some(act_s) = &act {
@@ -322,25 +491,46 @@ where
     }
 
     /// Set actor state with an actor ID.
+    ///
+    /// Returns a fatal error if the actor has a delegated address, but this state tree's version
+    /// can't record it.
     pub fn set_actor_id(&mut self, id: ActorID, actor: ActorState) -> Result<()> {
+        if actor.delegated_address.is_some()
+            && ActorStateEncoding::for_version(self.version) == ActorStateEncoding::Tuple4
+        {
+            return Err(ExecutionError::Fatal(anyhow!(
+                "cannot set actor {} with a delegated address in a {:?} state tree",
+                id,
+                self.version
+            )));
+        }
+        self.meter(StateAccess::ActorUpdate);
+        if let Some(accesses) = &mut self.accesses {
+            accesses.record_write(id);
+        }
         self.snaps.set_actor(id, actor)
     }
 
     /// Get an ID address from any Address
     pub fn lookup_id(&self, addr: &Address) -> Result<Option<ActorID>> {
-        if let &Payload::ID(id) = addr.payload() {
-            return Ok(Some(id));
+        if let &Payload::ID(_) = addr.payload() {
+            // No actor has an ID above the maximum.
+            return Ok(addr.id().ok());
         }
+        self.meter(StateAccess::AddressLookup);
 
         if let Some(res_address) = self.snaps.resolve_address(addr) {
+            self.count(|stats| stats.address_hits += 1);
             return Ok(Some(res_address));
         }
+        self.count(|stats| stats.address_misses += 1);
 
-        let (state, _) = InitActorState::load(self)?;
+        // Loading the init actor is part of the address lookup, and only happens on a cache miss.
+        let (state, _) = self.unmetered(|| InitActorState::load(self))?;
 
         let a = match state
             .resolve_address(self.store(), addr)
-            .context("Could not resolve address")
+            .with_context(|| format!("could not resolve address {}", addr))
             .or_fatal()?
         {
             Some(a) => a,
@@ -364,6 +554,10 @@ where
 
     /// Delete actor identified by the supplied ID. Returns no error if the actor doesn't exist.
     pub fn delete_actor_id(&mut self, id: ActorID) -> Result<()> {
+        self.meter(StateAccess::ActorUpdate);
+        if let Some(accesses) = &mut self.accesses {
+            accesses.record_write(id);
+        }
         // Remove value from cache
         self.snaps.delete_actor(id)?;
 
@@ -418,6 +612,15 @@ where
         Ok(true)
     }
 
+    /// Replaces the code CID of the actor identified by the supplied ID, preserving its balance,
+    /// sequence, and state root. Returns false if the actor doesn't exist.
+    pub fn set_actor_code(&mut self, id: ActorID, code: Cid) -> Result<bool> {
+        self.maybe_mutate_actor_id(id, |actor| {
+            actor.code = code;
+            Ok(())
+        })
+    }
+
     /// Register a new address through the init actor.
     pub fn register_new_address(&mut self, addr: &Address) -> Result<ActorID> {
         let (mut state, mut actor) = InitActorState::load(self)?;
@@ -437,11 +640,17 @@ where
 
     /// Begin a new state transaction. Transactions stack.
     pub fn begin_transaction(&mut self) {
+        if let Some(accesses) = &mut self.accesses {
+            accesses.begin_transaction();
+        }
         self.snaps.add_layer();
     }
 
     /// End a transaction, reverting if requested.
     pub fn end_transaction(&mut self, revert: bool) -> Result<()> {
+        if let Some(accesses) = &mut self.accesses {
+            accesses.end_transaction(revert);
+        }
         if revert {
             self.snaps.drop_layer()
         } else {
@@ -449,8 +658,66 @@ where
         }
     }
 
+    /// Start recording the IDs of the actors read and modified through this state tree, discarding
+    /// anything recorded so far. Actors modified within a transaction are only recorded as modified
+    /// if the transaction is committed.
+    pub fn begin_access_tracking(&mut self) {
+        self.accesses = Some(AccessTracker::new());
+    }
+
+    /// Stop recording actor accesses, returning the actors accessed since
+    /// [`begin_access_tracking`](Self::begin_access_tracking) was called, or `None` if access
+    /// tracking wasn't enabled. Writes made in transactions that are still open are included.
+    pub fn end_access_tracking(&mut self) -> Option<ActorAccesses> {
+        self.accesses.take().map(AccessTracker::finish)
+    }
+
+    /// Start recording the actor lookups, updates, and address resolutions made through this state
+    /// tree, discarding anything recorded so far. Every access is recorded, whether or not it hits
+    /// the cache, so that what's recorded only depends on the operations performed.
+    pub fn begin_metering(&self) {
+        self.metered.replace(Some(Vec::new()));
+    }
+
+    /// Stop recording accesses, returning those made since
+    /// [`begin_metering`](Self::begin_metering) was called, in order.
+    pub fn end_metering(&self) -> Vec<StateAccess> {
+        self.metered.take().unwrap_or_default()
+    }
+
+    fn meter(&self, access: StateAccess) {
+        if let Some(metered) = &mut *self.metered.borrow_mut() {
+            metered.push(access);
+        }
+    }
+
+    /// Runs `f` without recording the accesses it makes.
+    fn unmetered<T>(&self, f: impl FnOnce() -> T) -> T {
+        let metered = self.metered.take();
+        let res = f();
+        self.metered.replace(metered);
+        res
+    }
+
+    /// Returns the cache hits and misses since the state tree was loaded.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_stats.get()
+    }
+
+    fn count(&self, f: impl FnOnce(&mut CacheStats)) {
+        let mut stats = self.cache_stats.get();
+        f(&mut stats);
+        self.cache_stats.set(stats);
+    }
+
     /// Flush state tree and return Cid root.
     pub fn flush(&mut self) -> Result<Cid> {
+        self.flush_with_stats().map(|(root, _)| root)
+    }
+
+    /// Flushes the state tree like [`flush`](Self::flush), additionally returning what was
+    /// written.
+    pub fn flush_with_stats(&mut self) -> Result<(Cid, TreeFlushStats)> {
         if self.snaps.layers.len() != 1 {
             return Err(ExecutionError::Fatal(anyhow!(
                 "tried to flush state tree with snapshots on the stack: {:?}",
@@ -458,40 +725,87 @@ where
             )));
         }
 
+        let mut dirty_actors = 0;
         for (&id, sto) in self.snaps.layers[0].actors.borrow().iter() {
             let addr = Address::new_id(id);
-            match sto {
-                None => {
-                    self.hamt.delete(&addr.to_bytes()).or_fatal()?;
-                }
+            // Actors are cached when read, so only count those that actually change the HAMT.
+            let changed = match sto {
+                None => self
+                    .hamt
+                    .delete(&addr.to_bytes())
+                    .with_context(|| format!("failed to delete actor {} from the actors HAMT", id))
+                    .or_fatal()?
+                    .is_some(),
                 Some(ref state) => {
-                    self.hamt
-                        .set(addr.to_bytes().into(), state.clone())
+                    let stored = StoredActorState {
+                        encoding: ActorStateEncoding::for_version(self.version),
+                        state: state.clone(),
+                    };
+                    let old = self
+                        .hamt
+                        .set(addr.to_bytes().into(), stored)
+                        .with_context(|| format!("failed to write actor {} to the actors HAMT", id))
                         .or_fatal()?;
+                    old.map_or(true, |old| old.state != *state)
                 }
+            };
+            if changed {
+                dirty_actors += 1;
             }
         }
 
-        let root = self.hamt.flush().or_fatal()?;
+        let (root, hamt_nodes_written) = self
+            .hamt
+            .flush_with_count()
+            .context("failed to flush the actors HAMT")
+            .or_fatal()?;
 
-        match self.version {
-            StateTreeVersion::V0 => Ok(root),
-            _ => {
-                let cid = self
-                    .info
-                    .expect("malformed state tree, version 1+ require info");
-                let obj = &StateRoot {
-                    version: self.version,
-                    actors: root,
-                    info: cid,
-                };
-                let root = self
-                    .store()
-                    .put_cbor(obj, multihash::Code::Blake2b256)
-                    .or_fatal()?;
-                Ok(root)
-            }
+        let obj = &StateRoot {
+            version: self.version,
+            actors: root,
+            info: self.info,
+        };
+        let root = self
+            .store()
+            .put_cbor(obj, multihash::Code::Blake2b256)
+            .or_fatal()?;
+        Ok((
+            root,
+            TreeFlushStats {
+                dirty_actors,
+                hamt_nodes_written: hamt_nodes_written as u64,
+            },
+        ))
+    }
+
+    /// Calls `f` with every actor cached since the state tree was loaded, whether or not it has
+    /// since been flushed. Deleted actors are passed as `None`. Must not be called inside a
+    /// transaction.
+    pub(crate) fn for_each_cached<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(ActorID, Option<&ActorState>) -> Result<()>,
+    {
+        if self.snaps.layers.len() != 1 {
+            return Err(ExecutionError::Fatal(anyhow!(
+                "tried to walk the state cache with snapshots on the stack: {:?}",
+                self.snaps.layers.len()
+            )));
         }
+        for (&id, sto) in self.snaps.layers[0].actors.borrow().iter() {
+            f(id, sto.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Get the actor state as of the last flush, bypassing (and not populating) the cache.
+    pub(crate) fn get_flushed_actor_id(&self, id: ActorID) -> Result<Option<ActorState>> {
+        let key = Address::new_id(id).to_bytes();
+        Ok(self
+            .hamt
+            .get(&key)
+            .with_context(|| format!("failed to lookup actor {} in the actors HAMT", id))
+            .or_fatal()?
+            .map(|stored| stored.state.clone()))
     }
 
     /// Consumes this StateTree and returns the Blockstore it owns via the HAMT.
@@ -505,14 +819,14 @@ where
     {
         self.hamt.for_each(|k, v| {
             let addr = Address::from_bytes(&k.0)?;
-            f(addr, v)
+            f(addr, &v.state)
         })?;
         Ok(())
     }
 }
 
 /// State of all actor implementations.
-#[derive(PartialEq, Eq, Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+#[derive(PartialEq, Eq, Clone, Debug)]
 pub struct ActorState {
     /// Link to code for the actor.
     pub code: Cid,
@@ -522,6 +836,153 @@ pub struct ActorState {
     pub sequence: u64,
     /// Tokens available to the actor.
     pub balance: TokenAmount,
+    /// The actor's f4 address, if any. This is currently only set on placeholder actors.
+    pub delegated_address: Option<Address>,
+}
+
+/// Outside of the state tree, actors without a delegated address are encoded as a 4-tuple (as they
+/// were before delegated addresses were introduced), and actors with a delegated address as a
+/// 5-tuple. Either form can be decoded.
+impl ser::Serialize for ActorState {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let encoding = match self.delegated_address {
+            None => ActorStateEncoding::Tuple4,
+            Some(_) => ActorStateEncoding::Tuple5,
+        };
+        ser::Serialize::serialize(&StoredActorStateRef(encoding, self), serializer)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for ActorState {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        <StoredActorState as de::Deserialize>::deserialize(deserializer).map(|stored| stored.state)
+    }
+}
+
+/// How actor states are encoded in the state tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ActorStateEncoding {
+    /// `(code, state, sequence, balance)`, used by state trees before V5. These can't record
+    /// delegated addresses.
+    Tuple4,
+    /// `(code, state, sequence, balance, delegated_address)`, where the delegated address may be
+    /// null, used by V5 state trees.
+    Tuple5,
+}
+
+impl ActorStateEncoding {
+    fn for_version(version: StateTreeVersion) -> Self {
+        if version < StateTreeVersion::V5 {
+            ActorStateEncoding::Tuple4
+        } else {
+            ActorStateEncoding::Tuple5
+        }
+    }
+}
+
+/// An actor state along with its encoding, as stored in the state tree's HAMT. Decoding accepts
+/// either encoding, so a state tree can always be read, and re-encoding a decoded state is
+/// byte-identical.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct StoredActorState {
+    encoding: ActorStateEncoding,
+    state: ActorState,
+}
+
+struct StoredActorStateRef<'a>(ActorStateEncoding, &'a ActorState);
+
+impl ser::Serialize for StoredActorState {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        ser::Serialize::serialize(&StoredActorStateRef(self.encoding, &self.state), serializer)
+    }
+}
+
+impl ser::Serialize for StoredActorStateRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let ActorState {
+            code,
+            state,
+            sequence,
+            balance,
+            delegated_address,
+        } = self.1;
+        match (self.0, delegated_address) {
+            (ActorStateEncoding::Tuple4, None) => {
+                ser::Serialize::serialize(&(code, state, sequence, balance), serializer)
+            }
+            (ActorStateEncoding::Tuple4, Some(addr)) => Err(ser::Error::custom(format!(
+                "cannot encode delegated address {} in a 4-tuple actor state",
+                addr
+            ))),
+            (ActorStateEncoding::Tuple5, addr) => {
+                ser::Serialize::serialize(&(code, state, sequence, balance, addr), serializer)
+            }
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for StoredActorState {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct StoredActorStateVisitor;
+
+        impl<'de> de::Visitor<'de> for StoredActorStateVisitor {
+            type Value = StoredActorState;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("an actor state tuple of 4 or 5 elements")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> std::result::Result<StoredActorState, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                fn next<'de, T, A>(seq: &mut A, i: usize) -> std::result::Result<T, A::Error>
+                where
+                    T: de::Deserialize<'de>,
+                    A: de::SeqAccess<'de>,
+                {
+                    seq.next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(i, &StoredActorStateVisitor))
+                }
+
+                let code = next(&mut seq, 0)?;
+                let state = next(&mut seq, 1)?;
+                let sequence = next(&mut seq, 2)?;
+                let balance = next(&mut seq, 3)?;
+                let (encoding, delegated_address) = match seq.next_element::<Option<Address>>()? {
+                    None => (ActorStateEncoding::Tuple4, None),
+                    Some(addr) => (ActorStateEncoding::Tuple5, addr),
+                };
+                Ok(StoredActorState {
+                    encoding,
+                    state: ActorState {
+                        code,
+                        state,
+                        sequence,
+                        balance,
+                        delegated_address,
+                    },
+                })
+            }
+        }
+
+        deserializer.deserialize_seq(StoredActorStateVisitor)
+    }
 }
 
 impl ActorState {
@@ -532,6 +993,7 @@ impl ActorState {
             state,
             sequence,
             balance,
+            delegated_address: None,
         }
     }
     /// Safely deducts funds from an Actor
@@ -626,26 +1088,39 @@ pub mod json {
             state,
             sequence,
             balance: TokenAmount::from_str(&balance).map_err(de::Error::custom)?,
+            delegated_address: None,
         })
     }
 }
 
+/// Writes a state info block, returning its CID.
+fn put_info<S: Blockstore>(store: &S, info: &StateTreeInfo) -> Result<Cid> {
+    store
+        .put_cbor(info, multihash::Code::Blake2b256)
+        .context("failed to put state info")
+        .or_fatal()
+}
+
 #[cfg(test)]
 mod tests {
     use cid::multihash::Code::Blake2b256;
     use cid::multihash::Multihash;
     use cid::Cid;
-    use fvm_ipld_blockstore::MemoryBlockstore;
-    use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+    use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+    use fvm_ipld_encoding::{from_slice, to_vec, CborStore, DAG_CBOR};
     use fvm_shared::address::{Address, SECP_PUB_LEN};
     use fvm_shared::econ::TokenAmount;
-    use fvm_shared::state::StateTreeVersion;
+    use fvm_shared::state::{StateInfo0, StateRoot, StateTreeVersion};
     use fvm_shared::{IDENTITY_HASH, IPLD_RAW};
     use lazy_static::lazy_static;
+    use libipld_core::ipld::Ipld;
 
     use crate::init_actor;
     use crate::init_actor::INIT_ACTOR_ADDR;
-    use crate::state_tree::{ActorState, StateTree};
+    use crate::state_tree::{
+        ActorAccesses, ActorState, ActorStateEncoding, CacheStats, StateAccess, StateTree,
+        StateTreeInfo, StoredActorState, TreeFlushStats,
+    };
 
     lazy_static! {
         pub static ref DUMMY_ACCOUNT_ACTOR_CODE_ID: Cid = Cid::new_v1(
@@ -695,6 +1170,33 @@ mod tests {
         assert_eq!(tree.get_actor(&addr).unwrap(), None);
     }
 
+    #[test]
+    fn set_actor_code() {
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V3).unwrap();
+
+        let act = ActorState::new(
+            *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+            empty_cid(),
+            TokenAmount::from_atto(42),
+            7,
+        );
+        tree.set_actor_id(100, act.clone()).unwrap();
+
+        assert!(tree.set_actor_code(100, *DUMMY_INIT_ACTOR_CODE_ID).unwrap());
+        assert_eq!(
+            tree.get_actor_id(100).unwrap(),
+            Some(ActorState {
+                code: *DUMMY_INIT_ACTOR_CODE_ID,
+                ..act
+            })
+        );
+
+        // Missing actors are left alone.
+        assert!(!tree.set_actor_code(101, *DUMMY_INIT_ACTOR_CODE_ID).unwrap());
+        assert_eq!(tree.get_actor_id(101).unwrap(), None);
+    }
+
     #[test]
     fn get_set_non_id() {
         let store = MemoryBlockstore::default();
@@ -725,7 +1227,8 @@ mod tests {
                 code: *DUMMY_INIT_ACTOR_CODE_ID,
                 state: state_cid,
                 balance: Default::default(),
-                sequence: 2
+                sequence: 2,
+                delegated_address: None,
             })
         );
 
@@ -838,6 +1341,96 @@ mod tests {
         assert_eq!(tree.get_actor(&addr).unwrap(), None);
     }
 
+    #[test]
+    fn flush_and_cache_stats() {
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V4).unwrap();
+        let actor = |balance| {
+            ActorState::new(
+                *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                *DUMMY_ACCOUNT_ACTOR_CODE_ID,
+                TokenAmount::from_atto(balance),
+                1,
+            )
+        };
+
+        tree.set_actor_id(100, actor(1)).unwrap();
+        tree.set_actor_id(101, actor(1)).unwrap();
+        let (root, stats) = tree.flush_with_stats().unwrap();
+        assert_eq!(stats.dirty_actors, 2);
+        assert_eq!(stats.hamt_nodes_written, 1);
+
+        // Reading actors caches them, but doesn't dirty them.
+        let mut tree = StateTree::new_from_root(&store, &root).unwrap();
+        assert_eq!(tree.get_actor_id(100).unwrap(), Some(actor(1)));
+        assert_eq!(tree.get_actor_id(100).unwrap(), Some(actor(1)));
+        assert_eq!(tree.get_actor_id(102).unwrap(), None);
+        assert_eq!(
+            tree.cache_stats(),
+            CacheStats {
+                actor_hits: 1,
+                actor_misses: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            tree.flush_with_stats().unwrap(),
+            (root, TreeFlushStats::default())
+        );
+
+        // Rewriting an actor with its current state doesn't dirty it either.
+        tree.set_actor_id(100, actor(1)).unwrap();
+        tree.set_actor_id(101, actor(2)).unwrap();
+        tree.delete_actor_id(102).unwrap();
+        let (_, stats) = tree.flush_with_stats().unwrap();
+        assert_eq!(stats.dirty_actors, 1);
+        assert_eq!(stats.hamt_nodes_written, 1);
+    }
+
+    #[test]
+    fn state_info() {
+        let store = MemoryBlockstore::default();
+        for version in [
+            StateTreeVersion::V3,
+            StateTreeVersion::V4,
+            StateTreeVersion::V5,
+        ] {
+            // New trees have an empty info block, encoded like before it could be read.
+            let mut tree = StateTree::new(&store, version).unwrap();
+            assert_eq!(tree.info().unwrap(), StateTreeInfo::default());
+            let root = tree.flush().unwrap();
+            assert_eq!(tree.flush().unwrap(), root);
+            let StateRoot { info, .. } = store.get_cbor(&root).unwrap().unwrap();
+            assert_eq!(
+                store.get(&info).unwrap().unwrap(),
+                to_vec(&StateInfo0::default()).unwrap()
+            );
+
+            // Loading and flushing a tree leaves its root untouched.
+            let mut tree = StateTree::new_from_root(&store, &root).unwrap();
+            assert_eq!(tree.version(), version);
+            assert_eq!(tree.info().unwrap(), StateTreeInfo::default());
+            assert_eq!(tree.flush().unwrap(), root);
+
+            // Fields from future versions survive a round-trip.
+            let future: StateTreeInfo = from_slice(&to_vec(&(1u64, "registry")).unwrap()).unwrap();
+            assert_eq!(
+                future.unknown_fields(),
+                [Ipld::Integer(1), Ipld::String("registry".into())]
+            );
+            tree.set_info(&future).unwrap();
+            let future_root = tree.flush().unwrap();
+            assert_ne!(future_root, root);
+            let StateRoot { info, .. } = store.get_cbor(&future_root).unwrap().unwrap();
+            assert_eq!(
+                store.get(&info).unwrap().unwrap(),
+                to_vec(&(1u64, "registry")).unwrap()
+            );
+            let tree = StateTree::new_from_root(&store, &future_root).unwrap();
+            assert_eq!(tree.info().unwrap(), future);
+        }
+    }
+
     #[test]
     fn unsupported_versions() {
         let unsupported = vec![
@@ -852,4 +1445,153 @@ mod tests {
             assert!(err.is_fatal());
         }
     }
+
+    #[test]
+    fn actor_state_encodings() {
+        let code = *DUMMY_ACCOUNT_ACTOR_CODE_ID;
+        let state = empty_cid();
+        let balance = TokenAmount::from_atto(10);
+        let delegated = Address::new_delegated(10, b"foo").unwrap();
+
+        let fixtures = [
+            (
+                to_vec(&(code, state, 5u64, &balance)).unwrap(),
+                ActorStateEncoding::Tuple4,
+                None,
+            ),
+            (
+                to_vec(&(code, state, 5u64, &balance, None::<Address>)).unwrap(),
+                ActorStateEncoding::Tuple5,
+                None,
+            ),
+            (
+                to_vec(&(code, state, 5u64, &balance, Some(delegated))).unwrap(),
+                ActorStateEncoding::Tuple5,
+                Some(delegated),
+            ),
+        ];
+        for (bytes, encoding, delegated_address) in fixtures {
+            let stored: StoredActorState = from_slice(&bytes).unwrap();
+            assert_eq!(stored.encoding, encoding);
+            assert_eq!(
+                stored.state,
+                ActorState {
+                    delegated_address,
+                    ..ActorState::new(code, state, balance.clone(), 5)
+                }
+            );
+            assert_eq!(to_vec(&stored).unwrap(), bytes);
+
+            // Outside the state tree, the actor state is decoded the same way.
+            let actor: ActorState = from_slice(&bytes).unwrap();
+            assert_eq!(actor, stored.state);
+        }
+
+        // A 4-tuple can't record a delegated address.
+        let stored = StoredActorState {
+            encoding: ActorStateEncoding::Tuple4,
+            state: ActorState {
+                delegated_address: Some(delegated),
+                ..ActorState::new(code, state, balance, 5)
+            },
+        };
+        assert!(to_vec(&stored).is_err());
+    }
+
+    #[test]
+    fn delegated_address_requires_v5() {
+        let store = MemoryBlockstore::default();
+        let delegated = Address::new_delegated(10, b"foo").unwrap();
+        let actor = ActorState {
+            delegated_address: Some(delegated),
+            ..ActorState::new(empty_cid(), empty_cid(), Default::default(), 0)
+        };
+
+        let mut tree = StateTree::new(&store, StateTreeVersion::V4).unwrap();
+        let err = tree.set_actor_id(100, actor.clone()).unwrap_err();
+        assert!(err.is_fatal());
+
+        let mut tree = StateTree::new(&store, StateTreeVersion::V5).unwrap();
+        tree.set_actor_id(100, actor.clone()).unwrap();
+        let root = tree.flush().unwrap();
+
+        let tree = StateTree::new_from_root(&store, &root).unwrap();
+        assert_eq!(tree.version(), StateTreeVersion::V5);
+        assert_eq!(tree.get_actor_id(100).unwrap(), Some(actor));
+    }
+
+    #[test]
+    fn access_tracking() {
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V3).unwrap();
+        let act = ActorState::new(empty_cid(), empty_cid(), Default::default(), 1);
+        for id in 100..104 {
+            tree.set_actor_id(id, act.clone()).unwrap();
+        }
+
+        // Nothing is recorded until tracking begins.
+        assert_eq!(tree.end_access_tracking(), None);
+        tree.begin_access_tracking();
+
+        tree.begin_transaction();
+        tree.mutate_actor_id(100, |_| Ok(())).unwrap();
+
+        // Writes in a reverted transaction are forgotten, but its reads are not.
+        tree.begin_transaction();
+        tree.mutate_actor_id(101, |_| Ok(())).unwrap();
+        tree.delete_actor_id(102).unwrap();
+        tree.end_transaction(true).unwrap();
+
+        // Writes in a nested committed transaction are kept.
+        tree.begin_transaction();
+        tree.delete_actor_id(103).unwrap();
+        tree.end_transaction(false).unwrap();
+        tree.end_transaction(false).unwrap();
+
+        assert_eq!(
+            tree.end_access_tracking(),
+            Some(ActorAccesses {
+                read: vec![100, 101],
+                modified: vec![100, 103],
+            })
+        );
+        assert_eq!(tree.end_access_tracking(), None);
+    }
+
+    #[test]
+    fn metering() {
+        let store = MemoryBlockstore::default();
+        let mut tree = StateTree::new(&store, StateTreeVersion::V4).unwrap();
+        let init_state = init_actor::State::new_test(&store);
+        let state_cid = tree.store().put_cbor(&init_state, Blake2b256).unwrap();
+        let act = ActorState::new(*DUMMY_INIT_ACTOR_CODE_ID, state_cid, Default::default(), 1);
+        tree.set_actor(&INIT_ACTOR_ADDR, act.clone()).unwrap();
+        let addr = Address::new_secp256k1(&[2; SECP_PUB_LEN]).unwrap();
+        let id = tree.register_new_address(&addr).unwrap();
+        tree.set_actor_id(id, act).unwrap();
+
+        // Nothing is recorded until metering begins.
+        assert_eq!(tree.end_metering(), []);
+        tree.begin_metering();
+
+        // ID addresses don't need resolving, and loading the init actor to resolve an address is
+        // part of the address lookup. Cached accesses are recorded all the same.
+        assert_eq!(tree.lookup_id(&Address::new_id(id)).unwrap(), Some(id));
+        assert_eq!(tree.lookup_id(&addr).unwrap(), Some(id));
+        assert_eq!(tree.lookup_id(&addr).unwrap(), Some(id));
+        tree.mutate_actor_id(id, |_| Ok(())).unwrap();
+        tree.delete_actor_id(id).unwrap();
+
+        assert_eq!(
+            tree.end_metering(),
+            [
+                StateAccess::AddressLookup,
+                StateAccess::AddressLookup,
+                StateAccess::ActorLookup,
+                StateAccess::ActorUpdate,
+                StateAccess::ActorUpdate,
+            ]
+        );
+        assert_eq!(tree.end_metering(), []);
+    }
 }
@@ -114,10 +114,19 @@ pub fn install_actor(
     context.kernel.install_actor(typ)
 }
 
+pub fn upgrade_actor(
+    context: Context<'_, impl Kernel>,
+    actor_id: u64,
+    new_code_cid_off: u32, // Cid
+) -> Result<()> {
+    let new_code_cid = context.memory.read_cid(new_code_cid_off)?;
+    context.kernel.upgrade_actor(actor_id, new_code_cid)
+}
+
 pub fn balance_of(context: Context<'_, impl Kernel>, actor_id: u64) -> Result<sys::TokenAmount> {
     let balance = context.kernel.balance_of(actor_id)?;
     balance
         .try_into()
-        .context("base-fee exceeds u128 limit")
+        .context("balance exceeds u128")
         .or_fatal()
 }
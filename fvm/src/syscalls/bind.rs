@@ -2,6 +2,7 @@ use std::mem;
 
 use fvm_shared::error::ErrorNumber;
 use fvm_shared::sys::SyscallSafe;
+use fvm_shared::version::NetworkVersion;
 use wasmtime::{Caller, Linker, WasmTy};
 
 use super::context::Memory;
@@ -14,7 +15,7 @@ use crate::kernel::{self, ExecutionError, Kernel, SyscallError};
 ///
 /// 1. If the error is a syscall error, it's returned as the first return value.
 /// 2. If the error is a fatal error, a Trap is returned.
-pub(super) trait BindSyscall<Args, Ret, Func> {
+pub trait BindSyscall<Args, Ret, Func> {
     /// Bind a syscall to the linker.
     ///
     /// 1. The return type will be automatically adjusted to return `Result<u32, Trap>` where
@@ -31,7 +32,7 @@ pub(super) trait BindSyscall<Args, Ret, Func> {
     ///
     /// ```ignore
     /// mod my_module {
-    ///     pub fn zero(mut context: Context<'_, impl Kernel>, arg: i32) -> crate::fvm::kernel::Result<i32> {
+    ///     pub fn zero(mut context: Context<'_, impl Kernel>, arg: i32) -> fvm::kernel::Result<i32> {
     ///         Ok(0)
     ///     }
     /// }
@@ -92,6 +93,25 @@ fn memory_and_data<'a, K: Kernel>(
     (Memory::new(mem), data)
 }
 
+/// Returns the error to fail the syscall `module::name` with if it's one of the `unavailable`
+/// syscalls resolved for the invocation (see [`InvocationData::unavailable_syscalls`]).
+fn check_available(
+    unavailable: &[(&'static str, &'static str, NetworkVersion)],
+    module: &'static str,
+    name: &'static str,
+) -> Result<(), SyscallError> {
+    match unavailable
+        .iter()
+        .find(|&&(m, n, _)| m == module && n == name)
+    {
+        Some(&(_, _, since)) => Err(SyscallError(
+            format!("not available before network version {}", since),
+            ErrorNumber::IllegalOperation,
+        )),
+        None => Ok(()),
+    }
+}
+
 macro_rules! charge_syscall_gas {
     ($kernel:expr) => {
         let charge = $kernel.price_list().on_syscall();
@@ -126,8 +146,13 @@ macro_rules! impl_bind_syscalls {
                         let (mut memory, mut data) = memory_and_data(&mut caller);
                         charge_syscall_gas!(data.kernel);
 
-                        let ctx = Context{kernel: &mut data.kernel, memory: &mut memory};
-                        let out = syscall(ctx $(, $t)*).into();
+                        let out = match check_available(&data.unavailable_syscalls, module, name) {
+                            Ok(()) => {
+                                let ctx = Context{kernel: &mut data.kernel, memory: &mut memory};
+                                syscall(ctx $(, $t)*).into()
+                            },
+                            Err(err) => Ok(Err(err)),
+                        };
 
                         let result = match out {
                             Ok(Ok(_)) => {
@@ -157,18 +182,24 @@ macro_rules! impl_bind_syscalls {
                         charge_syscall_gas!(data.kernel);
 
                         // We need to check to make sure we can store the return value _before_ we do anything.
-                        if (ret as u64) > (memory.len() as u64)
-                            || memory.len() - (ret as usize) < mem::size_of::<Ret::Value>() {
+                        if memory.check_bounds(ret, mem::size_of::<Ret::Value>() as u32).is_err() {
                             let code = ErrorNumber::IllegalArgument;
                             data.last_error = Some(backtrace::Cause::from_syscall(module, name, SyscallError(format!("no space for return value"), code)));
                             return Ok(code as u32);
                         }
 
-                        let ctx = Context{kernel: &mut data.kernel, memory: &mut memory};
-                        let result = match syscall(ctx $(, $t)*).into() {
+                        let out = match check_available(&data.unavailable_syscalls, module, name) {
+                            Ok(()) => {
+                                let ctx = Context{kernel: &mut data.kernel, memory: &mut memory};
+                                syscall(ctx $(, $t)*).into()
+                            },
+                            Err(err) => Ok(Err(err)),
+                        };
+                        let result = match out {
                             Ok(Ok(value)) => {
                                 log::trace!("syscall {}::{}: ok", module, name);
-                                unsafe { *(memory.as_mut_ptr().offset(ret as isize) as *mut Ret::Value) = value };
+                                // We checked the bounds above, so this can't fail.
+                                memory.write_value(ret, value).map_err(Abort::from_error_as_fatal)?;
                                 data.last_error = None;
                                 Ok(0)
                             },
@@ -1,21 +1,30 @@
 use std::io::Cursor;
 use std::ops::{Deref, DerefMut};
-use std::panic;
+use std::{mem, panic, ptr};
 
 use cid::Cid;
 use fvm_ipld_encoding::{from_slice, Cbor};
 use fvm_shared::address::Address;
 use fvm_shared::error::ErrorNumber;
+use fvm_shared::sys::SyscallSafe;
 use fvm_shared::MAX_CID_LEN;
 
 use crate::kernel::{ClassifyResult, Context as _, Result};
 use crate::syscall_error;
 
+/// The context passed to every syscall: the kernel and the actor's memory, borrowed mutably at the
+/// same time.
+///
+/// The memory export is looked up once when the actor is instantiated and stored in the
+/// [`InvocationData`][super::InvocationData]. The binding layer then splits the store into the
+/// memory and the invocation data with `wasmtime::Memory::data_and_store_mut`, so syscalls can
+/// charge gas and write results straight into guest memory without copying or `unsafe`.
 pub struct Context<'a, K> {
     pub kernel: &'a mut K,
     pub memory: &'a mut Memory,
 }
 
+/// An actor's linear memory.
 #[repr(transparent)]
 pub struct Memory([u8]);
 
@@ -38,9 +47,20 @@ impl Memory {
     pub fn new<'a>(m: &'a mut [u8]) -> &'a mut Memory {
         // We explicitly specify the lifetimes here to ensure that the cast doesn't inadvertently
         // change them.
+        //
+        // SAFETY: Memory is a repr(transparent) wrapper around [u8].
         unsafe { &mut *(m as *mut [u8] as *mut Memory) }
     }
 
+    /// Writes a syscall return value at the given offset, which doesn't need to be aligned.
+    pub fn write_value<T: SyscallSafe>(&mut self, offset: u32, value: T) -> Result<()> {
+        let out = self.try_slice_mut(offset, mem::size_of::<T>() as u32)?;
+        // SAFETY: `out` is exactly `size_of::<T>()` bytes long, we don't assume any alignment, and
+        // `SyscallSafe` types can be safely copied byte for byte into actor memory.
+        unsafe { ptr::write_unaligned(out.as_mut_ptr() as *mut T, value) };
+        Ok(())
+    }
+
     pub fn check_bounds(&self, offset: u32, len: u32) -> Result<()> {
         if (offset as u64) + (len as u64) <= (self.0.len() as u64) {
             Ok(())
@@ -190,4 +210,48 @@ mod test {
         let mem = Memory::new(&mut []);
         mem.try_slice(0, 0).expect("slice was in bounds");
     }
+
+    #[test]
+    fn test_write_value_unaligned() {
+        let mut buf = [0u8; 16];
+        let mem = Memory::new(&mut buf);
+        mem.write_value(1, 0x0102030405060708u64)
+            .expect("value was in bounds");
+        mem.write_value(12, u32::MAX).expect("value was in bounds");
+        assert_eq!(&buf[1..9], &0x0102030405060708u64.to_ne_bytes());
+        assert_eq!(&buf[12..], &[0xff; 4]);
+        assert_eq!(buf[0], 0);
+    }
+
+    #[test]
+    fn test_write_value_out_of_bounds() {
+        let mut buf = [0u8; 8];
+        let mem = Memory::new(&mut buf);
+        expect_syscall_err!(IllegalArgument, mem.write_value(1, 0u64));
+        expect_syscall_err!(IllegalArgument, mem.write_value(u32::MAX, 0u8));
+        assert_eq!(buf, [0; 8]);
+    }
+
+    #[test]
+    fn test_context_split_borrow() {
+        // A stand-in kernel: a block registry we copy blocks into and out of.
+        let mut blocks: Vec<Vec<u8>> = Vec::new();
+        let mut buf = [0u8; 8];
+        let ctx = Context {
+            kernel: &mut blocks,
+            memory: Memory::new(&mut buf),
+        };
+
+        // Read from memory into the kernel, then write from the kernel into memory, without
+        // giving up either borrow.
+        ctx.memory.write_value(0, [1u8, 2, 3]).unwrap();
+        ctx.kernel
+            .push(ctx.memory.try_slice(0, 3).unwrap().to_vec());
+        let block = &ctx.kernel[0];
+        ctx.memory
+            .try_slice_mut(4, 3)
+            .unwrap()
+            .copy_from_slice(block);
+        assert_eq!(buf, [1, 2, 3, 0, 1, 2, 3, 0]);
+    }
 }
@@ -1,8 +1,9 @@
 use std::cmp;
 
 use anyhow::{anyhow, Context as _};
+use fvm_shared::clock::ChainEpoch;
 use fvm_shared::crypto::signature::{
-    SignatureType, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
+    SignatureType, BLS_PUB_LEN, BLS_SIG_LEN, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
 };
 use fvm_shared::piece::PieceInfo;
 use fvm_shared::sector::{
@@ -45,6 +46,72 @@ pub fn verify_signature(
         .map(|v| if v { 0 } else { -1 })
 }
 
+/// Verifies an aggregate BLS signature over a set of plaintexts, where the i-th plaintext was
+/// signed by the i-th public key.
+///
+/// The public keys are laid out back to back (48 bytes each), as are the plaintexts; the length
+/// of each plaintext is given by an array of little-endian u32s.
+///
+/// The return i32 indicates the status code of the verification:
+///  - 0: verification ok.
+///  - -1: verification failed.
+pub fn verify_bls_aggregate(
+    context: Context<'_, impl Kernel>,
+    num_signers: u32,
+    sig_off: u32,
+    pub_keys_off: u32,
+    plaintexts_off: u32,
+    plaintext_lens_off: u32,
+) -> Result<i32> {
+    let sig: &[u8; BLS_SIG_LEN] = context
+        .memory
+        .try_slice(sig_off, BLS_SIG_LEN as u32)?
+        .try_into()
+        .or_illegal_argument()?;
+
+    let pub_keys_len = num_signers
+        .checked_mul(BLS_PUB_LEN as u32)
+        .context("too many bls public keys")
+        .or_illegal_argument()?;
+    let pub_keys: Vec<[u8; BLS_PUB_LEN]> = context
+        .memory
+        .try_slice(pub_keys_off, pub_keys_len)?
+        .chunks_exact(BLS_PUB_LEN)
+        .map(|pk| pk.try_into().expect("chunks have the public key length"))
+        .collect();
+
+    let lens_len = num_signers
+        .checked_mul(4)
+        .context("too many plaintexts")
+        .or_illegal_argument()?;
+    let plaintext_lens: Vec<u32> = context
+        .memory
+        .try_slice(plaintext_lens_off, lens_len)?
+        .chunks_exact(4)
+        .map(|len| u32::from_le_bytes(len.try_into().expect("chunks are 4 bytes")))
+        .collect();
+
+    let total_len = plaintext_lens
+        .iter()
+        .try_fold(0u32, |total, len| total.checked_add(*len))
+        .context("plaintexts too large")
+        .or_illegal_argument()?;
+    let mut remaining = context.memory.try_slice(plaintexts_off, total_len)?;
+    let plaintexts: Vec<&[u8]> = plaintext_lens
+        .iter()
+        .map(|len| {
+            let (plaintext, rest) = remaining.split_at(*len as usize);
+            remaining = rest;
+            plaintext
+        })
+        .collect();
+
+    context
+        .kernel
+        .verify_bls_aggregate(sig, &pub_keys, &plaintexts)
+        .map(|v| if v { 0 } else { -1 })
+}
+
 pub fn recover_secp_public_key(
     context: Context<'_, impl Kernel>,
     hash_off: u32,
@@ -199,7 +266,7 @@ pub fn verify_consensus_fault(
         // No consensus fault.
         None => Ok(sys::out::crypto::VerifyConsensusFault {
             fault: 0,
-            epoch: 0,
+            epoch: ChainEpoch::new(0),
             target: 0,
         }),
     }
@@ -271,8 +338,8 @@ pub fn batch_verify_seals(
     }
 
     // Return.
-    unsafe {
-        output.copy_from_slice(&*(&*result as *const [bool] as *const [u8]));
+    for (out, ok) in output.iter_mut().zip(result) {
+        *out = ok as u8;
     }
     Ok(())
 }
@@ -4,7 +4,7 @@ use std::sync::Mutex;
 use anyhow::anyhow;
 use derive_more::Display;
 use fvm_shared::error::ExitCode;
-use wasmtime::Trap;
+use wasmtime::{Trap, TrapCode};
 
 use crate::kernel::ExecutionError;
 
@@ -58,6 +58,13 @@ impl From<Trap> for Abort {
     fn from(t: Trap) -> Self {
         use std::error::Error;
 
+        // Hitting the native stack guard depends on the host (platform, build flags, thread stack
+        // size), so it can't be an actor-visible failure. The instrumented stack limiter always
+        // traps first unless the engine is misconfigured.
+        if let Some(TrapCode::StackOverflow) = t.trap_code() {
+            return Abort::Fatal(anyhow!("actor exceeded the native wasm stack limit"));
+        }
+
         // Actor panic/wasm error.
         if let Some(code) = t.trap_code() {
             return Abort::Exit(ExitCode::SYS_ILLEGAL_INSTRUCTION, code.to_string());
@@ -3,18 +3,30 @@ use std::str;
 use super::Context;
 use crate::gas::Gas;
 use crate::kernel::{ClassifyResult, Result};
-use crate::Kernel;
+use crate::{syscall_error, Kernel};
+
+/// The maximum length, in bytes, of the name of a gas charge made by an actor.
+pub const MAX_GAS_CHARGE_NAME_LEN: u32 = 256;
 
 pub fn charge_gas(
     context: Context<'_, impl Kernel>,
     name_off: u32,
     name_len: u32,
-    compute: i64,
+    compute: u64,
 ) -> Result<()> {
+    if name_len > MAX_GAS_CHARGE_NAME_LEN {
+        return Err(syscall_error!(IllegalArgument;
+            "gas charge name too long: {} > {}", name_len, MAX_GAS_CHARGE_NAME_LEN
+        )
+        .into());
+    }
     let name =
         str::from_utf8(context.memory.try_slice(name_off, name_len)?).or_illegal_argument()?;
-    // Gas charges from actors are always in full gas units. We use milligas internally, so convert here.
-    context.kernel.charge_gas(name, Gas::new(compute))
+    // Gas charges from actors are always in full gas units. We use milligas internally, so convert
+    // here. Charges that don't fit in an i64 saturate (and will run the actor out of gas) instead of
+    // wrapping around into refunds.
+    let compute = Gas::new(i64::try_from(compute).unwrap_or(i64::MAX));
+    context.kernel.charge_gas(name, compute)
 }
 
 pub fn available(context: Context<'_, impl Kernel>) -> Result<u64> {
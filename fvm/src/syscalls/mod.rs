@@ -1,10 +1,12 @@
 use std::mem;
 
 use anyhow::{anyhow, Context as _};
-use wasmtime::{AsContextMut, Global, Linker, Memory, Val};
+use fvm_shared::version::NetworkVersion;
+use wasmtime::{AsContextMut, Global, Linker, Val};
 
 use crate::call_manager::backtrace;
-use crate::gas::Gas;
+use crate::gas::{Gas, GasMultiplier};
+use crate::machine::InstanceLimiter;
 use crate::Kernel;
 
 pub(crate) mod error;
@@ -22,7 +24,8 @@ mod send;
 mod sself;
 mod vm;
 
-pub(self) use context::Context;
+pub use bind::BindSyscall;
+pub use context::{Context, Memory};
 
 /// Invocation data attached to a wasm "store" and available to the syscall binding.
 pub struct InvocationData<K> {
@@ -41,15 +44,31 @@ pub struct InvocationData<K> {
     /// `last_milligas_available`.
     pub last_milligas_available: i64,
 
+    /// The multiplier applied to the actor's gas charges. Execution gas is charged after the fact,
+    /// so the gas made available to the actor's code is divided by it.
+    pub gas_multiplier: GasMultiplier,
+
     /// The invocation's imported "memory".
-    pub memory: Memory,
+    pub memory: wasmtime::Memory,
+
+    /// Limits memory and table growth.
+    pub(crate) limiter: InstanceLimiter,
+
+    /// The gated syscalls that aren't available at the network version the kernel executes at, as
+    /// `(module, name, since)`. Resolved when the store is created so that syscalls don't consult
+    /// the [`FeatureAvailability`](crate::machine::FeatureAvailability) on every call.
+    pub(crate) unavailable_syscalls: Vec<(&'static str, &'static str, NetworkVersion)>,
 }
 
 pub fn update_gas_available(
     ctx: &mut impl AsContextMut<Data = InvocationData<impl Kernel>>,
 ) -> Result<(), Abort> {
     let mut ctx = ctx.as_context_mut();
-    let avail_milligas = ctx.data_mut().kernel.gas_available().as_milligas();
+    let data = ctx.data_mut();
+    let avail_milligas = data
+        .gas_multiplier
+        .unapply(data.kernel.gas_available())
+        .as_milligas();
 
     let gas_global = ctx.data_mut().avail_gas_global;
     gas_global
@@ -89,9 +108,63 @@ pub fn charge_for_exec(
     Ok(())
 }
 
-use self::bind::BindSyscall;
 use self::error::Abort;
 
+/// The (module, name) pairs of all syscalls actors may import. This must match the syscalls bound
+/// in [`bind_syscalls`] (excluding `actor::install_actor`, which depends on the `m2-native`
+/// feature).
+pub const SYSCALL_IMPORTS: &[(&str, &str)] = &[
+    ("vm", "abort"),
+    ("vm", "context"),
+    ("network", "base_fee"),
+    ("network", "total_fil_circ_supply"),
+    ("network", "tipset_timestamp"),
+    ("network", "tipset_cid"),
+    ("network", "chain_id"),
+    ("ipld", "block_open"),
+    ("ipld", "block_create"),
+    ("ipld", "block_read"),
+    ("ipld", "block_stat"),
+    ("ipld", "block_link"),
+    ("self", "root"),
+    ("self", "set_root"),
+    ("self", "current_balance"),
+    ("self", "self_destruct"),
+    ("actor", "resolve_address"),
+    ("actor", "get_actor_code_cid"),
+    ("actor", "new_actor_address"),
+    ("actor", "create_actor"),
+    ("actor", "get_builtin_actor_type"),
+    ("actor", "get_code_cid_for_type"),
+    ("actor", "balance_of"),
+    ("actor", "upgrade_actor"),
+    ("crypto", "verify_signature"),
+    ("crypto", "verify_bls_aggregate"),
+    ("crypto", "recover_secp_public_key"),
+    ("crypto", "hash"),
+    ("crypto", "verify_seal"),
+    ("crypto", "verify_post"),
+    ("crypto", "compute_unsealed_sector_cid"),
+    ("crypto", "verify_consensus_fault"),
+    ("crypto", "verify_aggregate_seals"),
+    ("crypto", "verify_replica_update"),
+    ("crypto", "batch_verify_seals"),
+    ("rand", "get_chain_randomness"),
+    ("rand", "get_beacon_randomness"),
+    ("gas", "charge"),
+    ("gas", "available"),
+    ("send", "send"),
+    ("debug", "log"),
+    ("debug", "enabled"),
+    ("debug", "store_artifact"),
+];
+
+/// Returns true if actors may import the syscall `module::name`.
+pub(crate) fn is_syscall_import(module: &str, name: &str) -> bool {
+    (cfg!(feature = "m2-native") && (module, name) == ("actor", "install_actor"))
+        || SYSCALL_IMPORTS.contains(&(module, name))
+}
+
 // Binds the syscall handlers so they can handle invocations
 // from the actor code.
 pub fn bind_syscalls(
@@ -109,6 +182,7 @@ pub fn bind_syscalls(
 
     linker.bind("network", "tipset_timestamp", network::tipset_timestamp)?;
     linker.bind("network", "tipset_cid", network::tipset_cid)?;
+    linker.bind("network", "chain_id", network::chain_id)?;
 
     linker.bind("ipld", "block_open", ipld::block_open)?;
     linker.bind("ipld", "block_create", ipld::block_create)?;
@@ -141,8 +215,14 @@ pub fn bind_syscalls(
     linker.bind("actor", "install_actor", actor::install_actor)?;
 
     linker.bind("actor", "balance_of", actor::balance_of)?;
+    linker.bind("actor", "upgrade_actor", actor::upgrade_actor)?;
 
     linker.bind("crypto", "verify_signature", crypto::verify_signature)?;
+    linker.bind(
+        "crypto",
+        "verify_bls_aggregate",
+        crypto::verify_bls_aggregate,
+    )?;
     linker.bind(
         "crypto",
         "recover_secp_public_key",
@@ -28,6 +28,10 @@ pub fn tipset_timestamp(context: Context<'_, impl Kernel>) -> Result<u64> {
     Ok(context.kernel.tipset_timestamp())
 }
 
+pub fn chain_id(context: Context<'_, impl Kernel>) -> Result<u64> {
+    Ok(context.kernel.chain_id().into())
+}
+
 pub fn tipset_cid(
     context: Context<'_, impl Kernel>,
     epoch: i64,
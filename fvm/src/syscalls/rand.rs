@@ -1,3 +1,4 @@
+use fvm_shared::clock::ChainEpoch;
 use fvm_shared::randomness::RANDOMNESS_LENGTH;
 
 use super::Context;
@@ -18,7 +19,8 @@ pub fn get_chain_randomness(
     let entropy = context.memory.try_slice(entropy_off, entropy_len)?;
     context
         .kernel
-        .get_randomness_from_tickets(pers, round, entropy)
+        .get_randomness_from_tickets(pers, ChainEpoch::new(round), entropy)
+        .map(|rand| rand.0)
 }
 
 /// Gets 32 bytes of randomness from the beacon system (currently Drand).
@@ -35,5 +37,6 @@ pub fn get_beacon_randomness(
     let entropy = context.memory.try_slice(entropy_off, entropy_len)?;
     context
         .kernel
-        .get_randomness_from_beacon(pers, round, entropy)
+        .get_randomness_from_beacon(pers, ChainEpoch::new(round), entropy)
+        .map(|rand| rand.0)
 }
@@ -6,6 +6,7 @@ use fvm_shared::{ActorID, MethodNum};
 
 use crate::gas::GasCharge;
 use crate::kernel::SyscallError;
+use crate::machine::AbiVersion;
 
 /// Execution Trace, only for informational and debugging purposes.
 pub type ExecutionTrace = Vec<ExecutionEvent>;
@@ -24,6 +25,31 @@ pub enum ExecutionEvent {
         params: RawBytes,
         value: TokenAmount,
     },
+    /// A call made by the system on behalf of the current call, e.g., the constructor of an account
+    /// actor implicitly created by a send. Like a `Call`, it's followed by its gas charges and
+    /// nested calls, then a `CallReturn`, `CallAbort`, or `CallError`.
+    ImplicitCall {
+        from: ActorID,
+        to: Address,
+        method: MethodNum,
+        params: RawBytes,
+        value: TokenAmount,
+    },
+    /// The current call was completed by the [`CallInterceptor`](crate::machine::CallInterceptor)
+    /// instead of the receiving actor. It's followed by the interceptor's gas charge, then by the
+    /// call's `CallReturn` or `CallAbort`.
+    Intercepted(ExitCode),
+    /// The receiving actor's code is about to be invoked following the given ABI version. It's
+    /// recorded once the code has been loaded (after the `OnLoadModule` charge, if any), before any
+    /// of the actor's own gas charges and nested calls.
+    Invoke(AbiVersion),
+    /// An actor was created, e.g., an account by a send to a new key address, or an actor deployed
+    /// by the init actor. `count` is the number of actors the message has created so far,
+    /// including this one (see [`CallManager::actors_created`](crate::call_manager::CallManager::actors_created)).
+    ActorCreated {
+        id: ActorID,
+        count: u64,
+    },
     CallReturn(RawBytes),
     CallAbort(ExitCode),
     CallError(SyscallError),
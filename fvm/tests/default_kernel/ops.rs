@@ -326,7 +326,7 @@ mod ipld {
 
         // assert gas
         {
-            let price_list = call_manager.machine.context().price_list;
+            let price_list = &call_manager.machine.context().price_list;
             let expected_create_price = price_list.on_block_create(block.len() as usize).total();
             let expected_read_price = price_list.on_block_read(block.len() as usize).total();
 
@@ -345,6 +345,49 @@ mod ipld {
         Ok(())
     }
 
+    #[test]
+    fn read_in_chunks() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+
+        let block = "hello world!".as_bytes();
+        let id = kern.block_create(DAG_CBOR, block)?;
+
+        let stat = kern.block_stat(id)?;
+        assert_eq!(stat.size as usize, block.len());
+
+        // read the first chunk
+        let mut buf = vec![0u8; stat.size as usize];
+        let remaining = kern.block_read(id, 0, &mut buf[..5])?;
+        assert_eq!(remaining, 7, "7 bytes should remain after reading 5 of 12");
+
+        // read the rest, starting where we left off
+        let remaining = kern.block_read(id, 5, &mut buf[5..])?;
+        assert_eq!(
+            remaining, 0,
+            "the second chunk should exactly finish the block"
+        );
+        assert_eq!(buf, block, "chunked read does not match the block");
+
+        let (call_manager, _) = kern.into_inner();
+
+        // assert gas: each read is charged for the bytes it copies
+        {
+            let price_list = &call_manager.machine.context().price_list;
+            let expected_price = price_list.on_block_create(block.len()).total()
+                + price_list.on_block_stat().total()
+                + price_list.on_block_read(5).total()
+                + price_list.on_block_read(7).total();
+
+            assert_eq!(
+                call_manager.gas_tracker.gas_used(),
+                expected_price,
+                "gas use of reading a block in chunks does not match price list"
+            )
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn read_unexpected() -> anyhow::Result<()> {
         let (mut kern, test_data) = build_inspecting_test()?;
@@ -392,7 +435,7 @@ mod ipld {
 
         // assert gas
         {
-            let price_list = call_manager.machine.context().price_list;
+            let price_list = &call_manager.machine.context().price_list;
             let expected_create_price = price_list.on_block_create(block.len() as usize).total();
             let expected_stat_price = price_list.on_block_stat().total();
 
@@ -427,11 +470,45 @@ mod ipld {
 
         Ok(())
     }
+
+    #[test]
+    fn block_budget() -> anyhow::Result<()> {
+        // Two registries (e.g., a caller and a callee) sharing one 8 byte budget.
+        let budget = Rc::new(BlockBudget::new(8));
+        let (call_manager, _) = dummy::DummyCallManager::new_stub();
+        let mut kern = TestingKernel::new(
+            call_manager,
+            BlockRegistry::with_budget(budget.clone()),
+            0,
+            0,
+            0,
+            Zero::zero(),
+        );
+        let mut other = BlockRegistry::with_budget(budget.clone());
+
+        other.put(Block::new(DAG_CBOR, vec![0u8; 5]))?;
+        // Exactly fills the budget.
+        kern.block_create(DAG_CBOR, &[0u8; 3])?;
+        assert_eq!(budget.used(), 8);
+        // One byte over.
+        expect_syscall_err!(LimitExceeded, kern.block_create(DAG_CBOR, &[0u8; 1]));
+        // Empty blocks still fit.
+        kern.block_create(DAG_CBOR, &[])?;
+
+        // Dropping a registry returns its bytes to the budget.
+        drop(other);
+        assert_eq!(budget.used(), 3);
+        kern.block_create(DAG_CBOR, &[0u8; 5])?;
+        expect_syscall_err!(LimitExceeded, kern.block_create(DAG_CBOR, &[0u8; 1]));
+
+        Ok(())
+    }
 }
 
 mod gas {
     use fvm::gas::*;
-    use fvm::kernel::GasOps;
+    use fvm::kernel::{GasOps, IpldBlockOps};
+    use fvm_ipld_encoding::DAG_CBOR;
     use fvm_shared::econ::TokenAmount;
     use fvm_shared::version::NetworkVersion;
     use pretty_assertions::{assert_eq, assert_ne};
@@ -529,6 +606,44 @@ mod gas {
         Ok(())
     }
 
+    #[test]
+    fn price_list_override() -> anyhow::Result<()> {
+        let block = "foo".as_bytes();
+        let overridden = GasValues {
+            compute_gas: Gas::new(1234),
+            other_gas: Gas::new(5),
+        };
+
+        let (mut call_manager, _) = dummy::DummyCallManager::new_stub();
+        let price_list = call_manager.machine.ctx.price_list.with_overrides(
+            [("OnBlockCreate".to_owned(), overridden)]
+                .into_iter()
+                .collect(),
+        );
+        call_manager.machine.ctx.override_price_list(price_list);
+        let mut kern = TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            0,
+            0,
+            Zero::zero(),
+        );
+
+        kern.block_create(DAG_CBOR, block)?;
+        assert_eq!(kern.gas_used(), Gas::new(1239));
+
+        // Other charges are unaffected.
+        assert_eq!(
+            kern.price_list().on_block_stat().total(),
+            price_list_by_network_version(STUB_NETWORK_VER)
+                .on_block_stat()
+                .total()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn price_list() -> anyhow::Result<()> {
         let (kern, _) = build_inspecting_test()?;
@@ -547,3 +662,562 @@ mod gas {
         Ok(())
     }
 }
+
+mod actor {
+    use cid::Cid;
+    use fvm::gas::*;
+    use fvm::kernel::{ActorOps, SelfOps};
+    use fvm::machine::Machine;
+    use fvm::state_tree::ActorState;
+    use fvm_ipld_encoding::DAG_CBOR;
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::version::NetworkVersion;
+    use multihash::MultihashDigest;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn code_cid_lookup_charges() -> anyhow::Result<()> {
+        let code = *fvm::EMPTY_ARR_CID;
+        let (mut call_manager, test_data) = dummy::DummyCallManager::new_stub();
+        // State-tree access is free before nv17.
+        call_manager
+            .machine
+            .ctx
+            .override_price_list(price_list_by_network_version(NetworkVersion::V17).clone());
+        call_manager
+            .machine
+            .state_tree
+            .set_actor_id(100, ActorState::new(code, code, TokenAmount::zero(), 0))?;
+        let mut kern = TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            0,
+            0,
+            Zero::zero(),
+        );
+
+        assert_eq!(kern.get_actor_code_cid(100)?, Some(code));
+        assert_eq!(kern.get_actor_code_cid(101)?, None);
+        assert_eq!(
+            test_data.borrow().charge_gas_calls,
+            2,
+            "every actor lookup should be charged, even if the actor doesn't exist"
+        );
+
+        let (call_manager, _) = kern.into_inner();
+        let expected = call_manager
+            .machine
+            .context()
+            .price_list
+            .on_actor_lookup()
+            .total();
+        assert!(!expected.is_zero());
+        assert_eq!(call_manager.gas_tracker.gas_used(), expected * 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn upgrade_actor_privileged() -> anyhow::Result<()> {
+        let code = *fvm::EMPTY_ARR_CID;
+        let (mut call_manager, test_data) = dummy::DummyCallManager::new_stub();
+        // State-tree access is free before nv17.
+        call_manager
+            .machine
+            .ctx
+            .override_price_list(price_list_by_network_version(NetworkVersion::V17).clone());
+        call_manager
+            .machine
+            .state_tree
+            .set_actor_id(100, ActorState::new(code, code, TokenAmount::zero(), 0))?;
+        let mut kern = TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            100,
+            0,
+            Zero::zero(),
+        );
+
+        // Only the system actor (or the upgrade admin) may change actor code.
+        expect_syscall_err!(Forbidden, kern.upgrade_actor(100, code));
+        assert_eq!(test_data.borrow().charge_gas_calls, 0);
+
+        // The system actor may, but the target must exist.
+        let (mut kern, _) = build_inspecting_test()?;
+        expect_syscall_err!(NotFound, kern.upgrade_actor(101, code));
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_root_is_deferred() -> anyhow::Result<()> {
+        let code = *fvm::EMPTY_ARR_CID;
+        let (mut call_manager, _) = dummy::DummyCallManager::new_stub();
+        call_manager
+            .machine
+            .state_tree
+            .set_actor_id(100, ActorState::new(code, code, TokenAmount::zero(), 0))?;
+        let mut kern = TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            100,
+            0,
+            Zero::zero(),
+        );
+        let committed_root = |kern: &TestingKernel| {
+            kern.machine()
+                .state_tree()
+                .get_actor_id(100)
+                .unwrap()
+                .unwrap()
+                .state
+        };
+
+        // The actor sees its new root immediately, but the state tree doesn't until it's committed.
+        let new_root = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(b"foo"));
+        kern.set_root(new_root)?;
+        assert_eq!(kern.root()?, new_root);
+        assert_eq!(committed_root(&kern), code);
+
+        kern.commit_root()?;
+        assert_eq!(kern.root()?, new_root);
+        assert_eq!(committed_root(&kern), new_root);
+
+        // Committing again is a no-op.
+        kern.commit_root()?;
+        assert_eq!(committed_root(&kern), new_root);
+
+        // Deleted actors can't set their root.
+        let (mut kern, _) = build_inspecting_test()?;
+        expect_syscall_err!(IllegalOperation, kern.set_root(new_root));
+
+        Ok(())
+    }
+
+    #[test]
+    fn id_address_resolution_is_free() -> anyhow::Result<()> {
+        let (mut kern, test_data) = build_inspecting_test()?;
+
+        assert_eq!(kern.resolve_address(&Address::new_id(100))?, Some(100));
+        assert_eq!(test_data.borrow().charge_gas_calls, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn state_tree_access_prices() {
+        // Pin the state-tree access prices; changing these changes consensus.
+        let candy = price_list_by_network_version(NetworkVersion::V17);
+        assert_eq!(candy.on_actor_lookup().total(), Gas::new(135617));
+        assert_eq!(candy.on_actor_update().total(), Gas::new(353640));
+        assert_eq!(candy.on_address_lookup().total(), Gas::new(271234));
+
+        // State-tree access was free before.
+        for nv in [NetworkVersion::V15, NetworkVersion::V16] {
+            let price_list = price_list_by_network_version(nv);
+            assert_eq!(price_list.on_actor_lookup().total(), Gas::zero());
+            assert_eq!(price_list.on_actor_update().total(), Gas::zero());
+            assert_eq!(price_list.on_address_lookup().total(), Gas::zero());
+        }
+    }
+}
+
+mod crypto {
+    use fvm::gas::{price_list_by_network_version, Gas};
+    use fvm::kernel::{CryptoOps, ExecutionError, GasOps, SupportedHashes, SyscallError};
+    use fvm::machine::Machine;
+    use fvm_shared::address::Address;
+    use fvm_shared::crypto::signature::{
+        SignatureType, BLS_PUB_LEN, BLS_SIG_IDENTITY, BLS_SIG_LEN, SECP_SIG_LEN,
+        SECP_SIG_MESSAGE_HASH_SIZE,
+    };
+    use fvm_shared::error::ErrorNumber;
+    use fvm_shared::version::NetworkVersion;
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    use super::*;
+
+    #[test]
+    fn delegated_signature_rejected() -> anyhow::Result<()> {
+        let (mut kern, test_data) = build_inspecting_test()?;
+
+        expect_syscall_err!(
+            IllegalArgument,
+            kern.verify_signature(
+                SignatureType::Delegated,
+                &[0; SECP_SIG_LEN],
+                &Address::new_id(100),
+                b"plaintext",
+            )
+        );
+        assert_eq!(
+            test_data.borrow().charge_gas_calls,
+            0,
+            "delegated signatures should be rejected before charging gas"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn bls_aggregate() -> anyhow::Result<()> {
+        let (mut call_manager, test_data) = dummy::DummyCallManager::new_stub();
+        // The syscall is only priced from nv17.
+        call_manager
+            .machine
+            .ctx
+            .override_price_list(price_list_by_network_version(NetworkVersion::V17).clone());
+        let mut kern = TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            0,
+            0,
+            Zero::zero(),
+        );
+
+        // No signers: only the identity signature is valid.
+        assert!(kern.verify_bls_aggregate(&BLS_SIG_IDENTITY, &[], &[])?);
+        assert!(!kern.verify_bls_aggregate(&[0; BLS_SIG_LEN], &[], &[])?);
+        assert_eq!(test_data.borrow().charge_gas_calls, 2);
+
+        // Garbage keys don't verify, and the charge scales with the number of signers.
+        let keys = [[0xff; BLS_PUB_LEN]; 3];
+        assert!(!kern.verify_bls_aggregate(&BLS_SIG_IDENTITY, &keys, &[b"a", b"b", b"c"])?);
+
+        // Keys and plaintexts must line up.
+        expect_syscall_err!(
+            IllegalArgument,
+            kern.verify_bls_aggregate(&BLS_SIG_IDENTITY, &keys, &[b"a"])
+        );
+        assert_eq!(test_data.borrow().charge_gas_calls, 3);
+
+        let (call_manager, _) = kern.into_inner();
+        let price_list = &call_manager.machine.context().price_list;
+        let expected = price_list.on_verify_bls_aggregate(0).total() * 2
+            + price_list.on_verify_bls_aggregate(3).total();
+        assert_eq!(call_manager.gas_tracker.gas_used(), expected);
+
+        Ok(())
+    }
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn recover_secp_public_key() -> anyhow::Result<()> {
+        let (mut kern, test_data) = build_inspecting_test()?;
+
+        // Test vector from go-ethereum's ecrecover tests.
+        let hash: [u8; SECP_SIG_MESSAGE_HASH_SIZE] =
+            unhex("ce0677bb30baa8cf067c88db9811f4333d131bf8bcf12fe7065d211dce971008")
+                .try_into()
+                .unwrap();
+        let sig: [u8; SECP_SIG_LEN] = unhex(
+            "90f27b8b488db00b00606796d2987f6a5f59ae62ea05effe84fef5b8b0e54998\
+             4a691139ad57a3f0b906637673aa2f63d1f55cb1a69199d4009eea23ceaddc9301",
+        )
+        .try_into()
+        .unwrap();
+        let expected = unhex(
+            "04e32df42865e97135acfb65f3bae71bdc86f4d49150ad6a440b6f15878109880a\
+             0a2b2667f7e725ceea70c673093bf67663e0312623c8e091b13cf2c0f11ef652",
+        );
+        assert_eq!(
+            kern.recover_secp_public_key(&hash, &sig)?.to_vec(),
+            expected
+        );
+
+        // High-s signatures are accepted: it's up to the actor to reject them.
+        let mut high_s = sig;
+        high_s[32..64].copy_from_slice(&unhex(
+            "b596eec652a85c0f46f99c898c55d09ae8b9803508b70667bf337469018864ae",
+        ));
+        high_s[64] ^= 1;
+        assert_eq!(
+            kern.recover_secp_public_key(&hash, &high_s)?.to_vec(),
+            expected
+        );
+
+        // Invalid recovery IDs are rejected.
+        for rec_id in 4..=u8::MAX {
+            let mut bad = sig;
+            bad[64] = rec_id;
+            expect_syscall_err!(IllegalArgument, kern.recover_secp_public_key(&hash, &bad));
+        }
+
+        // As are unrecoverable signatures.
+        expect_syscall_err!(
+            IllegalArgument,
+            kern.recover_secp_public_key(&hash, &[0; SECP_SIG_LEN])
+        );
+
+        // Every recovery is charged the same, whether or not it succeeds.
+        let calls = test_data.borrow().charge_gas_calls;
+        assert_eq!(calls, 2 + 252 + 1);
+        let expected_gas = kern.price_list().on_recover_secp_public_key().total() * calls as i64;
+        assert_eq!(kern.gas_used(), expected_gas);
+
+        Ok(())
+    }
+
+    #[test]
+    fn recover_secp_public_key_random_inputs() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+
+        // A simple xorshift generator, so the inputs are reproducible.
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut fill = |buf: &mut [u8]| {
+            for b in buf {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *b = state as u8;
+            }
+        };
+
+        // Random inputs must either recover a key or fail with a syscall error, never panic.
+        for _ in 0..1000 {
+            let mut hash = [0u8; SECP_SIG_MESSAGE_HASH_SIZE];
+            let mut sig = [0u8; SECP_SIG_LEN];
+            fill(&mut hash);
+            fill(&mut sig);
+            sig[64] %= 4;
+            match kern.recover_secp_public_key(&hash, &sig) {
+                Ok(key) => assert_eq!(key[0], 0x04, "expected an uncompressed key"),
+                Err(e) => assert!(
+                    matches!(
+                        e,
+                        ExecutionError::Syscall(SyscallError(_, ErrorNumber::IllegalArgument))
+                    ),
+                    "unexpected error: {:?}",
+                    e
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn hash_known_answers() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+
+        // Digests of "abc" from the respective specifications' test vectors.
+        let vectors = [
+            (
+                SupportedHashes::Sha2_256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                SupportedHashes::Blake2b256,
+                "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319",
+            ),
+            (
+                SupportedHashes::Blake2b512,
+                "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                 7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+            ),
+            (
+                SupportedHashes::Keccak256,
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            ),
+            (
+                SupportedHashes::Ripemd160,
+                "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc",
+            ),
+        ];
+        for (hasher, expected) in vectors {
+            let digest = kern.hash(hasher.into(), b"abc")?;
+            assert_eq!(digest.code(), u64::from(hasher));
+            assert_eq!(digest.digest(), unhex(expected), "{:?}", hasher);
+        }
+
+        // Unsupported hash functions are rejected without charging gas.
+        let gas_used = kern.gas_used();
+        expect_syscall_err!(IllegalArgument, kern.hash(0xdead, b"abc"));
+        assert_eq!(kern.gas_used(), gas_used);
+
+        Ok(())
+    }
+
+    #[test]
+    fn hash_gas() -> anyhow::Result<()> {
+        let gas_for =
+            |nv: NetworkVersion, hasher: SupportedHashes, len: usize| -> anyhow::Result<Gas> {
+                let (mut call_manager, _) = dummy::DummyCallManager::new_stub();
+                call_manager
+                    .machine
+                    .ctx
+                    .override_price_list(price_list_by_network_version(nv).clone());
+                let mut kern = TestingKernel::new(
+                    call_manager,
+                    BlockRegistry::default(),
+                    0,
+                    0,
+                    0,
+                    Zero::zero(),
+                );
+                kern.hash(hasher.into(), &vec![0u8; len])?;
+                Ok(kern.gas_used())
+            };
+
+        // From nv17, hashing is charged per byte, at a different rate for each hash function.
+        let nv = NetworkVersion::V17;
+        assert!(
+            gas_for(nv, SupportedHashes::Sha2_256, 1024)?
+                > gas_for(nv, SupportedHashes::Sha2_256, 0)?
+        );
+        assert_ne!(
+            gas_for(nv, SupportedHashes::Sha2_256, 1024)?,
+            gas_for(nv, SupportedHashes::Keccak256, 1024)?
+        );
+        assert_ne!(
+            gas_for(nv, SupportedHashes::Blake2b256, 1024)?,
+            gas_for(nv, SupportedHashes::Ripemd160, 1024)?
+        );
+
+        // Before that, it's a flat charge.
+        let nv = NetworkVersion::V16;
+        assert_eq!(
+            gas_for(nv, SupportedHashes::Sha2_256, 1024)?,
+            gas_for(nv, SupportedHashes::Sha2_256, 0)?
+        );
+        assert_eq!(
+            gas_for(nv, SupportedHashes::Sha2_256, 1024)?,
+            gas_for(nv, SupportedHashes::Keccak256, 1024)?
+        );
+
+        Ok(())
+    }
+}
+
+mod rand {
+    use fvm::kernel::RandomnessOps;
+    use fvm_shared::clock::{ChainEpoch, EPOCH_UNDEFINED};
+    use fvm_shared::randomness::{draw_randomness, DomainSeparationTag};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn randomness_is_drawn_host_side() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+        let dst = DomainSeparationTag::SealRandomness as i64;
+        let epoch = ChainEpoch::new(10);
+
+        assert_eq!(
+            kern.get_randomness_from_tickets(dst, epoch, b"entropy")?,
+            draw_randomness(&dummy::STUB_CHAIN_RANDOMNESS, dst, epoch, b"entropy")
+        );
+        assert_eq!(
+            kern.get_randomness_from_beacon(dst, epoch, b"entropy")?,
+            draw_randomness(&dummy::STUB_BEACON_RANDOMNESS, dst, epoch, b"entropy")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn negative_epochs() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+        let dst = DomainSeparationTag::SealRandomness as i64;
+
+        // Negative epochs are looked up at genesis (the dummy externs have no randomness before
+        // it), but the requested epoch is still mixed in, as in Lotus.
+        let genesis = ChainEpoch::new(0);
+        for epoch in [EPOCH_UNDEFINED, ChainEpoch::new(i64::MIN)] {
+            let tickets = kern.get_randomness_from_tickets(dst, epoch, b"entropy")?;
+            assert_eq!(
+                tickets,
+                draw_randomness(&dummy::STUB_CHAIN_RANDOMNESS, dst, epoch, b"entropy")
+            );
+            assert_ne!(
+                tickets,
+                kern.get_randomness_from_tickets(dst, genesis, b"entropy")?
+            );
+
+            let beacon = kern.get_randomness_from_beacon(dst, epoch, b"entropy")?;
+            assert_eq!(
+                beacon,
+                draw_randomness(&dummy::STUB_BEACON_RANDOMNESS, dst, epoch, b"entropy")
+            );
+            assert_ne!(
+                beacon,
+                kern.get_randomness_from_beacon(dst, genesis, b"entropy")?
+            );
+        }
+
+        Ok(())
+    }
+}
+
+mod send {
+    use fvm::kernel::{IpldBlockOps, SendOps};
+    use fvm_ipld_encoding::DAG_CBOR;
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+
+    use super::*;
+
+    #[test]
+    fn params_too_large() -> anyhow::Result<()> {
+        let (mut call_manager, test_data) = dummy::DummyCallManager::new_stub();
+        call_manager.machine.ctx.max_params_size = 4;
+        let mut kern = TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            0,
+            0,
+            Zero::zero(),
+        );
+
+        let id = kern.block_create(DAG_CBOR, &[0u8; 5])?;
+        test_data.borrow_mut().charge_gas_calls = 0;
+
+        expect_syscall_err!(
+            LimitExceeded,
+            kern.send(&Address::new_id(1), 1, id, &TokenAmount::zero())
+        );
+        // Rejected before charging for, or sending, the parameters.
+        assert_eq!(test_data.borrow().charge_gas_calls, 0);
+
+        Ok(())
+    }
+}
+
+mod network {
+    use fvm::kernel::NetworkOps;
+    use fvm_shared::chainid::ChainID;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn chain_id() {
+        let (mut call_manager, test_data) = dummy::DummyCallManager::new_stub();
+        call_manager.machine.ctx.chain_id(ChainID::from(314));
+        let kern = TestingKernel::new(
+            call_manager,
+            BlockRegistry::default(),
+            0,
+            0,
+            0,
+            Zero::zero(),
+        );
+
+        assert_eq!(kern.chain_id(), ChainID::from(314));
+        assert_eq!(test_data.borrow().charge_gas_calls, 0);
+    }
+}
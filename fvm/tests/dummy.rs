@@ -4,8 +4,8 @@ use std::rc::Rc;
 
 use anyhow::Context;
 use fvm::call_manager::{Backtrace, CallManager, FinishRet, InvocationResult};
-use fvm::externs::{Consensus, Externs, Rand};
-use fvm::gas::{Gas, GasCharge, GasTracker};
+use fvm::externs::{Consensus, ExternError, Externs, Rand};
+use fvm::gas::{Gas, GasCharge, GasLimit, GasTimer, GasTracker};
 use fvm::machine::{Engine, Machine, MachineContext, Manifest, NetworkConfig};
 use fvm::state_tree::{ActorState, StateTree};
 use fvm::{kernel, Kernel};
@@ -13,7 +13,9 @@ use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
 use fvm_ipld_encoding::CborStore;
 use fvm_shared::address::Address;
 use fvm_shared::bigint::Zero;
+use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
+use fvm_shared::randomness::Randomness;
 use fvm_shared::state::StateTreeVersion;
 use fvm_shared::version::NetworkVersion;
 use fvm_shared::ActorID;
@@ -21,28 +23,35 @@ use multihash::Code;
 
 pub const STUB_NETWORK_VER: NetworkVersion = NetworkVersion::V15;
 
-/// Unimplemented and empty `Externs` impl
+/// Mostly unimplemented `Externs` impl
 pub struct DummyExterns;
 
 impl Externs for DummyExterns {}
 
+/// Base randomness returned by [`DummyExterns`]: the ticket chain and beacon return `[1; 32]`
+/// and `[2; 32]` respectively. There's no randomness before genesis.
+pub const STUB_CHAIN_RANDOMNESS: Randomness = Randomness([1; 32]);
+pub const STUB_BEACON_RANDOMNESS: Randomness = Randomness([2; 32]);
+
 impl Rand for DummyExterns {
-    fn get_chain_randomness(
-        &self,
-        _pers: i64,
-        _round: fvm_shared::clock::ChainEpoch,
-        _entropy: &[u8],
-    ) -> anyhow::Result<[u8; 32]> {
-        todo!()
+    fn get_chain_randomness(&self, round: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
+        if round < ChainEpoch::new(0) {
+            return Err(ExternError::NotAvailable(format!(
+                "no randomness at epoch {}",
+                round
+            )));
+        }
+        Ok((STUB_CHAIN_RANDOMNESS, 0))
     }
 
-    fn get_beacon_randomness(
-        &self,
-        _pers: i64,
-        _round: fvm_shared::clock::ChainEpoch,
-        _entropy: &[u8],
-    ) -> anyhow::Result<[u8; 32]> {
-        todo!()
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
+        if round < ChainEpoch::new(0) {
+            return Err(ExternError::NotAvailable(format!(
+                "no randomness at epoch {}",
+                round
+            )));
+        }
+        Ok((STUB_BEACON_RANDOMNESS, 0))
     }
 }
 
@@ -52,9 +61,9 @@ impl Consensus for DummyExterns {
         _h1: &[u8],
         _h2: &[u8],
         _extra: &[u8],
-    ) -> anyhow::Result<(Option<fvm_shared::consensus::ConsensusFault>, i64)> {
+    ) -> Result<(Option<fvm_shared::consensus::ConsensusFault>, i64), ExternError> {
         // consensus is always valid for tests :)
-        anyhow::Result::Ok((None, 0))
+        Ok((None, 0))
     }
 }
 
@@ -94,7 +103,9 @@ impl DummyMachine {
         let mut config = NetworkConfig::new(STUB_NETWORK_VER);
 
         // generate context from the new generated root and override actors with empty list
-        let ctx = config.override_actors(actors_cid).for_epoch(0, root);
+        let ctx = config
+            .override_actors(actors_cid)
+            .for_epoch(ChainEpoch::new(0), root);
 
         Ok(Self {
             ctx,
@@ -219,7 +230,7 @@ impl CallManager for DummyCallManager {
 
     fn new(
         machine: Self::Machine,
-        _gas_limit: i64,
+        _gas_limit: GasLimit,
         origin: (ActorID, Address),
         nonce: u64,
         gas_premium: TokenAmount,
@@ -244,7 +255,7 @@ impl CallManager for DummyCallManager {
         _params: Option<kernel::Block>,
         _value: &fvm_shared::econ::TokenAmount,
     ) -> kernel::Result<InvocationResult> {
-        // Ok(InvocationResult::Return(None))
+        // Ok(InvocationResult::default())
         todo!()
     }
 
@@ -252,12 +263,12 @@ impl CallManager for DummyCallManager {
         &mut self,
         _f: impl FnOnce(&mut Self) -> kernel::Result<InvocationResult>,
     ) -> kernel::Result<InvocationResult> {
-        // Ok(InvocationResult::Return(None))
+        // Ok(InvocationResult::default())
         todo!()
     }
 
-    fn finish(self) -> (FinishRet, Self::Machine) {
-        (
+    fn finish(self) -> kernel::Result<(FinishRet, Self::Machine)> {
+        Ok((
             FinishRet {
                 gas_used: 0,
                 backtrace: Backtrace {
@@ -265,9 +276,11 @@ impl CallManager for DummyCallManager {
                     cause: None,
                 },
                 exec_trace: Vec::new(),
+                gas_stats: None,
+                actors_created: 0,
             },
             self.machine,
-        )
+        ))
     }
 
     fn machine(&self) -> &Self::Machine {
@@ -286,7 +299,7 @@ impl CallManager for DummyCallManager {
         &mut self.gas_tracker
     }
 
-    fn charge_gas(&mut self, charge: GasCharge) -> kernel::Result<()> {
+    fn charge_gas(&mut self, charge: GasCharge) -> kernel::Result<GasTimer> {
         self.test_data.borrow_mut().charge_gas_calls += 1;
         self.gas_tracker_mut().apply_charge(charge)
     }
@@ -303,6 +316,18 @@ impl CallManager for DummyCallManager {
         todo!()
     }
 
+    fn count_actor_creation(&mut self, _id: ActorID) -> kernel::Result<()> {
+        todo!()
+    }
+
+    fn actors_created(&self) -> u64 {
+        todo!()
+    }
+
+    fn mark_pending_deletion(&mut self, _id: ActorID) {
+        todo!()
+    }
+
     fn invocation_count(&self) -> u64 {
         todo!()
     }
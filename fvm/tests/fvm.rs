@@ -1,4 +1,5 @@
 mod default_kernel;
 mod dummy;
+mod syscalls;
 
 use dummy::*;
@@ -0,0 +1,51 @@
+//! Snapshots of the price list of every supported network version.
+//!
+//! Changing a price changes gas accounting, and therefore consensus, so any change must show up
+//! in the snapshots under `tests/snapshots`. After an intentional change, regenerate them with:
+//!
+//! ```text
+//! UPDATE_GAS_SNAPSHOTS=1 cargo test -p fvm --test price_list_snapshots
+//! ```
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use fvm::gas::price_list_by_network_version;
+use fvm_shared::version::NetworkVersion;
+use pretty_assertions::assert_eq;
+
+const UPDATE_ENV: &str = "UPDATE_GAS_SNAPSHOTS";
+
+/// Renders the price list of the given network version, one `name = price` line per price, sorted
+/// by name.
+fn render(nv: NetworkVersion) -> String {
+    let mut out = String::new();
+    for (name, price) in price_list_by_network_version(nv).prices() {
+        writeln!(out, "{} = {}", name, price).unwrap();
+    }
+    out
+}
+
+#[test]
+fn price_list_snapshots() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+    let update = std::env::var_os(UPDATE_ENV).is_some();
+    for nv in [
+        NetworkVersion::V15,
+        NetworkVersion::V16,
+        NetworkVersion::V17,
+    ] {
+        let path = dir.join(format!("price_list_nv{}.txt", nv));
+        let rendered = render(nv);
+        if update {
+            std::fs::write(&path, &rendered).unwrap();
+            continue;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e));
+        assert_eq!(
+            expected, rendered,
+            "the nv{} price list changed; if intended, rerun with {}=1",
+            nv, UPDATE_ENV
+        );
+    }
+}
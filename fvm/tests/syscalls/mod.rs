@@ -0,0 +1,41 @@
+use fvm::kernel::BlockRegistry;
+use fvm::syscalls::{bind_syscalls, SYSCALL_IMPORTS};
+use fvm::{DefaultKernel, Kernel};
+use num_traits::Zero;
+
+use super::*;
+
+#[test]
+fn syscall_imports_match_bindings() -> anyhow::Result<()> {
+    let (call_manager, _) = DummyCallManager::new_stub();
+    let engine = call_manager.machine.engine.clone();
+
+    let mut linker = wasmtime::Linker::new(&engine);
+    bind_syscalls(&mut linker)?;
+
+    let kernel = DefaultKernel::new(
+        call_manager,
+        BlockRegistry::default(),
+        0,
+        0,
+        0,
+        Zero::zero(),
+    );
+    let mut store = engine.new_store(kernel);
+
+    let mut bound: Vec<(String, String)> = linker
+        .iter(&mut store)
+        .map(|(module, name, _)| (module.to_owned(), name.to_owned()))
+        .filter(|(module, name)| (module.as_str(), name.as_str()) != ("actor", "install_actor"))
+        .collect();
+    bound.sort();
+
+    let mut allowed: Vec<(String, String)> = SYSCALL_IMPORTS
+        .iter()
+        .map(|(module, name)| (module.to_string(), name.to_string()))
+        .collect();
+    allowed.sort();
+
+    assert_eq!(bound, allowed);
+    Ok(())
+}
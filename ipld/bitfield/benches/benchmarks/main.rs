@@ -134,6 +134,23 @@ fn contains_any(c: &mut Criterion) {
     c.bench_function("contains_any", |b| b.iter(|| bf2.contains_any(&difference)));
 }
 
+fn contains_any_disjoint(c: &mut Criterion) {
+    let bf1 = example1();
+    let bf2 = BitField::from_ranges(fvm_ipld_bitfield::iter::Ranges::new(
+        example2()
+            .ranges()
+            .map(|r| r.start + (1 << 32)..r.end + (1 << 32)),
+    ));
+    c.bench_function("contains_any_disjoint", |b| {
+        b.iter(|| bf1.contains_any(black_box(&bf2)))
+    });
+}
+
+fn len_upto(c: &mut Criterion) {
+    let bf = example1();
+    c.bench_function("len_upto", |b| b.iter(|| black_box(&bf).len_upto(100_000)));
+}
+
 fn get(c: &mut Criterion) {
     let bf = example1();
     c.bench_function("get", |b| b.iter(|| bf.get(500_000)));
@@ -160,6 +177,8 @@ criterion_group!(
         cut,
         contains_all,
         contains_any,
+        contains_any_disjoint,
+        len_upto,
         get,
 );
 criterion_main!(benches);
@@ -0,0 +1,54 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::{BitField, RangeSize};
+
+/// The number of runs displayed when no precision is specified.
+const DEFAULT_MAX_RUNS: usize = 32;
+
+/// Formats the bit field's runs of set bits as inclusive ranges, followed by the number of set
+/// bits, e.g., `0-99, 150, 200-210 (112 bits)`. Runs past the formatter's precision (32 by
+/// default) are elided, so `{:.2}` formats the same bit field as `0-99, 150, ... (112 bits)`.
+impl fmt::Display for BitField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let max_runs = f.precision().unwrap_or(DEFAULT_MAX_RUNS);
+        let mut runs = 0;
+        let mut bits = 0;
+        for range in self.ranges() {
+            if runs < max_runs {
+                if runs > 0 {
+                    f.write_str(", ")?;
+                }
+                write_run(f, &range)?;
+            } else if runs == max_runs {
+                f.write_str(if runs > 0 { ", ..." } else { "..." })?;
+            }
+            runs += 1;
+            bits += range.size();
+        }
+        if runs == 0 {
+            f.write_str("empty")?;
+        }
+        write!(f, " ({} bit{})", bits, if bits == 1 { "" } else { "s" })
+    }
+}
+
+/// Like [`Display`](fmt::Display), wrapped in `BitField(...)`.
+impl fmt::Debug for BitField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BitField(")?;
+        fmt::Display::fmt(self, f)?;
+        f.write_str(")")
+    }
+}
+
+fn write_run(f: &mut fmt::Formatter<'_>, range: &Range<u64>) -> fmt::Result {
+    if range.size() == 1 {
+        write!(f, "{}", range.start)
+    } else {
+        write!(f, "{}-{}", range.start, range.end - 1)
+    }
+}
@@ -5,6 +5,7 @@
 // hard to read code.
 #![allow(clippy::comparison_chain)]
 
+mod format;
 pub mod iter;
 mod ops;
 mod range;
@@ -38,7 +39,7 @@ impl From<OutOfRangeError> for Error {
 
 /// A bit field with buffered insertion/removal that serializes to/from RLE+. Similar to
 /// `HashSet<u64>`, but more memory-efficient when long runs of 1s and 0s are present.
-#[derive(Debug, Default, Clone)]
+#[derive(Default, Clone)]
 pub struct BitField {
     /// The underlying ranges of 1s.
     ranges: Vec<Range<u64>>,
@@ -287,6 +288,12 @@ impl BitField {
             .difference(ranges_from_bits(self.unset.iter().copied()))
     }
 
+    /// Returns the ranges of set bits that make up the bit field, like [`ranges`](Self::ranges),
+    /// but without borrowing the bit field.
+    pub fn to_ranges(&self) -> std::vec::IntoIter<Range<u64>> {
+        self.ranges().collect::<Vec<_>>().into_iter()
+    }
+
     /// Returns `true` if the bit field is empty.
     pub fn is_empty(&self) -> bool {
         self.set.is_empty()
@@ -340,13 +347,48 @@ impl BitField {
         bitfields.into_iter().fold(Self::new(), |a, b| a | b)
     }
 
+    /// Returns the number of set bits in the bit field below `n`, without visiting ranges that
+    /// start at or after `n`.
+    pub fn len_upto(&self, n: u64) -> u64 {
+        self.ranges()
+            .take_while(|range| range.start < n)
+            .map(|range| range.end.min(n) - range.start)
+            .sum()
+    }
+
+    /// Returns a range containing every set bit, without applying the set/unset bits. Returns
+    /// `None` if the bit field is trivially empty.
+    fn trivial_bounds(&self) -> Option<Range<u64>> {
+        let start = (self.ranges.first().map(|range| range.start))
+            .into_iter()
+            .chain(self.set.iter().next().copied())
+            .min()?;
+        let end = (self.ranges.last().map(|range| range.end))
+            .into_iter()
+            .chain(self.set.iter().next_back().map(|bit| bit + 1))
+            .max()?;
+        Some(start..end)
+    }
+
     /// Returns true if `self` overlaps with `other`.
+    ///
+    /// This walks the ranges of both bit fields together and stops at the first overlap. Bit
+    /// fields that don't overlap at all are detected without walking their ranges.
     pub fn contains_any(&self, other: &BitField) -> bool {
-        !(self.is_trivially_empty() || other.is_trivially_empty())
-            && self.ranges().intersection(other.ranges()).next().is_some()
+        let (a, b) = match (self.trivial_bounds(), other.trivial_bounds()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return false,
+        };
+        if a.end <= b.start || b.end <= a.start {
+            return false;
+        }
+        self.ranges().intersection(other.ranges()).next().is_some()
     }
 
     /// Returns true if the `self` is a superset of `other`.
+    ///
+    /// This walks the ranges of both bit fields together and stops at the first bit of `other`
+    /// missing from `self`.
     pub fn contains_all(&self, other: &BitField) -> bool {
         other.is_trivially_empty() || other.ranges().difference(self.ranges()).next().is_none()
     }
@@ -451,6 +493,71 @@ pub mod json {
         Ok(BitField::from_ranges(ranges))
     }
 
+    /// Wrapper for serializing a bit field to JSON as an array of `[start, length]` pairs, one
+    /// per run of set bits, for tools that display bit fields (e.g., explorers). Unlike
+    /// [`BitFieldJson`], this isn't the encoding Lotus uses.
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    #[serde(transparent)]
+    pub struct BitFieldRangesJson(#[serde(with = "ranges")] pub BitField);
+
+    /// Wrapper for serializing a bit field reference to JSON as `[start, length]` pairs.
+    #[derive(Serialize)]
+    #[serde(transparent)]
+    pub struct BitFieldRangesJsonRef<'a>(#[serde(with = "ranges")] pub &'a BitField);
+
+    impl From<BitFieldRangesJson> for BitField {
+        fn from(wrapper: BitFieldRangesJson) -> Self {
+            wrapper.0
+        }
+    }
+
+    impl From<BitField> for BitFieldRangesJson {
+        fn from(wrapper: BitField) -> Self {
+            BitFieldRangesJson(wrapper)
+        }
+    }
+
+    mod ranges {
+        use serde::de::Error as _;
+
+        use super::*;
+
+        pub fn serialize<S>(m: &BitField, serializer: S) -> std::result::Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_seq(m.ranges().map(|range| [range.start, range.size()]))
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> std::result::Result<BitField, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let pairs: Vec<(u64, u64)> = Deserialize::deserialize(deserializer)?;
+            let mut ranges: Vec<Range<u64>> = Vec::with_capacity(pairs.len());
+            for (start, len) in pairs {
+                if len == 0 {
+                    return Err(D::Error::custom("bit field runs may not be empty"));
+                }
+                // The run may end at u64::MAX, as it excludes its end.
+                let end = start
+                    .checked_add(len)
+                    .ok_or_else(|| D::Error::custom(OutOfRangeError))?;
+                match ranges.last_mut() {
+                    Some(last) if start < last.end => {
+                        return Err(D::Error::custom(
+                            "bit field runs must be sorted and may not overlap",
+                        ))
+                    }
+                    // Merge touching runs.
+                    Some(last) if start == last.end => last.end = end,
+                    _ => ranges.push(start..end),
+                }
+            }
+            Ok(BitField::from_ranges(Ranges::new(ranges)))
+        }
+    }
+
     #[test]
     fn serialization_starts_with_zeros() {
         let bf = BitFieldJson(bitfield![0, 0, 1, 1, 1, 1, 0, 0, 0, 1, 1]);
@@ -469,6 +576,48 @@ pub mod json {
         assert_eq!(bf, bitfield);
     }
 
+    #[test]
+    fn ranges_serialization() {
+        let bf = BitFieldRangesJson(bitfield![0, 0, 1, 1, 1, 1, 0, 0, 0, 1, 1]);
+        let j = serde_json::to_string(&bf).unwrap();
+        assert_eq!(j, "[[2,4],[9,2]]");
+        assert_eq!(
+            serde_json::to_string(&BitFieldRangesJsonRef(&bf.0)).unwrap(),
+            j
+        );
+        let bitfield: BitFieldRangesJson = serde_json::from_str(&j).unwrap();
+        assert_eq!(bf, bitfield);
+
+        let empty = serde_json::to_string(&BitFieldRangesJson(BitField::new())).unwrap();
+        assert_eq!(empty, "[]");
+
+        // Touching runs are merged.
+        let bitfield: BitFieldRangesJson = serde_json::from_str("[[2,4],[6,1]]").unwrap();
+        assert_eq!(bitfield.0, bitfield![0, 0, 1, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn ranges_deserialization_errors() {
+        for j in [
+            "[[2,0]]",
+            "[[4,2],[2,1]]",
+            "[[2,4],[5,1]]",
+            "[[18446744073709551615,1]]",
+            "[[18446744073709551614,2]]",
+        ] {
+            assert!(
+                serde_json::from_str::<BitFieldRangesJson>(j).is_err(),
+                "{}",
+                j
+            );
+        }
+
+        // The highest bit a bit field can hold.
+        let bitfield: BitFieldRangesJson =
+            serde_json::from_str("[[18446744073709551614,1]]").unwrap();
+        assert!(bitfield.0.get(u64::MAX - 1));
+    }
+
     #[test]
     fn serialization_with_single_unut() {
         let bf = BitFieldJson(bitfield![]);
@@ -2,9 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::convert::TryFrom;
+use std::fmt;
 
 use fvm_ipld_encoding::serde_bytes;
-use serde::{Deserialize, Deserializer, Serialize};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::BitField;
 use crate::{Error, MAX_ENCODED_SIZE};
@@ -19,7 +21,15 @@ impl<'a> Validate<'a> for &'a mut UnvalidatedBitField {
     /// Validates the RLE+ encoding of the bit field, returning a shared
     /// reference to the decoded bit field.
     fn validate(self) -> Result<&'a BitField, Error> {
-        self.validate_mut().map(|bf| &*bf)
+        self.try_validated()
+    }
+}
+
+impl<'a> Validate<'a> for &'a UnvalidatedBitField {
+    /// Validates the RLE+ encoding of the bit field, returning a shared
+    /// reference to the decoded bit field.
+    fn validate(self) -> Result<&'a BitField, Error> {
+        self.try_validated()
     }
 }
 
@@ -32,27 +42,96 @@ impl<'a> Validate<'a> for &'a BitField {
 /// A bit field that may not yet have been validated for valid RLE+.
 /// Used to defer this validation step until when the bit field is
 /// first used, rather than at deserialization.
-#[derive(Debug, Serialize)]
-#[serde(untagged)]
-pub enum UnvalidatedBitField {
-    Validated(BitField),
-    Unvalidated(#[serde(with = "serde_bytes")] Vec<u8>),
+///
+/// The bit field is decoded at most once: the first validation caches the decoded bit field.
+/// Until the bit field is mutated through [`validate_mut`](Self::validate_mut), it serializes to
+/// the exact bytes it was deserialized from. Afterwards, it serializes to the canonical encoding
+/// of the decoded bit field.
+pub struct UnvalidatedBitField {
+    /// The encoded bit field, if it hasn't been mutated since it was deserialized.
+    bytes: Option<Vec<u8>>,
+    /// The decoded bit field, once validated. Always set if `bytes` is `None`.
+    validated: OnceCell<BitField>,
+}
+
+#[cfg(test)]
+thread_local! {
+    /// The number of bit fields decoded by this thread.
+    static DECODES: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+fn decode(bytes: &[u8]) -> Result<BitField, Error> {
+    #[cfg(test)]
+    DECODES.with(|decodes| decodes.set(decodes.get() + 1));
+    BitField::from_bytes(bytes)
 }
 
 impl UnvalidatedBitField {
+    /// Wraps an encoded bit field without validating it.
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes: Some(bytes),
+            validated: OnceCell::new(),
+        }
+    }
+
+    /// Validates the RLE+ encoding of the bit field, returning a shared
+    /// reference to the decoded bit field.
+    pub fn try_validated(&self) -> Result<&BitField, Error> {
+        self.validated.get_or_try_init(|| {
+            // `validated` is always set when `bytes` is `None`.
+            decode(self.bytes.as_deref().unwrap_or_default())
+        })
+    }
+
     /// Validates the RLE+ encoding of the bit field, returning a unique
     /// reference to the decoded bit field.
     pub fn validate_mut(&mut self) -> Result<&mut BitField, Error> {
-        if let Self::Unvalidated(bytes) = self {
-            *self = Self::Validated(BitField::from_bytes(bytes)?);
+        self.try_validated()?;
+        // The caller may mutate the bit field, so the original bytes are now stale.
+        self.bytes = None;
+        Ok(self.validated.get_mut().unwrap())
+    }
+
+    /// Validates the RLE+ encoding of the bit field, returning the decoded bit field. The bit
+    /// field is only decoded if it hasn't been validated yet, and is never copied.
+    pub fn into_validated(self) -> Result<BitField, Error> {
+        match self.validated.into_inner() {
+            Some(bf) => Ok(bf),
+            None => decode(self.bytes.as_deref().unwrap_or_default()),
         }
+    }
+}
 
-        match self {
-            Self::Validated(bf) => Ok(bf),
-            Self::Unvalidated(_) => unreachable!(),
+/// Shows whether the bit field has been validated, and the length of its encoding if it still has
+/// the bytes it was deserialized from. The bits are only shown once validated: formatting never
+/// decodes the bit field.
+impl fmt::Debug for UnvalidatedBitField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("UnvalidatedBitField");
+        s.field("validated", &self.validated.get().is_some());
+        if let Some(bytes) = &self.bytes {
+            s.field("bytes", &bytes.len());
+        }
+        if let Some(bf) = self.validated.get() {
+            s.field("bits", &format_args!("{}", bf));
         }
+        s.finish()
     }
 }
+
+impl Serialize for UnvalidatedBitField {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match &self.bytes {
+            Some(bytes) => serde_bytes::serialize(bytes, serializer),
+            None => self.validated.get().unwrap().serialize(serializer),
+        }
+    }
+}
+
 #[cfg(feature = "enable-arbitrary")]
 use arbitrary::{Arbitrary, Unstructured};
 
@@ -61,9 +140,9 @@ impl<'a> Arbitrary<'a> for UnvalidatedBitField {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
         let bf: BitField = u.arbitrary()?;
         Ok(if *u.choose(&[true, false])? {
-            Self::Validated(bf)
+            Self::from(bf)
         } else {
-            Self::Unvalidated(bf.to_bytes())
+            Self::from_bytes(bf.to_bytes())
         })
     }
 
@@ -74,7 +153,10 @@ impl<'a> Arbitrary<'a> for UnvalidatedBitField {
 
 impl From<BitField> for UnvalidatedBitField {
     fn from(bf: BitField) -> Self {
-        Self::Validated(bf)
+        Self {
+            bytes: None,
+            validated: OnceCell::from(bf),
+        }
     }
 }
 
@@ -82,10 +164,7 @@ impl TryFrom<UnvalidatedBitField> for BitField {
     type Error = Error;
 
     fn try_from(bf: UnvalidatedBitField) -> Result<Self, Self::Error> {
-        match bf {
-            UnvalidatedBitField::Validated(bf) => Ok(bf),
-            UnvalidatedBitField::Unvalidated(bf) => BitField::from_bytes(&bf),
-        }
+        bf.into_validated()
     }
 }
 
@@ -101,6 +180,133 @@ impl<'de> Deserialize<'de> for UnvalidatedBitField {
                 bytes.len()
             )));
         }
-        Ok(Self::Unvalidated(bytes))
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::{from_slice, to_vec};
+
+    use super::*;
+
+    fn decodes() -> usize {
+        DECODES.with(|decodes| decodes.get())
+    }
+
+    #[test]
+    fn decodes_once() {
+        let bf = BitField::try_from_bits([1, 2, 3, 8]).unwrap();
+        let encoded = to_vec(&bf).unwrap();
+        let mut unvalidated: UnvalidatedBitField = from_slice(&encoded).unwrap();
+
+        let before = decodes();
+        assert_eq!(unvalidated.try_validated().unwrap(), &bf);
+        assert_eq!((&unvalidated).validate().unwrap(), &bf);
+        assert_eq!((&mut unvalidated).validate().unwrap(), &bf);
+        unvalidated.validate_mut().unwrap().set(9);
+        assert_eq!(unvalidated.try_validated().unwrap().len(), 5);
+        assert_eq!(decodes() - before, 1);
+
+        // Bit fields that were never encoded are never decoded.
+        let unvalidated = UnvalidatedBitField::from(bf.clone());
+        assert_eq!(unvalidated.try_validated().unwrap(), &bf);
+        assert_eq!(BitField::try_from(unvalidated).unwrap(), bf);
+        assert_eq!(decodes() - before, 1);
+    }
+
+    #[test]
+    fn into_validated_decodes_once() {
+        let bf = BitField::try_from_bits([1, 2, 3, 8]).unwrap();
+        let encoded = to_vec(&bf).unwrap();
+
+        // Unvalidated bit fields are decoded once, when converted.
+        let before = decodes();
+        let unvalidated: UnvalidatedBitField = from_slice(&encoded).unwrap();
+        assert_eq!(unvalidated.into_validated().unwrap(), bf);
+        let unvalidated: UnvalidatedBitField = from_slice(&encoded).unwrap();
+        assert_eq!(BitField::try_from(unvalidated).unwrap(), bf);
+        assert_eq!(decodes() - before, 2);
+
+        // Validated bit fields aren't decoded again.
+        let before = decodes();
+        let unvalidated: UnvalidatedBitField = from_slice(&encoded).unwrap();
+        unvalidated.try_validated().unwrap();
+        assert_eq!(unvalidated.into_validated().unwrap(), bf);
+        let mut unvalidated: UnvalidatedBitField = from_slice(&encoded).unwrap();
+        unvalidated.validate_mut().unwrap().set(9);
+        assert_eq!(BitField::try_from(unvalidated).unwrap().len(), 5);
+        assert_eq!(decodes() - before, 2);
+
+        // Neither are bit fields wrapped as already validated.
+        let before = decodes();
+        let mut unvalidated = UnvalidatedBitField::from(bf.clone());
+        assert_eq!((&mut unvalidated).validate().unwrap(), &bf);
+        assert_eq!(unvalidated.into_validated().unwrap(), bf);
+        assert_eq!(decodes(), before);
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        // Version 1 isn't supported.
+        let mut unvalidated: UnvalidatedBitField =
+            from_slice(&to_vec(&serde_bytes::Bytes::new(&[1])).unwrap()).unwrap();
+        assert!(unvalidated.try_validated().is_err());
+        assert!(unvalidated.validate_mut().is_err());
+        assert!(BitField::try_from(unvalidated).is_err());
+
+        let unvalidated: UnvalidatedBitField =
+            from_slice(&to_vec(&serde_bytes::Bytes::new(&[1])).unwrap()).unwrap();
+        assert!(unvalidated.into_validated().is_err());
+    }
+
+    #[test]
+    fn serialization() {
+        let bf = BitField::try_from_bits([1, 2, 3, 8]).unwrap();
+        let encoded = to_vec(&bf).unwrap();
+
+        // Validation preserves the original bytes.
+        let mut unvalidated: UnvalidatedBitField = from_slice(&encoded).unwrap();
+        assert_eq!(to_vec(&unvalidated).unwrap(), encoded);
+        unvalidated.try_validated().unwrap();
+        assert_eq!(to_vec(&unvalidated).unwrap(), encoded);
+
+        // Mutation re-encodes the bit field.
+        unvalidated.validate_mut().unwrap().unset(8);
+        let mut expected = bf.clone();
+        expected.unset(8);
+        assert_eq!(to_vec(&unvalidated).unwrap(), to_vec(&expected).unwrap());
+
+        assert_eq!(to_vec(&UnvalidatedBitField::from(bf)).unwrap(), encoded);
+    }
+
+    #[test]
+    fn debug() {
+        let bf = BitField::try_from_bits([1, 2, 3, 8]).unwrap();
+        let len = bf.to_bytes().len();
+        let mut unvalidated: UnvalidatedBitField = from_slice(&to_vec(&bf).unwrap()).unwrap();
+
+        // Formatting doesn't decode the bit field.
+        let before = decodes();
+        assert_eq!(
+            format!("{:?}", unvalidated),
+            format!("UnvalidatedBitField {{ validated: false, bytes: {} }}", len)
+        );
+        assert_eq!(decodes(), before);
+
+        unvalidated.try_validated().unwrap();
+        assert_eq!(
+            format!("{:?}", unvalidated),
+            format!(
+                "UnvalidatedBitField {{ validated: true, bytes: {}, bits: 1-3, 8 (4 bits) }}",
+                len
+            )
+        );
+
+        unvalidated.validate_mut().unwrap().unset(8);
+        assert_eq!(
+            format!("{:?}", unvalidated),
+            "UnvalidatedBitField { validated: true, bits: 1-3 (3 bits) }"
+        );
     }
 }
@@ -3,6 +3,7 @@
 
 use std::collections::HashSet;
 
+use fvm_ipld_bitfield::iter::Ranges;
 use fvm_ipld_bitfield::{bitfield, BitField, UnvalidatedBitField};
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
@@ -139,6 +140,75 @@ fn contains_all() {
         .contains_all(&BitField::try_from_bits(vec![1, 2, 3]).unwrap()));
 }
 
+/// Generates a random bit field made of runs of random lengths starting at `offset`, round-tripped
+/// through RLE+, with a few bits set and unset on top. Returns the bit field and its bits.
+fn random_rle(rng: &mut XorShiftRng, offset: u64) -> (BitField, HashSet<u64>) {
+    let mut bits = HashSet::new();
+    let mut bit = offset;
+    for _ in 0..rng.gen_range(0..20) {
+        bit += rng.gen_range(0..50);
+        let len = rng.gen_range(1..50);
+        bits.extend(bit..bit + len);
+        bit += len;
+    }
+    let mut bf = BitField::from_bytes(
+        &BitField::try_from_bits(bits.iter().copied())
+            .unwrap()
+            .to_bytes(),
+    )
+    .unwrap();
+    for _ in 0..rng.gen_range(0..5) {
+        let bit = offset + rng.gen_range(0..1000);
+        if rng.gen() {
+            bf.set(bit);
+            bits.insert(bit);
+        } else {
+            bf.unset(bit);
+            bits.remove(&bit);
+        }
+    }
+    (bf, bits)
+}
+
+#[test]
+fn contains_matches_naive() {
+    let mut rng = XorShiftRng::seed_from_u64(5);
+    for i in 0..1000 {
+        // Every fourth pair is far apart, and therefore disjoint.
+        let offset = if i % 4 == 0 { 100_000 } else { 0 };
+        let (a, a_bits) = random_rle(&mut rng, 0);
+        let (b, b_bits) = random_rle(&mut rng, offset);
+
+        assert_eq!(a.contains_any(&b), !a_bits.is_disjoint(&b_bits));
+        assert_eq!(b.contains_any(&a), !a_bits.is_disjoint(&b_bits));
+        assert_eq!(a.contains_all(&b), a_bits.is_superset(&b_bits));
+        assert_eq!(b.contains_all(&a), b_bits.is_superset(&a_bits));
+
+        let intersection = &a & &b;
+        assert!(a.contains_all(&intersection));
+        assert!(b.contains_all(&intersection));
+    }
+}
+
+#[test]
+fn len_upto_matches_naive() {
+    let mut rng = XorShiftRng::seed_from_u64(6);
+    for _ in 0..1000 {
+        let (bf, bits) = random_rle(&mut rng, 0);
+        let n = rng.gen_range(0..2000);
+        assert_eq!(
+            bf.len_upto(n),
+            bits.iter().filter(|&&bit| bit < n).count() as u64
+        );
+    }
+
+    let bf = BitField::try_from_bits(vec![1, 2, 3, 7]).unwrap();
+    assert_eq!(bf.len_upto(0), 0);
+    assert_eq!(bf.len_upto(2), 1);
+    assert_eq!(bf.len_upto(7), 3);
+    assert_eq!(bf.len_upto(u64::MAX), 4);
+}
+
 #[test]
 fn bit_ops() {
     macro_rules! assert_bits {
@@ -250,6 +320,56 @@ fn ranges() {
     assert_eq!(bit_field.ranges().count(), 4);
 }
 
+#[test]
+fn to_ranges() {
+    let bf = bitfield![0, 0, 1, 1, 1, 0, 1, 1, 0, 1];
+    assert_eq!(bf.to_ranges().collect::<Vec<_>>(), [2..5, 6..8, 9..10]);
+    assert_eq!(BitField::new().to_ranges().count(), 0);
+}
+
+#[test]
+fn display() {
+    assert_eq!(BitField::new().to_string(), "empty (0 bits)");
+    assert_eq!(
+        BitField::try_from_bits([7]).unwrap().to_string(),
+        "7 (1 bit)"
+    );
+
+    let mut bf = BitField::from_ranges(Ranges::new([0..100, 200..211]));
+    bf.set(150);
+    assert_eq!(bf.to_string(), "0-99, 150, 200-210 (112 bits)");
+    assert_eq!(
+        format!("{:?}", bf),
+        "BitField(0-99, 150, 200-210 (112 bits))"
+    );
+
+    // The highest bits a bit field can hold.
+    let bf = BitField::try_from_bits([0, u64::MAX - 2, u64::MAX - 1]).unwrap();
+    assert_eq!(
+        bf.to_string(),
+        "0, 18446744073709551613-18446744073709551614 (3 bits)"
+    );
+    let bf = BitField::from_ranges(Ranges::new([0..u64::MAX]));
+    assert_eq!(
+        bf.to_string(),
+        "0-18446744073709551614 (18446744073709551615 bits)"
+    );
+}
+
+#[test]
+fn display_truncated() {
+    let bf = BitField::try_from_bits((0..100u64).map(|i| i * 2)).unwrap();
+    assert_eq!(format!("{:.3}", bf), "0, 2, 4, ... (100 bits)");
+    assert_eq!(format!("{:.0}", bf), "... (100 bits)");
+    assert_eq!(format!("{:.3?}", bf), "BitField(0, 2, 4, ... (100 bits))");
+    assert_eq!(format!("{:.100}", bf).matches(", ").count(), 99);
+
+    // 32 runs by default.
+    let default = bf.to_string();
+    assert!(default.starts_with("0, 2, "));
+    assert!(default.ends_with(", 62, ... (100 bits)"), "{}", default);
+}
+
 #[test]
 fn serialize_node_symmetric() {
     let bit_field = bitfield![0, 1, 0, 1, 1, 1, 1, 1, 1];
@@ -0,0 +1,89 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use cid::multihash::Code;
+use cid::Cid;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use fvm_ipld_blockstore::{Block, Blockstore, MemoryBlockstore};
+
+const BLOCK_COUNT: usize = 50_000;
+
+/// The simulated fixed cost of a single call into the underlying store (e.g., a database
+/// transaction or an FFI call).
+const CALL_OVERHEAD: Duration = Duration::from_micros(1);
+
+/// Wraps a blockstore, adding a fixed overhead to every write call, however many blocks it writes.
+struct CallOverhead<BS>(BS);
+
+fn overhead() {
+    let start = Instant::now();
+    while start.elapsed() < CALL_OVERHEAD {
+        std::hint::spin_loop();
+    }
+}
+
+impl<BS: Blockstore> Blockstore for CallOverhead<BS> {
+    fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        self.0.get(k)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+        overhead();
+        self.0.put_keyed(k, block)
+    }
+
+    fn put_many_keyed<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        overhead();
+        self.0.put_many_keyed(blocks)
+    }
+}
+
+/// Blocks roughly the size of state-tree HAMT nodes, as written by a flush.
+fn blocks() -> Vec<(Cid, Vec<u8>)> {
+    (0..BLOCK_COUNT)
+        .map(|i| {
+            let mut data = vec![0u8; 256];
+            data[..8].copy_from_slice(&(i as u64).to_be_bytes());
+            let cid = Block::new(0x71, &data).cid(Code::Blake2b256);
+            (cid, data)
+        })
+        .collect()
+}
+
+fn flush(c: &mut Criterion) {
+    let blocks = blocks();
+    let mut group = c.benchmark_group("flush");
+    group.sample_size(10);
+
+    group.bench_function("put", |b| {
+        b.iter(|| {
+            let bs = CallOverhead(MemoryBlockstore::new());
+            for (k, v) in &blocks {
+                bs.put_keyed(k, v).unwrap();
+            }
+            black_box(bs)
+        })
+    });
+
+    group.bench_function("put_many", |b| {
+        b.iter(|| {
+            let bs = CallOverhead(MemoryBlockstore::new());
+            bs.put_many_keyed(blocks.iter().map(|(k, v)| (*k, v)))
+                .unwrap();
+            black_box(bs)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, flush);
+criterion_main!(benches);
@@ -0,0 +1,85 @@
+use std::fmt;
+
+use cid::Cid;
+
+/// The blockstore operation that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockstoreOp {
+    Get,
+    Put,
+    Has,
+}
+
+impl fmt::Display for BlockstoreOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BlockstoreOp::Get => "get",
+            BlockstoreOp::Put => "put",
+            BlockstoreOp::Has => "check for",
+        })
+    }
+}
+
+/// An error returned by an underlying blockstore, annotated with the operation that failed and the
+/// CID of the block it was operating on.
+///
+/// Blockstore wrappers return this (as an [`anyhow::Error`]) so that callers can tell _which_ block
+/// an embedder's store failed on, and attach their own context on top.
+#[derive(Debug)]
+pub struct BlockstoreError {
+    pub op: BlockstoreOp,
+    pub cid: Cid,
+    pub source: anyhow::Error,
+}
+
+impl BlockstoreError {
+    pub fn new(op: BlockstoreOp, cid: Cid, source: anyhow::Error) -> Self {
+        BlockstoreError { op, cid, source }
+    }
+}
+
+impl fmt::Display for BlockstoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to {} block {}", self.op, self.cid)
+    }
+}
+
+impl std::error::Error for BlockstoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Wraps the errors of a blockstore operation on a single block in a [`BlockstoreError`].
+pub trait BlockstoreContext<T> {
+    fn block_context(self, op: BlockstoreOp, cid: &Cid) -> anyhow::Result<T>;
+}
+
+impl<T> BlockstoreContext<T> for anyhow::Result<T> {
+    fn block_context(self, op: BlockstoreOp, cid: &Cid) -> anyhow::Result<T> {
+        self.map_err(|e| BlockstoreError::new(op, *cid, e).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::Code;
+
+    use super::*;
+    use crate::Block;
+
+    #[test]
+    fn error_names_block() {
+        let cid = Block::new(0x55, &b"foobar"[..]).cid(Code::Blake2b256);
+        let err = Err::<(), _>(anyhow::anyhow!("disk on fire"))
+            .block_context(BlockstoreOp::Get, &cid)
+            .unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            format!("failed to get block {}: disk on fire", cid)
+        );
+        let err = err.downcast::<BlockstoreError>().unwrap();
+        assert_eq!(err.op, BlockstoreOp::Get);
+        assert_eq!(err.cid, cid);
+    }
+}
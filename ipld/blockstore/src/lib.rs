@@ -1,4 +1,5 @@
 use std::rc::Rc;
+use std::sync::Arc;
 
 use anyhow::Result;
 use cid::{multihash, Cid};
@@ -11,6 +12,9 @@ pub use memory::MemoryBlockstore;
 mod block;
 pub use block::*;
 
+mod error;
+pub use error::*;
+
 /// An IPLD blockstore suitable for injection into the FVM.
 ///
 /// The cgo blockstore adapter implements this trait.
@@ -31,6 +35,14 @@ pub trait Blockstore {
         Ok(self.get(k)?.is_some())
     }
 
+    /// Checks which of the specified blocks the blockstore has, returning one result per CID (in
+    /// order).
+    ///
+    /// By default, this defers to has.
+    fn has_many(&self, ks: &[Cid]) -> Result<Vec<bool>> {
+        ks.iter().map(|k| self.has(k)).collect()
+    }
+
     /// Puts the block into the blockstore, computing the hash with the specified multicodec.
     ///
     /// By default, this defers to put.
@@ -101,6 +113,10 @@ where
         (*self).has(k)
     }
 
+    fn has_many(&self, ks: &[Cid]) -> Result<Vec<bool>> {
+        (*self).has_many(ks)
+    }
+
     fn put<D>(&self, mh_code: multihash::Code, block: &Block<D>) -> Result<Cid>
     where
         Self: Sized,
@@ -144,6 +160,57 @@ where
         (**self).has(k)
     }
 
+    fn has_many(&self, ks: &[Cid]) -> Result<Vec<bool>> {
+        (**self).has_many(ks)
+    }
+
+    fn put<D>(&self, mh_code: multihash::Code, block: &Block<D>) -> Result<Cid>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+    {
+        (**self).put(mh_code, block)
+    }
+
+    fn put_many<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (multihash::Code, Block<D>)>,
+    {
+        (**self).put_many(blocks)
+    }
+
+    fn put_many_keyed<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        (**self).put_many_keyed(blocks)
+    }
+}
+
+impl<BS> Blockstore for Arc<BS>
+where
+    BS: Blockstore,
+{
+    fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        (**self).get(k)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+        (**self).put_keyed(k, block)
+    }
+
+    fn has(&self, k: &Cid) -> Result<bool> {
+        (**self).has(k)
+    }
+
+    fn has_many(&self, ks: &[Cid]) -> Result<Vec<bool>> {
+        (**self).has_many(ks)
+    }
+
     fn put<D>(&self, mh_code: multihash::Code, block: &Block<D>) -> Result<Cid>
     where
         Self: Sized,
@@ -1,14 +1,14 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
+use std::sync::RwLock;
 
 use anyhow::Result;
 use cid::Cid;
 
 use super::Blockstore;
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default)]
 pub struct MemoryBlockstore {
-    blocks: RefCell<HashMap<Cid, Vec<u8>>>,
+    blocks: RwLock<HashMap<Cid, Vec<u8>>>,
 }
 
 impl MemoryBlockstore {
@@ -17,17 +17,44 @@ impl MemoryBlockstore {
     }
 }
 
+impl Clone for MemoryBlockstore {
+    fn clone(&self) -> Self {
+        Self {
+            blocks: RwLock::new(self.blocks.read().unwrap().clone()),
+        }
+    }
+}
+
 impl Blockstore for MemoryBlockstore {
     fn has(&self, k: &Cid) -> Result<bool> {
-        Ok(self.blocks.borrow().contains_key(k))
+        Ok(self.blocks.read().unwrap().contains_key(k))
+    }
+
+    fn has_many(&self, ks: &[Cid]) -> Result<Vec<bool>> {
+        let blocks = self.blocks.read().unwrap();
+        Ok(ks.iter().map(|k| blocks.contains_key(k)).collect())
     }
 
     fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
-        Ok(self.blocks.borrow().get(k).cloned())
+        Ok(self.blocks.read().unwrap().get(k).cloned())
     }
 
     fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
-        self.blocks.borrow_mut().insert(*k, block.into());
+        self.blocks.write().unwrap().insert(*k, block.into());
+        Ok(())
+    }
+
+    fn put_many_keyed<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        // Take the lock once for the whole batch.
+        self.blocks
+            .write()
+            .unwrap()
+            .extend(blocks.into_iter().map(|(k, v)| (k, v.as_ref().into())));
         Ok(())
     }
 }
@@ -6,7 +6,7 @@ use anyhow::Result;
 use cid::multihash::{self, Code};
 use cid::Cid;
 
-use super::{Block, Blockstore};
+use super::{Block, Blockstore, BlockstoreContext, BlockstoreError, BlockstoreOp};
 
 /// Stats for a [TrackingBlockstore] this indicates the amount of read and written data
 /// to the wrapped store.
@@ -49,7 +49,7 @@ where
     fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>> {
         let mut stats = self.stats.borrow_mut();
         stats.r += 1;
-        let bytes = self.base.get(cid)?;
+        let bytes = self.base.get(cid).block_context(BlockstoreOp::Get, cid)?;
         if let Some(bytes) = &bytes {
             stats.br += bytes.len();
         }
@@ -57,7 +57,12 @@ where
     }
     fn has(&self, cid: &Cid) -> Result<bool> {
         self.stats.borrow_mut().r += 1;
-        self.base.has(cid)
+        self.base.has(cid).block_context(BlockstoreOp::Has, cid)
+    }
+
+    fn has_many(&self, cids: &[Cid]) -> Result<Vec<bool>> {
+        self.stats.borrow_mut().r += cids.len();
+        self.base.has_many(cids)
     }
 
     fn put<D>(&self, code: Code, block: &Block<D>) -> Result<Cid>
@@ -67,14 +72,18 @@ where
         let mut stats = self.stats.borrow_mut();
         stats.w += 1;
         stats.bw += block.as_ref().len();
-        self.base.put(code, block)
+        self.base
+            .put(code, block)
+            .map_err(|e| BlockstoreError::new(BlockstoreOp::Put, block.cid(code), e).into())
     }
 
     fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
         let mut stats = self.stats.borrow_mut();
         stats.w += 1;
         stats.bw += block.len();
-        self.base.put_keyed(k, block)
+        self.base
+            .put_keyed(k, block)
+            .block_context(BlockstoreOp::Put, k)
     }
 
     fn put_many<D, I>(&self, blocks: I) -> Result<()>
@@ -140,4 +149,33 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn batch_tracking_store() {
+        let mem = MemoryBlockstore::default();
+        let tr_store = TrackingBlockstore::new(&mem);
+
+        let blocks = [
+            Block::new(0x55, &b"foo"[..]),
+            Block::new(0x55, &b"barbaz"[..]),
+        ];
+        let cids: Vec<_> = blocks.iter().map(|b| b.cid(Code::Blake2b256)).collect();
+        tr_store
+            .put_many_keyed(cids.iter().copied().zip(blocks.iter().map(|b| b.data)))
+            .unwrap();
+        let missing = Block::new(0x55, &b"missing"[..]).cid(Code::Blake2b256);
+        assert_eq!(
+            tr_store.has_many(&[cids[1], missing, cids[0]]).unwrap(),
+            vec![true, false, true]
+        );
+        assert_eq!(
+            *tr_store.stats.borrow(),
+            BSStats {
+                r: 3,
+                br: 0,
+                w: 2,
+                bw: 9,
+            }
+        );
+    }
 }
@@ -5,6 +5,7 @@ mod bytes;
 mod cbor;
 mod cbor_store;
 mod errors;
+mod limits;
 mod vec;
 use std::io;
 
@@ -15,6 +16,7 @@ pub use self::bytes::*;
 pub use self::cbor::*;
 pub use self::cbor_store::CborStore;
 pub use self::errors::*;
+pub use self::limits::{check_decode_limits, MAX_NESTING_DEPTH};
 pub use self::vec::*;
 
 // TODO: these really don't work all that well in a shared context like this as anyone importing
@@ -38,6 +40,9 @@ where
 }
 
 /// Decode a value from CBOR from the given reader.
+///
+/// Unlike [`from_slice`], this doesn't check the [decoding limits][check_decode_limits], so it
+/// must only be used on trusted input.
 pub fn from_reader<T, R>(reader: R) -> Result<T, Error>
 where
     T: de::DeserializeOwned,
@@ -47,10 +52,14 @@ where
 }
 
 /// Decode a value from CBOR from the given slice.
+///
+/// The input's structure is checked against the [decoding limits][check_decode_limits] first, so
+/// untrusted input can't make the decoder allocate or recurse out of proportion to its length.
 pub fn from_slice<'a, T>(slice: &'a [u8]) -> Result<T, Error>
 where
     T: de::Deserialize<'a>,
 {
+    check_decode_limits(slice)?;
     serde_ipld_dagcbor::from_slice(slice).map_err(Into::into)
 }
 
@@ -0,0 +1,178 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Structural limits checked before decoding untrusted CBOR.
+//!
+//! The decoder trusts the lengths declared by the input: an array claiming 2^60 elements, or a
+//! deeply nested value, can make it allocate or recurse far more than the input's size warrants.
+//! Checking the input's structure first (without allocating) bounds the work of decoding it by
+//! the input's length.
+
+use crate::errors::{CodecProtocol, Error};
+
+/// The maximum nesting depth of arrays, maps, and tags in a decoded value.
+pub const MAX_NESTING_DEPTH: usize = 64;
+
+// CBOR major types.
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+
+/// The "additional information" marking an indefinite-length item, which DAG-CBOR forbids.
+const INDEFINITE_LENGTH: u8 = 31;
+
+fn limit_error(description: impl Into<String>) -> Error {
+    Error {
+        description: description.into(),
+        protocol: CodecProtocol::Cbor,
+    }
+}
+
+/// Reads the head of a CBOR item, returning its major type, its argument, and the rest of the
+/// input.
+fn read_head(input: &[u8]) -> Result<(u8, u64, &[u8]), Error> {
+    let (&first, rest) = input
+        .split_first()
+        .ok_or_else(|| limit_error("unexpected end of input"))?;
+    let major = first >> 5;
+    let len = match first & 0x1f {
+        info @ 0..=23 => return Ok((major, info as u64, rest)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        INDEFINITE_LENGTH => return Err(limit_error("indefinite-length items are not allowed")),
+        info => {
+            return Err(limit_error(format!(
+                "invalid additional information {}",
+                info
+            )))
+        }
+    };
+    if rest.len() < len {
+        return Err(limit_error("unexpected end of input"));
+    }
+    let (arg, rest) = rest.split_at(len);
+    let arg = arg.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    Ok((major, arg, rest))
+}
+
+/// Checks that the first CBOR item in `input` can be decoded without exceeding the decoding
+/// limits:
+///
+/// - Arrays, maps, and tags are nested at most [`MAX_NESTING_DEPTH`] levels deep.
+/// - Arrays and maps declare no more elements than there are bytes left to encode them.
+/// - Byte and text strings declare no more bytes than there are left.
+///
+/// This doesn't allocate, and takes time linear in the input's length. Anything following the
+/// first item is left for the decoder to reject.
+pub fn check_decode_limits(mut input: &[u8]) -> Result<(), Error> {
+    // The number of items left to read at each nesting level, starting with the single top-level
+    // item.
+    let mut pending = [0u64; MAX_NESTING_DEPTH + 1];
+    pending[0] = 1;
+    let mut depth = 0;
+    loop {
+        if pending[depth] == 0 {
+            if depth == 0 {
+                return Ok(());
+            }
+            depth -= 1;
+            continue;
+        }
+        pending[depth] -= 1;
+
+        let (major, arg, rest) = read_head(input)?;
+        input = rest;
+        let remaining = input.len() as u64;
+        let items = match major {
+            MAJOR_BYTES | MAJOR_TEXT => {
+                if arg > remaining {
+                    return Err(limit_error(format!(
+                        "string of {} bytes exceeds the {} bytes left",
+                        arg, remaining
+                    )));
+                }
+                input = &input[arg as usize..];
+                continue;
+            }
+            MAJOR_ARRAY => arg,
+            MAJOR_MAP => arg.saturating_mul(2),
+            MAJOR_TAG => 1,
+            // Integers, floats, and simple values have no content beyond their head.
+            _ => continue,
+        };
+        // Every item takes at least one byte.
+        if items > remaining {
+            return Err(limit_error(format!(
+                "collection of {} items exceeds the {} bytes left",
+                items, remaining
+            )));
+        }
+        if depth == MAX_NESTING_DEPTH {
+            return Err(limit_error(format!(
+                "nesting depth exceeds {}",
+                MAX_NESTING_DEPTH
+            )));
+        }
+        depth += 1;
+        pending[depth] = items;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_slice, to_vec, RawBytes};
+
+    fn nested(depth: usize) -> Vec<u8> {
+        let mut bytes = vec![0x81; depth];
+        bytes.push(0x00);
+        bytes
+    }
+
+    #[test]
+    fn nesting_depth() {
+        assert!(check_decode_limits(&nested(MAX_NESTING_DEPTH)).is_ok());
+        assert!(check_decode_limits(&nested(MAX_NESTING_DEPTH + 1)).is_err());
+
+        // Empty collections count too.
+        let mut bytes = vec![0x81; MAX_NESTING_DEPTH];
+        bytes.push(0x80);
+        assert!(check_decode_limits(&bytes).is_err());
+    }
+
+    #[test]
+    fn declared_lengths() {
+        // An array of two items, with only one left.
+        assert!(check_decode_limits(&[0x82, 0x00]).is_err());
+        // A map of one entry, with only the key left.
+        assert!(check_decode_limits(&[0xa1, 0x00]).is_err());
+        // A byte string of 2^32 bytes.
+        assert!(check_decode_limits(&[0x5a, 0xff, 0xff, 0xff, 0xff]).is_err());
+        // Truncated heads.
+        assert!(check_decode_limits(&[0x19, 0x01]).is_err());
+        assert!(check_decode_limits(&[]).is_err());
+        // Indefinite-length arrays.
+        assert!(check_decode_limits(&[0x9f, 0xff]).is_err());
+    }
+
+    #[test]
+    fn valid_values() {
+        let value = (
+            vec![1u64, u64::MAX],
+            "text".to_owned(),
+            RawBytes::new(vec![0; 300]),
+            Some(-5i64),
+            1.5f64,
+        );
+        let bytes = to_vec(&value).unwrap();
+        assert!(check_decode_limits(&bytes).is_ok());
+        assert_eq!(
+            from_slice::<(Vec<u64>, String, RawBytes, Option<i64>, f64)>(&bytes).unwrap(),
+            value
+        );
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use fvm_ipld_encoding::de::{DeserializeOwned, IgnoredAny};
+use fvm_ipld_encoding::{from_slice, RawBytes, MAX_NESTING_DEPTH};
+
+/// Records the largest allocation made since it was last reset.
+struct LargestAllocation;
+
+static LARGEST: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for LargestAllocation {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LARGEST.fetch_max(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LARGEST.fetch_max(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: LargestAllocation = LargestAllocation;
+
+/// Asserts that decoding the input fails quickly, without any large allocation.
+fn assert_rejected<T: DeserializeOwned>(name: &str, input: &[u8]) {
+    LARGEST.store(0, Ordering::Relaxed);
+    let start = Instant::now();
+    let res = from_slice::<T>(input);
+    let elapsed = start.elapsed();
+    let largest = LARGEST.load(Ordering::Relaxed);
+
+    assert!(res.is_err(), "{}: decoded", name);
+    assert!(largest <= 4096, "{}: allocated {} bytes", name, largest);
+    assert!(
+        elapsed < Duration::from_secs(1),
+        "{}: took {:?}",
+        name,
+        elapsed
+    );
+}
+
+/// A head with the given major type and an 8 byte argument.
+fn head(major: u8, arg: u64) -> Vec<u8> {
+    let mut bytes = vec![(major << 5) | 27];
+    bytes.extend_from_slice(&arg.to_be_bytes());
+    bytes
+}
+
+// All fixtures are checked in a single test, so that no other test allocates concurrently.
+#[test]
+fn decode_bombs() {
+    // Arrays, maps, and strings claiming far more content than the input holds.
+    assert_rejected::<Vec<u64>>("huge array", &head(4, 1 << 60));
+    assert_rejected::<Vec<IgnoredAny>>("huge array", &head(4, u64::MAX));
+    assert_rejected::<BTreeMap<u64, u64>>("huge map", &head(5, 1 << 62));
+    assert_rejected::<RawBytes>("huge bytes", &head(2, u64::MAX));
+    assert_rejected::<String>("huge text", &head(3, 1 << 40));
+
+    // A huge array nested inside a plausible one.
+    let mut nested_huge = vec![0x82, 0x00];
+    nested_huge.extend(head(4, 1 << 60));
+    assert_rejected::<(u64, Vec<u64>)>("nested huge array", &nested_huge);
+
+    // Deeply nested arrays and tags.
+    let mut deep = vec![0x81; 1 << 20];
+    deep.push(0x00);
+    assert_rejected::<IgnoredAny>("deep arrays", &deep);
+    let mut tags = vec![0xc0; MAX_NESTING_DEPTH + 1];
+    tags.push(0x00);
+    assert_rejected::<IgnoredAny>("deep tags", &tags);
+}
@@ -300,13 +300,20 @@ where
 
     /// Flush root and return Cid for hamt
     pub fn flush(&mut self) -> Result<Cid, Error> {
+        self.flush_with_count().map(|(cid, _)| cid)
+    }
+
+    /// Flushes the HAMT like [`flush`](Self::flush), additionally returning the number of nodes
+    /// written (including the root). Nothing is written if the HAMT hasn't been modified since it
+    /// was last flushed or loaded.
+    pub fn flush_with_count(&mut self) -> Result<(Cid, usize), Error> {
         if let Some(cid) = self.flushed_cid {
-            return Ok(cid);
+            return Ok((cid, 0));
         }
-        self.root.flush(self.store.borrow())?;
+        let written = self.root.flush(self.store.borrow())?;
         let cid = self.store.put_cbor(&self.root, Code::Blake2b256)?;
         self.flushed_cid = Some(cid);
-        Ok(cid)
+        Ok((cid, written + 1))
     }
 
     /// Returns true if the HAMT has no entries
@@ -431,11 +431,13 @@ where
         }
     }
 
-    pub fn flush<S: Blockstore>(&mut self, store: &S) -> Result<(), Error> {
+    /// Writes the dirty sub nodes to the store, returning the number of nodes written.
+    pub fn flush<S: Blockstore>(&mut self, store: &S) -> Result<usize, Error> {
+        let mut written = 0;
         for pointer in &mut self.pointers {
             if let Pointer::Dirty(node) = pointer {
                 // Flush cached sub node to clear it's cache
-                node.flush(store)?;
+                written += node.flush(store)? + 1;
 
                 // Put node in blockstore and retrieve Cid
                 let cid = store.put_cbor(node, Code::Blake2b256)?;
@@ -448,7 +450,7 @@ where
             }
         }
 
-        Ok(())
+        Ok(written)
     }
 
     fn rm_child(&mut self, i: usize, idx: u32) -> Pointer<K, V, H> {
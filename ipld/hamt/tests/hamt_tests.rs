@@ -136,6 +136,33 @@ fn set_with_no_effect_does_not_put() {
     assert_eq!(*store.stats.borrow(), BSStats {r:0, w:18, br:0, bw:1282});
 }
 
+#[test]
+fn flush_with_count() {
+    let mem = MemoryBlockstore::default();
+    let store = TrackingBlockstore::new(&mem);
+
+    let mut hamt: Hamt<_, _> = Hamt::new_with_bit_width(&store, 1);
+    for i in 0..2 * BUCKET_SIZE * 5 {
+        hamt.set(tstring(i), tstring("filler")).unwrap();
+    }
+
+    // Every node written is counted.
+    let (c, written) = hamt.flush_with_count().unwrap();
+    assert_eq!(written, store.stats.borrow().w);
+
+    // Nothing is written if nothing changed.
+    assert_eq!(hamt.flush_with_count().unwrap(), (c, 0));
+    hamt.set(tstring(0), tstring("filler")).unwrap();
+    assert_eq!(hamt.flush_with_count().unwrap(), (c, 0));
+
+    // Otherwise, only the path to the modified entry is rewritten.
+    let before = store.stats.borrow().w;
+    hamt.set(tstring(0), tstring("modified")).unwrap();
+    let (_, written) = hamt.flush_with_count().unwrap();
+    assert_eq!(written, store.stats.borrow().w - before);
+    assert!(written < before);
+}
+
 #[test]
 fn delete() {
     let mem = MemoryBlockstore::default();
@@ -69,6 +69,14 @@ pub fn install_actor(code_cid: &Cid) -> SyscallResult<()> {
     unsafe { sys::actor::install_actor(cid.as_ptr()) }
 }
 
+/// Replaces the code of the specified actor, preserving its balance, sequence, and state.
+/// Note: this is a privileged syscall, restricted to the system actor (and the upgrade admin on
+/// devnets).
+pub fn upgrade_actor(actor_id: ActorID, new_code_cid: &Cid) -> SyscallResult<()> {
+    let cid = new_code_cid.to_bytes();
+    unsafe { sys::actor::upgrade_actor(actor_id, cid.as_ptr()) }
+}
+
 /// Determines whether the supplied CodeCID belongs to a built-in actor type,
 /// and to which.
 pub fn get_builtin_actor_type(code_cid: &Cid) -> Option<i32> {
@@ -97,7 +105,8 @@ pub fn get_code_cid_for_type(typ: i32) -> Cid {
     }
 }
 
-/// Retrieves the balance associated with an actor
+/// Retrieves the balance of the specified actor, or zero if it doesn't exist. Use
+/// [`current_balance`](crate::sself::current_balance) to get the calling actor's balance.
 pub fn balance_of(actor_id: ActorID) -> TokenAmount {
     unsafe {
         sys::actor::balance_of(actor_id)
@@ -4,8 +4,9 @@ use fvm_shared::address::Address;
 use fvm_shared::consensus::ConsensusFault;
 use fvm_shared::crypto::hash::SupportedHashes;
 use fvm_shared::crypto::signature::{
-    Signature, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
+    Signature, BLS_PUB_LEN, BLS_SIG_LEN, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
 };
+use fvm_shared::error::ErrorNumber;
 use fvm_shared::piece::PieceInfo;
 use fvm_shared::sector::{
     AggregateSealVerifyProofAndInfos, RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo,
@@ -39,6 +40,30 @@ pub fn verify_signature(
     }
 }
 
+/// Verifies an aggregate BLS signature over a set of plaintexts, where the i-th plaintext was
+/// signed by the i-th public key.
+pub fn verify_bls_aggregate(
+    sig: &[u8; BLS_SIG_LEN],
+    pub_keys: &[[u8; BLS_PUB_LEN]],
+    plaintexts: &[&[u8]],
+) -> SyscallResult<bool> {
+    if pub_keys.len() != plaintexts.len() {
+        return Err(ErrorNumber::IllegalArgument);
+    }
+    let plaintext_concat: Vec<u8> = plaintexts.iter().flat_map(|p| p.iter().copied()).collect();
+    let plaintext_lens: Vec<u32> = plaintexts.iter().map(|p| p.len() as u32).collect();
+    unsafe {
+        sys::crypto::verify_bls_aggregate(
+            pub_keys.len() as u32,
+            sig.as_ptr(),
+            pub_keys.as_ptr(),
+            plaintext_concat.as_ptr(),
+            plaintext_lens.as_ptr(),
+        )
+        .map(status_code_to_bool)
+    }
+}
+
 /// Recovers the signer public key from the message hash and signature.
 pub fn recover_secp_public_key(
     hash: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
@@ -3,10 +3,11 @@ use crate::sys;
 /// Charge gas for the operation identified by name.
 pub fn charge(name: &str, compute: u64) {
     unsafe { sys::gas::charge(name.as_ptr(), name.len() as u32, compute) }
-        // can only happen if name isn't utf8, memory corruption, etc.
+        // can only happen if name isn't utf8 or is too long, memory corruption, etc.
         .expect("failed to charge gas")
 }
 
+/// Returns the amount of gas remaining, in whole gas units.
 pub fn available() -> u64 {
     unsafe { sys::gas::available() }.expect("failed to check available gas")
 }
@@ -40,15 +40,34 @@ pub fn get(cid: &Cid) -> SyscallResult<Vec<u8>> {
     }
 }
 
+/// Returns the codec and size of the block referenced by BlockId, without reading it.
+pub fn stat_block(
+    id: fvm_shared::sys::BlockId,
+) -> SyscallResult<fvm_shared::sys::out::ipld::IpldStat> {
+    unsafe { sys::ipld::block_stat(id) }
+}
+
+/// Reads the block referenced by BlockId into `buf`, starting at `offset`. Returns the number of
+/// bytes remaining in the block after the end of the buffer (negative if the buffer extends past
+/// the end of the block).
+pub fn read_block(id: fvm_shared::sys::BlockId, offset: u32, buf: &mut [u8]) -> SyscallResult<i32> {
+    unsafe { sys::ipld::block_read(id, offset, buf.as_mut_ptr(), buf.len() as u32) }
+}
+
 /// Gets the data of the block referenced by BlockId. If the caller knows the size, this function
-/// will read the block in a single syscall. Otherwise, any block over 1KiB will take two syscalls.
+/// will read the block in a single syscall. Otherwise, it'll stat the block first to learn its
+/// size.
 pub fn get_block(id: fvm_shared::sys::BlockId, size_hint: Option<u32>) -> SyscallResult<Vec<u8>> {
     // Check for the "empty" block first.
     if id == UNIT {
         return Ok(Vec::new());
     }
 
-    let mut buf = Vec::with_capacity(size_hint.unwrap_or(1024) as usize);
+    let size = match size_hint {
+        Some(size) => size,
+        None => stat_block(id)?.size,
+    };
+    let mut buf = Vec::with_capacity(size as usize);
     unsafe {
         let mut remaining = sys::ipld::block_read(id, 0, buf.as_mut_ptr(), buf.capacity() as u32)?;
         if remaining > 0 {
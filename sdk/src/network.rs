@@ -1,6 +1,7 @@
 use std::convert::TryInto;
 
 use cid::Cid;
+use fvm_shared::chainid::ChainID;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ErrorNumber;
@@ -41,6 +42,12 @@ pub fn tipset_timestamp() -> u64 {
     unsafe { sys::network::tipset_timestamp() }.expect("failed to get timestamp")
 }
 
+pub fn chain_id() -> ChainID {
+    unsafe { sys::network::chain_id() }
+        .expect("failed to get chain id")
+        .into()
+}
+
 pub fn tipset_cid(epoch: i64) -> Option<Cid> {
     let mut buf = [0u8; MAX_CID_LEN];
 
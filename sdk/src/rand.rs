@@ -1,5 +1,5 @@
 use fvm_shared::clock::ChainEpoch;
-use fvm_shared::randomness::RANDOMNESS_LENGTH;
+use fvm_shared::randomness::Randomness;
 
 use crate::{sys, SyscallResult};
 
@@ -11,11 +11,11 @@ pub fn get_chain_randomness(
     dst: i64,
     round: ChainEpoch,
     entropy: &[u8],
-) -> SyscallResult<[u8; RANDOMNESS_LENGTH]> {
+) -> SyscallResult<Randomness> {
     let ret = unsafe {
-        sys::rand::get_chain_randomness(dst, round as i64, entropy.as_ptr(), entropy.len() as u32)?
+        sys::rand::get_chain_randomness(dst, round.value(), entropy.as_ptr(), entropy.len() as u32)?
     };
-    Ok(ret)
+    Ok(Randomness(ret))
 }
 
 /// Gets 32 bytes of randomness from the beacon system (currently Drand).
@@ -26,9 +26,14 @@ pub fn get_beacon_randomness(
     dst: i64,
     round: ChainEpoch,
     entropy: &[u8],
-) -> SyscallResult<[u8; RANDOMNESS_LENGTH]> {
+) -> SyscallResult<Randomness> {
     let ret = unsafe {
-        sys::rand::get_beacon_randomness(dst, round as i64, entropy.as_ptr(), entropy.len() as u32)?
+        sys::rand::get_beacon_randomness(
+            dst,
+            round.value(),
+            entropy.as_ptr(),
+            entropy.len() as u32,
+        )?
     };
-    Ok(ret)
+    Ok(Randomness(ret))
 }
@@ -4,20 +4,38 @@ use fvm_ipld_encoding::{RawBytes, DAG_CBOR};
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::{ErrorNumber, ExitCode};
-use fvm_shared::receipt::Receipt;
 use fvm_shared::MethodNum;
 
 use crate::{sys, SyscallResult, NO_DATA_BLOCK_ID};
 
+/// The result of a send that reached the receiving actor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+    /// The exit code: [`ExitCode::OK`] if the receiver returned, or the code it aborted with.
+    pub exit_code: ExitCode,
+    /// The returned block, if any. Always `None` if the receiver aborted.
+    pub return_data: Option<ReturnBlock>,
+}
+
+/// A block returned by the receiver of a send.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReturnBlock {
+    /// The block's codec.
+    pub codec: u64,
+    /// The block's data.
+    pub data: RawBytes,
+}
+
 /// Sends a message to another actor.
-// TODO: Drop the use of receipts here as we don't return the gas used. Alternatively, we _could_
-// return gas used?
+///
+/// The receiver aborting isn't an error: its exit code is returned in the [`Response`], and the
+/// caller may handle or ignore it. Errors are reserved for sends that couldn't be made at all.
 pub fn send(
     to: &Address,
     method: MethodNum,
     params: RawBytes,
     value: TokenAmount,
-) -> SyscallResult<Receipt> {
+) -> SyscallResult<Response> {
     let recipient = to.to_bytes();
     let value: fvm_shared::sys::TokenAmount = value
         .try_into()
@@ -35,7 +53,7 @@ pub fn send(
         let fvm_shared::sys::out::send::Send {
             exit_code,
             return_id,
-            return_codec: _, // assume cbor for now.
+            return_codec,
             return_size,
         } = sys::send::send(
             recipient.as_ptr(),
@@ -56,15 +74,17 @@ pub fn send(
                 // Now read the return data.
                 let unread = sys::ipld::block_read(return_id, 0, bytes.as_mut_ptr(), return_size)?;
                 assert_eq!(0, unread);
-                RawBytes::from(bytes)
+                Some(ReturnBlock {
+                    codec: return_codec,
+                    data: RawBytes::from(bytes),
+                })
             }
-            _ => Default::default(),
+            _ => None,
         };
 
-        Ok(Receipt {
+        Ok(Response {
             exit_code,
             return_data,
-            gas_used: 0,
         })
     }
 }
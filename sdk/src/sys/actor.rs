@@ -97,11 +97,45 @@ super::fvm_syscalls! {
     pub fn create_actor(actor_id: u64, typ_off: *const u8) -> Result<()>;
 
     /// Installs and ensures actor code is valid and loaded.
-    /// **Privileged:** May only be called by the init actor.
+    /// **Privileged:** May only be called by the init actor, the system actor, or the network's
+    /// upgrade admin.
     #[cfg(feature = "m2-native")]
     pub fn install_actor(cid_off: *const u8) -> Result<()>;
 
+    /// Gets the balance of the specified actor. Actors that don't exist have a zero balance.
+    ///
+    /// The balance reflects all transfers made so far in the current message, including the value
+    /// sent with pending calls. To get the calling actor's balance, prefer
+    /// [`current_balance`](crate::sys::sself::current_balance), which is cheaper.
+    ///
+    /// # Arguments
+    ///
+    /// - `actor_id` is the ID of the actor.
+    ///
+    /// # Errors
+    ///
+    /// None.
     pub fn balance_of(
         actor_id: u64
     )  -> Result<super::TokenAmount>;
+
+    /// Replaces the code of the specified actor, preserving its balance, sequence, and state.
+    ///
+    /// **Privileged:** May only be called by the system actor, or the network's upgrade admin (on
+    /// devnets).
+    ///
+    /// # Arguments
+    ///
+    /// - `actor_id` is the ID of the actor to upgrade.
+    /// - `new_code_cid_off` is the offset (in wasm memory) of the actor's new code CID.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                                                  |
+    /// |---------------------|---------------------------------------------------------|
+    /// | [`Forbidden`]       | the caller may not change actor code                    |
+    /// | [`NotFound`]        | the actor doesn't exist                                 |
+    /// | [`IllegalArgument`] | the CID is malformed, or its code can't be loaded       |
+    #[doc(hidden)]
+    pub fn upgrade_actor(actor_id: u64, new_code_cid_off: *const u8) -> Result<()>;
 }
@@ -1,6 +1,6 @@
 //! Syscalls for cryptographic operations.
 
-use fvm_shared::crypto::signature::SECP_PUB_LEN;
+use fvm_shared::crypto::signature::{BLS_PUB_LEN, SECP_PUB_LEN};
 #[doc(inline)]
 pub use fvm_shared::sys::out::crypto::*;
 
@@ -37,6 +37,32 @@ super::fvm_syscalls! {
         plaintext_len: u32,
     ) -> Result<i32>;
 
+    /// Verifies an aggregate BLS signature over a set of plaintexts, where the i-th plaintext was
+    /// signed by the i-th public key.
+    ///
+    /// Returns 0 on success, or -1 if the signature fails to validate.
+    ///
+    /// # Arguments
+    ///
+    /// - `num_signers` is the number of public keys and plaintexts.
+    /// - `sig_off` specifies the location of a 96-byte aggregate signature.
+    /// - `pub_keys_off` specifies the location of `num_signers` 48-byte public keys, back to back.
+    /// - `plaintexts_off` specifies the location of the plaintexts, back to back.
+    /// - `plaintext_lens_off` specifies the location of `num_signers` u32 plaintext lengths.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                                               |
+    /// |---------------------|------------------------------------------------------|
+    /// | [`IllegalArgument`] | signature, key, or plaintext buffers are invalid     |
+    pub fn verify_bls_aggregate(
+        num_signers: u32,
+        sig_off: *const u8,
+        pub_keys_off: *const [u8; BLS_PUB_LEN],
+        plaintexts_off: *const u8,
+        plaintext_lens_off: *const u32,
+    ) -> Result<i32>;
+
     /// Recovers the signer public key from a signed message hash and its signature.
     ///
     /// Returns the public key in uncompressed 65 bytes form.
@@ -7,19 +7,20 @@ use crate::sys::ErrorNumber::*;
 super::fvm_syscalls! {
     module = "gas";
 
-    /// Charge gas.
+    /// Charge gas. If the charge exceeds the gas remaining, the actor runs out of gas and execution
+    /// aborts, exactly as it would when running out of gas any other way.
     ///
     /// # Arguments
     ///
     /// - `name_off` and `name_len` specify the location and length of the "name" of the gas charge,
-    ///   for debugging.
-    /// - `amount` is the amount of gas to charge.
+    ///   for debugging. This name is recorded in execution traces, and may be at most 256 bytes.
+    /// - `amount` is the amount of gas to charge, in whole gas units.
     ///
     /// # Errors
     ///
-    /// | Error               | Reason               |
-    /// |---------------------|----------------------|
-    /// | [`IllegalArgument`] | invalid name buffer. |
+    /// | Error               | Reason                                  |
+    /// |---------------------|-----------------------------------------|
+    /// | [`IllegalArgument`] | invalid or too long (> 256 bytes) name. |
     pub fn charge(name_off: *const u8, name_len: u32, amount: u64) -> Result<()>;
 
     /// Returns the amount of gas remaining.
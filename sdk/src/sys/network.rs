@@ -17,7 +17,8 @@ super::fvm_syscalls! {
     /// None
     pub fn total_fil_circ_supply() -> Result<super::TokenAmount>;
 
-    /// Gets the current tipset's timestamp
+    /// Gets the current tipset's timestamp, in seconds since the UNIX epoch. Every message in a
+    /// tipset observes the same timestamp.
     ///
     /// # Errors
     ///
@@ -34,4 +35,11 @@ super::fvm_syscalls! {
         ret_off: *mut u8,
         ret_len: u32,
     ) -> Result<u32>;
+
+    /// Gets the network's chain ID.
+    ///
+    /// # Errors
+    ///
+    /// None
+    pub fn chain_id() -> Result<u64>;
 }
@@ -36,8 +36,8 @@ super::fvm_syscalls! {
     /// | [`NotFound`]          | target actor does not exist and cannot be created.   |
     /// | [`InsufficientFunds`] | tried to send more FIL than available.               |
     /// | [`InvalidHandle`]     | parameters block not found.                          |
-    /// | [`LimitExceeded`]     | recursion limit reached.                             |
-    /// | [`IllegalArgument`]   | invalid recipient address buffer.                    |
+    /// | [`LimitExceeded`]     | recursion limit reached, or params/return too large. |
+    /// | [`IllegalArgument`]   | invalid recipient, or value > supply (from nv17).    |
     pub fn send(
         recipient_off: *const u8,
         recipient_len: u32,
@@ -39,7 +39,9 @@ super::fvm_syscalls! {
     /// | [`NotFound`]         | specified root CID is not in the reachable set |
     pub fn set_root(cid: *const u8) -> Result<()>;
 
-    /// Gets the current balance for the calling actor.
+    /// Gets the current balance for the calling actor, including the value received with the
+    /// current call and any funds transferred since. Unlike
+    /// [`balance_of`](crate::sys::actor::balance_of), this doesn't charge for an actor lookup.
     ///
     /// # Errors
     ///
@@ -49,6 +51,11 @@ super::fvm_syscalls! {
     /// Destroys the calling actor, sending its current balance
     /// to the supplied address, which cannot be itself.
     ///
+    /// The actor is only removed from the state tree once the current invocation returns
+    /// successfully: if it aborts, the actor (and its balance) is restored. Until then, the actor
+    /// can still send messages and read the blocks it has open, but [`root`] and [`set_root`] fail
+    /// with [`IllegalOperation`].
+    ///
     /// # Arguments
     ///
     /// - `addr_off` and `addr_len` specify the location and length of beneficiary's address in wasm
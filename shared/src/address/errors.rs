@@ -7,7 +7,7 @@ use data_encoding::DecodeError;
 use thiserror::Error;
 use unsigned_varint::decode::Error as VarintError;
 
-use super::{BLS_PUB_LEN, SECP_PUB_LEN};
+use super::{BLS_PUB_LEN, MAX_ID, SECP_PUB_LEN};
 
 /// Address error
 #[derive(Debug, PartialEq, Error)]
@@ -32,6 +32,8 @@ pub enum Error {
     Base32Decoding(#[from] DecodeError),
     #[error("Cannot get id from non id address")]
     NonIDAddress,
+    #[error("ID {0} exceeds the maximum of {}", MAX_ID)]
+    IDOutOfRange(u64),
 }
 
 impl From<num::ParseIntError> for Error {
@@ -41,7 +41,11 @@ pub const BLS_PUB_LEN: usize = 48;
 pub const MAX_SUBADDRESS_LEN: usize = 32; // TODO: decide on a final length.
 
 /// Defines first available ID address after builtin actors
-pub const FIRST_NON_SINGLETON_ADDR: ActorID = 100;
+pub use crate::builtin::singletons::FIRST_NON_SINGLETON_ADDR;
+
+/// The largest ID an ID address may carry: IDs must also be valid (non-negative) signed 64-bit
+/// integers.
+pub const MAX_ID: ActorID = i64::MAX as ActorID;
 
 lazy_static::lazy_static! {
     static ref BLS_ZERO_ADDR_BYTES: [u8; BLS_PUB_LEN] = {
@@ -86,7 +90,10 @@ impl Address {
         })
     }
 
-    /// Creates address from encoded bytes
+    /// Creates address from encoded bytes: the protocol byte followed by the payload, as returned
+    /// by [`Address::to_bytes`]. The payload must have the exact length required by its protocol
+    /// (see [`Address::payload_bytes`]), with nothing trailing it, and IDs must not exceed
+    /// [`MAX_ID`].
     pub fn from_bytes(bz: &[u8]) -> Result<Self, Error> {
         if bz.len() < 2 {
             Err(Error::InvalidLength)
@@ -96,7 +103,10 @@ impl Address {
         }
     }
 
-    /// Generates new address using ID protocol
+    /// Generates new address using ID protocol.
+    ///
+    /// This doesn't check the ID: [`Address::id`] rejects IDs above [`MAX_ID`], and so does
+    /// decoding the address.
     pub const fn new_id(id: u64) -> Self {
         Self {
             network: NETWORK_DEFAULT,
@@ -168,7 +178,14 @@ impl Address {
         self.payload
     }
 
-    /// Returns the raw bytes data payload of the Address
+    /// Returns the raw bytes data payload of the Address, i.e., its encoding without the protocol
+    /// byte:
+    ///
+    /// - ID: the ID as an unsigned LEB128 varint (1 to 9 bytes, as the ID is at most [`MAX_ID`]).
+    /// - Secp256k1 and Actor: a [`PAYLOAD_HASH_LEN`] byte hash.
+    /// - BLS: a [`BLS_PUB_LEN`] byte public key.
+    /// - Delegated: the namespace as an unsigned LEB128 varint, followed by the subaddress (at
+    ///   most [`MAX_SUBADDRESS_LEN`] bytes).
     pub fn payload_bytes(&self) -> Vec<u8> {
         self.payload.to_raw_bytes()
     }
@@ -189,9 +206,10 @@ impl Address {
         self.payload.to_bytes()
     }
 
-    /// Get ID of the address. ID protocol only.
-    pub fn id(&self) -> Result<u64, Error> {
+    /// Get ID of the address. ID protocol only. Fails if the ID exceeds [`MAX_ID`].
+    pub fn id(&self) -> Result<ActorID, Error> {
         match self.payload {
+            Payload::ID(id) if id > MAX_ID => Err(Error::IDOutOfRange(id)),
             Payload::ID(id) => Ok(id),
             _ => Err(Error::NonIDAddress),
         }
@@ -274,7 +292,7 @@ impl FromStr for Address {
                     // 20 is max u64 as string
                     return Err(Error::InvalidLength);
                 }
-                let id = raw.parse::<u64>()?;
+                let id = check_id(raw.parse::<u64>()?)?;
                 Ok(Address {
                     network,
                     payload: Payload::ID(id),
@@ -355,6 +373,14 @@ pub(crate) fn to_leb_bytes(id: u64) -> Vec<u8> {
     unsigned_varint::encode::u64(id, &mut unsigned_varint::encode::u64_buffer()).into()
 }
 
+/// Checks that `id` doesn't exceed [`MAX_ID`].
+pub(crate) fn check_id(id: u64) -> Result<ActorID, Error> {
+    if id > MAX_ID {
+        return Err(Error::IDOutOfRange(id));
+    }
+    Ok(id)
+}
+
 pub(crate) fn from_leb_bytes(bz: &[u8]) -> Result<u64, Error> {
     // write id to buffer in leb128 format
     let (id, remaining) = unsigned_varint::decode::u64(bz)?;
@@ -6,8 +6,8 @@ use std::hash::Hash;
 use std::u64;
 
 use super::{
-    from_leb_bytes, to_leb_bytes, Error, Protocol, BLS_PUB_LEN, MAX_SUBADDRESS_LEN,
-    PAYLOAD_HASH_LEN,
+    check_id, from_leb_bytes, to_leb_bytes, Error, Protocol, BLS_PUB_LEN, MAX_ID,
+    MAX_SUBADDRESS_LEN, PAYLOAD_HASH_LEN,
 };
 use crate::ActorID;
 
@@ -23,7 +23,7 @@ pub struct DelegatedAddress {
 impl<'a> arbitrary::Arbitrary<'a> for DelegatedAddress {
     fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
         Ok(DelegatedAddress {
-            namespace: arbitrary::Arbitrary::arbitrary(u)?,
+            namespace: u.int_in_range(0..=MAX_ID)?,
             length: u.int_in_range(0usize..=MAX_SUBADDRESS_LEN)?,
             buffer: arbitrary::Arbitrary::arbitrary(u)?,
         })
@@ -33,6 +33,7 @@ impl<'a> arbitrary::Arbitrary<'a> for DelegatedAddress {
 impl DelegatedAddress {
     /// Construct a new delegated address from the namespace (actor id) and subaddress.
     pub fn new(namespace: ActorID, subaddress: &[u8]) -> Result<Self, Error> {
+        let namespace = check_id(namespace)?;
         let length = subaddress.len();
         if length > MAX_SUBADDRESS_LEN {
             return Err(Error::InvalidPayloadLength(length));
@@ -61,7 +62,6 @@ impl DelegatedAddress {
 
 /// Payload is the data of the Address. Variants are the supported Address protocols.
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
-#[cfg_attr(feature = "arb", derive(arbitrary::Arbitrary))]
 pub enum Payload {
     /// f0: ID protocol address.
     ID(u64),
@@ -75,6 +75,19 @@ pub enum Payload {
     Delegated(DelegatedAddress),
 }
 
+#[cfg(feature = "arb")]
+impl<'a> arbitrary::Arbitrary<'a> for Payload {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0u8..=4)? {
+            0 => Payload::ID(u.int_in_range(0..=MAX_ID)?),
+            1 => Payload::Secp256k1(arbitrary::Arbitrary::arbitrary(u)?),
+            2 => Payload::Actor(arbitrary::Arbitrary::arbitrary(u)?),
+            3 => Payload::BLS(arbitrary::Arbitrary::arbitrary(u)?),
+            _ => Payload::Delegated(arbitrary::Arbitrary::arbitrary(u)?),
+        })
+    }
+}
+
 impl Payload {
     /// Returns encoded bytes of Address without the protocol byte.
     pub fn to_raw_bytes(self) -> Vec<u8> {
@@ -99,10 +112,13 @@ impl Payload {
         bz
     }
 
-    /// Generates payload from raw bytes and protocol.
+    /// Generates payload from raw bytes and protocol. See [`Address::payload_bytes`] for the
+    /// length of each protocol's payload.
+    ///
+    /// [`Address::payload_bytes`]: super::Address::payload_bytes
     pub fn new(protocol: Protocol, payload: &[u8]) -> Result<Self, Error> {
         let payload = match protocol {
-            Protocol::ID => Self::ID(from_leb_bytes(payload)?),
+            Protocol::ID => Self::ID(check_id(from_leb_bytes(payload)?)?),
             Protocol::Secp256k1 => Self::Secp256k1(
                 payload
                     .try_into()
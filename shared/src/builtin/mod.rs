@@ -0,0 +1,6 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Protocol-level facts about the builtin actors.
+
+pub mod singletons;
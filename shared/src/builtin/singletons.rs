@@ -0,0 +1,90 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! The IDs and addresses of the singleton builtin actors. Singletons live at fixed IDs below
+//! [`FIRST_NON_SINGLETON_ADDR`], and are never assigned by the init actor.
+
+use crate::address::Address;
+use crate::ActorID;
+
+/// The system actor.
+pub const SYSTEM_ACTOR_ID: ActorID = 0;
+pub const SYSTEM_ACTOR_ADDR: Address = Address::new_id(SYSTEM_ACTOR_ID);
+
+/// The init actor, which assigns IDs to all other actors.
+pub const INIT_ACTOR_ID: ActorID = 1;
+pub const INIT_ACTOR_ADDR: Address = Address::new_id(INIT_ACTOR_ID);
+
+/// The reward actor, which receives gas tips (via the block miner) and pays block rewards.
+pub const REWARD_ACTOR_ID: ActorID = 2;
+pub const REWARD_ACTOR_ADDR: Address = Address::new_id(REWARD_ACTOR_ID);
+
+/// The cron actor.
+pub const CRON_ACTOR_ID: ActorID = 3;
+pub const CRON_ACTOR_ADDR: Address = Address::new_id(CRON_ACTOR_ID);
+
+/// The storage power actor.
+pub const STORAGE_POWER_ACTOR_ID: ActorID = 4;
+pub const STORAGE_POWER_ACTOR_ADDR: Address = Address::new_id(STORAGE_POWER_ACTOR_ID);
+
+/// The storage market actor.
+pub const STORAGE_MARKET_ACTOR_ID: ActorID = 5;
+pub const STORAGE_MARKET_ACTOR_ADDR: Address = Address::new_id(STORAGE_MARKET_ACTOR_ID);
+
+/// The verified registry actor.
+pub const VERIFIED_REGISTRY_ACTOR_ID: ActorID = 6;
+pub const VERIFIED_REGISTRY_ACTOR_ADDR: Address = Address::new_id(VERIFIED_REGISTRY_ACTOR_ID);
+
+/// The datacap token actor.
+pub const DATACAP_TOKEN_ACTOR_ID: ActorID = 7;
+pub const DATACAP_TOKEN_ACTOR_ADDR: Address = Address::new_id(DATACAP_TOKEN_ACTOR_ID);
+
+/// The reserve account, holding the funds reserved for future network use.
+pub const RESERVE_ACTOR_ID: ActorID = 90;
+pub const RESERVE_ACTOR_ADDR: Address = Address::new_id(RESERVE_ACTOR_ID);
+
+/// The chaos actor. It only exists on test networks.
+pub const CHAOS_ACTOR_ID: ActorID = 98;
+pub const CHAOS_ACTOR_ADDR: Address = Address::new_id(CHAOS_ACTOR_ID);
+
+/// The account to which all burnt funds (including burnt gas) are sent.
+pub const BURNT_FUNDS_ACTOR_ID: ActorID = 99;
+pub const BURNT_FUNDS_ACTOR_ADDR: Address = Address::new_id(BURNT_FUNDS_ACTOR_ID);
+
+/// The first ID assigned by the init actor. All lower IDs are reserved for singletons.
+pub const FIRST_NON_SINGLETON_ADDR: ActorID = 100;
+
+/// Returns true if the given ID is reserved for a singleton actor.
+pub const fn is_singleton(id: ActorID) -> bool {
+    id < FIRST_NON_SINGLETON_ADDR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol_ids() {
+        let ids = [
+            (SYSTEM_ACTOR_ADDR, SYSTEM_ACTOR_ID, 0),
+            (INIT_ACTOR_ADDR, INIT_ACTOR_ID, 1),
+            (REWARD_ACTOR_ADDR, REWARD_ACTOR_ID, 2),
+            (CRON_ACTOR_ADDR, CRON_ACTOR_ID, 3),
+            (STORAGE_POWER_ACTOR_ADDR, STORAGE_POWER_ACTOR_ID, 4),
+            (STORAGE_MARKET_ACTOR_ADDR, STORAGE_MARKET_ACTOR_ID, 5),
+            (VERIFIED_REGISTRY_ACTOR_ADDR, VERIFIED_REGISTRY_ACTOR_ID, 6),
+            (DATACAP_TOKEN_ACTOR_ADDR, DATACAP_TOKEN_ACTOR_ID, 7),
+            (RESERVE_ACTOR_ADDR, RESERVE_ACTOR_ID, 90),
+            (CHAOS_ACTOR_ADDR, CHAOS_ACTOR_ID, 98),
+            (BURNT_FUNDS_ACTOR_ADDR, BURNT_FUNDS_ACTOR_ID, 99),
+        ];
+        for (addr, id, expected) in ids {
+            assert_eq!(id, expected);
+            assert_eq!(addr.id().unwrap(), expected);
+            assert!(is_singleton(id));
+        }
+        assert_eq!(BURNT_FUNDS_ACTOR_ADDR.to_string(), "f099");
+        assert_eq!(FIRST_NON_SINGLETON_ADDR, 100);
+        assert!(!is_singleton(FIRST_NON_SINGLETON_ADDR));
+    }
+}
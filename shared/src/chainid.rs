@@ -0,0 +1,35 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Identifies a Filecoin network (mainnet, calibnet, a devnet, etc.). Actors can include it in
+/// signed payloads to prevent messages from being replayed across networks.
+#[derive(
+    PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Default, Clone, Copy, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct ChainID(u64);
+
+impl ChainID {
+    /// The chain ID of Filecoin mainnet.
+    pub const MAINNET: ChainID = ChainID(314);
+}
+
+impl From<u64> for ChainID {
+    fn from(id: u64) -> Self {
+        ChainID(id)
+    }
+}
+
+impl From<ChainID> for u64 {
+    fn from(id: ChainID) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for ChainID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
@@ -1,6 +1,11 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
 mod quantize;
 pub use quantize::*;
 
@@ -9,8 +14,188 @@ const _ISO_FORMAT: &str = "%FT%X.%.9F";
 /// Duration of each tipset epoch.
 pub const EPOCH_DURATION_SECONDS: i64 = 30;
 
+/// The number of epochs after which a tipset is considered final.
+pub const CHAIN_FINALITY: i64 = 900;
+
 /// Epoch number of a chain. This acts as a proxy for time within the VM.
-pub type ChainEpoch = i64;
+///
+/// Epochs are offset by durations (numbers of epochs, as `i64`), and the difference between two
+/// epochs is a duration. These operators panic on overflow; use [`ChainEpoch::checked_add`] and
+/// [`ChainEpoch::checked_sub`] when the operands aren't trusted.
+#[derive(
+    PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Default, Clone, Copy, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct ChainEpoch(i64);
 
 /// Const used within the VM to denote an unset `ChainEpoch`
-pub const EPOCH_UNDEFINED: ChainEpoch = -1;
+pub const EPOCH_UNDEFINED: ChainEpoch = ChainEpoch(-1);
+
+impl ChainEpoch {
+    pub const fn new(epoch: i64) -> Self {
+        Self(epoch)
+    }
+
+    pub const fn value(self) -> i64 {
+        self.0
+    }
+
+    /// Returns `true` if this is [`EPOCH_UNDEFINED`].
+    pub const fn is_undefined(self) -> bool {
+        self.0 == EPOCH_UNDEFINED.0
+    }
+
+    /// Returns `None` if this is [`EPOCH_UNDEFINED`].
+    pub const fn defined(self) -> Option<Self> {
+        if self.is_undefined() {
+            None
+        } else {
+            Some(self)
+        }
+    }
+
+    /// Returns the epoch `epochs` after this one, or `None` on overflow.
+    pub fn checked_add(self, epochs: i64) -> Option<Self> {
+        self.0.checked_add(epochs).map(Self)
+    }
+
+    /// Returns the epoch `epochs` before this one, or `None` on overflow.
+    pub fn checked_sub(self, epochs: i64) -> Option<Self> {
+        self.0.checked_sub(epochs).map(Self)
+    }
+
+    /// Returns the number of epochs from `earlier` to this epoch (negative if `earlier` is
+    /// actually later), or `None` on overflow.
+    pub fn checked_since(self, earlier: Self) -> Option<i64> {
+        self.0.checked_sub(earlier.0)
+    }
+}
+
+/// Clamps `lookback` to the epochs within finality of `current`, i.e., to
+/// `current - CHAIN_FINALITY..=current`.
+pub fn clamp_to_finality(current: ChainEpoch, lookback: ChainEpoch) -> ChainEpoch {
+    let oldest = ChainEpoch(current.0.saturating_sub(CHAIN_FINALITY));
+    lookback.clamp(oldest, current)
+}
+
+impl From<i64> for ChainEpoch {
+    fn from(epoch: i64) -> Self {
+        ChainEpoch(epoch)
+    }
+}
+
+impl From<ChainEpoch> for i64 {
+    fn from(epoch: ChainEpoch) -> Self {
+        epoch.0
+    }
+}
+
+impl fmt::Display for ChainEpoch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Add<i64> for ChainEpoch {
+    type Output = ChainEpoch;
+
+    fn add(self, epochs: i64) -> ChainEpoch {
+        self.checked_add(epochs).expect("epoch overflow")
+    }
+}
+
+impl AddAssign<i64> for ChainEpoch {
+    fn add_assign(&mut self, epochs: i64) {
+        *self = *self + epochs;
+    }
+}
+
+impl Sub<i64> for ChainEpoch {
+    type Output = ChainEpoch;
+
+    fn sub(self, epochs: i64) -> ChainEpoch {
+        self.checked_sub(epochs).expect("epoch overflow")
+    }
+}
+
+impl SubAssign<i64> for ChainEpoch {
+    fn sub_assign(&mut self, epochs: i64) {
+        *self = *self - epochs;
+    }
+}
+
+impl Sub for ChainEpoch {
+    type Output = i64;
+
+    fn sub(self, earlier: ChainEpoch) -> i64 {
+        self.checked_since(earlier).expect("epoch overflow")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::{from_slice, to_vec};
+
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let epoch = ChainEpoch::new(100);
+        assert_eq!(epoch + 5, ChainEpoch::new(105));
+        assert_eq!(epoch - 105, ChainEpoch::new(-5));
+        assert_eq!(epoch - ChainEpoch::new(30), 70);
+        assert_eq!(ChainEpoch::new(30) - epoch, -70);
+
+        let max = ChainEpoch::new(i64::MAX);
+        assert_eq!(max.checked_add(1), None);
+        assert_eq!(ChainEpoch::new(i64::MIN).checked_sub(1), None);
+        assert_eq!(ChainEpoch::new(-2).checked_since(max), None);
+        assert_eq!(max.checked_sub(1), Some(ChainEpoch::new(i64::MAX - 1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "epoch overflow")]
+    fn overflow_panics() {
+        let _ = ChainEpoch::new(i64::MAX) + 1;
+    }
+
+    #[test]
+    fn undefined() {
+        assert!(EPOCH_UNDEFINED.is_undefined());
+        assert_eq!(EPOCH_UNDEFINED.defined(), None);
+        assert_eq!(ChainEpoch::new(0).defined(), Some(ChainEpoch::new(0)));
+    }
+
+    #[test]
+    fn finality() {
+        let current = ChainEpoch::new(1000);
+        for (lookback, clamped) in [(1000, 1000), (2000, 1000), (100, 100), (99, 100), (-1, 100)] {
+            assert_eq!(
+                clamp_to_finality(current, ChainEpoch::new(lookback)),
+                ChainEpoch::new(clamped)
+            );
+        }
+        let early = ChainEpoch::new(10);
+        assert_eq!(
+            clamp_to_finality(early, ChainEpoch::new(-5)),
+            ChainEpoch::new(-5)
+        );
+        let min = ChainEpoch::new(i64::MIN);
+        assert_eq!(clamp_to_finality(min, ChainEpoch::new(0)), min);
+    }
+
+    #[test]
+    fn serialization() {
+        // Epochs encode as plain integers.
+        let epoch = ChainEpoch::new(-1234);
+        assert_eq!(to_vec(&epoch).unwrap(), to_vec(&-1234i64).unwrap());
+        assert_eq!(
+            from_slice::<ChainEpoch>(&to_vec(&-1234i64).unwrap()).unwrap(),
+            epoch
+        );
+
+        // Integers that don't fit in an i64 are rejected.
+        assert!(from_slice::<ChainEpoch>(&to_vec(&u64::MAX).unwrap()).is_err());
+    }
+}
@@ -4,13 +4,16 @@
 use super::ChainEpoch;
 
 /// Constant defining the [QuantSpec] which performs no quantization.
-pub const NO_QUANTIZATION: QuantSpec = QuantSpec { unit: 1, offset: 0 };
+pub const NO_QUANTIZATION: QuantSpec = QuantSpec {
+    unit: 1,
+    offset: ChainEpoch::new(0),
+};
 
 /// A spec for epoch quantization.
 #[derive(Copy, Clone)]
 pub struct QuantSpec {
-    /// The unit of quantization
-    pub unit: ChainEpoch,
+    /// The unit of quantization, in epochs
+    pub unit: i64,
     /// The offset from zero from which to base the modulus
     pub offset: ChainEpoch,
 }
@@ -24,20 +27,22 @@ impl QuantSpec {
     ///
     /// Precondition: `unit >= 0`
     pub fn quantize_up(&self, epoch: ChainEpoch) -> ChainEpoch {
-        let offset = self.offset % self.unit;
+        let epoch = epoch.value();
+        let offset = self.offset.value() % self.unit;
 
         let remainder = (epoch - offset) % self.unit;
         let quotient = (epoch - offset) / self.unit;
 
         // Don't round if epoch falls on a quantization epoch
-        if remainder == 0
+        let quantized = if remainder == 0
         // Negative truncating division rounds up
         || epoch - offset < 0
         {
             self.unit * quotient + offset
         } else {
             self.unit * (quotient + 1) + offset
-        }
+        };
+        ChainEpoch::new(quantized)
     }
 
     pub fn quantize_down(&self, epoch: ChainEpoch) -> ChainEpoch {
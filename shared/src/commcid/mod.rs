@@ -75,7 +75,7 @@ pub fn cid_to_replica_commitment_v1(c: &Cid) -> Result<Commitment, &'static str>
     let (codec, _, comm_r) = cid_to_commitment(c)?;
 
     if codec != FIL_COMMITMENT_SEALED {
-        return Err("data commitment codec must be Sealed");
+        return Err("replica commitment codec must be Sealed");
     }
 
     Ok(comm_r)
@@ -16,6 +16,13 @@ use crate::address::Error as AddressError;
 pub const BLS_SIG_LEN: usize = 96;
 /// BLS Public key length in bytes.
 pub const BLS_PUB_LEN: usize = 48;
+/// The compressed encoding of the BLS identity signature (the point at infinity): the aggregate
+/// of no signatures.
+pub const BLS_SIG_IDENTITY: [u8; BLS_SIG_LEN] = {
+    let mut sig = [0u8; BLS_SIG_LEN];
+    sig[0] = 0xc0;
+    sig
+};
 
 /// Secp256k1 signature length in bytes.
 pub const SECP_SIG_LEN: usize = 65;
@@ -32,6 +39,10 @@ pub const SECP_SIG_MESSAGE_HASH_SIZE: usize = 32;
 pub enum SignatureType {
     Secp256k1 = 1,
     BLS = 2,
+    /// A signature whose verification is delegated to the actor receiving the message (e.g., a
+    /// secp256k1 signature over an Ethereum "homestead" transaction). The kernel never verifies
+    /// these; the receiving actor must authenticate them itself.
+    Delegated = 3,
 }
 
 /// A cryptographic signature, represented in bytes, of any key protocol.
@@ -69,7 +80,7 @@ impl<'de> de::Deserialize<'de> for Signature {
 
         // Remove signature type byte
         let sig_type = SignatureType::from_u8(bytes[0])
-            .ok_or_else(|| de::Error::custom("Invalid signature type byte (must be 1 or 2)"))?;
+            .ok_or_else(|| de::Error::custom("Invalid signature type byte (must be 1, 2 or 3)"))?;
 
         Ok(Signature {
             bytes: bytes[1..].to_vec(),
@@ -95,6 +106,14 @@ impl Signature {
         }
     }
 
+    /// Creates a delegated Signature given the raw bytes.
+    pub fn new_delegated(bytes: Vec<u8>) -> Self {
+        Self {
+            sig_type: SignatureType::Delegated,
+            bytes,
+        }
+    }
+
     /// Returns reference to signature bytes.
     pub fn bytes(&self) -> &[u8] {
         &self.bytes
@@ -124,7 +143,48 @@ pub fn verify(
     match sig_type {
         SignatureType::BLS => self::ops::verify_bls_sig(sig_data, data, addr),
         SignatureType::Secp256k1 => self::ops::verify_secp256k1_sig(sig_data, data, addr),
+        SignatureType::Delegated => {
+            Err("delegated signatures must be authenticated by the receiving actor".to_owned())
+        }
+    }
+}
+
+/// Verifies an aggregate BLS signature over a set of messages (e.g., message CIDs) from a set of
+/// signers, hashing each message to G2 with the Filecoin domain separation tag.
+///
+/// The i-th message must have been signed by the i-th public key, so the number of messages and
+/// keys must match. An aggregate over no messages is valid if and only if the signature is the
+/// identity.
+#[cfg(feature = "crypto")]
+pub fn verify_bls_aggregate(
+    data: &[&[u8]],
+    pub_keys: &[&[u8; BLS_PUB_LEN]],
+    aggregate_sig: &[u8; BLS_SIG_LEN],
+) -> bool {
+    use bls_signatures::{verify_messages, PublicKey, Serialize, Signature as BlsSignature};
+
+    if data.len() != pub_keys.len() {
+        return false;
+    }
+    if data.is_empty() {
+        return *aggregate_sig == BLS_SIG_IDENTITY;
     }
+
+    let sig = match BlsSignature::from_bytes(aggregate_sig) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+
+    let pks = match pub_keys
+        .iter()
+        .map(|pk| PublicKey::from_bytes(&pk[..]))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+
+    verify_messages(&sig, data, &pks)
 }
 
 #[cfg(feature = "crypto")]
@@ -136,7 +196,7 @@ pub mod ops {
         recover, Error as SecpError, Message, PublicKey, RecoveryId, Signature as EcsdaSignature,
     };
 
-    use super::{Error, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE};
+    use super::{Error, BLS_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE};
     use crate::address::{Address, Protocol};
     use crate::crypto::signature::Signature;
 
@@ -208,35 +268,27 @@ pub mod ops {
             Err("Secp signature verification failed".to_owned())
         }
     }
-    /// Aggregates and verifies bls signatures collectively.
+    /// Aggregates and verifies bls signatures collectively. See [`super::verify_bls_aggregate`].
     pub fn verify_bls_aggregate(
         data: &[&[u8]],
         pub_keys: &[&[u8]],
         aggregate_sig: &Signature,
     ) -> bool {
-        // If the number of public keys and data does not match, then return false
-        if data.len() != pub_keys.len() {
-            return false;
-        }
-        if data.is_empty() {
-            return true;
-        }
-
-        let sig = match BlsSignature::from_bytes(aggregate_sig.bytes()) {
+        let sig = match aggregate_sig.bytes().try_into() {
             Ok(v) => v,
             Err(_) => return false,
         };
 
-        let pk_map_results: Result<Vec<_>, _> =
-            pub_keys.iter().map(|x| BlsPubKey::from_bytes(x)).collect();
-
-        let pks = match pk_map_results {
+        let pks = match pub_keys
+            .iter()
+            .map(|pk| (*pk).try_into())
+            .collect::<Result<Vec<&[u8; BLS_PUB_LEN]>, _>>()
+        {
             Ok(v) => v,
             Err(_) => return false,
         };
 
-        // Does the aggregate verification
-        verify_messages(&sig, data, &pks[..])
+        super::verify_bls_aggregate(data, &pks, sig)
     }
 
     /// Return the public key used for signing a message given it's signing bytes hash and signature.
@@ -321,6 +373,69 @@ mod tests {
         ),);
     }
 
+    #[test]
+    fn bls_agg_verify_arrays() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(12);
+
+        let private_keys: Vec<PrivateKey> =
+            (0..5).map(|_| PrivateKey::generate(&mut *rng)).collect();
+        let public_keys: Vec<[u8; BLS_PUB_LEN]> = private_keys
+            .iter()
+            .map(|k| k.public_key().as_bytes().try_into().unwrap())
+            .collect();
+        let public_keys: Vec<&[u8; BLS_PUB_LEN]> = public_keys.iter().collect();
+
+        let messages: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; 32]).collect();
+        let data: Vec<&[u8]> = messages.iter().map(|m| &m[..]).collect();
+
+        let signatures: Vec<BlsSignature> = private_keys
+            .iter()
+            .zip(&data)
+            .map(|(k, m)| k.sign(m))
+            .collect();
+        let sig: [u8; BLS_SIG_LEN] = bls_signatures::aggregate(&signatures)
+            .unwrap()
+            .as_bytes()
+            .try_into()
+            .unwrap();
+
+        assert!(verify_bls_aggregate(&data, &public_keys, &sig));
+
+        // Signers must line up with their messages.
+        let mut swapped = public_keys.clone();
+        swapped.swap(0, 1);
+        assert!(!verify_bls_aggregate(&data, &swapped, &sig));
+
+        // Mismatched lengths fail rather than panic.
+        assert!(!verify_bls_aggregate(&data[1..], &public_keys, &sig));
+        assert!(!verify_bls_aggregate(&data, &public_keys[1..], &sig));
+
+        // Garbage signatures and keys fail rather than panic.
+        assert!(!verify_bls_aggregate(
+            &data,
+            &public_keys,
+            &[0xff; BLS_SIG_LEN]
+        ));
+        let bad_key = [0xff; BLS_PUB_LEN];
+        let mut bad_keys = public_keys.clone();
+        bad_keys[0] = &bad_key;
+        assert!(!verify_bls_aggregate(&data, &bad_keys, &sig));
+    }
+
+    #[test]
+    fn bls_agg_verify_empty() {
+        assert!(verify_bls_aggregate(&[], &[], &BLS_SIG_IDENTITY));
+        assert!(!verify_bls_aggregate(&[], &[], &[0; BLS_SIG_LEN]));
+
+        let rng = &mut ChaCha8Rng::seed_from_u64(13);
+        let sig: [u8; BLS_SIG_LEN] = PrivateKey::generate(rng)
+            .sign(b"message")
+            .as_bytes()
+            .try_into()
+            .unwrap();
+        assert!(!verify_bls_aggregate(&[], &[], &sig));
+    }
+
     #[test]
     fn recover_pubkey() {
         let rng = &mut ChaCha8Rng::seed_from_u64(8);
@@ -373,6 +488,35 @@ mod tests {
 
         assert_eq!(ecrecover(&hash, &signature).unwrap(), secp_addr);
     }
+
+    #[test]
+    fn signature_serde_roundtrip() {
+        for sig in [
+            Signature::new_secp256k1(vec![1; SECP_SIG_LEN]),
+            Signature::new_bls(vec![2; BLS_SIG_LEN]),
+            Signature::new_delegated(vec![3; SECP_SIG_LEN]),
+        ] {
+            let bytes = fvm_ipld_encoding::to_vec(&sig).unwrap();
+            let decoded: Signature = fvm_ipld_encoding::from_slice(&bytes).unwrap();
+            assert_eq!(decoded, sig);
+        }
+
+        // The type byte prefixes the signature bytes.
+        let sig = Signature::new_delegated(vec![0xaa, 0xbb]);
+        let bytes = fvm_ipld_encoding::to_vec(&sig).unwrap();
+        assert_eq!(bytes, [0x43, 3, 0xaa, 0xbb]);
+
+        // Unknown type bytes are rejected.
+        fvm_ipld_encoding::from_slice::<Signature>(&[0x43, 4, 0xaa, 0xbb])
+            .expect_err("expected an unknown signature type to be rejected");
+    }
+
+    #[test]
+    fn delegated_not_verifiable() {
+        let addr = Address::new_id(1);
+        let sig = Signature::new_delegated(vec![0; SECP_SIG_LEN]);
+        assert!(sig.verify(b"data", &addr).is_err());
+    }
 }
 
 /// Crypto error
@@ -31,6 +31,40 @@ impl ExitCode {
     pub fn is_system_error(self) -> bool {
         self.value < (Self::FIRST_USER_EXIT_CODE)
     }
+
+    /// Returns true if the exit code is one of the standard codes shared by all built-in actors
+    /// (`USR_*`), as opposed to a system code or an actor-specific code.
+    pub fn is_common_user_error(self) -> bool {
+        (Self::FIRST_USER_EXIT_CODE..Self::FIRST_ACTOR_SPECIFIC_EXIT_CODE).contains(&self.value)
+    }
+
+    /// Returns the symbolic name of the exit code (e.g., `SYS_OUT_OF_GAS`), if it's one of the
+    /// named system or common user exit codes.
+    pub fn name(self) -> Option<&'static str> {
+        Some(match self {
+            Self::OK => "OK",
+            Self::SYS_SENDER_INVALID => "SYS_SENDER_INVALID",
+            Self::SYS_SENDER_STATE_INVALID => "SYS_SENDER_STATE_INVALID",
+            Self::SYS_INVALID_METHOD => "SYS_INVALID_METHOD",
+            Self::SYS_ILLEGAL_INSTRUCTION => "SYS_ILLEGAL_INSTRUCTION",
+            Self::SYS_INVALID_RECEIVER => "SYS_INVALID_RECEIVER",
+            Self::SYS_INSUFFICIENT_FUNDS => "SYS_INSUFFICIENT_FUNDS",
+            Self::SYS_OUT_OF_GAS => "SYS_OUT_OF_GAS",
+            Self::SYS_ILLEGAL_EXIT_CODE => "SYS_ILLEGAL_EXIT_CODE",
+            Self::SYS_ASSERTION_FAILED => "SYS_ASSERTION_FAILED",
+            Self::SYS_MISSING_RETURN => "SYS_MISSING_RETURN",
+            Self::USR_ILLEGAL_ARGUMENT => "USR_ILLEGAL_ARGUMENT",
+            Self::USR_NOT_FOUND => "USR_NOT_FOUND",
+            Self::USR_FORBIDDEN => "USR_FORBIDDEN",
+            Self::USR_INSUFFICIENT_FUNDS => "USR_INSUFFICIENT_FUNDS",
+            Self::USR_ILLEGAL_STATE => "USR_ILLEGAL_STATE",
+            Self::USR_SERIALIZATION => "USR_SERIALIZATION",
+            Self::USR_UNHANDLED_MESSAGE => "USR_UNHANDLED_MESSAGE",
+            Self::USR_UNSPECIFIED => "USR_UNSPECIFIED",
+            Self::USR_ASSERTION_FAILED => "USR_ASSERTION_FAILED",
+            _ => return None,
+        })
+    }
 }
 
 impl From<u32> for ExitCode {
@@ -41,7 +75,10 @@ impl From<u32> for ExitCode {
 
 impl std::fmt::Display for ExitCode {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.value)
+        match self.name() {
+            Some(name) => write!(f, "{}({})", name, self.value),
+            None => write!(f, "{}", self.value),
+        }
     }
 }
 
@@ -109,6 +146,9 @@ impl ExitCode {
     // pub const RESERVED_29: ExitCode = ExitCode::new(29);
     // pub const RESERVED_30: ExitCode = ExitCode::new(30);
     // pub const RESERVED_31: ExitCode = ExitCode::new(31);
+
+    /// The lowest exit code available for actor-specific errors.
+    pub const FIRST_ACTOR_SPECIFIC_EXIT_CODE: u32 = 32;
 }
 
 /// When a syscall fails, it returns an `ErrorNumber` to indicate why. The syscalls themselves
@@ -168,3 +208,59 @@ impl std::fmt::Display for ErrorNumber {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::{from_slice, to_vec, RawBytes};
+
+    use super::*;
+    use crate::receipt::Receipt;
+
+    #[test]
+    fn exit_code_ranges() {
+        assert!(ExitCode::OK.is_success());
+        assert!(ExitCode::OK.is_system_error());
+        assert!(ExitCode::new(15).is_system_error());
+        assert!(!ExitCode::SYS_OUT_OF_GAS.is_common_user_error());
+
+        assert!(!ExitCode::USR_ILLEGAL_ARGUMENT.is_system_error());
+        assert!(ExitCode::USR_ILLEGAL_ARGUMENT.is_common_user_error());
+        assert!(ExitCode::new(31).is_common_user_error());
+
+        assert!(!ExitCode::new(32).is_system_error());
+        assert!(!ExitCode::new(32).is_common_user_error());
+    }
+
+    #[test]
+    fn exit_code_display() {
+        assert_eq!(ExitCode::SYS_OUT_OF_GAS.to_string(), "SYS_OUT_OF_GAS(7)");
+        assert_eq!(ExitCode::USR_NOT_FOUND.to_string(), "USR_NOT_FOUND(17)");
+        assert_eq!(ExitCode::new(12).to_string(), "12");
+        assert_eq!(ExitCode::new(1000).to_string(), "1000");
+    }
+
+    #[test]
+    fn exit_code_serde() {
+        for (code, bytes) in [
+            (ExitCode::OK, vec![0x00]),
+            (ExitCode::SYS_OUT_OF_GAS, vec![0x07]),
+            (ExitCode::new(1000), vec![0x19, 0x03, 0xe8]),
+        ] {
+            assert_eq!(to_vec(&code).unwrap(), bytes);
+            assert_eq!(from_slice::<ExitCode>(&bytes).unwrap(), code);
+        }
+
+        // [exit_code, return_data, gas_used]
+        let bytes = [0x83, 0x07, 0x42, 0xca, 0xfe, 0x18, 0x64];
+        let receipt: Receipt = from_slice(&bytes).unwrap();
+        assert_eq!(
+            receipt,
+            Receipt {
+                exit_code: ExitCode::SYS_OUT_OF_GAS,
+                return_data: RawBytes::new(vec![0xca, 0xfe]),
+                gas_used: 100,
+            }
+        );
+        assert_eq!(to_vec(&receipt).unwrap(), bytes);
+    }
+}
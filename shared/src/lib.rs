@@ -9,6 +9,8 @@ use clock::ChainEpoch;
 
 pub mod address;
 pub mod bigint;
+pub mod builtin;
+pub mod chainid;
 pub mod clock;
 pub mod commcid;
 pub mod consensus;
@@ -18,6 +20,7 @@ pub mod econ;
 pub mod error;
 pub mod math;
 pub mod message;
+pub mod method;
 pub mod piece;
 pub mod randomness;
 pub mod receipt;
@@ -25,6 +28,7 @@ pub mod reward;
 pub mod sector;
 pub mod smooth;
 pub mod state;
+pub mod supply;
 pub mod sys;
 pub mod version;
 
@@ -61,9 +65,9 @@ pub const TOTAL_FILECOIN_BASE: i64 = 2_000_000_000;
 
 // Epochs
 /// Lookback height for retrieving ticket randomness.
-pub const TICKET_RANDOMNESS_LOOKBACK: ChainEpoch = 1;
+pub const TICKET_RANDOMNESS_LOOKBACK: i64 = 1;
 /// Epochs to look back for verifying PoSt proofs.
-pub const WINNING_POST_SECTOR_SET_LOOKBACK: ChainEpoch = 10;
+pub const WINNING_POST_SECTOR_SET_LOOKBACK: i64 = 10;
 
 /// The expected number of block producers in each epoch.
 pub const BLOCKS_PER_EPOCH: u64 = 5;
@@ -2,13 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use anyhow::anyhow;
+use cid::multihash::Multihash;
+use cid::Cid;
 use fvm_ipld_encoding::de::{Deserialize, Deserializer};
 use fvm_ipld_encoding::ser::{Serialize, Serializer};
-use fvm_ipld_encoding::{Cbor, RawBytes};
+use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
+use fvm_ipld_encoding::{Cbor, RawBytes, DAG_CBOR};
 
 use crate::address::Address;
+use crate::crypto::hash::SupportedHashes;
+use crate::crypto::signature::{Signature, SignatureType};
 use crate::econ::TokenAmount;
-use crate::MethodNum;
+use crate::{MethodNum, BLOCK_GAS_LIMIT, TOTAL_FILECOIN};
+
+/// The maximum size of a message's parameters, in bytes.
+pub const MAX_MESSAGE_PARAMS_LEN: usize = 64 << 10;
 
 /// Default Unsigned VM message type which includes all data needed for a state transition
 #[cfg_attr(feature = "testing", derive(Default))]
@@ -39,6 +47,132 @@ impl Message {
         }
         Ok(())
     }
+
+    /// Checks that the message's fields are within the bounds required for inclusion in a
+    /// block: the value must be non-negative and at most the total supply, the gas fields must be
+    /// non-negative with the premium at most the fee cap, the gas limit must be positive and at
+    /// most the block gas limit, and the params must be at most [`MAX_MESSAGE_PARAMS_LEN`].
+    ///
+    /// This is stricter than the executor, which only requires the gas limit to be in
+    /// `[0, BLOCK_GAS_LIMIT]`: it applies messages with a zero gas limit, failing them at preflight
+    /// with `SYS_OUT_OF_GAS` (and penalizing the miner for including them).
+    pub fn check_fields(&self) -> anyhow::Result<()> {
+        self.check()?;
+        if self.gas_limit > BLOCK_GAS_LIMIT {
+            return Err(anyhow!(
+                "Message gas limit {} exceeds the block gas limit",
+                self.gas_limit
+            ));
+        }
+        if self.value.is_negative() {
+            return Err(anyhow!("Message has negative value"));
+        }
+        if self.value > *TOTAL_FILECOIN {
+            return Err(anyhow!("Message value exceeds the total supply"));
+        }
+        if self.gas_fee_cap.is_negative() {
+            return Err(anyhow!("Message has negative gas fee cap"));
+        }
+        if self.gas_premium.is_negative() {
+            return Err(anyhow!("Message has negative gas premium"));
+        }
+        if self.gas_premium > self.gas_fee_cap {
+            return Err(anyhow!("Message gas premium exceeds its gas fee cap"));
+        }
+        if self.params.len() > MAX_MESSAGE_PARAMS_LEN {
+            return Err(anyhow!(
+                "Message params are {} bytes, exceeding the limit of {} bytes",
+                self.params.len(),
+                MAX_MESSAGE_PARAMS_LEN
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the message's CID: a blake2b-256, DAG-CBOR CID over the serialized message.
+    pub fn cid(&self) -> anyhow::Result<Cid> {
+        cbor_cid(&self.marshal_cbor()?)
+    }
+}
+
+/// Computes the blake2b-256, DAG-CBOR CID of the given serialized object.
+fn cbor_cid(bytes: &[u8]) -> anyhow::Result<Cid> {
+    let digest = blake2b_simd::Params::new()
+        .hash_length(32)
+        .to_state()
+        .update(bytes)
+        .finalize();
+    let mh = Multihash::wrap(SupportedHashes::Blake2b256 as u64, digest.as_bytes())?;
+    Ok(Cid::new_v1(DAG_CBOR, mh))
+}
+
+/// A message with its signature.
+#[derive(PartialEq, Clone, Debug, Hash, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct SignedMessage {
+    pub message: Message,
+    pub signature: Signature,
+}
+
+impl Cbor for SignedMessage {}
+
+impl SignedMessage {
+    /// Creates a signed message from a message and its signature, without checking the
+    /// signature.
+    pub fn new_unchecked(message: Message, signature: Signature) -> Self {
+        SignedMessage { message, signature }
+    }
+
+    /// Returns true if the message is signed with a BLS signature.
+    pub fn is_bls(&self) -> bool {
+        self.signature.signature_type() == SignatureType::BLS
+    }
+
+    /// Returns the signed message's CID. BLS signatures are aggregated into the block, so BLS
+    /// messages are identified by the CID of the unsigned message.
+    pub fn cid(&self) -> anyhow::Result<Cid> {
+        if self.is_bls() {
+            self.message.cid()
+        } else {
+            cbor_cid(&self.marshal_cbor()?)
+        }
+    }
+
+    /// Returns the size of the message as included on chain, in bytes: the size of the unsigned
+    /// message for BLS messages, and of the signed message otherwise.
+    pub fn chain_length(&self) -> anyhow::Result<usize> {
+        let bytes = if self.is_bls() {
+            self.message.marshal_cbor()?
+        } else {
+            self.marshal_cbor()?
+        };
+        Ok(bytes.len())
+    }
+}
+
+#[cfg(feature = "crypto")]
+impl SignedMessage {
+    /// Verifies the message's signature. See [`Message::check_signature_over_cid`].
+    pub fn verify(&self) -> anyhow::Result<()> {
+        self.message.check_signature_over_cid(&self.signature)
+    }
+}
+
+#[cfg(feature = "crypto")]
+impl Message {
+    /// Checks the signature over the message's signing payload: the bytes of the message's CID.
+    ///
+    /// BLS and secp256k1 signatures are verified against the sender's key address. Delegated
+    /// signatures can't be verified here and are always rejected; they must be authenticated by
+    /// the receiving actor.
+    pub fn check_signature_over_cid(&self, sig: &Signature) -> anyhow::Result<()> {
+        if sig.signature_type() == SignatureType::Delegated {
+            return Err(anyhow!(
+                "delegated signatures must be authenticated by the receiving actor"
+            ));
+        }
+        let payload = self.cid()?.to_bytes();
+        sig.verify(&payload, &self.from).map_err(|e| anyhow!(e))
+    }
 }
 
 impl Serialize for Message {
@@ -93,3 +227,57 @@ impl<'de> Deserialize<'de> for Message {
         })
     }
 }
+
+#[cfg(all(test, feature = "crypto"))]
+mod tests {
+    use libsecp256k1::{sign, PublicKey, SecretKey};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn sign_secp(key: &SecretKey, data: &[u8]) -> Signature {
+        let hash = blake2b_simd::Params::new()
+            .hash_length(32)
+            .to_state()
+            .update(data)
+            .finalize();
+        let msg =
+            libsecp256k1::Message::parse(hash.as_bytes().try_into().expect("fixed array size"));
+        let (sig, recovery_id) = sign(&msg, key);
+        let mut bytes = sig.serialize().to_vec();
+        bytes.push(recovery_id.serialize());
+        Signature::new_secp256k1(bytes)
+    }
+
+    #[test]
+    fn check_signature_over_cid() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(8);
+        let key = SecretKey::random(rng);
+        let from = Address::new_secp256k1(&PublicKey::from_secret_key(&key).serialize()).unwrap();
+
+        let mut msg = Message {
+            version: 0,
+            from,
+            to: Address::new_id(100),
+            sequence: 0,
+            value: TokenAmount::from_atto(1),
+            method_num: 0,
+            params: RawBytes::default(),
+            gas_limit: 1000,
+            gas_fee_cap: TokenAmount::from_atto(1),
+            gas_premium: TokenAmount::from_atto(1),
+        };
+
+        let sig = sign_secp(&key, &msg.cid().unwrap().to_bytes());
+        msg.check_signature_over_cid(&sig).unwrap();
+
+        // Delegated signatures are never verified here.
+        let delegated = Signature::new_delegated(sig.bytes().to_vec());
+        msg.check_signature_over_cid(&delegated).unwrap_err();
+
+        // Changing the message changes the signing payload.
+        msg.sequence = 1;
+        msg.check_signature_over_cid(&sig).unwrap_err();
+    }
+}
@@ -0,0 +1,100 @@
+//! Method numbers for user-programmable actors, derived from exported method names as specified in
+//! [FRC-0042](https://github.com/filecoin-project/FIPs/blob/master/FRCs/frc-0042.md).
+
+use thiserror::Error;
+
+use crate::MethodNum;
+
+/// The first method number that may be derived from a method name. Lower method numbers are
+/// reserved (builtin actor methods, for example, are numbered below 1024).
+pub const FIRST_EXPORTED_METHOD_NUMBER: MethodNum = 1 << 24;
+
+/// The reason a method number couldn't be derived from a method name.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MethodNameError {
+    #[error("method name is empty")]
+    Empty,
+    #[error("method name {0:?} doesn't start with an uppercase ASCII letter")]
+    IllegalStart(String),
+    #[error("method name {0:?} contains characters other than ASCII letters, digits and '_'")]
+    IllegalCharacters(String),
+    #[error("no method number can be derived from {0:?}")]
+    Indeterminable(String),
+}
+
+/// Derives the method number of an exported method from its name.
+///
+/// The name must start with an uppercase ASCII letter, followed by ASCII letters, digits and
+/// underscores. The method number is the first big-endian 4 byte chunk of `blake2b-512("1|" +
+/// name)` that's at least [`FIRST_EXPORTED_METHOD_NUMBER`].
+pub fn method_number(name: &str) -> Result<MethodNum, MethodNameError> {
+    check_method_name(name)?;
+
+    let digest = blake2b_simd::Params::new()
+        .hash_length(64)
+        .to_state()
+        .update(b"1|")
+        .update(name.as_bytes())
+        .finalize();
+
+    digest
+        .as_bytes()
+        .chunks_exact(4)
+        .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()) as MethodNum)
+        .find(|&num| num >= FIRST_EXPORTED_METHOD_NUMBER)
+        .ok_or_else(|| MethodNameError::Indeterminable(name.into()))
+}
+
+fn check_method_name(name: &str) -> Result<(), MethodNameError> {
+    match name.chars().next() {
+        None => Err(MethodNameError::Empty),
+        Some(c) if !c.is_ascii_uppercase() => Err(MethodNameError::IllegalStart(name.into())),
+        _ if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+            Err(MethodNameError::IllegalCharacters(name.into()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_method_numbers() {
+        for (name, num) in [
+            ("Receive", 3726118371),
+            ("Name", 48890204),
+            ("Symbol", 2061153854),
+            ("TotalSupply", 114981429),
+            ("BalanceOf", 2266030508),
+            ("Transfer", 80475954),
+        ] {
+            assert_eq!(method_number(name), Ok(num), "{}", name);
+        }
+    }
+
+    #[test]
+    fn invalid_method_names() {
+        assert_eq!(method_number(""), Err(MethodNameError::Empty));
+        for name in ["receive", "_Receive", "1Receive", "Ünicode"] {
+            assert_eq!(
+                method_number(name),
+                Err(MethodNameError::IllegalStart(name.into()))
+            );
+        }
+        for name in ["Re-ceive", "Receive!", "Re ceive", "Receivé"] {
+            assert_eq!(
+                method_number(name),
+                Err(MethodNameError::IllegalCharacters(name.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn method_numbers_are_not_reserved() {
+        for name in ["A", "B_", "Send", "Constructor", "X1", "Z_9_z"] {
+            assert!(method_number(name).unwrap() >= FIRST_EXPORTED_METHOD_NUMBER);
+        }
+    }
+}
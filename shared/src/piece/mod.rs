@@ -1,19 +1,20 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
 use fvm_ipld_encoding::Cbor;
 
-#[cfg(feature = "proofs")]
 pub mod zero;
 
 use cid::Cid;
 use serde::{Deserialize, Serialize};
 use serde_tuple::*;
-#[cfg(feature = "proofs")]
 pub use zero::zero_piece_commitment;
 
 /// Size of a piece in bytes.
-#[derive(PartialEq, Debug, Eq, Clone, Copy)]
+#[derive(PartialEq, Debug, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct UnpaddedPieceSize(pub u64);
 
 impl UnpaddedPieceSize {
@@ -62,6 +63,43 @@ impl PaddedPieceSize {
     }
 }
 
+/// Implements addition and subtraction of piece sizes of the same kind. Padded and unpadded sizes
+/// deliberately can't be mixed; convert explicitly with `padded` or `unpadded`.
+macro_rules! impl_piece_size_arith {
+    ($typ:ident) => {
+        impl Add for $typ {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                $typ(self.0 + rhs.0)
+            }
+        }
+
+        impl AddAssign for $typ {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl Sub for $typ {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                $typ(self.0 - rhs.0)
+            }
+        }
+
+        impl SubAssign for $typ {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+    };
+}
+
+impl_piece_size_arith!(UnpaddedPieceSize);
+impl_piece_size_arith!(PaddedPieceSize);
+
 /// Piece information for part or a whole file.
 #[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Clone, Debug)]
 pub struct PieceInfo {
@@ -121,5 +159,73 @@ mod tests {
             UnpaddedPieceSize(0b1110111000).validate(),
             Err("unpadded piece size must be a power of 2 multiple of 127")
         );
+        assert_eq!(
+            PaddedPieceSize(0).validate(),
+            Err("minimum piece size is 128 bytes")
+        );
+        assert_eq!(
+            UnpaddedPieceSize(128).validate(),
+            Err("unpadded piece size must be a power of 2 multiple of 127")
+        );
+        assert_eq!(
+            PaddedPieceSize(384).validate(),
+            Err("padded piece size must be a power of 2")
+        );
+    }
+
+    #[test]
+    fn piece_size_conversions() {
+        for shift in 0..=30 {
+            let padded = PaddedPieceSize(128 << shift);
+            let unpadded = UnpaddedPieceSize(127 << shift);
+            padded.validate().unwrap();
+            unpadded.validate().unwrap();
+            assert_eq!(padded.unpadded(), unpadded);
+            assert_eq!(unpadded.padded(), padded);
+        }
+    }
+
+    #[test]
+    fn piece_size_arithmetic() {
+        let mut sum = PaddedPieceSize(128) + PaddedPieceSize(256);
+        assert_eq!(sum, PaddedPieceSize(384));
+        sum -= PaddedPieceSize(128);
+        assert_eq!(sum, PaddedPieceSize(256));
+
+        let mut sum = UnpaddedPieceSize(127);
+        sum += UnpaddedPieceSize(254);
+        assert_eq!(sum - UnpaddedPieceSize(127), UnpaddedPieceSize(254));
+    }
+
+    #[test]
+    fn piece_info_serde() {
+        let info = PieceInfo {
+            size: PaddedPieceSize(2048),
+            cid: crate::commcid::data_commitment_v1_to_cid(&[1; 32]).unwrap(),
+        };
+
+        let bytes = fvm_ipld_encoding::to_vec(&info).unwrap();
+        let mut expected = vec![
+            0x82, // array(2)
+            0x19, 0x08, 0x00, // 2048
+            0xd8, 0x2a, // tag(42)
+            0x58, 0x28, // bytes(40)
+            0x00, // multibase identity prefix
+            0x01, // CIDv1
+            0x81, 0xe2, 0x03, // fil-commitment-unsealed
+            0x92, 0x20, // sha2-256-trunc254-padded
+            0x20, // digest length
+        ];
+        expected.extend([1; 32]);
+        assert_eq!(bytes, expected);
+
+        let decoded: PieceInfo = fvm_ipld_encoding::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, info);
+
+        let size: UnpaddedPieceSize = fvm_ipld_encoding::from_slice(
+            &fvm_ipld_encoding::to_vec(&UnpaddedPieceSize(127)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(size, UnpaddedPieceSize(127));
     }
 }
@@ -190,7 +190,36 @@ const PIECE_COMMS: [Commitment; LEVELS - SKIP] = [
 ];
 
 /// Returns a zero piece commitment based on the amount of space needed to pad.
+///
+/// Covers every valid padded piece size from 128 bytes up to 2TiB, which includes all standard
+/// sector sizes.
+///
+/// # Panics
+///
+/// Panics if the size isn't one of the above (see [`PaddedPieceSize::validate`]).
 pub fn zero_piece_commitment(sz: PaddedPieceSize) -> Commitment {
     let level: usize = sz.0.trailing_zeros() as usize - SKIP - 5; // 2^5 = 32
     PIECE_COMMS[level]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_piece_commitments() {
+        assert_eq!(zero_piece_commitment(PaddedPieceSize(128)), PIECE_COMMS[0]);
+        assert_eq!(
+            zero_piece_commitment(PaddedPieceSize(32 << 30)),
+            PIECE_COMMS[28]
+        );
+        assert_eq!(
+            zero_piece_commitment(PaddedPieceSize(64 << 30)),
+            PIECE_COMMS[29]
+        );
+        assert_eq!(
+            zero_piece_commitment(PaddedPieceSize(2 << 40)),
+            PIECE_COMMS[LEVELS - SKIP - 1]
+        );
+    }
+}
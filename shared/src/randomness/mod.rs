@@ -1,17 +1,31 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use fvm_ipld_encoding::{BytesDe, BytesSer};
+use fvm_ipld_encoding::{Byte32De, BytesSer};
+use num_derive::FromPrimitive;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-// TODO: turn this back into a 32byte array once we no longer need go compat. It's a vec so that the
-// errors match.
-/// String of random bytes usually generated from a randomness beacon or from tickets on chain.
-#[derive(PartialEq, Eq, Default, Clone, Debug)]
-pub struct Randomness(pub Vec<u8>);
+use crate::clock::ChainEpoch;
 
 pub const RANDOMNESS_LENGTH: usize = 32;
 
+/// Random bytes usually generated from a randomness beacon or from tickets on chain. Encoded as a
+/// CBOR byte string of exactly [`RANDOMNESS_LENGTH`] bytes.
+#[derive(PartialEq, Eq, Hash, Default, Clone, Copy, Debug)]
+pub struct Randomness(pub [u8; RANDOMNESS_LENGTH]);
+
+impl From<[u8; RANDOMNESS_LENGTH]> for Randomness {
+    fn from(bytes: [u8; RANDOMNESS_LENGTH]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Randomness {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl Serialize for Randomness {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -26,7 +40,148 @@ impl<'de> Deserialize<'de> for Randomness {
     where
         D: Deserializer<'de>,
     {
-        let bytes = BytesDe::deserialize(deserializer)?;
-        Ok(Self(bytes.0))
+        let Byte32De(bytes) = Byte32De::deserialize(deserializer)?;
+        Ok(Self(bytes))
+    }
+}
+
+/// Domain separation tags, mixed into randomness so that randomness drawn for one purpose can't
+/// be reused for another.
+#[derive(PartialEq, Eq, Copy, Clone, FromPrimitive, Debug, Hash)]
+#[repr(i64)]
+pub enum DomainSeparationTag {
+    TicketProduction = 1,
+    ElectionProofProduction = 2,
+    WinningPoStChallengeSeed = 3,
+    WindowedPoStChallengeSeed = 4,
+    SealRandomness = 5,
+    InteractiveSealChallengeSeed = 6,
+    WindowedPoStDeadlineAssignment = 7,
+    MarketDealCronSeed = 8,
+    PoStChainCommit = 9,
+}
+
+/// Draws randomness from a 32-byte base (the digest of a ticket or beacon entry), mixing in the
+/// domain separation tag, epoch, and caller-supplied entropy. This is:
+///
+/// ```text
+/// blake2b-256(dst as i64 BE || base || epoch as i64 BE || entropy)
+/// ```
+pub fn draw_randomness(
+    base: &Randomness,
+    dst: i64,
+    epoch: ChainEpoch,
+    entropy: &[u8],
+) -> Randomness {
+    let digest = blake2b_simd::Params::new()
+        .hash_length(RANDOMNESS_LENGTH)
+        .to_state()
+        .update(&dst.to_be_bytes())
+        .update(&base.0)
+        .update(&epoch.value().to_be_bytes())
+        .update(entropy)
+        .finalize();
+
+    let mut ret = Randomness::default();
+    ret.0.copy_from_slice(digest.as_bytes());
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use data_encoding::HEXLOWER;
+    use fvm_ipld_encoding::{from_slice, to_vec};
+
+    use super::*;
+
+    /// Expected values follow Lotus' `DrawRandomnessFromDigest` (lotus `chain/rand/rand.go`), and
+    /// were regenerated independently of this crate with Python's `hashlib`:
+    ///
+    /// ```text
+    /// h = hashlib.blake2b(digest_size=32)
+    /// h.update(struct.pack('>q', dst)); h.update(base)
+    /// h.update(struct.pack('>q', epoch)); h.update(entropy)
+    /// ```
+    ///
+    /// Tag values are those of go-state-types' `crypto.DomainSeparationTag`.
+    #[test]
+    fn draw_randomness_known_answers() {
+        let mut base = [0u8; 32];
+        for (i, b) in base.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        assert_eq!(
+            HEXLOWER.encode(&draw_randomness(
+                &Randomness(base),
+                DomainSeparationTag::ElectionProofProduction as i64,
+                ChainEpoch::new(12345),
+                b"entropy"
+            )),
+            "a462a881aa21b78727589961e09fedc4eceaca8b675f82a20b70e60cf4615beb"
+        );
+        assert_eq!(
+            HEXLOWER.encode(&draw_randomness(
+                &Randomness([0xff; 32]),
+                DomainSeparationTag::SealRandomness as i64,
+                ChainEpoch::new(-1),
+                &[]
+            )),
+            "f48c90dea6bef43676d17fbdbc856bd9b6d8e5b24f7a82cbbf28108663cd1157"
+        );
+        assert_eq!(
+            HEXLOWER.encode(&draw_randomness(
+                &Randomness([0; 32]),
+                DomainSeparationTag::PoStChainCommit as i64,
+                ChainEpoch::new(1 << 40),
+                &[1, 2, 3]
+            )),
+            "827c063b82da8b4c790dfbfe98af09bb223e15a7e85e7a88789cd1c9f716cc69"
+        );
+    }
+
+    /// Lotus' `DrawRandomnessFromBase` draws from the blake2b-256 digest of the raw ticket or beacon
+    /// entry, which is the base passed here. Regenerated as above, with
+    /// `base = hashlib.blake2b(bytes(range(96)), digest_size=32).digest()`.
+    #[test]
+    fn draw_randomness_from_vrf_digest() {
+        let vrf_proof: Vec<u8> = (0..96).collect();
+        let mut base = Randomness::default();
+        base.0.copy_from_slice(
+            blake2b_simd::Params::new()
+                .hash_length(RANDOMNESS_LENGTH)
+                .hash(&vrf_proof)
+                .as_bytes(),
+        );
+        assert_eq!(
+            HEXLOWER.encode(&base.0),
+            "6528ea458efd23391e968e0dd3a40202ac94e3854d1a4642cbbe0d13a15cb849"
+        );
+        assert_eq!(
+            HEXLOWER.encode(&draw_randomness(
+                &base,
+                DomainSeparationTag::TicketProduction as i64,
+                ChainEpoch::new(100),
+                b"hello"
+            )),
+            "8d98d892c664dc99b980b3d87b03c27bc962b4760f46731a0eeb96f2699478f6"
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let rand = Randomness([7; RANDOMNESS_LENGTH]);
+        let bytes = to_vec(&rand).unwrap();
+        assert_eq!(bytes, to_vec(&BytesSer(&[7; RANDOMNESS_LENGTH])).unwrap());
+        assert_eq!(from_slice::<Randomness>(&bytes).unwrap(), rand);
+
+        // Anything other than exactly 32 bytes is rejected.
+        for len in [0, 31, 33, 64] {
+            let bytes = to_vec(&BytesSer(&vec![7; len])).unwrap();
+            assert!(
+                from_slice::<Randomness>(&bytes).is_err(),
+                "accepted {} bytes",
+                len
+            );
+        }
     }
 }
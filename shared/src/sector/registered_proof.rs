@@ -7,7 +7,6 @@ use std::convert::TryFrom;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::SectorSize;
-use crate::clock;
 use crate::version::NetworkVersion;
 
 /// Seal proof type which defines the version and sector size.
@@ -66,7 +65,7 @@ impl RegisteredSealProof {
 
     #[deprecated(since = "0.1.10", note = "Logic should exist in actors")]
     /// The maximum duration a sector sealed with this proof may exist between activation and expiration.
-    pub fn sector_maximum_lifetime(self) -> clock::ChainEpoch {
+    pub fn sector_maximum_lifetime(self) -> i64 {
         // For all Stacked DRG sectors, the max is 5 years
         let epochs_per_year = 1_262_277;
         5 * epochs_per_year
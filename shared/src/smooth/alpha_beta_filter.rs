@@ -5,7 +5,6 @@ use fvm_ipld_encoding::tuple::*;
 use fvm_ipld_encoding::Cbor;
 
 use crate::bigint::{bigint_ser, BigInt, Integer};
-use crate::clock::ChainEpoch;
 use crate::math::PRECISION;
 
 #[derive(Default, Serialize_tuple, Deserialize_tuple, Clone, Debug, PartialEq)]
@@ -31,7 +30,7 @@ impl FilterEstimate {
     }
 
     /// Extrapolate filter "position" delta epochs in the future.
-    pub fn extrapolate(&self, delta: ChainEpoch) -> BigInt {
+    pub fn extrapolate(&self, delta: i64) -> BigInt {
         let delta_t = BigInt::from(delta) << PRECISION;
         let position = &self.position << PRECISION;
         (&self.velocity * delta_t) + position
@@ -55,7 +54,7 @@ impl<'a, 'b, 'f> AlphaBetaFilter<'a, 'b, 'f> {
         }
     }
 
-    pub fn next_estimate(&self, obs: &BigInt, epoch_delta: ChainEpoch) -> FilterEstimate {
+    pub fn next_estimate(&self, obs: &BigInt, epoch_delta: i64) -> FilterEstimate {
         let delta_t = BigInt::from(epoch_delta) << PRECISION;
         let delta_x = (&delta_t * &self.prev_est.velocity) >> PRECISION;
         let mut position = delta_x + &self.prev_est.position;
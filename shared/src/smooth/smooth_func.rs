@@ -3,7 +3,6 @@
 
 use super::alpha_beta_filter::*;
 use crate::bigint::{BigInt, Integer};
-use crate::clock::ChainEpoch;
 use crate::math::{poly_parse, poly_val, PRECISION};
 
 lazy_static! {
@@ -39,8 +38,8 @@ fn get_bit_len(z: &BigInt) -> u64 {
 
 /// Extrapolate the CumSumRatio given two filters.
 pub fn extrapolated_cum_sum_of_ratio(
-    delta: ChainEpoch,
-    relative_start: ChainEpoch,
+    delta: i64,
+    relative_start: i64,
     est_num: &FilterEstimate,
     est_denom: &FilterEstimate,
 ) -> BigInt {
@@ -19,8 +19,11 @@ pub enum StateTreeVersion {
     V2,
     /// Corresponds to actors = v4
     V3,
-    /// Corresponds to actors >= v5
+    /// Corresponds to actors v5 to v9
     V4,
+    /// Corresponds to actors >= v10. Actor states record the actor's delegated (f4) address, if
+    /// any.
+    V5,
 }
 
 /// State root information. Contains information about the version of the state tree,
@@ -0,0 +1,64 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! The canonical FIL supply and its genesis allocations, in whole FIL. The helpers return the
+//! same quantities as [`TokenAmount`]s (i.e., in attoFIL).
+//!
+//! The allocations add up to [`TOTAL_FILECOIN_BASE`].
+
+use crate::econ::TokenAmount;
+use crate::TOTAL_FILECOIN_BASE;
+
+/// FIL allocated to storage mining, held by the reward actor at genesis and paid out as block
+/// rewards.
+pub const STORAGE_MINING_ALLOCATION: i64 = 1_100_000_000;
+
+/// FIL held back in the mining reserve (the reserve actor) at genesis, for future mining
+/// incentives.
+pub const MINING_RESERVE_ALLOCATION: i64 = 300_000_000;
+
+/// FIL allocated to Protocol Labs, vesting from genesis.
+pub const PROTOCOL_LABS_ALLOCATION: i64 = 300_000_000;
+
+/// FIL allocated to the Filecoin Foundation, vesting from genesis.
+pub const FOUNDATION_ALLOCATION: i64 = 100_000_000;
+
+/// FIL allocated to fundraising participants, vesting from genesis.
+pub const FUNDRAISING_ALLOCATION: i64 = 200_000_000;
+
+/// The total FIL supply.
+pub fn total_fil() -> TokenAmount {
+    TokenAmount::from_whole(TOTAL_FILECOIN_BASE)
+}
+
+/// The FIL allocated to storage mining. See [`STORAGE_MINING_ALLOCATION`].
+pub fn storage_mining_allocation() -> TokenAmount {
+    TokenAmount::from_whole(STORAGE_MINING_ALLOCATION)
+}
+
+/// The FIL held in the mining reserve at genesis. See [`MINING_RESERVE_ALLOCATION`].
+pub fn mining_reserve_allocation() -> TokenAmount {
+    TokenAmount::from_whole(MINING_RESERVE_ALLOCATION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocations_add_up_to_total() {
+        assert_eq!(
+            STORAGE_MINING_ALLOCATION
+                + MINING_RESERVE_ALLOCATION
+                + PROTOCOL_LABS_ALLOCATION
+                + FOUNDATION_ALLOCATION
+                + FUNDRAISING_ALLOCATION,
+            TOTAL_FILECOIN_BASE
+        );
+        assert_eq!(total_fil(), *crate::TOTAL_FILECOIN);
+        assert_eq!(
+            total_fil().atto(),
+            &(num_bigint::BigInt::from(TOTAL_FILECOIN_BASE) * TokenAmount::PRECISION)
+        );
+    }
+}
@@ -7,7 +7,7 @@ use std::str::FromStr;
 use data_encoding::{DecodeError, DecodeKind};
 use fvm_ipld_encoding::{from_slice, Cbor};
 use fvm_shared::address::{
-    Address, Error, Network, Protocol, BLS_PUB_LEN, MAX_SUBADDRESS_LEN, PAYLOAD_HASH_LEN,
+    Address, Error, Network, Protocol, BLS_PUB_LEN, MAX_ID, MAX_SUBADDRESS_LEN, PAYLOAD_HASH_LEN,
     SECP_PUB_LEN,
 };
 
@@ -233,9 +233,9 @@ fn delegated_address() {
             expected: "f432-77777777",
         },
         F4TestVec {
-            namespace: std::u64::MAX,
+            namespace: MAX_ID,
             subaddr: &[],
-            expected: "f418446744073709551615-",
+            expected: "f49223372036854775807-",
         },
         F4TestVec {
             namespace: 100,
@@ -290,8 +290,8 @@ fn id_address() {
             expected: "f0999999",
         },
         IDTestVec {
-            input: std::u64::MAX,
-            expected: "f018446744073709551615",
+            input: MAX_ID,
+            expected: "f09223372036854775807",
         },
     ];
 
@@ -301,6 +301,25 @@ fn id_address() {
     }
 }
 
+#[test]
+fn id_range() {
+    let max = Address::new_id(MAX_ID);
+    assert_eq!(max.id(), Ok(MAX_ID));
+    assert_eq!(Address::from_bytes(&max.to_bytes()), Ok(max));
+
+    // 2^63 is out of range, however it's given.
+    let err = || Error::IDOutOfRange(MAX_ID + 1);
+    let over = Address::new_id(MAX_ID + 1);
+    assert_eq!(over.id(), Err(err()));
+    assert_eq!(Address::from_bytes(&over.to_bytes()), Err(err()));
+    assert_eq!(Address::from_str("f09223372036854775808"), Err(err()));
+    assert_eq!(Address::new_delegated(MAX_ID + 1, &[]), Err(err()));
+    assert_eq!(Address::from_str("f49223372036854775808-"), Err(err()));
+    let mut delegated = vec![4];
+    delegated.extend(over.payload_bytes());
+    assert_eq!(Address::from_bytes(&delegated), Err(err()));
+}
+
 #[test]
 fn invalid_string_addresses() {
     struct StringAddrVec {
@@ -401,6 +420,16 @@ fn invalid_byte_addresses() {
             input: vec![0],
             expected: Error::InvalidLength,
         },
+        StringAddrVec {
+            input: vec![0, 1, 0],
+            expected: Error::InvalidPayload,
+        },
+        StringAddrVec {
+            input: vec![
+                0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01,
+            ],
+            expected: Error::IDOutOfRange(u64::MAX),
+        },
         // SECP256K1 Protocol
         StringAddrVec {
             input: secp_l,
@@ -103,3 +103,45 @@ fn symmetric_conversion() {
         (FIL_COMMITMENT_UNSEALED, SHA2_256_TRUNC254_PADDED, comm)
     );
 }
+
+#[test]
+fn invalid_commitments() {
+    let comm = rand_comm();
+
+    // Unknown codec
+    assert!(commitment_to_cid(0x55, SHA2_256_TRUNC254_PADDED, &comm).is_err());
+    let mh = Multihash::wrap(SHA2_256_TRUNC254_PADDED, &comm).unwrap();
+    assert!(cid_to_commitment(&Cid::new_v1(0x55, mh)).is_err());
+
+    // Mismatched codec and hash
+    assert!(commitment_to_cid(FIL_COMMITMENT_SEALED, SHA2_256_TRUNC254_PADDED, &comm).is_err());
+    assert!(commitment_to_cid(FIL_COMMITMENT_UNSEALED, POSEIDON_BLS12_381_A1_FC1, &comm).is_err());
+
+    // Wrong digest length
+    let mh = Multihash::wrap(SHA2_256_TRUNC254_PADDED, &comm[..31]).unwrap();
+    let c = Cid::new_v1(FIL_COMMITMENT_UNSEALED, mh);
+    assert_eq!(
+        cid_to_data_commitment_v1(&c),
+        Err("commitments must be 32 bytes long")
+    );
+    let mh = Multihash::wrap(POSEIDON_BLS12_381_A1_FC1, &[0; 33]).unwrap();
+    let c = Cid::new_v1(FIL_COMMITMENT_SEALED, mh);
+    assert_eq!(
+        cid_to_replica_commitment_v1(&c),
+        Err("commitments must be 32 bytes long")
+    );
+
+    // Wrong codec, with the correct hash
+    let mh = Multihash::wrap(POSEIDON_BLS12_381_A1_FC1, &comm).unwrap();
+    let c = Cid::new_v1(FIL_COMMITMENT_SEALED, mh);
+    assert_eq!(
+        cid_to_data_commitment_v1(&c),
+        Err("data commitment codec must be Unsealed")
+    );
+    let mh = Multihash::wrap(SHA2_256_TRUNC254_PADDED, &comm).unwrap();
+    let c = Cid::new_v1(FIL_COMMITMENT_UNSEALED, mh);
+    assert_eq!(
+        cid_to_replica_commitment_v1(&c),
+        Err("replica commitment codec must be Sealed")
+    );
+}
@@ -0,0 +1,197 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fvm_ipld_encoding::{Cbor, RawBytes};
+use fvm_shared::address::Address;
+use fvm_shared::crypto::signature::Signature;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::{Message, SignedMessage, MAX_MESSAGE_PARAMS_LEN};
+use fvm_shared::BLOCK_GAS_LIMIT;
+
+fn transfer() -> Message {
+    Message {
+        version: 0,
+        from: Address::new_id(100),
+        to: Address::new_id(1234),
+        sequence: 42,
+        value: TokenAmount::from_whole(1),
+        method_num: 0,
+        params: RawBytes::default(),
+        gas_limit: 1_000_000,
+        gas_fee_cap: TokenAmount::from_atto(100_000),
+        gas_premium: TokenAmount::from_atto(1_000),
+    }
+}
+
+fn invoke() -> Message {
+    Message {
+        version: 0,
+        from: Address::new_id(1001),
+        to: Address::new_id(5),
+        sequence: 7,
+        value: TokenAmount::from_atto(0),
+        method_num: 2,
+        params: RawBytes::new(vec![0x82, 0x01, 0xf6]),
+        gas_limit: 2_500_000,
+        gas_fee_cap: TokenAmount::from_atto(2_000_000_000),
+        gas_premium: TokenAmount::from_atto(150_000),
+    }
+}
+
+#[test]
+fn message_encoding() {
+    // Fields are encoded in the order: version, to, from, nonce, value, gas limit, gas fee cap,
+    // gas premium, method, params.
+    let msg = transfer();
+    let bytes = msg.marshal_cbor().unwrap();
+    assert_eq!(
+        bytes,
+        [
+            0x8a, 0x00, 0x43, 0x00, 0xd2, 0x09, 0x42, 0x00, 0x64, 0x18, 0x2a, 0x49, 0x00, 0x0d,
+            0xe0, 0xb6, 0xb3, 0xa7, 0x64, 0x00, 0x00, 0x1a, 0x00, 0x0f, 0x42, 0x40, 0x44, 0x00,
+            0x01, 0x86, 0xa0, 0x43, 0x00, 0x03, 0xe8, 0x00, 0x40
+        ]
+    );
+    assert_eq!(Message::unmarshal_cbor(&bytes).unwrap(), msg);
+}
+
+#[test]
+fn message_cid() {
+    assert_eq!(
+        transfer().cid().unwrap().to_string(),
+        "bafy2bzacedg2u5ldwmqhi5cnewcbjurc3y6yl5ytesaqomisfn2wsasioxi2m"
+    );
+    assert_eq!(
+        invoke().cid().unwrap().to_string(),
+        "bafy2bzacea2ohfbddnan7cpzi54kfapmx4bu7dbkimlvbsuitvn57avn3ztby"
+    );
+}
+
+#[test]
+fn signed_message_cid() {
+    let sig_bytes: Vec<u8> = (0..65).collect();
+
+    // Secp messages are identified by the signed message.
+    let smsg = SignedMessage::new_unchecked(invoke(), Signature::new_secp256k1(sig_bytes));
+    assert!(!smsg.is_bls());
+    assert_eq!(smsg.chain_length().unwrap(), 101);
+    assert_eq!(
+        smsg.cid().unwrap().to_string(),
+        "bafy2bzacecv3owj4hac6b73jmoj4zrmuaowtluthnv44y3jl3huktvyqijzfk"
+    );
+
+    // BLS messages are identified by the unsigned message.
+    let smsg = SignedMessage::new_unchecked(invoke(), Signature::new_bls(vec![0; 96]));
+    assert!(smsg.is_bls());
+    assert_eq!(smsg.cid().unwrap(), invoke().cid().unwrap());
+    assert_eq!(smsg.chain_length().unwrap(), 32);
+
+    let bytes = smsg.marshal_cbor().unwrap();
+    assert_eq!(SignedMessage::unmarshal_cbor(&bytes).unwrap(), smsg);
+}
+
+/// Serialized messages with their CIDs and on-chain lengths, decoded rather than built so that the
+/// CIDs are checked against the bytes alone. The CIDs were computed with an encoder independent of
+/// this crate, following the Lotus field order.
+///
+/// TODO: replace these with messages taken from mainnet, along with the CIDs Lotus reports for them.
+const FIXTURES: &[(&str, &str, usize)] = &[
+    // An unsigned message (`invoke()`).
+    (
+        "8a004200054300e90707401a002625a045007735940044000249f002438201f6",
+        "bafy2bzacea2ohfbddnan7cpzi54kfapmx4bu7dbkimlvbsuitvn57avn3ztby",
+        32,
+    ),
+    // A secp256k1-signed message, identified by the signed message.
+    (
+        "828a004200054300e90707401a002625a045007735940044000249f002438201f658420100010203040506\
+         0708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f303132\
+         333435363738393a3b3c3d3e3f40",
+        "bafy2bzacecv3owj4hac6b73jmoj4zrmuaowtluthnv44y3jl3huktvyqijzfk",
+        101,
+    ),
+    // A BLS-signed message, identified by the unsigned message.
+    (
+        "828a004200054300e90707401a002625a045007735940044000249f002438201f658610200000000000000\
+         0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+         0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+         00",
+        "bafy2bzacea2ohfbddnan7cpzi54kfapmx4bu7dbkimlvbsuitvn57avn3ztby",
+        32,
+    ),
+];
+
+#[test]
+fn fixtures() {
+    for &(hex, cid, length) in FIXTURES {
+        let bytes: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        // Signed messages are 2-tuples, unsigned messages 10-tuples.
+        if bytes[0] == 0x82 {
+            let smsg = SignedMessage::unmarshal_cbor(&bytes).unwrap();
+            assert_eq!(smsg.marshal_cbor().unwrap(), bytes);
+            assert_eq!(smsg.cid().unwrap().to_string(), cid);
+            assert_eq!(smsg.chain_length().unwrap(), length);
+        } else {
+            let msg = Message::unmarshal_cbor(&bytes).unwrap();
+            assert_eq!(msg.marshal_cbor().unwrap(), bytes);
+            assert_eq!(msg.cid().unwrap().to_string(), cid);
+            assert_eq!(bytes.len(), length);
+        }
+    }
+}
+
+#[test]
+fn check_fields() {
+    transfer().check_fields().unwrap();
+    invoke().check_fields().unwrap();
+
+    let invalid = [
+        Message {
+            value: TokenAmount::from_atto(-1),
+            ..transfer()
+        },
+        Message {
+            value: TokenAmount::from_whole(2_000_000_001),
+            ..transfer()
+        },
+        Message {
+            gas_limit: 0,
+            ..transfer()
+        },
+        Message {
+            gas_limit: BLOCK_GAS_LIMIT + 1,
+            ..transfer()
+        },
+        Message {
+            gas_fee_cap: TokenAmount::from_atto(-1),
+            ..transfer()
+        },
+        Message {
+            gas_premium: TokenAmount::from_atto(-1),
+            ..transfer()
+        },
+        Message {
+            gas_premium: TokenAmount::from_atto(100_001),
+            ..transfer()
+        },
+        Message {
+            params: RawBytes::new(vec![0; MAX_MESSAGE_PARAMS_LEN + 1]),
+            ..transfer()
+        },
+    ];
+    for msg in invalid {
+        msg.check_fields().unwrap_err();
+    }
+
+    Message {
+        value: TokenAmount::from_whole(2_000_000_000),
+        gas_limit: BLOCK_GAS_LIMIT,
+        params: RawBytes::new(vec![0; MAX_MESSAGE_PARAMS_LEN]),
+        ..transfer()
+    }
+    .check_fields()
+    .unwrap();
+}
@@ -0,0 +1,182 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Round-trip tests for the sector types that cross the syscall boundary. The fixtures are the
+//! tuple-CBOR encodings produced by the Go (cbor-gen) types in go-state-types for the same values,
+//! so any change in field order or field encoding shows up here.
+
+use cid::Cid;
+use fvm_ipld_encoding::{from_slice, to_vec};
+use fvm_shared::commcid::{data_commitment_v1_to_cid, replica_commitment_v1_to_cid};
+use fvm_shared::randomness::Randomness;
+use fvm_shared::sector::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const SECTOR_ID: &str = "821903e8182a";
+
+const SECTOR_INFO: &str = concat!(
+    "8308182ad82a5829000182e20381e80220030303030303030303030303030303",
+    "0303030303030303030303030303030303",
+);
+
+const POST_PROOF: &str = "820843aabbcc";
+
+const WINDOW_POST_VERIFY_INFO: &str = concat!(
+    "8458200101010101010101010101010101010101010101010101010101010101",
+    "01010181820843aabbcc818308182ad82a5829000182e20381e8022003030303",
+    "030303030303030303030303030303030303030303030303030303031903e8",
+);
+
+const SEAL_VERIFY_INFO: &str = concat!(
+    "8808821903e8182a83010219012c582001010101010101010101010101010101",
+    "0101010101010101010101010101010158200202020202020202020202020202",
+    "02020202020202020202020202020202020243aabbccd82a5829000182e20381",
+    "e802200303030303030303030303030303030303030303030303030303030303",
+    "030303d82a5828000181e2039220200404040404040404040404040404040404",
+    "040404040404040404040404040404",
+);
+
+const AGGREGATE_SEAL_VERIFY_PROOF_AND_INFOS: &str = concat!(
+    "851903e8080043aabbcc8185182a582001010101010101010101010101010101",
+    "0101010101010101010101010101010158200202020202020202020202020202",
+    "020202020202020202020202020202020202d82a5829000182e20381e8022003",
+    "03030303030303030303030303030303030303030303030303030303030303d8",
+    "2a5828000181e203922020040404040404040404040404040404040404040404",
+    "0404040404040404040404",
+);
+
+// Unlike the Go type, the proof is encoded as a list of integers rather than a byte string. Changing
+// this would break compatibility with existing actors.
+const REPLICA_UPDATE_INFO: &str = concat!(
+    "8503d82a5829000182e20381e802200303030303030303030303030303030303",
+    "030303030303030303030303030303d82a5829000182e20381e8022005050505",
+    "05050505050505050505050505050505050505050505050505050505d82a5828",
+    "000181e203922020040404040404040404040404040404040404040404040404",
+    "04040404040404048318aa18bb18cc",
+);
+
+fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn assert_round_trip<T>(value: T, fixture: &str)
+where
+    T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    let bytes = unhex(fixture);
+    assert_eq!(to_vec(&value).unwrap(), bytes, "encoding mismatch");
+    assert_eq!(from_slice::<T>(&bytes).unwrap(), value, "decoding mismatch");
+}
+
+fn sealed_cid(fill: u8) -> Cid {
+    replica_commitment_v1_to_cid(&[fill; 32]).unwrap()
+}
+
+fn unsealed_cid() -> Cid {
+    data_commitment_v1_to_cid(&[4; 32]).unwrap()
+}
+
+fn sector_id() -> SectorID {
+    SectorID {
+        miner: 1000,
+        number: 42,
+    }
+}
+
+fn sector_info() -> SectorInfo {
+    SectorInfo {
+        proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+        sector_number: 42,
+        sealed_cid: sealed_cid(3),
+    }
+}
+
+fn post_proof() -> PoStProof {
+    PoStProof {
+        post_proof: RegisteredPoStProof::StackedDRGWindow32GiBV1,
+        proof_bytes: vec![0xaa, 0xbb, 0xcc],
+    }
+}
+
+#[test]
+fn sector_id_fixture() {
+    assert_round_trip(sector_id(), SECTOR_ID);
+}
+
+#[test]
+fn sector_info_fixture() {
+    assert_round_trip(sector_info(), SECTOR_INFO);
+}
+
+#[test]
+fn post_proof_fixture() {
+    assert_round_trip(post_proof(), POST_PROOF);
+}
+
+#[test]
+fn window_post_verify_info_fixture() {
+    assert_round_trip(
+        WindowPoStVerifyInfo {
+            randomness: Randomness([1; 32]),
+            proofs: vec![post_proof()],
+            challenged_sectors: vec![sector_info()],
+            prover: 1000,
+        },
+        WINDOW_POST_VERIFY_INFO,
+    );
+}
+
+#[test]
+fn seal_verify_info_fixture() {
+    assert_round_trip(
+        SealVerifyInfo {
+            registered_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+            sector_id: sector_id(),
+            deal_ids: vec![1, 2, 300],
+            randomness: Randomness([1; 32]),
+            interactive_randomness: Randomness([2; 32]),
+            proof: vec![0xaa, 0xbb, 0xcc],
+            sealed_cid: sealed_cid(3),
+            unsealed_cid: unsealed_cid(),
+        },
+        SEAL_VERIFY_INFO,
+    );
+}
+
+#[test]
+fn aggregate_seal_verify_proof_and_infos_fixture() {
+    assert_round_trip(
+        AggregateSealVerifyProofAndInfos {
+            miner: 1000,
+            seal_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+            aggregate_proof: RegisteredAggregateProof::SnarkPackV1,
+            proof: vec![0xaa, 0xbb, 0xcc],
+            infos: vec![AggregateSealVerifyInfo {
+                sector_number: 42,
+                randomness: Randomness([1; 32]),
+                interactive_randomness: Randomness([2; 32]),
+                sealed_cid: sealed_cid(3),
+                unsealed_cid: unsealed_cid(),
+            }],
+        },
+        AGGREGATE_SEAL_VERIFY_PROOF_AND_INFOS,
+    );
+}
+
+#[test]
+fn replica_update_info_fixture() {
+    assert_round_trip(
+        ReplicaUpdateInfo {
+            update_proof_type: RegisteredUpdateProof::StackedDRG32GiBV1,
+            old_sealed_cid: sealed_cid(3),
+            new_sealed_cid: sealed_cid(5),
+            new_unsealed_cid: unsealed_cid(),
+            proof: vec![0xaa, 0xbb, 0xcc],
+        },
+        REPLICA_UPDATE_INFO,
+    );
+}
@@ -0,0 +1,26 @@
+#![no_main]
+
+use std::str::FromStr;
+
+use fvm_shared::address::{Address, Protocol};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let addr = match Address::from_bytes(data) {
+        Ok(addr) => addr,
+        Err(_) => return,
+    };
+    if addr.to_bytes() != data {
+        panic!("decoded addresses must encode to the same bytes");
+    }
+
+    let s = addr.to_string();
+    let addr2 = Address::from_str(&s).expect("formatted addresses must parse");
+    if addr2 != addr {
+        panic!("string round-trip must be stable");
+    }
+
+    if addr.protocol() == Protocol::ID {
+        addr.id().expect("decoded ID addresses must be in range");
+    }
+});
@@ -5,7 +5,9 @@ use cid::Cid;
 use fmt::Display;
 use fvm::executor::{ApplyKind, ApplyRet, DefaultExecutor, Executor};
 use fvm::kernel::Context;
-use fvm::machine::{Machine, MultiEngine};
+use fvm::machine::{
+    diff_state, FeatureAvailability, FlushStats, Machine, MachineContext, MultiEngine,
+};
 use fvm::state_tree::{ActorState, StateTree};
 use fvm_ipld_blockstore::MemoryBlockstore;
 use fvm_ipld_encoding::{Cbor, CborStore};
@@ -44,6 +46,32 @@ pub fn is_runnable(entry: &DirEntry) -> bool {
     file_name.ends_with(".json")
 }
 
+/// Checks that the machine runs at the variant's network version, where exactly the syscalls
+/// available on mainnet at that version are available.
+fn check_availability(ctx: &MachineContext, variant: &Variant) -> Result<()> {
+    let nv = ctx.network_version;
+    if nv as u32 != variant.nv {
+        return Err(anyhow!(
+            "machine runs at nv{}, expected nv{}",
+            nv,
+            variant.nv
+        ));
+    }
+    let actual: Vec<_> = ctx.feature_availability.unavailable_syscalls(nv).collect();
+    let expected: Vec<_> = FeatureAvailability::default()
+        .unavailable_syscalls(nv)
+        .collect();
+    if actual != expected {
+        return Err(anyhow!(
+            "nv{}: unavailable syscalls {:?}, expected {:?}",
+            nv,
+            actual,
+            expected
+        ));
+    }
+    Ok(())
+}
+
 /// Compares the result of running a message with the expected result.
 fn check_msg_result(expected_rec: &Receipt, ret: &ApplyRet, label: impl Display) -> Result<()> {
     let error = ret
@@ -163,6 +191,17 @@ fn compare_state_roots(bs: &MemoryBlockstore, root: &Cid, vector: &MessageVector
         )?;
     }
 
+    // Finally, diff the entire state trees. This may fail if the vector doesn't include the whole
+    // expected state tree.
+    match diff_state(bs, &vector.postconditions.state_tree.root_cid, root) {
+        Ok(diffs) => {
+            for diff in diffs {
+                log::error!("state diff (expected -> actual): {}", diff);
+            }
+        }
+        Err(e) => log::warn!("failed to diff state trees: {:#}", e),
+    }
+
     return Err(anyhow!(
         "wrong post root cid; expected {}, but got {}",
         &vector.postconditions.state_tree.root_cid,
@@ -187,10 +226,58 @@ pub fn run_variant(
     engines: &MultiEngine,
     check_correctness: bool,
 ) -> anyhow::Result<VariantResult> {
+    run_variant_with_gas(bs, v, variant, engines, check_correctness).map(|(res, _)| res)
+}
+
+/// Runs a variant like [`run_variant`], additionally returning the difference between the gas
+/// used by the variant's messages and the gas used according to the vector (the gas delta).
+///
+/// The gas delta only covers the messages applied before the variant failed, and is always zero
+/// when correctness isn't checked.
+pub fn run_variant_with_gas(
+    bs: MemoryBlockstore,
+    v: &MessageVector,
+    variant: &Variant,
+    engines: &MultiEngine,
+    check_correctness: bool,
+) -> anyhow::Result<(VariantResult, i64)> {
+    run_variant_with_stats(bs, v, variant, engines, check_correctness)
+        .map(|run| (run.result, run.gas_delta))
+}
+
+/// A variant's result, along with measurements taken while running it.
+pub struct VariantRun {
+    pub result: VariantResult,
+    /// The gas delta. See [`run_variant_with_gas`].
+    pub gas_delta: i64,
+    /// The statistics of the machine's final flush, if the variant got that far.
+    pub flush_stats: Option<FlushStats>,
+}
+
+/// Runs a variant like [`run_variant_with_gas`], additionally returning the statistics of the
+/// machine's final flush.
+pub fn run_variant_with_stats(
+    bs: MemoryBlockstore,
+    v: &MessageVector,
+    variant: &Variant,
+    engines: &MultiEngine,
+    check_correctness: bool,
+) -> anyhow::Result<VariantRun> {
     let id = variant.id.clone();
+    let mut gas_delta = 0;
+    let run = |result, gas_delta, flush_stats| {
+        Ok(VariantRun {
+            result,
+            gas_delta,
+            flush_stats,
+        })
+    };
 
     // Construct the Machine.
     let machine = TestMachine::new_for_vector(v, variant, bs, engines);
+    if let Err(err) = check_availability(machine.context(), variant) {
+        return run(VariantResult::Failed { id, reason: err }, gas_delta, None);
+    }
     let mut exec: DefaultExecutor<TestKernel> = DefaultExecutor::new(machine);
 
     // Apply all messages in the vector.
@@ -206,49 +293,63 @@ pub fn run_variant(
 
         let ret = match exec.execute_message(msg, ApplyKind::Explicit, raw_length) {
             Ok(ret) => ret,
-            Err(e) => return Ok(VariantResult::Failed { id, reason: e }),
+            Err(e) => return run(VariantResult::Failed { id, reason: e }, gas_delta, None),
         };
 
         if check_correctness {
             // Compare the actual receipt with the expected receipt.
             let expected_receipt = &v.postconditions.receipts[i];
+            gas_delta += ret.msg_receipt.gas_used - expected_receipt.gas_used;
             if let Err(err) = check_msg_result(expected_receipt, &ret, i) {
-                return Ok(VariantResult::Failed { id, reason: err });
+                return run(VariantResult::Failed { id, reason: err }, gas_delta, None);
             }
         }
     }
 
     // Flush the machine, obtain the blockstore, and compare the
     // resulting state root with the expected state root.
-    let final_root = match exec.flush() {
-        Ok(cid) => cid,
+    let flush_stats = match exec.flush_with_stats() {
+        Ok(stats) => stats,
         Err(err) => {
-            return Ok(VariantResult::Failed {
-                id,
-                reason: err.context("flushing executor failed"),
-            });
+            return run(
+                VariantResult::Failed {
+                    id,
+                    reason: err.context("flushing executor failed"),
+                },
+                gas_delta,
+                None,
+            );
         }
     };
+    let final_root = flush_stats.root;
 
     let machine = match exec.into_machine() {
         Some(machine) => machine,
         None => {
-            return Ok(VariantResult::Failed {
-                id,
-                reason: anyhow!("machine poisoned"),
-            })
+            return run(
+                VariantResult::Failed {
+                    id,
+                    reason: anyhow!("machine poisoned"),
+                },
+                gas_delta,
+                Some(flush_stats),
+            )
         }
     };
     if check_correctness {
         let bs = machine.into_store().into_inner();
 
         if let Err(err) = compare_state_roots(&bs, &final_root, v) {
-            return Ok(VariantResult::Failed {
-                id,
-                reason: err.context("comparing state roots failed"),
-            });
+            return run(
+                VariantResult::Failed {
+                    id,
+                    reason: err.context("comparing state roots failed"),
+                },
+                gas_delta,
+                Some(flush_stats),
+            );
         }
     }
 
-    Ok(VariantResult::Ok { id })
+    run(VariantResult::Ok { id }, gas_delta, Some(flush_stats))
 }
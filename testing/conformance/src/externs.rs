@@ -1,44 +1,26 @@
-use fvm::externs::{Consensus, Externs, Rand};
+use fvm::externs::{Consensus, ExternError, Externs, Rand};
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::randomness::Randomness;
 
-use crate::rand::ReplayingRand;
-use crate::vector::Randomness;
+use crate::rand::TestFallbackRand;
 
-/// The externs stub for testing. Forwards randomness requests to the randomness
-/// replayer, which replays randomness stored in the vector.
+/// The externs stub for testing. Recorded randomness is replayed by the test kernel, so this
+/// only supplies fixed base randomness.
+#[derive(Default)]
 pub struct TestExterns {
-    rand: ReplayingRand,
-}
-
-impl TestExterns {
-    /// Creates a new TestExterns from randomness contained in a vector.
-    pub fn new(r: &Randomness) -> Self {
-        TestExterns {
-            rand: ReplayingRand::new(r.as_slice()),
-        }
-    }
+    rand: TestFallbackRand,
 }
 
 impl Externs for TestExterns {}
 
 impl Rand for TestExterns {
-    fn get_chain_randomness(
-        &self,
-        pers: i64,
-        round: ChainEpoch,
-        entropy: &[u8],
-    ) -> anyhow::Result<[u8; 32]> {
-        self.rand.get_chain_randomness(pers, round, entropy)
+    fn get_chain_randomness(&self, round: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
+        self.rand.get_chain_randomness(round)
     }
 
-    fn get_beacon_randomness(
-        &self,
-        pers: i64,
-        round: ChainEpoch,
-        entropy: &[u8],
-    ) -> anyhow::Result<[u8; 32]> {
-        self.rand.get_beacon_randomness(pers, round, entropy)
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
+        self.rand.get_beacon_randomness(round)
     }
 }
 
@@ -48,7 +30,7 @@ impl Consensus for TestExterns {
         _h1: &[u8],
         _h2: &[u8],
         _extra: &[u8],
-    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+    ) -> Result<(Option<ConsensusFault>, i64), ExternError> {
         todo!()
     }
 }
@@ -0,0 +1,59 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use fvm::gas::{Gas, GasValues, PriceList};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+lazy_static! {
+    /// Gas price overrides, loaded from the TOML file specified in `GAS_OVERRIDES`.
+    static ref GAS_OVERRIDES: Option<HashMap<String, GasValues>> =
+        std::env::var_os("GAS_OVERRIDES").map(|path| {
+            load_overrides(&path).expect("failed to load gas overrides")
+        });
+}
+
+/// A single gas override, in milligas.
+///
+/// ```toml
+/// [OnBlockOpenBase]
+/// compute_gas = 114617000
+/// other_gas = 0
+/// ```
+#[derive(Deserialize)]
+struct GasOverride {
+    #[serde(default)]
+    compute_gas: i64,
+    #[serde(default, alias = "storage_gas")]
+    other_gas: i64,
+}
+
+fn load_overrides(path: impl AsRef<std::path::Path>) -> anyhow::Result<HashMap<String, GasValues>> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let overrides: HashMap<String, GasOverride> =
+        toml::from_str(&contents).context("failed to parse gas overrides")?;
+    Ok(overrides
+        .into_iter()
+        .map(|(name, o)| {
+            (
+                name,
+                GasValues {
+                    compute_gas: Gas::from_milligas(o.compute_gas),
+                    other_gas: Gas::from_milligas(o.other_gas),
+                },
+            )
+        })
+        .collect())
+}
+
+/// Returns the given price list with the overrides from the `GAS_OVERRIDES` file applied, or
+/// `None` if no overrides were specified.
+pub fn price_list_override(base: &PriceList) -> Option<PriceList> {
+    let overrides = GAS_OVERRIDES.as_ref()?;
+    Some(base.with_overrides(overrides.clone()))
+}
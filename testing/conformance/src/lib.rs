@@ -4,7 +4,9 @@
 pub mod cidjson;
 pub mod driver;
 pub mod externs;
+pub mod gas;
 pub mod rand;
+pub mod runner;
 pub mod vector;
 pub mod vm;
 
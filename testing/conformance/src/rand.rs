@@ -1,14 +1,19 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use fvm::externs::Rand;
+use fvm::externs::{ExternError, Rand};
 use fvm_shared::clock::ChainEpoch;
+use fvm_shared::randomness::Randomness;
 
 use crate::vector::{RandomnessKind, RandomnessMatch, RandomnessRule};
 
 /// Takes recorded randomness and replays it when input parameters match.
 /// When there's no match, it falls back to TestFallbackRand, which returns a
 /// fixed output.
+///
+/// Test vectors record the final randomness returned to actors (after mixing in the domain
+/// separation tag, epoch and entropy), so this is consulted by the test kernel rather than
+/// through the externs.
 pub struct ReplayingRand {
     pub recorded: Vec<RandomnessMatch>,
     pub fallback: TestFallbackRand,
@@ -16,15 +21,20 @@ pub struct ReplayingRand {
 
 /// Implements the Rand extern and returns static values as randomness outputs
 /// when there's a vector miss.
+#[derive(Default)]
 pub struct TestFallbackRand;
 
+impl TestFallbackRand {
+    const RANDOMNESS: Randomness = Randomness(*b"i_am_random_____i_am_random_____");
+}
+
 impl Rand for TestFallbackRand {
-    fn get_chain_randomness(&self, _: i64, _: ChainEpoch, _: &[u8]) -> anyhow::Result<[u8; 32]> {
-        Ok(*b"i_am_random_____i_am_random_____")
+    fn get_chain_randomness(&self, _: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
+        Ok((Self::RANDOMNESS, 0))
     }
 
-    fn get_beacon_randomness(&self, _: i64, _: ChainEpoch, _: &[u8]) -> anyhow::Result<[u8; 32]> {
-        Ok(*b"i_am_random_____i_am_random_____")
+    fn get_beacon_randomness(&self, _: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
+        Ok((Self::RANDOMNESS, 0))
     }
 }
 
@@ -36,53 +46,35 @@ impl ReplayingRand {
         }
     }
 
-    pub fn matches(&self, requested: RandomnessRule) -> Option<[u8; 32]> {
+    pub fn matches(&self, requested: RandomnessRule) -> Option<Randomness> {
         for other in &self.recorded {
             if other.on == requested {
-                let mut randomness = [0u8; 32];
-                randomness.copy_from_slice(&other.ret);
-                return Some(randomness);
+                let randomness = other.ret.as_slice().try_into().unwrap_or_else(|_| {
+                    panic!("recorded randomness has {} bytes", other.ret.len())
+                });
+                return Some(Randomness(randomness));
             }
         }
         None
     }
-}
 
-impl Rand for ReplayingRand {
-    fn get_chain_randomness(
-        &self,
-        dst: i64,
-        epoch: ChainEpoch,
-        entropy: &[u8],
-    ) -> anyhow::Result<[u8; 32]> {
+    pub fn get_chain_randomness(&self, dst: i64, epoch: ChainEpoch, entropy: &[u8]) -> Randomness {
         let rule = RandomnessRule {
             kind: RandomnessKind::Chain,
             dst,
             epoch,
             entropy: entropy.to_vec(),
         };
-        if let Some(bz) = self.matches(rule) {
-            Ok(bz)
-        } else {
-            self.fallback.get_chain_randomness(dst, epoch, entropy)
-        }
+        self.matches(rule).unwrap_or(TestFallbackRand::RANDOMNESS)
     }
-    fn get_beacon_randomness(
-        &self,
-        dst: i64,
-        epoch: ChainEpoch,
-        entropy: &[u8],
-    ) -> anyhow::Result<[u8; 32]> {
+
+    pub fn get_beacon_randomness(&self, dst: i64, epoch: ChainEpoch, entropy: &[u8]) -> Randomness {
         let rule = RandomnessRule {
             kind: RandomnessKind::Beacon,
             dst,
             epoch,
             entropy: entropy.to_vec(),
         };
-        if let Some(bz) = self.matches(rule) {
-            Ok(bz)
-        } else {
-            self.fallback.get_beacon_randomness(dst, epoch, entropy)
-        }
+        self.matches(rule).unwrap_or(TestFallbackRand::RANDOMNESS)
     }
 }
@@ -0,0 +1,217 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Runs many test vectors in parallel.
+//!
+//! Vectors are loaded (decompressed, parsed, and imported into a blockstore) on a thread pool, and
+//! each variant is then run on the same pool as soon as its vector is loaded. Every variant runs
+//! on a single thread, against its own copy of the vector's blockstore, so parallelism never
+//! affects execution.
+//!
+//! All variants share a single [`MultiEngine`], so actor modules are compiled once per engine
+//! configuration (and therefore once per actors bundle) rather than once per vector.
+
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+
+use anyhow::{anyhow, Context};
+use fvm::machine::{FlushStats, MultiEngine};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use itertools::Itertools;
+
+use crate::driver::{run_variant_with_stats, VariantResult};
+use crate::vector::MessageVector;
+
+/// Options for [`run_vectors`].
+#[derive(Clone, Debug)]
+pub struct RunnerOptions {
+    /// The number of vectors loaded and variants run at the same time.
+    ///
+    /// DEFAULT: the number of CPUs.
+    pub jobs: usize,
+    /// Whether to check the receipts and the final state root of each variant against the
+    /// vector's postconditions.
+    ///
+    /// DEFAULT: `true`
+    pub check_correctness: bool,
+}
+
+impl Default for RunnerOptions {
+    fn default() -> Self {
+        Self {
+            jobs: num_cpus::get(),
+            check_correctness: true,
+        }
+    }
+}
+
+impl RunnerOptions {
+    /// Sets the number of vectors loaded and variants run at the same time.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs;
+        self
+    }
+}
+
+/// The result of running a single variant.
+pub struct VariantOutcome {
+    /// The vector the variant belongs to.
+    pub path: PathBuf,
+    pub result: VariantResult,
+    /// The gas used by the variant's messages minus the gas used according to the vector.
+    pub gas_delta: i64,
+    /// The statistics of the machine's final flush, if the variant got that far.
+    pub flush_stats: Option<FlushStats>,
+}
+
+/// The aggregate results of [`run_vectors`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// The sum of the gas deltas of all variants.
+    pub gas_delta: i64,
+}
+
+impl Summary {
+    fn record(&mut self, outcome: &VariantOutcome) {
+        match outcome.result {
+            VariantResult::Ok { .. } => self.passed += 1,
+            VariantResult::Failed { .. } => self.failed += 1,
+            VariantResult::Skipped { .. } => self.skipped += 1,
+        }
+        self.gas_delta += outcome.gas_delta;
+    }
+}
+
+/// A vector and the blockstore seeded from its CAR.
+pub struct LoadedVector {
+    pub vector: MessageVector,
+    /// The seeded blockstore, or `None` if the vector isn't supported (and was therefore not
+    /// seeded).
+    pub blockstore: Option<MemoryBlockstore>,
+}
+
+/// Loads a vector from a file, seeding a new blockstore with its CAR and checking that the CAR
+/// contains the pre and postcondition state roots.
+pub fn load_vector(path: &Path) -> anyhow::Result<LoadedVector> {
+    let vector = MessageVector::from_file(path).context("failed to parse vector")?;
+    if !vector.is_supported() {
+        return Ok(LoadedVector {
+            vector,
+            blockstore: None,
+        });
+    }
+
+    let (bs, imported_root) = futures::executor::block_on(vector.seed_blockstore())?;
+    if !imported_root.contains(&vector.preconditions.state_tree.root_cid) {
+        return Err(anyhow!(
+            "imported roots ({}) do not contain precondition CID {}",
+            imported_root.iter().join(", "),
+            vector.preconditions.state_tree.root_cid
+        ));
+    }
+    if !imported_root.contains(&vector.postconditions.state_tree.root_cid) {
+        return Err(anyhow!(
+            "imported roots ({}) do not contain postcondition CID {}",
+            imported_root.iter().join(", "),
+            vector.postconditions.state_tree.root_cid
+        ));
+    }
+
+    Ok(LoadedVector {
+        vector,
+        blockstore: Some(bs),
+    })
+}
+
+/// Runs every variant of the given vectors, calling `on_result` with the outcome of each variant
+/// as soon as it finishes. Outcomes are reported in completion order, not in vector order.
+///
+/// Returns an error (without waiting for the remaining variants) if a vector fails to load.
+pub fn run_vectors<I, F>(
+    paths: I,
+    options: &RunnerOptions,
+    mut on_result: F,
+) -> anyhow::Result<Summary>
+where
+    I: IntoIterator<Item = PathBuf>,
+    F: FnMut(&VariantOutcome),
+{
+    let jobs = options.jobs.max(1);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .thread_name(|i| format!("conformance-{}", i))
+        .build()
+        .context("failed to build the runner thread pool")?;
+    let engines = MultiEngine::with_concurrency(jobs as u32);
+    let check_correctness = options.check_correctness;
+
+    let (tx, rx) = mpsc::channel();
+    for path in paths {
+        let tx = tx.clone();
+        let engines = engines.clone();
+        pool.spawn(move || {
+            let loaded = match load_vector(&path)
+                .with_context(|| format!("failed to run vector: {}", path.display()))
+            {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                    return;
+                }
+            };
+
+            let bs = match loaded.blockstore {
+                Some(bs) => bs,
+                None => {
+                    for variant in &loaded.vector.preconditions.variants {
+                        let _ = tx.send(Ok(VariantOutcome {
+                            path: path.clone(),
+                            result: VariantResult::Skipped {
+                                id: variant.id.clone(),
+                                reason: "selector not supported".to_owned(),
+                            },
+                            gas_delta: 0,
+                            flush_stats: None,
+                        }));
+                    }
+                    return;
+                }
+            };
+
+            let v = Arc::new(loaded.vector);
+            for i in 0..v.preconditions.variants.len() {
+                let (v, tx, engines, path) = (v.clone(), tx.clone(), engines.clone(), path.clone());
+                // Each variant gets its own copy of the blockstore.
+                let bs = bs.clone();
+                // Spawned from a pool thread, so this runs on the same pool.
+                rayon::spawn(move || {
+                    let variant = &v.preconditions.variants[i];
+                    let res = run_variant_with_stats(bs, &v, variant, &engines, check_correctness)
+                        .with_context(|| {
+                            format!("failed to run vector: {} | {}", path.display(), variant.id)
+                        })
+                        .map(|run| VariantOutcome {
+                            path,
+                            result: run.result,
+                            gas_delta: run.gas_delta,
+                            flush_stats: run.flush_stats,
+                        });
+                    let _ = tx.send(res);
+                });
+            }
+        });
+    }
+    // The loop below ends once every task (and therefore every sender) is done.
+    drop(tx);
+
+    let mut summary = Summary::default();
+    for outcome in rx {
+        let outcome = outcome?;
+        summary.record(&outcome);
+        on_result(&outcome);
+    }
+    Ok(summary)
+}
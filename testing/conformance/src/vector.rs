@@ -208,7 +208,7 @@ pub struct ApplyMessage {
     #[serde(with = "base64_bytes")]
     pub bytes: Vec<u8>,
     #[serde(default)]
-    pub epoch_offset: Option<ChainEpoch>,
+    pub epoch_offset: Option<i64>,
 }
 
 mod base64_bytes {
@@ -1,44 +1,71 @@
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
+use std::sync::Arc;
 
 use cid::Cid;
 use futures::executor::block_on;
 use fvm::call_manager::{CallManager, DefaultCallManager, FinishRet, InvocationResult};
-use fvm::gas::{Gas, GasTracker, PriceList};
+use fvm::gas::{Gas, GasLimit, GasTracker, PriceList};
 use fvm::kernel::*;
 use fvm::machine::{
-    DefaultMachine, Engine, Machine, MachineContext, Manifest, MultiEngine, NetworkConfig,
+    DefaultMachine, Engine, FlushStats, Machine, MachineContext, Manifest, MultiEngine,
+    NetworkConfig,
 };
 use fvm::state_tree::{ActorState, StateTree};
 use fvm::DefaultKernel;
-use fvm_ipld_blockstore::MemoryBlockstore;
-use fvm_ipld_car::load_car_unchecked;
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_ipld_car::{Block, CarReader};
 use fvm_shared::address::Address;
+use fvm_shared::chainid::ChainID;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::consensus::ConsensusFault;
 use fvm_shared::crypto::signature::{
-    SignatureType, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
+    SignatureType, BLS_PUB_LEN, BLS_SIG_LEN, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
 };
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::piece::PieceInfo;
-use fvm_shared::randomness::RANDOMNESS_LENGTH;
+use fvm_shared::randomness::Randomness;
 use fvm_shared::sector::{
     AggregateSealVerifyProofAndInfos, RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo,
     WindowPoStVerifyInfo,
 };
 use fvm_shared::version::NetworkVersion;
 use fvm_shared::{ActorID, MethodNum, TOTAL_FILECOIN};
+use lazy_static::lazy_static;
 use multihash::MultihashGeneric;
 
 use crate::externs::TestExterns;
+use crate::gas::price_list_override;
+use crate::rand::ReplayingRand;
 use crate::vector::{MessageVector, Variant};
 
 const DEFAULT_BASE_FEE: u64 = 100;
 
+lazy_static! {
+    /// The builtin actors bundles, decoded once and copied into the blockstore of every vector.
+    static ref BUNDLES: Vec<(NetworkVersion, Cid, Vec<Block>)> =
+        [(NetworkVersion::V15, actors_v7::BUNDLE_CAR)]
+            .into_iter()
+            .map(|(nv, car)| {
+                block_on(async {
+                    let mut reader = CarReader::new_unchecked(car).await.unwrap();
+                    assert_eq!(reader.header.roots.len(), 1);
+                    let root = reader.header.roots[0];
+                    let mut blocks = Vec::new();
+                    while let Some(block) = reader.next_block().await.unwrap() {
+                        blocks.push(block);
+                    }
+                    (nv, root, blocks)
+                })
+            })
+            .collect();
+}
+
 #[derive(Clone)]
 pub struct TestData {
     circ_supply: TokenAmount,
     price_list: PriceList,
+    randomness: Arc<ReplayingRand>,
 }
 
 pub struct TestMachine<M = Box<DefaultMachine<MemoryBlockstore, TestExterns>>> {
@@ -63,7 +90,7 @@ impl TestMachine<Box<DefaultMachine<MemoryBlockstore, TestExterns>>> {
         let epoch = variant.epoch;
         let state_root = v.preconditions.state_tree.root_cid;
 
-        let externs = TestExterns::new(&v.randomness);
+        let externs = TestExterns::default();
 
         // Load the builtin actors bundles into the blockstore.
         let nv_actors = TestMachine::import_actors(&blockstore);
@@ -75,8 +102,14 @@ impl TestMachine<Box<DefaultMachine<MemoryBlockstore, TestExterns>>> {
 
         let mut nc = NetworkConfig::new(network_version);
         nc.override_actors(builtin_actors);
+        if let Some(price_list) = price_list_override(&nc.price_list) {
+            nc.override_price_list(price_list);
+        }
         let mut mc = nc.for_epoch(epoch, state_root);
         mc.set_base_fee(base_fee);
+        mc.enable_invariant_checks();
+        // Vectors only carry the state their messages touch.
+        mc.require_singletons([]);
 
         let engine = engines.get(&mc.network).expect("getting engine");
 
@@ -91,7 +124,7 @@ impl TestMachine<Box<DefaultMachine<MemoryBlockstore, TestExterns>>> {
             )
             .unwrap();
 
-        let price_list = machine.context().price_list.clone();
+        let price_list = machine.context().price_list.clone().into_owned();
 
         TestMachine::<Box<DefaultMachine<_, _>>> {
             machine: Box::new(machine),
@@ -102,18 +135,19 @@ impl TestMachine<Box<DefaultMachine<MemoryBlockstore, TestExterns>>> {
                     .map(TokenAmount::from_atto)
                     .unwrap_or_else(|| TOTAL_FILECOIN.clone()),
                 price_list,
+                randomness: Arc::new(ReplayingRand::new(v.randomness.as_slice())),
             },
         }
     }
 
     pub fn import_actors(blockstore: &MemoryBlockstore) -> BTreeMap<NetworkVersion, Cid> {
-        let bundles = [(NetworkVersion::V15, actors_v7::BUNDLE_CAR)];
-        bundles
-            .into_iter()
-            .map(|(nv, car)| {
-                let roots = block_on(async { load_car_unchecked(blockstore, car).await.unwrap() });
-                assert_eq!(roots.len(), 1);
-                (nv, roots[0])
+        BUNDLES
+            .iter()
+            .map(|(nv, root, blocks)| {
+                blockstore
+                    .put_many_keyed(blocks.iter().map(|b| (b.cid, &b.data)))
+                    .expect("failed to import builtin actors");
+                (*nv, *root)
             })
             .collect()
     }
@@ -166,7 +200,7 @@ where
         self.machine.into_store()
     }
 
-    fn flush(&mut self) -> Result<Cid> {
+    fn flush(&mut self) -> Result<FlushStats> {
         self.machine.flush()
     }
 
@@ -189,7 +223,7 @@ where
 
     fn new(
         machine: Self::Machine,
-        gas_limit: i64,
+        gas_limit: GasLimit,
         origin: (ActorID, Address),
         nonce: u64,
         gas_premium: TokenAmount,
@@ -227,7 +261,7 @@ where
         })
     }
 
-    fn finish(self) -> (FinishRet, Self::Machine) {
+    fn finish(self) -> Result<(FinishRet, Self::Machine)> {
         self.0.finish()
     }
 
@@ -259,6 +293,18 @@ where
         self.0.next_actor_idx()
     }
 
+    fn count_actor_creation(&mut self, id: ActorID) -> Result<()> {
+        self.0.count_actor_creation(id)
+    }
+
+    fn actors_created(&self) -> u64 {
+        self.0.actors_created()
+    }
+
+    fn mark_pending_deletion(&mut self, id: ActorID) {
+        self.0.mark_pending_deletion(id)
+    }
+
     fn price_list(&self) -> &fvm::gas::PriceList {
         self.0.price_list()
     }
@@ -283,7 +329,7 @@ where
         self.0.state_tree_mut()
     }
 
-    fn charge_gas(&mut self, charge: fvm::gas::GasCharge) -> Result<()> {
+    fn charge_gas(&mut self, charge: fvm::gas::GasCharge) -> Result<fvm::gas::GasTimer> {
         self.0.charge_gas(charge)
     }
 
@@ -311,6 +357,10 @@ where
         (cm.0, br)
     }
 
+    fn commit_root(&mut self) -> Result<()> {
+        self.0.commit_root()
+    }
+
     fn new(
         mgr: Self::CallManager,
         blocks: BlockRegistry,
@@ -349,11 +399,11 @@ where
     C: CallManager<Machine = TestMachine<M>>,
     K: Kernel<CallManager = TestCallManager<C>>,
 {
-    fn resolve_address(&self, address: &Address) -> Result<Option<ActorID>> {
+    fn resolve_address(&mut self, address: &Address) -> Result<Option<ActorID>> {
         self.0.resolve_address(address)
     }
 
-    fn get_actor_code_cid(&self, id: ActorID) -> Result<Option<Cid>> {
+    fn get_actor_code_cid(&mut self, id: ActorID) -> Result<Option<Cid>> {
         self.0.get_actor_code_cid(id)
     }
 
@@ -378,8 +428,12 @@ where
         Ok(())
     }
 
-    fn balance_of(&self, _actor_id: ActorID) -> Result<TokenAmount> {
-        todo!()
+    fn upgrade_actor(&mut self, actor_id: ActorID, new_code_cid: Cid) -> Result<()> {
+        self.0.upgrade_actor(actor_id, new_code_cid)
+    }
+
+    fn balance_of(&mut self, actor_id: ActorID) -> Result<TokenAmount> {
+        self.0.balance_of(actor_id)
     }
 }
 
@@ -454,6 +508,17 @@ where
             .verify_signature(sig_type, signature, signer, plaintext)
     }
 
+    // forwarded
+    fn verify_bls_aggregate(
+        &mut self,
+        aggregate_sig: &[u8; BLS_SIG_LEN],
+        pub_keys: &[[u8; BLS_PUB_LEN]],
+        plaintexts: &[&[u8]],
+    ) -> Result<bool> {
+        self.0
+            .verify_bls_aggregate(aggregate_sig, pub_keys, plaintexts)
+    }
+
     // forwarded
     fn recover_secp_public_key(
         &mut self,
@@ -611,6 +676,10 @@ where
     fn tipset_cid(&self, _epoch: i64) -> Result<Option<Cid>> {
         todo!()
     }
+
+    fn chain_id(&self) -> ChainID {
+        self.0.chain_id()
+    }
 }
 
 impl<M, C, K> RandomnessOps for TestKernel<K>
@@ -619,24 +688,36 @@ where
     C: CallManager<Machine = TestMachine<M>>,
     K: Kernel<CallManager = TestCallManager<C>>,
 {
+    // NOT forwarded
     fn get_randomness_from_tickets(
         &mut self,
         personalization: i64,
         rand_epoch: ChainEpoch,
         entropy: &[u8],
-    ) -> Result<[u8; RANDOMNESS_LENGTH]> {
+    ) -> Result<Randomness> {
+        // Charge for and draw the randomness as usual, but return the recorded randomness.
         self.0
-            .get_randomness_from_tickets(personalization, rand_epoch, entropy)
+            .get_randomness_from_tickets(personalization, rand_epoch, entropy)?;
+        Ok(self
+            .1
+            .randomness
+            .get_chain_randomness(personalization, rand_epoch, entropy))
     }
 
+    // NOT forwarded
     fn get_randomness_from_beacon(
         &mut self,
         personalization: i64,
         rand_epoch: ChainEpoch,
         entropy: &[u8],
-    ) -> Result<[u8; RANDOMNESS_LENGTH]> {
+    ) -> Result<Randomness> {
+        // Charge for and draw the randomness as usual, but return the recorded randomness.
         self.0
-            .get_randomness_from_beacon(personalization, rand_epoch, entropy)
+            .get_randomness_from_beacon(personalization, rand_epoch, entropy)?;
+        Ok(self
+            .1
+            .randomness
+            .get_beacon_randomness(personalization, rand_epoch, entropy))
     }
 }
 
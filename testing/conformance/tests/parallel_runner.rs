@@ -0,0 +1,89 @@
+// Copyright 2019-2022 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use cid::Cid;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use fvm_conformance_tests::cidjson::CidJsonRef;
+use fvm_conformance_tests::runner::{load_vector, run_vectors, RunnerOptions, Summary};
+use fvm_ipld_blockstore::{Block, Blockstore};
+use fvm_ipld_car::CarHeader;
+use fvm_ipld_encoding::{to_vec, DAG_CBOR};
+use multihash::Code;
+use serde_json::json;
+
+/// Writes a vector whose CAR contains a single block (the state root), returning the vector's
+/// path and the root.
+fn write_vector(name: &str, content: &str, selector: serde_json::Value) -> (PathBuf, Cid) {
+    let data = to_vec(content).unwrap();
+    let root = Block::new(DAG_CBOR, &data).cid(Code::Blake2b256);
+
+    let mut car = Vec::new();
+    futures::executor::block_on(
+        CarHeader::new(vec![root], 1)
+            .write_stream_async(&mut car, &mut futures::stream::iter(vec![(root, data)])),
+    )
+    .unwrap();
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(&car).unwrap();
+
+    let vector = json!({
+        "class": "message",
+        "selector": selector,
+        "car": base64::encode(gz.finish().unwrap()),
+        "preconditions": {
+            "state_tree": { "root_cid": CidJsonRef(&root) },
+            "variants": [
+                { "id": "a", "epoch": 0, "nv": 15 },
+                { "id": "b", "epoch": 0, "nv": 15 },
+            ],
+        },
+        "apply_messages": [],
+        "postconditions": {
+            "state_tree": { "root_cid": CidJsonRef(&root) },
+            "receipts": [],
+        },
+    });
+
+    let dir = std::env::temp_dir().join(format!("fvm-conformance-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.json", name));
+    std::fs::write(&path, serde_json::to_vec(&vector).unwrap()).unwrap();
+    (path, root)
+}
+
+#[test]
+fn parallel_loads_are_isolated() {
+    let (path_a, root_a) = write_vector("isolated-a", "a", json!({}));
+    let (path_b, root_b) = write_vector("isolated-b", "b", json!({}));
+
+    let (a, b) = rayon::join(|| load_vector(&path_a), || load_vector(&path_b));
+    let bs_a = a.unwrap().blockstore.expect("vector a is supported");
+    let bs_b = b.unwrap().blockstore.expect("vector b is supported");
+
+    assert_eq!(bs_a.has_many(&[root_a, root_b]).unwrap(), [true, false]);
+    assert_eq!(bs_b.has_many(&[root_a, root_b]).unwrap(), [false, true]);
+}
+
+#[test]
+fn unsupported_vectors_are_skipped() {
+    let unsupported = json!({ "chaos_actor": "true" });
+    let paths = vec![
+        write_vector("skipped-a", "a", unsupported.clone()).0,
+        write_vector("skipped-b", "b", unsupported).0,
+    ];
+
+    let mut reported = 0;
+    let summary = run_vectors(paths, &RunnerOptions::default().jobs(2), |_| reported += 1).unwrap();
+    assert_eq!(
+        summary,
+        Summary {
+            skipped: 4,
+            ..Default::default()
+        }
+    );
+    assert_eq!(reported, 4);
+}
@@ -0,0 +1,88 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use fil_hello_world_actor::WASM_BINARY as HELLO_BINARY;
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, IntegrationExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use num_traits::Zero;
+
+#[path = "../tests/bundles/mod.rs"]
+mod bundles;
+use bundles::*;
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, Default)]
+struct State {
+    count: u64,
+}
+
+/// Deploys the hello world actor, returning the executor, the sender, and the actor's address.
+fn setup(
+    pooling: bool,
+) -> (
+    IntegrationExecutor<MemoryBlockstore, DummyExterns>,
+    Address,
+    Address,
+) {
+    // Pooling needs the memory and table limits that apply from nv17.
+    let mut tester = new_tester(
+        NetworkVersion::V17,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+    let state_cid = tester.set_state(&State::default()).unwrap();
+
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            HELLO_BINARY.unwrap(),
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    tester
+        .instantiate_machine_with_config(DummyExterns, |ec| ec.pooling = pooling)
+        .unwrap();
+
+    (tester.executor.unwrap(), sender[0].1, actor_address)
+}
+
+/// Sends messages to an actor, measuring the cost of instantiating it with and without instance
+/// pooling.
+fn bench_instantiation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("instantiation");
+    for (name, pooling) in [("on-demand", false), ("pooling", true)] {
+        let (mut executor, sender, actor) = setup(pooling);
+        let mut sequence = 0;
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let message = Message {
+                    from: sender,
+                    to: actor,
+                    sequence,
+                    gas_limit: 1000000000,
+                    method_num: 1,
+                    ..Message::default()
+                };
+                sequence += 1;
+                executor
+                    .execute_message(message, ApplyKind::Explicit, 100)
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_instantiation);
+criterion_main!(benches);
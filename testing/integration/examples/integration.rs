@@ -30,7 +30,7 @@ pub fn main() {
     // Instantiate tester
     let bs = MemoryBlockstore::default();
     let bundle_root = bundle::import_bundle(&bs, actors_v10::BUNDLE_CAR).unwrap();
-    let mut tester =
+    let mut tester: Tester<_, _> =
         Tester::new(NetworkVersion::V15, StateTreeVersion::V4, bundle_root, bs).unwrap();
 
     let sender: [Account; 1] = tester.create_accounts().unwrap();
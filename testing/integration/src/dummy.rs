@@ -1,39 +1,38 @@
-use fvm::externs::{Consensus, Externs, Rand};
+use fvm::externs::{Consensus, ExternError, Externs, Rand};
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::randomness::Randomness;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
+#[derive(Clone)]
 pub struct DummyExterns;
 
 impl Externs for DummyExterns {}
 
 impl Rand for DummyExterns {
-    fn get_chain_randomness(
-        &self,
-        _pers: i64,
-        _round: fvm_shared::clock::ChainEpoch,
-        _entropy: &[u8],
-    ) -> anyhow::Result<[u8; 32]> {
+    fn get_chain_randomness(&self, _round: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
         let rng: String = thread_rng()
             .sample_iter(&Alphanumeric)
             .take(32)
             .map(char::from)
             .collect();
 
-        Ok(<[u8; 32]>::try_from(rng.into_bytes()).unwrap())
+        Ok((
+            Randomness(<[u8; 32]>::try_from(rng.into_bytes()).unwrap()),
+            0,
+        ))
     }
 
-    fn get_beacon_randomness(
-        &self,
-        _pers: i64,
-        _round: fvm_shared::clock::ChainEpoch,
-        _entropy: &[u8],
-    ) -> anyhow::Result<[u8; 32]> {
+    fn get_beacon_randomness(&self, _round: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
         let rng: String = thread_rng()
             .sample_iter(&Alphanumeric)
             .take(32)
             .map(char::from)
             .collect();
 
-        Ok(<[u8; 32]>::try_from(rng.into_bytes()).unwrap())
+        Ok((
+            Randomness(<[u8; 32]>::try_from(rng.into_bytes()).unwrap()),
+            0,
+        ))
     }
 }
 
@@ -43,7 +42,7 @@ impl Consensus for DummyExterns {
         _h1: &[u8],
         _h2: &[u8],
         _extra: &[u8],
-    ) -> anyhow::Result<(Option<fvm_shared::consensus::ConsensusFault>, i64)> {
+    ) -> Result<(Option<fvm_shared::consensus::ConsensusFault>, i64), ExternError> {
         Ok((None, 0))
     }
 }
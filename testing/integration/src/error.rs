@@ -13,4 +13,6 @@ pub(crate) enum Error {
     FailedToSetActor(String),
     #[error("failed to flush tree")]
     FailedToFlushTree,
+    #[error("failed to construct the machine (was the builtin-actors bundle imported into the blockstore?)")]
+    FailedToBootstrap,
 }
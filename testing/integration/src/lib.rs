@@ -2,4 +2,5 @@ mod builtin;
 pub mod bundle;
 pub mod dummy;
 pub mod error;
+pub mod scenarios;
 pub mod tester;
@@ -0,0 +1,227 @@
+//! Method numbers, parameters and state of the builtin actors driven by [`Scenario`], mirrored
+//! from the v10 actors bundle the integration tests run against. Only the types the scenarios
+//! need are mirrored, and they must be kept in sync with the bundle by hand.
+//!
+//! [`Scenario`]: super::Scenario
+
+use cid::Cid;
+use fvm_ipld_bitfield::BitField;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::BytesDe;
+use fvm_shared::address::Address;
+use fvm_shared::bigint::bigint_ser;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::deal::DealID;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::randomness::Randomness;
+use fvm_shared::sector::{
+    PoStProof, RegisteredPoStProof, RegisteredSealProof, SectorNumber, StoragePower,
+};
+use fvm_shared::smooth::FilterEstimate;
+use fvm_shared::MethodNum;
+
+pub const METHOD_CONSTRUCTOR: MethodNum = 1;
+
+pub const CRON_EPOCH_TICK: MethodNum = 2;
+
+pub const POWER_CREATE_MINER: MethodNum = 2;
+pub const POWER_ON_EPOCH_TICK_END: MethodNum = 5;
+
+pub const MARKET_CRON_TICK: MethodNum = 9;
+
+pub const MINER_SUBMIT_WINDOWED_POST: MethodNum = 5;
+pub const MINER_PROVE_COMMIT_SECTOR: MethodNum = 7;
+pub const MINER_TERMINATE_SECTORS: MethodNum = 9;
+pub const MINER_DECLARE_FAULTS: MethodNum = 10;
+pub const MINER_DECLARE_FAULTS_RECOVERED: MethodNum = 11;
+pub const MINER_PRE_COMMIT_SECTOR_BATCH: MethodNum = 25;
+
+/// Epochs between a sector's pre-commitment and the earliest epoch it can be proven in.
+pub const PRE_COMMIT_CHALLENGE_DELAY: i64 = 150;
+/// Epochs in a proving period.
+pub const WPOST_PROVING_PERIOD: i64 = 2880;
+/// Epochs in each deadline's challenge window.
+pub const WPOST_CHALLENGE_WINDOW: i64 = 60;
+/// Deadlines in a proving period.
+pub const WPOST_PERIOD_DEADLINES: u64 = 48;
+/// Epochs before a deadline's challenge window opens after which faults and recoveries can no
+/// longer be declared for it.
+pub const FAULT_DECLARATION_CUTOFF: i64 = 70;
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct CronEntry {
+    pub receiver: Address,
+    pub method_num: MethodNum,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct CronConstructorParams {
+    pub entries: Vec<CronEntry>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct CreateMinerParams {
+    pub owner: Address,
+    pub worker: Address,
+    pub window_post_proof_type: RegisteredPoStProof,
+    #[serde(with = "fvm_ipld_encoding::serde_bytes")]
+    pub peer: Vec<u8>,
+    pub multiaddrs: Vec<BytesDe>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct CreateMinerReturn {
+    pub id_address: Address,
+    pub robust_address: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct PowerState {
+    #[serde(with = "bigint_ser")]
+    pub total_raw_byte_power: StoragePower,
+    #[serde(with = "bigint_ser")]
+    pub total_bytes_committed: StoragePower,
+    #[serde(with = "bigint_ser")]
+    pub total_quality_adj_power: StoragePower,
+    #[serde(with = "bigint_ser")]
+    pub total_qa_bytes_committed: StoragePower,
+    pub total_pledge_collateral: TokenAmount,
+    #[serde(with = "bigint_ser")]
+    pub this_epoch_raw_byte_power: StoragePower,
+    #[serde(with = "bigint_ser")]
+    pub this_epoch_quality_adj_power: StoragePower,
+    pub this_epoch_pledge_collateral: TokenAmount,
+    pub this_epoch_qa_power_smoothed: FilterEstimate,
+    pub miner_count: i64,
+    pub miner_above_min_power_count: i64,
+    pub cron_event_queue: Cid,
+    pub first_cron_epoch: ChainEpoch,
+    pub claims: Cid,
+    pub proof_validation_batch: Option<Cid>,
+}
+
+/// A miner's power, as claimed in the power actor.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, PartialEq, Eq)]
+pub struct Claim {
+    pub window_post_proof_type: RegisteredPoStProof,
+    #[serde(with = "bigint_ser")]
+    pub raw_byte_power: StoragePower,
+    #[serde(with = "bigint_ser")]
+    pub quality_adj_power: StoragePower,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct PreCommitSectorParams {
+    pub seal_proof: RegisteredSealProof,
+    pub sector_number: SectorNumber,
+    pub sealed_cid: Cid,
+    pub seal_rand_epoch: ChainEpoch,
+    pub deal_ids: Vec<DealID>,
+    pub expiration: ChainEpoch,
+    pub replace_capacity: bool,
+    pub replace_sector_deadline: u64,
+    pub replace_sector_partition: u64,
+    pub replace_sector_number: SectorNumber,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct PreCommitSectorBatchParams {
+    pub sectors: Vec<PreCommitSectorParams>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct ProveCommitSectorParams {
+    pub sector_number: SectorNumber,
+    #[serde(with = "fvm_ipld_encoding::serde_bytes")]
+    pub proof: Vec<u8>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct PoStPartition {
+    pub index: u64,
+    pub skipped: BitField,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct SubmitWindowedPoStParams {
+    pub deadline: u64,
+    pub partitions: Vec<PoStPartition>,
+    pub proofs: Vec<PoStProof>,
+    pub chain_commit_epoch: ChainEpoch,
+    pub chain_commit_rand: Randomness,
+}
+
+/// Sectors in a single partition, as declared faulty, recovered or terminated.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct PartitionSectors {
+    pub deadline: u64,
+    pub partition: u64,
+    pub sectors: BitField,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct DeclareFaultsParams {
+    pub faults: Vec<PartitionSectors>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct DeclareFaultsRecoveredParams {
+    pub recoveries: Vec<PartitionSectors>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct TerminateSectorsParams {
+    pub terminations: Vec<PartitionSectors>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct TerminateSectorsReturn {
+    pub done: bool,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct MinerState {
+    pub info: Cid,
+    pub pre_commit_deposits: TokenAmount,
+    pub locked_funds: TokenAmount,
+    pub vesting_funds: Cid,
+    pub fee_debt: TokenAmount,
+    pub initial_pledge: TokenAmount,
+    pub pre_committed_sectors: Cid,
+    pub pre_committed_sectors_cleanup: Cid,
+    pub allocated_sectors: Cid,
+    pub sectors: Cid,
+    pub proving_period_start: ChainEpoch,
+    pub current_deadline: u64,
+    pub deadlines: Cid,
+    pub early_terminations: BitField,
+    pub deadline_cron_active: bool,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct Deadlines {
+    pub due: Vec<Cid>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PowerPair {
+    #[serde(with = "bigint_ser")]
+    pub raw: StoragePower,
+    #[serde(with = "bigint_ser")]
+    pub qa: StoragePower,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
+pub struct Deadline {
+    pub partitions: Cid,
+    pub expirations_epochs: Cid,
+    pub partitions_posted: BitField,
+    pub early_terminations: BitField,
+    pub live_sectors: u64,
+    pub total_sectors: u64,
+    pub faulty_power: PowerPair,
+    pub optimistic_post_submissions: Cid,
+    pub sectors_snapshot: Cid,
+    pub partitions_snapshot: Cid,
+    pub optimistic_post_submissions_snapshot: Cid,
+}
@@ -0,0 +1,679 @@
+//! Drives storage miners through their lifecycle (creation, sector pre-commitment and
+//! prove-commitment, Window PoSt, faults, recoveries and termination) against the real builtin
+//! actors, advancing the chain epoch by epoch with cron, and reads the resulting actor state
+//! directly from the blockstore.
+//!
+//! Proofs are only checked structurally, so tests using these scenarios must be built with the
+//! `mock-proofs` feature (which enables `fvm/mock-proofs`).
+
+use anyhow::{anyhow, bail, Result};
+use cid::Cid;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::externs::{Consensus, ExternError, Externs, Rand};
+use fvm::machine::{Machine, Manifest};
+use fvm::state_tree::ActorState;
+use fvm_ipld_bitfield::BitField;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{CborStore, RawBytes};
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::bigint_ser::BigIntSer;
+use fvm_shared::bigint::Zero;
+use fvm_shared::builtin::singletons::{
+    BURNT_FUNDS_ACTOR_ADDR, BURNT_FUNDS_ACTOR_ID, CRON_ACTOR_ADDR, CRON_ACTOR_ID,
+    REWARD_ACTOR_ADDR, REWARD_ACTOR_ID, STORAGE_MARKET_ACTOR_ADDR, STORAGE_MARKET_ACTOR_ID,
+    STORAGE_POWER_ACTOR_ADDR, STORAGE_POWER_ACTOR_ID, SYSTEM_ACTOR_ADDR,
+};
+use fvm_shared::clock::{ChainEpoch, EPOCH_DURATION_SECONDS};
+use fvm_shared::commcid::replica_commitment_v1_to_cid;
+use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::randomness::{draw_randomness, DomainSeparationTag, Randomness};
+use fvm_shared::sector::{PoStProof, RegisteredSealProof, SectorNumber, StoragePower};
+use fvm_shared::{ActorID, MethodNum, BLOCK_GAS_LIMIT};
+use libsecp256k1::SecretKey;
+use multihash::Code;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::tester::{Account, IntegrationExecutor, Tester};
+
+pub mod actors;
+
+use actors::*;
+
+/// Gas limit of the implicit messages sent by the system actor (constructors and cron).
+const IMPLICIT_GAS_LIMIT: i64 = BLOCK_GAS_LIMIT * 10000;
+
+/// Size of the proofs submitted by miners. Proofs are only checked structurally, so any
+/// non-empty proof that isn't too large for its type is accepted.
+const SEAL_PROOF_SIZE: usize = 1920;
+const POST_PROOF_SIZE: usize = 192;
+
+/// Epochs between a sector's pre-commitment and its expiration. This must be within the bounds
+/// enforced by the miner actor, accounting for the maximum prove-commit delay.
+const SECTOR_LIFETIME: i64 = 700_000;
+
+/// Externs returning deterministic randomness derived from the requested epoch, so that tests can
+/// compute the randomness actors draw (see [`chain_randomness`]).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ScenarioExterns;
+
+impl Externs for ScenarioExterns {}
+
+impl Rand for ScenarioExterns {
+    fn get_chain_randomness(&self, round: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
+        Ok((base_randomness(0xcc, round), 0))
+    }
+
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
+        Ok((base_randomness(0xbb, round), 0))
+    }
+}
+
+impl Consensus for ScenarioExterns {
+    fn verify_consensus_fault(
+        &self,
+        _h1: &[u8],
+        _h2: &[u8],
+        _extra: &[u8],
+    ) -> Result<(Option<ConsensusFault>, i64), ExternError> {
+        Ok((None, 0))
+    }
+}
+
+fn base_randomness(fill: u8, round: ChainEpoch) -> Randomness {
+    let mut base = Randomness([fill; 32]);
+    base.0[..8].copy_from_slice(&round.value().to_be_bytes());
+    base
+}
+
+/// Returns the chain randomness actors draw at the given epoch under [`ScenarioExterns`].
+pub fn chain_randomness(tag: DomainSeparationTag, epoch: ChainEpoch, entropy: &[u8]) -> Randomness {
+    draw_randomness(&base_randomness(0xcc, epoch), tag as i64, epoch, entropy)
+}
+
+/// A storage miner created by [`Scenario::create_miner`].
+#[derive(Clone, Debug)]
+pub struct Miner {
+    pub id: ActorID,
+    /// The miner's owner, also used as its worker.
+    pub owner: Account,
+    pub seal_proof: RegisteredSealProof,
+    /// The number assigned to the next sector pre-committed by [`Scenario::precommit_sectors`].
+    pub next_sector: SectorNumber,
+}
+
+impl Miner {
+    pub fn address(&self) -> Address {
+        Address::new_id(self.id)
+    }
+
+    /// Returns the raw byte power of the given number of sectors of this miner.
+    pub fn sector_power(&self, sectors: u64) -> u64 {
+        let size = self.seal_proof.sector_size().expect("invalid seal proof") as u64;
+        size * sectors
+    }
+}
+
+/// The location of a partition holding a miner's sectors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SectorLocation {
+    pub deadline: u64,
+    pub partition: u64,
+}
+
+/// Drives builtin actors through storage mining scenarios.
+///
+/// Create the scenario with a [`Tester`] whose machine hasn't been instantiated yet: the power,
+/// market, reward and cron actors are deployed and constructed on top of it, and the machine is
+/// then instantiated at epoch 0. The verified registry and datacap actors aren't deployed, so
+/// sectors can't hold verified deals.
+pub struct Scenario<B: Blockstore + 'static> {
+    pub tester: Tester<B, ScenarioExterns>,
+}
+
+impl<B: Blockstore + 'static> Scenario<B> {
+    /// Deploys the singleton actors and creates `N` accounts, each with the given balance.
+    pub fn new<const N: usize>(
+        mut tester: Tester<B, ScenarioExterns>,
+        balance: TokenAmount,
+    ) -> Result<(Self, [Account; N])> {
+        use rand::SeedableRng;
+
+        let builtin_actors = *tester.builtin_actors();
+
+        let state_tree = tester
+            .state_tree
+            .as_mut()
+            .ok_or_else(|| anyhow!("machine has already been instantiated"))?;
+        let (version, data): (u32, Cid) = state_tree
+            .store()
+            .get_cbor(&builtin_actors)?
+            .ok_or_else(|| anyhow!("missing builtin actors manifest"))?;
+        let manifest = Manifest::load(state_tree.store(), &data, version)?;
+        let code = |name: &str| {
+            manifest
+                .code_by_name(name)
+                .copied()
+                .ok_or_else(|| anyhow!("no {name} actor in the manifest"))
+        };
+
+        let empty_array = state_tree
+            .store()
+            .put_cbor(&Vec::<()>::new(), Code::Blake2b256)?;
+        debug_assert_eq!(empty_array, *fvm::EMPTY_ARR_CID);
+
+        // The constructors called below replace this placeholder state.
+        for (id, name) in [
+            (REWARD_ACTOR_ID, "reward"),
+            (CRON_ACTOR_ID, "cron"),
+            (STORAGE_POWER_ACTOR_ID, "storagepower"),
+            (STORAGE_MARKET_ACTOR_ID, "storagemarket"),
+        ] {
+            let actor = ActorState::new(code(name)?, empty_array, TokenAmount::zero(), 0);
+            state_tree
+                .set_actor_id(id, actor)
+                .map_err(anyhow::Error::from)?;
+        }
+
+        let burnt_funds = fvm::account_actor::State {
+            address: BURNT_FUNDS_ACTOR_ADDR,
+        };
+        let burnt_funds = state_tree
+            .store()
+            .put_cbor(&burnt_funds, Code::Blake2b256)?;
+        state_tree
+            .set_actor_id(
+                BURNT_FUNDS_ACTOR_ID,
+                ActorState::new(code("account")?, burnt_funds, TokenAmount::zero(), 0),
+            )
+            .map_err(anyhow::Error::from)?;
+
+        let rng = &mut rand_chacha::ChaCha8Rng::seed_from_u64(8);
+        let mut accounts: [Account; N] = [(0, Address::default()); N];
+        for account in accounts.iter_mut() {
+            *account = tester.make_secp256k1_account(SecretKey::random(rng), balance.clone())?;
+        }
+
+        tester.instantiate_machine(ScenarioExterns)?;
+
+        let mut scenario = Scenario { tester };
+        scenario.implicit(
+            REWARD_ACTOR_ADDR,
+            METHOD_CONSTRUCTOR,
+            RawBytes::serialize(Some(BigIntSer(&Zero::zero())))?,
+        )?;
+        scenario.implicit(
+            STORAGE_POWER_ACTOR_ADDR,
+            METHOD_CONSTRUCTOR,
+            RawBytes::default(),
+        )?;
+        scenario.implicit(
+            STORAGE_MARKET_ACTOR_ADDR,
+            METHOD_CONSTRUCTOR,
+            RawBytes::default(),
+        )?;
+        scenario.implicit(
+            CRON_ACTOR_ADDR,
+            METHOD_CONSTRUCTOR,
+            RawBytes::serialize(CronConstructorParams {
+                entries: vec![
+                    CronEntry {
+                        receiver: STORAGE_POWER_ACTOR_ADDR,
+                        method_num: POWER_ON_EPOCH_TICK_END,
+                    },
+                    CronEntry {
+                        receiver: STORAGE_MARKET_ACTOR_ADDR,
+                        method_num: MARKET_CRON_TICK,
+                    },
+                ],
+            })?,
+        )?;
+
+        Ok((scenario, accounts))
+    }
+
+    /// Returns the current epoch.
+    pub fn epoch(&self) -> ChainEpoch {
+        self.tester.epoch()
+    }
+
+    /// Runs cron at the current epoch, then moves to the next one, until `epoch` is reached. Cron
+    /// hasn't run at `epoch` when this returns, so messages sent next are executed before it, as
+    /// they would be on chain.
+    pub fn advance_to_epoch_with_cron(&mut self, epoch: ChainEpoch) -> Result<()> {
+        if epoch < self.epoch() {
+            bail!("cannot go back from epoch {} to {}", self.epoch(), epoch);
+        }
+        while self.epoch() < epoch {
+            self.implicit(CRON_ACTOR_ADDR, CRON_EPOCH_TICK, RawBytes::default())?;
+            let next = self.epoch() + 1;
+            let timestamp = self.tester.timestamp() + EPOCH_DURATION_SECONDS as u64;
+            self.tester.next_tipset(ScenarioExterns, next, timestamp)?;
+        }
+        Ok(())
+    }
+
+    /// Creates a miner owned (and worked) by the given account, funding it with `value`.
+    pub fn create_miner(
+        &mut self,
+        owner: Account,
+        seal_proof: RegisteredSealProof,
+        value: TokenAmount,
+    ) -> Result<Miner> {
+        let params = CreateMinerParams {
+            owner: owner.1,
+            worker: owner.1,
+            window_post_proof_type: seal_proof
+                .registered_window_post_proof()
+                .map_err(|e| anyhow!(e))?,
+            peer: vec![],
+            multiaddrs: vec![],
+        };
+        let ret: CreateMinerReturn = self
+            .call(
+                owner,
+                STORAGE_POWER_ACTOR_ADDR,
+                POWER_CREATE_MINER,
+                &params,
+                value,
+            )?
+            .deserialize()?;
+        Ok(Miner {
+            id: ret.id_address.id()?,
+            owner,
+            seal_proof,
+            next_sector: 0,
+        })
+    }
+
+    /// Pre-commits `count` committed capacity sectors at the current epoch, returning their
+    /// numbers.
+    pub fn precommit_sectors(
+        &mut self,
+        miner: &mut Miner,
+        count: u64,
+    ) -> Result<Vec<SectorNumber>> {
+        let epoch = self.epoch();
+        let sectors: Vec<_> = (miner.next_sector..miner.next_sector + count).collect();
+        let params = PreCommitSectorBatchParams {
+            sectors: sectors
+                .iter()
+                .map(|&sector_number| {
+                    Ok(PreCommitSectorParams {
+                        seal_proof: miner.seal_proof,
+                        sector_number,
+                        sealed_cid: sealed_cid(miner.id, sector_number)?,
+                        seal_rand_epoch: epoch - 1,
+                        deal_ids: vec![],
+                        expiration: epoch + SECTOR_LIFETIME,
+                        replace_capacity: false,
+                        replace_sector_deadline: 0,
+                        replace_sector_partition: 0,
+                        replace_sector_number: 0,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        };
+        self.call(
+            miner.owner,
+            miner.address(),
+            MINER_PRE_COMMIT_SECTOR_BATCH,
+            &params,
+            TokenAmount::zero(),
+        )?;
+        miner.next_sector += count;
+        Ok(sectors)
+    }
+
+    /// Submits prove-commitments for the given pre-committed sectors. They're verified, and the
+    /// sectors activated, when cron runs at the current epoch. Sectors only gain power once
+    /// they've been proven by a Window PoSt.
+    pub fn prove_commit_sectors(&mut self, miner: &Miner, sectors: &[SectorNumber]) -> Result<()> {
+        for &sector_number in sectors {
+            let params = ProveCommitSectorParams {
+                sector_number,
+                proof: vec![1; SEAL_PROOF_SIZE],
+            };
+            self.call(
+                miner.owner,
+                miner.address(),
+                MINER_PROVE_COMMIT_SECTOR,
+                &params,
+                TokenAmount::zero(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns the epoch at which the next challenge window of the given deadline opens, or the
+    /// epoch at which the current one opened if it's still open.
+    pub fn deadline_open(&self, miner: &Miner, deadline: u64) -> Result<ChainEpoch> {
+        let offset = self.miner_state(miner)?.proving_period_start.value();
+        let epoch = self.epoch();
+        let period_start = epoch - (epoch.value() - offset).rem_euclid(WPOST_PROVING_PERIOD);
+        let open = period_start + deadline as i64 * WPOST_CHALLENGE_WINDOW;
+        if epoch >= open + WPOST_CHALLENGE_WINDOW {
+            Ok(open + WPOST_PROVING_PERIOD)
+        } else {
+            Ok(open)
+        }
+    }
+
+    /// Advances to the next challenge window of the given partition's deadline (if it isn't
+    /// already open), and submits a Window PoSt for the partition.
+    pub fn submit_post(&mut self, miner: &Miner, location: SectorLocation) -> Result<()> {
+        let open = self.deadline_open(miner, location.deadline)?;
+        self.advance_to_epoch_with_cron(open.max(self.epoch()))?;
+
+        let chain_commit_epoch = self.epoch() - 1;
+        let params = SubmitWindowedPoStParams {
+            deadline: location.deadline,
+            partitions: vec![PoStPartition {
+                index: location.partition,
+                skipped: BitField::new(),
+            }],
+            proofs: vec![PoStProof {
+                post_proof: miner
+                    .seal_proof
+                    .registered_window_post_proof()
+                    .map_err(|e| anyhow!(e))?,
+                proof_bytes: vec![1; POST_PROOF_SIZE],
+            }],
+            chain_commit_epoch,
+            chain_commit_rand: chain_randomness(
+                DomainSeparationTag::PoStChainCommit,
+                chain_commit_epoch,
+                &[],
+            ),
+        };
+        self.call(
+            miner.owner,
+            miner.address(),
+            MINER_SUBMIT_WINDOWED_POST,
+            &params,
+            TokenAmount::zero(),
+        )?;
+        Ok(())
+    }
+
+    /// Declares the given sectors faulty. The fault takes effect at the partition deadline's next
+    /// challenge window, so this must be called before that window's fault cutoff.
+    pub fn declare_faults(
+        &mut self,
+        miner: &Miner,
+        location: SectorLocation,
+        sectors: &[SectorNumber],
+    ) -> Result<()> {
+        let params = DeclareFaultsParams {
+            faults: vec![partition_sectors(location, sectors)?],
+        };
+        self.call(
+            miner.owner,
+            miner.address(),
+            MINER_DECLARE_FAULTS,
+            &params,
+            TokenAmount::zero(),
+        )?;
+        Ok(())
+    }
+
+    /// Declares the given faulty sectors recovered. They regain power once proven by the Window
+    /// PoSt submitted for the partition's deadline next.
+    pub fn declare_recoveries(
+        &mut self,
+        miner: &Miner,
+        location: SectorLocation,
+        sectors: &[SectorNumber],
+    ) -> Result<()> {
+        let params = DeclareFaultsRecoveredParams {
+            recoveries: vec![partition_sectors(location, sectors)?],
+        };
+        self.call(
+            miner.owner,
+            miner.address(),
+            MINER_DECLARE_FAULTS_RECOVERED,
+            &params,
+            TokenAmount::zero(),
+        )?;
+        Ok(())
+    }
+
+    /// Terminates the given sectors. The partition's deadline must not be the current or next
+    /// one.
+    pub fn terminate_sectors(
+        &mut self,
+        miner: &Miner,
+        location: SectorLocation,
+        sectors: &[SectorNumber],
+    ) -> Result<()> {
+        let params = TerminateSectorsParams {
+            terminations: vec![partition_sectors(location, sectors)?],
+        };
+        let ret: TerminateSectorsReturn = self
+            .call(
+                miner.owner,
+                miner.address(),
+                MINER_TERMINATE_SECTORS,
+                &params,
+                TokenAmount::zero(),
+            )?
+            .deserialize()?;
+        if !ret.done {
+            bail!("termination of sectors {sectors:?} was deferred to cron");
+        }
+        Ok(())
+    }
+
+    /// Returns the location of the single partition holding all of the miner's live sectors.
+    pub fn sector_location(&self, miner: &Miner) -> Result<SectorLocation> {
+        let st = self.miner_state(miner)?;
+        let deadlines: Deadlines = self.get_cbor(&st.deadlines)?;
+        let mut location = None;
+        for (index, cid) in deadlines.due.iter().enumerate() {
+            let deadline: Deadline = self.get_cbor(cid)?;
+            if deadline.live_sectors == 0 {
+                continue;
+            }
+            if location.is_some() {
+                bail!("miner {} has sectors in more than one deadline", miner.id);
+            }
+            location = Some(SectorLocation {
+                deadline: index as u64,
+                partition: 0,
+            });
+        }
+        location.ok_or_else(|| anyhow!("miner {} has no live sectors", miner.id))
+    }
+
+    /// Returns the balance of the given actor.
+    pub fn balance(&self, id: ActorID) -> Result<TokenAmount> {
+        Ok(self.actor(id)?.balance)
+    }
+
+    /// Returns the state of the power actor.
+    pub fn power_state(&self) -> Result<PowerState> {
+        self.actor_state(STORAGE_POWER_ACTOR_ID)
+    }
+
+    /// Returns the miner's power, as claimed in the power actor.
+    pub fn miner_power(&self, miner: &Miner) -> Result<Claim> {
+        let claims =
+            Hamt::<_, Claim>::load_with_bit_width(&self.power_state()?.claims, self.store(), 5)?;
+        claims
+            .get(&BytesKey(miner.address().to_bytes()))?
+            .cloned()
+            .ok_or_else(|| anyhow!("no power claim for miner {}", miner.id))
+    }
+
+    /// Returns the state of the given miner.
+    pub fn miner_state(&self, miner: &Miner) -> Result<MinerState> {
+        self.actor_state(miner.id)
+    }
+
+    /// Asserts that both the miner's raw byte and quality adjusted power are those of the given
+    /// number of sectors (the sectors hold no deals), and that the network's total power is the
+    /// miner's.
+    pub fn assert_power(&self, miner: &Miner, sectors: u64) -> Result<()> {
+        let expected = miner.sector_power(sectors);
+        let claim = self.miner_power(miner)?;
+        let expected = StoragePower::from(expected);
+        if claim.raw_byte_power != expected || claim.quality_adj_power != expected {
+            bail!(
+                "miner {} has power {:?} at epoch {}, expected {} bytes",
+                miner.id,
+                claim,
+                self.epoch(),
+                expected
+            );
+        }
+        let st = self.power_state()?;
+        if st.total_bytes_committed != claim.raw_byte_power {
+            bail!(
+                "network has {} bytes committed, expected {}",
+                st.total_bytes_committed,
+                claim.raw_byte_power
+            );
+        }
+        Ok(())
+    }
+
+    /// Asserts that the miner's initial pledge is the given amount, and that the network's total
+    /// pledge is the miner's.
+    pub fn assert_pledge(&self, miner: &Miner, expected: &TokenAmount) -> Result<()> {
+        let pledge = self.miner_state(miner)?.initial_pledge;
+        if &pledge != expected {
+            bail!(
+                "miner {} has pledged {} at epoch {}, expected {}",
+                miner.id,
+                pledge,
+                self.epoch(),
+                expected
+            );
+        }
+        let total = self.power_state()?.total_pledge_collateral;
+        if total != pledge {
+            bail!("network total pledge is {}, expected {}", total, pledge);
+        }
+        Ok(())
+    }
+
+    /// Sends an implicit message from the system actor, failing if it doesn't succeed.
+    fn implicit(&mut self, to: Address, method_num: MethodNum, params: RawBytes) -> Result<()> {
+        let message = Message {
+            from: SYSTEM_ACTOR_ADDR,
+            to,
+            method_num,
+            params,
+            gas_limit: IMPLICIT_GAS_LIMIT,
+            ..Message::default()
+        };
+        let ret = self
+            .executor()?
+            .execute_message(message, ApplyKind::Implicit, 100)?;
+        check_ret(&ret, to, method_num, self.epoch())
+    }
+
+    /// Sends a message from the given account, failing if it doesn't succeed, and returns the
+    /// return value.
+    fn call(
+        &mut self,
+        from: Account,
+        to: Address,
+        method_num: MethodNum,
+        params: &impl Serialize,
+        value: TokenAmount,
+    ) -> Result<RawBytes> {
+        let sequence = self.actor(from.0)?.sequence;
+        let message = Message {
+            from: from.1,
+            to,
+            sequence,
+            value,
+            method_num,
+            params: RawBytes::serialize(params)?,
+            gas_limit: BLOCK_GAS_LIMIT,
+            ..Message::default()
+        };
+        let epoch = self.epoch();
+        let ret = self
+            .executor()?
+            .execute_message(message, ApplyKind::Explicit, 100)?;
+        check_ret(&ret, to, method_num, epoch)?;
+        Ok(ret.msg_receipt.return_data)
+    }
+
+    fn executor(&mut self) -> Result<&mut IntegrationExecutor<B, ScenarioExterns>> {
+        self.tester
+            .executor
+            .as_mut()
+            .ok_or_else(|| anyhow!("machine has not been instantiated"))
+    }
+
+    fn store(&self) -> &impl Blockstore {
+        self.tester
+            .executor
+            .as_ref()
+            .expect("machine has not been instantiated")
+            .machine()
+            .expect("machine poisoned")
+            .blockstore()
+    }
+
+    fn get_cbor<T: DeserializeOwned>(&self, cid: &Cid) -> Result<T> {
+        self.store()
+            .get_cbor(cid)?
+            .ok_or_else(|| anyhow!("missing block {cid}"))
+    }
+
+    fn actor(&self, id: ActorID) -> Result<ActorState> {
+        self.tester
+            .executor
+            .as_ref()
+            .ok_or_else(|| anyhow!("machine has not been instantiated"))?
+            .machine()?
+            .state_tree()
+            .get_actor_id(id)
+            .map_err(anyhow::Error::from)?
+            .ok_or_else(|| anyhow!("actor {id} doesn't exist"))
+    }
+
+    fn actor_state<T: DeserializeOwned>(&self, id: ActorID) -> Result<T> {
+        self.get_cbor(&self.actor(id)?.state)
+    }
+}
+
+fn check_ret(ret: &ApplyRet, to: Address, method_num: MethodNum, epoch: ChainEpoch) -> Result<()> {
+    if ret.msg_receipt.exit_code != ExitCode::OK {
+        bail!(
+            "method {} on {} failed at epoch {} with {}: {:?}",
+            method_num,
+            to,
+            epoch,
+            ret.msg_receipt.exit_code,
+            ret.failure_info
+        );
+    }
+    Ok(())
+}
+
+fn sealed_cid(miner: ActorID, sector: SectorNumber) -> Result<Cid> {
+    let mut comm_r = [0xaa; 32];
+    comm_r[..8].copy_from_slice(&miner.to_be_bytes());
+    comm_r[8..16].copy_from_slice(&sector.to_be_bytes());
+    replica_commitment_v1_to_cid(&comm_r).map_err(|e| anyhow!(e))
+}
+
+fn partition_sectors(
+    location: SectorLocation,
+    sectors: &[SectorNumber],
+) -> Result<PartitionSectors> {
+    Ok(PartitionSectors {
+        deadline: location.deadline,
+        partition: location.partition,
+        sectors: BitField::try_from_bits(sectors.iter().copied())?,
+    })
+}
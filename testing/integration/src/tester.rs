@@ -1,14 +1,24 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
 use anyhow::{anyhow, Context, Result};
 use cid::Cid;
-use fvm::call_manager::DefaultCallManager;
-use fvm::executor::DefaultExecutor;
+use fvm::call_manager::{CallManager, DefaultCallManager};
+use fvm::executor::{DefaultExecutor, Executor, SenderPolicy};
 use fvm::externs::Externs;
-use fvm::machine::{DefaultMachine, Engine, Machine, NetworkConfig};
+use fvm::gas::GasMultiplier;
+use fvm::machine::{
+    CallInterceptor, DefaultMachine, Engine, EngineConfig, FeatureAvailability, Machine,
+    MachineContext, MachineError, MachineMetrics, ModuleCache, NetworkConfig,
+};
 use fvm::state_tree::{ActorState, StateTree};
-use fvm::{init_actor, system_actor, DefaultKernel};
+use fvm::{init_actor, system_actor, DefaultKernel, Kernel};
 use fvm_ipld_blockstore::{Block, Blockstore};
 use fvm_ipld_encoding::{ser, CborStore};
 use fvm_shared::address::Address;
+use fvm_shared::builtin::singletons::{INIT_ACTOR_ID, SYSTEM_ACTOR_ID};
+use fvm_shared::chainid::ChainID;
+use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::state::StateTreeVersion;
 use fvm_shared::version::NetworkVersion;
@@ -17,7 +27,7 @@ use libsecp256k1::{PublicKey, SecretKey};
 use multihash::Code;
 
 use crate::builtin::{fetch_builtin_code_cid, set_init_actor, set_sys_actor};
-use crate::error::Error::{FailedToFlushTree, NoManifestInformation};
+use crate::error::Error::{FailedToBootstrap, FailedToFlushTree, NoManifestInformation};
 
 const DEFAULT_BASE_FEE: u64 = 100;
 
@@ -28,7 +38,13 @@ pub type IntegrationExecutor<B, E> =
 
 pub type Account = (ActorID, Address);
 
-pub struct Tester<B: Blockstore + 'static, E: Externs + 'static> {
+/// Sets up a state tree and a Machine to execute messages against, with actors running on the
+/// kernel `K`.
+pub struct Tester<
+    B: Blockstore + 'static,
+    E: Externs + 'static,
+    K: Kernel = DefaultKernel<DefaultCallManager<DefaultMachine<B, E>>>,
+> {
     // Network version used in the test
     nv: NetworkVersion,
     // Builtin actors root Cid used in the Machine
@@ -38,15 +54,45 @@ pub struct Tester<B: Blockstore + 'static, E: Externs + 'static> {
     // Custom code cid deployed by developer
     code_cids: Vec<Cid>,
     // Executor used to interact with deployed actors.
-    pub executor: Option<IntegrationExecutor<B, E>>,
+    pub executor: Option<DefaultExecutor<K>>,
     // State tree constructed before instantiating the Machine
     pub state_tree: Option<StateTree<B>>,
+    // Whether execution traces are recorded by the Machine
+    tracing: bool,
+    // Actor allowed to install and upgrade actor code, in addition to the system actor
+    upgrade_admin: Option<ActorID>,
+    // Builtin actors manifests for specific network versions
+    builtin_actors_by_version: BTreeMap<NetworkVersion, Cid>,
+    // Receives metrics from the Machine
+    metrics: Option<Arc<dyn MachineMetrics>>,
+    // Whether params sent to builtin actors are validated before instantiating them
+    validate_builtin_params: bool,
+    // Intercepts sends made by the Machine
+    call_interceptor: Option<Arc<Mutex<dyn CallInterceptor>>>,
+    // Multipliers for the gas charged to actors with specific code CIDs
+    actor_gas_multipliers: HashMap<Cid, GasMultiplier>,
+    // The chain ID of the network the Machine runs on
+    chain_id: ChainID,
+    // Which actors may send explicit messages
+    sender_policy: SenderPolicy,
+    // Network versions from which syscalls and builtin methods are available, if not the default
+    feature_availability: Option<FeatureAvailability>,
+    // Maximum number of actors a message may create, if limited
+    max_actors_created: Option<u64>,
+    // Cache of compiled actor modules used by the engine, if any
+    module_cache: Option<Arc<dyn ModuleCache>>,
+    // Epoch of the tipset the Machine executes messages in
+    epoch: ChainEpoch,
+    // Timestamp of the tipset the Machine executes messages in
+    timestamp: u64,
 }
 
-impl<B, E> Tester<B, E>
+impl<B, E, K> Tester<B, E, K>
 where
     B: Blockstore,
     E: Externs,
+    K: Kernel,
+    K::CallManager: CallManager<Machine = DefaultMachine<B, E>>,
 {
     pub fn new(
         nv: NetworkVersion,
@@ -80,6 +126,20 @@ where
             code_cids: vec![],
             state_tree: Some(state_tree),
             accounts_code_cid,
+            tracing: false,
+            upgrade_admin: None,
+            metrics: None,
+            validate_builtin_params: false,
+            call_interceptor: None,
+            actor_gas_multipliers: HashMap::new(),
+            chain_id: ChainID::default(),
+            sender_policy: SenderPolicy::AccountsOnly,
+            feature_availability: None,
+            max_actors_created: None,
+            module_cache: None,
+            epoch: ChainEpoch::new(0),
+            timestamp: 0,
+            builtin_actors_by_version: BTreeMap::new(),
         })
     }
 
@@ -119,6 +179,24 @@ where
         state_cid: Cid,
         actor_address: Address,
         balance: TokenAmount,
+    ) -> Result<Cid> {
+        let code_cid =
+            self.set_actor_from_bin_unloaded(wasm_bin, state_cid, actor_address, balance)?;
+
+        // Add code cid to list of deployed contract
+        self.code_cids.push(code_cid);
+
+        Ok(code_cid)
+    }
+
+    /// Like [`Tester::set_actor_from_bin`], but the code isn't preloaded when the machine is
+    /// instantiated, so it's only loaded (and validated) when the actor is first invoked.
+    pub fn set_actor_from_bin_unloaded(
+        &mut self,
+        wasm_bin: &[u8],
+        state_cid: Cid,
+        actor_address: Address,
+        balance: TokenAmount,
     ) -> Result<Cid> {
         // Register actor address
         self.state_tree
@@ -130,9 +208,6 @@ where
         // Put the WASM code into the blockstore.
         let code_cid = put_wasm_code(self.state_tree.as_mut().unwrap().store(), wasm_bin)?;
 
-        // Add code cid to list of deployed contract
-        self.code_cids.push(code_cid);
-
         // Initialize actor state
         let actor_state = ActorState::new(code_cid, state_cid, balance, 1);
 
@@ -146,8 +221,156 @@ where
         Ok(code_cid)
     }
 
+    /// Records execution traces (including gas charges) in the [`ApplyRet`] of every message
+    /// applied once the machine is instantiated. Must be called before
+    /// [`Tester::instantiate_machine`].
+    ///
+    /// [`ApplyRet`]: fvm::executor::ApplyRet
+    pub fn enable_tracing(&mut self) {
+        self.tracing = true;
+    }
+
+    /// Allows the specified actor to upgrade actor code, as on a devnet. Must be called before
+    /// [`Tester::instantiate_machine`].
+    pub fn set_upgrade_admin(&mut self, actor_id: ActorID) {
+        self.upgrade_admin = Some(actor_id);
+    }
+
+    /// Records the Machine's metrics with the given [`MachineMetrics`]. Must be called before
+    /// [`Tester::instantiate_machine`].
+    pub fn set_metrics(&mut self, metrics: Arc<dyn MachineMetrics>) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Rejects malformed params sent to builtin actors before instantiating them (see
+    /// [`NetworkConfig::validate_builtin_params`]). Must be called before
+    /// [`Tester::instantiate_machine`].
+    pub fn enable_builtin_param_validation(&mut self) {
+        self.validate_builtin_params = true;
+    }
+
+    /// Intercepts sends with the given [`CallInterceptor`]. Must be called before
+    /// [`Tester::instantiate_machine`].
+    pub fn set_call_interceptor(&mut self, interceptor: Arc<Mutex<dyn CallInterceptor>>) {
+        self.call_interceptor = Some(interceptor);
+    }
+
+    /// Scales the gas charged while actors with the given code execute (see
+    /// [`PriceList::with_actor_multipliers`]). Must be called before
+    /// [`Tester::instantiate_machine`].
+    ///
+    /// [`PriceList::with_actor_multipliers`]: fvm::gas::PriceList::with_actor_multipliers
+    pub fn set_actor_gas_multiplier(&mut self, code: Cid, multiplier: GasMultiplier) {
+        self.actor_gas_multipliers.insert(code, multiplier);
+    }
+
+    /// Sets the chain ID of the network the Machine runs on. Must be called before
+    /// [`Tester::instantiate_machine`].
+    pub fn set_chain_id(&mut self, chain_id: ChainID) {
+        self.chain_id = chain_id;
+    }
+
+    /// Lets actors send explicit messages according to the given [`SenderPolicy`]. Must be called
+    /// before [`Tester::instantiate_machine`].
+    pub fn set_sender_policy(&mut self, policy: SenderPolicy) {
+        self.sender_policy = policy;
+    }
+
+    /// Gates syscalls and builtin methods according to the given [`FeatureAvailability`] instead
+    /// of the default one. Must be called before [`Tester::instantiate_machine`].
+    pub fn set_feature_availability(&mut self, availability: FeatureAvailability) {
+        self.feature_availability = Some(availability);
+    }
+
+    /// Limits the number of actors a single message may create. Must be called before
+    /// [`Tester::instantiate_machine`].
+    pub fn limit_actors_created(&mut self, max: u64) {
+        self.max_actors_created = Some(max);
+    }
+
+    /// Loads and stores compiled actor modules through the given [`ModuleCache`]. Must be called
+    /// before [`Tester::instantiate_machine`].
+    pub fn set_module_cache(&mut self, cache: Arc<dyn ModuleCache>) {
+        self.module_cache = Some(cache);
+    }
+
+    /// Uses the specified builtin actors manifest for the given network version, in addition to
+    /// the one the tester was created with. Must be called before [`Tester::instantiate_machine`].
+    pub fn override_actors_for(&mut self, nv: NetworkVersion, manifest: Cid) {
+        self.builtin_actors_by_version.insert(nv, manifest);
+    }
+
+    /// Returns the CID of the builtin actors manifest the tester was created with.
+    pub fn builtin_actors(&self) -> &Cid {
+        &self.builtin_actors
+    }
+
+    /// Sets the epoch and timestamp of the tipset in which the Machine executes messages. Must be
+    /// called before [`Tester::instantiate_machine`].
+    pub fn set_tipset(&mut self, epoch: ChainEpoch, timestamp: u64) {
+        self.epoch = epoch;
+        self.timestamp = timestamp;
+    }
+
+    /// Returns the epoch of the tipset in which the Machine executes messages.
+    pub fn epoch(&self) -> ChainEpoch {
+        self.epoch
+    }
+
+    /// Returns the timestamp of the tipset in which the Machine executes messages.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Flushes the current Machine's state, and instantiates a new Machine on top of it to execute
+    /// messages in the given tipset. The new Machine shares the current Machine's engine, so
+    /// actor code isn't compiled again.
+    pub fn next_tipset(&mut self, externs: E, epoch: ChainEpoch, timestamp: u64) -> Result<()> {
+        let mut executor = self
+            .executor
+            .take()
+            .ok_or_else(|| anyhow!("machine has not been instantiated"))?;
+        let state_root = executor.flush()?;
+        let engine = executor.machine()?.engine().clone();
+        let blockstore = executor
+            .into_machine()
+            .ok_or_else(|| anyhow!("executor has no machine"))?
+            .into_store();
+        self.state_tree =
+            Some(StateTree::new_from_root(blockstore, &state_root).map_err(anyhow::Error::from)?);
+
+        self.set_tipset(epoch, timestamp);
+        self.instantiate_machine_with_engine(externs, |_| Ok(engine))
+    }
+
     /// Sets the Machine and the Executor in our Tester structure.
     pub fn instantiate_machine(&mut self, externs: E) -> Result<()> {
+        self.instantiate_machine_with_config(externs, |_| ())
+    }
+
+    /// Like [`Tester::instantiate_machine`], but allows customizing the engine configuration (e.g.,
+    /// to disable instance pooling).
+    pub fn instantiate_machine_with_config(
+        &mut self,
+        externs: E,
+        configure_engine: impl FnOnce(&mut EngineConfig),
+    ) -> Result<()> {
+        let module_cache = self.module_cache.clone();
+        self.instantiate_machine_with_engine(externs, |mc| {
+            let mut engine_config: EngineConfig = (&mc.network).into();
+            configure_engine(&mut engine_config);
+            match module_cache {
+                Some(cache) => Engine::new_with_cache(engine_config, cache),
+                None => Engine::new_default(engine_config),
+            }
+        })
+    }
+
+    fn instantiate_machine_with_engine(
+        &mut self,
+        externs: E,
+        engine: impl FnOnce(&MachineContext) -> Result<Engine>,
+    ) -> Result<()> {
         // Take the state tree and leave None behind.
         let mut state_tree = self.state_tree.take().unwrap();
 
@@ -164,34 +387,68 @@ where
         nc.actor_debugging = true;
         nc.override_actors(self.builtin_actors);
         nc.enable_actor_debugging();
+        if let Some(admin) = self.upgrade_admin {
+            nc.set_upgrade_admin(admin);
+        }
+        for (nv, manifest) in &self.builtin_actors_by_version {
+            nc.override_actors_for(*nv, *manifest);
+        }
+        if let Some(metrics) = &self.metrics {
+            nc.set_metrics(metrics.clone());
+        }
+        if self.validate_builtin_params {
+            nc.enable_builtin_param_validation();
+        }
+        if let Some(interceptor) = &self.call_interceptor {
+            nc.set_call_interceptor(interceptor.clone());
+        }
+        if !self.actor_gas_multipliers.is_empty() {
+            let price_list = nc
+                .price_list
+                .with_actor_multipliers(self.actor_gas_multipliers.clone());
+            nc.override_price_list(price_list);
+        }
+        nc.chain_id(self.chain_id);
+        nc.set_sender_policy(self.sender_policy);
+        if let Some(availability) = &self.feature_availability {
+            nc.set_feature_availability(availability.clone());
+        }
+        if let Some(max) = self.max_actors_created {
+            nc.limit_actors_created(max);
+        }
 
-        let mut mc = nc.for_epoch(0, state_root);
+        let mut mc = nc.for_epoch(self.epoch, state_root);
         mc.set_base_fee(TokenAmount::from_atto(DEFAULT_BASE_FEE));
+        mc.set_timestamp(self.timestamp);
+        mc.enable_invariant_checks();
+        // Test trees only have the system and init actors.
+        mc.require_singletons([SYSTEM_ACTOR_ID, INIT_ACTOR_ID]);
+        if self.tracing {
+            mc.enable_tracing();
+        }
+
+        let machine =
+            DefaultMachine::new(&engine(&mc)?, &mc, blockstore, externs).map_err(|e| {
+                if e.is::<MachineError>() {
+                    e.context(FailedToBootstrap)
+                } else {
+                    e
+                }
+            })?;
 
-        let machine = DefaultMachine::new(
-            &Engine::new_default((&mc.network.clone()).into())?,
-            &mc,
-            blockstore,
-            externs,
-        )?;
-
-        let executor =
-            DefaultExecutor::<DefaultKernel<DefaultCallManager<DefaultMachine<B, E>>>>::new(
-                machine,
-            );
-        executor
+        machine
             .engine()
-            .preload(executor.blockstore(), &self.code_cids)?;
+            .preload(machine.blockstore(), &self.code_cids)?;
 
-        self.executor = Some(executor);
+        self.executor = Some(DefaultExecutor::<K>::new(machine));
 
         Ok(())
     }
 
     /// Get blockstore
     pub fn blockstore(&self) -> &dyn Blockstore {
-        if self.executor.is_some() {
-            self.executor.as_ref().unwrap().blockstore()
+        if let Some(executor) = &self.executor {
+            executor.machine().expect("machine poisoned").blockstore()
         } else {
             self.state_tree.as_ref().unwrap().store()
         }
@@ -222,6 +479,7 @@ where
             state: cid,
             sequence: 0,
             balance: init_balance,
+            delegated_address: None,
         };
 
         state_tree
@@ -1,18 +1,26 @@
 use std::collections::BTreeMap;
 
 use anyhow::Context;
+use fvm::executor::ApplyRet;
 use fvm::externs::Externs;
+use fvm::gas::Gas;
+use fvm::trace::ExecutionEvent;
 use fvm_integration_tests::bundle;
-use fvm_integration_tests::tester::Tester;
-use fvm_ipld_blockstore::Blockstore;
+use fvm_integration_tests::tester::{Account, Tester};
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
 use fvm_shared::state::StateTreeVersion;
 use fvm_shared::version::NetworkVersion;
+use fvm_shared::ActorID;
 use lazy_static::lazy_static;
+use serde::Serialize;
 
 lazy_static! {
     static ref BUNDLES: BTreeMap<NetworkVersion, &'static [u8]> = [
         (NetworkVersion::V15, actors_v10::BUNDLE_CAR),
         (NetworkVersion::V16, actors_v10::BUNDLE_CAR), // todo bad hack
+        (NetworkVersion::V17, actors_v10::BUNDLE_CAR), // todo bad hack
     ].into_iter().collect();
 }
 
@@ -27,3 +35,34 @@ pub fn new_tester<B: Blockstore, E: Externs>(
     let root = bundle::import_bundle(&blockstore, bundle)?;
     Tester::new(nv, stv, root, blockstore)
 }
+
+/// Creates a tester with a single account, and the given test actor deployed at each of the given
+/// IDs with the given balance and state. The machine isn't instantiated, so the tester can still be
+/// configured.
+#[allow(dead_code)] // Not every test crate uses test actors.
+pub fn new_tester_with_actor<E: Externs>(
+    nv: NetworkVersion,
+    wasm_bin: &[u8],
+    state: &impl Serialize,
+    actors: &[(ActorID, TokenAmount)],
+) -> anyhow::Result<(Tester<MemoryBlockstore, E>, Account)> {
+    let mut tester = new_tester(nv, StateTreeVersion::V4, MemoryBlockstore::default())?;
+    let [sender]: [Account; 1] = tester.create_accounts()?;
+    let state_cid = tester.set_state(state)?;
+    for (id, balance) in actors {
+        tester.set_actor_from_bin(wasm_bin, state_cid, Address::new_id(*id), balance.clone())?;
+    }
+    Ok((tester, sender))
+}
+
+/// Returns the gas charged under the given name in the execution trace, one entry per charge.
+#[allow(dead_code)] // Not every test crate inspects gas charges.
+pub fn charged(res: &ApplyRet, name: &str) -> Vec<Gas> {
+    res.exec_trace
+        .iter()
+        .filter_map(|ev| match ev {
+            ExecutionEvent::GasCharge(charge) if charge.name == name => Some(charge.total()),
+            _ => None,
+        })
+        .collect()
+}
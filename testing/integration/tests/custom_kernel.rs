@@ -0,0 +1,604 @@
+use cid::Cid;
+use fil_custom_syscall_actor::{METHOD_DOUBLE, WASM_BINARY};
+use fvm::call_manager::{CallManager, DefaultCallManager};
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::gas::{Gas, PriceList};
+use fvm::kernel::{
+    self, ActorOps, BlockId, BlockRegistry, BlockStat, CircSupplyOps, CryptoOps, DebugOps, GasOps,
+    IpldBlockOps, MessageOps, NetworkOps, RandomnessOps, Result, SelfOps, SendOps, SendResult,
+};
+use fvm::machine::DefaultMachine;
+use fvm::syscalls::{BindSyscall, Context, InvocationData};
+use fvm::{syscall_error, DefaultKernel, Kernel};
+use fvm_integration_tests::bundle;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, Tester};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::chainid::ChainID;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::crypto::signature::{
+    SignatureType, BLS_PUB_LEN, BLS_SIG_LEN, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE,
+};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::piece::PieceInfo;
+use fvm_shared::randomness::Randomness;
+use fvm_shared::sector::{
+    AggregateSealVerifyProofAndInfos, RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo,
+    WindowPoStVerifyInfo,
+};
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, MethodNum};
+use multihash::MultihashGeneric;
+use num_traits::Zero;
+use wabt::wat2wasm;
+use wasmtime::Linker;
+
+const ACTOR: u64 = 10000;
+const PANICKING_ACTOR: u64 = 10001;
+
+/// Invoking any actor with this method makes the [`ExampleKernel`] panic while it's being
+/// constructed, which loses the call manager along with the machine.
+const METHOD_POISON: MethodNum = 3;
+
+/// An actor calling `example::panic`.
+const PANICKING_ACTOR_WAT: &str = r#"
+(module
+  (import "example" "panic" (func $panic (result i32)))
+  (memory (export "memory") 1)
+  (func (export "invoke") (param $x i32) (result i32)
+    (drop (call $panic))
+    (i32.const 0)))
+"#;
+
+/// An example kernel, exposing an `example::double` syscall on top of the standard ones, which are
+/// all forwarded to the wrapped kernel.
+pub struct ExampleKernel<K>(K);
+
+/// Doubles `x`, failing with `IllegalArgument` on overflow.
+fn double(context: Context<'_, ExampleKernel<impl Kernel>>, x: u64) -> kernel::Result<u64> {
+    context.kernel.charge_gas("example_double", Gas::new(100))?;
+    x.checked_mul(2)
+        .ok_or_else(|| syscall_error!(IllegalArgument; "doubling {} overflows", x).into())
+}
+
+/// Panics, like a buggy syscall.
+fn panic_syscall(_: Context<'_, ExampleKernel<impl Kernel>>) -> kernel::Result<()> {
+    panic!("example syscall panicked")
+}
+
+impl<K: Kernel> Kernel for ExampleKernel<K> {
+    type CallManager = K::CallManager;
+
+    fn into_inner(self) -> (Self::CallManager, BlockRegistry)
+    where
+        Self: Sized,
+    {
+        self.0.into_inner()
+    }
+
+    fn commit_root(&mut self) -> Result<()> {
+        self.0.commit_root()
+    }
+
+    fn new(
+        mgr: Self::CallManager,
+        blocks: BlockRegistry,
+        caller: ActorID,
+        actor_id: ActorID,
+        method: MethodNum,
+        value_received: TokenAmount,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        if method == METHOD_POISON {
+            panic!("example kernel panicked");
+        }
+        ExampleKernel(K::new(
+            mgr,
+            blocks,
+            caller,
+            actor_id,
+            method,
+            value_received,
+        ))
+    }
+
+    fn machine(&self) -> &<Self::CallManager as CallManager>::Machine {
+        self.0.machine()
+    }
+
+    fn bind_extra_syscalls(linker: &mut Linker<InvocationData<Self>>) -> anyhow::Result<()> {
+        linker.bind("example", "double", double)?;
+        linker.bind("example", "panic", panic_syscall)?;
+        Ok(())
+    }
+}
+
+impl<K: Kernel> ActorOps for ExampleKernel<K> {
+    fn resolve_address(&mut self, address: &Address) -> Result<Option<ActorID>> {
+        self.0.resolve_address(address)
+    }
+
+    fn get_actor_code_cid(&mut self, id: ActorID) -> Result<Option<Cid>> {
+        self.0.get_actor_code_cid(id)
+    }
+
+    fn new_actor_address(&mut self) -> Result<Address> {
+        self.0.new_actor_address()
+    }
+
+    fn create_actor(&mut self, code_id: Cid, actor_id: ActorID) -> Result<()> {
+        self.0.create_actor(code_id, actor_id)
+    }
+
+    #[cfg(feature = "m2-native")]
+    fn install_actor(&mut self, code_id: Cid) -> Result<()> {
+        self.0.install_actor(code_id)
+    }
+
+    fn upgrade_actor(&mut self, actor_id: ActorID, new_code_cid: Cid) -> Result<()> {
+        self.0.upgrade_actor(actor_id, new_code_cid)
+    }
+
+    fn get_builtin_actor_type(&self, code_cid: &Cid) -> u32 {
+        self.0.get_builtin_actor_type(code_cid)
+    }
+
+    fn get_code_cid_for_type(&self, typ: u32) -> Result<Cid> {
+        self.0.get_code_cid_for_type(typ)
+    }
+
+    fn balance_of(&mut self, actor_id: ActorID) -> Result<TokenAmount> {
+        self.0.balance_of(actor_id)
+    }
+}
+
+impl<K: Kernel> IpldBlockOps for ExampleKernel<K> {
+    fn block_open(&mut self, cid: &Cid) -> Result<(BlockId, BlockStat)> {
+        self.0.block_open(cid)
+    }
+
+    fn block_create(&mut self, codec: u64, data: &[u8]) -> Result<BlockId> {
+        self.0.block_create(codec, data)
+    }
+
+    fn block_link(&mut self, id: BlockId, hash_fun: u64, hash_len: u32) -> Result<Cid> {
+        self.0.block_link(id, hash_fun, hash_len)
+    }
+
+    fn block_read(&mut self, id: BlockId, offset: u32, buf: &mut [u8]) -> Result<i32> {
+        self.0.block_read(id, offset, buf)
+    }
+
+    fn block_stat(&mut self, id: BlockId) -> Result<BlockStat> {
+        self.0.block_stat(id)
+    }
+}
+
+impl<K: Kernel> CircSupplyOps for ExampleKernel<K> {
+    fn total_fil_circ_supply(&self) -> Result<TokenAmount> {
+        self.0.total_fil_circ_supply()
+    }
+}
+
+impl<K: Kernel> CryptoOps for ExampleKernel<K> {
+    fn verify_signature(
+        &mut self,
+        sig_type: SignatureType,
+        signature: &[u8],
+        signer: &Address,
+        plaintext: &[u8],
+    ) -> Result<bool> {
+        self.0
+            .verify_signature(sig_type, signature, signer, plaintext)
+    }
+
+    fn verify_bls_aggregate(
+        &mut self,
+        aggregate_sig: &[u8; BLS_SIG_LEN],
+        pub_keys: &[[u8; BLS_PUB_LEN]],
+        plaintexts: &[&[u8]],
+    ) -> Result<bool> {
+        self.0
+            .verify_bls_aggregate(aggregate_sig, pub_keys, plaintexts)
+    }
+
+    fn recover_secp_public_key(
+        &mut self,
+        hash: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
+        signature: &[u8; SECP_SIG_LEN],
+    ) -> Result<[u8; SECP_PUB_LEN]> {
+        self.0.recover_secp_public_key(hash, signature)
+    }
+
+    fn hash(&mut self, code: u64, data: &[u8]) -> Result<MultihashGeneric<64>> {
+        self.0.hash(code, data)
+    }
+
+    fn compute_unsealed_sector_cid(
+        &mut self,
+        proof_type: RegisteredSealProof,
+        pieces: &[PieceInfo],
+    ) -> Result<Cid> {
+        self.0.compute_unsealed_sector_cid(proof_type, pieces)
+    }
+
+    fn verify_seal(&mut self, vi: &SealVerifyInfo) -> Result<bool> {
+        self.0.verify_seal(vi)
+    }
+
+    fn verify_post(&mut self, verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
+        self.0.verify_post(verify_info)
+    }
+
+    fn verify_consensus_fault(
+        &mut self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> Result<Option<ConsensusFault>> {
+        self.0.verify_consensus_fault(h1, h2, extra)
+    }
+
+    fn batch_verify_seals(&mut self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>> {
+        self.0.batch_verify_seals(vis)
+    }
+
+    fn verify_aggregate_seals(&mut self, agg: &AggregateSealVerifyProofAndInfos) -> Result<bool> {
+        self.0.verify_aggregate_seals(agg)
+    }
+
+    fn verify_replica_update(&mut self, replica: &ReplicaUpdateInfo) -> Result<bool> {
+        self.0.verify_replica_update(replica)
+    }
+}
+
+impl<K: Kernel> DebugOps for ExampleKernel<K> {
+    fn log(&self, msg: String) {
+        self.0.log(msg)
+    }
+
+    fn debug_enabled(&self) -> bool {
+        self.0.debug_enabled()
+    }
+
+    fn store_artifact(&self, name: &str, data: &[u8]) -> Result<()> {
+        self.0.store_artifact(name, data)
+    }
+}
+
+impl<K: Kernel> GasOps for ExampleKernel<K> {
+    fn gas_used(&self) -> Gas {
+        self.0.gas_used()
+    }
+
+    fn gas_available(&self) -> Gas {
+        self.0.gas_available()
+    }
+
+    fn charge_gas(&mut self, name: &str, compute: Gas) -> Result<()> {
+        self.0.charge_gas(name, compute)
+    }
+
+    fn price_list(&self) -> &PriceList {
+        self.0.price_list()
+    }
+}
+
+impl<K: Kernel> MessageOps for ExampleKernel<K> {
+    fn msg_caller(&self) -> ActorID {
+        self.0.msg_caller()
+    }
+
+    fn msg_origin(&self) -> (ActorID, &Address) {
+        self.0.msg_origin()
+    }
+
+    fn msg_receiver(&self) -> ActorID {
+        self.0.msg_receiver()
+    }
+
+    fn msg_method_number(&self) -> MethodNum {
+        self.0.msg_method_number()
+    }
+
+    fn msg_value_received(&self) -> TokenAmount {
+        self.0.msg_value_received()
+    }
+
+    fn msg_gas_premium(&self) -> TokenAmount {
+        self.0.msg_gas_premium()
+    }
+
+    fn msg_gas_limit(&self) -> u64 {
+        self.0.msg_gas_limit()
+    }
+}
+
+impl<K: Kernel> NetworkOps for ExampleKernel<K> {
+    fn network_epoch(&self) -> ChainEpoch {
+        self.0.network_epoch()
+    }
+
+    fn network_version(&self) -> NetworkVersion {
+        self.0.network_version()
+    }
+
+    fn network_base_fee(&self) -> &TokenAmount {
+        self.0.network_base_fee()
+    }
+
+    fn tipset_timestamp(&self) -> u64 {
+        self.0.tipset_timestamp()
+    }
+
+    fn tipset_cid(&self, epoch: i64) -> Result<Option<Cid>> {
+        self.0.tipset_cid(epoch)
+    }
+
+    fn chain_id(&self) -> ChainID {
+        self.0.chain_id()
+    }
+}
+
+impl<K: Kernel> RandomnessOps for ExampleKernel<K> {
+    fn get_randomness_from_tickets(
+        &mut self,
+        personalization: i64,
+        rand_epoch: ChainEpoch,
+        entropy: &[u8],
+    ) -> Result<Randomness> {
+        self.0
+            .get_randomness_from_tickets(personalization, rand_epoch, entropy)
+    }
+
+    fn get_randomness_from_beacon(
+        &mut self,
+        personalization: i64,
+        rand_epoch: ChainEpoch,
+        entropy: &[u8],
+    ) -> Result<Randomness> {
+        self.0
+            .get_randomness_from_beacon(personalization, rand_epoch, entropy)
+    }
+}
+
+impl<K: Kernel> SelfOps for ExampleKernel<K> {
+    fn root(&self) -> Result<Cid> {
+        self.0.root()
+    }
+
+    fn set_root(&mut self, root: Cid) -> Result<()> {
+        self.0.set_root(root)
+    }
+
+    fn current_balance(&self) -> Result<TokenAmount> {
+        self.0.current_balance()
+    }
+
+    fn self_destruct(&mut self, beneficiary: &Address) -> Result<()> {
+        self.0.self_destruct(beneficiary)
+    }
+}
+
+impl<K: Kernel> SendOps for ExampleKernel<K> {
+    fn send(
+        &mut self,
+        recipient: &Address,
+        method: u64,
+        params: BlockId,
+        value: &TokenAmount,
+    ) -> Result<SendResult> {
+        self.0.send(recipient, method, params, value)
+    }
+}
+
+type ExampleTester = Tester<
+    MemoryBlockstore,
+    DummyExterns,
+    ExampleKernel<
+        DefaultKernel<DefaultCallManager<DefaultMachine<MemoryBlockstore, DummyExterns>>>,
+    >,
+>;
+
+/// Sets up a tester running actors on the [`ExampleKernel`], with the custom syscall and
+/// panicking actors deployed, and instantiates its machine. The actors may only import the
+/// `example` syscalls if `allow_import` is set.
+fn instantiate_tester(allow_import: bool) -> (ExampleTester, [Account; 2]) {
+    let blockstore = MemoryBlockstore::default();
+    let root = bundle::import_bundle(&blockstore, actors_v10::BUNDLE_CAR).unwrap();
+    let mut tester: ExampleTester =
+        Tester::new(NetworkVersion::V16, StateTreeVersion::V4, root, blockstore).unwrap();
+
+    let accounts: [Account; 2] = tester.create_accounts().unwrap();
+    let state_cid = tester.set_state(&()).unwrap();
+    // Not preloaded, so that rejecting the import fails the message rather than instantiation.
+    tester
+        .set_actor_from_bin_unloaded(
+            WASM_BINARY.unwrap(),
+            state_cid,
+            Address::new_id(ACTOR),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester
+        .set_actor_from_bin_unloaded(
+            &wat2wasm(PANICKING_ACTOR_WAT).unwrap(),
+            state_cid,
+            Address::new_id(PANICKING_ACTOR),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester
+        .instantiate_machine_with_config(DummyExterns, |ec| {
+            if allow_import {
+                for name in ["double", "panic"] {
+                    ec.wasm_limits
+                        .extra_syscall_imports
+                        .push(("example".into(), name.into()));
+                }
+            }
+        })
+        .unwrap();
+    (tester, accounts)
+}
+
+fn execute(tester: &mut ExampleTester, message: Message) -> ApplyRet {
+    tester
+        .executor
+        .as_mut()
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+fn double_message(sender: &Account, sequence: u64, x: u64) -> Message {
+    Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR),
+        gas_limit: 1000000000,
+        method_num: METHOD_DOUBLE,
+        params: RawBytes::serialize(x).unwrap(),
+        sequence,
+        ..Message::default()
+    }
+}
+
+#[test]
+fn custom_syscall() {
+    let (mut tester, [sender, _]) = instantiate_tester(true);
+
+    let res = execute(&mut tester, double_message(&sender, 0, 21));
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    assert_eq!(
+        res.msg_receipt.return_data.deserialize::<u64>().unwrap(),
+        42
+    );
+
+    // Syscall errors are returned to the actor, like those of standard syscalls.
+    let res = execute(&mut tester, double_message(&sender, 1, u64::MAX));
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::USR_ILLEGAL_ARGUMENT);
+}
+
+#[test]
+fn custom_syscall_import_not_allowed() {
+    let (mut tester, [sender, _]) = instantiate_tester(false);
+
+    let res = execute(&mut tester, double_message(&sender, 0, 21));
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_ILLEGAL_INSTRUCTION);
+}
+
+// Builtin actors run unchanged on the custom kernel.
+#[test]
+fn custom_kernel_runs_builtin_actors() {
+    let (mut tester, [sender, receiver]) = instantiate_tester(true);
+
+    let res = execute(
+        &mut tester,
+        Message {
+            from: sender.1,
+            to: receiver.1,
+            value: TokenAmount::from_atto(100),
+            gas_limit: 1000000000,
+            ..Message::default()
+        },
+    );
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+
+    let executor = tester.executor.as_ref().unwrap();
+    let balance = |id| {
+        executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .get_actor_id(id)
+            .unwrap()
+            .unwrap()
+            .balance
+    };
+    // The gas fee cap is zero, so the sender is only charged the value sent.
+    assert_eq!(balance(sender.0), TokenAmount::from_atto(9900));
+    assert_eq!(balance(receiver.0), TokenAmount::from_atto(10100));
+}
+
+// A panic in a syscall fails the message with a fatal error, leaving the process and the machine
+// usable.
+#[test]
+fn panicking_syscall() {
+    let (mut tester, [sender, _]) = instantiate_tester(true);
+
+    let res = execute(
+        &mut tester,
+        Message {
+            from: sender.1,
+            to: Address::new_id(PANICKING_ACTOR),
+            gas_limit: 1000000000,
+            method_num: 2,
+            ..Message::default()
+        },
+    );
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_ASSERTION_FAILED);
+    let failure = res.failure_info.unwrap().to_string();
+    assert!(failure.contains("example syscall panicked"), "{}", failure);
+
+    let res = execute(&mut tester, double_message(&sender, 1, 21));
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+
+    let mut executor = tester.executor.take().unwrap();
+    executor.flush().unwrap();
+    assert!(executor.into_machine().is_some());
+}
+
+// A panic that loses the call manager fails the message, and leaves the executor returning errors
+// rather than panicking.
+#[test]
+fn poisoned_executor() {
+    let (mut tester, [sender, _]) = instantiate_tester(true);
+
+    let executor = tester.executor.as_mut().unwrap();
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(PANICKING_ACTOR),
+        gas_limit: 1000000000,
+        method_num: METHOD_POISON,
+        ..Message::default()
+    };
+    let err = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("example kernel panicked"),
+        "{:#}",
+        err
+    );
+
+    assert!(executor.machine().is_err());
+    assert!(executor.state_reader().is_err());
+    assert!(executor
+        .execute_message(double_message(&sender, 1, 21), ApplyKind::Explicit, 100)
+        .is_err());
+    assert!(executor.flush().is_err());
+    assert!(tester.executor.take().unwrap().into_machine().is_none());
+}
@@ -0,0 +1,94 @@
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{from_slice, RawBytes, DAG_CBOR};
+use fvm_sdk as sdk;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::ActorID;
+
+include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+
+/// Send `amount` to `peer`'s `METHOD_OBSERVE`, and return what the peer observed along with this
+/// actor's observations before and after the send.
+pub const METHOD_TRANSFER: u64 = 2;
+/// Return this actor's observed balances.
+pub const METHOD_OBSERVE: u64 = 3;
+
+/// An actor that doesn't exist.
+pub const MISSING_ACTOR: ActorID = 1 << 40;
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, PartialEq, Eq)]
+pub struct TransferParams {
+    pub peer: ActorID,
+    pub amount: TokenAmount,
+}
+
+/// Balances observed by an actor.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, PartialEq, Eq)]
+pub struct Observation {
+    /// As returned by `self::current_balance`.
+    pub current: TokenAmount,
+    /// The actor's own balance, as returned by `actor::balance_of`.
+    pub own: TokenAmount,
+    /// The caller's balance, as returned by `actor::balance_of`.
+    pub caller: TokenAmount,
+    /// The balance of an actor that doesn't exist, as returned by `actor::balance_of`.
+    pub missing: TokenAmount,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, PartialEq, Eq)]
+pub struct TransferReturn {
+    pub before: Observation,
+    pub peer: Observation,
+    pub after: Observation,
+}
+
+fn observe() -> Observation {
+    Observation {
+        current: sdk::sself::current_balance(),
+        own: sdk::actor::balance_of(sdk::message::receiver()),
+        caller: sdk::actor::balance_of(sdk::message::caller()),
+        missing: sdk::actor::balance_of(MISSING_ACTOR),
+    }
+}
+
+#[no_mangle]
+pub fn invoke(params: u32) -> u32 {
+    std::panic::set_hook(Box::new(|info| {
+        sdk::vm::abort(
+            ExitCode::USR_ASSERTION_FAILED.value(),
+            Some(&format!("{}", info)),
+        )
+    }));
+
+    let ret = match sdk::message::method_number() {
+        METHOD_TRANSFER => {
+            let params: TransferParams =
+                from_slice(&sdk::message::params_raw(params).unwrap().1).unwrap();
+            let before = observe();
+            let response = sdk::send::send(
+                &Address::new_id(params.peer),
+                METHOD_OBSERVE,
+                RawBytes::default(),
+                params.amount,
+            )
+            .unwrap();
+            assert!(response.exit_code.is_success(), "send failed");
+            let peer = response.return_data.unwrap().data.deserialize().unwrap();
+            let after = observe();
+            RawBytes::serialize(TransferReturn {
+                before,
+                peer,
+                after,
+            })
+            .unwrap()
+        }
+        METHOD_OBSERVE => RawBytes::serialize(observe()).unwrap(),
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unrecognized method"),
+        ),
+    };
+
+    sdk::ipld::put_block(DAG_CBOR, ret.bytes()).unwrap()
+}
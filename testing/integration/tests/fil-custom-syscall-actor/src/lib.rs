@@ -0,0 +1,51 @@
+use fvm_ipld_encoding::{from_slice, RawBytes, DAG_CBOR};
+use fvm_sdk as sdk;
+use fvm_shared::error::ExitCode;
+
+include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+
+/// Double the `u64` passed as parameter with the `example::double` syscall, and return the result.
+pub const METHOD_DOUBLE: u64 = 2;
+
+/// A syscall that isn't part of the standard set, bound by a custom kernel.
+#[link(wasm_import_module = "example")]
+extern "C" {
+    #[link_name = "double"]
+    fn example_double(ret: *mut u64, x: u64) -> u32;
+}
+
+fn double(x: u64) -> Result<u64, u32> {
+    let mut ret = 0u64;
+    match unsafe { example_double(&mut ret, x) } {
+        0 => Ok(ret),
+        code => Err(code),
+    }
+}
+
+#[no_mangle]
+pub fn invoke(params: u32) -> u32 {
+    std::panic::set_hook(Box::new(|info| {
+        sdk::vm::abort(
+            ExitCode::USR_ASSERTION_FAILED.value(),
+            Some(&format!("{}", info)),
+        )
+    }));
+
+    match sdk::message::method_number() {
+        METHOD_DOUBLE => {
+            let x: u64 = from_slice(&sdk::message::params_raw(params).unwrap().1).unwrap();
+            let ret = match double(x) {
+                Ok(doubled) => RawBytes::serialize(doubled).unwrap(),
+                Err(code) => sdk::vm::abort(
+                    ExitCode::USR_ILLEGAL_ARGUMENT.value(),
+                    Some(&format!("example::double failed with {}", code)),
+                ),
+            };
+            sdk::ipld::put_block(DAG_CBOR, ret.bytes()).unwrap()
+        }
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unrecognized method"),
+        ),
+    }
+}
@@ -0,0 +1,47 @@
+use fvm_ipld_encoding::{RawBytes, DAG_CBOR};
+use fvm_sdk as sdk;
+use fvm_shared::error::ExitCode;
+
+include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+
+/// Draw randomness from the ticket chain at the current epoch. Aborts with
+/// `USR_ILLEGAL_ARGUMENT` if the syscall fails.
+pub const METHOD_CHAIN_RANDOMNESS: u64 = 2;
+/// Verify a (bogus) consensus fault. Returns whether a fault was found, or aborts with
+/// `USR_ILLEGAL_ARGUMENT` if the syscall fails.
+pub const METHOD_CONSENSUS_FAULT: u64 = 3;
+
+#[no_mangle]
+pub fn invoke(_: u32) -> u32 {
+    std::panic::set_hook(Box::new(|info| {
+        sdk::vm::abort(
+            ExitCode::USR_ASSERTION_FAILED.value(),
+            Some(&format!("{}", info)),
+        )
+    }));
+
+    let ret = match sdk::message::method_number() {
+        METHOD_CHAIN_RANDOMNESS => {
+            let epoch = sdk::network::curr_epoch();
+            match sdk::rand::get_chain_randomness(1, epoch, b"entropy") {
+                Ok(rand) => RawBytes::serialize(rand.0.to_vec()).unwrap(),
+                Err(e) => sdk::vm::abort(
+                    ExitCode::USR_ILLEGAL_ARGUMENT.value(),
+                    Some(&format!("failed to get randomness: {}", e)),
+                ),
+            }
+        }
+        METHOD_CONSENSUS_FAULT => match sdk::crypto::verify_consensus_fault(b"h1", b"h2", b"") {
+            Ok(fault) => RawBytes::serialize(fault.is_some()).unwrap(),
+            Err(e) => sdk::vm::abort(
+                ExitCode::USR_ILLEGAL_ARGUMENT.value(),
+                Some(&format!("failed to verify consensus fault: {}", e)),
+            ),
+        },
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unrecognized method"),
+        ),
+    };
+    sdk::ipld::put_block(DAG_CBOR, ret.bytes()).unwrap()
+}
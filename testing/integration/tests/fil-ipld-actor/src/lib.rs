@@ -89,6 +89,27 @@ fn test_read_block() {
             );
         }
 
+        // Test reading the block in two chunks
+        {
+            let stat = sdk::ipld::stat_block(id).unwrap();
+            assert_eq!(
+                stat.size as usize,
+                test_bytes.len(),
+                "stat returned the wrong size"
+            );
+            let mut chunked = vec![0u8; stat.size as usize];
+            let (first, second) = chunked.split_at_mut(10);
+            let remaining = sdk::ipld::read_block(id, 0, first).unwrap();
+            assert_eq!(
+                remaining as usize,
+                second.len(),
+                "first chunk should leave the rest"
+            );
+            let remaining = sdk::ipld::read_block(id, first.len() as u32, second).unwrap();
+            assert_eq!(remaining, 0, "second chunk should finish the block");
+            assert_eq!(test_bytes, chunked, "can read in chunks");
+        }
+
         // Test get_block with no hint
         assert_eq!(
             test_bytes,
@@ -0,0 +1,65 @@
+use fvm_ipld_encoding::{from_slice, RawBytes, DAG_CBOR};
+use fvm_sdk as sdk;
+use fvm_shared::error::ExitCode;
+use fvm_shared::sector::{
+    AggregateSealVerifyProofAndInfos, ReplicaUpdateInfo, SealVerifyInfo, WindowPoStVerifyInfo,
+};
+
+include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+
+/// Verify the aggregate seal proof passed as a parameter.
+pub const METHOD_VERIFY_AGGREGATE_SEALS: u64 = 2;
+/// Verify the replica update passed as a parameter.
+pub const METHOD_VERIFY_REPLICA_UPDATE: u64 = 3;
+/// Verify the seal proof passed as a parameter.
+pub const METHOD_VERIFY_SEAL: u64 = 4;
+/// Verify the window PoSt passed as a parameter.
+pub const METHOD_VERIFY_POST: u64 = 5;
+
+/// The result of a verification, returned by every method: the error number the syscall failed
+/// with (or 0), and whether the proof verified.
+pub type VerifyResult = (u32, bool);
+
+fn to_result(res: sdk::SyscallResult<bool>) -> VerifyResult {
+    match res {
+        Ok(valid) => (0, valid),
+        Err(e) => (e as u32, false),
+    }
+}
+
+#[no_mangle]
+pub fn invoke(params: u32) -> u32 {
+    std::panic::set_hook(Box::new(|info| {
+        sdk::vm::abort(
+            ExitCode::USR_ASSERTION_FAILED.value(),
+            Some(&format!("{}", info)),
+        )
+    }));
+
+    let params = sdk::message::params_raw(params).unwrap().1;
+    let ret: VerifyResult = match sdk::message::method_number() {
+        METHOD_VERIFY_AGGREGATE_SEALS => {
+            let info: AggregateSealVerifyProofAndInfos = from_slice(&params).unwrap();
+            to_result(sdk::crypto::verify_aggregate_seals(&info))
+        }
+        METHOD_VERIFY_REPLICA_UPDATE => {
+            let info: ReplicaUpdateInfo = from_slice(&params).unwrap();
+            to_result(sdk::crypto::verify_replica_update(&info))
+        }
+        METHOD_VERIFY_SEAL => {
+            let info: SealVerifyInfo = from_slice(&params).unwrap();
+            to_result(sdk::crypto::verify_seal(&info))
+        }
+        METHOD_VERIFY_POST => {
+            let info: WindowPoStVerifyInfo = from_slice(&params).unwrap();
+            to_result(sdk::crypto::verify_post(&info))
+        }
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unrecognized method"),
+        ),
+    };
+
+    let ret = RawBytes::serialize(ret).unwrap();
+    sdk::ipld::put_block(DAG_CBOR, ret.bytes()).unwrap()
+}
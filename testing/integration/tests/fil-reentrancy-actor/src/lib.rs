@@ -0,0 +1,96 @@
+use cid::Cid;
+use fvm_ipld_encoding::{from_slice, to_vec, RawBytes, DAG_CBOR};
+use fvm_sdk as sdk;
+use fvm_sdk::NO_DATA_BLOCK_ID;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::ActorID;
+
+include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+
+/// Set the state to 1, then call the peer (whose ID is passed as a parameter), which calls back
+/// into this actor. Returns the state observed by the reentrant call, and the final state.
+pub const METHOD_OUTER: u64 = 2;
+/// Call back into the caller's `METHOD_INNER`, returning its result.
+pub const METHOD_BOUNCE: u64 = 3;
+/// Add 10 to the state, returning the state observed before doing so.
+pub const METHOD_INNER: u64 = 4;
+/// Set the state to 5, then abort.
+pub const METHOD_ABORT: u64 = 5;
+/// Set the state to 2, then call the peer's `METHOD_ABORT` (whose ID is passed as a parameter),
+/// ignoring its failure. Returns the exit code observed, and the final state.
+pub const METHOD_IGNORE_FAILURE: u64 = 6;
+
+fn load() -> u64 {
+    let root = sdk::sself::root().unwrap();
+    from_slice(&sdk::ipld::get(&root).unwrap()).unwrap()
+}
+
+fn save(value: u64) -> Cid {
+    let cid = sdk::ipld::put(0xb220, 32, DAG_CBOR, &to_vec(&value).unwrap()).unwrap();
+    sdk::sself::set_root(&cid).unwrap();
+    cid
+}
+
+fn send(to: ActorID, method: u64, params: RawBytes) -> RawBytes {
+    let response =
+        sdk::send::send(&Address::new_id(to), method, params, TokenAmount::default()).unwrap();
+    assert!(response.exit_code.is_success(), "send failed");
+    response.return_data.map(|blk| blk.data).unwrap_or_default()
+}
+
+#[no_mangle]
+pub fn invoke(params: u32) -> u32 {
+    std::panic::set_hook(Box::new(|info| {
+        sdk::vm::abort(
+            ExitCode::USR_ASSERTION_FAILED.value(),
+            Some(&format!("{}", info)),
+        )
+    }));
+
+    let ret: Option<RawBytes> = match sdk::message::method_number() {
+        METHOD_OUTER => {
+            let peer: ActorID = from_slice(&sdk::message::params_raw(params).unwrap().1).unwrap();
+            save(1);
+            let me = RawBytes::serialize(sdk::message::receiver()).unwrap();
+            let seen: u64 = send(peer, METHOD_BOUNCE, me).deserialize().unwrap();
+            Some(RawBytes::serialize((seen, load())).unwrap())
+        }
+        METHOD_BOUNCE => {
+            let caller: ActorID = from_slice(&sdk::message::params_raw(params).unwrap().1).unwrap();
+            Some(send(caller, METHOD_INNER, RawBytes::default()))
+        }
+        METHOD_INNER => {
+            let seen = load();
+            save(seen + 10);
+            Some(RawBytes::serialize(seen).unwrap())
+        }
+        METHOD_ABORT => {
+            save(5);
+            sdk::vm::abort(ExitCode::USR_ASSERTION_FAILED.value(), Some("aborting"))
+        }
+        METHOD_IGNORE_FAILURE => {
+            let peer: ActorID = from_slice(&sdk::message::params_raw(params).unwrap().1).unwrap();
+            save(2);
+            let response = sdk::send::send(
+                &Address::new_id(peer),
+                METHOD_ABORT,
+                RawBytes::default(),
+                TokenAmount::default(),
+            )
+            .unwrap();
+            assert!(response.return_data.is_none(), "failed send returned data");
+            Some(RawBytes::serialize((response.exit_code.value(), load())).unwrap())
+        }
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unrecognized method"),
+        ),
+    };
+
+    match ret {
+        None => NO_DATA_BLOCK_ID,
+        Some(v) => sdk::ipld::put_block(DAG_CBOR, v.bytes()).unwrap(),
+    }
+}
@@ -0,0 +1,72 @@
+use fvm_ipld_encoding::{from_slice, RawBytes, DAG_CBOR};
+use fvm_sdk as sdk;
+use fvm_sdk::NO_DATA_BLOCK_ID;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::IPLD_RAW;
+
+include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+
+/// Send parameters of the given size to ourselves. Returns 0 on success, or the error number of
+/// the failed send.
+pub const METHOD_SEND_PARAMS: u64 = 2;
+/// Ask ourselves to return a value of the given size. Returns 0 on success, or the error number of
+/// the failed send.
+pub const METHOD_REQUEST_RETURN: u64 = 3;
+/// Do nothing.
+pub const METHOD_NOOP: u64 = 4;
+/// Return a value of the given size.
+pub const METHOD_RETURN: u64 = 5;
+
+fn size_param(params: u32) -> u64 {
+    from_slice(&sdk::message::params_raw(params).unwrap().1).unwrap()
+}
+
+fn send_self(method: u64, params: RawBytes) -> u32 {
+    let me = Address::new_id(sdk::message::receiver());
+    match sdk::send::send(&me, method, params, TokenAmount::default()) {
+        Ok(response) => {
+            assert!(response.exit_code.is_success(), "send failed");
+            0
+        }
+        Err(e) => e as u32,
+    }
+}
+
+#[no_mangle]
+pub fn invoke(params: u32) -> u32 {
+    std::panic::set_hook(Box::new(|info| {
+        sdk::vm::abort(
+            ExitCode::USR_ASSERTION_FAILED.value(),
+            Some(&format!("{}", info)),
+        )
+    }));
+
+    let ret: Option<RawBytes> = match sdk::message::method_number() {
+        METHOD_SEND_PARAMS => {
+            let size = size_param(params);
+            let code = send_self(METHOD_NOOP, RawBytes::new(vec![0u8; size as usize]));
+            Some(RawBytes::serialize(code).unwrap())
+        }
+        METHOD_REQUEST_RETURN => {
+            let size = size_param(params);
+            let code = send_self(METHOD_RETURN, RawBytes::serialize(size).unwrap());
+            Some(RawBytes::serialize(code).unwrap())
+        }
+        METHOD_NOOP => None,
+        METHOD_RETURN => {
+            let size = size_param(params);
+            return sdk::ipld::put_block(IPLD_RAW, &vec![0u8; size as usize]).unwrap();
+        }
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unrecognized method"),
+        ),
+    };
+
+    match ret {
+        None => NO_DATA_BLOCK_ID,
+        Some(v) => sdk::ipld::put_block(DAG_CBOR, v.bytes()).unwrap(),
+    }
+}
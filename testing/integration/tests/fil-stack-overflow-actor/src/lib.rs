@@ -64,7 +64,7 @@ pub fn do_send(m: u64) -> u32 {
         TokenAmount::zero(),
     );
     match r {
-        Ok(rec) => match rec.exit_code {
+        Ok(response) => match response.exit_code {
             ExitCode::OK => 0,
             e => sdk::vm::abort(e.value() | 0x80000000, None),
         },
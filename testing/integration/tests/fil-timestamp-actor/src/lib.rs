@@ -0,0 +1,45 @@
+use fvm_ipld_encoding::{from_slice, to_vec, RawBytes, DAG_CBOR};
+use fvm_sdk as sdk;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::error::ExitCode;
+
+include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+
+/// Record the current tipset's timestamp in the state, asserting that it didn't decrease since
+/// the last recorded one. Returns the current epoch and timestamp.
+pub const METHOD_RECORD: u64 = 2;
+
+#[no_mangle]
+pub fn invoke(_: u32) -> u32 {
+    std::panic::set_hook(Box::new(|info| {
+        sdk::vm::abort(
+            ExitCode::USR_ASSERTION_FAILED.value(),
+            Some(&format!("{}", info)),
+        )
+    }));
+
+    match sdk::message::method_number() {
+        METHOD_RECORD => {
+            let epoch: ChainEpoch = sdk::network::curr_epoch();
+            let timestamp = sdk::network::tipset_timestamp();
+
+            let root = sdk::sself::root().unwrap();
+            let last: u64 = from_slice(&sdk::ipld::get(&root).unwrap()).unwrap();
+            assert!(
+                timestamp >= last,
+                "timestamp decreased from {} to {}",
+                last,
+                timestamp
+            );
+            let root = sdk::ipld::put(0xb220, 32, DAG_CBOR, &to_vec(&timestamp).unwrap()).unwrap();
+            sdk::sself::set_root(&root).unwrap();
+
+            let ret = RawBytes::serialize((epoch, timestamp)).unwrap();
+            sdk::ipld::put_block(DAG_CBOR, ret.bytes()).unwrap()
+        }
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unrecognized method"),
+        ),
+    }
+}
@@ -0,0 +1,221 @@
+use fil_balance_actor::{
+    Observation, TransferParams, TransferReturn, METHOD_OBSERVE, METHOD_TRANSFER, WASM_BINARY,
+};
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::gas::{price_list_by_network_version, Gas};
+use fvm::trace::ExecutionEvent;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, Tester};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::METHOD_SEND;
+use num_traits::Zero;
+
+mod bundles;
+use bundles::*;
+
+const A: u64 = 10000;
+const B: u64 = 10001;
+
+/// Sets up two balance actors, `A` and `B`, holding 1000 and 0 respectively.
+fn instantiate_tester() -> (Tester<MemoryBlockstore, DummyExterns>, Account) {
+    instantiate_tester_at(NetworkVersion::V16)
+}
+
+fn instantiate_tester_at(nv: NetworkVersion) -> (Tester<MemoryBlockstore, DummyExterns>, Account) {
+    let (mut tester, sender) = new_tester_with_actor(
+        nv,
+        WASM_BINARY.unwrap(),
+        &(),
+        &[(A, TokenAmount::from_atto(1000)), (B, TokenAmount::zero())],
+    )
+    .unwrap();
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (tester, sender)
+}
+
+/// Sends `value` to `A`, asking it to transfer `amount` to `peer`.
+fn transfer(
+    tester: &mut Tester<MemoryBlockstore, DummyExterns>,
+    sender: Account,
+    sequence: u64,
+    value: u64,
+    peer: u64,
+    amount: u64,
+) -> ApplyRet {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(A),
+        value: TokenAmount::from_atto(value),
+        gas_limit: 1000000000,
+        method_num: METHOD_TRANSFER,
+        params: RawBytes::serialize(TransferParams {
+            peer,
+            amount: TokenAmount::from_atto(amount),
+        })
+        .unwrap(),
+        sequence,
+        ..Message::default()
+    };
+    tester
+        .executor
+        .as_mut()
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+fn balance(tester: &Tester<MemoryBlockstore, DummyExterns>, id: u64) -> TokenAmount {
+    tester
+        .executor
+        .as_ref()
+        .unwrap()
+        .state_tree()
+        .get_actor_id(id)
+        .unwrap()
+        .unwrap()
+        .balance
+}
+
+fn observation(own: u64, caller: u64) -> Observation {
+    Observation {
+        current: TokenAmount::from_atto(own),
+        own: TokenAmount::from_atto(own),
+        caller: TokenAmount::from_atto(caller),
+        missing: TokenAmount::zero(),
+    }
+}
+
+// Balances observed mid-call reflect the value received with the call, and transfers made since.
+#[test]
+fn mid_call_balances() {
+    let (mut tester, sender) = instantiate_tester();
+
+    let res = transfer(&mut tester, sender, 0, 100, B, 30);
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    let ret: TransferReturn = res.msg_receipt.return_data.deserialize().unwrap();
+
+    // The sender is only charged the value sent, as the gas fee cap is zero.
+    assert_eq!(ret.before, observation(1100, 9900));
+    assert_eq!(ret.peer, observation(30, 1070));
+    assert_eq!(ret.after, observation(1070, 9900));
+}
+
+// Sending value to oneself leaves the balance untouched, but still invokes the method.
+#[test]
+fn self_transfer() {
+    let (mut tester, sender) = instantiate_tester();
+
+    let res = transfer(&mut tester, sender, 0, 100, A, 30);
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    let ret: TransferReturn = res.msg_receipt.return_data.deserialize().unwrap();
+    assert_eq!(ret.before, observation(1100, 9900));
+    assert_eq!(ret.peer, observation(1100, 1100));
+    assert_eq!(ret.after, observation(1100, 9900));
+    assert_eq!(balance(&tester, A), TokenAmount::from_atto(1100));
+
+    let self_calls = res
+        .exec_trace
+        .iter()
+        .filter(|ev| {
+            matches!(ev, ExecutionEvent::Call { from: A, to, method: METHOD_OBSERVE, value, .. }
+                if *to == Address::new_id(A) && *value == TokenAmount::from_atto(30))
+        })
+        .count();
+    assert_eq!(self_calls, 1);
+
+    // The actor must still hold the value it sends to itself.
+    let res = transfer(&mut tester, sender, 1, 0, A, 5000);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::USR_ASSERTION_FAILED);
+    assert_eq!(balance(&tester, A), TokenAmount::from_atto(1100));
+}
+
+// A message sending value from an account to its own robust address is a self-transfer too.
+#[test]
+fn self_transfer_robust_address() {
+    let (mut tester, sender) = instantiate_tester();
+
+    let message = Message {
+        from: sender.1,
+        to: sender.1,
+        value: TokenAmount::from_atto(100),
+        gas_limit: 1000000000,
+        method_num: METHOD_SEND,
+        ..Message::default()
+    };
+    let res = tester
+        .executor
+        .as_mut()
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    assert_eq!(balance(&tester, sender.0), TokenAmount::from_atto(10000));
+}
+
+// Actors pay for the state-tree lookups they trigger from nv17, but not for resolving the receiver
+// of the top-level message.
+#[test]
+fn state_tree_access_gas() {
+    for nv in [NetworkVersion::V16, NetworkVersion::V17] {
+        let (mut tester, sender) = instantiate_tester_at(nv);
+        let lookup = price_list_by_network_version(nv).on_actor_lookup().total();
+
+        // Each of the three observations looks up three balances.
+        let res = transfer(&mut tester, sender, 0, 100, B, 30);
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::OK, "{}", nv);
+        assert_eq!(charged(&res, "OnActorLookup"), [lookup; 9], "{}", nv);
+
+        let message = Message {
+            from: sender.1,
+            to: sender.1,
+            gas_limit: 1000000000,
+            method_num: METHOD_SEND,
+            sequence: 1,
+            ..Message::default()
+        };
+        let res = tester
+            .executor
+            .as_mut()
+            .unwrap()
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::OK, "{}", nv);
+        assert_eq!(charged(&res, "OnAddressLookup"), [], "{}", nv);
+    }
+
+    // Pin the nv17 price.
+    assert_eq!(
+        price_list_by_network_version(NetworkVersion::V17)
+            .on_actor_lookup()
+            .total(),
+        Gas::new(135617)
+    );
+    assert_eq!(
+        price_list_by_network_version(NetworkVersion::V16)
+            .on_actor_lookup()
+            .total(),
+        Gas::zero()
+    );
+}
@@ -0,0 +1,222 @@
+use fil_externs_actor::{METHOD_CHAIN_RANDOMNESS, METHOD_CONSENSUS_FAULT, WASM_BINARY};
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::externs::{Consensus, ExternError, Externs, Rand};
+use fvm::gas::Gas;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::randomness::Randomness;
+use fvm_shared::version::NetworkVersion;
+use num_traits::Zero;
+
+mod bundles;
+use bundles::*;
+
+const ACTOR: u64 = 10000;
+const GAS_LIMIT: i64 = 1000000000;
+
+/// How every extern call fails.
+#[derive(Clone, Copy)]
+enum Failure {
+    None,
+    Fatal,
+    NotAvailable,
+}
+
+/// Externs that fail in a configurable way, e.g. to simulate a database error on one node, and
+/// otherwise report consuming the configured amount of gas.
+struct FlakyExterns {
+    failure: Failure,
+    gas: i64,
+}
+
+impl FlakyExterns {
+    fn result<T>(&self, value: T) -> Result<(T, i64), ExternError> {
+        match self.failure {
+            Failure::None => Ok((value, self.gas)),
+            Failure::Fatal => Err(anyhow::anyhow!("database is locked").into()),
+            Failure::NotAvailable => Err(ExternError::NotAvailable("no such epoch".into())),
+        }
+    }
+}
+
+impl Externs for FlakyExterns {}
+
+impl Rand for FlakyExterns {
+    fn get_chain_randomness(&self, _round: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
+        self.result(Randomness([1; 32]))
+    }
+
+    fn get_beacon_randomness(&self, _round: ChainEpoch) -> Result<(Randomness, i64), ExternError> {
+        self.result(Randomness([2; 32]))
+    }
+}
+
+impl Consensus for FlakyExterns {
+    fn verify_consensus_fault(
+        &self,
+        _h1: &[u8],
+        _h2: &[u8],
+        _extra: &[u8],
+    ) -> Result<(Option<ConsensusFault>, i64), ExternError> {
+        self.result(None)
+    }
+}
+
+fn execute(failure: Failure, method_num: u64) -> anyhow::Result<ApplyRet> {
+    execute_with(
+        NetworkVersion::V16,
+        FlakyExterns { failure, gas: 0 },
+        method_num,
+    )
+}
+
+fn execute_with(
+    nv: NetworkVersion,
+    externs: FlakyExterns,
+    method_num: u64,
+) -> anyhow::Result<ApplyRet> {
+    let (mut tester, sender) = new_tester_with_actor(
+        nv,
+        WASM_BINARY.unwrap(),
+        &(),
+        &[(ACTOR, TokenAmount::zero())],
+    )
+    .unwrap();
+    tester.enable_tracing();
+    tester.instantiate_machine(externs).unwrap();
+
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR),
+        gas_limit: GAS_LIMIT,
+        method_num,
+        ..Message::default()
+    };
+    tester
+        .executor
+        .as_mut()
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+}
+
+#[test]
+fn randomness_extern_failures() {
+    let res = execute(Failure::None, METHOD_CHAIN_RANDOMNESS).unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    // Unavailable randomness is reported to the actor.
+    let res = execute(Failure::NotAvailable, METHOD_CHAIN_RANDOMNESS).unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::USR_ILLEGAL_ARGUMENT);
+
+    // A node-local failure aborts the message without a receipt.
+    let err = execute(Failure::Fatal, METHOD_CHAIN_RANDOMNESS).unwrap_err();
+    assert!(err.is::<ExternError>(), "{:#}", err);
+}
+
+#[test]
+fn consensus_fault_extern_failures() {
+    let res = execute(Failure::None, METHOD_CONSENSUS_FAULT).unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert!(!res.msg_receipt.return_data.deserialize::<bool>().unwrap());
+
+    // A fault that can't be verified isn't a fault.
+    let res = execute(Failure::NotAvailable, METHOD_CONSENSUS_FAULT).unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert!(!res.msg_receipt.return_data.deserialize::<bool>().unwrap());
+
+    let err = execute(Failure::Fatal, METHOD_CONSENSUS_FAULT).unwrap_err();
+    assert!(err.is::<ExternError>(), "{:#}", err);
+}
+
+#[test]
+fn extern_gas() {
+    for (nv, method_num, name) in [
+        (
+            NetworkVersion::V17,
+            METHOD_CHAIN_RANDOMNESS,
+            "get_chain_randomness",
+        ),
+        (
+            NetworkVersion::V17,
+            METHOD_CONSENSUS_FAULT,
+            "verify_consensus_fault",
+        ),
+        // The consensus fault extern's gas was charged in nv15, under another name.
+        (
+            NetworkVersion::V15,
+            METHOD_CONSENSUS_FAULT,
+            "verify_consensus_fault_accesses",
+        ),
+    ] {
+        // The gas reported by the extern is charged under its name.
+        let externs = FlakyExterns {
+            failure: Failure::None,
+            gas: 1234,
+        };
+        let res = execute_with(nv, externs, method_num).unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::OK, "{}", name);
+        assert_eq!(charged(&res, name), [Gas::new(1234)], "{}", name);
+
+        // Reporting more gas than remains runs the message out of gas before the extern's result
+        // reaches the actor.
+        let externs = FlakyExterns {
+            failure: Failure::None,
+            gas: GAS_LIMIT + 1,
+        };
+        let res = execute_with(nv, externs, method_num).unwrap();
+        assert_eq!(
+            res.msg_receipt.exit_code,
+            ExitCode::SYS_OUT_OF_GAS,
+            "{}",
+            name
+        );
+        assert_eq!(res.msg_receipt.gas_used, GAS_LIMIT, "{}", name);
+        assert!(res.msg_receipt.return_data.is_empty());
+    }
+
+    // Otherwise, the gas reported by externs isn't charged before nv17.
+    for (nv, method_num, names) in [
+        (
+            NetworkVersion::V16,
+            METHOD_CHAIN_RANDOMNESS,
+            &["get_chain_randomness"][..],
+        ),
+        (
+            NetworkVersion::V15,
+            METHOD_CHAIN_RANDOMNESS,
+            &["get_chain_randomness"][..],
+        ),
+        (
+            NetworkVersion::V16,
+            METHOD_CONSENSUS_FAULT,
+            &["verify_consensus_fault", "verify_consensus_fault_accesses"][..],
+        ),
+    ] {
+        let externs = FlakyExterns {
+            failure: Failure::None,
+            gas: 1234,
+        };
+        let res = execute_with(nv, externs, method_num).unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+        for name in names {
+            assert!(charged(&res, name).is_empty(), "{} at {}", name, nv);
+        }
+    }
+    let externs = FlakyExterns {
+        failure: Failure::None,
+        gas: 1234,
+    };
+    let res = execute_with(NetworkVersion::V17, externs, METHOD_CONSENSUS_FAULT).unwrap();
+    assert!(charged(&res, "verify_consensus_fault_accesses").is_empty());
+
+    // Reporting negative gas is a bug in the node.
+    let externs = FlakyExterns {
+        failure: Failure::None,
+        gas: -1,
+    };
+    assert!(execute_with(NetworkVersion::V16, externs, METHOD_CHAIN_RANDOMNESS).is_err());
+}
@@ -0,0 +1,234 @@
+use fil_proofs_actor::{
+    VerifyResult, METHOD_VERIFY_AGGREGATE_SEALS, METHOD_VERIFY_POST, METHOD_VERIFY_REPLICA_UPDATE,
+    METHOD_VERIFY_SEAL, WASM_BINARY,
+};
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, IntegrationExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::commcid::{data_commitment_v1_to_cid, replica_commitment_v1_to_cid};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::message::Message;
+use fvm_shared::randomness::Randomness;
+use fvm_shared::sector::{
+    AggregateSealVerifyInfo, AggregateSealVerifyProofAndInfos, PoStProof, RegisteredAggregateProof,
+    RegisteredPoStProof, RegisteredSealProof, RegisteredUpdateProof, ReplicaUpdateInfo,
+    SealVerifyInfo, SectorID, SectorInfo, WindowPoStVerifyInfo,
+};
+use fvm_shared::version::NetworkVersion;
+use num_traits::Zero;
+use serde::Serialize;
+
+mod bundles;
+use bundles::*;
+
+// These tests run against the structure-only verifier enabled by the `mock-proofs` feature.
+
+const ACTOR: u64 = 10000;
+
+fn instantiate() -> (Account, IntegrationExecutor<MemoryBlockstore, DummyExterns>) {
+    let (mut tester, sender) = new_tester_with_actor(
+        NetworkVersion::V16,
+        WASM_BINARY.unwrap(),
+        &(),
+        &[(ACTOR, TokenAmount::zero())],
+    )
+    .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    (sender, tester.executor.unwrap())
+}
+
+fn verify(method_num: u64, params: &impl Serialize) -> VerifyResult {
+    let (sender, mut executor) = instantiate();
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR),
+        gas_limit: 5_000_000_000,
+        method_num,
+        params: RawBytes::serialize(params).unwrap(),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    res.msg_receipt.return_data.deserialize().unwrap()
+}
+
+fn aggregate(count: u64) -> AggregateSealVerifyProofAndInfos {
+    AggregateSealVerifyProofAndInfos {
+        miner: 1000,
+        seal_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+        aggregate_proof: RegisteredAggregateProof::SnarkPackV1,
+        proof: vec![1; 192],
+        infos: (0..count)
+            .map(|i| AggregateSealVerifyInfo {
+                sector_number: i,
+                randomness: Randomness([2; 32]),
+                interactive_randomness: Randomness([3; 32]),
+                sealed_cid: replica_commitment_v1_to_cid(&[4; 32]).unwrap(),
+                unsealed_cid: data_commitment_v1_to_cid(&[5; 32]).unwrap(),
+            })
+            .collect(),
+    }
+}
+
+fn seal() -> SealVerifyInfo {
+    SealVerifyInfo {
+        registered_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+        sector_id: SectorID {
+            miner: 1000,
+            number: 1,
+        },
+        deal_ids: vec![],
+        randomness: Randomness([2; 32]),
+        interactive_randomness: Randomness([3; 32]),
+        proof: vec![1; 1920],
+        sealed_cid: replica_commitment_v1_to_cid(&[4; 32]).unwrap(),
+        unsealed_cid: data_commitment_v1_to_cid(&[5; 32]).unwrap(),
+    }
+}
+
+fn post() -> WindowPoStVerifyInfo {
+    WindowPoStVerifyInfo {
+        randomness: Randomness([1; 32]),
+        proofs: vec![PoStProof {
+            post_proof: RegisteredPoStProof::StackedDRGWindow32GiBV1,
+            proof_bytes: vec![2; 192],
+        }],
+        challenged_sectors: (0..2)
+            .map(|i| SectorInfo {
+                proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+                sector_number: i,
+                sealed_cid: replica_commitment_v1_to_cid(&[3; 32]).unwrap(),
+            })
+            .collect(),
+        prover: 1000,
+    }
+}
+
+fn replica_update() -> ReplicaUpdateInfo {
+    ReplicaUpdateInfo {
+        update_proof_type: RegisteredUpdateProof::StackedDRG32GiBV1,
+        old_sealed_cid: replica_commitment_v1_to_cid(&[1; 32]).unwrap(),
+        new_sealed_cid: replica_commitment_v1_to_cid(&[2; 32]).unwrap(),
+        new_unsealed_cid: data_commitment_v1_to_cid(&[3; 32]).unwrap(),
+        proof: vec![4; 192],
+    }
+}
+
+#[test]
+fn aggregate_seals() {
+    assert_eq!(
+        verify(METHOD_VERIFY_AGGREGATE_SEALS, &aggregate(4)),
+        (0, true)
+    );
+    assert_eq!(
+        verify(METHOD_VERIFY_AGGREGATE_SEALS, &aggregate(819)),
+        (0, true)
+    );
+
+    // An empty proof doesn't verify.
+    let mut agg = aggregate(4);
+    agg.proof.clear();
+    assert_eq!(verify(METHOD_VERIFY_AGGREGATE_SEALS, &agg), (0, false));
+
+    // Structurally invalid aggregates are rejected.
+    let illegal = (ErrorNumber::IllegalArgument as u32, false);
+    assert_eq!(
+        verify(METHOD_VERIFY_AGGREGATE_SEALS, &aggregate(0)),
+        illegal
+    );
+
+    let mut agg = aggregate(4);
+    agg.seal_proof = RegisteredSealProof::Invalid(-1);
+    assert_eq!(verify(METHOD_VERIFY_AGGREGATE_SEALS, &agg), illegal);
+
+    let mut agg = aggregate(4);
+    agg.aggregate_proof = RegisteredAggregateProof::Invalid(-1);
+    assert_eq!(verify(METHOD_VERIFY_AGGREGATE_SEALS, &agg), illegal);
+
+    // The sealed and unsealed CIDs are swapped.
+    let mut agg = aggregate(4);
+    agg.infos[2].sealed_cid = agg.infos[2].unsealed_cid;
+    assert_eq!(verify(METHOD_VERIFY_AGGREGATE_SEALS, &agg), illegal);
+}
+
+#[test]
+fn replica_updates() {
+    assert_eq!(
+        verify(METHOD_VERIFY_REPLICA_UPDATE, &replica_update()),
+        (0, true)
+    );
+
+    // An empty proof doesn't verify.
+    let mut update = replica_update();
+    update.proof.clear();
+    assert_eq!(verify(METHOD_VERIFY_REPLICA_UPDATE, &update), (0, false));
+
+    // Structurally invalid updates are rejected.
+    let illegal = (ErrorNumber::IllegalArgument as u32, false);
+
+    let mut update = replica_update();
+    update.update_proof_type = RegisteredUpdateProof::Invalid(-1);
+    assert_eq!(verify(METHOD_VERIFY_REPLICA_UPDATE, &update), illegal);
+
+    let mut update = replica_update();
+    update.new_unsealed_cid = update.new_sealed_cid;
+    assert_eq!(verify(METHOD_VERIFY_REPLICA_UPDATE, &update), illegal);
+}
+
+#[test]
+fn seals() {
+    assert_eq!(verify(METHOD_VERIFY_SEAL, &seal()), (0, true));
+
+    // An empty proof doesn't verify.
+    let mut info = seal();
+    info.proof.clear();
+    assert_eq!(verify(METHOD_VERIFY_SEAL, &info), (0, false));
+
+    // Structurally invalid seals are rejected.
+    let illegal = (ErrorNumber::IllegalArgument as u32, false);
+
+    let mut info = seal();
+    info.registered_proof = RegisteredSealProof::Invalid(-1);
+    assert_eq!(verify(METHOD_VERIFY_SEAL, &info), illegal);
+
+    let mut info = seal();
+    info.unsealed_cid = info.sealed_cid;
+    assert_eq!(verify(METHOD_VERIFY_SEAL, &info), illegal);
+}
+
+#[test]
+fn posts() {
+    assert_eq!(verify(METHOD_VERIFY_POST, &post()), (0, true));
+
+    // An empty proof doesn't verify.
+    let mut info = post();
+    info.proofs[0].proof_bytes.clear();
+    assert_eq!(verify(METHOD_VERIFY_POST, &info), (0, false));
+
+    // Structurally invalid PoSts are rejected.
+    let illegal = (ErrorNumber::IllegalArgument as u32, false);
+
+    let mut info = post();
+    info.proofs.clear();
+    assert_eq!(verify(METHOD_VERIFY_POST, &info), illegal);
+
+    let mut info = post();
+    info.proofs[0].post_proof = RegisteredPoStProof::Invalid(-1);
+    assert_eq!(verify(METHOD_VERIFY_POST, &info), illegal);
+
+    let mut info = post();
+    info.challenged_sectors[1].sealed_cid = data_commitment_v1_to_cid(&[3; 32]).unwrap();
+    assert_eq!(verify(METHOD_VERIFY_POST, &info), illegal);
+}
@@ -0,0 +1,132 @@
+use fil_reentrancy_actor::{
+    METHOD_ABORT, METHOD_IGNORE_FAILURE, METHOD_OUTER, WASM_BINARY as REENTRANCY_BINARY,
+};
+use fvm::executor::{ApplyKind, Executor};
+use fvm::machine::Machine;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, IntegrationExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{CborStore, RawBytes};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::receipt::Receipt;
+use fvm_shared::version::NetworkVersion;
+use num_traits::Zero;
+
+mod bundles;
+use bundles::*;
+
+const A: u64 = 10000;
+const B: u64 = 10001;
+
+// Utility function to instantiate the machine with two reentrancy actors, A and B, both with a
+// state of 0.
+fn instantiate() -> (Account, IntegrationExecutor<MemoryBlockstore, DummyExterns>) {
+    let (mut tester, sender) = new_tester_with_actor(
+        NetworkVersion::V16,
+        REENTRANCY_BINARY.unwrap(),
+        &0u64,
+        &[(A, TokenAmount::zero()), (B, TokenAmount::zero())],
+    )
+    .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    (sender, tester.executor.unwrap())
+}
+
+fn call(
+    executor: &mut IntegrationExecutor<MemoryBlockstore, DummyExterns>,
+    sender: Account,
+    method_num: u64,
+    params: RawBytes,
+) -> Receipt {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(A),
+        gas_limit: 1000000000,
+        method_num,
+        params,
+        ..Message::default()
+    };
+    executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+        .msg_receipt
+}
+
+fn state_of(executor: &IntegrationExecutor<MemoryBlockstore, DummyExterns>, id: u64) -> u64 {
+    let actor = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .get_actor_id(id)
+        .unwrap()
+        .expect("actor should exist");
+    executor
+        .machine()
+        .unwrap()
+        .blockstore()
+        .get_cbor(&actor.state)
+        .unwrap()
+        .expect("state should exist")
+}
+
+#[test]
+fn reentrant_call_sees_pending_root() {
+    let (sender, mut executor) = instantiate();
+
+    // A sets its state to 1 and calls B, which calls back into A. The second A frame must see the
+    // first frame's state, and the first frame must see the second frame's update once it returns.
+    let receipt = call(
+        &mut executor,
+        sender,
+        METHOD_OUTER,
+        RawBytes::serialize(B).unwrap(),
+    );
+    assert_eq!(receipt.exit_code, ExitCode::OK);
+    let (seen, after): (u64, u64) = receipt.return_data.deserialize().unwrap();
+    assert_eq!(
+        seen, 1,
+        "reentrant frame should observe the outer frame's state"
+    );
+    assert_eq!(
+        after, 11,
+        "outer frame should observe the reentrant frame's state"
+    );
+
+    // The final root is committed.
+    assert_eq!(state_of(&executor, A), 11);
+    assert_eq!(state_of(&executor, B), 0);
+}
+
+#[test]
+fn aborted_root_is_reverted() {
+    let (sender, mut executor) = instantiate();
+
+    let receipt = call(&mut executor, sender, METHOD_ABORT, RawBytes::default());
+    assert_eq!(receipt.exit_code, ExitCode::USR_ASSERTION_FAILED);
+    assert_eq!(state_of(&executor, A), 0);
+}
+
+#[test]
+fn caller_ignores_callee_failure() {
+    let (sender, mut executor) = instantiate();
+
+    // A sets its state to 2 and calls B, which sets its state and aborts. A observes B's exit code,
+    // carries on, and returns successfully; only B's changes are reverted.
+    let receipt = call(
+        &mut executor,
+        sender,
+        METHOD_IGNORE_FAILURE,
+        RawBytes::serialize(B).unwrap(),
+    );
+    assert_eq!(receipt.exit_code, ExitCode::OK);
+    let (code, after): (u32, u64) = receipt.return_data.deserialize().unwrap();
+    assert_eq!(ExitCode::new(code), ExitCode::USR_ASSERTION_FAILED);
+    assert_eq!(after, 2);
+
+    assert_eq!(state_of(&executor, A), 2);
+    assert_eq!(state_of(&executor, B), 0);
+}
@@ -0,0 +1,119 @@
+use fil_send_limits_actor::{METHOD_REQUEST_RETURN, METHOD_SEND_PARAMS, WASM_BINARY};
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, IntegrationExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ErrorNumber;
+use fvm_shared::message::Message;
+use fvm_shared::version::NetworkVersion;
+use num_traits::Zero;
+
+mod bundles;
+use bundles::*;
+
+/// The default maximum params and return sizes, from nv17.
+const MAX_SIZE: u64 = 1 << 20;
+
+const ACTOR: u64 = 10000;
+
+fn instantiate(
+    nv: NetworkVersion,
+) -> (Account, IntegrationExecutor<MemoryBlockstore, DummyExterns>) {
+    let (mut tester, sender) = new_tester_with_actor(
+        nv,
+        WASM_BINARY.unwrap(),
+        &(),
+        &[(ACTOR, TokenAmount::zero())],
+    )
+    .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    (sender, tester.executor.unwrap())
+}
+
+/// Calls the given method with the given size, returning the actor's result: 0 if its inner send
+/// succeeded, or the error number it failed with.
+fn call(
+    executor: &mut IntegrationExecutor<MemoryBlockstore, DummyExterns>,
+    sender: Account,
+    sequence: u64,
+    method_num: u64,
+    size: u64,
+) -> u32 {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR),
+        gas_limit: 1000000000,
+        method_num,
+        sequence,
+        params: RawBytes::serialize(size).unwrap(),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    if !res.msg_receipt.exit_code.is_success() {
+        panic!(
+            "non-zero exit code {}: {:?}",
+            res.msg_receipt.exit_code, res.failure_info
+        )
+    }
+    res.msg_receipt.return_data.deserialize().unwrap()
+}
+
+#[test]
+fn params_size_limit() {
+    let (sender, mut executor) = instantiate(NetworkVersion::V17);
+
+    assert_eq!(
+        call(&mut executor, sender, 0, METHOD_SEND_PARAMS, MAX_SIZE),
+        0
+    );
+    assert_eq!(
+        call(&mut executor, sender, 1, METHOD_SEND_PARAMS, MAX_SIZE + 1),
+        ErrorNumber::LimitExceeded as u32
+    );
+}
+
+#[test]
+fn return_size_limit() {
+    let (sender, mut executor) = instantiate(NetworkVersion::V17);
+
+    assert_eq!(
+        call(&mut executor, sender, 0, METHOD_REQUEST_RETURN, MAX_SIZE),
+        0
+    );
+    assert_eq!(
+        call(
+            &mut executor,
+            sender,
+            1,
+            METHOD_REQUEST_RETURN,
+            MAX_SIZE + 1
+        ),
+        ErrorNumber::LimitExceeded as u32
+    );
+}
+
+#[test]
+fn no_size_limits_before_nv17() {
+    let (sender, mut executor) = instantiate(NetworkVersion::V16);
+
+    assert_eq!(
+        call(&mut executor, sender, 0, METHOD_SEND_PARAMS, MAX_SIZE + 1),
+        0
+    );
+    assert_eq!(
+        call(
+            &mut executor,
+            sender,
+            1,
+            METHOD_REQUEST_RETURN,
+            MAX_SIZE + 1
+        ),
+        0
+    );
+}
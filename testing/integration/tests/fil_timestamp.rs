@@ -0,0 +1,85 @@
+use fil_timestamp_actor::{METHOD_RECORD, WASM_BINARY};
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, Tester};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::version::NetworkVersion;
+use num_traits::Zero;
+
+mod bundles;
+use bundles::*;
+
+const ACTOR: u64 = 10000;
+
+fn record(
+    tester: &mut Tester<MemoryBlockstore, DummyExterns>,
+    sender: Account,
+    sequence: u64,
+) -> (ExitCode, Option<(ChainEpoch, u64)>) {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR),
+        gas_limit: 1000000000,
+        method_num: METHOD_RECORD,
+        sequence,
+        ..Message::default()
+    };
+    let res = tester
+        .executor
+        .as_mut()
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    let ret = res
+        .msg_receipt
+        .exit_code
+        .is_success()
+        .then(|| res.msg_receipt.return_data.deserialize().unwrap());
+    (res.msg_receipt.exit_code, ret)
+}
+
+#[test]
+fn tipset_timestamp() {
+    let (mut tester, sender) = new_tester_with_actor(
+        NetworkVersion::V16,
+        WASM_BINARY.unwrap(),
+        &0u64,
+        &[(ACTOR, TokenAmount::zero())],
+    )
+    .unwrap();
+    tester.set_tipset(ChainEpoch::new(100), 1_600_000_000);
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    // Every message in a tipset sees the same timestamp.
+    assert_eq!(
+        record(&mut tester, sender, 0),
+        (ExitCode::OK, Some((ChainEpoch::new(100), 1_600_000_000)))
+    );
+    assert_eq!(
+        record(&mut tester, sender, 1),
+        (ExitCode::OK, Some((ChainEpoch::new(100), 1_600_000_000)))
+    );
+
+    // The next tipset sees its own, later, timestamp.
+    tester
+        .next_tipset(DummyExterns, ChainEpoch::new(101), 1_600_000_030)
+        .unwrap();
+    assert_eq!(
+        record(&mut tester, sender, 2),
+        (ExitCode::OK, Some((ChainEpoch::new(101), 1_600_000_030)))
+    );
+
+    // A tipset with an earlier timestamp than the one recorded is caught by the actor.
+    tester
+        .next_tipset(DummyExterns, ChainEpoch::new(102), 1_599_999_999)
+        .unwrap();
+    assert_eq!(
+        record(&mut tester, sender, 3),
+        (ExitCode::USR_ASSERTION_FAILED, None)
+    );
+}
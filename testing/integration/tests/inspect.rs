@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use cid::Cid;
+use fil_hello_world_actor::WASM_BINARY;
+use fvm::executor::Executor;
+use fvm::inspect::{dump_actor, dump_all, InspectOptions};
+use fvm::machine::{InspectionMachine, Machine};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::Account;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use multihash::{Code, MultihashDigest};
+use num_traits::Zero;
+use serde_json::{json, Value};
+
+mod bundles;
+use bundles::*;
+
+#[test]
+fn dump_genesis_tree() {
+    let store = Arc::new(MemoryBlockstore::default());
+    let mut tester = new_tester(NetworkVersion::V16, StateTreeVersion::V4, store.clone()).unwrap();
+    let accounts: [Account; 1] = tester.create_accounts().unwrap();
+    let state_cid = tester.set_state(&(1u64, "x")).unwrap();
+    let other = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(WASM_BINARY.unwrap(), state_cid, other, TokenAmount::zero())
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let executor = tester.executor.as_mut().unwrap();
+    let account_code = executor
+        .machine()
+        .unwrap()
+        .builtin_actors()
+        .get_account_code()
+        .to_string();
+    let account_head = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .get_actor(&accounts[0].1)
+        .unwrap()
+        .unwrap()
+        .state
+        .to_string();
+    let root = executor.flush().unwrap();
+    let options = InspectOptions::default();
+
+    let account = dump_actor(&store, &root, &accounts[0].1, &options)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account,
+        json!({
+            "address": "f0100",
+            "code": account_code,
+            "name": "account",
+            "head": account_head,
+            "sequence": 0,
+            "balance": "0.00000000000001",
+            "delegated_address": null,
+            "state": { "address": accounts[0].1.to_string() },
+        })
+    );
+
+    let init = dump_actor(&store, &root, &Address::new_id(1), &options)
+        .unwrap()
+        .unwrap();
+    assert_eq!(init["name"], "init");
+    assert_eq!(
+        init["state"],
+        json!({
+            "address_map": {
+                "count": 1,
+                "entries": { (accounts[0].1.to_string()): "f0100" },
+            },
+            "next_id": 101,
+            "network_name": "test",
+        })
+    );
+
+    // Actors the FVM doesn't know about are rendered as CBOR.
+    let unknown = dump_actor(&store, &root, &other, &options)
+        .unwrap()
+        .unwrap();
+    assert_eq!(unknown["name"], Value::Null);
+    assert_eq!(unknown["state"], json!({ "cbor": "[1, \"x\"]" }));
+
+    // Collections are truncated, but keep their size.
+    let truncated = dump_actor(
+        &store,
+        &root,
+        &Address::new_id(1),
+        &InspectOptions { max_entries: 0 },
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        truncated["state"]["address_map"],
+        json!({ "count": 1, "entries": {} })
+    );
+
+    assert_eq!(
+        dump_actor(&store, &root, &Address::new_id(999), &options).unwrap(),
+        None
+    );
+
+    // Dumping the whole tree renders every actor, one per line.
+    let mut out = Vec::new();
+    dump_all(&store, &root, &mut out, &options).unwrap();
+    let actors: Vec<Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let mut addresses: Vec<_> = actors
+        .iter()
+        .map(|a| a["address"].as_str().unwrap().to_owned())
+        .collect();
+    addresses.sort();
+    assert_eq!(addresses, ["f00", "f01", "f0100", "f010000"]);
+    assert!(actors.contains(&account));
+    assert!(actors.contains(&init));
+    assert!(actors.contains(&unknown));
+}
+
+#[test]
+fn inspection_machine() {
+    let store = Arc::new(MemoryBlockstore::default());
+    let mut tester = new_tester(NetworkVersion::V16, StateTreeVersion::V4, store.clone()).unwrap();
+    let [account]: [Account; 1] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let root = tester.executor.as_mut().unwrap().flush().unwrap();
+
+    // No bundle, engine, or externs needed.
+    let machine = InspectionMachine::new(store.clone(), &root).unwrap();
+    assert_eq!(machine.state_root(), &root);
+
+    let reader = machine.state_reader();
+    assert_eq!(reader.resolve_address(&account.1).unwrap(), Some(account.0));
+    assert_eq!(
+        reader.get_actor_id(account.0).unwrap().unwrap().balance,
+        TokenAmount::from_atto(10000)
+    );
+
+    let options = InspectOptions::default();
+    for address in [account.1, Address::new_id(1)] {
+        assert_eq!(
+            machine.dump_actor(&address, &options).unwrap(),
+            dump_actor(&store, &root, &address, &options).unwrap()
+        );
+    }
+    assert_eq!(
+        machine.dump_actor(&account.1, &options).unwrap().unwrap()["name"],
+        "account"
+    );
+    assert!(machine.diff(&root).unwrap().is_empty());
+
+    // The state root must exist.
+    let missing = Cid::new_v1(0x71, Code::Blake2b256.digest(b"missing"));
+    assert!(InspectionMachine::new(store, &missing).is_err());
+}
@@ -1,6 +1,7 @@
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 use anyhow::anyhow;
 use cid::Cid;
@@ -8,17 +9,34 @@ use fil_hello_world_actor::WASM_BINARY as HELLO_BINARY;
 use fil_ipld_actor::WASM_BINARY as IPLD_BINARY;
 use fil_stack_overflow_actor::WASM_BINARY as OVERFLOW_BINARY;
 use fil_syscall_actor::WASM_BINARY as SYSCALL_BINARY;
-use fvm::executor::{ApplyKind, Executor, ThreadedExecutor};
+use fvm::executor::{
+    ApplyKind, ApplyRet, DefaultExecutor, Executor, SenderPolicy, ThreadedExecutor,
+};
+use fvm::gas::{price_list_by_network_version, Gas, GasCharge, GasMultiplier};
+use fvm::init_actor::INIT_ACTOR_ADDR;
+use fvm::machine::{
+    AbiVersion, CallInterceptor, Engine, FeatureAvailability, InterceptResult, Machine,
+    MachineError, MemoryMetrics, MissingPiece, ModuleCache, ModuleCacheKey, NetworkConfig,
+    ValidationError, WasmFeatureSet,
+};
+use fvm::state_tree::ActorState;
+use fvm::trace::ExecutionEvent;
 use fvm_integration_tests::dummy::DummyExterns;
-use fvm_integration_tests::tester::{Account, IntegrationExecutor};
-use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_integration_tests::tester::{Account, IntegrationExecutor, Tester};
+use fvm_ipld_blockstore::{Block, Blockstore, MemoryBlockstore};
 use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{CborStore, RawBytes};
 use fvm_shared::address::Address;
+use fvm_shared::builtin::singletons::REWARD_ACTOR_ID;
+use fvm_shared::chainid::ChainID;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::{ErrorNumber, ExitCode};
-use fvm_shared::message::Message;
+use fvm_shared::message::{Message, SignedMessage, MAX_MESSAGE_PARAMS_LEN};
+use fvm_shared::receipt::Receipt;
 use fvm_shared::state::StateTreeVersion;
 use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, MethodNum, BLOCK_GAS_LIMIT, IPLD_RAW, METHOD_SEND};
+use multihash::{Code, MultihashDigest};
 use num_traits::Zero;
 use wabt::wat2wasm;
 
@@ -247,14 +265,95 @@ fn native_stack_overflow() {
     assert_eq!(exec_test(&mut executor, 3), 0x80000042);
 }
 
+/// Runs an actor that recurses without bound on a fresh thread with the given native stack size,
+/// returning the exit code, gas used, and failure.
+fn stack_overflow_on_thread(stack_size: usize) -> (ExitCode, i64, String) {
+    std::thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(|| {
+            let mut tester = new_tester(
+                NetworkVersion::V16,
+                StateTreeVersion::V4,
+                MemoryBlockstore::default(),
+            )
+            .unwrap();
+
+            // The only way this can fail (short of running out of gas) is overflowing the stack.
+            let wasm_bin = wat2wasm(
+                r#"(module
+                     (memory (export "memory") 1)
+                     (func $recurse (param $x i32) (result i32)
+                       (call $recurse (i32.add (local.get $x) (i32.const 1))))
+                     (func (export "invoke") (param $x i32) (result i32)
+                       (call $recurse (i32.const 0))))"#,
+            )
+            .unwrap();
+            let sender: [Account; 1] = tester.create_accounts().unwrap();
+            let state_cid = tester.set_state(&State::default()).unwrap();
+            let actor_address = Address::new_id(10000);
+            tester
+                .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+                .unwrap();
+            tester.instantiate_machine(DummyExterns).unwrap();
+
+            let message = Message {
+                from: sender[0].1,
+                to: actor_address,
+                gas_limit: 10_000_000_000,
+                method_num: 1,
+                ..Message::default()
+            };
+            let res = tester
+                .executor
+                .as_mut()
+                .unwrap()
+                .execute_message(message, ApplyKind::Explicit, 100)
+                .unwrap();
+            (
+                res.msg_receipt.exit_code,
+                res.msg_receipt.gas_used,
+                res.failure_info.map(|f| f.to_string()).unwrap_or_default(),
+            )
+        })
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+#[test]
+fn wasm_stack_limit_independent_of_host_stack() {
+    // 2MiB is the default stack size of Rust threads (and so of test threads).
+    let small = stack_overflow_on_thread(2 << 20);
+    let large = stack_overflow_on_thread(512 << 20);
+
+    // Exceeding `NetworkConfig::max_wasm_stack` traps with `unreachable` (inserted by the
+    // instrumented stack limiter) well before the native stack is exhausted, which fails the
+    // invocation with SYS_ILLEGAL_INSTRUCTION. Hitting the native stack guard instead would be a
+    // fatal error, and running out of gas would be SYS_OUT_OF_GAS.
+    assert_eq!(small.0, ExitCode::SYS_ILLEGAL_INSTRUCTION);
+    assert!(small.1 < 10_000_000_000);
+    assert!(small.2.contains("unreachable"), "{}", small.2);
+    assert_eq!(small, large);
+}
+
 fn test_exitcode(wat: &str, code: ExitCode) {
+    test_exitcode_with(NetworkVersion::V16, wat, code, Tester::set_actor_from_bin)
+}
+
+fn test_exitcode_with(
+    nv: NetworkVersion,
+    wat: &str,
+    code: ExitCode,
+    set_actor: impl FnOnce(
+        &mut Tester<MemoryBlockstore, DummyExterns>,
+        &[u8],
+        Cid,
+        Address,
+        TokenAmount,
+    ) -> anyhow::Result<Cid>,
+) {
     // Instantiate tester
-    let mut tester = new_tester(
-        NetworkVersion::V16,
-        StateTreeVersion::V4,
-        MemoryBlockstore::default(),
-    )
-    .unwrap();
+    let mut tester = new_tester(nv, StateTreeVersion::V4, MemoryBlockstore::default()).unwrap();
 
     let sender: [Account; 1] = tester.create_accounts().unwrap();
 
@@ -268,9 +367,14 @@ fn test_exitcode(wat: &str, code: ExitCode) {
     // Set actor
     let actor_address = Address::new_id(10000);
 
-    tester
-        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
-        .unwrap();
+    set_actor(
+        &mut tester,
+        &wasm_bin,
+        state_cid,
+        actor_address,
+        TokenAmount::zero(),
+    )
+    .unwrap();
 
     // Instantiate machine
     tester.instantiate_machine(DummyExterns).unwrap();
@@ -305,43 +409,2453 @@ fn out_of_gas() {
 }
 
 #[test]
-fn unreachable() {
-    test_exitcode(
-        r#"(module
-             (memory (export "memory") 1)
-             (func (export "invoke") (param $x i32) (result i32)
-               unreachable))"#,
-        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+fn zero_gas_limit() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let executor = tester.executor.as_mut().unwrap();
+    let before = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .get_actor(&sender[0].1)
+        .unwrap();
+
+    let message = Message {
+        from: sender[0].1,
+        to: sender[0].1,
+        gas_limit: 0,
+        method_num: METHOD_SEND,
+        value: TokenAmount::from_atto(1),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message.clone(), ApplyKind::Explicit, 100)
+        .unwrap();
+
+    // The message fails preflight: the miner pays for its inclusion, and nothing is executed.
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_OUT_OF_GAS);
+    assert_eq!(res.msg_receipt.gas_used, 0);
+    let inclusion = price_list_by_network_version(NetworkVersion::V16)
+        .on_chain_message(100)
+        .total()
+        .round_up();
+    assert_eq!(
+        res.penalty,
+        &executor
+            .machine()
+            .unwrap()
+            .context()
+            .network_context
+            .base_fee
+            * inclusion
+    );
+    assert!(res.exec_trace.is_empty());
+    assert_eq!(
+        executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .get_actor(&sender[0].1)
+            .unwrap(),
+        before
+    );
+
+    // Gas limits outside [0, BLOCK_GAS_LIMIT] never make it into a block, and fail preflight the
+    // same way.
+    for gas_limit in [-1, BLOCK_GAS_LIMIT + 1, i64::MAX] {
+        let res = executor
+            .execute_message(
+                Message {
+                    gas_limit,
+                    ..message.clone()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_OUT_OF_GAS);
+        assert_eq!(res.msg_receipt.gas_used, 0);
+        assert_eq!(
+            res.penalty,
+            &executor
+                .machine()
+                .unwrap()
+                .context()
+                .network_context
+                .base_fee
+                * inclusion
+        );
+        assert_eq!(
+            executor
+                .machine()
+                .unwrap()
+                .state_tree()
+                .get_actor(&sender[0].1)
+                .unwrap(),
+            before
+        );
+    }
+
+    // Implicit messages aren't bound by the block gas limit, but can't have a negative one.
+    let res = executor
+        .execute_message(
+            Message {
+                gas_limit: i64::MAX,
+                ..message.clone()
+            },
+            ApplyKind::Implicit,
+            100,
+        )
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
     );
+    assert!(executor
+        .execute_message(
+            Message {
+                gas_limit: -1,
+                ..message
+            },
+            ApplyKind::Implicit,
+            100,
+        )
+        .is_err());
 }
 
 #[test]
-fn div_by_zero() {
-    test_exitcode(
-        r#"(module
-             (memory (export "memory") 1)
-             (func (export "invoke") (param $x i32) (result i32)
-               i32.const 10
-               i32.const 0
-               i32.div_u))"#,
-        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+fn actor_gas_charge() {
+    // Charges far more gas than the message's limit, under a custom name.
+    const WAT: &str = r#"
+    (module
+      ;; gas::charge
+      (type (;0;) (func (param i32 i32 i64) (result i32)))
+      (import "gas" "charge" (func $fvm_sdk::sys::gas::charge::syscall (type 0)))
+      (memory (export "memory") 1)
+      (data (i32.const 0) "interpreter_step")
+      (func (export "invoke") (param $x i32) (result i32)
+        (i32.const 0)
+        (i32.const 16)
+        (i64.const -1)
+        (call $fvm_sdk::sys::gas::charge::syscall)
+        drop
+        (i32.const 0)
+      )
+    )
+    "#;
+
+    // Instantiate tester
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    // Set actor
+    let state_cid = tester.set_state(&State::default()).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(WAT).unwrap(),
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    // Instantiate machine
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    // Send message
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 10_000_000,
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let mut executor = ThreadedExecutor(tester.executor.unwrap());
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_OUT_OF_GAS);
+    assert_eq!(res.msg_receipt.gas_used, 10_000_000);
+    assert!(res.exec_trace.iter().any(|ev| matches!(
+        ev,
+        ExecutionEvent::GasCharge(charge) if charge.name == "interpreter_step"
+    )));
+}
+
+// Implicit messages (e.g., cron) don't count toward the block gas limit, however much gas they use,
+// while explicit messages count their whole gas limit.
+#[test]
+fn block_gas_accounting() {
+    // Charges twice the block gas limit.
+    const WAT: &str = r#"
+    (module
+      (import "gas" "charge" (func $charge (param i32 i32 i64) (result i32)))
+      (memory (export "memory") 1)
+      (data (i32.const 0) "cron")
+      (func (export "invoke") (param $x i32) (result i32)
+        (drop (call $charge (i32.const 0) (i32.const 4) (i64.const 20000000000)))
+        (i32.const 0)
+      )
+    )
+    "#;
+
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [(_, sender), (_, receiver)]: [Account; 2] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&State::default()).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(WAT).unwrap(),
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let res = executor
+        .execute_message(
+            Message {
+                from: Address::new_id(0),
+                to: actor_address,
+                gas_limit: i64::MAX,
+                method_num: 1,
+                ..Message::default()
+            },
+            ApplyKind::Implicit,
+            100,
+        )
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert!(res.msg_receipt.gas_used > BLOCK_GAS_LIMIT);
+    assert_eq!(res.gas_burned_for_block_accounting, 0);
+
+    // Explicit messages count their gas limit, however much gas they use.
+    let mut block_gas = res.gas_burned_for_block_accounting;
+    for sequence in 0..2 {
+        let res = executor
+            .execute_message(
+                Message {
+                    from: sender,
+                    to: receiver,
+                    gas_limit: BLOCK_GAS_LIMIT / 2,
+                    method_num: METHOD_SEND,
+                    value: TokenAmount::from_atto(1),
+                    sequence,
+                    ..Message::default()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+        assert!(res.msg_receipt.gas_used > 0);
+        assert!(res.msg_receipt.gas_used < BLOCK_GAS_LIMIT / 2);
+        assert_eq!(res.gas_burned_for_block_accounting, BLOCK_GAS_LIMIT / 2);
+        block_gas += res.gas_burned_for_block_accounting;
+    }
+    assert_eq!(block_gas, BLOCK_GAS_LIMIT);
+
+    // So do messages failing preflight.
+    let res = executor
+        .execute_message(
+            Message {
+                from: sender,
+                to: receiver,
+                gas_limit: 100,
+                method_num: METHOD_SEND,
+                sequence: 2,
+                ..Message::default()
+            },
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_OUT_OF_GAS);
+    assert_eq!(res.msg_receipt.gas_used, 0);
+    assert_eq!(res.gas_burned_for_block_accounting, 100);
+
+    // Out-of-range gas limits only count what a block can hold.
+    for (gas_limit, accounted) in [(-1, 0), (i64::MIN, 0), (i64::MAX, BLOCK_GAS_LIMIT)] {
+        let res = executor
+            .execute_message(
+                Message {
+                    from: sender,
+                    to: receiver,
+                    gas_limit,
+                    method_num: METHOD_SEND,
+                    sequence: 2,
+                    ..Message::default()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_OUT_OF_GAS);
+        assert_eq!(res.gas_burned_for_block_accounting, accounted);
+    }
+}
+
+#[test]
+fn send_to_new_bls_address() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let receiver = Address::new_bls(&[7; fvm_shared::address::BLS_PUB_LEN]).unwrap();
+    let value = TokenAmount::from_atto(100);
+    let message = Message {
+        from: sender[0].1,
+        to: receiver,
+        gas_limit: 1_000_000_000,
+        method_num: METHOD_SEND,
+        value: value.clone(),
+        ..Message::default()
+    };
+
+    let executor = tester.executor.as_mut().unwrap();
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    // The account is created, and receives the funds.
+    let actor = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .get_actor(&receiver)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        &actor.code,
+        executor
+            .machine()
+            .unwrap()
+            .builtin_actors()
+            .get_account_code()
     );
+    assert_eq!(actor.balance, value);
+    assert_eq!(actor.sequence, 0);
+
+    // Creating the account is charged exactly once, under its own name, at the cost of creating
+    // an actor.
+    let expected = price_list_by_network_version(NetworkVersion::V16).on_create_actor();
+    let charges: Vec<_> = res
+        .exec_trace
+        .iter()
+        .filter_map(|ev| match ev {
+            ExecutionEvent::GasCharge(charge) if charge.name == "OnCreateAccountActor" => {
+                Some(charge)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(charges.len(), 1);
+    assert_eq!(charges[0].compute_gas, expected.compute_gas);
+    assert_eq!(charges[0].other_gas, expected.other_gas);
 }
 
 #[test]
-fn out_of_stack() {
-    test_exitcode(
-        r#"(module
-             (memory (export "memory") 1)
-             (func (export "invoke") (param $x i32) (result i32)
-               (i64.const 123)
-               (call 1)
-               (drop)
-               (i32.const 0))
-             (func (param $x i64) (result i64)
-               (local.get 0)
-               (call 1)))"#,
-        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+fn send_to_new_f4_address() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    // Any existing actor can serve as the namespace.
+    let receiver = Address::new_delegated(sender[0].0, b"placeholder").unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+    let send = |executor: &mut IntegrationExecutor<MemoryBlockstore, DummyExterns>,
+                sequence,
+                method_num,
+                value: u64| {
+        let message = Message {
+            from: sender[0].1,
+            to: receiver,
+            gas_limit: 1_000_000_000,
+            method_num,
+            sequence,
+            value: TokenAmount::from_atto(value),
+            ..Message::default()
+        };
+        executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap()
+            .msg_receipt
+            .exit_code
+    };
+
+    // Funds sent to the address accumulate on a single placeholder.
+    assert_eq!(send(executor, 0, METHOD_SEND, 100), ExitCode::OK);
+    let id = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .lookup_id(&receiver)
+        .unwrap()
+        .unwrap();
+    assert_eq!(send(executor, 1, METHOD_SEND, 50), ExitCode::OK);
+    assert_eq!(
+        executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .lookup_id(&receiver)
+            .unwrap(),
+        Some(id)
+    );
+
+    let actor = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .get_actor_id(id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        Some(&actor.code),
+        executor
+            .machine()
+            .unwrap()
+            .builtin_actors()
+            .get_placeholder_code()
+    );
+    assert_eq!(actor.balance, TokenAmount::from_atto(150));
+    assert_eq!(actor.delegated_address, Some(receiver));
+
+    // Placeholders can't be invoked, and the failed call transfers nothing.
+    assert_eq!(send(executor, 2, 5, 10), ExitCode::SYS_INVALID_RECEIVER);
+    let actor = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .get_actor_id(id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(actor.balance, TokenAmount::from_atto(150));
+}
+
+#[test]
+fn send_to_new_f4_address_v4_tree() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    // V4 state trees can't record delegated addresses, so there's nowhere to put a placeholder.
+    let receiver = Address::new_delegated(sender[0].0, b"placeholder").unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+    let message = Message {
+        from: sender[0].1,
+        to: receiver,
+        gas_limit: 1_000_000_000,
+        method_num: METHOD_SEND,
+        value: TokenAmount::from_atto(100),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_INVALID_RECEIVER);
+    assert_eq!(
+        executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .lookup_id(&receiver)
+            .unwrap(),
+        None
+    );
+}
+
+#[derive(Serialize_tuple)]
+struct ExecParams {
+    code_cid: Cid,
+    constructor_params: RawBytes,
+}
+
+#[derive(Deserialize_tuple)]
+struct ExecReturn {
+    id_address: Address,
+    #[allow(dead_code)]
+    robust_address: Address,
+}
+
+#[derive(Serialize_tuple)]
+struct MultisigConstructorParams {
+    signers: Vec<Address>,
+    num_approvals_threshold: u64,
+    unlock_duration: i64,
+    start_epoch: i64,
+}
+
+#[derive(Serialize_tuple)]
+struct MultisigProposeParams {
+    to: Address,
+    value: TokenAmount,
+    method: u64,
+    params: RawBytes,
+}
+
+#[derive(Serialize_tuple)]
+struct MultisigTxnIdParams {
+    id: i64,
+    proposal_hash: RawBytes,
+}
+
+/// Sends nothing to new secp256k1 addresses, creating an account each time, until a send fails. It
+/// then aborts with 100 + the number of accounts created if invoked with method 1 and the send
+/// failed with `LimitExceeded`, and returns if invoked with method 2.
+const CREATE_ACCOUNTS_WAT: &str = r#"
+(module
+  (import "vm" "context" (func $context (param i32) (result i32)))
+  (import "vm" "abort" (func $abort (param i32 i32 i32) (result i32)))
+  (import "send" "send" (func $send (param i32 i32 i32 i64 i32 i64 i64) (result i32)))
+  (memory (export "memory") 1)
+  ;; The recipient: the secp256k1 protocol byte, followed by a 20 byte payload.
+  (data (i32.const 0) "\01")
+  (func (export "invoke") (param $x i32) (result i32)
+    (local $created i32)
+    (local $errno i32)
+    (drop (call $context (i32.const 256)))
+    (loop $create
+      (i32.store (i32.const 1) (i32.add (local.get $created) (i32.const 1)))
+      (local.set $errno (call $send
+        (i32.const 64) (i32.const 0) (i32.const 21) (i64.const 0) (i32.const 0) (i64.const 0) (i64.const 0)))
+      (if (i32.eqz (local.get $errno))
+        (then
+          (local.set $created (i32.add (local.get $created) (i32.const 1)))
+          (br $create))))
+    (if (i32.ne (local.get $errno) (i32.const 3))
+      (then (drop (call $abort (i32.const 99) (i32.const 0) (i32.const 0)))))
+    ;; The method number is at offset 32 of the invocation context.
+    (if (i64.eq (i64.load (i32.const 288)) (i64.const 1))
+      (then (drop (call $abort (i32.add (i32.const 100) (local.get $created)) (i32.const 0) (i32.const 0)))))
+    (i32.const 0)
+  )
+)
+"#;
+
+// A message can't create more than `max_actors_created` actors: the send that would create one
+// more fails with LimitExceeded. The actors created before are reverted along with the caller if it
+// aborts, but still counted.
+#[test]
+fn actor_creation_limit() {
+    const MAX: u64 = 3;
+
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [(_, sender)]: [Account; 1] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&State::default()).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(CREATE_ACCOUNTS_WAT).unwrap(),
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester.limit_actors_created(MAX);
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    // The address of the nth account the actor creates.
+    let account = |n: u32| {
+        let mut bytes = vec![0; 21];
+        bytes[0] = 1;
+        bytes[1..5].copy_from_slice(&(n + 1).to_le_bytes());
+        Address::from_bytes(&bytes).unwrap()
+    };
+
+    let apply = |executor: &mut IntegrationExecutor<MemoryBlockstore, DummyExterns>,
+                 sequence,
+                 method_num| {
+        executor
+            .execute_message(
+                Message {
+                    from: sender,
+                    to: actor_address,
+                    gas_limit: 1_000_000_000,
+                    method_num,
+                    sequence,
+                    ..Message::default()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap()
+    };
+
+    // The actor aborts after creating MAX accounts, reverting them.
+    let res = apply(executor, 0, 1);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::new(100 + MAX as u32));
+    assert_eq!(res.actors_created, MAX);
+    let counts: Vec<_> = res
+        .exec_trace
+        .iter()
+        .filter_map(|ev| match ev {
+            ExecutionEvent::ActorCreated { count, .. } => Some(*count),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(counts, (1..=MAX).collect::<Vec<_>>());
+    for n in 0..=MAX as u32 {
+        assert_eq!(
+            executor
+                .machine()
+                .unwrap()
+                .state_tree()
+                .lookup_id(&account(n))
+                .unwrap(),
+            None
+        );
+    }
+
+    // The actor returns after creating MAX accounts, keeping them.
+    let res = apply(executor, 1, 2);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert_eq!(res.actors_created, MAX);
+    for n in 0..MAX as u32 {
+        assert!(executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .lookup_id(&account(n))
+            .unwrap()
+            .is_some());
+    }
+    assert_eq!(
+        executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .lookup_id(&account(MAX as u32))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn actors_accessed_by_message() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [alice, bob, carol]: [Account; 3] = tester.create_accounts().unwrap();
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let executor = tester.executor.as_mut().unwrap();
+    let multisig_code = *executor
+        .machine()
+        .unwrap()
+        .builtin_actors()
+        .code_by_name("multisig")
+        .unwrap();
+    let mut apply = |from: Account, sequence, to, method_num, params, value: u64| {
+        let ret = executor
+            .execute_message(
+                Message {
+                    from: from.1,
+                    to,
+                    gas_limit: 1_000_000_000,
+                    method_num,
+                    sequence,
+                    params,
+                    value: TokenAmount::from_atto(value),
+                    ..Message::default()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap();
+        assert_eq!(
+            ret.msg_receipt.exit_code,
+            ExitCode::OK,
+            "{:?}",
+            ret.failure_info
+        );
+        ret
+    };
+
+    // Alice creates and funds a 2-of-2 multisig with Bob.
+    let ret = apply(
+        alice,
+        0,
+        INIT_ACTOR_ADDR,
+        2,
+        RawBytes::serialize(ExecParams {
+            code_cid: multisig_code,
+            constructor_params: RawBytes::serialize(MultisigConstructorParams {
+                signers: vec![alice.1, bob.1],
+                num_approvals_threshold: 2,
+                unlock_duration: 0,
+                start_epoch: 0,
+            })
+            .unwrap(),
+        })
+        .unwrap(),
+        1000,
+    );
+    let multisig = ret
+        .msg_receipt
+        .return_data
+        .deserialize::<ExecReturn>()
+        .unwrap()
+        .id_address
+        .id()
+        .unwrap();
+
+    // Alice proposes a transfer to Carol.
+    apply(
+        alice,
+        1,
+        Address::new_id(multisig),
+        2,
+        RawBytes::serialize(MultisigProposeParams {
+            to: carol.1,
+            value: TokenAmount::from_atto(100),
+            method: METHOD_SEND,
+            params: RawBytes::default(),
+        })
+        .unwrap(),
+        0,
+    );
+
+    // Bob's approval executes the transfer.
+    let ret = apply(
+        bob,
+        0,
+        Address::new_id(multisig),
+        3,
+        RawBytes::serialize(MultisigTxnIdParams {
+            id: 0,
+            proposal_hash: RawBytes::default(),
+        })
+        .unwrap(),
+        0,
+    );
+
+    for id in [bob.0, multisig, carol.0] {
+        assert!(ret.actors_modified.contains(&id), "{} not modified", id);
+        assert!(ret.actors_read.contains(&id), "{} not read", id);
+    }
+    assert!(!ret.actors_modified.contains(&alice.0));
+    assert!(ret.actors_modified.windows(2).all(|w| w[0] < w[1]));
+    assert!(ret.actors_read.windows(2).all(|w| w[0] < w[1]));
+}
+
+/// Creates a 1-of-1 multisig, then proposes a transaction to it with the given (raw) params.
+/// Returns the proposal's exit code and gas used.
+fn propose_to_multisig(validate_params: bool, params: RawBytes) -> (ExitCode, i64) {
+    propose_to_multisig_with(
+        |tester| {
+            if validate_params {
+                tester.enable_builtin_param_validation();
+            }
+        },
+        params,
+    )
+}
+
+/// Like [`propose_to_multisig`], but configures the tester with `configure` before instantiating
+/// the machine.
+fn propose_to_multisig_with(
+    configure: impl FnOnce(&mut Tester<MemoryBlockstore, DummyExterns>),
+    params: RawBytes,
+) -> (ExitCode, i64) {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [owner]: [Account; 1] = tester.create_accounts().unwrap();
+    configure(&mut tester);
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let executor = tester.executor.as_mut().unwrap();
+    let multisig_code = *executor
+        .machine()
+        .unwrap()
+        .builtin_actors()
+        .code_by_name("multisig")
+        .unwrap();
+    let mut apply = |sequence, to, method_num, params| {
+        executor
+            .execute_message(
+                Message {
+                    from: owner.1,
+                    to,
+                    gas_limit: 1_000_000_000,
+                    method_num,
+                    sequence,
+                    params,
+                    ..Message::default()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap()
+            .msg_receipt
+    };
+
+    let receipt = apply(
+        0,
+        INIT_ACTOR_ADDR,
+        2,
+        RawBytes::serialize(ExecParams {
+            code_cid: multisig_code,
+            constructor_params: RawBytes::serialize(MultisigConstructorParams {
+                signers: vec![owner.1],
+                num_approvals_threshold: 1,
+                unlock_duration: 0,
+                start_epoch: 0,
+            })
+            .unwrap(),
+        })
+        .unwrap(),
+    );
+    assert_eq!(receipt.exit_code, ExitCode::OK);
+    let multisig = receipt
+        .return_data
+        .deserialize::<ExecReturn>()
+        .unwrap()
+        .id_address;
+
+    let receipt = apply(1, multisig, 2, params);
+    (receipt.exit_code, receipt.gas_used)
+}
+
+#[test]
+fn builtin_param_validation() {
+    // A CBOR integer where the actor expects a tuple.
+    let garbage = RawBytes::new(vec![0x01]);
+
+    let (unchecked_code, unchecked_gas) = propose_to_multisig(false, garbage.clone());
+    let (checked_code, checked_gas) = propose_to_multisig(true, garbage);
+    assert_eq!(unchecked_code, ExitCode::USR_SERIALIZATION);
+    assert_eq!(checked_code, ExitCode::USR_SERIALIZATION);
+    // Validation skips loading and running the actor.
+    assert!(
+        checked_gas < unchecked_gas,
+        "{} >= {}",
+        checked_gas,
+        unchecked_gas
+    );
+
+    // Valid params are unaffected.
+    let propose = RawBytes::serialize(MultisigProposeParams {
+        to: Address::new_id(100),
+        value: TokenAmount::zero(),
+        method: METHOD_SEND,
+        params: RawBytes::default(),
+    })
+    .unwrap();
+    assert_eq!(
+        propose_to_multisig(false, propose.clone()),
+        propose_to_multisig(true, propose)
+    );
+}
+
+// A syscall gated at nv16 fails with IllegalOperation before then, without reaching the kernel.
+#[test]
+fn gated_syscall_availability() {
+    // Aborts with 16 + the error number returned by `network::chain_id`, if any.
+    const WAT: &str = r#"
+    (module
+      (import "network" "chain_id" (func $chain_id (param i32) (result i32)))
+      (import "vm" "abort" (func $abort (param i32 i32 i32) (result i32)))
+      (memory (export "memory") 1)
+      (func (export "invoke") (param $x i32) (result i32)
+        (local $errno i32)
+        (local.set $errno (call $chain_id (i32.const 0)))
+        (if (local.get $errno)
+          (then (drop (call $abort (i32.add (i32.const 16) (local.get $errno)) (i32.const 0) (i32.const 0)))))
+        (i32.const 0)
+      )
+    )
+    "#;
+
+    let chain_id_gate = FeatureAvailability::default().syscall_since("network", "chain_id");
+    assert_eq!(chain_id_gate, Some(NetworkVersion::V16));
+
+    for (nv, expected) in [
+        (
+            NetworkVersion::V15,
+            ExitCode::new(16 + ErrorNumber::IllegalOperation as u32),
+        ),
+        (NetworkVersion::V16, ExitCode::OK),
+    ] {
+        let mut tester = new_tester(nv, StateTreeVersion::V4, MemoryBlockstore::default()).unwrap();
+        let [(_, sender)]: [Account; 1] = tester.create_accounts().unwrap();
+        let state_cid = tester.set_state(&State::default()).unwrap();
+        let actor_address = Address::new_id(10000);
+        tester
+            .set_actor_from_bin(
+                &wat2wasm(WAT).unwrap(),
+                state_cid,
+                actor_address,
+                TokenAmount::zero(),
+            )
+            .unwrap();
+        tester.instantiate_machine(DummyExterns).unwrap();
+
+        let res = tester
+            .executor
+            .as_mut()
+            .unwrap()
+            .execute_message(
+                Message {
+                    from: sender,
+                    to: actor_address,
+                    gas_limit: 10_000_000,
+                    method_num: 1,
+                    ..Message::default()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, expected, "nv{}", nv);
+    }
+}
+
+// A builtin method gated at a later network version is unhandled, without instantiating the actor.
+#[test]
+fn gated_builtin_method() {
+    let propose = RawBytes::serialize(MultisigProposeParams {
+        to: Address::new_id(100),
+        value: TokenAmount::zero(),
+        method: METHOD_SEND,
+        params: RawBytes::default(),
+    })
+    .unwrap();
+
+    let (ungated_code, ungated_gas) = propose_to_multisig_with(|_| (), propose.clone());
+    assert_eq!(ungated_code, ExitCode::OK);
+
+    // Gate Propose (method 2) at nv17, on an nv16 network.
+    let (gated_code, gated_gas) = propose_to_multisig_with(
+        |tester| {
+            let mut availability = FeatureAvailability::default();
+            availability.gate_method("multisig", 2, NetworkVersion::V17);
+            tester.set_feature_availability(availability);
+        },
+        propose,
+    );
+    assert_eq!(gated_code, ExitCode::USR_UNHANDLED_MESSAGE);
+    assert!(gated_gas < ungated_gas, "{} >= {}", gated_gas, ungated_gas);
+}
+
+/// Fakes the reward actor's `ThisEpochReward` (method 3) and fails its `UpdateNetworkKPI` (method
+/// 4), recording the intercepted calls.
+#[derive(Debug, Default)]
+struct FakeReward {
+    intercepted: Vec<(ActorID, MethodNum, TokenAmount)>,
+}
+
+impl CallInterceptor for FakeReward {
+    fn intercept(
+        &mut self,
+        from: ActorID,
+        to: ActorID,
+        method: MethodNum,
+        _params: Option<&fvm::kernel::Block>,
+        value: &TokenAmount,
+    ) -> Option<InterceptResult> {
+        if to != REWARD_ACTOR_ID {
+            return None;
+        }
+        let exit_code = match method {
+            3 => ExitCode::OK,
+            4 => ExitCode::USR_FORBIDDEN,
+            _ => return None,
+        };
+        self.intercepted.push((from, method, value.clone()));
+        Some(InterceptResult {
+            exit_code,
+            return_data: RawBytes::serialize(42u64).unwrap(),
+            gas: Gas::new(1_000_000),
+        })
+    }
+}
+
+#[test]
+fn intercept_calls() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender]: [Account; 1] = tester.create_accounts().unwrap();
+    let interceptor = Arc::new(Mutex::new(FakeReward::default()));
+    tester.set_call_interceptor(interceptor.clone());
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let executor = tester.executor.as_mut().unwrap();
+    // The reward actor is never invoked, so it doesn't need any state.
+    let reward_code = *executor
+        .machine()
+        .unwrap()
+        .builtin_actors()
+        .code_by_name("reward")
+        .unwrap();
+    executor
+        .machine_mut()
+        .unwrap()
+        .state_tree_mut()
+        .set_actor_id(
+            REWARD_ACTOR_ID,
+            ActorState::new(reward_code, *fvm::EMPTY_ARR_CID, TokenAmount::zero(), 0),
+        )
+        .unwrap();
+
+    let mut call = |sequence, method_num| {
+        executor
+            .execute_message(
+                Message {
+                    from: sender.1,
+                    to: Address::new_id(REWARD_ACTOR_ID),
+                    gas_limit: 1_000_000_000,
+                    method_num,
+                    sequence,
+                    value: TokenAmount::from_atto(10),
+                    ..Message::default()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap()
+    };
+
+    // The faked result is returned, the declared gas is charged, and the value is transferred.
+    let ret = call(0, 3);
+    assert_eq!(ret.msg_receipt.exit_code, ExitCode::OK);
+    assert_eq!(
+        ret.msg_receipt.return_data.deserialize::<u64>().unwrap(),
+        42
+    );
+    let intercepted = ret
+        .exec_trace
+        .iter()
+        .position(|ev| matches!(ev, ExecutionEvent::Intercepted(ExitCode::OK)))
+        .expect("no interception in the trace");
+    assert!(matches!(
+        &ret.exec_trace[intercepted + 1],
+        ExecutionEvent::GasCharge(charge)
+            if charge.name == "OnInterceptedCall" && charge.total() == Gas::new(1_000_000)
+    ));
+    assert!(ret.msg_receipt.gas_used > 1_000_000);
+
+    // A faked failure reverts the transfer.
+    let ret = call(1, 4);
+    assert_eq!(ret.msg_receipt.exit_code, ExitCode::USR_FORBIDDEN);
+
+    let executor = tester.executor.as_ref().unwrap();
+    let reward = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .get_actor_id(REWARD_ACTOR_ID)
+        .unwrap()
+        .unwrap();
+    assert_eq!(reward.balance, TokenAmount::from_atto(10));
+    assert_eq!(
+        interceptor.lock().unwrap().intercepted,
+        [
+            (sender.0, 3, TokenAmount::from_atto(10)),
+            (sender.0, 4, TokenAmount::from_atto(10))
+        ]
+    );
+}
+
+#[test]
+fn send_to_new_secp_address_trace() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let receiver = Address::new_secp256k1(&[4; 65]).unwrap();
+    let message = Message {
+        from: sender[0].1,
+        to: receiver,
+        gas_limit: 1_000_000_000,
+        method_num: METHOD_SEND,
+        value: TokenAmount::from_atto(100),
+        ..Message::default()
+    };
+
+    let executor = tester.executor.as_mut().unwrap();
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    let receiver_id = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .lookup_id(&receiver)
+        .unwrap()
+        .unwrap();
+
+    // The constructor is an implicit call nested in the send, and its params are charged like
+    // those of any other send.
+    let implicit = res
+        .exec_trace
+        .iter()
+        .position(|ev| matches!(ev, ExecutionEvent::ImplicitCall { .. }))
+        .expect("no implicit call");
+    assert!(matches!(
+        &res.exec_trace[implicit - 1],
+        ExecutionEvent::GasCharge(charge) if charge.name == "OnSendParams"
+    ));
+
+    let calls: Vec<_> = res
+        .exec_trace
+        .iter()
+        .filter(|ev| !matches!(ev, ExecutionEvent::GasCharge(_)))
+        .collect();
+    assert_eq!(calls.len(), 5, "{:?}", calls);
+    match calls[0] {
+        ExecutionEvent::Call {
+            from, to, method, ..
+        } => {
+            assert_eq!((*from, *to, *method), (sender[0].0, receiver, METHOD_SEND));
+        }
+        ev => panic!("expected the send, got {:?}", ev),
+    }
+    match calls[1] {
+        ExecutionEvent::ImplicitCall {
+            from,
+            to,
+            method,
+            params,
+            value,
+        } => {
+            assert_eq!(*from, 0);
+            assert_eq!(*to, Address::new_id(receiver_id));
+            assert_eq!(*method, fvm_shared::METHOD_CONSTRUCTOR);
+            assert_eq!(params.deserialize::<Address>().unwrap(), receiver);
+            assert!(value.is_zero());
+        }
+        ev => panic!("expected the constructor, got {:?}", ev),
+    }
+    // Only the account actor's code is invoked: plain sends don't run the receiver.
+    assert!(matches!(calls[2], ExecutionEvent::Invoke(AbiVersion::V1)));
+    assert!(matches!(calls[3], ExecutionEvent::CallReturn(_)));
+    assert!(matches!(calls[4], ExecutionEvent::CallReturn(_)));
+
+    // Every unit of gas used is attributed to some charge in the trace.
+    let traced = res
+        .exec_trace
+        .iter()
+        .filter_map(|ev| match ev {
+            ExecutionEvent::GasCharge(charge) => Some(charge.total()),
+            _ => None,
+        })
+        .fold(Gas::zero(), |acc, gas| acc + gas);
+    assert_eq!(traced.round_up(), res.msg_receipt.gas_used);
+}
+
+#[test]
+fn unreachable() {
+    test_exitcode(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               unreachable))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+    );
+}
+
+#[test]
+fn div_by_zero() {
+    test_exitcode(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               i32.const 10
+               i32.const 0
+               i32.div_u))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+    );
+}
+
+#[test]
+fn out_of_stack() {
+    test_exitcode(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i64.const 123)
+               (call 1)
+               (drop)
+               (i32.const 0))
+             (func (param $x i64) (result i64)
+               (local.get 0)
+               (call 1)))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+    );
+}
+
+#[test]
+fn invalid_import() {
+    test_exitcode_with(
+        NetworkVersion::V16,
+        r#"(module
+             (import "bogus" "func" (func))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+        Tester::set_actor_from_bin_unloaded,
+    );
+}
+
+#[test]
+fn float_instructions() {
+    test_exitcode_with(
+        NetworkVersion::V16,
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (f64.const 1.5)
+               (drop)
+               (i32.const 0)))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+        Tester::set_actor_from_bin_unloaded,
+    );
+}
+
+/// Modules using each optional Wasm proposal, with the feature set that enables it.
+fn wasm_feature_modules() -> Vec<(&'static str, &'static str, WasmFeatureSet)> {
+    let enabled = |f: fn(&mut WasmFeatureSet)| {
+        let mut features = WasmFeatureSet::default();
+        f(&mut features);
+        features
+    };
+    vec![
+        (
+            "simd",
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func (export "invoke") (param $x i32) (result i32)
+                   (v128.const i64x2 0 0)
+                   (drop)
+                   (i32.const 0)))"#,
+            enabled(|f| f.simd = true),
+        ),
+        (
+            "bulk memory",
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func (export "invoke") (param $x i32) (result i32)
+                   (memory.fill (i32.const 0) (i32.const 0) (i32.const 0))
+                   (i32.const 0)))"#,
+            WasmFeatureSet::default(),
+        ),
+        (
+            "reference types",
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func (export "invoke") (param $x i32) (result i32)
+                   (ref.null extern)
+                   (drop)
+                   (i32.const 0)))"#,
+            enabled(|f| f.reference_types = true),
+        ),
+        (
+            "multi-value",
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func $pair (result i32 i32)
+                   (i32.const 0)
+                   (i32.const 0))
+                 (func (export "invoke") (param $x i32) (result i32)
+                   (call $pair)
+                   (drop)))"#,
+            enabled(|f| f.multi_value = true),
+        ),
+        (
+            "threads",
+            r#"(module
+                 (memory (export "memory") 1 1 shared)
+                 (func (export "invoke") (param $x i32) (result i32)
+                   (i32.atomic.load (i32.const 0))))"#,
+            WasmFeatureSet::default(),
+        ),
+    ]
+}
+
+#[test]
+fn wasm_feature_gates() {
+    let all_features = || {
+        let mut features = wabt::Features::new();
+        features.enable_all();
+        features
+    };
+
+    let load = |features: &WasmFeatureSet, wasm: &[u8]| {
+        let mut nc = NetworkConfig::new(NetworkVersion::V16);
+        nc.wasm_features = features.clone();
+        let engine = Engine::new_default((&nc).into()).unwrap();
+        engine.prepare_wasm_bytecode(&Cid::new_v1(IPLD_RAW, Code::Identity.digest(wasm)), wasm)
+    };
+
+    let mut nothing = WasmFeatureSet::default();
+    nothing.bulk_memory = false;
+
+    for (name, wat, features) in wasm_feature_modules() {
+        let wasm = wabt::wat2wasm_with_features(wat, all_features()).unwrap();
+
+        // Rejected when loaded, as invalid actor code, if the proposal is disabled.
+        let err = load(&nothing, &wasm).expect_err(name);
+        assert!(err.is::<ValidationError>(), "{}: {:#}", name, err);
+
+        // If enabled, the module either loads or is rejected as invalid actor code (e.g., because
+        // the instrumentation doesn't support it), but it never fails in any other way.
+        if let Err(err) = load(&features, &wasm) {
+            assert!(err.is::<ValidationError>(), "{}: {:#}", name, err);
+        }
+    }
+
+    // Reference types can't be enabled without bulk memory.
+    let mut features = WasmFeatureSet::default();
+    features.bulk_memory = false;
+    features.reference_types = true;
+    let mut nc = NetworkConfig::new(NetworkVersion::V16);
+    nc.wasm_features = features;
+    assert!(Engine::new_default((&nc).into()).is_err());
+}
+
+// Actors following different ABI versions can run side by side, and the trace records the version
+// each was invoked with before the actor runs.
+#[test]
+fn abi_versions() {
+    let mut tester = new_tester(
+        NetworkVersion::V17,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+    let state_cid = tester.set_state(&State::default()).unwrap();
+
+    let v1_actor = Address::new_id(10000);
+    let v1_bin = wat2wasm(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    tester
+        .set_actor_from_bin(&v1_bin, state_cid, v1_actor, TokenAmount::zero())
+        .unwrap();
+
+    let v2_actor = Address::new_id(10001);
+    let v2_bin = wat2wasm(
+        r#"(module
+             (global (export "abi_version") i32 (i32.const 2))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i64)
+               (i64.const 0)))"#,
+    )
+    .unwrap();
+    tester
+        .set_actor_from_bin(&v2_bin, state_cid, v2_actor, TokenAmount::zero())
+        .unwrap();
+
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    for (sequence, (to, version)) in [(v1_actor, AbiVersion::V1), (v2_actor, AbiVersion::V2)]
+        .into_iter()
+        .enumerate()
+    {
+        let message = Message {
+            from: sender[0].1,
+            to,
+            gas_limit: 10_000_000,
+            method_num: 1,
+            sequence: sequence as u64,
+            ..Message::default()
+        };
+        let res = executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::OK, "{:?}", to);
+        assert!(res.msg_receipt.return_data.is_empty());
+
+        let invoked: Vec<_> = res
+            .exec_trace
+            .iter()
+            .enumerate()
+            .filter_map(|(i, ev)| match ev {
+                ExecutionEvent::Invoke(version) => Some((i, *version)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(invoked.len(), 1);
+        assert_eq!(invoked[0].1, version);
+
+        // The actor's execution is charged after it's invoked.
+        let exec = res
+            .exec_trace
+            .iter()
+            .position(
+                |ev| matches!(ev, ExecutionEvent::GasCharge(charge) if charge.name == "wasm_exec"),
+            )
+            .expect("no execution charge");
+        assert!(invoked[0].0 < exec, "{:?}", res.exec_trace);
+    }
+}
+
+#[test]
+fn abi_v2_exit_code() {
+    // 16 << 32: USR_ILLEGAL_ARGUMENT in the high bits, and no return block.
+    test_exitcode_with(
+        NetworkVersion::V17,
+        r#"(module
+             (global (export "abi_version") i32 (i32.const 2))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i64)
+               (i64.const 68719476736)))"#,
+        ExitCode::USR_ILLEGAL_ARGUMENT,
+        Tester::set_actor_from_bin,
+    );
+}
+
+#[test]
+fn abi_v2_system_exit_code() {
+    // 4 << 32: actors may not return system exit codes, just like they may not abort with them.
+    test_exitcode_with(
+        NetworkVersion::V17,
+        r#"(module
+             (global (export "abi_version") i32 (i32.const 2))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i64)
+               (i64.const 17179869184)))"#,
+        ExitCode::SYS_ILLEGAL_EXIT_CODE,
+        Tester::set_actor_from_bin,
+    );
+}
+
+#[test]
+fn abi_v2_before_nv17() {
+    // The V2 ABI is only supported from nv17.
+    test_exitcode_with(
+        NetworkVersion::V16,
+        r#"(module
+             (global (export "abi_version") i32 (i32.const 2))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i64)
+               (i64.const 0)))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+        Tester::set_actor_from_bin,
+    );
+}
+
+#[test]
+fn unsupported_abi_version() {
+    test_exitcode_with(
+        NetworkVersion::V16,
+        r#"(module
+             (global (export "abi_version") i32 (i32.const 3))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i64)
+               (i64.const 0)))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+        Tester::set_actor_from_bin_unloaded,
+    );
+}
+
+#[test]
+fn mutable_abi_version() {
+    test_exitcode_with(
+        NetworkVersion::V16,
+        r#"(module
+             (global (export "abi_version") (mut i32) (i32.const 2))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i64)
+               (i64.const 0)))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+        Tester::set_actor_from_bin_unloaded,
+    );
+}
+
+#[test]
+fn abi_version_mismatch() {
+    // Declares the V2 ABI, but invoke follows V1.
+    test_exitcode_with(
+        NetworkVersion::V16,
+        r#"(module
+             (global (export "abi_version") i32 (i32.const 2))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+        Tester::set_actor_from_bin_unloaded,
+    );
+}
+
+#[test]
+fn missing_invoke() {
+    test_exitcode_with(
+        NetworkVersion::V16,
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "run") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION,
+        Tester::set_actor_from_bin_unloaded,
+    );
+}
+
+#[test]
+fn machine_metrics() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&State::default()).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            IPLD_BINARY.unwrap(),
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    let metrics = Arc::new(MemoryMetrics::new());
+    tester.set_metrics(metrics.clone());
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: 1,
+        ..Message::default()
+    };
+    let res = tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+    let messages = metrics.messages();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].exit_code, ExitCode::OK);
+    assert_eq!(messages[0].gas_used, res.msg_receipt.gas_used);
+
+    // The actor was invoked, so its module was looked up and instantiated.
+    assert!(metrics.module_cache_hits() + metrics.module_cache_misses() >= 1);
+    assert!(!metrics.instantiations().is_empty());
+
+    // The IPLD actor both writes and reads blocks.
+    assert!(metrics.bytes_written() > 0);
+    assert!(metrics.bytes_read() > 0);
+}
+
+#[test]
+fn flush_stats() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [(_, sender), (_, receiver)]: [Account; 2] = tester.create_accounts().unwrap();
+    let metrics = Arc::new(MemoryMetrics::new());
+    tester.set_metrics(metrics.clone());
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let mut executor = tester.executor.unwrap();
+
+    for sequence in 0..2 {
+        let res = send_value(&mut executor, sender, receiver, sequence);
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    }
+
+    // Only the sender and the receiver changed (the messages pay no fees, so neither does any
+    // singleton). Everything written to the buffer is part of the new state: the state root, and
+    // the rewritten HAMT nodes.
+    let stats = executor.flush_with_stats().unwrap();
+    assert_eq!(stats.dirty_actors, 2);
+    assert!(stats.hamt_nodes_written >= 1);
+    assert_eq!(stats.blocks_written, stats.hamt_nodes_written + 1);
+    assert_eq!(stats.blocks_skipped, 0);
+    assert!(stats.cache.actor_misses > 0);
+    assert!(stats.cache.actor_hits > 0);
+    assert_eq!(
+        stats.cache,
+        executor.machine().unwrap().state_tree().cache_stats()
+    );
+    assert_eq!(metrics.flushes(), vec![stats]);
+
+    // Flushing again only rewrites the state root.
+    let again = executor.flush_with_stats().unwrap();
+    assert_eq!(again.root, stats.root);
+    assert_eq!(again.dirty_actors, 0);
+    assert_eq!(again.hamt_nodes_written, 0);
+    assert_eq!(again.blocks_written, 1);
+    assert_eq!(metrics.flushes().len(), 2);
+}
+
+#[test]
+fn load_module_gas() {
+    let mut tester = new_tester(
+        NetworkVersion::V17,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat2wasm(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+    let state_cid = tester.set_state(&State::default()).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin_unloaded(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let mut executor = tester.executor.unwrap();
+    let expected = executor
+        .machine()
+        .unwrap()
+        .context()
+        .price_list
+        .on_load_module(wasm_bin.len());
+    assert!(expected.total() > Gas::zero());
+    let gas_used: Vec<i64> = (0..2)
+        .map(|sequence| {
+            let message = Message {
+                from: sender[0].1,
+                to: actor_address,
+                gas_limit: 1_000_000_000,
+                method_num: 1,
+                sequence,
+                ..Message::default()
+            };
+            let res = executor
+                .execute_message(message, ApplyKind::Explicit, 100)
+                .unwrap();
+            assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+
+            // Loading the code is charged by size, whether or not it's already compiled.
+            let charges: Vec<_> = res
+                .exec_trace
+                .iter()
+                .filter_map(|ev| match ev {
+                    ExecutionEvent::GasCharge(charge) if charge.name == "OnLoadModule" => {
+                        Some(charge)
+                    }
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(charges.len(), 1);
+            assert_eq!(charges[0].compute_gas, expected.compute_gas);
+            res.msg_receipt.gas_used
+        })
+        .collect();
+
+    // Only the first send compiles the module, but both cost the same.
+    assert_eq!(gas_used[0], gas_used[1]);
+}
+
+/// An actor that charges 1000 gas under the given (12 byte) name.
+fn gas_charging_actor(name: &str) -> Vec<u8> {
+    let wat = format!(
+        r#"(module
+             ;; gas::charge
+             (type (;0;) (func (param i32 i32 i64) (result i32)))
+             (import "gas" "charge" (func $fvm_sdk::sys::gas::charge::syscall (type 0)))
+             (memory (export "memory") 1)
+             (data (i32.const 0) "{name}")
+             (func (export "invoke") (param $x i32) (result i32)
+               (i32.const 0)
+               (i32.const 12)
+               (i64.const 1000)
+               (call $fvm_sdk::sys::gas::charge::syscall)
+               drop
+               (i32.const 0)))"#
+    );
+    wat2wasm(wat).unwrap()
+}
+
+/// Invokes two actors, where the first one's gas is scaled by the given multiplier, and returns
+/// the result of each invocation.
+fn run_with_actor_multiplier(multiplier: GasMultiplier) -> (ApplyRet, ApplyRet) {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender]: [Account; 1] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&State::default()).unwrap();
+    let scaled = Address::new_id(10000);
+    let scaled_code = tester
+        .set_actor_from_bin(
+            &gas_charging_actor("frame_charge"),
+            state_cid,
+            scaled,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    let other = Address::new_id(10001);
+    tester
+        .set_actor_from_bin(
+            &gas_charging_actor("other_charge"),
+            state_cid,
+            other,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester.set_actor_gas_multiplier(scaled_code, multiplier);
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let executor = tester.executor.as_mut().unwrap();
+    let mut call = |sequence, to| {
+        let res = executor
+            .execute_message(
+                Message {
+                    from: sender.1,
+                    to,
+                    gas_limit: 1_000_000_000,
+                    method_num: 1,
+                    sequence,
+                    ..Message::default()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+        res
+    };
+    (call(0, scaled), call(1, other))
+}
+
+#[test]
+fn actor_gas_multipliers() {
+    let charges = |ret: &ApplyRet| -> Vec<GasCharge> {
+        ret.exec_trace
+            .iter()
+            .filter_map(|ev| match ev {
+                ExecutionEvent::GasCharge(charge) => Some(charge.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let (scaled_base, other_base) = run_with_actor_multiplier(GasMultiplier::ONE);
+    let (scaled, other) = run_with_actor_multiplier(GasMultiplier::new(3).unwrap());
+
+    // Everything charged in the scaled actor's frame costs exactly three times as much, and
+    // records the unscaled values. The message's own charges are unaffected.
+    let (base, tripled) = (charges(&scaled_base), charges(&scaled));
+    assert_eq!(base.len(), tripled.len());
+    let mut scaled_names = HashSet::new();
+    for (base, tripled) in base.iter().zip(&tripled) {
+        assert_eq!(base.name, tripled.name);
+        assert!(base.raw.is_none());
+        match &tripled.raw {
+            Some(raw) => {
+                assert_eq!(raw.compute_gas, base.compute_gas);
+                assert_eq!(raw.other_gas, base.other_gas);
+                assert_eq!(tripled.compute_gas, base.compute_gas * 3);
+                assert_eq!(tripled.other_gas, base.other_gas * 3);
+                scaled_names.insert(tripled.name.to_string());
+            }
+            None => {
+                assert_eq!(tripled.compute_gas, base.compute_gas);
+                assert_eq!(tripled.other_gas, base.other_gas);
+            }
+        }
+    }
+    for name in ["OnMethodInvocation", "frame_charge", "wasm_exec"] {
+        assert!(scaled_names.contains(name), "{} wasn't scaled", name);
+    }
+    assert!(!scaled_names.contains("OnChainMessage"));
+    assert!(scaled.msg_receipt.gas_used > scaled_base.msg_receipt.gas_used);
+
+    // Other actors are charged as usual.
+    let (base, unchanged) = (charges(&other_base), charges(&other));
+    assert!(unchanged.iter().all(|charge| charge.raw.is_none()));
+    assert_eq!(
+        base.iter().map(GasCharge::total).collect::<Vec<_>>(),
+        unchanged.iter().map(GasCharge::total).collect::<Vec<_>>()
+    );
+    assert_eq!(other.msg_receipt.gas_used, other_base.msg_receipt.gas_used);
+}
+
+#[test]
+fn actor_gas_multipliers_rejected_on_mainnet() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    tester.set_actor_gas_multiplier(*fvm::EMPTY_ARR_CID, GasMultiplier::new(3).unwrap());
+    tester.set_chain_id(ChainID::MAINNET);
+    assert!(tester.instantiate_machine(DummyExterns).is_err());
+}
+
+fn engine_for(nv: NetworkVersion) -> Engine {
+    Engine::new_default((&NetworkConfig::new(nv)).into()).unwrap()
+}
+
+/// A [`ModuleCache`] kept in memory.
+#[derive(Default)]
+struct MemoryModuleCache(Mutex<HashMap<ModuleCacheKey, Vec<u8>>>);
+
+impl ModuleCache for MemoryModuleCache {
+    fn get(&self, key: &ModuleCacheKey) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.0.lock().unwrap().get(key).cloned())
+    }
+
+    fn put(&self, key: &ModuleCacheKey, artifact: Vec<u8>) -> anyhow::Result<()> {
+        self.0.lock().unwrap().insert(*key, artifact);
+        Ok(())
+    }
+}
+
+/// Compiles the hello world actor with an engine for the given network version, returning the code
+/// CID and the compiled module, as stored in the engine's module cache.
+fn precompile_hello_world(nv: NetworkVersion) -> (Cid, Vec<u8>) {
+    let bs = MemoryBlockstore::default();
+    let code_cid = bs
+        .put(
+            Code::Blake2b256,
+            &Block {
+                codec: IPLD_RAW,
+                data: HELLO_BINARY.unwrap(),
+            },
+        )
+        .unwrap();
+
+    let cache = Arc::new(MemoryModuleCache::default());
+    let engine = Engine::new_with_cache((&NetworkConfig::new(nv)).into(), cache.clone()).unwrap();
+    engine.persist_compiled(&bs, [&code_cid]).unwrap();
+    let artifact = cache
+        .get(&engine.module_cache_key(&code_cid))
+        .unwrap()
+        .unwrap();
+    (code_cid, artifact)
+}
+
+/// Returns a module cache holding the given artifact where a V16 engine will look for the compiled
+/// hello world actor.
+fn v16_module_cache(code_cid: &Cid, artifact: Vec<u8>) -> Arc<MemoryModuleCache> {
+    let cache = Arc::new(MemoryModuleCache::default());
+    let key = engine_for(NetworkVersion::V16).module_cache_key(code_cid);
+    cache.put(&key, artifact).unwrap();
+    cache
+}
+
+/// Invokes the hello world actor on a V16 network, with an engine using the given module cache. If
+/// `with_bytecode` is false, only the actor (not its Wasm bytecode) is added to the state tree.
+fn run_hello_world(cache: Arc<MemoryModuleCache>, with_bytecode: bool) -> ApplyRet {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    tester.set_module_cache(cache);
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let wasm_bin = HELLO_BINARY.unwrap();
+    let state_cid = tester.set_state(&State::default()).unwrap();
+    let actor_address = Address::new_id(10000);
+
+    if with_bytecode {
+        tester
+            .set_actor_from_bin_unloaded(wasm_bin, state_cid, actor_address, TokenAmount::zero())
+            .unwrap();
+    } else {
+        let code_cid = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(wasm_bin));
+        let state_tree = tester.state_tree.as_mut().unwrap();
+        state_tree.register_new_address(&actor_address).unwrap();
+        state_tree
+            .set_actor(
+                &actor_address,
+                ActorState::new(code_cid, state_cid, TokenAmount::zero(), 1),
+            )
+            .unwrap();
+    }
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: 1,
+        ..Message::default()
+    };
+
+    tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+#[test]
+fn precompiled_module() {
+    let (code_cid, artifact) = precompile_hello_world(NetworkVersion::V16);
+
+    // The bytecode isn't in the blockstore, so the actor can only run from the compiled module.
+    let res = run_hello_world(v16_module_cache(&code_cid, artifact), false);
+    assert_eq!(res.msg_receipt.exit_code.value(), 16);
+}
+
+#[test]
+fn precompiled_module_config_mismatch() {
+    // V15 doesn't charge for wasm instructions, so a module compiled for V15 is instrumented
+    // differently.
+    let (code_cid, artifact) = precompile_hello_world(NetworkVersion::V15);
+
+    let expected = run_hello_world(Default::default(), true);
+
+    // Store the V15 module where a V16 engine will look for it. The config hash won't match, so
+    // the engine must recompile the bytecode.
+    let res = run_hello_world(v16_module_cache(&code_cid, artifact), true);
+    assert_eq!(res.msg_receipt.exit_code.value(), 16);
+    assert_eq!(res.gas_used, expected.gas_used);
+}
+
+#[test]
+fn precompiled_module_corrupted() {
+    let (code_cid, mut artifact) = precompile_hello_world(NetworkVersion::V16);
+    *artifact.last_mut().unwrap() ^= 1;
+
+    let expected = run_hello_world(Default::default(), true);
+
+    // The checksum won't match, so the engine must not deserialize the module, and recompiles the
+    // bytecode instead.
+    let res = run_hello_world(v16_module_cache(&code_cid, artifact), true);
+    assert_eq!(res.msg_receipt.exit_code.value(), 16);
+    assert_eq!(res.gas_used, expected.gas_used);
+}
+
+/// Deploys several actors (including one that tries to grow its memory past the nv17 limit),
+/// sends a message to each, and returns the receipts and the final state root.
+fn run_actors(nv: NetworkVersion, pooling: bool) -> (Vec<Receipt>, Cid) {
+    let mut tester = new_tester(nv, StateTreeVersion::V4, MemoryBlockstore::default()).unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+    let state_cid = tester.set_state(&State::default()).unwrap();
+
+    let grow_memory = wat2wasm(
+        r#"(module
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (if (i32.eq (memory.grow (i32.const 8192)) (i32.const -1))
+                 (then (unreachable)))
+               (i32.const 0)))"#,
+    )
+    .unwrap();
+
+    let actors: Vec<Address> = [
+        HELLO_BINARY.unwrap(),
+        IPLD_BINARY.unwrap(),
+        SYSCALL_BINARY.unwrap(),
+        &grow_memory,
+    ]
+    .into_iter()
+    .zip(10000..)
+    .map(|(wasm_bin, id)| {
+        let actor_address = Address::new_id(id);
+        tester
+            .set_actor_from_bin(wasm_bin, state_cid, actor_address, TokenAmount::zero())
+            .unwrap();
+        actor_address
+    })
+    .collect();
+
+    tester
+        .instantiate_machine_with_config(DummyExterns, |ec| ec.pooling = pooling)
+        .unwrap();
+
+    let mut executor = tester.executor.unwrap();
+    let receipts = actors
+        .into_iter()
+        .zip(0..)
+        .map(|(to, sequence)| {
+            let message = Message {
+                from: sender[0].1,
+                to,
+                sequence,
+                gas_limit: 1000000000,
+                method_num: 1,
+                ..Message::default()
+            };
+            executor
+                .execute_message(message, ApplyKind::Explicit, 100)
+                .unwrap()
+                .msg_receipt
+        })
+        .collect();
+
+    (receipts, executor.flush().unwrap())
+}
+
+#[test]
+fn pooling_allocator() {
+    let (receipts, root) = run_actors(NetworkVersion::V17, true);
+    let (expected_receipts, expected_root) = run_actors(NetworkVersion::V17, false);
+
+    assert_eq!(receipts, expected_receipts);
+    assert_eq!(root, expected_root);
+
+    // The memory limit is enforced either way.
+    assert_eq!(
+        receipts.last().unwrap().exit_code,
+        ExitCode::SYS_ILLEGAL_INSTRUCTION
+    );
+}
+
+#[test]
+fn memory_limit_from_nv17() {
+    let (receipts, _) = run_actors(NetworkVersion::V16, false);
+    assert_eq!(receipts.last().unwrap().exit_code, ExitCode::OK);
+}
+
+#[test]
+fn forked_machines() {
+    let base = Arc::new(MemoryBlockstore::default());
+    let mut tester = new_tester(NetworkVersion::V16, StateTreeVersion::V4, base.clone()).unwrap();
+    let accounts: [Account; 3] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let mut machine = tester.executor.unwrap().into_machine().unwrap();
+    let base_root = machine.state_tree_mut().flush().unwrap();
+
+    // Both forks spend the sender's first nonce, transferring to different accounts.
+    let handles: Vec<_> = [accounts[1].1, accounts[2].1]
+        .into_iter()
+        .map(|to| {
+            let fork = machine.fork().unwrap();
+            let from = accounts[0].1;
+            std::thread::Builder::new()
+                .stack_size(64 << 20)
+                .spawn(move || {
+                    let mut executor: IntegrationExecutor<_, _> = DefaultExecutor::new(fork);
+                    let message = Message {
+                        from,
+                        to,
+                        value: TokenAmount::from_atto(100),
+                        gas_limit: 1000000000,
+                        method_num: 0,
+                        ..Message::default()
+                    };
+                    let res = executor
+                        .execute_message(message, ApplyKind::Explicit, 100)
+                        .unwrap();
+                    assert!(res.msg_receipt.exit_code.is_success());
+
+                    // Flush the state tree, but not the write buffer.
+                    executor
+                        .machine_mut()
+                        .unwrap()
+                        .state_tree_mut()
+                        .flush()
+                        .unwrap()
+                })
+                .unwrap()
+        })
+        .collect();
+    let roots: Vec<Cid> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    assert_ne!(roots[0], roots[1]);
+    for root in &roots {
+        assert_ne!(*root, base_root);
+        assert!(!base.has(root).unwrap());
+    }
+
+    // The original machine didn't observe either transfer.
+    assert_eq!(machine.state_tree_mut().flush().unwrap(), base_root);
+    for (_, addr) in &accounts[1..] {
+        let actor = machine.state_tree().get_actor(addr).unwrap().unwrap();
+        assert_eq!(actor.balance, TokenAmount::from_atto(10000));
+    }
+}
+
+/// A counter actor that only handles method 1.
+const COUNTER_V1_WAT: &str = r#"
+(module
+  (import "vm" "context" (func $context (param i32) (result i32)))
+  (import "vm" "abort" (func $abort (param i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "invoke") (param $x i32) (result i32)
+    ;; The method number is at offset 32 of the invocation context.
+    (drop (call $context (i32.const 0)))
+    (if (i64.ne (i64.load (i32.const 32)) (i64.const 1))
+      (then (drop (call $abort (i32.const 22) (i32.const 0) (i32.const 0)))))
+    (i32.const 0)
+  )
+)
+"#;
+
+/// The next version of the counter actor, with a method 2 that asserts the (old) state is `[7]`.
+const COUNTER_V2_WAT: &str = r#"
+(module
+  (import "vm" "context" (func $context (param i32) (result i32)))
+  (import "vm" "abort" (func $abort (param i32 i32 i32) (result i32)))
+  (import "self" "root" (func $root (param i32 i32 i32) (result i32)))
+  (import "ipld" "block_open" (func $block_open (param i32 i32) (result i32)))
+  (import "ipld" "block_read" (func $block_read (param i32 i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "invoke") (param $x i32) (result i32)
+    (local $method i64)
+    (drop (call $context (i32.const 0)))
+    (local.set $method (i64.load (i32.const 32)))
+    (if (i64.eq (local.get $method) (i64.const 2))
+      (then
+        ;; Read the state root into 256, open it (the block ID is at offset 8 of the result),
+        ;; and read the state into 512.
+        (drop (call $root (i32.const 128) (i32.const 256) (i32.const 128)))
+        (drop (call $block_open (i32.const 128) (i32.const 256)))
+        (drop (call $block_read
+          (i32.const 144) (i32.load (i32.const 136)) (i32.const 0) (i32.const 512) (i32.const 64)))
+        ;; The state is the CBOR tuple [count].
+        (if (i32.ne (i32.load16_u (i32.const 512)) (i32.const 0x0781))
+          (then (drop (call $abort (i32.const 24) (i32.const 0) (i32.const 0)))))
+        (return (i32.const 0))))
+    (if (i64.ne (local.get $method) (i64.const 1))
+      (then (drop (call $abort (i32.const 22) (i32.const 0) (i32.const 0)))))
+    (i32.const 0)
+  )
+)
+"#;
+
+/// Deploys the v1 counter actor (with a count of 7) and an actor that upgrades the counter to v2,
+/// then asks the counter's method 2, upgrades it, and asks again. Returns the three receipts' exit
+/// codes, the counter's state before and after, and the v2 code CID.
+fn run_counter_upgrade(admin: bool) -> (Vec<ExitCode>, ActorState, ActorState, Cid) {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let counter_address = Address::new_id(10000);
+    let upgrader_address = Address::new_id(10001);
+
+    let state_cid = tester.set_state(&State { count: 7 }).unwrap();
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(COUNTER_V1_WAT).unwrap(),
+            state_cid,
+            counter_address,
+            TokenAmount::from_atto(42),
+        )
+        .unwrap();
+
+    // Only store the new code: the upgrade must load it.
+    let v2_bin = wat2wasm(COUNTER_V2_WAT).unwrap();
+    let v2_code = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(&v2_bin));
+    tester.blockstore().put_keyed(&v2_code, &v2_bin).unwrap();
+
+    let v2_code_bytes: String = v2_code
+        .to_bytes()
+        .iter()
+        .map(|b| format!("\\{:02x}", b))
+        .collect();
+    let upgrader = format!(
+        r#"
+        (module
+          (import "actor" "upgrade_actor" (func $upgrade (param i64 i32) (result i32)))
+          (import "vm" "abort" (func $abort (param i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "{}")
+          (func (export "invoke") (param $x i32) (result i32)
+            (local $err i32)
+            (local.set $err (call $upgrade (i64.const 10000) (i32.const 0)))
+            ;; Abort with FIRST_USER_EXIT_CODE + the error number on failure.
+            (if (local.get $err)
+              (then (drop (call $abort (i32.add (i32.const 16) (local.get $err)) (i32.const 0) (i32.const 0)))))
+            (i32.const 0)
+          )
+        )
+        "#,
+        v2_code_bytes
+    );
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(upgrader).unwrap(),
+            state_cid,
+            upgrader_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    if admin {
+        tester.set_upgrade_admin(10001);
+    }
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let mut executor = tester.executor.unwrap();
+    let before = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .get_actor(&counter_address)
+        .unwrap()
+        .unwrap();
+
+    let exit_codes = [counter_address, upgrader_address, counter_address]
+        .into_iter()
+        .enumerate()
+        .map(|(sequence, to)| {
+            let message = Message {
+                from: sender[0].1,
+                to,
+                sequence: sequence as u64,
+                gas_limit: 1_000_000_000,
+                method_num: 2,
+                ..Message::default()
+            };
+            executor
+                .execute_message(message, ApplyKind::Explicit, 100)
+                .unwrap()
+                .msg_receipt
+                .exit_code
+        })
+        .collect();
+
+    let after = executor
+        .machine()
+        .unwrap()
+        .state_tree()
+        .get_actor(&counter_address)
+        .unwrap()
+        .unwrap();
+    (exit_codes, before, after, v2_code)
+}
+
+#[test]
+fn upgrade_actor() {
+    let (exit_codes, before, after, v2_code) = run_counter_upgrade(true);
+    assert_eq!(
+        exit_codes,
+        [ExitCode::USR_UNHANDLED_MESSAGE, ExitCode::OK, ExitCode::OK]
+    );
+
+    // Only the code changes.
+    assert_eq!(
+        after,
+        ActorState {
+            code: v2_code,
+            ..before
+        }
+    );
+}
+
+#[test]
+fn upgrade_actor_forbidden() {
+    let (exit_codes, before, after, _) = run_counter_upgrade(false);
+    assert_eq!(
+        exit_codes,
+        [
+            ExitCode::USR_UNHANDLED_MESSAGE,
+            ExitCode::new(16 + ErrorNumber::Forbidden as u32),
+            ExitCode::USR_UNHANDLED_MESSAGE
+        ]
+    );
+    assert_eq!(after, before);
+}
+
+/// Returns a copy of the tester's builtin actors manifest, with an extra "counter" actor.
+fn manifest_with_counter(
+    tester: &Tester<MemoryBlockstore, DummyExterns>,
+    counter_code: Cid,
+) -> Cid {
+    let bs = tester.state_tree.as_ref().unwrap().store();
+    let (version, data): (u32, Cid) = bs.get_cbor(tester.builtin_actors()).unwrap().unwrap();
+    let mut actors: Vec<(String, Cid)> = bs.get_cbor(&data).unwrap().unwrap();
+    actors.push(("counter".into(), counter_code));
+    let data = bs.put_cbor(&actors, Code::Blake2b256).unwrap();
+    bs.put_cbor(&(version, data), Code::Blake2b256).unwrap()
+}
+
+#[test]
+fn builtin_actors_by_version() {
+    let mut tester = new_tester(
+        NetworkVersion::V15,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    // Each version of the counter aborts with a different exit code.
+    let [v1_bin, v2_bin] = [17, 18].map(|code| {
+        wat2wasm(format!(
+            r#"(module
+                 (import "vm" "abort" (func $abort (param i32 i32 i32) (result i32)))
+                 (memory (export "memory") 1)
+                 (func (export "invoke") (param $x i32) (result i32)
+                   (call $abort (i32.const {}) (i32.const 0) (i32.const 0))))"#,
+            code
+        ))
+        .unwrap()
+    });
+
+    // The counter is deployed with the V15 code.
+    let actor_address = Address::new_id(10000);
+    let state_cid = tester.set_state(&State::default()).unwrap();
+    let v1_code = tester
+        .set_actor_from_bin(&v1_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+    let v2_code = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(&v2_bin));
+    tester.blockstore().put_keyed(&v2_code, &v2_bin).unwrap();
+
+    let v1_manifest = manifest_with_counter(&tester, v1_code);
+    let v2_manifest = manifest_with_counter(&tester, v2_code);
+    tester.override_actors_for(NetworkVersion::V15, v1_manifest);
+    tester.override_actors_for(NetworkVersion::V16, v2_manifest);
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let mut executor = tester.executor.unwrap();
+    let send = |executor: &mut IntegrationExecutor<MemoryBlockstore, DummyExterns>, sequence| {
+        let message = Message {
+            from: sender[0].1,
+            to: actor_address,
+            sequence,
+            gas_limit: 1_000_000_000,
+            method_num: 1,
+            ..Message::default()
+        };
+        executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap()
+            .msg_receipt
+            .exit_code
+    };
+    assert_eq!(send(&mut executor, 0), ExitCode::new(17));
+
+    // After the upgrade, the same actor runs the V16 code.
+    executor
+        .machine_mut()
+        .unwrap()
+        .set_network_version(NetworkVersion::V16)
+        .unwrap();
+    assert_eq!(send(&mut executor, 1), ExitCode::new(18));
+    assert_eq!(
+        executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .get_actor(&actor_address)
+            .unwrap()
+            .unwrap()
+            .code,
+        v1_code
+    );
+    assert_eq!(
+        executor
+            .machine()
+            .unwrap()
+            .builtin_actors()
+            .id_by_code(&v1_code),
+        executor
+            .machine()
+            .unwrap()
+            .builtin_actors()
+            .id_by_code(&v2_code)
     );
 }
 
@@ -480,9 +2994,861 @@ fn backtraces() {
     println!("panic backtrace: {}", res.failure_info.unwrap());
 }
 
+#[test]
+fn missing_block_is_fatal() {
+    let blockstore = Rc::new(FailingBlockstore::default());
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        blockstore.clone(),
+    )
+    .unwrap();
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    // An actor whose method 2 opens its state.
+    let address = Address::new_id(10000);
+    let state_cid = tester.set_state(&State { count: 7 }).unwrap();
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(COUNTER_V2_WAT).unwrap(),
+            state_cid,
+            address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let mut open_state = |sequence| {
+        let message = Message {
+            from: sender[0].1,
+            to: address,
+            sequence,
+            gas_limit: 1_000_000_000,
+            method_num: 2,
+            ..Message::default()
+        };
+        let res = executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap();
+        (
+            res.msg_receipt.exit_code,
+            res.failure_info.unwrap().to_string(),
+        )
+    };
+
+    // The embedder's store lost the actor's state: that's its fault, not the actor's.
+    blockstore.remove(state_cid);
+    let (exit_code, failure) = open_state(0);
+    assert_eq!(exit_code, ExitCode::SYS_ASSERTION_FAILED);
+    assert!(failure.contains("[FATAL]"), "{}", failure);
+    assert!(
+        failure.contains(&format!("actor 10000 opened block {}", state_cid)),
+        "{}",
+        failure
+    );
+
+    // Store errors name the block too.
+    blockstore.add_fail(state_cid);
+    let (exit_code, failure) = open_state(1);
+    assert_eq!(exit_code, ExitCode::SYS_ASSERTION_FAILED);
+    assert!(
+        failure.contains(&format!(
+            "actor 10000 failed to open block {cid}: failed to get block {cid}: an error was triggered",
+            cid = state_cid
+        )),
+        "{}",
+        failure
+    );
+}
+
+/// An actor (f010000) that opens its state, self-destructs in favour of f010001 (at offset 1024),
+/// then checks what it can still do, aborting with exit code 100 + the step number if a check
+/// fails. Method 2 then aborts with exit code 42.
+const SELF_DESTRUCT_WAT: &str = r#"
+(module
+  (import "vm" "context" (func $context (param i32) (result i32)))
+  (import "vm" "abort" (func $abort (param i32 i32 i32) (result i32)))
+  (import "self" "root" (func $root (param i32 i32 i32) (result i32)))
+  (import "self" "set_root" (func $set_root (param i32) (result i32)))
+  (import "self" "current_balance" (func $current_balance (param i32) (result i32)))
+  (import "self" "self_destruct" (func $self_destruct (param i32 i32) (result i32)))
+  (import "ipld" "block_open" (func $block_open (param i32 i32) (result i32)))
+  (import "ipld" "block_read" (func $block_read (param i32 i32 i32 i32 i32) (result i32)))
+  (import "send" "send" (func $send (param i32 i32 i32 i64 i32 i64 i64) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 1024) "\00\91\4e")
+  (data (i32.const 1032) "\00\90\4e")
+  (data (i32.const 1040) "\00\92\4e")
+  (func $check (param $got i32) (param $want i32) (param $step i32)
+    (if (i32.ne (local.get $got) (local.get $want))
+      (then (drop (call $abort (i32.add (i32.const 100) (local.get $step)) (i32.const 0) (i32.const 0))))))
+  (func (export "invoke") (param $x i32) (result i32)
+    (local $method i64)
+    (local $block i32)
+    (drop (call $context (i32.const 0)))
+    (local.set $method (i64.load (i32.const 32)))
+
+    ;; Open the state (the block ID is at offset 8 of the result), then self-destruct.
+    (call $check (call $root (i32.const 128) (i32.const 256) (i32.const 128)) (i32.const 0) (i32.const 1))
+    (call $check (call $block_open (i32.const 128) (i32.const 256)) (i32.const 0) (i32.const 2))
+    (local.set $block (i32.load (i32.const 136)))
+    (call $check (call $self_destruct (i32.const 1024) (i32.const 3)) (i32.const 0) (i32.const 3))
+
+    ;; The state root can't be read or set (IllegalOperation).
+    (call $check (call $root (i32.const 128) (i32.const 256) (i32.const 128)) (i32.const 2) (i32.const 4))
+    (call $check (call $set_root (i32.const 256)) (i32.const 2) (i32.const 5))
+
+    ;; The balance went to the beneficiary.
+    (call $check (call $current_balance (i32.const 512)) (i32.const 0) (i32.const 6))
+    (call $check
+      (i64.eqz (i64.or (i64.load (i32.const 512)) (i64.load (i32.const 520)))) (i32.const 1) (i32.const 7))
+
+    ;; Sends still work, from the zero balance (InsufficientFunds).
+    (call $check
+      (call $send (i32.const 128) (i32.const 1024) (i32.const 3) (i64.const 0) (i32.const 0) (i64.const 0) (i64.const 0))
+      (i32.const 0) (i32.const 8))
+    (call $check
+      (call $send (i32.const 128) (i32.const 1024) (i32.const 3) (i64.const 0) (i32.const 0) (i64.const 0) (i64.const 1))
+      (i32.const 5) (i32.const 9))
+
+    ;; The state block is still readable. The state is the CBOR tuple [7].
+    (call $check
+      (call $block_read (i32.const 128) (local.get $block) (i32.const 0) (i32.const 512) (i32.const 64))
+      (i32.const 0) (i32.const 10))
+    (call $check (i32.load16_u (i32.const 512)) (i32.const 0x0781) (i32.const 11))
+
+    ;; It can't be invoked again (NotFound).
+    (call $check
+      (call $send (i32.const 128) (i32.const 1032) (i32.const 3) (i64.const 1) (i32.const 0) (i64.const 0) (i64.const 0))
+      (i32.const 6) (i32.const 12))
+
+    ;; Nor receive funds: f010002 tries to send it some, and aborts with 100 + the error number.
+    (call $check
+      (call $send (i32.const 128) (i32.const 1040) (i32.const 3) (i64.const 1) (i32.const 0) (i64.const 0) (i64.const 0))
+      (i32.const 0) (i32.const 13))
+    (call $check (i32.load (i32.const 128)) (i32.const 106) (i32.const 14))
+
+    (if (i64.eq (local.get $method) (i64.const 2))
+      (then (drop (call $abort (i32.const 42) (i32.const 0) (i32.const 0)))))
+    (i32.const 0)
+  )
+)
+"#;
+
+/// An actor that sends 1 attoFIL to f010000, aborting with exit code 100 + the error number if the
+/// send fails.
+const SEND_BACK_WAT: &str = r#"
+(module
+  (import "vm" "abort" (func $abort (param i32 i32 i32) (result i32)))
+  (import "send" "send" (func $send (param i32 i32 i32 i64 i32 i64 i64) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 1024) "\00\90\4e")
+  (func (export "invoke") (param $x i32) (result i32)
+    (local $err i32)
+    (local.set $err
+      (call $send (i32.const 128) (i32.const 1024) (i32.const 3) (i64.const 0) (i32.const 0) (i64.const 0) (i64.const 1)))
+    (if (local.get $err)
+      (then (drop (call $abort (i32.add (i32.const 100) (local.get $err)) (i32.const 0) (i32.const 0)))))
+    (i32.const 0)
+  )
+)
+"#;
+
+#[test]
+fn self_destruct_semantics() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let actor_address = Address::new_id(10000);
+    let beneficiary_address = Address::new_id(10001);
+    let sender_back_address = Address::new_id(10002);
+    let state_cid = tester.set_state(&State { count: 7 }).unwrap();
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(SELF_DESTRUCT_WAT).unwrap(),
+            state_cid,
+            actor_address,
+            TokenAmount::from_atto(1000),
+        )
+        .unwrap();
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(
+                r#"(module
+                     (memory (export "memory") 1)
+                     (func (export "invoke") (param $x i32) (result i32)
+                       (i32.const 0)))"#,
+            )
+            .unwrap(),
+            state_cid,
+            beneficiary_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(SEND_BACK_WAT).unwrap(),
+            state_cid,
+            sender_back_address,
+            TokenAmount::from_atto(1000),
+        )
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let mut executor = tester.executor.unwrap();
+    let send = |executor: &mut IntegrationExecutor<MemoryBlockstore, DummyExterns>,
+                sequence,
+                method_num| {
+        let message = Message {
+            from: sender[0].1,
+            to: actor_address,
+            sequence,
+            gas_limit: 1_000_000_000,
+            method_num,
+            ..Message::default()
+        };
+        executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap()
+            .msg_receipt
+            .exit_code
+    };
+    let get_actor = |executor: &IntegrationExecutor<MemoryBlockstore, DummyExterns>, addr| {
+        executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .get_actor(&addr)
+            .unwrap()
+    };
+
+    // Aborting after self-destructing resurrects the actor, with its balance and state.
+    assert_eq!(send(&mut executor, 0, 2), ExitCode::new(42));
+    let actor = get_actor(&executor, actor_address).unwrap();
+    assert_eq!(actor.balance, TokenAmount::from_atto(1000));
+    assert_eq!(actor.state, state_cid);
+    assert_eq!(
+        get_actor(&executor, beneficiary_address).unwrap().balance,
+        TokenAmount::zero()
+    );
+
+    // Otherwise, it's deleted when it returns. Nothing was sent to it after it self-destructed.
+    assert_eq!(send(&mut executor, 1, 1), ExitCode::OK);
+    assert!(get_actor(&executor, actor_address).is_none());
+    assert_eq!(
+        get_actor(&executor, beneficiary_address).unwrap().balance,
+        TokenAmount::from_atto(1000)
+    );
+    assert_eq!(
+        get_actor(&executor, sender_back_address).unwrap().balance,
+        TokenAmount::from_atto(1000)
+    );
+}
+
+/// Deploys an actor that sends `(value_hi << 64) | value_lo` attoFIL to another actor through the
+/// raw send syscall, aborting with `FIRST_USER_EXIT_CODE` + the error number if the send fails.
+/// Returns the exit code, and the balances of both actors afterwards.
+fn run_raw_send(
+    nv: NetworkVersion,
+    value_hi: u64,
+    value_lo: u64,
+) -> (ExitCode, TokenAmount, TokenAmount) {
+    let mut tester = new_tester(nv, StateTreeVersion::V4, MemoryBlockstore::default()).unwrap();
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    let sender_address = Address::new_id(10000);
+    let receiver_address = Address::new_id(10001);
+    let state_cid = tester.set_state(&State::default()).unwrap();
+
+    // The receiver's address, f010001, is at offset 0.
+    let malicious = format!(
+        r#"
+        (module
+          (import "send" "send" (func $send (param i32 i32 i32 i64 i32 i64 i64) (result i32)))
+          (import "vm" "abort" (func $abort (param i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "\00\91\4e")
+          (func (export "invoke") (param $x i32) (result i32)
+            (local $err i32)
+            (local.set $err (call $send
+              (i32.const 64) (i32.const 0) (i32.const 3) (i64.const 0) (i32.const 0)
+              (i64.const {}) (i64.const {})))
+            (if (local.get $err)
+              (then (drop (call $abort (i32.add (i32.const 16) (local.get $err)) (i32.const 0) (i32.const 0)))))
+            (i32.const 0)
+          )
+        )
+        "#,
+        value_hi as i64, value_lo as i64
+    );
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(malicious).unwrap(),
+            state_cid,
+            sender_address,
+            TokenAmount::from_atto(1000),
+        )
+        .unwrap();
+    tester
+        .set_actor_from_bin(
+            &wat2wasm(
+                r#"(module
+                     (memory (export "memory") 1)
+                     (func (export "invoke") (param $x i32) (result i32)
+                       (i32.const 0)))"#,
+            )
+            .unwrap(),
+            state_cid,
+            receiver_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let mut executor = tester.executor.unwrap();
+    let res = executor
+        .execute_message(
+            Message {
+                from: sender[0].1,
+                to: sender_address,
+                gas_limit: 1_000_000_000,
+                method_num: 1,
+                ..Message::default()
+            },
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap();
+
+    let balance = |addr| {
+        executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .get_actor(&addr)
+            .unwrap()
+            .unwrap()
+            .balance
+    };
+    (
+        res.msg_receipt.exit_code,
+        balance(sender_address),
+        balance(receiver_address),
+    )
+}
+
+#[test]
+fn send_value_out_of_range() {
+    let illegal_argument = ExitCode::new(16 + ErrorNumber::IllegalArgument as u32);
+    let insufficient_funds = ExitCode::new(16 + ErrorNumber::InsufficientFunds as u32);
+
+    // The largest value the syscall can encode far exceeds the total supply.
+    assert_eq!(
+        run_raw_send(NetworkVersion::V17, u64::MAX, u64::MAX),
+        (
+            illegal_argument,
+            TokenAmount::from_atto(1000),
+            TokenAmount::zero()
+        )
+    );
+    // Values with the high bit of either word set are still unsigned.
+    assert_eq!(
+        run_raw_send(NetworkVersion::V17, 1 << 63, 0),
+        (
+            illegal_argument,
+            TokenAmount::from_atto(1000),
+            TokenAmount::zero()
+        )
+    );
+    assert_eq!(
+        run_raw_send(NetworkVersion::V17, 0, 1 << 63),
+        (
+            insufficient_funds,
+            TokenAmount::from_atto(1000),
+            TokenAmount::zero()
+        )
+    );
+    assert_eq!(
+        run_raw_send(NetworkVersion::V17, 0, 100),
+        (
+            ExitCode::OK,
+            TokenAmount::from_atto(900),
+            TokenAmount::from_atto(100)
+        )
+    );
+
+    // Before nv17, sending more than the total supply fails like any other unaffordable send.
+    assert_eq!(
+        run_raw_send(NetworkVersion::V16, u64::MAX, u64::MAX),
+        (
+            insufficient_funds,
+            TokenAmount::from_atto(1000),
+            TokenAmount::zero()
+        )
+    );
+}
+
+#[test]
+fn message_value_out_of_range() {
+    let mut tester = new_tester(
+        NetworkVersion::V17,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [(sender_id, sender), (_, receiver)]: [Account; 2] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let too_much = fvm_shared::TOTAL_FILECOIN.clone() + TokenAmount::from_atto(1);
+    for value in [TokenAmount::from_atto(-1), too_much] {
+        let res = executor
+            .execute_message(
+                Message {
+                    from: sender,
+                    to: receiver,
+                    gas_limit: 1_000_000_000,
+                    method_num: METHOD_SEND,
+                    value: value.clone(),
+                    ..Message::default()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_ASSERTION_FAILED);
+
+        // The message is rejected before the sender pays for gas, or its sequence is bumped.
+        let actor = executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .get_actor_id(sender_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(actor.sequence, 0);
+        assert_eq!(actor.balance, TokenAmount::from_atto(10000));
+
+        let res = executor.execute_message(
+            Message {
+                from: Address::new_id(0),
+                to: receiver,
+                gas_limit: 1_000_000_000,
+                method_num: METHOD_SEND,
+                value,
+                ..Message::default()
+            },
+            ApplyKind::Implicit,
+            100,
+        );
+        assert!(res.is_err());
+    }
+}
+
+#[test]
+fn message_value_out_of_range_before_nv17() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [(_, sender), (_, receiver)]: [Account; 2] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    // The value isn't checked at preflight, so the transfer simply fails.
+    let res = executor
+        .execute_message(
+            Message {
+                from: sender,
+                to: receiver,
+                gas_limit: 1_000_000_000,
+                method_num: METHOD_SEND,
+                value: fvm_shared::TOTAL_FILECOIN.clone() + TokenAmount::from_atto(1),
+                ..Message::default()
+            },
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_INSUFFICIENT_FUNDS);
+}
+
+#[test]
+fn message_fields_out_of_bounds() {
+    let too_big = RawBytes::new(vec![0; MAX_MESSAGE_PARAMS_LEN + 1]);
+    let out_of_bounds = |message: &Message| {
+        [
+            Message {
+                version: 1,
+                ..message.clone()
+            },
+            Message {
+                params: too_big.clone(),
+                ..message.clone()
+            },
+        ]
+    };
+
+    // Before nv17, the bounds aren't checked.
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [(_, sender), (_, receiver)]: [Account; 2] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let message = Message {
+        from: sender,
+        to: receiver,
+        gas_limit: 1_000_000_000,
+        method_num: METHOD_SEND,
+        ..Message::default()
+    };
+    for (sequence, message) in out_of_bounds(&message).into_iter().enumerate() {
+        let res = executor
+            .execute_message(
+                Message {
+                    sequence: sequence as u64,
+                    ..message
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap();
+        assert_eq!(
+            res.msg_receipt.exit_code,
+            ExitCode::OK,
+            "{:?}",
+            res.failure_info
+        );
+    }
+
+    let mut tester = new_tester(
+        NetworkVersion::V17,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [(sender_id, sender), (_, receiver)]: [Account; 2] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let message = Message {
+        from: sender,
+        to: receiver,
+        gas_limit: 1_000_000_000,
+        method_num: METHOD_SEND,
+        ..Message::default()
+    };
+    let penalty = &executor
+        .machine()
+        .unwrap()
+        .context()
+        .network_context
+        .base_fee
+        * message.gas_limit;
+
+    for invalid in out_of_bounds(&message) {
+        let res = executor
+            .execute_message(invalid, ApplyKind::Explicit, 100)
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_ASSERTION_FAILED);
+        assert_eq!(res.msg_receipt.gas_used, 0);
+        assert_eq!(res.penalty, penalty);
+        assert!(res.exec_trace.is_empty());
+
+        // The message is rejected before the sender pays for gas, or its sequence is bumped.
+        let actor = executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .get_actor_id(sender_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(actor.sequence, 0);
+        assert_eq!(actor.balance, TokenAmount::from_atto(10000));
+    }
+
+    // Params of exactly the maximum size are fine.
+    let res = executor
+        .execute_message(
+            Message {
+                params: RawBytes::new(vec![0; MAX_MESSAGE_PARAMS_LEN]),
+                ..message
+            },
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+}
+
+const AUTHENTICATE_METHOD: MethodNum = 3;
+
+/// A mock abstracted account, returning the given exit code from every method (including
+/// authentication). It follows the V2 ABI, so it can only be invoked from nv17.
+fn abstracted_account(exit_code: ExitCode) -> Vec<u8> {
+    let wat = format!(
+        r#"(module
+             (global (export "abi_version") i32 (i32.const 2))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i64)
+               (i64.const {})))"#,
+        (exit_code.value() as u64) << 32
+    );
+    wat2wasm(wat).unwrap()
+}
+
+/// Sends 10 atto from `from` to `to` with the given sequence, returning the result.
+fn send_value(
+    executor: &mut IntegrationExecutor<MemoryBlockstore, DummyExterns>,
+    from: Address,
+    to: Address,
+    sequence: u64,
+) -> ApplyRet {
+    executor
+        .execute_message(
+            Message {
+                from,
+                to,
+                gas_limit: 1_000_000_000,
+                method_num: METHOD_SEND,
+                value: TokenAmount::from_atto(10),
+                sequence,
+                ..Message::default()
+            },
+            ApplyKind::Explicit,
+            100,
+        )
+        .unwrap()
+}
+
+fn authenticated(ret: &ApplyRet) -> bool {
+    ret.exec_trace.iter().any(|ev| {
+        matches!(ev, ExecutionEvent::GasCharge(charge) if charge.name == "OnAuthenticateMessage")
+    })
+}
+
+#[test]
+fn abstracted_account_senders() {
+    let run = |policy: SenderPolicy, exit_code: ExitCode| {
+        let mut tester = new_tester(
+            NetworkVersion::V17,
+            StateTreeVersion::V4,
+            MemoryBlockstore::default(),
+        )
+        .unwrap();
+        let [(_, account), (_, receiver)]: [Account; 2] = tester.create_accounts().unwrap();
+        let state_cid = tester.set_state(&State::default()).unwrap();
+        let abstracted = Address::new_id(10000);
+        tester
+            .set_actor_from_bin(
+                &abstracted_account(exit_code),
+                state_cid,
+                abstracted,
+                TokenAmount::from_atto(100),
+            )
+            .unwrap();
+        tester.set_sender_policy(policy);
+        tester.enable_tracing();
+        tester.instantiate_machine(DummyExterns).unwrap();
+        let mut executor = tester.executor.unwrap();
+
+        // Accounts send messages as usual.
+        let res = send_value(&mut executor, account, receiver, 0);
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+        assert!(!authenticated(&res));
+
+        let res = send_value(&mut executor, abstracted, receiver, 0);
+        let sender = executor
+            .machine()
+            .unwrap()
+            .state_tree()
+            .get_actor(&abstracted)
+            .unwrap()
+            .unwrap();
+        (res, sender)
+    };
+    let authenticate = SenderPolicy::Authenticate {
+        method: AUTHENTICATE_METHOD,
+    };
+
+    // Only accounts may send messages by default.
+    let (res, sender) = run(SenderPolicy::AccountsOnly, ExitCode::OK);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_SENDER_INVALID);
+    assert_eq!(sender.sequence, 0);
+
+    // An abstracted account accepting the message sends it, paying for its authentication.
+    let (res, sender) = run(authenticate, ExitCode::OK);
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    assert!(authenticated(&res));
+    assert_eq!(sender.sequence, 1);
+    assert_eq!(sender.balance, TokenAmount::from_atto(90));
+
+    // One rejecting the message fails it like an invalid signature: the miner pays.
+    let (res, sender) = run(authenticate, ExitCode::USR_FORBIDDEN);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_SENDER_INVALID);
+    assert_eq!(res.msg_receipt.gas_used, 0);
+    assert!(res.penalty.is_positive());
+    assert_eq!(sender.sequence, 0);
+    assert_eq!(sender.balance, TokenAmount::from_atto(100));
+}
+
+#[test]
+fn placeholder_senders_rejected() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [(account_id, account)]: [Account; 1] = tester.create_accounts().unwrap();
+    tester.set_sender_policy(SenderPolicy::Authenticate {
+        method: AUTHENTICATE_METHOD,
+    });
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    // Funding an f4 address creates a placeholder, which can't send messages.
+    let placeholder = Address::new_delegated(account_id, b"placeholder").unwrap();
+    let res = send_value(executor, account, placeholder, 0);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    let res = send_value(executor, placeholder, account, 0);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_SENDER_INVALID);
+}
+
+#[test]
+fn sender_authentication_requires_nv16() {
+    let policy = SenderPolicy::Authenticate {
+        method: AUTHENTICATE_METHOD,
+    };
+    for (nv, allowed) in [(NetworkVersion::V15, false), (NetworkVersion::V16, true)] {
+        let mut tester = new_tester(nv, StateTreeVersion::V4, MemoryBlockstore::default()).unwrap();
+        tester.set_sender_policy(policy);
+        assert_eq!(
+            tester.instantiate_machine(DummyExterns).is_ok(),
+            allowed,
+            "{}",
+            nv
+        );
+    }
+}
+
+#[test]
+fn signed_messages() {
+    use fvm_shared::crypto::signature::Signature;
+    use libsecp256k1::SecretKey;
+    use rand::SeedableRng;
+
+    let mut tester = new_tester(
+        NetworkVersion::V17,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let key = SecretKey::random(&mut rand_chacha::ChaCha8Rng::seed_from_u64(9));
+    let (_, account) = tester
+        .make_secp256k1_account(key.clone(), TokenAmount::from_atto(10000))
+        .unwrap();
+    let [(_, receiver)]: [Account; 1] = tester.create_accounts().unwrap();
+    let state_cid = tester.set_state(&State::default()).unwrap();
+    let abstracted = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            &abstracted_account(ExitCode::OK),
+            state_cid,
+            abstracted,
+            TokenAmount::from_atto(100),
+        )
+        .unwrap();
+    tester.set_sender_policy(SenderPolicy::Authenticate {
+        method: AUTHENTICATE_METHOD,
+    });
+    tester.enable_tracing();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let message = |from: Address, sequence: u64| Message {
+        from,
+        to: receiver,
+        gas_limit: 1_000_000_000,
+        method_num: METHOD_SEND,
+        value: TokenAmount::from_atto(10),
+        sequence,
+        ..Message::default()
+    };
+    let sign = |msg: &Message| {
+        let digest = Code::Blake2b256.digest(&msg.cid().unwrap().to_bytes());
+        let (sig, recovery_id) = libsecp256k1::sign(
+            &libsecp256k1::Message::parse_slice(digest.digest()).unwrap(),
+            &key,
+        );
+        let mut bytes = sig.serialize().to_vec();
+        bytes.push(recovery_id.serialize());
+        Signature::new_secp256k1(bytes)
+    };
+
+    // Secp256k1 signatures are verified before the message is applied.
+    let msg = message(account, 0);
+    let sig = sign(&msg);
+    let res = executor
+        .execute_signed_message(SignedMessage::new_unchecked(msg, sig.clone()))
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert!(!authenticated(&res));
+    executor
+        .execute_signed_message(SignedMessage::new_unchecked(message(account, 1), sig))
+        .unwrap_err();
+
+    // Delegated signatures are left to the sender to authenticate, which accounts can't do.
+    let delegated = Signature::new_delegated(vec![0; 65]);
+    let res = executor
+        .execute_signed_message(SignedMessage::new_unchecked(
+            message(account, 1),
+            delegated.clone(),
+        ))
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_SENDER_INVALID);
+    let res = executor
+        .execute_signed_message(SignedMessage::new_unchecked(
+            message(abstracted, 0),
+            delegated,
+        ))
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    assert!(authenticated(&res));
+}
+
 #[derive(Default)]
 pub struct FailingBlockstore {
     fail_for: RefCell<HashSet<Cid>>,
+    removed: RefCell<HashSet<Cid>>,
     target: MemoryBlockstore,
     panic: RefCell<bool>,
 }
@@ -492,6 +3858,11 @@ impl FailingBlockstore {
         self.fail_for.borrow_mut().insert(cid);
     }
 
+    /// Makes the block `cid` appear missing, as if the store had lost it.
+    pub fn remove(&self, cid: Cid) {
+        self.removed.borrow_mut().insert(cid);
+    }
+
     pub fn panic(&self, enabled: bool) {
         *self.panic.borrow_mut() = enabled
     }
@@ -505,6 +3876,9 @@ impl Blockstore for FailingBlockstore {
             }
             return Err(anyhow!("an error was triggered"));
         }
+        if self.removed.borrow().contains(k) {
+            return Ok(None);
+        }
         self.target.get(k)
     }
 
@@ -512,3 +3886,46 @@ impl Blockstore for FailingBlockstore {
         self.target.put_keyed(k, block)
     }
 }
+
+#[test]
+fn missing_bundle_code() {
+    // A manifest, but none of the actor code it points to.
+    let blockstore = MemoryBlockstore::default();
+    let code = |name: &str| Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(name.as_bytes()));
+    let names = ["account", "init", "system"];
+    let manifest_data = blockstore
+        .put_cbor(
+            &names.map(|name| (name.to_owned(), code(name))).to_vec(),
+            Code::Blake2b256,
+        )
+        .unwrap();
+    let manifest = blockstore
+        .put_cbor(&(1u32, manifest_data), Code::Blake2b256)
+        .unwrap();
+
+    let mut tester: Tester<_, DummyExterns> = Tester::new(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        manifest,
+        blockstore,
+    )
+    .unwrap();
+    let err = tester.instantiate_machine(DummyExterns).unwrap_err();
+
+    assert!(
+        err.to_string()
+            .contains("was the builtin-actors bundle imported"),
+        "{:#}",
+        err
+    );
+    let MachineError::Bootstrap(missing) = err.downcast_ref::<MachineError>().unwrap();
+    assert_eq!(
+        missing,
+        &names
+            .map(|name| MissingPiece::ActorCode {
+                name: name.to_owned(),
+                code: code(name),
+            })
+            .to_vec()
+    );
+}
@@ -0,0 +1,117 @@
+use fvm_integration_tests::scenarios::actors::{
+    PRE_COMMIT_CHALLENGE_DELAY, WPOST_CHALLENGE_WINDOW,
+};
+use fvm_integration_tests::scenarios::Scenario;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::builtin::singletons::BURNT_FUNDS_ACTOR_ID;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::sector::RegisteredSealProof;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use num_traits::Zero;
+
+mod bundles;
+use bundles::*;
+
+const SECTORS: u64 = 2;
+
+#[test]
+fn miner_lifecycle() {
+    let tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let (mut scenario, [owner]) =
+        Scenario::new(tester, TokenAmount::from_whole(1_000_000)).unwrap();
+
+    let mut miner = scenario
+        .create_miner(
+            owner,
+            RegisteredSealProof::StackedDRG32GiBV1P1,
+            TokenAmount::from_whole(100_000),
+        )
+        .unwrap();
+    scenario.assert_power(&miner, 0).unwrap();
+    scenario
+        .assert_pledge(&miner, &TokenAmount::zero())
+        .unwrap();
+
+    // Pre-commit. The seal randomness is drawn from the previous epoch.
+    scenario
+        .advance_to_epoch_with_cron(ChainEpoch::new(1))
+        .unwrap();
+    let sectors = scenario.precommit_sectors(&mut miner, SECTORS).unwrap();
+    let precommit_epoch = scenario.epoch();
+    assert!(scenario
+        .miner_state(&miner)
+        .unwrap()
+        .pre_commit_deposits
+        .is_positive());
+
+    // Prove-commit. The proofs are verified, and the sectors activated, by cron.
+    scenario
+        .advance_to_epoch_with_cron(precommit_epoch + PRE_COMMIT_CHALLENGE_DELAY + 1)
+        .unwrap();
+    scenario.prove_commit_sectors(&miner, &sectors).unwrap();
+    scenario
+        .advance_to_epoch_with_cron(scenario.epoch() + 1)
+        .unwrap();
+    let st = scenario.miner_state(&miner).unwrap();
+    assert!(st.pre_commit_deposits.is_zero());
+    let pledge = st.initial_pledge;
+    assert!(pledge.is_positive());
+    scenario.assert_pledge(&miner, &pledge).unwrap();
+    // Sectors are only powered once proven by a Window PoSt.
+    scenario.assert_power(&miner, 0).unwrap();
+
+    // Prove the sectors in their deadline, then for a full proving period.
+    let location = scenario.sector_location(&miner).unwrap();
+    scenario.submit_post(&miner, location).unwrap();
+    scenario.assert_power(&miner, SECTORS).unwrap();
+    let balance = scenario.balance(miner.id).unwrap();
+
+    scenario
+        .advance_to_epoch_with_cron(scenario.epoch() + WPOST_CHALLENGE_WINDOW)
+        .unwrap();
+    scenario.submit_post(&miner, location).unwrap();
+    scenario
+        .advance_to_epoch_with_cron(scenario.epoch() + WPOST_CHALLENGE_WINDOW)
+        .unwrap();
+    scenario.assert_power(&miner, SECTORS).unwrap();
+    scenario.assert_pledge(&miner, &pledge).unwrap();
+    assert_eq!(scenario.balance(miner.id).unwrap(), balance);
+
+    // Declare the sectors faulty for the next challenge window, and recover them before it.
+    scenario.declare_faults(&miner, location, &sectors).unwrap();
+    scenario.assert_power(&miner, 0).unwrap();
+    scenario
+        .declare_recoveries(&miner, location, &sectors)
+        .unwrap();
+    scenario.assert_power(&miner, 0).unwrap();
+
+    // Recovered sectors regain their power once proven, and aren't penalized as the fault was
+    // declared ahead of time.
+    scenario.submit_post(&miner, location).unwrap();
+    scenario.assert_power(&miner, SECTORS).unwrap();
+    scenario.assert_pledge(&miner, &pledge).unwrap();
+    assert_eq!(scenario.balance(miner.id).unwrap(), balance);
+
+    // Terminate the sectors once their deadline can be modified again. The termination fee is
+    // burnt, and the pledge released.
+    scenario
+        .advance_to_epoch_with_cron(scenario.epoch() + WPOST_CHALLENGE_WINDOW)
+        .unwrap();
+    let burnt = scenario.balance(BURNT_FUNDS_ACTOR_ID).unwrap();
+    scenario
+        .terminate_sectors(&miner, location, &sectors)
+        .unwrap();
+    scenario.assert_power(&miner, 0).unwrap();
+    scenario
+        .assert_pledge(&miner, &TokenAmount::zero())
+        .unwrap();
+    assert!(scenario.balance(BURNT_FUNDS_ACTOR_ID).unwrap() > burnt);
+    assert!(scenario.balance(miner.id).unwrap() < balance);
+}
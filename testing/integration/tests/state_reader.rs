@@ -0,0 +1,104 @@
+use fil_timestamp_actor::{METHOD_RECORD, WASM_BINARY};
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::Account;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::to_vec;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use num_traits::Zero;
+
+mod bundles;
+use bundles::*;
+
+const ACTOR: u64 = 10000;
+const TIMESTAMP: u64 = 1_600_000_000;
+
+// Reads between messages observe the state left by the last message, before it's flushed.
+#[test]
+fn state_reader_sees_unflushed_state() {
+    let mut tester = new_tester(
+        NetworkVersion::V16,
+        StateTreeVersion::V4,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender]: [Account; 1] = tester.create_accounts().unwrap();
+    let state_cid = tester.set_state(&0u64).unwrap();
+    tester
+        .set_actor_from_bin(
+            WASM_BINARY.unwrap(),
+            state_cid,
+            Address::new_id(ACTOR),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester.set_tipset(ChainEpoch::new(100), TIMESTAMP);
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let actor = Address::new_id(ACTOR);
+    {
+        let reader = executor.state_reader().unwrap();
+        assert_eq!(reader.resolve_address(&sender.1).unwrap(), Some(sender.0));
+        assert_eq!(reader.actor_state::<u64>(&actor).unwrap(), Some(0));
+        let account = reader.get_actor(&sender.1).unwrap().unwrap();
+        assert_eq!(account.sequence, 0);
+        assert_eq!(account.balance, TokenAmount::from_atto(10000));
+        assert_eq!(reader.get_actor_id(sender.0).unwrap(), Some(account));
+
+        assert!(reader
+            .get_actor(&Address::new_id(ACTOR + 1))
+            .unwrap()
+            .is_none());
+        assert!(reader
+            .actor_state::<u64>(&Address::new_id(ACTOR + 1))
+            .unwrap()
+            .is_none());
+        assert!(reader.actor_state::<String>(&actor).is_err());
+    }
+
+    for sequence in 0..2 {
+        let res = executor
+            .execute_message(
+                Message {
+                    from: sender.1,
+                    to: actor,
+                    value: TokenAmount::from_atto(10),
+                    gas_limit: 1000000000,
+                    method_num: METHOD_RECORD,
+                    sequence,
+                    ..Message::default()
+                },
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap();
+        assert_eq!(
+            res.msg_receipt.exit_code,
+            ExitCode::OK,
+            "{:?}",
+            res.failure_info
+        );
+
+        let reader = executor.state_reader().unwrap();
+        assert_eq!(reader.actor_state::<u64>(&actor).unwrap(), Some(TIMESTAMP));
+        let state = reader.get_actor(&actor).unwrap().unwrap();
+        assert_eq!(state.balance, TokenAmount::from_atto(10 * (sequence + 1)));
+        assert_eq!(
+            reader.get_block(&state.state).unwrap().unwrap(),
+            to_vec(&TIMESTAMP).unwrap()
+        );
+        assert_eq!(reader.get_cbor(&state.state).unwrap(), Some(TIMESTAMP));
+        assert_eq!(
+            reader.get_actor(&sender.1).unwrap().unwrap().sequence,
+            sequence + 1
+        );
+    }
+}